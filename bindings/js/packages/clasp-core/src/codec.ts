@@ -26,6 +26,7 @@ import {
   GetMessage,
   SnapshotMessage,
   BundleMessage,
+  ChannelMessage,
   SyncMessage,
   AckMessage,
   ErrorMessage,
@@ -48,6 +49,15 @@ const HEADER_SIZE = 4;
 /** Header size with timestamp */
 const HEADER_SIZE_WITH_TS = 12;
 
+/** Size of the optional TTL header field */
+const TTL_SIZE = 4;
+
+/** Header size for the given flags */
+function headerSizeFor(flags: FrameFlags): number {
+  const base = flags.hasTimestamp ? HEADER_SIZE_WITH_TS : HEADER_SIZE;
+  return flags.hasTtl ? base + TTL_SIZE : base;
+}
+
 // ============================================================================
 // MESSAGE TYPE CODES
 // ============================================================================
@@ -57,6 +67,7 @@ export const MSG = {
   HELLO: 0x01,
   WELCOME: 0x02,
   ANNOUNCE: 0x03,
+  CHANNEL: 0x04,
   SUBSCRIBE: 0x10,
   UNSUBSCRIBE: 0x11,
   PUBLISH: 0x20,
@@ -123,7 +134,8 @@ export function encodeFlags(flags: FrameFlagsV3): number {
   if (flags.hasTimestamp) byte |= 0x20;
   if (flags.encrypted) byte |= 0x10;
   if (flags.compressed) byte |= 0x08;
-  byte |= (flags.version ?? 1) & 0x07;
+  if (flags.hasTtl) byte |= 0x04;
+  byte |= (flags.version ?? 1) & 0x03;
   return byte;
 }
 
@@ -134,7 +146,8 @@ export function decodeFlags(byte: number): FrameFlagsV3 {
     hasTimestamp: (byte & 0x20) !== 0,
     encrypted: (byte & 0x10) !== 0,
     compressed: (byte & 0x08) !== 0,
-    version: byte & 0x07,
+    hasTtl: (byte & 0x04) !== 0,
+    version: byte & 0x03,
   };
 }
 
@@ -154,6 +167,8 @@ export interface DecodedFrame {
   payload: Uint8Array;
   qos: QoS;
   timestamp?: bigint;
+  /** Time-to-live in milliseconds, if the sender set one */
+  ttl?: number;
   flags: FrameFlagsV3;
 }
 
@@ -202,7 +217,7 @@ export function decodeFrame(data: Uint8Array): DecodedFrame {
   const flags = decodeFlags(data[1]);
   const payloadLength = view.getUint16(2, false);
 
-  const headerSize = flags.hasTimestamp ? HEADER_SIZE_WITH_TS : HEADER_SIZE;
+  const headerSize = headerSizeFor(flags);
 
   if (data.length < headerSize + payloadLength) {
     throw new Error('Frame incomplete');
@@ -213,9 +228,14 @@ export function decodeFrame(data: Uint8Array): DecodedFrame {
     timestamp = view.getBigUint64(4, false);
   }
 
+  let ttl: number | undefined;
+  if (flags.hasTtl) {
+    ttl = view.getUint32(headerSize - TTL_SIZE, false);
+  }
+
   const payload = data.slice(headerSize, headerSize + payloadLength);
 
-  return { payload, qos: flags.qos, timestamp, flags };
+  return { payload, qos: flags.qos, timestamp, ttl, flags };
 }
 
 /**
@@ -278,6 +298,9 @@ export function encodeMessageBinary(message: Message): Uint8Array {
     case 'RESULT':
       offset = encodeResult(view, offset, message);
       break;
+    case 'CHANNEL':
+      offset = encodeChannel(view, offset, message);
+      break;
     default:
       throw new Error(`Unknown message type: ${(message as Message).type}`);
   }
@@ -358,7 +381,7 @@ export function checkComplete(data: Uint8Array): number | null {
   const view = new DataView(data.buffer, data.byteOffset);
   const payloadLength = view.getUint16(2, false);
 
-  const headerSize = flags.hasTimestamp ? HEADER_SIZE_WITH_TS : HEADER_SIZE;
+  const headerSize = headerSizeFor(flags);
   const totalSize = headerSize + payloadLength;
 
   if (data.length >= totalSize) {
@@ -593,6 +616,7 @@ function encodeSubscribe(view: DataView, offset: number, msg: SubscribeMessage):
     if (msg.options.epsilon !== undefined) optFlags |= 0x02;
     if (msg.options.history !== undefined) optFlags |= 0x04;
     if (msg.options.window !== undefined) optFlags |= 0x08;
+    if (msg.options.batch !== undefined) optFlags |= 0x20;
     view.setUint8(offset++, optFlags);
 
     if (msg.options.maxRate !== undefined) {
@@ -611,6 +635,10 @@ function encodeSubscribe(view: DataView, offset: number, msg: SubscribeMessage):
       view.setUint32(offset, msg.options.window, false);
       offset += 4;
     }
+    if (msg.options.batch !== undefined) {
+      view.setUint32(offset, msg.options.batch, false);
+      offset += 4;
+    }
   } else {
     view.setUint8(offset++, 0);
   }
@@ -685,6 +713,21 @@ function encodeBundle(view: DataView, offset: number, msg: BundleMessage): numbe
   return offset;
 }
 
+function encodeChannel(view: DataView, offset: number, msg: ChannelMessage): number {
+  view.setUint8(offset++, MSG.CHANNEL);
+  view.setUint16(offset, msg.channel, false);
+  offset += 2;
+
+  // Wrapped message fills the rest of the payload; nothing when closing
+  if (msg.message !== undefined) {
+    const innerPayload = encodeMessageBinary(msg.message);
+    new Uint8Array(view.buffer).set(innerPayload, offset);
+    offset += innerPayload.length;
+  }
+
+  return offset;
+}
+
 function encodeSync(view: DataView, offset: number, msg: SyncMessage): number {
   view.setUint8(offset++, MSG.SYNC);
 
@@ -827,6 +870,8 @@ function decodeV3Binary(data: Uint8Array): Message {
       return decodeQuery(view, offset);
     case MSG.RESULT:
       return decodeResultMsg(view, offset);
+    case MSG.CHANNEL:
+      return decodeChannel(view, offset);
     default:
       throw new Error(`Unknown message type: 0x${msgType.toString(16)}`);
   }
@@ -1071,6 +1116,10 @@ function decodeSubscribe(view: DataView, offset: number): SubscribeMessage {
       options.window = view.getUint32(offset, false);
       offset += 4;
     }
+    if (optFlags & 0x20) {
+      options.batch = view.getUint32(offset, false);
+      offset += 4;
+    }
   }
 
   return { type: 'SUBSCRIBE', id, pattern, types: types.length > 0 ? types : undefined, options };
@@ -1141,6 +1190,17 @@ function decodeBundle(view: DataView, offset: number): BundleMessage {
   return { type: 'BUNDLE', timestamp, messages };
 }
 
+function decodeChannel(view: DataView, offset: number): ChannelMessage {
+  const channel = view.getUint16(offset, false);
+  offset += 2;
+
+  if (offset >= view.byteLength) {
+    return { type: 'CHANNEL', channel };
+  }
+  const innerData = new Uint8Array(view.buffer, view.byteOffset + offset, view.byteLength - offset);
+  return { type: 'CHANNEL', channel, message: decodeV3Binary(innerData) };
+}
+
 function decodeSyncMsg(view: DataView, offset: number): SyncMessage {
   const flags = view.getUint8(offset++);
   const t1 = Number(view.getBigUint64(offset, false));
@@ -1459,6 +1519,8 @@ function getDefaultQoS(message: Message): QoS {
     case 'SUBSCRIBE':
     case 'UNSUBSCRIBE':
       return QoS.Confirm;
+    case 'CHANNEL':
+      return message.message !== undefined ? getDefaultQoS(message.message) : QoS.Fire;
     default:
       return QoS.Fire;
   }
@@ -71,6 +71,8 @@ export type Value =
 export interface FrameFlags {
   qos: QoS;
   hasTimestamp: boolean;
+  /** A 4-byte TTL (milliseconds) follows the timestamp */
+  hasTtl?: boolean;
   encrypted: boolean;
   compressed: boolean;
   /** Encoding version: 0 = MessagePack (legacy), 1 = binary (default) */
@@ -116,6 +118,8 @@ export interface SubscribeOptions {
   epsilon?: number;
   history?: number;
   window?: number;
+  /** Deliver param changes as one SNAPSHOT per this many milliseconds */
+  batch?: number;
 }
 
 /** UNSUBSCRIBE message */
@@ -233,6 +237,14 @@ export interface ResultMessage {
   signals: SignalDefinition[];
 }
 
+/** CHANNEL message - traffic of a logical client sharing the connection */
+export interface ChannelMessage {
+  type: 'CHANNEL';
+  channel: number;
+  /** Omitted when closing the channel */
+  message?: Message;
+}
+
 /** Signal definition */
 export interface SignalDefinition {
   address: string;
@@ -264,6 +276,7 @@ export type Message =
   | SyncMessage
   | QueryMessage
   | ResultMessage
+  | ChannelMessage
   | { type: 'PING' }
   | { type: 'PONG' };
 
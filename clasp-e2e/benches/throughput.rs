@@ -12,6 +12,8 @@ fn bench_encode_set(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let mut group = c.benchmark_group("encode");
@@ -29,6 +31,8 @@ fn bench_decode_set(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let encoded = encode(&msg).unwrap();
 
@@ -49,6 +53,8 @@ fn bench_roundtrip(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let mut group = c.benchmark_group("roundtrip");
@@ -101,6 +107,8 @@ fn bench_bundle(c: &mut Criterion) {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
             Message::Set(SetMessage {
                 address: "/bundle/2".to_string(),
@@ -108,6 +116,8 @@ fn bench_bundle(c: &mut Criterion) {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
             Message::Set(SetMessage {
                 address: "/bundle/3".to_string(),
@@ -115,8 +125,11 @@ fn bench_bundle(c: &mut Criterion) {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
         ],
+        correlation_id: None,
     });
 
     let mut group = c.benchmark_group("bundle");
@@ -141,6 +154,8 @@ fn bench_large_array(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let mut group = c.benchmark_group("large_payload");
@@ -0,0 +1,185 @@
+//! Real-World Benchmarks for Batched Delivery
+//!
+//! A dashboard subscribed to `/**` normally receives one SET frame per
+//! change. These benchmarks count the frames the router actually sends to
+//! dashboards with and without a batch window, all watching the same writes.
+
+use bytes::Bytes;
+use clasp_client::Clasp;
+use clasp_core::{codec, HelloMessage, Message, SubscribeMessage, SubscribeOptions};
+use clasp_router::{Router, RouterConfig};
+use clasp_transport::websocket::{WebSocketReceiver, WebSocketSender};
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Find an available port
+async fn find_port() -> u16 {
+    use tokio::net::TcpListener;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// Frames and param updates seen by one dashboard
+#[derive(Default)]
+struct Counts {
+    frames: AtomicU64,
+    params: AtomicU64,
+    bytes: AtomicU64,
+}
+
+/// Connect a raw dashboard subscribed to `/**` and count what it receives
+async fn dashboard(url: &str, batch: Option<u32>) -> Arc<Counts> {
+    let (sender, mut receiver): (WebSocketSender, WebSocketReceiver) =
+        WebSocketTransport::connect(url).await.unwrap();
+    let send = |msg: Message| -> Bytes { codec::encode(&msg).unwrap() };
+
+    sender
+        .send(send(Message::Hello(HelloMessage {
+            version: 3,
+            name: format!("dashboard-{:?}", batch),
+            features: vec!["param".to_string()],
+            capabilities: None,
+            token: None,
+            since: None,
+        })))
+        .await
+        .unwrap();
+    sender
+        .send(send(Message::Subscribe(SubscribeMessage {
+            id: 1,
+            pattern: "/**".to_string(),
+            types: vec![],
+            options: Some(SubscribeOptions {
+                batch,
+                ..Default::default()
+            }),
+            correlation_id: None,
+        })))
+        .await
+        .unwrap();
+
+    let counts = Arc::new(Counts::default());
+    let counting = Arc::clone(&counts);
+    tokio::spawn(async move {
+        let _sender = sender;
+        while let Some(event) = receiver.recv().await {
+            let TransportEvent::Data(data) = event else {
+                continue;
+            };
+            let params = match codec::decode(&data) {
+                Ok((Message::Set(_), _)) => 1,
+                Ok((Message::Snapshot(snapshot), _)) => snapshot.params.len() as u64,
+                _ => continue,
+            };
+            counting.frames.fetch_add(1, Ordering::Relaxed);
+            counting.params.fetch_add(params, Ordering::Relaxed);
+            counting
+                .bytes
+                .fetch_add(data.len() as u64, Ordering::Relaxed);
+        }
+    });
+    counts
+}
+
+#[tokio::main]
+async fn main() {
+    println!("\n╔══════════════════════════════════════════════════════════════════╗");
+    println!("║        Batched Delivery - REAL METRICS (No Assumptions)          ║");
+    println!("╚══════════════════════════════════════════════════════════════════╝\n");
+
+    // Benchmark 1: a fader bank moving at 120Hz
+    benchmark_fader_bank(8, 120).await;
+
+    // Benchmark 2: a large rig with many slowly changing params
+    benchmark_fader_bank(64, 30).await;
+}
+
+/// `faders` params each updated `rate` times per second for one second
+async fn benchmark_fader_bank(faders: usize, rate: u64) {
+    println!("┌──────────────────────────────────────────────────────────────────┐");
+    println!(
+        "│ {:<64} │",
+        format!(
+            "Fader bank: {} params at {}Hz → dashboards on /**",
+            faders, rate
+        )
+    );
+    println!("└──────────────────────────────────────────────────────────────────┘");
+
+    let port = find_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let router = Router::new(RouterConfig {
+        max_messages_per_second: 0,
+        rate_limiting_enabled: false,
+        ..Default::default()
+    });
+    let router_handle = {
+        let addr = addr.clone();
+        tokio::spawn(async move {
+            let _ = router.serve_websocket(&addr).await;
+        })
+    };
+    sleep(Duration::from_millis(100)).await;
+
+    let url = format!("ws://{}", addr);
+    let windows = [None, Some(16), Some(33)];
+    let mut dashboards = Vec::new();
+    for batch in windows {
+        dashboards.push((batch, dashboard(&url, batch).await));
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    let writer = Clasp::connect_to(&url).await.unwrap();
+    let start = Instant::now();
+    let tick = Duration::from_nanos(1_000_000_000 / rate);
+    for step in 0..rate {
+        for fader in 0..faders {
+            let level = (step as f64 / rate as f64 + fader as f64 * 0.01) % 1.0;
+            writer
+                .set(&format!("/rig/fader/{}", fader), level)
+                .await
+                .unwrap();
+        }
+        sleep(tick).await;
+    }
+    let elapsed = start.elapsed();
+
+    // Let the last batch window close
+    sleep(Duration::from_millis(300)).await;
+    router_handle.abort();
+
+    let changes = faders as u64 * rate;
+    let baseline = dashboards[0].1.frames.load(Ordering::Relaxed).max(1);
+    println!("  Changes written:      {} in {:?}", changes, elapsed);
+    for (batch, counts) in &dashboards {
+        let frames = counts.frames.load(Ordering::Relaxed);
+        let label = match batch {
+            None => "unbatched".to_string(),
+            Some(ms) => format!("batch {}ms", ms),
+        };
+        println!(
+            "  {:<12} {:>6} frames  {:>6} params  {:>8} bytes  ({:.1}% of unbatched frames)",
+            label,
+            frames,
+            counts.params.load(Ordering::Relaxed),
+            counts.bytes.load(Ordering::Relaxed),
+            frames as f64 / baseline as f64 * 100.0
+        );
+    }
+
+    let batched = dashboards[1].1.frames.load(Ordering::Relaxed);
+    if batched < baseline {
+        println!(
+            "  ✅ Batching cuts frames by {:.1}%",
+            (1.0 - batched as f64 / baseline as f64) * 100.0
+        );
+    } else {
+        println!("  ⚠️  Unexpected: batching did not reduce frames");
+    }
+    println!();
+}
@@ -88,6 +88,7 @@ fn test_mqtt_config_custom() -> TestResult {
         qos: 1,
         keep_alive_secs: 30,
         namespace: "/sensors".to_string(),
+        ..Default::default()
     };
 
     if config.broker_host == "mqtt.example.com"
@@ -172,6 +173,7 @@ fn test_http_config_client_mode() -> TestResult {
         namespace: "/api".to_string(),
         poll_interval_ms: 0,
         poll_endpoints: vec![],
+        webhooks: vec![],
     };
 
     if config.mode == HttpMode::Client
@@ -301,6 +303,7 @@ fn test_websocket_config_server_mode() -> TestResult {
         reconnect_delay_secs: 10,
         headers: HashMap::new(),
         namespace: "/live".to_string(),
+        mapping: None,
     };
 
     if config.mode == WsMode::Server
@@ -18,7 +18,12 @@ fn main() {
             })
             .collect();
 
-        let snapshot = Message::Snapshot(SnapshotMessage { params });
+        let snapshot = Message::Snapshot(SnapshotMessage {
+            params,
+            correlation_id: None,
+            page: None,
+            sync: None,
+        });
 
         // Measure encoding
         let start = Instant::now();
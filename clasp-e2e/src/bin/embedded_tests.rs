@@ -105,6 +105,8 @@ fn test_decoding_compatibility() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&set_msg).unwrap();
@@ -118,6 +118,7 @@ impl TestClient {
             ],
             capabilities: None,
             token: None,
+            since: None,
         });
 
         self.sender
@@ -168,6 +169,7 @@ impl TestClient {
             pattern: pattern.to_string(),
             types: vec![],
             options: None,
+            correlation_id: None,
         });
 
         self.sender
@@ -183,6 +185,8 @@ impl TestClient {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         self.sender
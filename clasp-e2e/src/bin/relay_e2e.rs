@@ -136,6 +136,7 @@ impl TestClient {
             features: vec!["param".to_string(), "event".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
 
         self.sender
@@ -184,6 +185,7 @@ impl TestClient {
             pattern: pattern.to_string(),
             types: vec![],
             options: None,
+            correlation_id: None,
         });
 
         self.sender
@@ -199,6 +201,8 @@ impl TestClient {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         self.sender
@@ -649,6 +653,7 @@ async fn test_state_persistence() -> TestResult {
             features: vec!["param".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
         client2
             .sender
@@ -162,6 +162,7 @@ impl RawClient {
             ],
             capabilities: None,
             token: None,
+            since: None,
         });
         self.send(&hello).await?;
 
@@ -274,6 +275,8 @@ async fn bench_set_ack_latency() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
             client.recv(5000).await?;
@@ -290,6 +293,8 @@ async fn bench_set_ack_latency() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
 
@@ -333,6 +338,7 @@ async fn bench_pubsub_latency() -> TestResult {
                 pattern: format!("{}/**", base),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .await?;
 
@@ -354,6 +360,8 @@ async fn bench_pubsub_latency() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
 
@@ -411,6 +419,8 @@ async fn bench_throughput() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await
                 .is_ok()
@@ -488,6 +498,7 @@ async fn bench_fanout_throughput() -> TestResult {
                 pattern: format!("{}/**", base),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .await?;
             subscribers.push(sub);
@@ -507,6 +518,8 @@ async fn bench_fanout_throughput() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
         }
@@ -603,6 +616,8 @@ async fn stress_concurrent_clients() -> TestResult {
                                         revision: None,
                                         lock: false,
                                         unlock: false,
+                                        op: None,
+                                        correlation_id: None,
                                     }))
                                     .await
                                     .is_ok()
@@ -703,6 +718,8 @@ async fn stress_concurrent_writes() -> TestResult {
                             revision: None,
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         }))
                         .await
                         .is_ok()
@@ -835,6 +852,7 @@ async fn test_subscription_patterns() -> TestResult {
                 pattern: pattern.clone(),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .await?;
             tokio::time::sleep(Duration::from_millis(50)).await;
@@ -850,6 +868,8 @@ async fn test_subscription_patterns() -> TestResult {
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     }))
                     .await?;
                 let _ = pub_client.recv(500).await;
@@ -896,6 +916,7 @@ async fn test_message_ordering() -> TestResult {
             pattern: format!("{}/**", base),
             types: vec![],
             options: None,
+            correlation_id: None,
         }))
         .await?;
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -911,6 +932,8 @@ async fn test_message_ordering() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
         }
@@ -985,6 +1008,8 @@ async fn test_state_consistency() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
             let _ = setter.recv(1000).await;
@@ -997,6 +1022,8 @@ async fn test_state_consistency() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
             .await?;
         let _ = setter.recv(1000).await;
@@ -1012,6 +1039,7 @@ async fn test_state_consistency() -> TestResult {
                 pattern: addr.clone(),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .await?;
 
@@ -1069,6 +1097,7 @@ async fn test_bundle_atomicity() -> TestResult {
             pattern: format!("{}/**", base),
             types: vec![],
             options: None,
+            correlation_id: None,
         }))
         .await?;
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -1083,6 +1112,8 @@ async fn test_bundle_atomicity() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
             Message::Set(SetMessage {
                 address: format!("{}/b", base),
@@ -1090,6 +1121,8 @@ async fn test_bundle_atomicity() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
             Message::Set(SetMessage {
                 address: format!("{}/c", base),
@@ -1097,6 +1130,8 @@ async fn test_bundle_atomicity() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
         ];
 
@@ -1104,6 +1139,7 @@ async fn test_bundle_atomicity() -> TestResult {
             .send(&Message::Bundle(BundleMessage {
                 messages: bundle_messages,
                 timestamp: None,
+                correlation_id: None,
             }))
             .await?;
 
@@ -1162,6 +1198,8 @@ async fn test_large_payloads() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await
                 .is_err()
@@ -1219,6 +1257,8 @@ async fn test_special_characters() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
 
@@ -1270,6 +1310,8 @@ async fn test_empty_null_values() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await?;
 
@@ -1325,6 +1367,7 @@ async fn test_sustained_load() -> TestResult {
                     pattern: format!("{}/**", sub_base),
                     types: vec![],
                     options: None,
+                    correlation_id: None,
                 }))
                 .await;
 
@@ -1351,6 +1394,8 @@ async fn test_sustained_load() -> TestResult {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .await
                 .is_ok()
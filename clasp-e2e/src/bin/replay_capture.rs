@@ -0,0 +1,107 @@
+//! Replay `.claspcap` captures against a router
+//!
+//! Each capture is replayed and checked against the expectations in its
+//! header. Without `--url`, every capture gets a fresh embedded router.
+//!
+//! Usage:
+//!   cargo run -p clasp-e2e --bin replay-capture -- captures/*.claspcap
+//!   cargo run -p clasp-e2e --bin replay-capture -- --speed 0 bug-1234.claspcap
+//!   cargo run -p clasp-e2e --bin replay-capture -- --url ws://localhost:7330 show.claspcap
+
+use clasp_e2e::replay::{Capture, Replay};
+use clasp_e2e::TestRouter;
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    let mut url: Option<String> = None;
+    let mut speed = 10.0;
+    let mut paths = Vec::new();
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--url" | "-u" => {
+                if i + 1 < args.len() {
+                    url = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
+            "--speed" | "-s" => {
+                if i + 1 < args.len() {
+                    speed = args[i + 1].parse().unwrap_or(speed);
+                    i += 1;
+                }
+            }
+            "--help" | "-h" => {
+                print_help();
+                return;
+            }
+            path => paths.push(path.to_string()),
+        }
+        i += 1;
+    }
+
+    if paths.is_empty() {
+        print_help();
+        std::process::exit(2);
+    }
+
+    let mut failed = 0;
+    for path in &paths {
+        let capture = match Capture::load(path) {
+            Ok(capture) => capture,
+            Err(e) => {
+                println!("✗ {}: {:#}", path, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let _embedded_router;
+        let router_url = match &url {
+            Some(u) => u.clone(),
+            None => {
+                _embedded_router = TestRouter::start().await;
+                _embedded_router.url()
+            }
+        };
+
+        let name = capture.name.clone().unwrap_or_else(|| path.clone());
+        let frames = capture.frames.len();
+        match Replay::new(capture)
+            .speed(speed)
+            .run_and_verify(&router_url)
+            .await
+        {
+            Ok(report) => {
+                println!(
+                    "✓ {} ({} frames in {:?}, delivered {:?})",
+                    name, frames, report.elapsed, report.delivered
+                );
+            }
+            Err(e) => {
+                println!("✗ {}: {:#}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        println!("\n{} of {} capture(s) failed", failed, paths.len());
+        std::process::exit(1);
+    }
+}
+
+fn print_help() {
+    println!("Replay .claspcap captures against a CLASP router");
+    println!();
+    println!("USAGE:");
+    println!("    replay-capture [OPTIONS] <CAPTURE>...");
+    println!();
+    println!("OPTIONS:");
+    println!("    -u, --url <URL>       Router URL (default: fresh embedded router per capture)");
+    println!("    -s, --speed <FACTOR>  Speed-up factor, 0 for no pacing (default: 10)");
+    println!("    -h, --help            Print this help message");
+}
@@ -159,6 +159,7 @@ async fn connect_with_token(
         features: vec!["param".to_string()],
         capabilities: None,
         token: token.map(|s| s.to_string()),
+        since: None,
     });
 
     sender
@@ -207,6 +208,8 @@ async fn try_set<S: TransportSender, R: TransportReceiver>(
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     sender
@@ -506,6 +509,7 @@ async fn test_read_scope_enforcement() -> TestResult {
                 pattern: "/forbidden/**".to_string(),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             });
 
             sender
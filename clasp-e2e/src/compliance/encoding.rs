@@ -33,6 +33,7 @@ async fn test_hello_encoding(_config: &ConformanceConfig, report: &mut Conforman
             features: vec!["param".to_string(), "event".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
 
         // Encode
@@ -88,6 +89,8 @@ async fn test_set_encoding(_config: &ConformanceConfig, report: &mut Conformance
             revision: Some(1),
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&set)?;
@@ -156,6 +159,8 @@ async fn test_value_int_encoding(_config: &ConformanceConfig, report: &mut Confo
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded = codec::encode(&msg)?;
@@ -211,6 +216,8 @@ async fn test_value_float_encoding(_config: &ConformanceConfig, report: &mut Con
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded = codec::encode(&msg)?;
@@ -265,6 +272,8 @@ async fn test_value_string_encoding(_config: &ConformanceConfig, report: &mut Co
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded = codec::encode(&msg)?;
@@ -314,6 +323,8 @@ async fn test_value_bool_encoding(_config: &ConformanceConfig, report: &mut Conf
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded = codec::encode(&msg)?;
@@ -367,6 +378,8 @@ async fn test_value_bytes_encoding(_config: &ConformanceConfig, report: &mut Con
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded = codec::encode(&msg)?;
@@ -417,6 +430,7 @@ async fn test_roundtrip_encoding(_config: &ConformanceConfig, report: &mut Confo
                 features: vec![],
                 capabilities: None,
                 token: Some("token".to_string()),
+                since: None,
             }),
             Message::Set(SetMessage {
                 address: "/a/b/c".to_string(),
@@ -424,6 +438,8 @@ async fn test_roundtrip_encoding(_config: &ConformanceConfig, report: &mut Confo
                 revision: Some(5),
                 lock: true,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }),
         ];
 
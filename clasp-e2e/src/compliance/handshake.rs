@@ -39,6 +39,8 @@ async fn test_hello_must_be_first(config: &ConformanceConfig, report: &mut Confo
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         sender.send(codec::encode(&set)?).await?;
 
@@ -135,6 +137,7 @@ async fn test_version_negotiation(config: &ConformanceConfig, report: &mut Confo
             features: vec![],
             capabilities: None,
             token: None,
+            since: None,
         });
         sender.send(codec::encode(&hello)?).await?;
 
@@ -234,6 +237,7 @@ async fn test_duplicate_hello_rejected(config: &ConformanceConfig, report: &mut
             features: vec![],
             capabilities: None,
             token: None,
+            since: None,
         });
         sender.send(codec::encode(&hello)?).await?;
 
@@ -267,6 +271,7 @@ async fn test_duplicate_hello_rejected(config: &ConformanceConfig, report: &mut
             features: vec![],
             capabilities: None,
             token: None,
+            since: None,
         });
         sender.send(codec::encode(&hello2)?).await?;
 
@@ -6,6 +6,7 @@
 use std::time::Duration;
 
 pub mod compliance;
+pub mod replay;
 pub mod tests;
 
 // Re-export test utilities from clasp-test-utils
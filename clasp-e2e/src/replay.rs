@@ -0,0 +1,478 @@
+//! Deterministic replay of recorded captures
+//!
+//! A `.claspcap` capture is a JSON-lines file: a header line followed by one
+//! line per message a client sent, in the order the router received them.
+//!
+//! ```text
+//! {"claspcap":1,"name":"fader storm","expect":{"state":{"/mixer/1":0.5},"delivered":{"desk":2}}}
+//! {"t":0,"client":"desk","msg":{"type":"SUBSCRIBE","id":1,"pattern":"/mixer/**"}}
+//! {"t":1500,"client":"fader","msg":{"type":"SET","address":"/mixer/1","value":0.25}}
+//! {"t":3000,"client":"fader","msg":{"type":"SET","address":"/mixer/1","value":0.5}}
+//! ```
+//!
+//! `t` is microseconds since the start of the capture and `msg` is a
+//! [`Message`] in its JSON form. The optional `expect` block holds the final
+//! param state and per-client delivery counts a regression test asserts on.
+//!
+//! [`Replay`] connects one WebSocket session per captured client and sends
+//! the frames at the recorded pace (or faster). Whenever the sending client
+//! changes, it waits for a PING round trip on the previous client, so the
+//! router sees frames from different clients in exactly the captured order
+//! regardless of speed.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clasp_core::{codec, HelloMessage, Message, SubscribeMessage, Value, PROTOCOL_VERSION};
+use clasp_transport::{
+    websocket::WebSocketSender, Transport, TransportEvent, TransportReceiver, TransportSender,
+    WebSocketTransport,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// Capture format version written in the header
+pub const CAPTURE_VERSION: u32 = 1;
+
+/// How long to wait for any single reply from the router
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Header line of a `.claspcap` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Header {
+    claspcap: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expect: Option<Expectations>,
+}
+
+/// One message a client sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureFrame {
+    /// Microseconds since the start of the capture
+    pub t: u64,
+    /// Name of the sending client
+    pub client: String,
+    /// The message as sent
+    #[serde(rename = "msg")]
+    pub message: Message,
+}
+
+/// What a replay must end with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Expectations {
+    /// Final value of each listed param
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub state: BTreeMap<String, Value>,
+    /// SET/PUBLISH messages delivered to each listed client
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub delivered: BTreeMap<String, usize>,
+}
+
+/// A recorded session
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    pub name: Option<String>,
+    pub expect: Option<Expectations>,
+    pub frames: Vec<CaptureFrame>,
+}
+
+impl Capture {
+    /// Load a `.claspcap` file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Parse the contents of a `.claspcap` file
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, header) = lines.next().ok_or_else(|| anyhow!("empty capture"))?;
+        let header: Header = serde_json::from_str(header).context("invalid header")?;
+        if header.claspcap != CAPTURE_VERSION {
+            bail!("unsupported capture version {}", header.claspcap);
+        }
+
+        let mut frames = Vec::new();
+        for (number, line) in lines {
+            let frame: CaptureFrame = serde_json::from_str(line)
+                .with_context(|| format!("invalid frame on line {}", number + 1))?;
+            frames.push(frame);
+        }
+        // Stable, so frames with equal timestamps keep their file order
+        frames.sort_by_key(|frame| frame.t);
+
+        Ok(Self {
+            name: header.name,
+            expect: header.expect,
+            frames,
+        })
+    }
+
+    /// Serialize to the `.claspcap` format
+    pub fn to_claspcap(&self) -> Result<String> {
+        let header = Header {
+            claspcap: CAPTURE_VERSION,
+            name: self.name.clone(),
+            expect: self.expect.clone(),
+        };
+        let mut out = serde_json::to_string(&header)?;
+        out.push('\n');
+        for frame in &self.frames {
+            out.push_str(&serde_json::to_string(frame)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Write a `.claspcap` file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_claspcap()?)?;
+        Ok(())
+    }
+
+    /// Append a frame
+    pub fn push(&mut self, t: u64, client: &str, message: Message) {
+        self.frames.push(CaptureFrame {
+            t,
+            client: client.to_string(),
+            message,
+        });
+    }
+
+    /// Distinct client names, in order of first appearance
+    pub fn clients(&self) -> Vec<&str> {
+        let mut clients: Vec<&str> = Vec::new();
+        for frame in &self.frames {
+            if !clients.contains(&frame.client.as_str()) {
+                clients.push(&frame.client);
+            }
+        }
+        clients
+    }
+
+    /// Time between the first and last frame
+    pub fn duration(&self) -> Duration {
+        match (self.frames.first(), self.frames.last()) {
+            (Some(first), Some(last)) => Duration::from_micros(last.t - first.t),
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// Outcome of a replay
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    /// Frames sent
+    pub sent: usize,
+    /// SET/PUBLISH messages delivered to each client
+    pub delivered: BTreeMap<String, usize>,
+    /// ERROR replies received by each client
+    pub errors: BTreeMap<String, usize>,
+    /// Router param state after the replay
+    pub state: BTreeMap<String, Value>,
+    /// Wall-clock replay time
+    pub elapsed: Duration,
+}
+
+impl ReplayReport {
+    /// Compare against expectations, listing every mismatch
+    pub fn verify(&self, expect: &Expectations) -> std::result::Result<(), Vec<String>> {
+        let mut mismatches = Vec::new();
+        for (address, expected) in &expect.state {
+            match self.state.get(address) {
+                Some(actual) if actual == expected => {}
+                actual => mismatches.push(format!(
+                    "state {}: expected {:?}, got {:?}",
+                    address, expected, actual
+                )),
+            }
+        }
+        for (client, expected) in &expect.delivered {
+            let actual = self.delivered.get(client).copied().unwrap_or(0);
+            if actual != *expected {
+                mismatches.push(format!(
+                    "delivered to {}: expected {}, got {}",
+                    client, expected, actual
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+}
+
+/// Replays a [`Capture`] against a router
+pub struct Replay {
+    capture: Capture,
+    speed: f64,
+}
+
+/// A connected captured client
+struct ReplayClient {
+    sender: WebSocketSender,
+    pongs: mpsc::Receiver<()>,
+    delivered: Arc<AtomicUsize>,
+    errors: Arc<AtomicUsize>,
+}
+
+impl ReplayClient {
+    async fn connect(url: &str, name: &str, hello: Option<Message>) -> Result<Self> {
+        let (sender, mut receiver) = WebSocketTransport::connect(url)
+            .await
+            .with_context(|| format!("connecting {} to {}", name, url))?;
+
+        let hello = hello.unwrap_or_else(|| {
+            Message::Hello(HelloMessage {
+                version: PROTOCOL_VERSION,
+                name: name.to_string(),
+                features: ["param", "event", "stream", "gesture", "timeline"]
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect(),
+                capabilities: None,
+                token: None,
+                since: None,
+            })
+        });
+        sender.send(codec::encode(&hello)?).await?;
+
+        // The handshake is not part of the timed replay
+        loop {
+            match next_message(&mut receiver).await? {
+                Message::Welcome(_) => break,
+                Message::Error(error) => bail!("{} refused: {}", name, error.message),
+                _ => {}
+            }
+        }
+
+        let (pong_tx, pongs) = mpsc::channel(16);
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(AtomicUsize::new(0));
+        let (delivered_count, error_count) = (Arc::clone(&delivered), Arc::clone(&errors));
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                let TransportEvent::Data(data) = event else {
+                    continue;
+                };
+                let Ok((msg, _)) = codec::decode(&data) else {
+                    continue;
+                };
+                match msg {
+                    Message::Pong => {
+                        let _ = pong_tx.send(()).await;
+                    }
+                    Message::Error(_) => {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                    }
+                    msg => {
+                        delivered_count.fetch_add(deliveries(&msg), Ordering::Relaxed);
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            sender,
+            pongs,
+            delivered,
+            errors,
+        })
+    }
+
+    async fn send(&self, msg: &Message) -> Result<()> {
+        self.sender.send(codec::encode(msg)?).await?;
+        Ok(())
+    }
+
+    /// Wait until the router has handled everything sent so far and this
+    /// client has received everything queued for it
+    async fn barrier(&mut self) -> Result<()> {
+        self.send(&Message::Ping).await?;
+        timeout(REPLY_TIMEOUT, self.pongs.recv())
+            .await
+            .map_err(|_| anyhow!("timed out waiting for PONG"))?
+            .ok_or_else(|| anyhow!("connection closed"))
+    }
+}
+
+impl Replay {
+    /// Replay at the recorded pace
+    pub fn new(capture: Capture) -> Self {
+        Self {
+            capture,
+            speed: 1.0,
+        }
+    }
+
+    /// Speed-up factor; `0.0` sends frames back to back
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// The capture being replayed
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    /// Replay against the router at `url` and collect the results
+    pub async fn run(&self, url: &str) -> Result<ReplayReport> {
+        let started = Instant::now();
+        let origin = self.capture.frames.first().map(|f| f.t).unwrap_or(0);
+        let mut clients: HashMap<String, ReplayClient> = HashMap::new();
+        let mut order: Vec<String> = Vec::new();
+        let mut previous: Option<String> = None;
+        let mut paused = Duration::ZERO;
+        let mut sent = 0;
+
+        for frame in &self.capture.frames {
+            if !clients.contains_key(&frame.client) {
+                let connecting = Instant::now();
+                let hello =
+                    matches!(frame.message, Message::Hello(_)).then(|| frame.message.clone());
+                let client = ReplayClient::connect(url, &frame.client, hello).await?;
+                clients.insert(frame.client.clone(), client);
+                order.push(frame.client.clone());
+                // Connecting is not part of the timed replay either
+                paused += connecting.elapsed();
+                if matches!(frame.message, Message::Hello(_)) {
+                    continue;
+                }
+            }
+
+            if previous.as_deref() != Some(frame.client.as_str()) {
+                if let Some(client) = previous.as_ref().and_then(|name| clients.get_mut(name)) {
+                    client.barrier().await?;
+                }
+                previous = Some(frame.client.clone());
+            }
+
+            if self.speed > 0.0 {
+                let due = Duration::from_secs_f64((frame.t - origin) as f64 / 1e6 / self.speed);
+                let elapsed = started.elapsed().saturating_sub(paused);
+                if due > elapsed {
+                    tokio::time::sleep(due - elapsed).await;
+                }
+            }
+
+            clients[&frame.client].send(&frame.message).await?;
+            sent += 1;
+        }
+
+        // Senders first, so every delivery is queued before the recipients' PINGs
+        if let Some(client) = previous.as_ref().and_then(|name| clients.get_mut(name)) {
+            client.barrier().await?;
+        }
+        for name in &order {
+            clients.get_mut(name).unwrap().barrier().await?;
+        }
+        let elapsed = started.elapsed();
+
+        let mut report = ReplayReport {
+            sent,
+            elapsed,
+            state: fetch_state(url).await?,
+            ..Default::default()
+        };
+        for (name, client) in &clients {
+            report
+                .delivered
+                .insert(name.clone(), client.delivered.load(Ordering::Relaxed));
+            report
+                .errors
+                .insert(name.clone(), client.errors.load(Ordering::Relaxed));
+        }
+        Ok(report)
+    }
+
+    /// Replay and check the capture's own expectations
+    pub async fn run_and_verify(&self, url: &str) -> Result<ReplayReport> {
+        let report = self.run(url).await?;
+        if let Some(expect) = &self.capture.expect {
+            if let Err(mismatches) = report.verify(expect) {
+                bail!("replay mismatch:\n  {}", mismatches.join("\n  "));
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// SET/PUBLISH messages carried by a message received from the router
+fn deliveries(msg: &Message) -> usize {
+    match msg {
+        Message::Set(_) | Message::Publish(_) => 1,
+        Message::Bundle(bundle) => bundle.messages.iter().map(deliveries).sum(),
+        _ => 0,
+    }
+}
+
+async fn next_message(receiver: &mut impl TransportReceiver) -> Result<Message> {
+    loop {
+        match timeout(REPLY_TIMEOUT, receiver.recv()).await {
+            Ok(Some(TransportEvent::Data(data))) => return Ok(codec::decode(&data)?.0),
+            Ok(Some(TransportEvent::Disconnected { .. })) | Ok(None) => {
+                bail!("connection closed")
+            }
+            Ok(Some(_)) => continue,
+            Err(_) => bail!("timed out waiting for the router"),
+        }
+    }
+}
+
+/// Read every param from the router with a fresh session
+async fn fetch_state(url: &str) -> Result<BTreeMap<String, Value>> {
+    const CORRELATION_ID: u32 = 1;
+
+    let (sender, mut receiver) = WebSocketTransport::connect(url).await?;
+    let hello = Message::Hello(HelloMessage {
+        version: PROTOCOL_VERSION,
+        name: "replay-observer".to_string(),
+        features: vec!["param".to_string()],
+        capabilities: None,
+        token: None,
+        since: None,
+    });
+    sender.send(codec::encode(&hello)?).await?;
+    while !matches!(next_message(&mut receiver).await?, Message::Welcome(_)) {}
+
+    let subscribe = Message::Subscribe(SubscribeMessage {
+        id: 1,
+        pattern: "/**".to_string(),
+        types: vec![],
+        options: None,
+        correlation_id: Some(CORRELATION_ID),
+    });
+    sender.send(codec::encode(&subscribe)?).await?;
+
+    let mut state = BTreeMap::new();
+    loop {
+        match next_message(&mut receiver).await? {
+            Message::Snapshot(snapshot) => {
+                for param in snapshot.params {
+                    state.insert(param.address, param.value);
+                }
+            }
+            Message::Ack(ack) if ack.correlation_id == Some(CORRELATION_ID) => break,
+            Message::Error(error) => bail!("reading state failed: {}", error.message),
+            _ => {}
+        }
+    }
+    let _ = sender.close().await;
+    Ok(state)
+}
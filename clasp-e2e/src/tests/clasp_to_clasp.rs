@@ -48,6 +48,7 @@ async fn test_message_encoding() -> TestResult {
                 ],
                 capabilities: None,
                 token: None,
+                since: None,
             });
 
             let encoded = encode(&msg).map_err(|e| format!("Failed to encode Hello: {:?}", e))?;
@@ -134,6 +135,7 @@ async fn test_all_message_types() -> TestResult {
                     features: vec!["param".to_string()],
                     capabilities: None,
                     token: None,
+                    since: None,
                 }),
                 Message::Welcome(WelcomeMessage {
                     session: "sess-1".to_string(),
@@ -148,6 +150,7 @@ async fn test_all_message_types() -> TestResult {
                     pattern: "/test/**".to_string(),
                     types: vec![SignalType::Param],
                     options: None,
+                    correlation_id: None,
                 }),
                 Message::Unsubscribe(UnsubscribeMessage { id: 1 }),
                 Message::Set(SetMessage {
@@ -156,6 +159,8 @@ async fn test_all_message_types() -> TestResult {
                     revision: Some(1),
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }),
                 Message::Publish(PublishMessage {
                     address: "/test/event".to_string(),
@@ -177,7 +182,10 @@ async fn test_all_message_types() -> TestResult {
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     })],
+                    correlation_id: None,
                 }),
                 Message::Sync(SyncMessage {
                     t1: 1000000,
@@ -191,6 +199,7 @@ async fn test_all_message_types() -> TestResult {
                     revision: Some(1),
                     locked: None,
                     holder: None,
+                    value: None,
                     correlation_id: None,
                 }),
                 Message::Error(ErrorMessage {
@@ -198,6 +207,8 @@ async fn test_all_message_types() -> TestResult {
                     message: "Bad request".to_string(),
                     address: None,
                     correlation_id: None,
+                    revision: None,
+                    value: None,
                 }),
                 Message::Query(QueryMessage {
                     pattern: "/test/**".to_string(),
@@ -262,6 +273,8 @@ async fn test_value_types() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded =
@@ -300,6 +313,8 @@ async fn test_qos_levels() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             // Test each QoS level
@@ -336,6 +351,8 @@ async fn test_timestamp_handling() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let timestamp = 1704067200000000u64; // Microseconds
@@ -429,6 +446,8 @@ async fn test_bundle_messages() -> TestResult {
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     }),
                     Message::Set(SetMessage {
                         address: "/bundle/light/2".to_string(),
@@ -436,6 +455,8 @@ async fn test_bundle_messages() -> TestResult {
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     }),
                     Message::Publish(PublishMessage {
                         address: "/bundle/cue".to_string(),
@@ -450,6 +471,7 @@ async fn test_bundle_messages() -> TestResult {
                         timeline: None,
                     }),
                 ],
+                correlation_id: None,
             });
 
             let encoded = encode(&bundle).map_err(|e| format!("Bundle encode failed: {:?}", e))?;
@@ -491,7 +513,14 @@ async fn test_subscription_patterns() -> TestResult {
                     epsilon: Some(0.001),
                     history: None,
                     window: None,
+                    batch: None,
+                    origins: None,
+                    envelope: false,
+                    qos: None,
+                    since: None,
+                    credits: None,
                 }),
+                correlation_id: None,
             });
 
             let encoded =
@@ -536,6 +565,8 @@ async fn test_state_revision() -> TestResult {
                 revision: Some(1),
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded1 = encode(&set1).map_err(|e| format!("Set1 encode failed: {:?}", e))?;
@@ -559,6 +590,8 @@ async fn test_state_revision() -> TestResult {
                 revision: Some(42),
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded2 = encode(&set2).map_err(|e| format!("Set2 encode failed: {:?}", e))?;
@@ -41,6 +41,8 @@ async fn test_encoding_throughput() -> TestResult {
                 revision: Some(1),
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let count = 10_000;
@@ -77,6 +79,8 @@ async fn test_decoding_throughput() -> TestResult {
                 revision: Some(1),
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             // Pre-encode messages
@@ -119,6 +123,8 @@ async fn test_roundtrip_throughput() -> TestResult {
                     revision: Some(i as u64),
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 });
 
                 let encoded = encode(&msg).map_err(|e| format!("Encode {} failed: {:?}", i, e))?;
@@ -156,6 +162,8 @@ async fn test_large_payload() -> TestResult {
                 revision: Some(1),
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let start = Instant::now();
@@ -276,6 +284,8 @@ async fn test_concurrent_encoding() -> TestResult {
                             revision: Some(i as u64),
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         });
 
                         if encode(&msg).is_ok() {
@@ -338,6 +348,8 @@ async fn test_memory_stability() -> TestResult {
                         revision: Some(idx as u64),
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     });
 
                     let encoded =
@@ -378,6 +390,8 @@ async fn test_latency_distribution() -> TestResult {
                     revision: Some(i as u64),
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 });
 
                 let start = Instant::now();
@@ -43,6 +43,8 @@ pub fn benchmark_three_way_encoding(iterations: usize) -> TestResult {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         let _encoded = codec::encode(&msg).unwrap();
     }
@@ -136,6 +138,8 @@ pub fn benchmark_three_way_decoding(iterations: usize) -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_encoded = codec::encode(&clasp_msg).unwrap();
 
@@ -236,6 +240,8 @@ pub fn benchmark_three_way_sizes() -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_size = codec::encode(&clasp_msg).unwrap().len();
 
@@ -323,6 +329,8 @@ pub fn benchmark_clasp_vs_osc_encoding(iterations: usize) -> TestResult {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         let _encoded = codec::encode(&msg).unwrap();
     }
@@ -397,6 +405,8 @@ pub fn benchmark_clasp_vs_osc_decoding(iterations: usize) -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_encoded = codec::encode(&clasp_msg).unwrap();
 
@@ -521,6 +531,8 @@ pub fn benchmark_message_sizes() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             })
         } else {
             Message::Set(SetMessage {
@@ -529,6 +541,8 @@ pub fn benchmark_message_sizes() -> TestResult {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             })
         };
         let clasp_encoded = codec::encode(&clasp_msg).unwrap();
@@ -586,6 +600,8 @@ pub fn benchmark_latency_distribution(samples: usize) -> TestResult {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         let encoded = codec::encode(&msg).unwrap();
         let _ = codec::decode(&encoded).unwrap();
@@ -601,6 +617,8 @@ pub fn benchmark_latency_distribution(samples: usize) -> TestResult {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         let encoded = codec::encode(&msg).unwrap();
         let _ = codec::decode(&encoded).unwrap();
@@ -742,6 +760,8 @@ pub fn visualize_osc_to_clasp_bridge() -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_bytes = codec::encode(&clasp_msg).unwrap();
 
@@ -792,6 +812,8 @@ pub fn visualize_osc_to_clasp_bridge() -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_rgb_bytes = codec::encode(&clasp_rgb).unwrap();
 
@@ -818,6 +840,8 @@ pub fn visualize_osc_to_clasp_bridge() -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_str_bytes = codec::encode(&clasp_str).unwrap();
 
@@ -863,6 +887,8 @@ pub fn visualize_midi_to_clasp_bridge() -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let clasp_cc_bytes = codec::encode(&clasp_cc).unwrap();
 
@@ -1257,6 +1283,8 @@ pub fn stress_test_throughput_limit() -> TestResult {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     // Test increasing batch sizes
@@ -1330,6 +1358,8 @@ pub fn stress_test_concurrent_access() -> TestResult {
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     });
 
                     for _ in 0..messages_per_thread {
@@ -1393,6 +1423,8 @@ pub fn stress_test_memory_stability() -> TestResult {
             revision: Some(i as u64),
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).unwrap();
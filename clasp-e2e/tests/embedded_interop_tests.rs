@@ -0,0 +1,343 @@
+//! Embedded <-> desktop interop tests
+//!
+//! The embedded `Client` talks to a real `clasp_router::Router` over TCP, and
+//! the embedded `MiniRouter` serves frames produced by the clasp-core codec.
+//! Nothing is re-encoded on the way: the bytes the embedded crate produces are
+//! exactly what goes on the wire, which is what its protocol compatibility
+//! claim rests on.
+
+use bytes::Bytes;
+use clasp_core::{codec, HelloMessage, Message, SetMessage, SubscribeMessage, Value};
+use clasp_embedded::{self as embedded, Client};
+use clasp_router::{Router, RouterConfig};
+use clasp_transport::tcp::{TcpReceiver, TcpSender};
+use clasp_transport::{
+    TcpServer, TcpTransport, TransportEvent, TransportReceiver, TransportSender, TransportServer,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Start a router on an ephemeral TCP port
+async fn start_router() -> String {
+    let server = TcpServer::bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    let router = Router::new(RouterConfig::default());
+    tokio::spawn(async move {
+        let _ = router.serve_on(server).await;
+    });
+    addr
+}
+
+/// A TCP connection carrying raw CLASP frames
+struct Conn {
+    sender: TcpSender,
+    receiver: TcpReceiver,
+}
+
+impl Conn {
+    async fn open(addr: &str) -> Self {
+        let (sender, receiver) = TcpTransport::new().connect(addr).await.unwrap();
+        Self { sender, receiver }
+    }
+
+    async fn send(&self, frame: &[u8]) {
+        self.sender
+            .send(Bytes::copy_from_slice(frame))
+            .await
+            .unwrap();
+    }
+
+    async fn recv(&mut self) -> Bytes {
+        loop {
+            match timeout(TIMEOUT, self.receiver.recv()).await {
+                Ok(Some(TransportEvent::Data(data))) => return data,
+                Ok(Some(TransportEvent::Connected)) => continue,
+                Ok(other) => panic!("connection ended: {:?}", other),
+                Err(_) => panic!("timed out waiting for a frame"),
+            }
+        }
+    }
+}
+
+/// An embedded device: the no_std client driven over a std socket
+struct Device {
+    client: Client,
+    conn: Conn,
+}
+
+impl Device {
+    async fn connect(addr: &str, name: &str) -> Self {
+        let mut device = Self {
+            client: Client::new(),
+            conn: Conn::open(addr).await,
+        };
+        let hello = device.client.prepare_hello(name).to_vec();
+        device.conn.send(&hello).await;
+        device
+            .until(|msg| matches!(msg, embedded::Message::Welcome { .. }))
+            .await;
+        assert!(device.client.is_connected());
+        device
+    }
+
+    /// Feed frames through the client until one matches
+    async fn until(&mut self, done: impl Fn(&embedded::Message) -> bool) {
+        loop {
+            let frame = self.conn.recv().await;
+            if self.client.process(&frame).is_some_and(|msg| done(&msg)) {
+                return;
+            }
+        }
+    }
+
+    /// Round-trip a PING; everything sent before it has been handled
+    async fn ping(&mut self) {
+        let ping = self.client.prepare_ping().to_vec();
+        self.conn.send(&ping).await;
+        self.until(|msg| matches!(msg, embedded::Message::Pong))
+            .await;
+    }
+
+    async fn subscribe(&mut self, pattern: &str) {
+        let frame = self.client.prepare_subscribe(pattern).to_vec();
+        self.conn.send(&frame).await;
+        self.ping().await;
+    }
+
+    async fn set(&mut self, address: &str, value: embedded::Value) {
+        let frame = self.client.prepare_set(address, value).to_vec();
+        self.conn.send(&frame).await;
+    }
+}
+
+/// A desktop peer speaking clasp-core frames
+struct Desktop {
+    conn: Conn,
+}
+
+impl Desktop {
+    async fn connect(addr: &str) -> Self {
+        let mut desktop = Self {
+            conn: Conn::open(addr).await,
+        };
+        desktop
+            .send(Message::Hello(HelloMessage {
+                version: 3,
+                name: "desktop".to_string(),
+                features: vec!["param".to_string()],
+                capabilities: None,
+                token: None,
+                since: None,
+            }))
+            .await;
+        desktop
+            .until(|msg| matches!(msg, Message::Welcome(_)))
+            .await;
+        desktop
+    }
+
+    async fn send(&self, msg: Message) {
+        self.conn.send(&codec::encode(&msg).unwrap()).await;
+    }
+
+    async fn until(&mut self, done: impl Fn(&Message) -> bool) -> Message {
+        loop {
+            let frame = self.conn.recv().await;
+            let (msg, _) = codec::decode(&frame).unwrap();
+            if done(&msg) {
+                return msg;
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, pattern: &str) {
+        self.send(Message::Subscribe(SubscribeMessage {
+            id: 1,
+            pattern: pattern.to_string(),
+            types: vec![],
+            options: None,
+            correlation_id: None,
+        }))
+        .await;
+        self.send(Message::Ping).await;
+        self.until(|msg| matches!(msg, Message::Pong)).await;
+    }
+
+    async fn set(&self, address: &str, value: Value) {
+        self.send(Message::Set(SetMessage {
+            address: address.to_string(),
+            value,
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        }))
+        .await;
+    }
+}
+
+fn is_set(msg: &Message, address: &str) -> bool {
+    matches!(msg, Message::Set(set) if set.address == address)
+}
+
+// ============================================================================
+// Embedded Client -> Router
+// ============================================================================
+
+#[tokio::test]
+async fn test_embedded_handshake_and_ping() {
+    let addr = start_router().await;
+    let mut device = Device::connect(&addr, "ESP32-Sensor").await;
+    device.ping().await;
+}
+
+#[tokio::test]
+async fn test_embedded_wildcard_subscription() {
+    let addr = start_router().await;
+    let mut device = Device::connect(&addr, "esp32").await;
+    let desktop = Desktop::connect(&addr).await;
+
+    device.subscribe("/lights/**").await;
+    desktop
+        .set("/lights/stage/dimmer", Value::Float(0.75))
+        .await;
+    desktop.set("/audio/master", Value::Float(0.1)).await;
+    desktop.set("/lights/count", Value::Int(12)).await;
+
+    device
+        .until(|msg| matches!(msg, embedded::Message::Set { address, .. } if *address == "/lights/count"))
+        .await;
+    assert_eq!(
+        device.client.get_cached("/lights/stage/dimmer"),
+        Some(embedded::Value::Float(0.75))
+    );
+    assert_eq!(
+        device.client.get_cached("/lights/count"),
+        Some(embedded::Value::Int(12))
+    );
+    assert_eq!(device.client.get_cached("/audio/master"), None);
+}
+
+#[tokio::test]
+async fn test_embedded_set_reaches_desktop() {
+    let addr = start_router().await;
+    let mut device = Device::connect(&addr, "esp32").await;
+    let mut desktop = Desktop::connect(&addr).await;
+    desktop.subscribe("/sensors/*").await;
+
+    device
+        .set("/sensors/temp", embedded::Value::Float(22.5))
+        .await;
+    device
+        .set("/sensors/armed", embedded::Value::Bool(true))
+        .await;
+
+    match desktop.until(|msg| is_set(msg, "/sensors/temp")).await {
+        Message::Set(set) => assert_eq!(set.value, Value::Float(22.5)),
+        _ => unreachable!(),
+    }
+    match desktop.until(|msg| is_set(msg, "/sensors/armed")).await {
+        Message::Set(set) => assert_eq!(set.value, Value::Bool(true)),
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn test_embedded_set_persists() {
+    let addr = start_router().await;
+    {
+        let mut device = Device::connect(&addr, "esp32").await;
+        device.set("/device/mode", embedded::Value::Int(3)).await;
+        device.ping().await;
+    }
+
+    // The router keeps the value after the device goes away
+    let mut desktop = Desktop::connect(&addr).await;
+    desktop
+        .send(Message::Get(clasp_core::GetMessage {
+            address: "/device/mode".to_string(),
+            correlation_id: None,
+        }))
+        .await;
+    match desktop
+        .until(|msg| matches!(msg, Message::Snapshot(_)))
+        .await
+    {
+        Message::Snapshot(snapshot) => {
+            assert_eq!(snapshot.params.len(), 1);
+            assert_eq!(snapshot.params[0].value, Value::Int(3));
+        }
+        _ => unreachable!(),
+    }
+}
+
+// ============================================================================
+// Desktop frames -> MiniRouter
+// ============================================================================
+
+#[test]
+fn test_mini_router_serves_desktop_frames() {
+    use clasp_embedded::server::MiniRouter;
+
+    let mut router = MiniRouter::new();
+    let frame = |msg: Message| codec::encode(&msg).unwrap();
+    let decode = |bytes: &[u8]| codec::decode(bytes).unwrap().0;
+
+    for client in 0..2 {
+        let hello = frame(Message::Hello(HelloMessage {
+            version: 3,
+            name: format!("desktop-{}", client),
+            features: vec!["param".to_string()],
+            capabilities: None,
+            token: None,
+            since: None,
+        }));
+        let welcome = router.process(client, &hello).expect("WELCOME");
+        assert!(matches!(decode(welcome), Message::Welcome(_)));
+    }
+    assert_eq!(router.session_count(), 2);
+
+    let subscribe = frame(Message::Subscribe(SubscribeMessage {
+        id: 7,
+        pattern: "/lights/**".to_string(),
+        types: vec![],
+        options: None,
+        correlation_id: None,
+    }));
+    assert!(router.process(1, &subscribe).is_none());
+
+    let set = frame(Message::Set(SetMessage {
+        address: "/lights/stage/dimmer".to_string(),
+        value: Value::Float(0.5),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    }));
+    router.process(0, &set);
+    assert_eq!(
+        router.get("/lights/stage/dimmer"),
+        Some(embedded::Value::Float(0.5))
+    );
+
+    let targets = router.get_broadcast_targets("/lights/stage/dimmer", 0);
+    assert_eq!(targets.count, 1);
+    assert!(targets.clients[1]);
+    assert_eq!(router.get_broadcast_targets("/audio/master", 0).count, 0);
+
+    let broadcast = router.prepare_broadcast("/lights/stage/dimmer", embedded::Value::Float(0.5));
+    match decode(broadcast) {
+        Message::Set(set) => {
+            assert_eq!(set.address, "/lights/stage/dimmer");
+            assert_eq!(set.value, Value::Float(0.5));
+        }
+        other => panic!("expected SET, got {:?}", other),
+    }
+
+    let pong = router.process(0, &frame(Message::Ping)).expect("PONG");
+    assert!(matches!(decode(pong), Message::Pong));
+}
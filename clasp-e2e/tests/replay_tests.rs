@@ -0,0 +1,111 @@
+//! Capture replay tests
+//!
+//! Captures under `captures/` are regression cases; each one carries the state
+//! and delivery counts it must end with.
+
+use clasp_core::{Message, SetMessage, Value};
+use clasp_e2e::replay::{Capture, Expectations, Replay};
+use clasp_test_utils::TestRouter;
+use std::path::PathBuf;
+
+fn captures() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("captures");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "claspcap"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn set(address: &str, value: impl Into<Value>) -> Message {
+    Message::Set(SetMessage {
+        address: address.to_string(),
+        value: value.into(),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
+#[tokio::test]
+async fn test_replay_recorded_captures() {
+    let paths = captures();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let capture = Capture::load(&path).unwrap();
+        assert!(
+            capture.expect.is_some(),
+            "{} has no expectations",
+            path.display()
+        );
+
+        // Accelerated and unpaced replays must end the same way
+        for speed in [10.0, 0.0] {
+            let router = TestRouter::start().await;
+            if let Err(e) = Replay::new(capture.clone())
+                .speed(speed)
+                .run_and_verify(&router.url())
+                .await
+            {
+                panic!("{} at speed {}: {:#}", path.display(), speed, e);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_replay_keeps_cross_client_order() {
+    // Two clients writing the same address in the same instant: the last
+    // frame in the capture must always win
+    let mut capture = Capture::default();
+    for i in 0..20 {
+        let client = if i % 2 == 0 { "left" } else { "right" };
+        capture.push(0, client, set("/race", i));
+    }
+    capture.expect = Some(Expectations {
+        state: [("/race".to_string(), Value::Int(19))].into(),
+        ..Default::default()
+    });
+
+    let capture = Capture::parse(&capture.to_claspcap().unwrap()).unwrap();
+    for _ in 0..3 {
+        let router = TestRouter::start().await;
+        Replay::new(capture.clone())
+            .speed(0.0)
+            .run_and_verify(&router.url())
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_replay_reports_mismatches() {
+    let mut capture = Capture::default();
+    capture.push(0, "desk", set("/a", 1));
+    let router = TestRouter::start().await;
+    let report = Replay::new(capture)
+        .speed(0.0)
+        .run(&router.url())
+        .await
+        .unwrap();
+
+    let expect = Expectations {
+        state: [("/a".to_string(), Value::Int(2))].into(),
+        delivered: [("desk".to_string(), 1)].into(),
+    };
+    let mismatches = report.verify(&expect).unwrap_err();
+    assert_eq!(mismatches.len(), 2, "{:?}", mismatches);
+}
+
+#[test]
+fn test_capture_parse_errors() {
+    assert!(Capture::parse("").is_err());
+    assert!(Capture::parse(r#"{"claspcap":99}"#).is_err());
+    let err = Capture::parse("{\"claspcap\":1}\n{\"t\":0}\n").unwrap_err();
+    assert!(format!("{:#}", err).contains("line 2"), "{:#}", err);
+}
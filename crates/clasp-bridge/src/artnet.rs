@@ -291,6 +291,8 @@ fn artnet_to_clasp(
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     }));
                 }
             }
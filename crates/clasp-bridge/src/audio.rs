@@ -0,0 +1,668 @@
+//! Audio analysis bridge
+//!
+//! Captures an input device through cpal and publishes what it hears as
+//! streams, for sound-reactive visuals without an external analyzer:
+//!
+//! - `/audio/<device>/level`: RMS level since the last frame, 0-1
+//! - `/audio/<device>/peak`: peak sample since the last frame, 0-1
+//! - `/audio/<device>/band/<n>`: FFT magnitude of band `n`, 0-1, with bands
+//!   spaced logarithmically from 20 Hz to 20 kHz (or Nyquist)
+//!
+//! Frames are published at `frame_rate`. Multi-channel input is mixed down
+//! to mono before analysis.
+
+use async_trait::async_trait;
+use clasp_core::{Message, PublishMessage, SignalType, Value};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
+
+/// Lowest band edge in Hz
+const MIN_FREQUENCY: f64 = 20.0;
+/// Highest band edge in Hz, capped at Nyquist
+const MAX_FREQUENCY: f64 = 20_000.0;
+
+/// Audio analysis bridge configuration
+#[derive(Debug, Clone)]
+pub struct AudioBridgeConfig {
+    /// Input device name (or part of it); the default input when unset
+    pub device: Option<String>,
+    /// Address namespace
+    pub namespace: String,
+    /// Device name in addresses
+    pub device_name: String,
+    /// Frames published per second
+    pub frame_rate: f64,
+    /// Number of FFT bands
+    pub bands: usize,
+    /// FFT window size in samples, rounded up to a power of two
+    pub fft_size: usize,
+    /// How much of the previous frame is kept (0 = none, 0.9 = very smooth)
+    pub smoothing: f64,
+}
+
+impl Default for AudioBridgeConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            namespace: "/audio".to_string(),
+            device_name: "default".to_string(),
+            frame_rate: 60.0,
+            bands: 8,
+            fft_size: 1024,
+            smoothing: 0.5,
+        }
+    }
+}
+
+/// One frame of analysis
+#[derive(Debug, Clone, PartialEq)]
+struct AudioFrame {
+    level: f64,
+    peak: f64,
+    bands: Vec<f64>,
+}
+
+/// In-place radix-2 FFT; `re.len()` must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Turns mono samples into level, peak and band frames
+struct AudioAnalyzer {
+    fft_size: usize,
+    /// Hann window
+    window: Vec<f64>,
+    /// Scale that makes a full-scale sine read 1.0 in its bin
+    magnitude_scale: f64,
+    /// FFT bin range `[start, end)` of each band
+    band_bins: Vec<(usize, usize)>,
+    smoothing: f64,
+    /// The last `fft_size` samples
+    samples: VecDeque<f32>,
+    /// Sum of squares and count since the last frame
+    energy: f64,
+    count: usize,
+    peak: f64,
+    previous: Option<AudioFrame>,
+}
+
+impl AudioAnalyzer {
+    fn new(sample_rate: u32, fft_size: usize, bands: usize, smoothing: f64) -> Self {
+        let fft_size = fft_size.max(16).next_power_of_two();
+        let window: Vec<f64> = (0..fft_size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / fft_size as f64).cos())
+            .collect();
+        let magnitude_scale = 2.0 / window.iter().sum::<f64>();
+
+        // Logarithmic band edges, each band at least one bin wide
+        let bin_width = sample_rate as f64 / fft_size as f64;
+        let max_frequency = MAX_FREQUENCY.min(sample_rate as f64 / 2.0);
+        let bands = bands.max(1);
+        let mut band_bins = Vec::with_capacity(bands);
+        let mut start = ((MIN_FREQUENCY / bin_width).floor() as usize).max(1);
+        for i in 1..=bands {
+            let edge =
+                MIN_FREQUENCY * (max_frequency / MIN_FREQUENCY).powf(i as f64 / bands as f64);
+            let end = ((edge / bin_width).ceil() as usize)
+                .max(start + 1)
+                .min(fft_size / 2 + 1);
+            band_bins.push((start.min(end - 1), end));
+            start = end;
+        }
+
+        Self {
+            fft_size,
+            window,
+            magnitude_scale,
+            band_bins,
+            smoothing: smoothing.clamp(0.0, 0.99),
+            samples: VecDeque::from(vec![0.0; fft_size]),
+            energy: 0.0,
+            count: 0,
+            peak: 0.0,
+            previous: None,
+        }
+    }
+
+    fn push(&mut self, sample: f32) {
+        if self.samples.len() == self.fft_size {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        let sample = sample as f64;
+        self.energy += sample * sample;
+        self.count += 1;
+        self.peak = self.peak.max(sample.abs());
+    }
+
+    /// Analyse the samples received since the last frame
+    fn frame(&mut self) -> AudioFrame {
+        let level = if self.count > 0 {
+            (self.energy / self.count as f64).sqrt()
+        } else {
+            0.0
+        };
+        let peak = self.peak;
+        self.energy = 0.0;
+        self.count = 0;
+        self.peak = 0.0;
+
+        let mut re: Vec<f64> = self
+            .samples
+            .iter()
+            .zip(&self.window)
+            .map(|(s, w)| *s as f64 * w)
+            .collect();
+        let mut im = vec![0.0; self.fft_size];
+        fft(&mut re, &mut im);
+
+        let bands = self
+            .band_bins
+            .iter()
+            .map(|&(start, end)| {
+                (start..end)
+                    .map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt() * self.magnitude_scale)
+                    .fold(0.0, f64::max)
+                    .min(1.0)
+            })
+            .collect();
+
+        let mut frame = AudioFrame {
+            level: level.min(1.0),
+            peak: peak.min(1.0),
+            bands,
+        };
+        if let Some(previous) = &self.previous {
+            let s = self.smoothing;
+            let smooth = |old: f64, new: f64| old * s + new * (1.0 - s);
+            frame.level = smooth(previous.level, frame.level);
+            // Peaks jump up straight away and fall back smoothly
+            frame.peak = frame.peak.max(previous.peak * s);
+            for (band, old) in frame.bands.iter_mut().zip(&previous.bands) {
+                *band = smooth(*old, *band);
+            }
+        }
+        self.previous = Some(frame.clone());
+        frame
+    }
+}
+
+fn stream(address: String, value: f64) -> Message {
+    Message::Publish(PublishMessage {
+        address,
+        signal: Some(SignalType::Stream),
+        value: Some(Value::Float(value)),
+        payload: None,
+        samples: None,
+        rate: None,
+        id: None,
+        phase: None,
+        timestamp: Some(clasp_core::time::now()),
+        timeline: None,
+    })
+}
+
+impl AudioFrame {
+    fn messages(&self, base: &str) -> Vec<Message> {
+        let mut messages = Vec::with_capacity(self.bands.len() + 2);
+        messages.push(stream(format!("{}/level", base), self.level));
+        messages.push(stream(format!("{}/peak", base), self.peak));
+        for (i, band) in self.bands.iter().enumerate() {
+            messages.push(stream(format!("{}/band/{}", base, i), *band));
+        }
+        messages
+    }
+}
+
+/// Find an input device by name (or part of it), or the default input
+pub(crate) fn input_device(
+    name: Option<&str>,
+) -> Result<(cpal::Device, cpal::StreamConfig, SampleFormat)> {
+    let host = cpal::default_host();
+    let device = match name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| BridgeError::DeviceNotFound(e.to_string()))?
+            .find(|d| d.name().map(|n| n.contains(name)).unwrap_or(false))
+            .ok_or_else(|| BridgeError::DeviceNotFound(name.to_string()))?,
+        None => host
+            .default_input_device()
+            .ok_or_else(|| BridgeError::DeviceNotFound("default input".to_string()))?,
+    };
+    let supported = device
+        .default_input_config()
+        .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+    let sample_format = supported.sample_format();
+    let stream_config: cpal::StreamConfig = supported.into();
+    info!(
+        "Opening audio input: {} ({} Hz, {} channel(s))",
+        device.name().unwrap_or_else(|_| "Unknown".to_string()),
+        stream_config.sample_rate.0,
+        stream_config.channels
+    );
+    Ok((device, stream_config, sample_format))
+}
+
+/// Find an output device by name (or part of it), or the default output
+#[cfg_attr(not(feature = "timecode"), allow(dead_code))]
+pub(crate) fn output_device(
+    name: Option<&str>,
+) -> Result<(cpal::Device, cpal::StreamConfig, SampleFormat)> {
+    let host = cpal::default_host();
+    let device = match name {
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| BridgeError::DeviceNotFound(e.to_string()))?
+            .find(|d| d.name().map(|n| n.contains(name)).unwrap_or(false))
+            .ok_or_else(|| BridgeError::DeviceNotFound(name.to_string()))?,
+        None => host
+            .default_output_device()
+            .ok_or_else(|| BridgeError::DeviceNotFound("default output".to_string()))?,
+    };
+    let supported = device
+        .default_output_config()
+        .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+    let sample_format = supported.sample_format();
+    let stream_config: cpal::StreamConfig = supported.into();
+    info!(
+        "Opening audio output: {} ({} Hz, {} channel(s))",
+        device.name().unwrap_or_else(|_| "Unknown".to_string()),
+        stream_config.sample_rate.0,
+        stream_config.channels
+    );
+    Ok((device, stream_config, sample_format))
+}
+
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut on_sample: impl FnMut(f32) + Send + 'static,
+) -> std::result::Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            for frame in data.chunks(channels) {
+                let sum: f32 = frame.iter().map(|s| s.to_sample::<f32>()).sum();
+                on_sample(sum / channels as f32);
+            }
+        },
+        |e| warn!("Audio input error: {}", e),
+        None,
+    )
+}
+
+#[cfg_attr(not(feature = "timecode"), allow(dead_code))]
+fn build_output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut next_sample: impl FnMut() -> f32 + Send + 'static,
+) -> std::result::Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels.max(1) as usize;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            for frame in data.chunks_mut(channels) {
+                let sample = T::from_sample(next_sample());
+                frame.fill(sample);
+            }
+        },
+        |e| warn!("Audio output error: {}", e),
+        None,
+    )
+}
+
+/// Start capturing, mixing each frame down to one mono sample for `on_sample`
+pub(crate) fn start_input(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: SampleFormat,
+    on_sample: impl FnMut(f32) + Send + 'static,
+) -> Result<cpal::Stream> {
+    let stream = match sample_format {
+        SampleFormat::F32 => build_input_stream::<f32>(device, config, on_sample),
+        SampleFormat::I16 => build_input_stream::<i16>(device, config, on_sample),
+        SampleFormat::U16 => build_input_stream::<u16>(device, config, on_sample),
+        SampleFormat::I32 => build_input_stream::<i32>(device, config, on_sample),
+        other => {
+            return Err(BridgeError::Other(format!(
+                "Unsupported sample format: {:?}",
+                other
+            )))
+        }
+    }
+    .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+    stream
+        .play()
+        .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+    Ok(stream)
+}
+
+/// Start playing the mono samples from `next_sample` on every channel
+#[cfg_attr(not(feature = "timecode"), allow(dead_code))]
+pub(crate) fn start_output(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: SampleFormat,
+    next_sample: impl FnMut() -> f32 + Send + 'static,
+) -> Result<cpal::Stream> {
+    let stream = match sample_format {
+        SampleFormat::F32 => build_output_stream::<f32>(device, config, next_sample),
+        SampleFormat::I16 => build_output_stream::<i16>(device, config, next_sample),
+        SampleFormat::U16 => build_output_stream::<u16>(device, config, next_sample),
+        SampleFormat::I32 => build_output_stream::<i32>(device, config, next_sample),
+        other => {
+            return Err(BridgeError::Other(format!(
+                "Unsupported sample format: {:?}",
+                other
+            )))
+        }
+    }
+    .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+    stream
+        .play()
+        .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+    Ok(stream)
+}
+
+/// Audio analysis to CLASP bridge (input only)
+pub struct AudioBridge {
+    config: BridgeConfig,
+    audio_config: AudioBridgeConfig,
+    running: Arc<Mutex<bool>>,
+    /// Handle to the capture thread
+    _input_thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl AudioBridge {
+    pub fn new(audio_config: AudioBridgeConfig) -> Self {
+        let config = BridgeConfig {
+            name: format!("Audio Bridge ({})", audio_config.device_name),
+            protocol: "audio".to_string(),
+            bidirectional: false,
+            ..Default::default()
+        };
+
+        Self {
+            config,
+            audio_config,
+            running: Arc::new(Mutex::new(false)),
+            _input_thread: None,
+        }
+    }
+
+    /// List audio input devices
+    pub fn list_input_devices() -> Result<Vec<String>> {
+        let host = cpal::default_host();
+        let devices = host
+            .input_devices()
+            .map_err(|e| BridgeError::DeviceNotFound(e.to_string()))?;
+        Ok(devices.filter_map(|d| d.name().ok()).collect())
+    }
+
+    /// Open the device and start capturing into a new analyzer
+    fn open(config: &AudioBridgeConfig) -> Result<(cpal::Stream, Arc<Mutex<AudioAnalyzer>>)> {
+        let (device, stream_config, sample_format) = input_device(config.device.as_deref())?;
+        let analyzer = Arc::new(Mutex::new(AudioAnalyzer::new(
+            stream_config.sample_rate.0,
+            config.fft_size,
+            config.bands,
+            config.smoothing,
+        )));
+        let input = analyzer.clone();
+        let stream = start_input(&device, &stream_config, sample_format, move |sample| {
+            input.lock().push(sample)
+        })?;
+        Ok((stream, analyzer))
+    }
+}
+
+#[async_trait]
+impl Bridge for AudioBridge {
+    fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
+        if *self.running.lock() {
+            return Err(BridgeError::Other("Bridge already running".to_string()));
+        }
+
+        let (tx, rx) = mpsc::channel(100);
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let config = self.audio_config.clone();
+        let running = self.running.clone();
+        *running.lock() = true;
+
+        // cpal streams are not Send, so capture and analysis get their own thread
+        let input_thread = std::thread::spawn(move || {
+            let (_stream, analyzer) = match Self::open(&config) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+            let _ = tx.blocking_send(BridgeEvent::Connected);
+
+            let base = format!("{}/{}", config.namespace, config.device_name);
+            let frame_interval =
+                Duration::from_secs_f64(1.0 / config.frame_rate.clamp(1.0, 1000.0));
+            let mut next_frame = Instant::now() + frame_interval;
+            while *running.lock() {
+                std::thread::sleep(next_frame.saturating_duration_since(Instant::now()));
+                next_frame += frame_interval;
+
+                let frame = analyzer.lock().frame();
+                for msg in frame.messages(&base) {
+                    match tx.try_send(BridgeEvent::ToClasp(msg)) {
+                        Ok(()) => {}
+                        // Streams are fire-and-forget; skip rather than fall behind
+                        Err(mpsc::error::TrySendError::Full(_)) => {
+                            debug!("Audio frame dropped, router is behind");
+                            break;
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => return,
+                    }
+                }
+            }
+        });
+
+        match ready_rx.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                *self.running.lock() = false;
+                return Err(e);
+            }
+            Err(_) => {
+                *self.running.lock() = false;
+                return Err(BridgeError::Other("Audio thread exited".to_string()));
+            }
+        }
+
+        self._input_thread = Some(input_thread);
+        info!("Audio bridge started");
+        Ok(rx)
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        *self.running.lock() = false;
+        // The thread closes the stream and exits once running is false
+        self._input_thread = None;
+        info!("Audio bridge stopped");
+        Ok(())
+    }
+
+    async fn send(&self, _message: Message) -> Result<()> {
+        Err(BridgeError::Send("Audio bridge is input only".to_string()))
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock()
+    }
+
+    fn namespace(&self) -> &str {
+        &self.audio_config.namespace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn sine(analyzer: &mut AudioAnalyzer, frequency: f64, amplitude: f64, count: usize) {
+        for i in 0..count {
+            let t = i as f64 / SAMPLE_RATE as f64;
+            analyzer.push((amplitude * (2.0 * PI * frequency * t).sin()) as f32);
+        }
+    }
+
+    #[test]
+    fn test_level_and_peak() {
+        let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 1024, 8, 0.0);
+        sine(&mut analyzer, 1000.0, 0.5, 4800);
+        let frame = analyzer.frame();
+        assert!(
+            (frame.level - 0.5 / 2f64.sqrt()).abs() < 0.01,
+            "{:?}",
+            frame
+        );
+        assert!((frame.peak - 0.5).abs() < 0.01, "{:?}", frame);
+
+        // Nothing new arrived, so the next frame reads silence
+        let frame = analyzer.frame();
+        assert_eq!(frame.level, 0.0);
+        assert_eq!(frame.peak, 0.0);
+    }
+
+    #[test]
+    fn test_sine_lands_in_its_band() {
+        let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 2048, 8, 0.0);
+        sine(&mut analyzer, 1000.0, 1.0, 2048);
+        let frame = analyzer.frame();
+
+        let (loudest, magnitude) =
+            frame
+                .bands
+                .iter()
+                .copied()
+                .enumerate()
+                .fold(
+                    (0, 0.0),
+                    |best, (i, m)| if m > best.1 { (i, m) } else { best },
+                );
+        let (start, end) = analyzer.band_bins[loudest];
+        let bin = 1000.0 * 2048.0 / SAMPLE_RATE as f64;
+        assert!(
+            (start as f64) <= bin && bin < end as f64,
+            "band {}",
+            loudest
+        );
+        assert!(magnitude > 0.9 && magnitude <= 1.0, "{}", magnitude);
+        assert!(frame.bands[0] < 0.01, "{:?}", frame.bands);
+    }
+
+    #[test]
+    fn test_bands_cover_spectrum() {
+        let analyzer = AudioAnalyzer::new(SAMPLE_RATE, 1024, 16, 0.0);
+        assert_eq!(analyzer.band_bins.len(), 16);
+        for pair in analyzer.band_bins.windows(2) {
+            assert!(pair[0].0 < pair[0].1);
+            assert!(pair[0].1 <= pair[1].1);
+        }
+        assert!(analyzer.band_bins.last().unwrap().1 <= 513);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let mut analyzer = AudioAnalyzer::new(SAMPLE_RATE, 1024, 4, 0.5);
+        sine(&mut analyzer, 440.0, 1.0, 1024);
+        let loud = analyzer.frame();
+        // Silence only halves the smoothed level and peak
+        for _ in 0..1024 {
+            analyzer.push(0.0);
+        }
+        let quiet = analyzer.frame();
+        assert!((quiet.level - loud.level / 2.0).abs() < 1e-9);
+        assert!((quiet.peak - loud.peak / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_frame_messages() {
+        let frame = AudioFrame {
+            level: 0.5,
+            peak: 0.75,
+            bands: vec![0.1, 0.2],
+        };
+        let addresses: Vec<String> = frame
+            .messages("/audio/mic")
+            .into_iter()
+            .map(|m| match m {
+                Message::Publish(p) => {
+                    assert_eq!(p.signal, Some(SignalType::Stream));
+                    p.address
+                }
+                other => panic!("Expected stream, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            addresses,
+            vec![
+                "/audio/mic/level",
+                "/audio/mic/peak",
+                "/audio/mic/band/0",
+                "/audio/mic/band/1"
+            ]
+        );
+    }
+}
@@ -1,11 +1,18 @@
 //! DMX-512 bridge (USB DMX interfaces)
 //!
-//! Supports common USB-DMX interfaces like ENTTEC DMX USB Pro
+//! Outputs one universe to a USB-DMX interface: ENTTEC DMX USB Pro, ENTTEC
+//! Open DMX and other FTDI-based interfaces, uDMX, or Art-Net for software on
+//! the same machine. The full frame is sent at the configured refresh rate,
+//! as DMX receivers expect.
 
 use async_trait::async_trait;
 use clasp_core::{Message, SetMessage, Value};
 use parking_lot::Mutex;
+use serialport::{SerialPort, SerialPortInfo, SerialPortType};
+use std::io::Write;
+use std::net::{SocketAddr, UdpSocket};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
@@ -20,6 +27,12 @@ pub enum DmxInterfaceType {
     EnttecOpen,
     /// Generic FTDI-based
     Ftdi,
+    /// Anyma uDMX (Linux only)
+    Udmx,
+    /// Art-Net to a local (or other) address, for visualizers and software
+    /// nodes on the same machine. `port` is the destination, 127.0.0.1:6454
+    /// by default.
+    ArtNetLoopback,
     /// Virtual (for testing)
     Virtual,
 }
@@ -33,7 +46,8 @@ impl Default for DmxInterfaceType {
 /// DMX bridge configuration
 #[derive(Debug, Clone)]
 pub struct DmxBridgeConfig {
-    /// Serial port path
+    /// Serial port path, uDMX device node or Art-Net destination; detected
+    /// when unset
     pub port: Option<String>,
     /// Interface type
     pub interface_type: DmxInterfaceType,
@@ -41,7 +55,7 @@ pub struct DmxBridgeConfig {
     pub universe: u16,
     /// Address namespace
     pub namespace: String,
-    /// Refresh rate in Hz
+    /// Refresh rate in Hz, up to 44
     pub refresh_rate: f64,
 }
 
@@ -57,14 +71,389 @@ impl Default for DmxBridgeConfig {
     }
 }
 
+/// FTDI's USB vendor id, used by ENTTEC and most other serial DMX interfaces
+const FTDI_VID: u16 = 0x0403;
+/// uDMX USB ids (shared with other V-USB devices, so the product is checked)
+const UDMX_VID: u16 = 0x16C0;
+const UDMX_PID: u16 = 0x05DC;
+
+/// Default Art-Net loopback destination
+const ARTNET_LOOPBACK: &str = "127.0.0.1:6454";
+
+/// Full DMX frames per second a universe can carry
+const MAX_REFRESH_RATE: f64 = 44.0;
+const MIN_REFRESH_RATE: f64 = 1.0;
+
+impl DmxInterfaceType {
+    /// Find a connected interface of this type, returning the port to use
+    pub fn detect(&self) -> Option<String> {
+        match self {
+            DmxInterfaceType::EnttecPro | DmxInterfaceType::EnttecOpen | DmxInterfaceType::Ftdi => {
+                let ports = serialport::available_ports().ok()?;
+                pick_serial_port(*self, &ports)
+            }
+            DmxInterfaceType::Udmx => udmx::detect(),
+            DmxInterfaceType::ArtNetLoopback => Some(ARTNET_LOOPBACK.to_string()),
+            DmxInterfaceType::Virtual => None,
+        }
+    }
+}
+
+/// Pick the serial port for an FTDI-based interface. ENTTEC Pro widgets
+/// report "DMX USB PRO" as their product; the rest are plain FTDI chips.
+fn pick_serial_port(interface: DmxInterfaceType, ports: &[SerialPortInfo]) -> Option<String> {
+    let ftdi: Vec<(&str, bool)> = ports
+        .iter()
+        .filter_map(|port| match &port.port_type {
+            SerialPortType::UsbPort(usb) if usb.vid == FTDI_VID => {
+                let pro = usb
+                    .product
+                    .as_deref()
+                    .is_some_and(|p| p.to_uppercase().contains("PRO"));
+                Some((port.port_name.as_str(), pro))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let want_pro = interface == DmxInterfaceType::EnttecPro;
+    ftdi.iter()
+        .find(|(_, pro)| *pro == want_pro)
+        .or_else(|| ftdi.first())
+        .map(|(name, _)| name.to_string())
+}
+
+fn clamp_refresh_rate(hz: f64) -> f64 {
+    if !(MIN_REFRESH_RATE..=MAX_REFRESH_RATE).contains(&hz) {
+        warn!(
+            "DMX refresh rate {} Hz out of range, using {} to {} Hz",
+            hz, MIN_REFRESH_RATE, MAX_REFRESH_RATE
+        );
+    }
+    if hz.is_nan() {
+        return MAX_REFRESH_RATE;
+    }
+    hz.clamp(MIN_REFRESH_RATE, MAX_REFRESH_RATE)
+}
+
+/// Frame output to one interface, driven by the output thread
+trait DmxOutput: Send {
+    fn send_frame(&mut self, frame: &[u8; 512]) -> std::io::Result<()>;
+
+    /// Tell interfaces that pace their own output about a new rate
+    fn set_refresh_rate(&mut self, _hz: f64) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Open the output for an interface
+fn open_output(
+    interface: DmxInterfaceType,
+    port: Option<String>,
+    universe: u16,
+    refresh_rate: f64,
+) -> Result<Box<dyn DmxOutput>> {
+    if interface == DmxInterfaceType::Virtual {
+        return Ok(Box::new(VirtualOutput));
+    }
+
+    let port = port.ok_or_else(|| {
+        BridgeError::DeviceNotFound(format!("No {:?} DMX interface found", interface))
+    })?;
+    let failed = |e: &dyn std::fmt::Display| {
+        BridgeError::ConnectionFailed(format!("Failed to open DMX interface {}: {}", port, e))
+    };
+
+    let mut output: Box<dyn DmxOutput> = match interface {
+        DmxInterfaceType::EnttecPro => {
+            Box::new(EnttecProOutput::open(&port).map_err(|e| failed(&e))?)
+        }
+        DmxInterfaceType::EnttecOpen | DmxInterfaceType::Ftdi => {
+            Box::new(OpenDmxOutput::open(&port).map_err(|e| failed(&e))?)
+        }
+        DmxInterfaceType::Udmx => Box::new(udmx::UdmxOutput::open(&port).map_err(|e| failed(&e))?),
+        DmxInterfaceType::ArtNetLoopback => {
+            let target: SocketAddr = port.parse().map_err(|e| failed(&e))?;
+            Box::new(ArtNetOutput::open(target, universe).map_err(|e| failed(&e))?)
+        }
+        DmxInterfaceType::Virtual => unreachable!(),
+    };
+    output
+        .set_refresh_rate(refresh_rate)
+        .map_err(|e| failed(&e))?;
+
+    info!("DMX output on {:?} interface {}", interface, port);
+    Ok(output)
+}
+
+/// Discards frames
+struct VirtualOutput;
+
+impl DmxOutput for VirtualOutput {
+    fn send_frame(&mut self, _frame: &[u8; 512]) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// ENTTEC DMX USB Pro message labels
+const ENTTEC_SET_PARAMETERS: u8 = 4;
+const ENTTEC_OUTPUT_DMX: u8 = 6;
+
+/// Wrap a payload in an ENTTEC Pro message: start code, label, length (LSB
+/// first), payload, end code
+fn enttec_message(label: u8, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len() as u16;
+    let mut message = Vec::with_capacity(payload.len() + 5);
+    message.extend_from_slice(&[0x7E, label, len as u8, (len >> 8) as u8]);
+    message.extend_from_slice(payload);
+    message.push(0xE7);
+    message
+}
+
+/// ENTTEC DMX USB Pro: framing and timing are done by the widget
+struct EnttecProOutput {
+    port: Box<dyn SerialPort>,
+}
+
+impl EnttecProOutput {
+    fn open(path: &str) -> serialport::Result<Self> {
+        let port = serialport::new(path, 57_600)
+            .timeout(Duration::from_secs(1))
+            .open()?;
+        Ok(Self { port })
+    }
+}
+
+impl DmxOutput for EnttecProOutput {
+    fn send_frame(&mut self, frame: &[u8; 512]) -> std::io::Result<()> {
+        let mut payload = Vec::with_capacity(513);
+        payload.push(0x00); // DMX start code
+        payload.extend_from_slice(frame);
+        self.port
+            .write_all(&enttec_message(ENTTEC_OUTPUT_DMX, &payload))
+    }
+
+    fn set_refresh_rate(&mut self, hz: f64) -> std::io::Result<()> {
+        // Break 9 x 10.67us, mark-after-break 1 x 10.67us, rate 1-40
+        let rate = hz.round().clamp(1.0, 40.0) as u8;
+        self.port
+            .write_all(&enttec_message(ENTTEC_SET_PARAMETERS, &[0, 0, 9, 1, rate]))
+    }
+}
+
+/// ENTTEC Open DMX and other FTDI interfaces: the host generates the break
+/// and sends raw 250 kbaud 8N2 serial
+struct OpenDmxOutput {
+    port: Box<dyn SerialPort>,
+}
+
+impl OpenDmxOutput {
+    fn open(path: &str) -> serialport::Result<Self> {
+        let port = serialport::new(path, 250_000)
+            .data_bits(serialport::DataBits::Eight)
+            .parity(serialport::Parity::None)
+            .stop_bits(serialport::StopBits::Two)
+            .timeout(Duration::from_secs(1))
+            .open()?;
+        Ok(Self { port })
+    }
+}
+
+impl DmxOutput for OpenDmxOutput {
+    fn send_frame(&mut self, frame: &[u8; 512]) -> std::io::Result<()> {
+        // Break of at least 88us, then mark-after-break of at least 8us
+        self.port.set_break()?;
+        std::thread::sleep(Duration::from_micros(110));
+        self.port.clear_break()?;
+        std::thread::sleep(Duration::from_micros(12));
+
+        let mut data = Vec::with_capacity(513);
+        data.push(0x00); // DMX start code
+        data.extend_from_slice(frame);
+        self.port.write_all(&data)
+    }
+}
+
+/// Build an ArtDmx packet for a full universe
+fn artdmx_packet(universe: u16, sequence: u8, frame: &[u8; 512]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(18 + 512);
+    packet.extend_from_slice(b"Art-Net\0");
+    packet.extend_from_slice(&0x5000u16.to_le_bytes()); // OpDmx
+    packet.extend_from_slice(&14u16.to_be_bytes()); // Protocol version
+    packet.push(sequence);
+    packet.push(0); // Physical port
+    packet.push(universe as u8); // SubUni
+    packet.push((universe >> 8) as u8 & 0x7F); // Net
+    packet.extend_from_slice(&512u16.to_be_bytes());
+    packet.extend_from_slice(frame);
+    packet
+}
+
+/// Art-Net output to one destination
+struct ArtNetOutput {
+    socket: UdpSocket,
+    target: SocketAddr,
+    universe: u16,
+    sequence: u8,
+}
+
+impl ArtNetOutput {
+    fn open(target: SocketAddr, universe: u16) -> std::io::Result<Self> {
+        let bind: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            target,
+            universe,
+            sequence: 0,
+        })
+    }
+}
+
+impl DmxOutput for ArtNetOutput {
+    fn send_frame(&mut self, frame: &[u8; 512]) -> std::io::Result<()> {
+        // Sequence runs 1-255; 0 would disable reordering on receivers
+        self.sequence = self.sequence % 255 + 1;
+        self.socket.send_to(
+            &artdmx_packet(self.universe, self.sequence, frame),
+            self.target,
+        )?;
+        Ok(())
+    }
+}
+
+/// uDMX output through Linux usbfs control transfers
+#[cfg(target_os = "linux")]
+mod udmx {
+    use super::{DmxOutput, UDMX_PID, UDMX_VID};
+    use std::fs::{File, OpenOptions};
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    /// uDMX request: set a range of channels
+    const SET_CHANNEL_RANGE: u8 = 2;
+    /// Vendor request, host to device
+    const REQUEST_TYPE_VENDOR_OUT: u8 = 0x40;
+
+    /// `struct usbdevfs_ctrltransfer`
+    #[repr(C)]
+    struct CtrlTransfer {
+        request_type: u8,
+        request: u8,
+        value: u16,
+        index: u16,
+        length: u16,
+        timeout_ms: u32,
+        data: *mut libc::c_void,
+    }
+
+    /// `USBDEVFS_CONTROL`, i.e. `_IOWR('U', 0, struct usbdevfs_ctrltransfer)`
+    const USBDEVFS_CONTROL: u64 =
+        (3 << 30) | ((std::mem::size_of::<CtrlTransfer>() as u64) << 16) | ((b'U' as u64) << 8);
+
+    pub(super) struct UdmxOutput {
+        device: File,
+    }
+
+    impl UdmxOutput {
+        pub(super) fn open(path: &str) -> std::io::Result<Self> {
+            let device = OpenOptions::new().read(true).write(true).open(path)?;
+            Ok(Self { device })
+        }
+    }
+
+    impl DmxOutput for UdmxOutput {
+        fn send_frame(&mut self, frame: &[u8; 512]) -> std::io::Result<()> {
+            let mut data = *frame;
+            let mut transfer = CtrlTransfer {
+                request_type: REQUEST_TYPE_VENDOR_OUT,
+                request: SET_CHANNEL_RANGE,
+                value: data.len() as u16, // Channel count
+                index: 0,                 // First channel
+                length: data.len() as u16,
+                timeout_ms: 1000,
+                data: data.as_mut_ptr().cast(),
+            };
+            // SAFETY: `transfer` and the buffer it points to outlive the call,
+            // and the buffer holds `length` bytes
+            let result = unsafe {
+                libc::ioctl(
+                    self.device.as_raw_fd(),
+                    USBDEVFS_CONTROL as _,
+                    &mut transfer as *mut CtrlTransfer,
+                )
+            };
+            if result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    /// Find a uDMX in sysfs, returning its usbfs device node
+    pub(super) fn detect() -> Option<String> {
+        let read = |dir: &Path, file: &str| {
+            std::fs::read_to_string(dir.join(file))
+                .ok()
+                .map(|s| s.trim().to_string())
+        };
+
+        std::fs::read_dir("/sys/bus/usb/devices")
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .find_map(|dir| {
+                let vid = u16::from_str_radix(&read(&dir, "idVendor")?, 16).ok()?;
+                let pid = u16::from_str_radix(&read(&dir, "idProduct")?, 16).ok()?;
+                let product = read(&dir, "product").unwrap_or_default();
+                if vid != UDMX_VID || pid != UDMX_PID || !product.contains("uDMX") {
+                    return None;
+                }
+                let bus: u32 = read(&dir, "busnum")?.parse().ok()?;
+                let dev: u32 = read(&dir, "devnum")?.parse().ok()?;
+                Some(format!("/dev/bus/usb/{:03}/{:03}", bus, dev))
+            })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod udmx {
+    use super::DmxOutput;
+
+    pub(super) struct UdmxOutput;
+
+    impl UdmxOutput {
+        pub(super) fn open(_path: &str) -> std::io::Result<Self> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "uDMX output is only supported on Linux",
+            ))
+        }
+    }
+
+    impl DmxOutput for UdmxOutput {
+        fn send_frame(&mut self, _frame: &[u8; 512]) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub(super) fn detect() -> Option<String> {
+        None
+    }
+}
+
 /// DMX sender for thread-safe output
 struct DmxSender {
     tx: std::sync::mpsc::Sender<DmxCommand>,
 }
 
 enum DmxCommand {
-    SetChannel(u16, u8),
-    SetFrame([u8; 512]),
+    SetRefreshRate(f64),
     Stop,
 }
 
@@ -101,51 +490,15 @@ impl DmxBridge {
         }
     }
 
-    /// List available DMX interfaces
+    /// List USB serial ports that might be DMX interfaces
     pub fn list_ports() -> Result<Vec<String>> {
-        // List available serial ports that might be DMX interfaces
-        #[cfg(target_os = "macos")]
-        {
-            let ports: Vec<String> = std::fs::read_dir("/dev")
-                .map(|entries| {
-                    entries
-                        .filter_map(|e| e.ok())
-                        .map(|e| e.path().to_string_lossy().to_string())
-                        .filter(|p| p.contains("tty.usbserial") || p.contains("cu.usbserial"))
-                        .collect()
-                })
-                .unwrap_or_default();
-            Ok(ports)
-        }
-
-        #[cfg(target_os = "linux")]
-        {
-            let ports: Vec<String> = std::fs::read_dir("/dev")
-                .map(|entries| {
-                    entries
-                        .filter_map(|e| e.ok())
-                        .map(|e| e.path().to_string_lossy().to_string())
-                        .filter(|p| p.contains("ttyUSB") || p.contains("ttyACM"))
-                        .collect()
-                })
-                .unwrap_or_default();
-            Ok(ports)
-        }
-
-        #[cfg(target_os = "windows")]
-        {
-            // Windows COM ports
-            Ok(vec![
-                "COM1".to_string(),
-                "COM2".to_string(),
-                "COM3".to_string(),
-            ])
-        }
-
-        #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-        {
-            Ok(vec![])
-        }
+        let ports = serialport::available_ports()
+            .map_err(|e| BridgeError::Other(format!("Failed to list serial ports: {}", e)))?;
+        Ok(ports
+            .into_iter()
+            .filter(|port| matches!(port.port_type, SerialPortType::UsbPort(_)))
+            .map(|port| port.port_name)
+            .collect())
     }
 
     /// Set a single channel value
@@ -153,19 +506,21 @@ impl DmxBridge {
         if channel > 0 && channel <= 512 {
             let mut state = self.dmx_state.lock();
             state[(channel - 1) as usize] = value;
-
-            if let Some(sender) = &self.dmx_sender {
-                let _ = sender.tx.send(DmxCommand::SetChannel(channel, value));
-            }
         }
     }
 
     /// Set entire DMX frame
     pub fn set_frame(&self, data: &[u8; 512]) {
         *self.dmx_state.lock() = *data;
+    }
+
+    /// Change the refresh rate, clamped to 1-44 Hz
+    pub fn set_refresh_rate(&mut self, hz: f64) {
+        let hz = clamp_refresh_rate(hz);
+        self.dmx_config.refresh_rate = hz;
 
         if let Some(sender) = &self.dmx_sender {
-            let _ = sender.tx.send(DmxCommand::SetFrame(*data));
+            let _ = sender.tx.send(DmxCommand::SetRefreshRate(hz));
         }
     }
 
@@ -190,89 +545,69 @@ impl Bridge for DmxBridge {
             return Err(BridgeError::Other("Bridge already running".to_string()));
         }
 
+        let interface_type = self.dmx_config.interface_type;
+        let port = self
+            .dmx_config
+            .port
+            .clone()
+            .or_else(|| interface_type.detect());
+        let refresh_rate = clamp_refresh_rate(self.dmx_config.refresh_rate);
+        let mut output = open_output(interface_type, port, self.dmx_config.universe, refresh_rate)?;
+
         let (tx, rx) = mpsc::channel(100);
         self.tx = Some(tx.clone());
 
         let (dmx_tx, dmx_rx) = std::sync::mpsc::channel::<DmxCommand>();
         self.dmx_sender = Some(DmxSender { tx: dmx_tx });
 
-        let port_path = self.dmx_config.port.clone();
-        let interface_type = self.dmx_config.interface_type;
-        let refresh_rate = self.dmx_config.refresh_rate;
+        *self.running.lock() = true;
         let running = self.running.clone();
         let dmx_state = self.dmx_state.clone();
+        let events = tx.clone();
 
         // Spawn DMX output thread
         let output_thread = std::thread::spawn(move || {
-            let refresh_interval = std::time::Duration::from_secs_f64(1.0 / refresh_rate);
-
-            match interface_type {
-                DmxInterfaceType::Virtual => {
-                    info!("DMX bridge started in virtual mode");
-
-                    // Virtual mode - just log changes
-                    while *running.lock() {
-                        match dmx_rx.recv_timeout(refresh_interval) {
-                            Ok(DmxCommand::SetChannel(ch, val)) => {
-                                debug!("Virtual DMX: Channel {} = {}", ch, val);
-                            }
-                            Ok(DmxCommand::SetFrame(_)) => {
-                                debug!("Virtual DMX: Frame updated");
-                            }
-                            Ok(DmxCommand::Stop) => break,
-                            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                                // Send current frame at refresh rate
-                                let _frame = *dmx_state.lock();
-                                // In real implementation, send to hardware here
-                            }
-                            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+            let mut refresh_interval = Duration::from_secs_f64(1.0 / refresh_rate);
+            let mut next_frame = Instant::now();
+            let mut failing = false;
+
+            while *running.lock() {
+                let wait = next_frame.saturating_duration_since(Instant::now());
+                match dmx_rx.recv_timeout(wait) {
+                    Ok(DmxCommand::SetRefreshRate(hz)) => {
+                        debug!("DMX refresh rate now {} Hz", hz);
+                        refresh_interval = Duration::from_secs_f64(1.0 / hz);
+                        if let Err(e) = output.set_refresh_rate(hz) {
+                            warn!("DMX interface rejected refresh rate: {}", e);
                         }
+                        continue;
                     }
+                    Ok(DmxCommand::Stop) => break,
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
                 }
-                DmxInterfaceType::EnttecPro => {
-                    if let Some(port) = port_path {
-                        info!("Opening ENTTEC DMX USB Pro on {}", port);
-                        // TODO: Implement ENTTEC Pro protocol
-                        // The ENTTEC Pro uses a specific serial protocol with:
-                        // - Start code: 0x7E
-                        // - Label (0x06 for DMX output)
-                        // - Data length (2 bytes, LSB first)
-                        // - DMX data (start code 0x00 + 512 channels)
-                        // - End code: 0xE7
-                        warn!("ENTTEC Pro not yet implemented, using virtual mode");
-                    } else {
-                        error!("No port specified for ENTTEC Pro");
-                    }
 
-                    // Fallback to virtual mode for now
-                    while *running.lock() {
-                        match dmx_rx.recv_timeout(refresh_interval) {
-                            Ok(DmxCommand::Stop) => break,
-                            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
-                            _ => {}
-                        }
+                let frame = *dmx_state.lock();
+                match output.send_frame(&frame) {
+                    Ok(()) if failing => {
+                        info!("DMX output recovered");
+                        failing = false;
                     }
-                }
-                DmxInterfaceType::EnttecOpen | DmxInterfaceType::Ftdi => {
-                    if let Some(port) = port_path {
-                        info!("Opening DMX interface on {}", port);
-                        // TODO: Implement FTDI-based DMX output
-                        // This requires setting specific serial port parameters:
-                        // - Baud rate: 250000
-                        // - Break signal for frame start
-                        // - 8N2 format
-                        warn!("FTDI DMX not yet implemented, using virtual mode");
-                    } else {
-                        error!("No port specified for DMX interface");
+                    Ok(()) => {}
+                    Err(e) if !failing => {
+                        error!("DMX output failed: {}", e);
+                        let _ = events
+                            .try_send(BridgeEvent::Error(format!("DMX output failed: {}", e)));
+                        failing = true;
                     }
+                    Err(_) => {}
+                }
 
-                    while *running.lock() {
-                        match dmx_rx.recv_timeout(refresh_interval) {
-                            Ok(DmxCommand::Stop) => break,
-                            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
-                            _ => {}
-                        }
-                    }
+                // Keep the cadence, but don't burst to catch up after a stall
+                next_frame += refresh_interval;
+                let now = Instant::now();
+                if next_frame < now {
+                    next_frame = now + refresh_interval;
                 }
             }
 
@@ -280,7 +615,6 @@ impl Bridge for DmxBridge {
         });
 
         self._output_thread = Some(output_thread);
-        *self.running.lock() = true;
 
         let _ = tx.send(BridgeEvent::Connected).await;
         Ok(rx)
@@ -368,4 +702,113 @@ mod tests {
         assert_eq!(bridge.get_channel(0), None);
         assert_eq!(bridge.get_channel(513), None);
     }
+
+    #[test]
+    fn test_enttec_pro_message() {
+        let message = enttec_message(ENTTEC_OUTPUT_DMX, &[0x00; 513]);
+        assert_eq!(&message[..4], &[0x7E, 6, 0x01, 0x02]);
+        assert_eq!(message.len(), 4 + 513 + 1);
+        assert_eq!(message.last(), Some(&0xE7));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_enttec_pro_output() {
+        use std::io::Read;
+
+        let (widget, host) = serialport::TTYPort::pair().unwrap();
+        let mut output = EnttecProOutput {
+            port: Box::new(host),
+        };
+        let mut widget: Box<dyn SerialPort> = Box::new(widget);
+        widget.set_timeout(Duration::from_secs(1)).unwrap();
+
+        output.set_refresh_rate(30.0).unwrap();
+        let mut params = [0u8; 10];
+        widget.read_exact(&mut params).unwrap();
+        assert_eq!(params, [0x7E, 4, 5, 0, 0, 0, 9, 1, 30, 0xE7]);
+
+        let mut frame = [0u8; 512];
+        frame[0] = 255;
+        frame[511] = 7;
+        output.send_frame(&frame).unwrap();
+        let mut message = [0u8; 518];
+        widget.read_exact(&mut message).unwrap();
+        assert_eq!(&message[..5], &[0x7E, 6, 0x01, 0x02, 0x00]);
+        assert_eq!(message[5], 255);
+        assert_eq!(message[516], 7);
+        assert_eq!(message[517], 0xE7);
+    }
+
+    #[test]
+    fn test_artdmx_packet() {
+        let mut frame = [0u8; 512];
+        frame[0] = 200;
+        let packet = artdmx_packet(0x0123, 9, &frame);
+        assert_eq!(&packet[..8], b"Art-Net\0");
+        assert_eq!(&packet[8..10], &[0x00, 0x50]);
+        assert_eq!(&packet[10..12], &[0, 14]);
+        assert_eq!(packet[12], 9);
+        assert_eq!(&packet[14..16], &[0x23, 0x01]);
+        assert_eq!(&packet[16..18], &[0x02, 0x00]);
+        assert_eq!(packet[18], 200);
+        assert_eq!(packet.len(), 18 + 512);
+    }
+
+    #[test]
+    fn test_pick_serial_port() {
+        use serialport::UsbPortInfo;
+
+        let usb = |name: &str, vid: u16, product: &str| SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid,
+                pid: 0x6001,
+                serial_number: None,
+                manufacturer: None,
+                product: Some(product.to_string()),
+            }),
+        };
+        let ports = vec![
+            usb("/dev/ttyACM0", 0x2341, "Arduino Uno"),
+            usb("/dev/ttyUSB0", FTDI_VID, "FT232R USB UART"),
+            usb("/dev/ttyUSB1", FTDI_VID, "DMX USB PRO"),
+        ];
+
+        assert_eq!(
+            pick_serial_port(DmxInterfaceType::EnttecPro, &ports).as_deref(),
+            Some("/dev/ttyUSB1")
+        );
+        assert_eq!(
+            pick_serial_port(DmxInterfaceType::EnttecOpen, &ports).as_deref(),
+            Some("/dev/ttyUSB0")
+        );
+        assert_eq!(
+            pick_serial_port(DmxInterfaceType::EnttecPro, &ports[..2]).as_deref(),
+            Some("/dev/ttyUSB0")
+        );
+        assert_eq!(pick_serial_port(DmxInterfaceType::Ftdi, &ports[..1]), None);
+    }
+
+    #[test]
+    fn test_refresh_rate_clamped() {
+        assert_eq!(clamp_refresh_rate(30.0), 30.0);
+        assert_eq!(clamp_refresh_rate(120.0), MAX_REFRESH_RATE);
+        assert_eq!(clamp_refresh_rate(0.0), MIN_REFRESH_RATE);
+        assert_eq!(clamp_refresh_rate(f64::NAN), MAX_REFRESH_RATE);
+    }
+
+    #[tokio::test]
+    async fn test_missing_interface() {
+        let mut bridge = DmxBridge::new(DmxBridgeConfig {
+            interface_type: DmxInterfaceType::EnttecPro,
+            port: Some("/nonexistent/ttyUSB9".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(
+            bridge.start().await,
+            Err(BridgeError::ConnectionFailed(_))
+        ));
+        assert!(!bridge.is_running());
+    }
 }
@@ -0,0 +1,470 @@
+//! Gamepad / HID bridge
+//!
+//! Reads gamepads and joysticks through gilrs and publishes their controls:
+//! sticks as streams under `/hid/<device>/axis/<n>`, buttons as events under
+//! `/hid/<device>/button/<n>`, and how far analog triggers are pulled as
+//! streams under `/hid/<device>/button/<n>/value`. `<device>` is the
+//! index gilrs assigned the controller, which stays the same while it is
+//! plugged in. Axes pass through a dead zone and an optional response curve
+//! before they are published.
+
+use async_trait::async_trait;
+use clasp_core::{Message, PublishMessage, SetMessage, SignalType, Value};
+use gilrs::{Axis, Button, EventType, Gilrs};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, CurveType, Result};
+
+/// HID bridge configuration
+#[derive(Debug, Clone)]
+pub struct HidBridgeConfig {
+    /// Address namespace
+    pub namespace: String,
+    /// Only read controllers whose name contains this (case-insensitive)
+    pub device: Option<String>,
+    /// Axis values closer to centre than this (0-1) are published as 0
+    pub dead_zone: f64,
+    /// Response curve applied to axis magnitude after the dead zone
+    pub curve: CurveType,
+    /// How often to poll for input, in milliseconds
+    pub poll_interval_ms: u64,
+}
+
+impl Default for HidBridgeConfig {
+    fn default() -> Self {
+        Self {
+            namespace: "/hid".to_string(),
+            device: None,
+            dead_zone: 0.1,
+            curve: CurveType::Linear,
+            poll_interval_ms: 4,
+        }
+    }
+}
+
+/// A controller input, decoupled from gilrs so mapping can be tested.
+/// Controls are named by their address below the device, e.g. `axis/0`.
+#[derive(Debug, Clone, PartialEq)]
+enum HidInput {
+    Connected(String),
+    Disconnected,
+    /// Axis position, -1 to 1 for sticks and 0 to 1 for triggers
+    Axis(String, f64),
+    Button(String, bool),
+}
+
+/// Control number for a gilrs axis, or its raw code when gilrs has no mapping
+fn axis_control(axis: Axis, code: u32) -> String {
+    let index = match axis {
+        Axis::LeftStickX => 0,
+        Axis::LeftStickY => 1,
+        Axis::LeftZ => 2,
+        Axis::RightStickX => 3,
+        Axis::RightStickY => 4,
+        Axis::RightZ => 5,
+        Axis::DPadX => 6,
+        Axis::DPadY => 7,
+        _ => return format!("raw/{}", code),
+    };
+    index.to_string()
+}
+
+/// Control number for a gilrs button, or its raw code when gilrs has no mapping
+fn button_control(button: Button, code: u32) -> String {
+    let index = match button {
+        Button::South => 0,
+        Button::East => 1,
+        Button::North => 2,
+        Button::West => 3,
+        Button::C => 4,
+        Button::Z => 5,
+        Button::LeftTrigger => 6,
+        Button::LeftTrigger2 => 7,
+        Button::RightTrigger => 8,
+        Button::RightTrigger2 => 9,
+        Button::Select => 10,
+        Button::Start => 11,
+        Button::Mode => 12,
+        Button::LeftThumb => 13,
+        Button::RightThumb => 14,
+        Button::DPadUp => 15,
+        Button::DPadDown => 16,
+        Button::DPadLeft => 17,
+        Button::DPadRight => 18,
+        _ => return format!("raw/{}", code),
+    };
+    index.to_string()
+}
+
+/// Convert a gilrs event into a controller input
+fn gilrs_input(event: &EventType, name: &str) -> Option<HidInput> {
+    match *event {
+        EventType::Connected => Some(HidInput::Connected(name.to_string())),
+        EventType::Disconnected => Some(HidInput::Disconnected),
+        EventType::AxisChanged(axis, value, code) => Some(HidInput::Axis(
+            format!("axis/{}", axis_control(axis, code.into_u32())),
+            value as f64,
+        )),
+        EventType::ButtonPressed(button, code) => Some(HidInput::Button(
+            format!("button/{}", button_control(button, code.into_u32())),
+            true,
+        )),
+        EventType::ButtonReleased(button, code) => Some(HidInput::Button(
+            format!("button/{}", button_control(button, code.into_u32())),
+            false,
+        )),
+        // Analog triggers also report how far they are pulled, which gets the
+        // same dead zone and curve as an axis
+        EventType::ButtonChanged(
+            button @ (Button::LeftTrigger2 | Button::RightTrigger2),
+            value,
+            code,
+        ) => Some(HidInput::Axis(
+            format!("button/{}/value", button_control(button, code.into_u32())),
+            value as f64,
+        )),
+        _ => None,
+    }
+}
+
+/// Apply the dead zone and curve to an axis value, keeping its sign
+fn shape_axis(value: f64, dead_zone: f64, curve: &CurveType) -> f64 {
+    let dead_zone = dead_zone.clamp(0.0, 0.99);
+    let magnitude = value.abs().min(1.0);
+    if magnitude <= dead_zone {
+        return 0.0;
+    }
+    let scaled = (magnitude - dead_zone) / (1.0 - dead_zone);
+    curve.apply(scaled).clamp(0.0, 1.0).copysign(value)
+}
+
+/// Turns controller inputs into CLASP messages
+struct HidMapper {
+    namespace: String,
+    dead_zone: f64,
+    curve: CurveType,
+    /// Last published value per axis address, so jitter inside the dead zone
+    /// does not flood the router with zeros
+    axes: HashMap<String, f64>,
+}
+
+impl HidMapper {
+    fn new(config: &HidBridgeConfig) -> Self {
+        Self {
+            namespace: config.namespace.clone(),
+            dead_zone: config.dead_zone,
+            curve: config.curve,
+            axes: HashMap::new(),
+        }
+    }
+
+    fn map(&mut self, device: usize, input: HidInput) -> Option<Message> {
+        let base = format!("{}/{}", self.namespace, device);
+        match input {
+            HidInput::Connected(name) => {
+                info!("Gamepad {} connected: {}", device, name);
+                Some(set(format!("{}/name", base), Value::String(name)))
+            }
+            HidInput::Disconnected => {
+                info!("Gamepad {} disconnected", device);
+                let prefix = format!("{}/", base);
+                self.axes.retain(|address, _| !address.starts_with(&prefix));
+                Some(set(format!("{}/name", base), Value::Null))
+            }
+            HidInput::Axis(control, value) => {
+                let address = format!("{}/{}", base, control);
+                let value = shape_axis(value, self.dead_zone, &self.curve);
+                if self.axes.insert(address.clone(), value) == Some(value) {
+                    return None;
+                }
+                Some(publish(address, SignalType::Stream, Value::Float(value)))
+            }
+            HidInput::Button(control, pressed) => Some(publish(
+                format!("{}/{}", base, control),
+                SignalType::Event,
+                Value::Bool(pressed),
+            )),
+        }
+    }
+}
+
+fn set(address: String, value: Value) -> Message {
+    Message::Set(SetMessage {
+        address,
+        value,
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
+fn publish(address: String, signal: SignalType, value: Value) -> Message {
+    let (value, payload) = match signal {
+        SignalType::Event => (None, Some(value)),
+        _ => (Some(value), None),
+    };
+    Message::Publish(PublishMessage {
+        address,
+        signal: Some(signal),
+        value,
+        payload,
+        samples: None,
+        rate: None,
+        id: None,
+        phase: None,
+        timestamp: Some(clasp_core::time::now()),
+        timeline: None,
+    })
+}
+
+/// Gamepad / HID to CLASP bridge (input only)
+pub struct HidBridge {
+    config: BridgeConfig,
+    hid_config: HidBridgeConfig,
+    running: Arc<Mutex<bool>>,
+    /// Handle to the polling thread
+    _input_thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl HidBridge {
+    pub fn new(hid_config: HidBridgeConfig) -> Self {
+        let config = BridgeConfig {
+            name: "HID Bridge".to_string(),
+            protocol: "hid".to_string(),
+            bidirectional: false,
+            ..Default::default()
+        };
+
+        Self {
+            config,
+            hid_config,
+            running: Arc::new(Mutex::new(false)),
+            _input_thread: None,
+        }
+    }
+
+    /// List connected gamepads and joysticks
+    pub fn list_devices() -> Result<Vec<String>> {
+        let gilrs = Gilrs::new().map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+        Ok(gilrs
+            .gamepads()
+            .map(|(id, gamepad)| format!("{}: {}", usize::from(id), gamepad.name()))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Bridge for HidBridge {
+    fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
+        if *self.running.lock() {
+            return Err(BridgeError::Other("Bridge already running".to_string()));
+        }
+
+        let (tx, rx) = mpsc::channel(100);
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let config = self.hid_config.clone();
+        let running = self.running.clone();
+        *running.lock() = true;
+
+        // gilrs is not Send on every platform, so it lives on its own thread
+        let input_thread = std::thread::spawn(move || {
+            let mut gilrs = match Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            let filter = config.device.as_ref().map(|d| d.to_lowercase());
+            let matches = |name: &str| match &filter {
+                Some(f) => name.to_lowercase().contains(f),
+                None => true,
+            };
+            let mut mapper = HidMapper::new(&config);
+
+            // Announce controllers that were plugged in before the bridge started
+            let mut initial = Vec::new();
+            for (id, gamepad) in gilrs.gamepads() {
+                if matches(gamepad.name()) {
+                    initial.push((usize::from(id), gamepad.name().to_string()));
+                }
+            }
+            if initial.is_empty() {
+                warn!("No gamepads found, waiting for one to be connected");
+            }
+            let _ = ready_tx.send(Ok(()));
+            let _ = tx.blocking_send(BridgeEvent::Connected);
+            for (device, name) in initial {
+                if let Some(msg) = mapper.map(device, HidInput::Connected(name)) {
+                    let _ = tx.blocking_send(BridgeEvent::ToClasp(msg));
+                }
+            }
+
+            let poll_interval = Duration::from_millis(config.poll_interval_ms.max(1));
+            while *running.lock() {
+                while let Some(event) = gilrs.next_event() {
+                    let name = gilrs.gamepad(event.id).name().to_string();
+                    if !matches(&name) {
+                        continue;
+                    }
+                    let Some(input) = gilrs_input(&event.event, &name) else {
+                        continue;
+                    };
+                    if let Some(msg) = mapper.map(usize::from(event.id), input) {
+                        if tx.blocking_send(BridgeEvent::ToClasp(msg)).is_err() {
+                            debug!("HID event receiver dropped");
+                            return;
+                        }
+                    }
+                }
+                std::thread::sleep(poll_interval);
+            }
+        });
+
+        match ready_rx.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                *self.running.lock() = false;
+                return Err(BridgeError::ConnectionFailed(format!(
+                    "Failed to open gamepads: {}",
+                    e
+                )));
+            }
+            Err(_) => {
+                *self.running.lock() = false;
+                return Err(BridgeError::Other("HID thread exited".to_string()));
+            }
+        }
+
+        self._input_thread = Some(input_thread);
+        info!("HID bridge started");
+        Ok(rx)
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        *self.running.lock() = false;
+        // The thread exits on its own once running is false
+        self._input_thread = None;
+        info!("HID bridge stopped");
+        Ok(())
+    }
+
+    async fn send(&self, _message: Message) -> Result<()> {
+        Err(BridgeError::Send("HID bridge is input only".to_string()))
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock()
+    }
+
+    fn namespace(&self) -> &str {
+        &self.hid_config.namespace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream_value(msg: Option<Message>) -> (String, f64) {
+        match msg {
+            Some(Message::Publish(PublishMessage {
+                address,
+                signal: Some(SignalType::Stream),
+                value: Some(Value::Float(v)),
+                ..
+            })) => (address, v),
+            other => panic!("Expected stream, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_shape_axis_dead_zone() {
+        assert_eq!(shape_axis(0.05, 0.1, &CurveType::Linear), 0.0);
+        assert_eq!(shape_axis(-0.1, 0.1, &CurveType::Linear), 0.0);
+        assert!((shape_axis(0.55, 0.1, &CurveType::Linear) - 0.5).abs() < 1e-9);
+        assert!((shape_axis(-0.55, 0.1, &CurveType::Linear) + 0.5).abs() < 1e-9);
+        assert_eq!(shape_axis(1.0, 0.1, &CurveType::Linear), 1.0);
+        assert_eq!(shape_axis(-1.5, 0.1, &CurveType::Linear), -1.0);
+    }
+
+    #[test]
+    fn test_shape_axis_curve() {
+        // Quadratic keeps the sign and gives finer control near centre
+        assert!((shape_axis(0.5, 0.0, &CurveType::QuadIn) - 0.25).abs() < 1e-9);
+        assert!((shape_axis(-0.5, 0.0, &CurveType::QuadIn) + 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_axis_mapping_skips_repeats() {
+        let mut mapper = HidMapper::new(&HidBridgeConfig::default());
+        let (address, value) =
+            stream_value(mapper.map(0, HidInput::Axis("axis/0".to_string(), 1.0)));
+        assert_eq!(address, "/hid/0/axis/0");
+        assert_eq!(value, 1.0);
+
+        stream_value(mapper.map(0, HidInput::Axis("axis/0".to_string(), 0.0)));
+        // Jitter around centre stays at 0, so nothing more is published
+        assert!(mapper
+            .map(0, HidInput::Axis("axis/0".to_string(), 0.03))
+            .is_none());
+        assert!(mapper
+            .map(0, HidInput::Axis("axis/0".to_string(), -0.05))
+            .is_none());
+
+        // Another device's axis is tracked separately
+        stream_value(mapper.map(1, HidInput::Axis("axis/0".to_string(), 0.0)));
+    }
+
+    #[test]
+    fn test_button_mapping() {
+        let mut mapper = HidMapper::new(&HidBridgeConfig::default());
+        match mapper.map(2, HidInput::Button("button/0".to_string(), true)) {
+            Some(Message::Publish(msg)) => {
+                assert_eq!(msg.address, "/hid/2/button/0");
+                assert_eq!(msg.signal, Some(SignalType::Event));
+                assert_eq!(msg.payload, Some(Value::Bool(true)));
+            }
+            other => panic!("Expected event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_connect_and_disconnect() {
+        let mut mapper = HidMapper::new(&HidBridgeConfig::default());
+        match mapper.map(0, HidInput::Connected("Xbox Controller".to_string())) {
+            Some(Message::Set(set)) => {
+                assert_eq!(set.address, "/hid/0/name");
+                assert_eq!(set.value, Value::String("Xbox Controller".to_string()));
+            }
+            other => panic!("Expected SET, got {:?}", other),
+        }
+        stream_value(mapper.map(0, HidInput::Axis("axis/1".to_string(), 0.0)));
+
+        match mapper.map(0, HidInput::Disconnected) {
+            Some(Message::Set(set)) => assert_eq!(set.value, Value::Null),
+            other => panic!("Expected SET, got {:?}", other),
+        }
+        // The axis state was forgotten, so a reconnected pad reports again
+        stream_value(mapper.map(0, HidInput::Axis("axis/1".to_string(), 0.0)));
+    }
+
+    #[test]
+    fn test_config_default() {
+        let config = HidBridgeConfig::default();
+        assert_eq!(config.namespace, "/hid");
+        assert_eq!(config.dead_zone, 0.1);
+        assert!(config.device.is_none());
+    }
+}
@@ -0,0 +1,431 @@
+//! Global hotkey bridge
+//!
+//! Registers system-wide hotkeys so an operator can fire cues, GOs and panic
+//! buttons from the keyboard without focusing a particular app. Each binding
+//! emits an event on `/hotkey/<key>` (e.g. `/hotkey/ctrl+shift+f1`) unless it
+//! names its own address and value. Bindings can be loaded from a JSON
+//! mapping file.
+//!
+//! Linux needs an X11 session (Wayland compositors do not allow global
+//! hotkeys). On Windows and macOS the thread that starts the bridge must run
+//! the platform event loop, as GUI hosts such as Electron or tao do.
+
+use async_trait::async_trait;
+use clasp_core::{Message, PublishMessage, SetMessage, SignalType, Value};
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
+
+/// Modifier names in the order they appear in a normalized key
+const MODIFIERS: [&str; 4] = ["ctrl", "alt", "shift", "super"];
+
+/// A key combination and what it sends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    /// Key combination, e.g. `F1`, `ctrl+shift+p` or `alt+Space`
+    pub key: String,
+    /// Address to send to; `<namespace>/<key>` when unset
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Value to send; `true` when unset
+    #[serde(default)]
+    pub value: Option<Value>,
+    /// Send a SET (Param) instead of an event
+    #[serde(default)]
+    pub set: bool,
+}
+
+impl HotkeyBinding {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            address: None,
+            value: None,
+            set: false,
+        }
+    }
+
+    /// Send to `address` instead of the default
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// Send `value` instead of `true`
+    pub fn with_value(mut self, value: impl Into<Value>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// The message sent when the key is pressed
+    fn message(&self, namespace: &str) -> Result<Message> {
+        let address = match &self.address {
+            Some(address) => address.clone(),
+            None => format!("{}/{}", namespace, normalize_key(&self.key)?),
+        };
+        let value = self.value.clone().unwrap_or(Value::Bool(true));
+
+        Ok(if self.set {
+            Message::Set(SetMessage {
+                address,
+                value,
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            })
+        } else {
+            Message::Publish(PublishMessage {
+                address,
+                signal: Some(SignalType::Event),
+                value: None,
+                payload: Some(value),
+                samples: None,
+                rate: None,
+                id: None,
+                phase: None,
+                timestamp: None,
+                timeline: None,
+            })
+        })
+    }
+}
+
+/// Normalize a key combination to lowercase with modifiers in a fixed order,
+/// so `Shift+Ctrl+F1` and `ctrl+shift+f1` name the same hotkey
+fn normalize_key(key: &str) -> Result<String> {
+    let mut modifiers = Vec::new();
+    let mut code = None;
+    for part in key.split('+').map(|p| p.trim().to_lowercase()) {
+        let modifier = match part.as_str() {
+            "ctrl" | "control" => "ctrl",
+            "alt" | "option" => "alt",
+            "shift" => "shift",
+            "super" | "cmd" | "command" | "meta" => "super",
+            "" => {
+                return Err(BridgeError::Mapping(format!("Invalid hotkey: {}", key)));
+            }
+            _ => {
+                if code.replace(part).is_some() {
+                    return Err(BridgeError::Mapping(format!(
+                        "Hotkey has more than one key: {}",
+                        key
+                    )));
+                }
+                continue;
+            }
+        };
+        if !modifiers.contains(&modifier) {
+            modifiers.push(modifier);
+        }
+    }
+
+    let code = code.ok_or_else(|| BridgeError::Mapping(format!("Hotkey has no key: {}", key)))?;
+    let mut parts: Vec<&str> = MODIFIERS
+        .iter()
+        .copied()
+        .filter(|m| modifiers.contains(m))
+        .collect();
+    parts.push(&code);
+    Ok(parts.join("+"))
+}
+
+/// Hotkey bridge configuration
+#[derive(Debug, Clone)]
+pub struct HotkeyBridgeConfig {
+    /// Address namespace for bindings without their own address
+    pub namespace: String,
+    /// Hotkeys to register
+    pub bindings: Vec<HotkeyBinding>,
+    /// JSON file with more bindings, read when the bridge starts
+    pub mapping_file: Option<PathBuf>,
+}
+
+impl Default for HotkeyBridgeConfig {
+    fn default() -> Self {
+        Self {
+            namespace: "/hotkey".to_string(),
+            bindings: Vec::new(),
+            mapping_file: None,
+        }
+    }
+}
+
+impl HotkeyBridgeConfig {
+    /// Read bindings from a JSON mapping file, an array of
+    /// `{ "key", "address"?, "value"?, "set"? }` objects
+    pub fn load_bindings(path: impl AsRef<Path>) -> Result<Vec<HotkeyBinding>> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            BridgeError::Mapping(format!("Invalid hotkey mapping {}: {}", path.display(), e))
+        })
+    }
+
+    /// All bindings, including the mapping file, keyed by normalized key
+    fn resolve(&self) -> Result<Vec<(String, Message)>> {
+        let mut bindings = self.bindings.clone();
+        if let Some(path) = &self.mapping_file {
+            bindings.extend(Self::load_bindings(path)?);
+        }
+
+        let mut resolved: Vec<(String, Message)> = Vec::with_capacity(bindings.len());
+        for binding in &bindings {
+            let key = normalize_key(&binding.key)?;
+            if resolved.iter().any(|(k, _)| *k == key) {
+                return Err(BridgeError::Mapping(format!(
+                    "Hotkey bound more than once: {}",
+                    key
+                )));
+            }
+            resolved.push((key, binding.message(&self.namespace)?));
+        }
+        Ok(resolved)
+    }
+}
+
+/// Global hotkey to CLASP bridge (input only)
+pub struct HotkeyBridge {
+    config: BridgeConfig,
+    hotkey_config: HotkeyBridgeConfig,
+    running: Arc<Mutex<bool>>,
+    /// Handle to the thread that owns the registrations
+    _hotkey_thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl HotkeyBridge {
+    pub fn new(hotkey_config: HotkeyBridgeConfig) -> Self {
+        let config = BridgeConfig {
+            name: "Hotkey Bridge".to_string(),
+            protocol: "hotkey".to_string(),
+            bidirectional: false,
+            ..Default::default()
+        };
+
+        Self {
+            config,
+            hotkey_config,
+            running: Arc::new(Mutex::new(false)),
+            _hotkey_thread: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Bridge for HotkeyBridge {
+    fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
+        if *self.running.lock() {
+            return Err(BridgeError::Other("Bridge already running".to_string()));
+        }
+
+        let bindings = self.hotkey_config.resolve()?;
+        if bindings.is_empty() {
+            warn!("Hotkey bridge started with no bindings");
+        }
+
+        let (tx, rx) = mpsc::channel(100);
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let running = self.running.clone();
+        *running.lock() = true;
+
+        // The manager is not Send on every platform, so it lives on its own thread
+        let hotkey_thread = std::thread::spawn(move || {
+            let manager = match GlobalHotKeyManager::new() {
+                Ok(manager) => manager,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(BridgeError::ConnectionFailed(format!(
+                        "Failed to start hotkey manager: {}",
+                        e
+                    ))));
+                    return;
+                }
+            };
+
+            let mut registered = Vec::with_capacity(bindings.len());
+            let mut messages = HashMap::with_capacity(bindings.len());
+            for (key, message) in bindings {
+                let hotkey: HotKey = match key.parse() {
+                    Ok(hotkey) => hotkey,
+                    Err(e) => {
+                        let _ = manager.unregister_all(&registered);
+                        let _ = ready_tx.send(Err(BridgeError::Mapping(format!(
+                            "Invalid hotkey {}: {}",
+                            key, e
+                        ))));
+                        return;
+                    }
+                };
+                if let Err(e) = manager.register(hotkey) {
+                    // Usually another app already holds the combination
+                    warn!("Failed to register hotkey {}: {}", key, e);
+                    continue;
+                }
+                debug!("Registered hotkey {}", key);
+                registered.push(hotkey);
+                messages.insert(hotkey.id(), (key, message));
+            }
+
+            let _ = ready_tx.send(Ok(()));
+            let _ = tx.blocking_send(BridgeEvent::Connected);
+
+            let events = GlobalHotKeyEvent::receiver();
+            while *running.lock() {
+                let Ok(event) = events.recv_timeout(Duration::from_millis(100)) else {
+                    continue;
+                };
+                if event.state != HotKeyState::Pressed {
+                    continue;
+                }
+                // The event channel is shared by the whole process, so ignore
+                // hotkeys registered elsewhere
+                let Some((key, message)) = messages.get(&event.id) else {
+                    continue;
+                };
+                debug!("Hotkey {} pressed", key);
+                if tx
+                    .blocking_send(BridgeEvent::ToClasp(message.clone()))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+
+            if let Err(e) = manager.unregister_all(&registered) {
+                warn!("Failed to unregister hotkeys: {}", e);
+            }
+        });
+
+        match ready_rx.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                *self.running.lock() = false;
+                return Err(e);
+            }
+            Err(_) => {
+                *self.running.lock() = false;
+                return Err(BridgeError::Other("Hotkey thread exited".to_string()));
+            }
+        }
+
+        self._hotkey_thread = Some(hotkey_thread);
+        info!("Hotkey bridge started");
+        Ok(rx)
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        *self.running.lock() = false;
+        // The thread unregisters the hotkeys and exits once running is false
+        self._hotkey_thread = None;
+        info!("Hotkey bridge stopped");
+        Ok(())
+    }
+
+    async fn send(&self, _message: Message) -> Result<()> {
+        Err(BridgeError::Send("Hotkey bridge is input only".to_string()))
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock()
+    }
+
+    fn namespace(&self) -> &str {
+        &self.hotkey_config.namespace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(normalize_key("F1").unwrap(), "f1");
+        assert_eq!(normalize_key("Shift+Ctrl+P").unwrap(), "ctrl+shift+p");
+        assert_eq!(
+            normalize_key("cmd + option + Space").unwrap(),
+            "alt+super+space"
+        );
+        assert!(normalize_key("ctrl+shift").is_err());
+        assert!(normalize_key("ctrl+a+b").is_err());
+        assert!(normalize_key("ctrl++a").is_err());
+    }
+
+    #[test]
+    fn test_default_binding_message() {
+        match HotkeyBinding::new("Ctrl+F1").message("/hotkey").unwrap() {
+            Message::Publish(msg) => {
+                assert_eq!(msg.address, "/hotkey/ctrl+f1");
+                assert_eq!(msg.signal, Some(SignalType::Event));
+                assert_eq!(msg.payload, Some(Value::Bool(true)));
+            }
+            other => panic!("Expected event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_custom_binding_message() {
+        let mut binding = HotkeyBinding::new("Escape")
+            .with_address("/show/blackout")
+            .with_value(1.0);
+        binding.set = true;
+        match binding.message("/hotkey").unwrap() {
+            Message::Set(msg) => {
+                assert_eq!(msg.address, "/show/blackout");
+                assert_eq!(msg.value, Value::Float(1.0));
+            }
+            other => panic!("Expected SET, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mapping_file() {
+        let path =
+            std::env::temp_dir().join(format!("clasp-hotkeys-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"[
+                {"key": "space", "address": "/cue/go"},
+                {"key": "shift+escape", "address": "/show/panic", "value": 1, "set": true}
+            ]"#,
+        )
+        .unwrap();
+
+        let config = HotkeyBridgeConfig {
+            bindings: vec![HotkeyBinding::new("F1")],
+            mapping_file: Some(path.clone()),
+            ..Default::default()
+        };
+        let resolved = config.resolve().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let keys: Vec<&str> = resolved.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["f1", "space", "shift+escape"]);
+        assert!(matches!(&resolved[2].1, Message::Set(set) if set.value == Value::Int(1)));
+    }
+
+    #[test]
+    fn test_duplicate_binding_rejected() {
+        let config = HotkeyBridgeConfig {
+            bindings: vec![
+                HotkeyBinding::new("ctrl+shift+g"),
+                HotkeyBinding::new("Shift+Ctrl+G").with_address("/cue/go"),
+            ],
+            ..Default::default()
+        };
+        assert!(matches!(config.resolve(), Err(BridgeError::Mapping(_))));
+    }
+}
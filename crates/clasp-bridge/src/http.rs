@@ -3,7 +3,9 @@
 //! Provides both HTTP server and client capabilities for CLASP.
 //! - Server mode: Expose CLASP signals as REST endpoints
 //! - Client mode: Bridge HTTP requests to CLASP signals
+//! - Webhook mode: Push matching CLASP signals to webhook URLs
 
+use crate::mapping::render_json_template;
 use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
 use async_trait::async_trait;
 use axum::{
@@ -13,16 +15,28 @@ use axum::{
     routing::{delete, get},
     Router,
 };
+use clasp_core::address::Pattern;
 use clasp_core::{Message, PublishMessage, SetMessage, SignalType, Value};
+use hmac::{Hmac, Mac};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// Deliveries queued per webhook before new ones are dropped
+const WEBHOOK_QUEUE_SIZE: usize = 256;
+
+/// Upper bound on the delay between webhook retries
+const MAX_WEBHOOK_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Header carrying the HMAC-SHA256 signature of a webhook body
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Clasp-Signature";
 
 /// HTTP method
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -45,6 +59,8 @@ pub enum HttpMode {
     Server,
     /// HTTP client making requests
     Client,
+    /// Outbound webhooks for matching CLASP signals
+    Webhook,
 }
 
 /// Endpoint configuration for server mode
@@ -75,6 +91,122 @@ fn endpoint_enabled_default() -> bool {
     true
 }
 
+/// Webhook configuration for webhook mode
+///
+/// The body is built from `template` when set, otherwise it is
+/// `{"address": ..., "value": ..., "timestamp": ...}`. Template strings may
+/// contain `{{address}}`, `{{value}}`, `{{timestamp}}` and `{{type}}`
+/// (`"set"` or `"event"`); a string that is only a placeholder is replaced by
+/// the JSON value itself, so `"{{value}}"` keeps numbers as numbers. Any
+/// other template fields are sent as-is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// URL to deliver to
+    pub url: String,
+    /// CLASP address pattern that triggers the webhook (e.g., "/lights/**")
+    pub pattern: String,
+    /// HTTP method (default: POST)
+    #[serde(default = "default_webhook_method")]
+    pub method: HttpMethod,
+    /// JSON body template
+    #[serde(default)]
+    pub template: Option<serde_json::Value>,
+    /// Extra request headers
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Shared secret for signing the body with HMAC-SHA256
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Retries after a failed delivery (default: 3)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled for each
+    /// further retry (default: 500)
+    #[serde(default = "default_retry_backoff")]
+    pub retry_backoff_ms: u64,
+}
+
+fn default_webhook_method() -> HttpMethod {
+    HttpMethod::POST
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff() -> u64 {
+    500
+}
+
+impl WebhookConfig {
+    /// Create a webhook posting the default body for addresses matching `pattern`
+    pub fn new(url: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            pattern: pattern.into(),
+            method: default_webhook_method(),
+            template: None,
+            headers: HashMap::new(),
+            secret: None,
+            max_retries: default_max_retries(),
+            retry_backoff_ms: default_retry_backoff(),
+        }
+    }
+
+    /// Build the request body for a signal
+    pub fn render(
+        &self,
+        address: &str,
+        value: &Value,
+        kind: &str,
+        timestamp: u64,
+    ) -> serde_json::Value {
+        let vars = [
+            ("address", serde_json::Value::String(address.to_string())),
+            ("value", HttpBridge::value_to_json(value)),
+            ("timestamp", serde_json::Value::Number(timestamp.into())),
+            ("type", serde_json::Value::String(kind.to_string())),
+        ];
+
+        match &self.template {
+            Some(template) => render_json_template(template, &vars),
+            None => serde_json::json!({
+                "address": address,
+                "value": vars[1].1,
+                "timestamp": timestamp,
+            }),
+        }
+    }
+
+    /// Signature header value for `body`, if a secret is configured
+    pub fn signature(&self, body: &[u8]) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        Some(format!(
+            "sha256={}",
+            hex::encode(mac.finalize().into_bytes())
+        ))
+    }
+
+    /// Delay before retry number `attempt` (starting at 0)
+    fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(
+            self.retry_backoff_ms
+                .saturating_mul(1u64 << attempt.min(16)),
+        )
+        .min(MAX_WEBHOOK_BACKOFF)
+    }
+}
+
+/// A started webhook and the queue feeding its delivery task
+struct Webhook {
+    config: WebhookConfig,
+    pattern: Pattern,
+    queue: mpsc::Sender<serde_json::Value>,
+}
+
 /// HTTP Bridge configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpBridgeConfig {
@@ -108,6 +240,9 @@ pub struct HttpBridgeConfig {
     /// Endpoints to poll in client mode
     #[serde(default)]
     pub poll_endpoints: Vec<String>,
+    /// Webhooks to deliver to in webhook mode
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
 }
 
 fn default_true() -> bool {
@@ -176,6 +311,7 @@ impl Default for HttpBridgeConfig {
             namespace: "/http".to_string(),
             poll_interval_ms: 0,
             poll_endpoints: vec![],
+            webhooks: vec![],
         }
     }
 }
@@ -195,6 +331,7 @@ pub struct HttpBridge {
     running: Arc<Mutex<bool>>,
     shutdown_tx: Option<mpsc::Sender<()>>,
     signals: Arc<parking_lot::RwLock<HashMap<String, Value>>>,
+    webhooks: Vec<Webhook>,
 }
 
 impl HttpBridge {
@@ -213,6 +350,7 @@ impl HttpBridge {
             running: Arc::new(Mutex::new(false)),
             shutdown_tx: None,
             signals: Arc::new(parking_lot::RwLock::new(HashMap::new())),
+            webhooks: Vec::new(),
         }
     }
 
@@ -365,6 +503,8 @@ async fn set_signal(
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     if let Err(e) = state.event_tx.send(BridgeEvent::ToClasp(msg)).await {
@@ -445,6 +585,8 @@ async fn delete_signal(
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let _ = state.event_tx.send(BridgeEvent::ToClasp(msg)).await;
@@ -639,6 +781,8 @@ impl Bridge for HttpBridge {
                                                                         revision: None,
                                                                         lock: false,
                                                                         unlock: false,
+                                                                        op: None,
+                                                                        correlation_id: None,
                                                                     });
 
                                                                 if let Err(e) = tx_clone
@@ -678,6 +822,8 @@ impl Bridge for HttpBridge {
                                                                 revision: None,
                                                                 lock: false,
                                                                 unlock: false,
+                                                                op: None,
+                                                                correlation_id: None,
                                                             });
 
                                                             let _ = tx_clone
@@ -705,6 +851,42 @@ impl Bridge for HttpBridge {
                     self.http_config.url
                 );
             }
+            HttpMode::Webhook => {
+                let client = reqwest::Client::builder()
+                    .timeout(Duration::from_secs(self.http_config.timeout_secs as u64))
+                    .build()
+                    .map_err(|e| BridgeError::Other(format!("HTTP client error: {}", e)))?;
+
+                let mut webhooks = Vec::with_capacity(self.http_config.webhooks.len());
+                for config in &self.http_config.webhooks {
+                    let pattern = Pattern::compile(&config.pattern).map_err(|e| {
+                        BridgeError::Other(format!(
+                            "Invalid webhook pattern '{}': {}",
+                            config.pattern, e
+                        ))
+                    })?;
+                    let (queue, queue_rx) = mpsc::channel(WEBHOOK_QUEUE_SIZE);
+                    tokio::spawn(run_webhook(
+                        client.clone(),
+                        config.clone(),
+                        queue_rx,
+                        tx.clone(),
+                    ));
+                    webhooks.push(Webhook {
+                        config: config.clone(),
+                        pattern,
+                        queue,
+                    });
+                }
+                self.webhooks = webhooks;
+
+                *self.running.lock() = true;
+                let _ = tx.send(BridgeEvent::Connected).await;
+                info!(
+                    "HTTP bridge started in webhook mode with {} webhook(s)",
+                    self.webhooks.len()
+                );
+            }
         }
 
         Ok(rx)
@@ -715,6 +897,8 @@ impl Bridge for HttpBridge {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(()).await;
         }
+        // Delivery tasks finish what is queued, then exit
+        self.webhooks.clear();
         info!("HTTP bridge stopped");
         Ok(())
     }
@@ -785,6 +969,28 @@ impl Bridge for HttpBridge {
                 debug!("HTTP {} {} -> {}", method, url, response.status());
                 Ok(())
             }
+            HttpMode::Webhook => {
+                let (address, value, kind) = match &msg {
+                    Message::Set(set) => (&set.address, &set.value, "set"),
+                    Message::Publish(pub_msg) => match &pub_msg.value {
+                        Some(val) => (&pub_msg.address, val, "event"),
+                        None => return Ok(()),
+                    },
+                    _ => return Ok(()),
+                };
+
+                let timestamp = clasp_core::time::now();
+                for webhook in self.webhooks.iter().filter(|w| w.pattern.matches(address)) {
+                    let body = webhook.config.render(address, value, kind, timestamp);
+                    if webhook.queue.try_send(body).is_err() {
+                        warn!(
+                            "Webhook queue for {} is full, dropping {}",
+                            webhook.config.url, address
+                        );
+                    }
+                }
+                Ok(())
+            }
         }
     }
 
@@ -797,6 +1003,91 @@ impl Bridge for HttpBridge {
     }
 }
 
+/// Deliver queued webhook bodies in order until the bridge stops
+async fn run_webhook(
+    client: reqwest::Client,
+    config: WebhookConfig,
+    mut queue: mpsc::Receiver<serde_json::Value>,
+    event_tx: mpsc::Sender<BridgeEvent>,
+) {
+    while let Some(body) = queue.recv().await {
+        if let Err(e) = deliver_webhook(&client, &config, &body).await {
+            error!("{}", e);
+            let _ = event_tx.send(BridgeEvent::Error(e)).await;
+        }
+    }
+    debug!("Webhook delivery to {} stopped", config.url);
+}
+
+/// Send one webhook, retrying connection errors, 5xx and 429 with backoff
+async fn deliver_webhook(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    body: &serde_json::Value,
+) -> std::result::Result<(), String> {
+    let body = serde_json::to_vec(body).map_err(|e| e.to_string())?;
+    let signature = config.signature(&body);
+    let method = reqwest::Method::from_bytes(config.method.to_string().as_bytes())
+        .expect("HttpMethod is a valid method");
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .request(method.clone(), &config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        for (name, value) in &config.headers {
+            request = request.header(name, value);
+        }
+        if let Some(signature) = &signature {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, signature);
+        }
+
+        let failure = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!(
+                    "Webhook {} {} -> {}",
+                    config.method,
+                    config.url,
+                    response.status()
+                );
+                return Ok(());
+            }
+            Ok(response)
+                if response.status().is_server_error()
+                    || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS =>
+            {
+                format!("HTTP {}", response.status())
+            }
+            Ok(response) => {
+                return Err(format!(
+                    "Webhook {} rejected delivery: HTTP {}",
+                    config.url,
+                    response.status()
+                ));
+            }
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= config.max_retries {
+            return Err(format!(
+                "Webhook {} failed after {} attempt(s): {}",
+                config.url,
+                attempt + 1,
+                failure
+            ));
+        }
+
+        let delay = config.backoff(attempt);
+        warn!(
+            "Webhook {} failed ({}), retrying in {:?}",
+            config.url, failure, delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
 impl std::fmt::Display for HttpMethod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -833,4 +1124,60 @@ mod tests {
 
         assert_eq!(json, back);
     }
+
+    #[test]
+    fn test_webhook_default_body() {
+        let webhook = WebhookConfig::new("http://localhost/hook", "/lights/**");
+        let body = webhook.render("/lights/1", &Value::Float(0.5), "set", 42);
+
+        assert_eq!(
+            body,
+            serde_json::json!({ "address": "/lights/1", "value": 0.5, "timestamp": 42 })
+        );
+    }
+
+    #[test]
+    fn test_webhook_template() {
+        let mut webhook = WebhookConfig::new("http://localhost/hook", "/lights/**");
+        webhook.template = Some(serde_json::json!({
+            "text": "{{address}} is now {{value}}",
+            "data": { "level": "{{value}}", "kind": "{{type}}", "at": "{{timestamp}}" },
+            "source": "stage-left",
+            "tags": ["{{address}}", 7]
+        }));
+        let body = webhook.render("/lights/1", &Value::Int(255), "event", 42);
+
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "text": "/lights/1 is now 255",
+                "data": { "level": 255, "kind": "event", "at": 42 },
+                "source": "stage-left",
+                "tags": ["/lights/1", 7]
+            })
+        );
+    }
+
+    #[test]
+    fn test_webhook_signature() {
+        let mut webhook = WebhookConfig::new("http://localhost/hook", "/**");
+        assert_eq!(webhook.signature(b"{}"), None);
+
+        // RFC 4231 test case 2
+        webhook.secret = Some("Jefe".to_string());
+        assert_eq!(
+            webhook
+                .signature(b"what do ya want for nothing?")
+                .as_deref(),
+            Some("sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+    }
+
+    #[test]
+    fn test_webhook_backoff() {
+        let webhook = WebhookConfig::new("http://localhost/hook", "/**");
+        assert_eq!(webhook.backoff(0), Duration::from_millis(500));
+        assert_eq!(webhook.backoff(2), Duration::from_millis(2000));
+        assert_eq!(webhook.backoff(40), MAX_WEBHOOK_BACKOFF);
+    }
 }
@@ -8,12 +8,19 @@
 //! - Art-Net (Ethernet DMX)
 //! - sACN/E1.31 (Streaming ACN)
 //! - DMX-512 (via USB interfaces)
+//! - Gamepads and joysticks (HID)
+//! - Global keyboard hotkeys
+//! - Audio input analysis (level, peak, FFT bands)
+//! - Timecode (LTC and Art-Net timecode)
 //!
 //! ## Modern Protocols
 //! - MQTT (IoT messaging)
 //! - WebSocket (real-time bidirectional)
 //! - Socket.IO (event-based WebSocket)
 //! - HTTP/REST (request-response API)
+//!
+//! ## Plugins
+//! - Out-of-tree bridges loaded from dynamic libraries at runtime
 
 pub mod error;
 pub mod mapping;
@@ -35,6 +42,21 @@ pub mod dmx;
 #[cfg(feature = "sacn")]
 pub mod sacn;
 
+#[cfg(feature = "hid")]
+pub mod hid;
+
+#[cfg(feature = "audio")]
+pub mod audio;
+
+#[cfg(feature = "timecode")]
+pub mod timecode;
+
+#[cfg(all(
+    feature = "hotkey",
+    any(target_os = "linux", target_os = "windows", target_os = "macos")
+))]
+pub mod hotkey;
+
 #[cfg(feature = "mqtt")]
 pub mod mqtt;
 
@@ -47,13 +69,16 @@ pub mod socketio;
 #[cfg(feature = "http")]
 pub mod http;
 
+#[cfg(feature = "plugin")]
+pub mod plugin;
+
 pub use error::{BridgeError, Result};
 pub use mapping::{AddressMapping, ValueTransform};
 pub use traits::{Bridge, BridgeConfig, BridgeEvent};
 pub use transform::{Aggregator, AggregatorState, Condition, CurveType, Transform, TransformState};
 
 #[cfg(feature = "osc")]
-pub use osc::{OscBridge, OscBridgeConfig};
+pub use osc::{OscBridge, OscBridgeConfig, OscFrameDecoder, OscFraming, OscTransport};
 
 #[cfg(feature = "midi")]
 pub use midi::{MidiBridge, MidiBridgeConfig};
@@ -67,14 +92,35 @@ pub use dmx::{DmxBridge, DmxBridgeConfig, DmxInterfaceType};
 #[cfg(feature = "sacn")]
 pub use sacn::{SacnBridge, SacnBridgeConfig, SacnMode};
 
+#[cfg(feature = "audio")]
+pub use audio::{AudioBridge, AudioBridgeConfig};
+
+#[cfg(feature = "timecode")]
+pub use timecode::{Timecode, TimecodeBridge, TimecodeBridgeConfig, TimecodeRate, TimecodeSource};
+
+#[cfg(feature = "hid")]
+pub use hid::{HidBridge, HidBridgeConfig};
+
+#[cfg(all(
+    feature = "hotkey",
+    any(target_os = "linux", target_os = "windows", target_os = "macos")
+))]
+pub use hotkey::{HotkeyBinding, HotkeyBridge, HotkeyBridgeConfig};
+
 #[cfg(feature = "mqtt")]
-pub use mqtt::{MqttBridge, MqttBridgeConfig};
+pub use mqtt::{MqttBridge, MqttBridgeConfig, MqttStatusMessage, MqttTlsConfig, MqttVersion};
 
 #[cfg(feature = "websocket")]
-pub use websocket::{WebSocketBridge, WebSocketBridgeConfig, WsMessageFormat, WsMode};
+pub use websocket::{
+    WebSocketBridge, WebSocketBridgeConfig, WsCodec, WsFrame, WsIncomingRule, WsJsonMapping,
+    WsMessageFormat, WsMode, WsOutgoingRule,
+};
 
 #[cfg(feature = "socketio")]
 pub use socketio::{SocketIOBridge, SocketIOBridgeConfig};
 
 #[cfg(feature = "http")]
-pub use http::{EndpointConfig, HttpBridge, HttpBridgeConfig, HttpMethod, HttpMode};
+pub use http::{EndpointConfig, HttpBridge, HttpBridgeConfig, HttpMethod, HttpMode, WebhookConfig};
+
+#[cfg(feature = "plugin")]
+pub use plugin::{Plugin, PluginBridge, PluginRegistry};
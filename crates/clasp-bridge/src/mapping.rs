@@ -236,6 +236,52 @@ impl MappingTable {
     }
 }
 
+/// Substitute `{{name}}` placeholders throughout a JSON template
+///
+/// A string that is only a placeholder becomes the variable's JSON value;
+/// placeholders inside longer strings are replaced with its text.
+#[cfg_attr(not(any(feature = "http", feature = "websocket")), allow(dead_code))]
+pub(crate) fn render_json_template(
+    template: &serde_json::Value,
+    vars: &[(&str, serde_json::Value)],
+) -> serde_json::Value {
+    match template {
+        serde_json::Value::String(s) => {
+            for (name, value) in vars {
+                if s.trim() == format!("{{{{{}}}}}", name) {
+                    return value.clone();
+                }
+            }
+
+            let mut out = s.clone();
+            for (name, value) in vars {
+                let placeholder = format!("{{{{{}}}}}", name);
+                if out.contains(&placeholder) {
+                    let text = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    out = out.replace(&placeholder, &text);
+                }
+            }
+            serde_json::Value::String(out)
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(|v| render_json_template(v, vars))
+                .collect(),
+        ),
+        serde_json::Value::Object(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), render_json_template(v, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -366,6 +366,8 @@ fn midi_message_to_clasp(message: &[u8], base_addr: &str) -> Option<Message> {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
         }
         // Program Change
@@ -395,6 +397,8 @@ fn midi_message_to_clasp(message: &[u8], base_addr: &str) -> Option<Message> {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
         }
         // System messages (clock, transport)
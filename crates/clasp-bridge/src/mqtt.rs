@@ -1,13 +1,22 @@
 //! MQTT Bridge for CLASP
 //!
 //! Provides bidirectional bridging between MQTT and CLASP protocols.
-//! Supports MQTT 3.1.1 and 5.0 via rumqttc.
+//! Supports MQTT 3.1.1 and 5.0 via rumqttc, over plain TCP or TLS.
+//!
+//! With MQTT 5, user properties on incoming messages are kept: the CLASP
+//! value becomes a map of the payload (`value`) and its properties
+//! (`user_properties`), and a SET of such a map publishes with properties.
 
 use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
 use async_trait::async_trait;
+use bytes::Bytes;
 use clasp_core::{Message, PublishMessage, SetMessage, SignalType, Value};
 use parking_lot::Mutex;
-use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS as MqttQoS};
+use rumqttc::v5::mqttbytes::v5::{LastWill as LastWillV5, PublishProperties};
+use rumqttc::v5::mqttbytes::QoS as MqttQoSV5;
+use rumqttc::{
+    AsyncClient, Event, LastWill, MqttOptions, Packet, QoS as MqttQoS, TlsConfiguration, Transport,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -42,12 +51,92 @@ pub struct MqttBridgeConfig {
     /// CLASP namespace prefix
     #[serde(default = "default_namespace")]
     pub namespace: String,
+    /// MQTT protocol version
+    #[serde(default)]
+    pub version: MqttVersion,
+    /// TLS settings; plain TCP when unset
+    #[serde(default)]
+    pub tls: Option<MqttTlsConfig>,
+    /// Published whenever the bridge connects to the broker
+    #[serde(default)]
+    pub birth: Option<MqttStatusMessage>,
+    /// Registered as the bridge's last will; also published on a clean stop,
+    /// since the broker only sends it when the connection is lost
+    #[serde(default)]
+    pub last_will: Option<MqttStatusMessage>,
+    /// Publish CLASP params (SETs) as retained messages, so MQTT clients
+    /// that subscribe later still see the current state. Events are never
+    /// retained.
+    #[serde(default = "default_true")]
+    pub mirror_retained: bool,
+}
+
+/// MQTT protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MqttVersion {
+    /// MQTT 3.1.1
+    #[default]
+    #[serde(rename = "3.1.1")]
+    V311,
+    /// MQTT 5.0, with user properties
+    #[serde(rename = "5")]
+    V5,
+}
+
+/// TLS configuration for brokers that require it (AWS IoT, HiveMQ Cloud, ...)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MqttTlsConfig {
+    /// Path to the CA certificate (PEM format); the platform's trusted roots
+    /// when unset
+    #[serde(default)]
+    pub ca_path: Option<String>,
+    /// Path to the client certificate (PEM format), for brokers that
+    /// authenticate clients by certificate
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// Path to the client private key (PEM format)
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// ALPN protocols, e.g. "x-amzn-mqtt-ca" for AWS IoT on port 443
+    #[serde(default)]
+    pub alpn: Vec<String>,
+}
+
+/// A status message for the birth or last-will topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttStatusMessage {
+    /// MQTT topic
+    pub topic: String,
+    /// Payload, e.g. "online" or "offline"
+    pub payload: String,
+    /// QoS level (0, 1, or 2)
+    #[serde(default)]
+    pub qos: u8,
+    /// Retain on the broker
+    #[serde(default = "default_true")]
+    pub retain: bool,
+}
+
+impl MqttStatusMessage {
+    /// Retained status message at QoS 1
+    pub fn new(topic: impl Into<String>, payload: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: payload.into(),
+            qos: 1,
+            retain: true,
+        }
+    }
 }
 
 fn default_keep_alive() -> u16 {
     60
 }
 
+fn default_true() -> bool {
+    true
+}
+
 fn default_namespace() -> String {
     "/mqtt".to_string()
 }
@@ -67,6 +156,143 @@ impl Default for MqttBridgeConfig {
             qos: 0,
             keep_alive_secs: 60,
             namespace: "/mqtt".to_string(),
+            version: MqttVersion::V311,
+            tls: None,
+            birth: None,
+            last_will: None,
+            mirror_retained: true,
+        }
+    }
+}
+
+/// Client for either protocol version
+#[derive(Clone)]
+enum MqttClient {
+    V311(AsyncClient),
+    V5(rumqttc::v5::AsyncClient),
+}
+
+impl MqttClient {
+    async fn subscribe(&self, topic: &str, qos: u8) -> Result<()> {
+        let result = match self {
+            MqttClient::V311(client) => client
+                .subscribe(topic, MqttBridge::parse_qos(qos))
+                .await
+                .map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client
+                .subscribe(topic, MqttBridge::parse_qos_v5(qos))
+                .await
+                .map_err(|e| e.to_string()),
+        };
+        result.map_err(|e| BridgeError::ConnectionFailed(format!("Subscribe failed: {}", e)))
+    }
+
+    async fn publish(
+        &self,
+        topic: &str,
+        qos: u8,
+        retain: bool,
+        payload: Vec<u8>,
+        user_properties: Vec<(String, String)>,
+    ) -> Result<()> {
+        let result = match self {
+            MqttClient::V311(client) => client
+                .publish(topic, MqttBridge::parse_qos(qos), retain, payload)
+                .await
+                .map_err(|e| e.to_string()),
+            MqttClient::V5(client) if user_properties.is_empty() => client
+                .publish(topic, MqttBridge::parse_qos_v5(qos), retain, payload)
+                .await
+                .map_err(|e| e.to_string()),
+            MqttClient::V5(client) => {
+                let properties = PublishProperties {
+                    user_properties,
+                    ..Default::default()
+                };
+                client
+                    .publish_with_properties(
+                        topic,
+                        MqttBridge::parse_qos_v5(qos),
+                        retain,
+                        payload,
+                        properties,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        };
+        result.map_err(|e| BridgeError::Send(format!("MQTT publish failed: {}", e)))
+    }
+
+    async fn publish_status(&self, status: &MqttStatusMessage) -> Result<()> {
+        self.publish(
+            &status.topic,
+            status.qos,
+            status.retain,
+            status.payload.clone().into_bytes(),
+            Vec::new(),
+        )
+        .await
+    }
+
+    async fn disconnect(&self) {
+        let _ = match self {
+            MqttClient::V311(client) => client.disconnect().await.map_err(|e| e.to_string()),
+            MqttClient::V5(client) => client.disconnect().await.map_err(|e| e.to_string()),
+        };
+    }
+}
+
+/// What the event loop reports, whichever protocol version is in use
+enum MqttIncoming {
+    Publish {
+        topic: String,
+        payload: Bytes,
+        user_properties: Vec<(String, String)>,
+    },
+    Connected,
+    Disconnected,
+    Error(String),
+    Other,
+}
+
+/// Event loop for either protocol version
+enum MqttEventLoop {
+    V311(rumqttc::EventLoop),
+    V5(rumqttc::v5::EventLoop),
+}
+
+impl MqttEventLoop {
+    async fn poll(&mut self) -> MqttIncoming {
+        use rumqttc::v5::mqttbytes::v5::Packet as PacketV5;
+        use rumqttc::v5::Event as EventV5;
+
+        match self {
+            MqttEventLoop::V311(eventloop) => match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::Publish(publish))) => MqttIncoming::Publish {
+                    topic: publish.topic,
+                    payload: publish.payload,
+                    user_properties: Vec::new(),
+                },
+                Ok(Event::Incoming(Packet::ConnAck(_))) => MqttIncoming::Connected,
+                Ok(Event::Incoming(Packet::Disconnect)) => MqttIncoming::Disconnected,
+                Ok(_) => MqttIncoming::Other,
+                Err(e) => MqttIncoming::Error(format!("{:?}", e)),
+            },
+            MqttEventLoop::V5(eventloop) => match eventloop.poll().await {
+                Ok(EventV5::Incoming(PacketV5::Publish(publish))) => MqttIncoming::Publish {
+                    topic: String::from_utf8_lossy(&publish.topic).into_owned(),
+                    payload: publish.payload,
+                    user_properties: publish
+                        .properties
+                        .map(|p| p.user_properties)
+                        .unwrap_or_default(),
+                },
+                Ok(EventV5::Incoming(PacketV5::ConnAck(_))) => MqttIncoming::Connected,
+                Ok(EventV5::Incoming(PacketV5::Disconnect(_))) => MqttIncoming::Disconnected,
+                Ok(_) => MqttIncoming::Other,
+                Err(e) => MqttIncoming::Error(format!("{:?}", e)),
+            },
         }
     }
 }
@@ -75,7 +301,7 @@ impl Default for MqttBridgeConfig {
 pub struct MqttBridge {
     config: BridgeConfig,
     mqtt_config: MqttBridgeConfig,
-    client: Option<AsyncClient>,
+    client: Option<MqttClient>,
     running: Arc<Mutex<bool>>,
 }
 
@@ -120,6 +346,172 @@ impl MqttBridge {
         }
     }
 
+    /// Parse MQTT 5 QoS level
+    fn parse_qos_v5(qos: u8) -> MqttQoSV5 {
+        match qos {
+            0 => MqttQoSV5::AtMostOnce,
+            1 => MqttQoSV5::AtLeastOnce,
+            _ => MqttQoSV5::ExactlyOnce,
+        }
+    }
+
+    /// Build the transport from the TLS settings
+    fn transport(tls: &Option<MqttTlsConfig>) -> Result<Transport> {
+        let Some(tls) = tls else {
+            return Ok(Transport::Tcp);
+        };
+
+        let read = |kind: &str, path: &str| {
+            std::fs::read(path).map_err(|e| {
+                BridgeError::ConnectionFailed(format!("Failed to read {} {}: {}", kind, path, e))
+            })
+        };
+
+        let client_auth = match (&tls.cert_path, &tls.key_path) {
+            (Some(cert), Some(key)) => Some((read("certificate", cert)?, read("key", key)?)),
+            (None, None) => None,
+            _ => {
+                return Err(BridgeError::ConnectionFailed(
+                    "TLS client authentication needs both cert_path and key_path".to_string(),
+                ))
+            }
+        };
+        let alpn = if tls.alpn.is_empty() {
+            None
+        } else {
+            Some(tls.alpn.iter().map(|p| p.as_bytes().to_vec()).collect())
+        };
+
+        match &tls.ca_path {
+            Some(ca) => Ok(Transport::tls_with_config(TlsConfiguration::Simple {
+                ca: read("CA certificate", ca)?,
+                alpn,
+                client_auth,
+            })),
+            // Without a CA, trust the platform's roots
+            None if client_auth.is_none() && alpn.is_none() => {
+                Ok(Transport::tls_with_default_config())
+            }
+            None => Err(BridgeError::ConnectionFailed(
+                "TLS client authentication and ALPN need ca_path".to_string(),
+            )),
+        }
+    }
+
+    /// Build the client and event loop for the configured version
+    fn connect(config: &MqttBridgeConfig) -> Result<(MqttClient, MqttEventLoop)> {
+        let transport = Self::transport(&config.tls)?;
+        let keep_alive = Duration::from_secs(config.keep_alive_secs as u64);
+        let credentials = config.username.as_ref().zip(config.password.as_ref());
+
+        match config.version {
+            MqttVersion::V311 => {
+                let mut options =
+                    MqttOptions::new(&config.client_id, &config.broker_host, config.broker_port);
+                options.set_keep_alive(keep_alive);
+                options.set_transport(transport);
+                if let Some((user, pass)) = credentials {
+                    options.set_credentials(user, pass);
+                }
+                if let Some(will) = &config.last_will {
+                    options.set_last_will(LastWill::new(
+                        &will.topic,
+                        will.payload.clone(),
+                        Self::parse_qos(will.qos),
+                        will.retain,
+                    ));
+                }
+
+                let (client, eventloop) = AsyncClient::new(options, 100);
+                Ok((MqttClient::V311(client), MqttEventLoop::V311(eventloop)))
+            }
+            MqttVersion::V5 => {
+                let mut options = rumqttc::v5::MqttOptions::new(
+                    &config.client_id,
+                    &config.broker_host,
+                    config.broker_port,
+                );
+                options.set_keep_alive(keep_alive);
+                options.set_transport(transport);
+                if let Some((user, pass)) = credentials {
+                    options.set_credentials(user, pass);
+                }
+                if let Some(will) = &config.last_will {
+                    options.set_last_will(LastWillV5::new(
+                        &will.topic,
+                        will.payload.clone(),
+                        Self::parse_qos_v5(will.qos),
+                        will.retain,
+                        None,
+                    ));
+                }
+
+                let (client, eventloop) = rumqttc::v5::AsyncClient::new(options, 100);
+                Ok((MqttClient::V5(client), MqttEventLoop::V5(eventloop)))
+            }
+        }
+    }
+
+    /// Attach MQTT 5 user properties to a value, as a map of the value
+    /// and its properties. Repeated property names become arrays.
+    fn with_user_properties(value: Value, user_properties: Vec<(String, String)>) -> Value {
+        if user_properties.is_empty() {
+            return value;
+        }
+
+        let mut properties: HashMap<String, Value> = HashMap::new();
+        for (key, property) in user_properties {
+            let property = Value::String(property);
+            match properties.remove(&key) {
+                None => {
+                    properties.insert(key, property);
+                }
+                Some(Value::Array(mut values)) => {
+                    values.push(property);
+                    properties.insert(key, Value::Array(values));
+                }
+                Some(first) => {
+                    properties.insert(key, Value::Array(vec![first, property]));
+                }
+            }
+        }
+
+        let mut map = HashMap::new();
+        map.insert("value".to_string(), value);
+        map.insert("user_properties".to_string(), Value::Map(properties));
+        Value::Map(map)
+    }
+
+    /// Split a value built by [`Self::with_user_properties`] back into the
+    /// value and its properties
+    fn split_user_properties(value: &Value) -> Option<(&Value, Vec<(String, String)>)> {
+        let Value::Map(map) = value else {
+            return None;
+        };
+        if map.len() != 2 {
+            return None;
+        }
+        let inner = map.get("value")?;
+        let Some(Value::Map(properties)) = map.get("user_properties") else {
+            return None;
+        };
+
+        let mut user_properties = Vec::new();
+        for (key, property) in properties {
+            match property {
+                Value::String(s) => user_properties.push((key.clone(), s.clone())),
+                Value::Array(values) => {
+                    for v in values {
+                        user_properties.push((key.clone(), v.as_str()?.to_string()));
+                    }
+                }
+                _ => return None,
+            }
+        }
+        user_properties.sort_by(|a, b| a.0.cmp(&b.0));
+        Some((inner, user_properties))
+    }
+
     /// Parse incoming MQTT payload to CLASP Value
     fn parse_payload(payload: &[u8]) -> Value {
         if let Ok(text) = std::str::from_utf8(payload) {
@@ -199,40 +591,30 @@ impl Bridge for MqttBridge {
             return Err(BridgeError::Other("Bridge already running".to_string()));
         }
 
-        // Create MQTT options
-        let mut mqttoptions = MqttOptions::new(
-            &self.mqtt_config.client_id,
-            &self.mqtt_config.broker_host,
-            self.mqtt_config.broker_port,
-        );
-        mqttoptions.set_keep_alive(Duration::from_secs(self.mqtt_config.keep_alive_secs as u64));
-
-        if let (Some(user), Some(pass)) = (&self.mqtt_config.username, &self.mqtt_config.password) {
-            mqttoptions.set_credentials(user, pass);
-        }
-
-        // Create client
-        let (client, mut eventloop) = AsyncClient::new(mqttoptions, 100);
+        let (client, mut eventloop) = Self::connect(&self.mqtt_config)?;
         self.client = Some(client.clone());
         *self.running.lock() = true;
 
         // Subscribe to topics
-        let qos = Self::parse_qos(self.mqtt_config.qos);
         for topic in &self.mqtt_config.subscribe_topics {
-            client
-                .subscribe(topic, qos)
-                .await
-                .map_err(|e| BridgeError::ConnectionFailed(format!("Subscribe failed: {}", e)))?;
+            client.subscribe(topic, self.mqtt_config.qos).await?;
             debug!("MQTT subscribed to: {}", topic);
         }
 
         let (tx, rx) = mpsc::channel(100);
         let running = self.running.clone();
         let namespace = self.mqtt_config.namespace.clone();
+        let birth = self.mqtt_config.birth.clone();
 
         info!(
-            "MQTT bridge connecting to {}:{}",
-            self.mqtt_config.broker_host, self.mqtt_config.broker_port
+            "MQTT bridge connecting to {}:{}{}",
+            self.mqtt_config.broker_host,
+            self.mqtt_config.broker_port,
+            if self.mqtt_config.tls.is_some() {
+                " (TLS)"
+            } else {
+                ""
+            }
         );
 
         // Spawn event loop
@@ -243,14 +625,18 @@ impl Bridge for MqttBridge {
                 }
 
                 match eventloop.poll().await {
-                    Ok(Event::Incoming(Packet::Publish(publish))) => {
-                        let topic = publish.topic.clone();
-                        let payload = publish.payload.to_vec();
-
+                    MqttIncoming::Publish {
+                        topic,
+                        payload,
+                        user_properties,
+                    } => {
                         debug!("MQTT received: {} ({} bytes)", topic, payload.len());
 
                         let address = format!("{}/{}", namespace, topic);
-                        let value = MqttBridge::parse_payload(&payload);
+                        let value = MqttBridge::with_user_properties(
+                            MqttBridge::parse_payload(&payload),
+                            user_properties,
+                        );
 
                         let msg = Message::Set(SetMessage {
                             address,
@@ -258,17 +644,29 @@ impl Bridge for MqttBridge {
                             revision: None,
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         });
 
                         if tx.send(BridgeEvent::ToClasp(msg)).await.is_err() {
                             break;
                         }
                     }
-                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    MqttIncoming::Connected => {
                         info!("MQTT connected to broker");
+                        // Published from a separate task: the client's
+                        // requests only go out while this loop is polling
+                        if let Some(birth) = birth.clone() {
+                            let client = client.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = client.publish_status(&birth).await {
+                                    warn!("MQTT birth message failed: {}", e);
+                                }
+                            });
+                        }
                         let _ = tx.send(BridgeEvent::Connected).await;
                     }
-                    Ok(Event::Incoming(Packet::Disconnect)) => {
+                    MqttIncoming::Disconnected => {
                         warn!("MQTT disconnected from broker");
                         let _ = tx
                             .send(BridgeEvent::Disconnected {
@@ -276,11 +674,11 @@ impl Bridge for MqttBridge {
                             })
                             .await;
                     }
-                    Err(e) => {
-                        error!("MQTT error: {:?}", e);
+                    MqttIncoming::Error(e) => {
+                        error!("MQTT error: {}", e);
                         tokio::time::sleep(Duration::from_secs(5)).await;
                     }
-                    _ => {}
+                    MqttIncoming::Other => {}
                 }
             }
 
@@ -291,10 +689,14 @@ impl Bridge for MqttBridge {
     }
 
     async fn stop(&mut self) -> Result<()> {
-        *self.running.lock() = false;
         if let Some(client) = &self.client {
-            let _ = client.disconnect().await;
+            // The broker only sends the last will if the connection is lost
+            if let Some(will) = &self.mqtt_config.last_will {
+                let _ = client.publish_status(will).await;
+            }
+            client.disconnect().await;
         }
+        *self.running.lock() = false;
         self.client = None;
         info!("MQTT bridge stopped");
         Ok(())
@@ -306,11 +708,11 @@ impl Bridge for MqttBridge {
             .as_ref()
             .ok_or_else(|| BridgeError::Other("Not connected".to_string()))?;
 
-        let (address, value) = match &msg {
-            Message::Set(set) => (&set.address, &set.value),
+        let (address, value, retain) = match &msg {
+            Message::Set(set) => (&set.address, &set.value, self.mqtt_config.mirror_retained),
             Message::Publish(pub_msg) => {
                 if let Some(val) = &pub_msg.value {
-                    (&pub_msg.address, val)
+                    (&pub_msg.address, val, false)
                 } else {
                     return Ok(());
                 }
@@ -318,14 +720,23 @@ impl Bridge for MqttBridge {
             _ => return Ok(()),
         };
 
+        let (value, user_properties) = match self.mqtt_config.version {
+            MqttVersion::V5 => Self::split_user_properties(value).unwrap_or((value, Vec::new())),
+            MqttVersion::V311 => (value, Vec::new()),
+        };
+
         let topic = self.address_to_topic(address);
         let payload = Self::value_to_payload(value);
-        let qos = Self::parse_qos(self.mqtt_config.qos);
 
         client
-            .publish(&topic, qos, false, payload)
-            .await
-            .map_err(|e| BridgeError::Other(format!("MQTT publish failed: {}", e)))?;
+            .publish(
+                &topic,
+                self.mqtt_config.qos,
+                retain,
+                payload,
+                user_properties,
+            )
+            .await?;
 
         debug!("MQTT sent to topic: {}", topic);
         Ok(())
@@ -381,4 +792,90 @@ mod tests {
         let value = MqttBridge::parse_payload(payload);
         assert!(matches!(value, Value::Bool(true)));
     }
+
+    #[test]
+    fn test_user_properties_roundtrip() {
+        let value = MqttBridge::with_user_properties(
+            Value::Float(21.5),
+            vec![
+                ("unit".to_string(), "celsius".to_string()),
+                ("tag".to_string(), "a".to_string()),
+                ("tag".to_string(), "b".to_string()),
+            ],
+        );
+        let Value::Map(map) = &value else {
+            panic!("Expected map, got {:?}", value);
+        };
+        assert_eq!(map.get("value"), Some(&Value::Float(21.5)));
+
+        let (inner, properties) = MqttBridge::split_user_properties(&value).unwrap();
+        assert_eq!(inner, &Value::Float(21.5));
+        assert_eq!(
+            properties,
+            vec![
+                ("tag".to_string(), "a".to_string()),
+                ("tag".to_string(), "b".to_string()),
+                ("unit".to_string(), "celsius".to_string()),
+            ]
+        );
+
+        // Without properties, values pass through untouched
+        assert_eq!(
+            MqttBridge::with_user_properties(Value::Int(1), vec![]),
+            Value::Int(1)
+        );
+        assert!(
+            MqttBridge::split_user_properties(&MqttBridge::parse_payload(
+                b"{\"value\": 1, \"other\": 2}"
+            ))
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_tls_transport() {
+        assert!(matches!(MqttBridge::transport(&None), Ok(Transport::Tcp)));
+        assert!(matches!(
+            MqttBridge::transport(&Some(MqttTlsConfig::default())),
+            Ok(Transport::Tls(_))
+        ));
+
+        // A client certificate needs its key
+        let tls = MqttTlsConfig {
+            cert_path: Some("client.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(MqttBridge::transport(&Some(tls)).is_err());
+
+        let tls = MqttTlsConfig {
+            ca_path: Some("/nonexistent/ca.pem".to_string()),
+            ..Default::default()
+        };
+        let err = MqttBridge::transport(&Some(tls)).err().unwrap();
+        assert!(err.to_string().contains("/nonexistent/ca.pem"));
+    }
+
+    #[test]
+    fn test_config_from_json() {
+        let config: MqttBridgeConfig = serde_json::from_str(
+            r#"{
+                "broker_host": "broker.hivemq.cloud",
+                "broker_port": 8883,
+                "client_id": "lighting-desk",
+                "version": "5",
+                "tls": {"ca_path": "/etc/ssl/ca.pem"},
+                "birth": {"topic": "clasp/status", "payload": "online"},
+                "last_will": {"topic": "clasp/status", "payload": "offline", "qos": 1}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.version, MqttVersion::V5);
+        assert_eq!(
+            config.tls.unwrap().ca_path.as_deref(),
+            Some("/etc/ssl/ca.pem")
+        );
+        assert!(config.birth.unwrap().retain);
+        assert_eq!(config.last_will.unwrap().qos, 1);
+        assert!(config.mirror_retained);
+    }
 }
@@ -1,4 +1,8 @@
 //! OSC (Open Sound Control) bridge
+//!
+//! OSC runs over UDP by default. Consoles such as ETC Eos prefer a TCP
+//! stream, which needs each packet framed: either with an int32 size
+//! (OSC 1.0) or with SLIP (RFC 1055, OSC 1.1). See [`OscTransport`].
 
 use async_trait::async_trait;
 use clasp_core::{Message, PublishMessage, QoS, SetMessage, SignalType, Value};
@@ -6,8 +10,10 @@ use parking_lot::Mutex;
 use rosc::{OscMessage, OscPacket, OscType};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::UdpSocket;
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::{mpsc, watch};
 use tracing::{debug, error, info, warn};
 
 use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
@@ -21,6 +27,8 @@ pub struct OscBridgeConfig {
     pub remote_addr: Option<String>,
     /// Address prefix for Clasp
     pub namespace: String,
+    /// UDP or TCP
+    pub transport: OscTransport,
 }
 
 impl Default for OscBridgeConfig {
@@ -29,15 +37,164 @@ impl Default for OscBridgeConfig {
             bind_addr: "0.0.0.0:8000".to_string(),
             remote_addr: None,
             namespace: "/osc".to_string(),
+            transport: OscTransport::Udp,
         }
     }
 }
 
+/// How OSC packets travel to and from the remote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OscTransport {
+    /// One packet per datagram
+    #[default]
+    Udp,
+    /// A TCP stream of framed packets. The bridge connects to `remote_addr`
+    /// when it is set, reconnecting if the connection drops; otherwise it
+    /// listens on `bind_addr` and talks to every peer that connects.
+    Tcp(OscFraming),
+}
+
+/// Packet framing on an OSC TCP stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OscFraming {
+    /// Big-endian int32 size before each packet (OSC 1.0)
+    PacketLength,
+    /// SLIP (RFC 1055) with an END byte before and after each packet
+    /// (OSC 1.1)
+    Slip,
+}
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+/// Largest packet accepted from a TCP stream
+const MAX_TCP_PACKET: usize = 1024 * 1024;
+
+/// Delay between attempts to reconnect to a TCP remote
+const TCP_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+impl OscFraming {
+    /// Frame an encoded OSC packet for a TCP stream
+    pub fn encode(&self, packet: &[u8]) -> Vec<u8> {
+        match self {
+            OscFraming::PacketLength => {
+                let mut frame = Vec::with_capacity(packet.len() + 4);
+                frame.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+                frame.extend_from_slice(packet);
+                frame
+            }
+            OscFraming::Slip => {
+                let mut frame = Vec::with_capacity(packet.len() + 2);
+                frame.push(SLIP_END);
+                for &byte in packet {
+                    match byte {
+                        SLIP_END => frame.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+                        SLIP_ESC => frame.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+                        _ => frame.push(byte),
+                    }
+                }
+                frame.push(SLIP_END);
+                frame
+            }
+        }
+    }
+}
+
+/// Splits an OSC TCP stream back into packets
+#[derive(Debug)]
+pub struct OscFrameDecoder {
+    framing: OscFraming,
+    buf: Vec<u8>,
+    /// SLIP: the last byte was ESC
+    escaped: bool,
+}
+
+impl OscFrameDecoder {
+    pub fn new(framing: OscFraming) -> Self {
+        Self {
+            framing,
+            buf: Vec::new(),
+            escaped: false,
+        }
+    }
+
+    /// Feed bytes read from the stream, returning the packets they complete
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        match self.framing {
+            OscFraming::PacketLength => self.push_length_prefixed(data),
+            OscFraming::Slip => self.push_slip(data),
+        }
+    }
+
+    fn push_length_prefixed(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buf.extend_from_slice(data);
+
+        let mut packets = Vec::new();
+        let mut consumed = 0;
+        while self.buf.len() - consumed >= 4 {
+            let header = &self.buf[consumed..consumed + 4];
+            let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            if len > MAX_TCP_PACKET {
+                return Err(BridgeError::Protocol(format!(
+                    "OSC packet of {} bytes exceeds the {} byte limit",
+                    len, MAX_TCP_PACKET
+                )));
+            }
+            if self.buf.len() - consumed - 4 < len {
+                break;
+            }
+            packets.push(self.buf[consumed + 4..consumed + 4 + len].to_vec());
+            consumed += 4 + len;
+        }
+        self.buf.drain(..consumed);
+
+        Ok(packets)
+    }
+
+    fn push_slip(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut packets = Vec::new();
+        for &byte in data {
+            if self.escaped {
+                self.escaped = false;
+                match byte {
+                    SLIP_ESC_END => self.buf.push(SLIP_END),
+                    SLIP_ESC_ESC => self.buf.push(SLIP_ESC),
+                    // RFC 1055 leaves a bad escape as the byte itself
+                    _ => self.buf.push(byte),
+                }
+            } else {
+                match byte {
+                    // Empty packets are the END bytes either side of a packet
+                    SLIP_END if self.buf.is_empty() => {}
+                    SLIP_END => packets.push(std::mem::take(&mut self.buf)),
+                    SLIP_ESC => self.escaped = true,
+                    _ => self.buf.push(byte),
+                }
+            }
+            if self.buf.len() > MAX_TCP_PACKET {
+                return Err(BridgeError::Protocol(format!(
+                    "OSC packet exceeds the {} byte limit",
+                    MAX_TCP_PACKET
+                )));
+            }
+        }
+
+        Ok(packets)
+    }
+}
+
+/// Outgoing frame queues of connected TCP peers
+type TcpPeers = Arc<Mutex<Vec<mpsc::Sender<Vec<u8>>>>>;
+
 /// OSC to Clasp bridge
 pub struct OscBridge {
     config: BridgeConfig,
     osc_config: OscBridgeConfig,
     socket: Option<Arc<UdpSocket>>,
+    peers: TcpPeers,
+    shutdown: Option<watch::Sender<bool>>,
     running: Arc<Mutex<bool>>,
 }
 
@@ -54,6 +211,8 @@ impl OscBridge {
             config,
             osc_config,
             socket: None,
+            peers: Arc::new(Mutex::new(Vec::new())),
+            shutdown: None,
             running: Arc::new(Mutex::new(false)),
         }
     }
@@ -77,6 +236,8 @@ impl OscBridge {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
     }
 
@@ -119,19 +280,9 @@ impl OscBridge {
             _ => None,
         }
     }
-}
-
-#[async_trait]
-impl Bridge for OscBridge {
-    fn config(&self) -> &BridgeConfig {
-        &self.config
-    }
-
-    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
-        if *self.running.lock() {
-            return Err(BridgeError::Other("Bridge already running".to_string()));
-        }
 
+    /// Listen for OSC datagrams
+    async fn start_udp(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
         let socket = UdpSocket::bind(&self.osc_config.bind_addr)
             .await
             .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
@@ -157,20 +308,8 @@ impl Bridge for OscBridge {
                     Ok((len, from)) => {
                         debug!("OSC received {} bytes from {}", len, from);
 
-                        // Parse OSC packet
-                        match rosc::decoder::decode_udp(&buf[..len]) {
-                            Ok((_, packet)) => {
-                                if let Some(messages) = packet_to_messages(&packet, &namespace) {
-                                    for msg in messages {
-                                        if tx.send(BridgeEvent::ToClasp(msg)).await.is_err() {
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                debug!("OSC decode error: {:?}", e);
-                            }
+                        if !forward_packet(&buf[..len], &namespace, &tx).await {
+                            break;
                         }
                     }
                     Err(e) => {
@@ -186,14 +325,125 @@ impl Bridge for OscBridge {
         Ok(rx)
     }
 
-    async fn stop(&mut self) -> Result<()> {
-        *self.running.lock() = false;
-        self.socket = None;
-        info!("OSC bridge stopped");
-        Ok(())
+    /// Accept OSC TCP peers on the bind address
+    async fn start_tcp_server(
+        &mut self,
+        framing: OscFraming,
+    ) -> Result<mpsc::Receiver<BridgeEvent>> {
+        let listener = TcpListener::bind(&self.osc_config.bind_addr)
+            .await
+            .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+
+        info!(
+            "OSC bridge listening for TCP on {}",
+            self.osc_config.bind_addr
+        );
+
+        let (tx, rx) = mpsc::channel(100);
+        let (shutdown_tx, mut shutdown) = watch::channel(false);
+        self.shutdown = Some(shutdown_tx);
+        *self.running.lock() = true;
+
+        let peers = self.peers.clone();
+        let namespace = self.osc_config.namespace.clone();
+
+        tokio::spawn(async move {
+            let _ = tx.send(BridgeEvent::Connected).await;
+
+            loop {
+                let (stream, addr) = tokio::select! {
+                    _ = shutdown.changed() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            error!("OSC TCP accept error: {}", e);
+                            let _ = tx.send(BridgeEvent::Error(e.to_string())).await;
+                            continue;
+                        }
+                    },
+                };
+
+                info!("OSC TCP peer connected: {}", addr);
+                let tx = tx.clone();
+                let peers = peers.clone();
+                let namespace = namespace.clone();
+                let mut shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    if let Some(reason) =
+                        run_tcp_peer(stream, framing, &namespace, &tx, &peers, &mut shutdown).await
+                    {
+                        info!("OSC TCP peer {} disconnected: {}", addr, reason);
+                    }
+                });
+            }
+
+            let _ = tx.send(BridgeEvent::Disconnected { reason: None }).await;
+        });
+
+        Ok(rx)
     }
 
-    async fn send(&self, message: Message) -> Result<()> {
+    /// Connect to an OSC TCP remote, reconnecting whenever it drops
+    async fn start_tcp_client(
+        &mut self,
+        framing: OscFraming,
+        remote: String,
+    ) -> Result<mpsc::Receiver<BridgeEvent>> {
+        let stream = TcpStream::connect(&remote)
+            .await
+            .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+
+        info!("OSC bridge connected to {} over TCP", remote);
+
+        let (tx, rx) = mpsc::channel(100);
+        let (shutdown_tx, mut shutdown) = watch::channel(false);
+        self.shutdown = Some(shutdown_tx);
+        *self.running.lock() = true;
+
+        let peers = self.peers.clone();
+        let namespace = self.osc_config.namespace.clone();
+
+        tokio::spawn(async move {
+            let _ = tx.send(BridgeEvent::Connected).await;
+
+            let mut stream = Some(stream);
+            loop {
+                if let Some(stream) = stream.take() {
+                    let Some(reason) =
+                        run_tcp_peer(stream, framing, &namespace, &tx, &peers, &mut shutdown).await
+                    else {
+                        break;
+                    };
+                    warn!("OSC TCP connection to {} lost: {}", remote, reason);
+                    let _ = tx
+                        .send(BridgeEvent::Disconnected {
+                            reason: Some(reason),
+                        })
+                        .await;
+                }
+
+                tokio::select! {
+                    _ = shutdown.changed() => break,
+                    _ = tokio::time::sleep(TCP_RECONNECT_DELAY) => {}
+                }
+
+                match TcpStream::connect(&remote).await {
+                    Ok(reconnected) => {
+                        info!("OSC bridge reconnected to {}", remote);
+                        let _ = tx.send(BridgeEvent::Connected).await;
+                        stream = Some(reconnected);
+                    }
+                    Err(e) => debug!("OSC TCP reconnect to {} failed: {}", remote, e),
+                }
+            }
+
+            let _ = tx.send(BridgeEvent::Disconnected { reason: None }).await;
+        });
+
+        Ok(rx)
+    }
+
+    async fn send_udp(&self, message: &Message) -> Result<()> {
         let socket = self
             .socket
             .as_ref()
@@ -212,7 +462,7 @@ impl Bridge for OscBridge {
         // Check for QoS degradation - OSC is always Fire (UDP, no guarantees)
         let original_qos = message.default_qos();
         if original_qos != QoS::Fire {
-            let address = match &message {
+            let address = match message {
                 Message::Set(set) => &set.address,
                 Message::Publish(pub_msg) => &pub_msg.address,
                 _ => "",
@@ -224,7 +474,7 @@ impl Bridge for OscBridge {
             );
         }
 
-        if let Some(packet) = self.clasp_to_osc(&message) {
+        if let Some(packet) = self.clasp_to_osc(message) {
             let bytes = rosc::encoder::encode(&packet)
                 .map_err(|e| BridgeError::Protocol(format!("OSC encode error: {:?}", e)))?;
 
@@ -239,6 +489,74 @@ impl Bridge for OscBridge {
         Ok(())
     }
 
+    async fn send_tcp(&self, message: &Message, framing: OscFraming) -> Result<()> {
+        if !self.is_running() {
+            return Err(BridgeError::ConnectionFailed("Not connected".to_string()));
+        }
+
+        let Some(packet) = self.clasp_to_osc(message) else {
+            return Ok(());
+        };
+        let bytes = rosc::encoder::encode(&packet)
+            .map_err(|e| BridgeError::Protocol(format!("OSC encode error: {:?}", e)))?;
+        let frame = framing.encode(&bytes);
+
+        let peers: Vec<_> = self.peers.lock().clone();
+        if peers.is_empty() {
+            return Err(BridgeError::Send("No OSC TCP peer connected".to_string()));
+        }
+        for peer in &peers {
+            // A peer that has gone away is pruned below
+            let _ = peer.send(frame.clone()).await;
+        }
+        self.peers.lock().retain(|peer| !peer.is_closed());
+
+        debug!("Sent OSC message to {} TCP peer(s)", peers.len());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Bridge for OscBridge {
+    fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
+        if *self.running.lock() {
+            return Err(BridgeError::Other("Bridge already running".to_string()));
+        }
+
+        match (
+            self.osc_config.transport,
+            self.osc_config.remote_addr.clone(),
+        ) {
+            (OscTransport::Udp, _) => self.start_udp().await,
+            (OscTransport::Tcp(framing), Some(remote)) => {
+                self.start_tcp_client(framing, remote).await
+            }
+            (OscTransport::Tcp(framing), None) => self.start_tcp_server(framing).await,
+        }
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        *self.running.lock() = false;
+        self.socket = None;
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(true);
+        }
+        self.peers.lock().clear();
+        info!("OSC bridge stopped");
+        Ok(())
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        match self.osc_config.transport {
+            OscTransport::Udp => self.send_udp(&message).await,
+            OscTransport::Tcp(framing) => self.send_tcp(&message, framing).await,
+        }
+    }
+
     fn is_running(&self) -> bool {
         *self.running.lock()
     }
@@ -248,6 +566,78 @@ impl Bridge for OscBridge {
     }
 }
 
+/// Decode one OSC packet and pass its messages on, returning false once the
+/// event channel has closed
+async fn forward_packet(data: &[u8], namespace: &str, tx: &mpsc::Sender<BridgeEvent>) -> bool {
+    match rosc::decoder::decode_udp(data) {
+        Ok((_, packet)) => {
+            if let Some(messages) = packet_to_messages(&packet, namespace) {
+                for msg in messages {
+                    if tx.send(BridgeEvent::ToClasp(msg)).await.is_err() {
+                        return false;
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            debug!("OSC decode error: {:?}", e);
+        }
+    }
+    true
+}
+
+/// Exchange framed packets with one TCP peer until it disconnects, returning
+/// why, or `None` once the bridge has stopped
+async fn run_tcp_peer(
+    stream: TcpStream,
+    framing: OscFraming,
+    namespace: &str,
+    tx: &mpsc::Sender<BridgeEvent>,
+    peers: &TcpPeers,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Option<String> {
+    let _ = stream.set_nodelay(true);
+    let (mut reader, mut writer) = stream.into_split();
+
+    let (out_tx, mut out_rx) = mpsc::channel::<Vec<u8>>(256);
+    peers.lock().push(out_tx.clone());
+    let writing = tokio::spawn(async move {
+        while let Some(frame) = out_rx.recv().await {
+            if writer.write_all(&frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut decoder = OscFrameDecoder::new(framing);
+    let mut buf = vec![0u8; 65536];
+    let reason = 'read: loop {
+        let len = tokio::select! {
+            _ = shutdown.changed() => break None,
+            read = reader.read(&mut buf) => match read {
+                Ok(0) => break Some("Connection closed".to_string()),
+                Ok(len) => len,
+                Err(e) => break Some(e.to_string()),
+            },
+        };
+        debug!("OSC received {} bytes over TCP", len);
+
+        let packets = match decoder.push(&buf[..len]) {
+            Ok(packets) => packets,
+            Err(e) => break Some(e.to_string()),
+        };
+        for packet in packets {
+            if !forward_packet(&packet, namespace, tx).await {
+                break 'read None;
+            }
+        }
+    };
+
+    peers.lock().retain(|peer| !peer.same_channel(&out_tx));
+    writing.abort();
+    reason
+}
+
 /// Convert OSC argument to Clasp value
 fn osc_arg_to_value(arg: &OscType) -> Value {
     match arg {
@@ -299,6 +689,8 @@ fn packet_to_messages(packet: &OscPacket, namespace: &str) -> Option<Vec<Message
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             })])
         }
         OscPacket::Bundle(bundle) => {
@@ -319,6 +711,7 @@ fn packet_to_messages(packet: &OscPacket, namespace: &str) -> Option<Vec<Message
                 Some(vec![Message::Bundle(clasp_core::BundleMessage {
                     timestamp: Some(timestamp),
                     messages,
+                    correlation_id: None,
                 })])
             }
         }
@@ -348,4 +741,54 @@ mod tests {
             _ => panic!("Expected Double"),
         }
     }
+
+    #[test]
+    fn test_packet_length_framing() {
+        let framing = OscFraming::PacketLength;
+        let mut stream = framing.encode(b"/a\0\0");
+        stream.extend(framing.encode(b"/bc\0"));
+        assert_eq!(&stream[..4], &[0, 0, 0, 4]);
+
+        // Packets split across reads come out whole
+        let mut decoder = OscFrameDecoder::new(framing);
+        assert!(decoder.push(&stream[..6]).unwrap().is_empty());
+        let packets = decoder.push(&stream[6..]).unwrap();
+        assert_eq!(packets, vec![b"/a\0\0".to_vec(), b"/bc\0".to_vec()]);
+
+        let mut decoder = OscFrameDecoder::new(framing);
+        assert!(decoder.push(&[0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+    }
+
+    #[test]
+    fn test_slip_framing() {
+        let framing = OscFraming::Slip;
+        let packet = [0x2F, SLIP_END, 0x00, SLIP_ESC, 0x01];
+        let frame = framing.encode(&packet);
+        assert_eq!(
+            frame,
+            vec![
+                SLIP_END,
+                0x2F,
+                SLIP_ESC,
+                SLIP_ESC_END,
+                0x00,
+                SLIP_ESC,
+                SLIP_ESC_ESC,
+                0x01,
+                SLIP_END
+            ]
+        );
+
+        let mut decoder = OscFrameDecoder::new(framing);
+        let mut packets = Vec::new();
+        for byte in frame.iter().chain(frame.iter()) {
+            packets.extend(decoder.push(&[*byte]).unwrap());
+        }
+        assert_eq!(packets, vec![packet.to_vec(), packet.to_vec()]);
+
+        // A single END between packets (OSC 1.0 style SLIP) works too
+        let mut decoder = OscFrameDecoder::new(framing);
+        let packets = decoder.push(&[0x01, SLIP_END, 0x02, SLIP_END]).unwrap();
+        assert_eq!(packets, vec![vec![0x01], vec![0x02]]);
+    }
 }
@@ -0,0 +1,612 @@
+//! Runtime-loaded bridge plugins
+//!
+//! Bridges for protocols that are not built into this crate can be shipped as
+//! dynamic libraries (`.so`, `.dylib`, `.dll`) and loaded at runtime, so
+//! closed-source or out-of-tree protocols don't need a rebuild.
+//!
+//! A plugin exports one C function, `clasp_plugin_entry`, returning a
+//! [`PluginVTable`]. Messages cross the boundary as encoded CLASP frames, so
+//! the plugin and host only share the `#[repr(C)]` types in this module, not
+//! Rust type layouts or compiler versions. The host refuses plugins built
+//! against a different [`PLUGIN_ABI_VERSION`].
+//!
+//! Plugins run in the host process with its privileges. Only load plugins you
+//! trust.
+//!
+//! ```ignore
+//! let registry = unsafe { PluginRegistry::load_dir("plugins")? };
+//! let plugin = registry.get("knx").unwrap();
+//! let mut bridge = plugin.create_bridge(&serde_json::json!({ "gateway": "10.0.0.5" }))?;
+//! let events = bridge.start().await?;
+//! ```
+
+use async_trait::async_trait;
+use clasp_core::{codec, Message};
+use libloading::Library;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
+
+/// Version of the plugin ABI, bumped on any change to the types below
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol every plugin exports, with the signature of [`PluginEntry`]
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"clasp_plugin_entry\0";
+
+/// `extern "C" fn clasp_plugin_entry() -> *const PluginVTable`
+pub type PluginEntry = unsafe extern "C" fn() -> *const PluginVTable;
+
+/// Event kind: `data` is an encoded CLASP frame to route
+pub const PLUGIN_EVENT_MESSAGE: u32 = 0;
+/// Event kind: the protocol side (re)connected
+pub const PLUGIN_EVENT_CONNECTED: u32 = 1;
+/// Event kind: the protocol side disconnected, `data` is an optional UTF-8 reason
+pub const PLUGIN_EVENT_DISCONNECTED: u32 = 2;
+/// Event kind: `data` is a UTF-8 error message
+pub const PLUGIN_EVENT_ERROR: u32 = 3;
+
+/// Callback the host hands to a plugin when it starts
+///
+/// `emit` may be called from any thread until the plugin's `stop` returns.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PluginHost {
+    /// Opaque host state, passed back to `emit`
+    pub context: *mut c_void,
+    /// Deliver an event of one of the `PLUGIN_EVENT_*` kinds
+    pub emit: unsafe extern "C" fn(context: *mut c_void, kind: u32, data: *const u8, len: usize),
+}
+
+// The host's emit is thread-safe
+unsafe impl Send for PluginHost {}
+unsafe impl Sync for PluginHost {}
+
+impl PluginHost {
+    /// Send a message into CLASP
+    pub fn emit_message(&self, message: &Message) -> Result<()> {
+        let frame = codec::encode(message).map_err(|e| BridgeError::Protocol(e.to_string()))?;
+        self.emit(PLUGIN_EVENT_MESSAGE, &frame);
+        Ok(())
+    }
+
+    /// Report that the protocol side reconnected
+    pub fn emit_connected(&self) {
+        self.emit(PLUGIN_EVENT_CONNECTED, &[]);
+    }
+
+    /// Report that the protocol side disconnected
+    pub fn emit_disconnected(&self, reason: Option<&str>) {
+        self.emit(
+            PLUGIN_EVENT_DISCONNECTED,
+            reason.unwrap_or_default().as_bytes(),
+        );
+    }
+
+    /// Report an error
+    pub fn emit_error(&self, error: &str) {
+        self.emit(PLUGIN_EVENT_ERROR, error.as_bytes());
+    }
+
+    fn emit(&self, kind: u32, data: &[u8]) {
+        unsafe { (self.emit)(self.context, kind, data.as_ptr(), data.len()) }
+    }
+}
+
+/// The functions and metadata a plugin provides
+///
+/// Functions returning `i32` return 0 on success. A plugin can `emit` a
+/// [`PLUGIN_EVENT_ERROR`] with details before returning a failure.
+/// `instance` functions may be called from any thread, and `send` may run
+/// while the plugin is emitting.
+#[repr(C)]
+pub struct PluginVTable {
+    /// Must be [`PLUGIN_ABI_VERSION`]
+    pub abi_version: u32,
+    /// Protocol identifier (NUL-terminated), used as the bridge type
+    pub protocol: *const c_char,
+    /// Human-readable name (NUL-terminated)
+    pub name: *const c_char,
+    /// Whether `send` does anything
+    pub bidirectional: bool,
+    /// Create an instance from UTF-8 JSON configuration, or return null
+    pub create: unsafe extern "C" fn(config: *const u8, len: usize) -> *mut c_void,
+    /// Start the instance; events go through `host` until `stop` returns.
+    /// The host reports the bridge connected once this succeeds.
+    pub start: unsafe extern "C" fn(instance: *mut c_void, host: PluginHost) -> i32,
+    /// Send an encoded CLASP frame to the protocol
+    pub send: unsafe extern "C" fn(instance: *mut c_void, data: *const u8, len: usize) -> i32,
+    /// Stop the instance. It must not call `emit` after this returns.
+    pub stop: unsafe extern "C" fn(instance: *mut c_void) -> i32,
+    /// Free the instance
+    pub destroy: unsafe extern "C" fn(instance: *mut c_void),
+}
+
+// Lets plugins keep their table in a `static`; it is never written to
+unsafe impl Sync for PluginVTable {}
+
+/// A loaded plugin, which creates bridges
+#[derive(Clone)]
+pub struct Plugin {
+    vtable: &'static PluginVTable,
+    protocol: String,
+    name: String,
+    /// Keeps the library loaded while the table or any bridge is alive
+    _library: Option<Arc<Library>>,
+}
+
+impl std::fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin")
+            .field("protocol", &self.protocol)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl Plugin {
+    /// Load a plugin from a dynamic library
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialisers, and the library must export
+    /// `clasp_plugin_entry` with the signature of [`PluginEntry`]. Only load
+    /// trusted plugins.
+    pub unsafe fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let library = Library::new(path)
+            .map_err(|e| BridgeError::Other(format!("{}: {}", path.display(), e)))?;
+        let entry = library
+            .get::<PluginEntry>(PLUGIN_ENTRY_SYMBOL)
+            .map_err(|e| BridgeError::Other(format!("{}: {}", path.display(), e)))?;
+        let vtable = entry();
+        if vtable.is_null() {
+            return Err(BridgeError::Other(format!(
+                "{}: clasp_plugin_entry returned null",
+                path.display()
+            )));
+        }
+
+        let mut plugin = Self::from_vtable(&*vtable)?;
+        plugin._library = Some(Arc::new(library));
+        Ok(plugin)
+    }
+
+    /// Use a plugin table that is linked into the host
+    ///
+    /// # Safety
+    ///
+    /// The table's strings must be valid NUL-terminated UTF-8, and its
+    /// functions must follow the contract on [`PluginVTable`].
+    pub unsafe fn from_vtable(vtable: &'static PluginVTable) -> Result<Self> {
+        if vtable.abi_version != PLUGIN_ABI_VERSION {
+            return Err(BridgeError::Other(format!(
+                "plugin ABI version {} is not supported (expected {})",
+                vtable.abi_version, PLUGIN_ABI_VERSION
+            )));
+        }
+        let text = |s: *const c_char, field: &str| {
+            if s.is_null() {
+                return Err(BridgeError::Other(format!("plugin {} is null", field)));
+            }
+            CStr::from_ptr(s)
+                .to_str()
+                .map(String::from)
+                .map_err(|_| BridgeError::Other(format!("plugin {} is not UTF-8", field)))
+        };
+
+        Ok(Self {
+            vtable,
+            protocol: text(vtable.protocol, "protocol")?,
+            name: text(vtable.name, "name")?,
+            _library: None,
+        })
+    }
+
+    /// Protocol identifier
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    /// Human-readable name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Create a bridge from JSON configuration
+    ///
+    /// The bridge namespace is the `namespace` key, or `/<protocol>`.
+    pub fn create_bridge(&self, config: &serde_json::Value) -> Result<PluginBridge> {
+        let json = config.to_string();
+        let instance = unsafe { (self.vtable.create)(json.as_ptr(), json.len()) };
+        if instance.is_null() {
+            return Err(BridgeError::Other(format!(
+                "{} plugin rejected its configuration",
+                self.protocol
+            )));
+        }
+
+        let namespace = config
+            .get("namespace")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| format!("/{}", self.protocol));
+
+        Ok(PluginBridge {
+            config: BridgeConfig {
+                name: self.name.clone(),
+                protocol: self.protocol.clone(),
+                bidirectional: self.vtable.bidirectional,
+                ..Default::default()
+            },
+            plugin: self.clone(),
+            instance,
+            namespace,
+            running: Arc::new(Mutex::new(false)),
+            host: None,
+        })
+    }
+}
+
+/// Plugins loaded from a directory, by protocol
+#[derive(Debug, Default, Clone)]
+pub struct PluginRegistry {
+    plugins: HashMap<String, Plugin>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every dynamic library in `dir`
+    ///
+    /// Libraries that fail to load are logged and skipped. A missing
+    /// directory gives an empty registry.
+    ///
+    /// # Safety
+    ///
+    /// See [`Plugin::load`]; every library in the directory is loaded.
+    pub unsafe fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut registry = Self::new();
+        if !dir.exists() {
+            debug!("Plugin directory {} does not exist", dir.display());
+            return Ok(registry);
+        }
+
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match Plugin::load(&path) {
+                Ok(plugin) => {
+                    info!(
+                        "Loaded {} plugin ({}) from {}",
+                        plugin.protocol,
+                        plugin.name,
+                        path.display()
+                    );
+                    registry.register(plugin);
+                }
+                Err(e) => warn!("Skipping plugin: {}", e),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Add a plugin, replacing any with the same protocol
+    pub fn register(&mut self, plugin: Plugin) {
+        if let Some(old) = self.plugins.insert(plugin.protocol.clone(), plugin) {
+            warn!("Plugin for {} replaced an earlier one", old.protocol);
+        }
+    }
+
+    /// Plugin for a protocol
+    pub fn get(&self, protocol: &str) -> Option<&Plugin> {
+        self.plugins.get(protocol)
+    }
+
+    /// Protocols with a loaded plugin
+    pub fn protocols(&self) -> impl Iterator<Item = &str> {
+        self.plugins.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+/// Host side of [`PluginHost`]
+struct HostContext {
+    tx: mpsc::Sender<BridgeEvent>,
+}
+
+unsafe extern "C" fn host_emit(context: *mut c_void, kind: u32, data: *const u8, len: usize) {
+    let context = &*(context as *const HostContext);
+    let data = if data.is_null() || len == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    };
+
+    let event = match kind {
+        PLUGIN_EVENT_MESSAGE => match codec::decode(data) {
+            Ok((message, _)) => BridgeEvent::ToClasp(message),
+            Err(e) => {
+                warn!("Plugin sent an invalid frame: {}", e);
+                return;
+            }
+        },
+        PLUGIN_EVENT_CONNECTED => BridgeEvent::Connected,
+        PLUGIN_EVENT_DISCONNECTED => BridgeEvent::Disconnected {
+            reason: (!data.is_empty()).then(|| String::from_utf8_lossy(data).into_owned()),
+        },
+        PLUGIN_EVENT_ERROR => BridgeEvent::Error(String::from_utf8_lossy(data).into_owned()),
+        other => {
+            warn!("Plugin sent unknown event kind {}", other);
+            return;
+        }
+    };
+    // Plugins may emit from any thread, including runtime threads, so never block
+    if context.tx.try_send(event).is_err() {
+        debug!("Plugin event dropped, channel full");
+    }
+}
+
+/// A bridge provided by a plugin
+pub struct PluginBridge {
+    config: BridgeConfig,
+    plugin: Plugin,
+    instance: *mut c_void,
+    namespace: String,
+    running: Arc<Mutex<bool>>,
+    /// Kept alive from start until the plugin has stopped
+    host: Option<Box<HostContext>>,
+}
+
+// Plugin instances may be used from any thread (see `PluginVTable`)
+unsafe impl Send for PluginBridge {}
+unsafe impl Sync for PluginBridge {}
+
+#[async_trait]
+impl Bridge for PluginBridge {
+    fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
+        if *self.running.lock() {
+            return Err(BridgeError::Other("Bridge already running".to_string()));
+        }
+
+        let (tx, rx) = mpsc::channel(100);
+        let host = Box::new(HostContext { tx: tx.clone() });
+        let callbacks = PluginHost {
+            context: &*host as *const HostContext as *mut c_void,
+            emit: host_emit,
+        };
+
+        let code = unsafe { (self.plugin.vtable.start)(self.instance, callbacks) };
+        if code != 0 {
+            return Err(BridgeError::ConnectionFailed(format!(
+                "{} plugin failed to start ({})",
+                self.plugin.protocol, code
+            )));
+        }
+        self.host = Some(host);
+        *self.running.lock() = true;
+
+        let _ = tx.send(BridgeEvent::Connected).await;
+        info!("{} plugin bridge started", self.plugin.protocol);
+        Ok(rx)
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        if !std::mem::replace(&mut *self.running.lock(), false) {
+            return Ok(());
+        }
+        let code = unsafe { (self.plugin.vtable.stop)(self.instance) };
+        // The plugin no longer emits, so the context can go
+        self.host = None;
+        if code != 0 {
+            return Err(BridgeError::Other(format!(
+                "{} plugin failed to stop ({})",
+                self.plugin.protocol, code
+            )));
+        }
+        info!("{} plugin bridge stopped", self.plugin.protocol);
+        Ok(())
+    }
+
+    async fn send(&self, message: Message) -> Result<()> {
+        if !*self.running.lock() {
+            return Err(BridgeError::Send("Not running".to_string()));
+        }
+        let frame = codec::encode(&message).map_err(|e| BridgeError::Send(e.to_string()))?;
+        let code = unsafe { (self.plugin.vtable.send)(self.instance, frame.as_ptr(), frame.len()) };
+        if code != 0 {
+            return Err(BridgeError::Send(format!(
+                "{} plugin failed to send ({})",
+                self.plugin.protocol, code
+            )));
+        }
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock()
+    }
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+}
+
+impl Drop for PluginBridge {
+    fn drop(&mut self) {
+        unsafe {
+            if *self.running.lock() {
+                (self.plugin.vtable.stop)(self.instance);
+            }
+            (self.plugin.vtable.destroy)(self.instance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clasp_core::{SetMessage, Value};
+
+    /// A plugin that sends every message straight back
+    struct Echo {
+        host: Mutex<Option<PluginHost>>,
+    }
+
+    unsafe extern "C" fn echo_create(config: *const u8, len: usize) -> *mut c_void {
+        let config = std::slice::from_raw_parts(config, len);
+        match serde_json::from_slice::<serde_json::Value>(config) {
+            Ok(v) if v.get("fail").is_none() => Box::into_raw(Box::new(Echo {
+                host: Mutex::new(None),
+            })) as *mut c_void,
+            _ => std::ptr::null_mut(),
+        }
+    }
+
+    unsafe extern "C" fn echo_start(instance: *mut c_void, host: PluginHost) -> i32 {
+        *(*(instance as *const Echo)).host.lock() = Some(host);
+        0
+    }
+
+    unsafe extern "C" fn echo_send(instance: *mut c_void, data: *const u8, len: usize) -> i32 {
+        let echo = &*(instance as *const Echo);
+        let Some(host) = *echo.host.lock() else {
+            return 1;
+        };
+        let Ok((message, _)) = codec::decode(std::slice::from_raw_parts(data, len)) else {
+            host.emit_error("bad frame");
+            return 2;
+        };
+        match host.emit_message(&message) {
+            Ok(()) => 0,
+            Err(_) => 3,
+        }
+    }
+
+    unsafe extern "C" fn echo_stop(instance: *mut c_void) -> i32 {
+        *(*(instance as *const Echo)).host.lock() = None;
+        0
+    }
+
+    unsafe extern "C" fn echo_destroy(instance: *mut c_void) {
+        drop(Box::from_raw(instance as *mut Echo));
+    }
+
+    // c"" literals need a newer compiler than the workspace MSRV
+    #[allow(clippy::manual_c_str_literals)]
+    static ECHO: PluginVTable = PluginVTable {
+        abi_version: PLUGIN_ABI_VERSION,
+        protocol: b"echo\0".as_ptr() as *const c_char,
+        name: b"Echo Plugin\0".as_ptr() as *const c_char,
+        bidirectional: true,
+        create: echo_create,
+        start: echo_start,
+        send: echo_send,
+        stop: echo_stop,
+        destroy: echo_destroy,
+    };
+
+    static FUTURE: PluginVTable = PluginVTable {
+        abi_version: PLUGIN_ABI_VERSION + 1,
+        ..ECHO
+    };
+
+    #[test]
+    fn test_plugin_metadata() {
+        let plugin = unsafe { Plugin::from_vtable(&ECHO) }.unwrap();
+        assert_eq!(plugin.protocol(), "echo");
+        assert_eq!(plugin.name(), "Echo Plugin");
+    }
+
+    #[test]
+    fn test_abi_version_mismatch_is_rejected() {
+        assert!(unsafe { Plugin::from_vtable(&FUTURE) }.is_err());
+    }
+
+    #[test]
+    fn test_rejected_configuration() {
+        let plugin = unsafe { Plugin::from_vtable(&ECHO) }.unwrap();
+        assert!(plugin
+            .create_bridge(&serde_json::json!({ "fail": true }))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_messages_round_trip() {
+        let plugin = unsafe { Plugin::from_vtable(&ECHO) }.unwrap();
+        let mut bridge = plugin
+            .create_bridge(&serde_json::json!({ "namespace": "/loop" }))
+            .unwrap();
+        assert_eq!(bridge.namespace(), "/loop");
+
+        let mut events = bridge.start().await.unwrap();
+        assert!(matches!(events.recv().await, Some(BridgeEvent::Connected)));
+
+        let set = Message::Set(SetMessage {
+            address: "/loop/level".to_string(),
+            value: Value::Float(0.5),
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        });
+        bridge.send(set).await.unwrap();
+        match events.recv().await {
+            Some(BridgeEvent::ToClasp(Message::Set(set))) => {
+                assert_eq!(set.address, "/loop/level");
+                assert_eq!(set.value, Value::Float(0.5));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        bridge.stop().await.unwrap();
+        assert!(!bridge.is_running());
+        assert!(bridge.send(Message::Ping).await.is_err());
+    }
+
+    #[test]
+    fn test_registry_skips_non_plugins() {
+        let dir = std::env::temp_dir().join(format!("clasp-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION)),
+            b"not a library",
+        )
+        .unwrap();
+
+        let mut registry = unsafe { PluginRegistry::load_dir(&dir) }.unwrap();
+        assert!(registry.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(unsafe { PluginRegistry::load_dir(dir.join("missing")) }
+            .unwrap()
+            .is_empty());
+
+        registry.register(unsafe { Plugin::from_vtable(&ECHO) }.unwrap());
+        assert!(registry.get("echo").is_some());
+        assert_eq!(registry.protocols().collect::<Vec<_>>(), vec!["echo"]);
+    }
+}
@@ -6,6 +6,12 @@
 //! - Priority-based source selection
 //! - Synchronization between universes
 //!
+//! The receiver merges every source sending to a universe: the highest
+//! priority wins each slot (per-address priority from 0xDD packets where
+//! sources send it), ties take the highest level, and the source driving the
+//! most slots is published at `/sacn/<universe>/source`. Data carrying a
+//! synchronization address is held until the matching sync packet arrives.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -33,12 +39,13 @@ use async_trait::async_trait;
 use clasp_core::{Message, SetMessage, Value};
 use parking_lot::Mutex;
 use sacn_lib::packet::ACN_SDT_MULTICAST_PORT;
-use sacn_lib::receive::SacnReceiver;
 use sacn_lib::source::SacnSource;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
@@ -89,6 +96,10 @@ pub struct SacnBridgeConfig {
     /// Synchronization address (0 = no sync)
     #[serde(default)]
     pub sync_address: u16,
+    /// Time in milliseconds after which a silent source is dropped from the
+    /// merge (receiver mode)
+    #[serde(default = "default_source_timeout")]
+    pub source_timeout_ms: u64,
 }
 
 fn default_universes() -> Vec<u16> {
@@ -111,6 +122,10 @@ fn default_namespace() -> String {
     "/sacn".to_string()
 }
 
+fn default_source_timeout() -> u64 {
+    2500 // E1.31 network data loss timeout
+}
+
 impl Default for SacnBridgeConfig {
     fn default() -> Self {
         Self {
@@ -124,8 +139,428 @@ impl Default for SacnBridgeConfig {
             namespace: default_namespace(),
             preview: false,
             sync_address: 0,
+            source_timeout_ms: default_source_timeout(),
+        }
+    }
+}
+
+/// ACN packet identifier at the start of every E1.31 packet
+const ACN_PACKET_IDENTIFIER: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
+const VECTOR_ROOT_E131_EXTENDED: u32 = 0x0000_0008;
+const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
+const VECTOR_E131_EXTENDED_SYNCHRONIZATION: u32 = 0x0000_0001;
+const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+
+/// Start code of DMX level data
+const START_CODE_LEVELS: u8 = 0x00;
+/// Start code of per-address priority data
+const START_CODE_ADDRESS_PRIORITY: u8 = 0xDD;
+
+const OPTION_PREVIEW: u8 = 0x80;
+const OPTION_STREAM_TERMINATED: u8 = 0x40;
+
+/// How often silent sources and stale sync state are checked
+const EXPIRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A decoded E1.31 packet
+#[derive(Debug, Clone, PartialEq)]
+enum E131Packet {
+    Data(E131Data),
+    Sync { sync_address: u16 },
+}
+
+/// An E1.31 data packet
+#[derive(Debug, Clone, PartialEq)]
+struct E131Data {
+    cid: [u8; 16],
+    source_name: String,
+    priority: u8,
+    sync_address: u16,
+    sequence: u8,
+    options: u8,
+    universe: u16,
+    start_code: u8,
+    slots: Vec<u8>,
+}
+
+/// Decode an E1.31 data or synchronization packet
+fn parse_e131(buf: &[u8]) -> Option<E131Packet> {
+    if buf.len() < 38 || &buf[4..16] != ACN_PACKET_IDENTIFIER {
+        return None;
+    }
+    let u16_at = |i: usize| u16::from_be_bytes([buf[i], buf[i + 1]]);
+    let u32_at = |i: usize| u32::from_be_bytes([buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]);
+
+    let mut cid = [0u8; 16];
+    cid.copy_from_slice(&buf[22..38]);
+
+    match u32_at(18) {
+        VECTOR_ROOT_E131_DATA => {
+            if buf.len() < 126
+                || u32_at(40) != VECTOR_E131_DATA_PACKET
+                || buf[117] != VECTOR_DMP_SET_PROPERTY
+            {
+                return None;
+            }
+            // Property value count includes the start code
+            let count = u16_at(123) as usize;
+            if count == 0 || count > 513 || buf.len() < 125 + count {
+                return None;
+            }
+            let name = &buf[44..108];
+            let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+
+            Some(E131Packet::Data(E131Data {
+                cid,
+                source_name: String::from_utf8_lossy(&name[..name_len]).into_owned(),
+                priority: buf[108].min(200),
+                sync_address: u16_at(109),
+                sequence: buf[111],
+                options: buf[112],
+                universe: u16_at(113),
+                start_code: buf[125],
+                slots: buf[126..125 + count].to_vec(),
+            }))
+        }
+        VECTOR_ROOT_E131_EXTENDED => {
+            if buf.len() < 49 || u32_at(40) != VECTOR_E131_EXTENDED_SYNCHRONIZATION {
+                return None;
+            }
+            Some(E131Packet::Sync {
+                sync_address: u16_at(45),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// One source's contribution to a universe
+#[derive(Debug)]
+struct MergeSource {
+    name: String,
+    priority: u8,
+    levels: Vec<u8>,
+    /// Per-address priorities (0xDD) and when they were received
+    address_priority: Option<(Vec<u8>, Instant)>,
+    sequence: u8,
+    last_seen: Instant,
+}
+
+impl MergeSource {
+    /// Priority of this source for `slot`, or `None` if it doesn't drive it
+    fn slot_priority(&self, slot: usize, now: Instant, timeout: Duration) -> Option<u8> {
+        if slot >= self.levels.len() {
+            return None;
+        }
+        match &self.address_priority {
+            // A per-address priority of 0 means "not sourcing this slot"
+            Some((priorities, received)) if now.duration_since(*received) < timeout => {
+                priorities.get(slot).copied().filter(|&p| p > 0)
+            }
+            _ => Some(self.priority),
+        }
+    }
+}
+
+/// Sources sending to one universe, and what was last passed on to CLASP
+#[derive(Debug)]
+struct MergeUniverse {
+    sources: BTreeMap<[u8; 16], MergeSource>,
+    output: [Option<u8>; 512],
+    winner: Option<[u8; 16]>,
+}
+
+impl MergeUniverse {
+    fn new() -> Self {
+        Self {
+            sources: BTreeMap::new(),
+            output: [None; 512],
+            winner: None,
+        }
+    }
+
+    /// Take in a data packet, returning whether the merge may have changed
+    fn accept(&mut self, data: E131Data, now: Instant) -> bool {
+        if data.options & OPTION_STREAM_TERMINATED != 0 {
+            return self.sources.remove(&data.cid).is_some();
+        }
+
+        if let Some(source) = self.sources.get(&data.cid) {
+            // E1.31 6.7.2: discard packets that arrive out of order
+            let diff = data.sequence.wrapping_sub(source.sequence) as i8;
+            if diff <= 0 && diff > -20 {
+                return false;
+            }
+        }
+
+        let source = self.sources.entry(data.cid).or_insert_with(|| MergeSource {
+            name: String::new(),
+            priority: data.priority,
+            levels: Vec::new(),
+            address_priority: None,
+            sequence: data.sequence,
+            last_seen: now,
+        });
+        source.name = data.source_name;
+        source.sequence = data.sequence;
+        source.last_seen = now;
+
+        match data.start_code {
+            START_CODE_LEVELS => {
+                source.priority = data.priority;
+                source.levels = data.slots;
+                true
+            }
+            START_CODE_ADDRESS_PRIORITY => {
+                source.address_priority = Some((data.slots, now));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drop sources that have gone quiet, returning whether any were dropped
+    fn expire(&mut self, now: Instant, timeout: Duration) -> bool {
+        let before = self.sources.len();
+        self.sources
+            .retain(|_, source| now.duration_since(source.last_seen) < timeout);
+        self.sources.len() != before
+    }
+
+    /// Merge all sources: the highest priority wins each slot and ties take
+    /// the highest level. Also returns the source driving the most slots.
+    fn merge(&self, now: Instant, timeout: Duration) -> ([u8; 512], Option<[u8; 16]>) {
+        let mut levels = [0u8; 512];
+        let mut wins: BTreeMap<[u8; 16], usize> = BTreeMap::new();
+
+        for (slot, level_out) in levels.iter_mut().enumerate() {
+            let mut best: Option<(u8, u8, &[u8; 16])> = None;
+            for (cid, source) in &self.sources {
+                let Some(priority) = source.slot_priority(slot, now, timeout) else {
+                    continue;
+                };
+                let level = source.levels[slot];
+                let better = match best {
+                    Some((p, l, _)) => (priority, level) > (p, l),
+                    None => true,
+                };
+                if better {
+                    best = Some((priority, level, cid));
+                }
+            }
+            if let Some((_, level, cid)) = best {
+                *level_out = level;
+                *wins.entry(*cid).or_default() += 1;
+            }
+        }
+
+        let winner = wins
+            .into_iter()
+            .max_by_key(|(cid, count)| (*count, self.sources[cid].priority))
+            .map(|(cid, _)| cid);
+        (levels, winner)
+    }
+
+    /// Re-merge and build CLASP messages for whatever changed since last time
+    fn updates(
+        &mut self,
+        namespace: &str,
+        universe: u16,
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<Message> {
+        let (levels, winner) = self.merge(now, timeout);
+        let mut messages = Vec::new();
+
+        for (idx, &level) in levels.iter().enumerate() {
+            if self.output[idx] != Some(level) {
+                self.output[idx] = Some(level);
+                messages.push(SacnBridge::to_clasp_message(
+                    namespace,
+                    universe,
+                    (idx + 1) as u16,
+                    level,
+                ));
+            }
+        }
+
+        if winner != self.winner {
+            self.winner = winner;
+            let value = match winner.and_then(|cid| self.sources.get(&cid).map(|s| (cid, s))) {
+                Some((cid, source)) => Value::Map(HashMap::from([
+                    ("name".to_string(), Value::String(source.name.clone())),
+                    (
+                        "cid".to_string(),
+                        Value::String(uuid::Uuid::from_bytes(cid).to_string()),
+                    ),
+                    ("priority".to_string(), Value::Int(source.priority as i64)),
+                ])),
+                None => Value::Null,
+            };
+            messages.push(Message::Set(SetMessage {
+                address: format!("{}/{}/source", namespace, universe),
+                value,
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            }));
+        }
+
+        messages
+    }
+}
+
+/// Receive-side merge and synchronization state for all universes
+#[derive(Debug)]
+struct SacnMerger {
+    universes: HashMap<u16, MergeUniverse>,
+    /// Data waiting for a sync packet, keyed by synchronization address
+    held: HashMap<u16, Vec<E131Data>>,
+    /// When each synchronization address last saw a sync packet
+    last_sync: HashMap<u16, Instant>,
+    timeout: Duration,
+}
+
+impl SacnMerger {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            universes: HashMap::new(),
+            held: HashMap::new(),
+            last_sync: HashMap::new(),
+            timeout,
+        }
+    }
+
+    /// Handle a packet, returning the universes whose output may have changed
+    fn handle(&mut self, packet: E131Packet, now: Instant) -> Vec<u16> {
+        match packet {
+            E131Packet::Data(data) => {
+                if data.options & OPTION_PREVIEW != 0 {
+                    return Vec::new();
+                }
+
+                // Only hold data while sync packets are actually arriving
+                // (E1.31 11.2.2), otherwise process it unsynchronized
+                let synced = data.sync_address != 0
+                    && data.options & OPTION_STREAM_TERMINATED == 0
+                    && self
+                        .last_sync
+                        .get(&data.sync_address)
+                        .is_some_and(|&at| now.duration_since(at) < self.timeout);
+                if synced {
+                    let held = self.held.entry(data.sync_address).or_default();
+                    held.retain(|d| {
+                        !(d.cid == data.cid
+                            && d.universe == data.universe
+                            && d.start_code == data.start_code)
+                    });
+                    held.push(data);
+                    return Vec::new();
+                }
+
+                self.apply(data, now).into_iter().collect()
+            }
+            E131Packet::Sync { sync_address } => {
+                self.last_sync.insert(sync_address, now);
+                let mut changed = Vec::new();
+                for data in self.held.remove(&sync_address).unwrap_or_default() {
+                    if let Some(universe) = self.apply(data, now) {
+                        if !changed.contains(&universe) {
+                            changed.push(universe);
+                        }
+                    }
+                }
+                changed
+            }
+        }
+    }
+
+    fn apply(&mut self, data: E131Data, now: Instant) -> Option<u16> {
+        let universe = data.universe;
+        self.universes
+            .entry(universe)
+            .or_insert_with(MergeUniverse::new)
+            .accept(data, now)
+            .then_some(universe)
+    }
+
+    /// Drop silent sources and release data whose sync packets stopped
+    fn expire(&mut self, now: Instant) -> Vec<u16> {
+        let timeout = self.timeout;
+        self.last_sync
+            .retain(|_, at| now.duration_since(*at) < timeout);
+        let stale: Vec<u16> = self
+            .held
+            .keys()
+            .filter(|address| !self.last_sync.contains_key(address))
+            .copied()
+            .collect();
+        let mut changed: Vec<u16> = Vec::new();
+        for address in stale {
+            for data in self.held.remove(&address).unwrap_or_default() {
+                changed.extend(self.apply(data, now));
+            }
+        }
+
+        // Per-address priorities also time out, so every universe is re-merged
+        for (&universe, state) in &mut self.universes {
+            state.expire(now, timeout);
+            changed.push(universe);
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+    }
+
+    fn updates(&mut self, namespace: &str, universe: u16, now: Instant) -> Vec<Message> {
+        let timeout = self.timeout;
+        match self.universes.get_mut(&universe) {
+            Some(state) => state.updates(namespace, universe, now, timeout),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Bind the receiver socket and join the multicast group of each universe
+fn bind_receiver(config: &SacnBridgeConfig) -> std::io::Result<UdpSocket> {
+    let bind_addr = config
+        .bind_address
+        .as_deref()
+        .and_then(|s| {
+            s.parse::<SocketAddr>()
+                .ok()
+                .or_else(|| Some(SocketAddr::new(s.parse().ok()?, ACN_SDT_MULTICAST_PORT)))
+        })
+        .unwrap_or_else(|| SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), ACN_SDT_MULTICAST_PORT));
+
+    // Multicast is only delivered to sockets bound to the wildcard address,
+    // so the configured IP just selects the interface for the groups
+    let socket = socket2::Socket::new(
+        socket2::Domain::IPV4,
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), bind_addr.port()).into())?;
+    let socket = UdpSocket::from_std(socket.into())?;
+
+    let interface = match bind_addr.ip() {
+        IpAddr::V4(ip) => ip,
+        IpAddr::V6(_) => Ipv4Addr::UNSPECIFIED,
+    };
+    for &universe in &config.universes {
+        let group = Ipv4Addr::new(239, 255, (universe >> 8) as u8, universe as u8);
+        match socket.join_multicast_v4(group, interface) {
+            Ok(()) => info!("sACN subscribed to universe {}", universe),
+            Err(e) => warn!("Failed to subscribe to universe {}: {}", universe, e),
         }
     }
+
+    Ok(socket)
 }
 
 /// sACN/E1.31 bridge
@@ -174,6 +609,8 @@ impl SacnBridge {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         })
     }
 
@@ -201,17 +638,8 @@ impl SacnBridge {
         mut shutdown_rx: mpsc::Receiver<()>,
         running: Arc<Mutex<bool>>,
     ) {
-        let bind_addr: SocketAddr = config
-            .bind_address
-            .as_ref()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or_else(|| {
-                SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), ACN_SDT_MULTICAST_PORT)
-            });
-
-        // Create receiver
-        let mut receiver = match SacnReceiver::with_ip(bind_addr, None) {
-            Ok(r) => r,
+        let socket = match bind_receiver(&config) {
+            Ok(s) => s,
             Err(e) => {
                 error!("Failed to create sACN receiver: {}", e);
                 let _ = event_tx
@@ -221,70 +649,45 @@ impl SacnBridge {
             }
         };
 
-        // Subscribe to universes
-        for &universe in &config.universes {
-            if let Err(e) = receiver.listen_universes(&[universe]) {
-                warn!("Failed to subscribe to universe {}: {}", universe, e);
-            } else {
-                info!("sACN subscribed to universe {}", universe);
-            }
-        }
-
         *running.lock() = true;
         let _ = event_tx.send(BridgeEvent::Connected).await;
         info!(
             "sACN receiver started on {:?}, universes: {:?}",
-            bind_addr, config.universes
+            socket.local_addr().ok(),
+            config.universes
         );
 
-        // Track previous values to only send changes
-        let mut prev_values: HashMap<(u16, u16), u8> = HashMap::new();
+        let mut merger = SacnMerger::new(Duration::from_millis(config.source_timeout_ms));
+        let mut expiry = tokio::time::interval(EXPIRY_INTERVAL);
+        let mut buf = [0u8; 1144];
 
         loop {
-            tokio::select! {
-                // Check for shutdown
+            let changed = tokio::select! {
                 _ = shutdown_rx.recv() => {
                     info!("sACN receiver shutting down");
                     break;
                 }
-                // Poll for data (non-blocking with timeout)
-                _ = tokio::time::sleep(std::time::Duration::from_millis(10)) => {
-                    // Try to receive data
-                    match receiver.recv(Some(std::time::Duration::from_millis(1))) {
-                        Ok(packets) => {
-                            for packet in packets {
-                                let universe = packet.universe;
-                                let data = packet.values;
-
-                                // Send changes to CLASP
-                                for (idx, &value) in data.iter().enumerate() {
-                                    let channel = (idx + 1) as u16;
-                                    let key = (universe, channel);
-
-                                    // Only send if value changed
-                                    if prev_values.get(&key) != Some(&value) {
-                                        prev_values.insert(key, value);
-
-                                        let msg = Self::to_clasp_message(
-                                            &config.namespace,
-                                            universe,
-                                            channel,
-                                            value,
-                                        );
-
-                                        if let Err(e) = event_tx.send(BridgeEvent::ToClasp(msg)).await {
-                                            debug!("Failed to send sACN data to CLASP: {}", e);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            // Timeout is expected, other errors should be logged
-                            if !e.to_string().contains("timeout") && !e.to_string().contains("WouldBlock") {
-                                debug!("sACN receive error: {}", e);
-                            }
+                result = socket.recv_from(&mut buf) => match result {
+                    Ok((len, _)) => match parse_e131(&buf[..len]) {
+                        Some(E131Packet::Data(data)) if !config.universes.contains(&data.universe) => {
+                            continue;
                         }
+                        Some(packet) => merger.handle(packet, Instant::now()),
+                        None => continue,
+                    },
+                    Err(e) => {
+                        debug!("sACN receive error: {}", e);
+                        continue;
+                    }
+                },
+                _ = expiry.tick() => merger.expire(Instant::now()),
+            };
+
+            let now = Instant::now();
+            for universe in changed {
+                for msg in merger.updates(&config.namespace, universe, now) {
+                    if let Err(e) = event_tx.send(BridgeEvent::ToClasp(msg)).await {
+                        debug!("Failed to send sACN data to CLASP: {}", e);
                     }
                 }
             }
@@ -578,4 +981,271 @@ mod tests {
             panic!("Expected SET message");
         }
     }
+
+    /// Build an E1.31 data packet
+    fn data_packet(
+        cid: u8,
+        priority: u8,
+        sequence: u8,
+        sync_address: u16,
+        start_code: u8,
+        slots: &[u8],
+    ) -> Vec<u8> {
+        let mut buf = vec![0u8; 126 + slots.len()];
+        buf[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+        buf[4..16].copy_from_slice(ACN_PACKET_IDENTIFIER);
+        buf[18..22].copy_from_slice(&VECTOR_ROOT_E131_DATA.to_be_bytes());
+        buf[22..38].copy_from_slice(&[cid; 16]);
+        buf[40..44].copy_from_slice(&VECTOR_E131_DATA_PACKET.to_be_bytes());
+        let name = format!("Console {}", cid);
+        buf[44..44 + name.len()].copy_from_slice(name.as_bytes());
+        buf[108] = priority;
+        buf[109..111].copy_from_slice(&sync_address.to_be_bytes());
+        buf[111] = sequence;
+        buf[113..115].copy_from_slice(&1u16.to_be_bytes());
+        buf[117] = VECTOR_DMP_SET_PROPERTY;
+        buf[118] = 0xa1;
+        buf[121..123].copy_from_slice(&1u16.to_be_bytes());
+        buf[123..125].copy_from_slice(&((slots.len() + 1) as u16).to_be_bytes());
+        buf[125] = start_code;
+        buf[126..].copy_from_slice(slots);
+        buf
+    }
+
+    fn sync_packet(sync_address: u16) -> Vec<u8> {
+        let mut buf = vec![0u8; 49];
+        buf[4..16].copy_from_slice(ACN_PACKET_IDENTIFIER);
+        buf[18..22].copy_from_slice(&VECTOR_ROOT_E131_EXTENDED.to_be_bytes());
+        buf[40..44].copy_from_slice(&VECTOR_E131_EXTENDED_SYNCHRONIZATION.to_be_bytes());
+        buf[45..47].copy_from_slice(&sync_address.to_be_bytes());
+        buf
+    }
+
+    fn feed(merger: &mut SacnMerger, packet: &[u8], now: Instant) -> Vec<u16> {
+        merger.handle(parse_e131(packet).expect("valid packet"), now)
+    }
+
+    fn levels(merger: &SacnMerger, now: Instant) -> ([u8; 512], Option<[u8; 16]>) {
+        merger.universes[&1].merge(now, merger.timeout)
+    }
+
+    #[test]
+    fn test_parse_data_packet() {
+        let packet = data_packet(7, 150, 42, 0, START_CODE_LEVELS, &[1, 2, 3]);
+        match parse_e131(&packet) {
+            Some(E131Packet::Data(data)) => {
+                assert_eq!(data.cid, [7; 16]);
+                assert_eq!(data.source_name, "Console 7");
+                assert_eq!(data.priority, 150);
+                assert_eq!(data.sequence, 42);
+                assert_eq!(data.universe, 1);
+                assert_eq!(data.start_code, START_CODE_LEVELS);
+                assert_eq!(data.slots, vec![1, 2, 3]);
+            }
+            other => panic!("Expected data packet, got {:?}", other),
+        }
+
+        assert_eq!(
+            parse_e131(&sync_packet(9)),
+            Some(E131Packet::Sync { sync_address: 9 })
+        );
+        assert_eq!(parse_e131(&packet[..100]), None);
+        assert_eq!(parse_e131(b"Art-Net\0"), None);
+    }
+
+    #[test]
+    fn test_priority_arbitration() {
+        let now = Instant::now();
+        let mut merger = SacnMerger::new(Duration::from_millis(2500));
+
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 0, 0, START_CODE_LEVELS, &[200, 10]),
+            now,
+        );
+        feed(
+            &mut merger,
+            &data_packet(2, 120, 0, 0, START_CODE_LEVELS, &[50, 20]),
+            now,
+        );
+        let (out, winner) = levels(&merger, now);
+        assert_eq!(&out[..2], &[50, 20]);
+        assert_eq!(winner, Some([2; 16]));
+
+        // Equal priority falls back to highest takes precedence
+        feed(
+            &mut merger,
+            &data_packet(1, 120, 1, 0, START_CODE_LEVELS, &[200, 10]),
+            now,
+        );
+        let (out, _) = levels(&merger, now);
+        assert_eq!(&out[..2], &[200, 20]);
+
+        // The winner stops sending and times out
+        let later = now + Duration::from_secs(3);
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 2, 0, START_CODE_LEVELS, &[200, 10]),
+            later,
+        );
+        merger.expire(later);
+        let (out, winner) = levels(&merger, later);
+        assert_eq!(&out[..2], &[200, 10]);
+        assert_eq!(winner, Some([1; 16]));
+    }
+
+    #[test]
+    fn test_per_address_priority() {
+        let now = Instant::now();
+        let mut merger = SacnMerger::new(Duration::from_millis(2500));
+
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 0, 0, START_CODE_LEVELS, &[11, 12, 13]),
+            now,
+        );
+        feed(
+            &mut merger,
+            &data_packet(2, 100, 0, 0, START_CODE_LEVELS, &[1, 2, 3]),
+            now,
+        );
+        // Source 2 wins slot 1, doesn't drive slot 2, loses slot 3
+        feed(
+            &mut merger,
+            &data_packet(2, 100, 1, 0, START_CODE_ADDRESS_PRIORITY, &[200, 0, 1]),
+            now,
+        );
+        let (out, _) = levels(&merger, now);
+        assert_eq!(&out[..3], &[1, 12, 13]);
+
+        // Without fresh 0xDD data, source 2 reverts to its universe priority
+        let later = now + Duration::from_secs(3);
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 1, 0, START_CODE_LEVELS, &[11, 12, 13]),
+            later,
+        );
+        feed(
+            &mut merger,
+            &data_packet(2, 100, 2, 0, START_CODE_LEVELS, &[1, 2, 3]),
+            later,
+        );
+        let (out, _) = levels(&merger, later);
+        assert_eq!(&out[..3], &[11, 12, 13]);
+    }
+
+    #[test]
+    fn test_sequence_and_termination() {
+        let now = Instant::now();
+        let mut merger = SacnMerger::new(Duration::from_millis(2500));
+
+        assert_eq!(
+            feed(
+                &mut merger,
+                &data_packet(1, 100, 250, 0, START_CODE_LEVELS, &[5]),
+                now
+            ),
+            vec![1]
+        );
+        // Late packet is discarded
+        assert!(feed(
+            &mut merger,
+            &data_packet(1, 100, 249, 0, START_CODE_LEVELS, &[6]),
+            now
+        )
+        .is_empty());
+        // Wrapping forward is accepted
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 0, 0, START_CODE_LEVELS, &[8]),
+            now,
+        );
+        assert_eq!(levels(&merger, now).0[0], 8);
+
+        let mut terminate = data_packet(1, 100, 1, 0, START_CODE_LEVELS, &[8]);
+        terminate[112] = OPTION_STREAM_TERMINATED;
+        feed(&mut merger, &terminate, now);
+        assert_eq!(levels(&merger, now), ([0; 512], None));
+    }
+
+    #[test]
+    fn test_sync_holds_data() {
+        let now = Instant::now();
+        let mut merger = SacnMerger::new(Duration::from_millis(2500));
+
+        // No sync seen yet, so data is processed straight away
+        assert_eq!(
+            feed(
+                &mut merger,
+                &data_packet(1, 100, 0, 7, START_CODE_LEVELS, &[1]),
+                now
+            ),
+            vec![1]
+        );
+
+        assert!(feed(&mut merger, &sync_packet(7), now).is_empty());
+        assert!(feed(
+            &mut merger,
+            &data_packet(1, 100, 1, 7, START_CODE_LEVELS, &[2]),
+            now
+        )
+        .is_empty());
+        assert!(feed(
+            &mut merger,
+            &data_packet(1, 100, 2, 7, START_CODE_LEVELS, &[3]),
+            now
+        )
+        .is_empty());
+        assert_eq!(levels(&merger, now).0[0], 1);
+
+        assert_eq!(feed(&mut merger, &sync_packet(7), now), vec![1]);
+        assert_eq!(levels(&merger, now).0[0], 3);
+
+        // Held data is released once sync packets stop
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 3, 7, START_CODE_LEVELS, &[4]),
+            now,
+        );
+        let later = now + Duration::from_secs(3);
+        assert_eq!(merger.expire(later), vec![1]);
+        assert_eq!(levels(&merger, later).0[0], 4);
+    }
+
+    #[test]
+    fn test_merge_updates() {
+        let now = Instant::now();
+        let mut merger = SacnMerger::new(Duration::from_millis(2500));
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 0, 0, START_CODE_LEVELS, &[255]),
+            now,
+        );
+
+        let messages = merger.updates("/sacn", 1, now);
+        assert_eq!(messages.len(), 513);
+        match messages.last() {
+            Some(Message::Set(set)) => {
+                assert_eq!(set.address, "/sacn/1/source");
+                let Value::Map(info) = &set.value else {
+                    panic!("Expected source map");
+                };
+                assert_eq!(info["name"], Value::String("Console 1".to_string()));
+                assert_eq!(info["priority"], Value::Int(100));
+            }
+            other => panic!("Expected source update, got {:?}", other),
+        }
+
+        // Only changes are sent afterwards
+        feed(
+            &mut merger,
+            &data_packet(1, 100, 1, 0, START_CODE_LEVELS, &[128]),
+            now,
+        );
+        let messages = merger.updates("/sacn", 1, now);
+        assert_eq!(messages.len(), 1);
+        assert!(
+            matches!(&messages[0], Message::Set(set) if set.address == "/sacn/1/1" && set.value == Value::Int(128))
+        );
+    }
 }
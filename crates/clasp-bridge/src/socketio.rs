@@ -244,6 +244,8 @@ impl Bridge for SocketIOBridge {
                         revision: None,
                         lock: false,
                         unlock: false,
+                        op: None,
+                        correlation_id: None,
                     });
 
                     debug!("Socket.IO received event: {}", event_name);
@@ -0,0 +1,956 @@
+//! Timecode bridge (LTC and Art-Net timecode)
+//!
+//! Reads house timecode and publishes it as streams, so cues can follow it:
+//!
+//! - `/timecode/hours`, `/minutes`, `/seconds`, `/frames`: the current
+//!   timecode, with hours to seconds only sent when they change
+//! - `/timecode/frame_count`: frames since 00:00:00:00, which counts up one
+//!   per frame while timecode runs and accounts for drop-frame
+//!
+//! Timecode comes from Art-Net (ArtTimeCode packets) or from LTC on an audio
+//! input. LTC needs the `audio` feature, which can also generate LTC on an
+//! audio output, driven by SETs or a CLASP timeline sent to
+//! `/timecode/generate`.
+
+use async_trait::async_trait;
+use clasp_core::{Message, PublishMessage, SignalType, Value};
+use parking_lot::Mutex;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
+
+/// Art-Net packet header
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+/// ArtTimeCode opcode
+const OP_TIMECODE: u16 = 0x9700;
+/// LTC sync word, bits 64-79 of a frame read as a little-endian number
+const LTC_SYNC_WORD: u16 = 0xBFFC;
+/// Bits in an LTC frame
+const LTC_FRAME_BITS: usize = 80;
+
+/// Timecode frame rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimecodeRate {
+    /// 24 fps (film)
+    Film24,
+    /// 25 fps (EBU)
+    #[default]
+    Ebu25,
+    /// 29.97 fps drop-frame
+    Df2997,
+    /// 30 fps (SMPTE non-drop)
+    Smpte30,
+}
+
+impl TimecodeRate {
+    /// Frames numbered per second, 30 for 29.97 drop-frame
+    pub fn nominal_fps(&self) -> u32 {
+        match self {
+            TimecodeRate::Film24 => 24,
+            TimecodeRate::Ebu25 => 25,
+            TimecodeRate::Df2997 | TimecodeRate::Smpte30 => 30,
+        }
+    }
+
+    /// Frames actually played per second
+    pub fn fps(&self) -> f64 {
+        match self {
+            TimecodeRate::Df2997 => 30_000.0 / 1001.0,
+            other => other.nominal_fps() as f64,
+        }
+    }
+
+    fn from_artnet(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(TimecodeRate::Film24),
+            1 => Some(TimecodeRate::Ebu25),
+            2 => Some(TimecodeRate::Df2997),
+            3 => Some(TimecodeRate::Smpte30),
+            _ => None,
+        }
+    }
+}
+
+/// A SMPTE timecode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub rate: TimecodeRate,
+}
+
+impl Timecode {
+    pub fn new(hours: u8, minutes: u8, seconds: u8, frames: u8, rate: TimecodeRate) -> Self {
+        Self {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            rate,
+        }
+    }
+
+    /// Frames since 00:00:00:00, skipping the frame numbers drop-frame leaves out
+    pub fn frame_count(&self) -> u64 {
+        let fps = self.rate.nominal_fps() as u64;
+        let total_minutes = self.hours as u64 * 60 + self.minutes as u64;
+        let count = (total_minutes * 60 + self.seconds as u64) * fps + self.frames as u64;
+        if self.rate == TimecodeRate::Df2997 {
+            // Frames 0 and 1 are skipped every minute except every tenth
+            count - 2 * (total_minutes - total_minutes / 10)
+        } else {
+            count
+        }
+    }
+
+    /// The timecode `count` frames after 00:00:00:00, wrapping after 24 hours
+    pub fn from_frame_count(count: u64, rate: TimecodeRate) -> Self {
+        let fps = rate.nominal_fps() as u64;
+        let mut count = count;
+        if rate == TimecodeRate::Df2997 {
+            // 17982 frames per ten minutes, 1798 per dropped minute
+            let tens = count / 17_982;
+            let rest = count % 17_982;
+            count += 18 * tens;
+            if rest >= 2 {
+                count += 2 * ((rest - 2) / 1798);
+            }
+        }
+        let count = count % (24 * 3600 * fps);
+        Self {
+            hours: (count / (3600 * fps)) as u8,
+            minutes: (count / (60 * fps) % 60) as u8,
+            seconds: (count / fps % 60) as u8,
+            frames: (count % fps) as u8,
+            rate,
+        }
+    }
+
+    /// The timecode `seconds` after 00:00:00:00
+    pub fn from_seconds(seconds: f64, rate: TimecodeRate) -> Self {
+        Self::from_frame_count((seconds.max(0.0) * rate.fps()) as u64, rate)
+    }
+
+    /// The next frame
+    pub fn next(&self) -> Self {
+        Self::from_frame_count(self.frame_count() + 1, self.rate)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.rate == TimecodeRate::Df2997 {
+            ';'
+        } else {
+            ':'
+        };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours, self.minutes, self.seconds, separator, self.frames
+        )
+    }
+}
+
+impl FromStr for Timecode {
+    type Err = BridgeError;
+
+    /// Parse `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame. The rate is
+    /// 25 fps (or 29.97 for drop-frame); set `rate` afterwards to change it.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || BridgeError::Mapping(format!("Invalid timecode: {}", s));
+        let rate = if s.contains(';') {
+            TimecodeRate::Df2997
+        } else {
+            TimecodeRate::default()
+        };
+        let parts: Vec<u8> = s
+            .split([':', ';'])
+            .map(|p| p.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+        match parts[..] {
+            [hours, minutes, seconds, frames] if hours < 24 && minutes < 60 && seconds < 60 => {
+                Ok(Self::new(hours, minutes, seconds, frames, rate))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Parse an ArtTimeCode packet
+fn parse_artnet_timecode(packet: &[u8]) -> Option<Timecode> {
+    if packet.len() < 19 || &packet[0..8] != ARTNET_ID {
+        return None;
+    }
+    if u16::from_le_bytes([packet[8], packet[9]]) != OP_TIMECODE {
+        return None;
+    }
+    let rate = TimecodeRate::from_artnet(packet[18])?;
+    Some(Timecode::new(
+        packet[17], packet[16], packet[15], packet[14], rate,
+    ))
+}
+
+/// Build an ArtTimeCode packet
+#[cfg_attr(not(test), allow(dead_code))]
+fn artnet_timecode_packet(tc: &Timecode) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(19);
+    packet.extend_from_slice(ARTNET_ID);
+    packet.extend_from_slice(&OP_TIMECODE.to_le_bytes());
+    packet.extend_from_slice(&[0, 14, 0, 0]);
+    packet.extend_from_slice(&[tc.frames, tc.seconds, tc.minutes, tc.hours]);
+    packet.push(match tc.rate {
+        TimecodeRate::Film24 => 0,
+        TimecodeRate::Ebu25 => 1,
+        TimecodeRate::Df2997 => 2,
+        TimecodeRate::Smpte30 => 3,
+    });
+    packet
+}
+
+/// Pack a timecode into the 80 bits of an LTC frame, bit 0 first
+fn ltc_frame_bits(tc: &Timecode) -> u128 {
+    let mut bits: u128 = 0;
+    let mut put = |value: u8, start: usize, width: usize| {
+        for i in 0..width {
+            if value >> i & 1 == 1 {
+                bits |= 1 << (start + i);
+            }
+        }
+    };
+    put(tc.frames % 10, 0, 4);
+    put(tc.frames / 10, 8, 2);
+    put((tc.rate == TimecodeRate::Df2997) as u8, 10, 1);
+    put(tc.seconds % 10, 16, 4);
+    put(tc.seconds / 10, 24, 3);
+    put(tc.minutes % 10, 32, 4);
+    put(tc.minutes / 10, 40, 3);
+    put(tc.hours % 10, 48, 4);
+    put(tc.hours / 10, 56, 2);
+    bits |= (LTC_SYNC_WORD as u128) << 64;
+
+    // The polarity bit keeps the count of zeros even, so every frame starts
+    // on the same edge
+    let polarity_bit = if tc.rate == TimecodeRate::Ebu25 {
+        59
+    } else {
+        27
+    };
+    if (LTC_FRAME_BITS as u32 - bits.count_ones()) % 2 == 1 {
+        bits |= 1 << polarity_bit;
+    }
+    bits
+}
+
+/// Read the timecode from the 80 bits of an LTC frame
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+fn ltc_frame_timecode(bits: u128, rate: TimecodeRate) -> Timecode {
+    let get = |start: usize, width: usize| ((bits >> start) as u8) & ((1 << width) - 1);
+    Timecode::new(
+        get(56, 2) * 10 + get(48, 4),
+        get(40, 3) * 10 + get(32, 4),
+        get(24, 3) * 10 + get(16, 4),
+        get(8, 2) * 10 + get(0, 4),
+        rate,
+    )
+}
+
+/// Decodes LTC from mono audio samples
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+struct LtcDecoder {
+    sample_rate: f64,
+    /// Estimated samples per bit
+    bit_period: f64,
+    /// Samples since the last zero crossing
+    since_crossing: f64,
+    /// Samples since the last sync word
+    since_sync: f64,
+    /// Measured samples per frame, used to tell the frame rate
+    frame_period: Option<f64>,
+    /// Signal is above zero
+    high: bool,
+    /// Decaying peak, for crossing hysteresis
+    envelope: f32,
+    /// A short half-bit is waiting for its pair
+    half_bit: bool,
+    /// The last 80 bits received, newest at the top
+    bits: u128,
+}
+
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+impl LtcDecoder {
+    fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f64;
+        Self {
+            sample_rate,
+            // Between 24 and 30 fps, so short and long intervals are told
+            // apart from the first edge whichever rate is playing
+            bit_period: sample_rate / (27.0 * LTC_FRAME_BITS as f64),
+            since_crossing: 0.0,
+            since_sync: 0.0,
+            frame_period: None,
+            high: false,
+            envelope: 0.0,
+            half_bit: false,
+            bits: 0,
+        }
+    }
+
+    /// Feed one sample, returning a timecode when a frame completes
+    fn push(&mut self, sample: f32) -> Option<Timecode> {
+        self.since_crossing += 1.0;
+        self.since_sync += 1.0;
+        self.envelope = (self.envelope * 0.999).max(sample.abs());
+
+        let threshold = self.envelope * 0.1;
+        let crossed = if self.high {
+            sample < -threshold
+        } else {
+            sample > threshold
+        };
+        if !crossed || self.envelope < 0.01 {
+            return None;
+        }
+        self.high = !self.high;
+        let interval = std::mem::replace(&mut self.since_crossing, 0.0);
+
+        if interval < self.bit_period * 0.75 {
+            // Half of a one
+            self.bit_period = self.bit_period * 0.75 + interval * 2.0 * 0.25;
+            self.half_bit = !self.half_bit;
+            if self.half_bit {
+                return None;
+            }
+            self.push_bit(true)
+        } else if interval < self.bit_period * 1.5 {
+            self.bit_period = self.bit_period * 0.75 + interval * 0.25;
+            self.half_bit = false;
+            self.push_bit(false)
+        } else {
+            // Too long for a bit: a dropout or the signal stopped
+            self.half_bit = false;
+            self.bits = 0;
+            None
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) -> Option<Timecode> {
+        self.bits = (self.bits >> 1) | ((bit as u128) << (LTC_FRAME_BITS - 1));
+        if (self.bits >> 64) as u16 != LTC_SYNC_WORD {
+            return None;
+        }
+
+        let period = std::mem::replace(&mut self.since_sync, 0.0);
+        self.frame_period = Some(match self.frame_period {
+            Some(previous) if (period - previous).abs() < previous * 0.1 => {
+                previous * 0.9 + period * 0.1
+            }
+            _ => period,
+        });
+        let fps = self.sample_rate / self.frame_period.unwrap_or(period);
+        let rate = if (self.bits >> 10) & 1 == 1 {
+            TimecodeRate::Df2997
+        } else if fps < 24.5 {
+            TimecodeRate::Film24
+        } else if fps < 27.5 {
+            TimecodeRate::Ebu25
+        } else {
+            TimecodeRate::Smpte30
+        };
+        Some(ltc_frame_timecode(self.bits, rate))
+    }
+}
+
+/// Where the LTC generator takes its position from
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+enum GeneratorClock {
+    Stopped,
+    /// Runs on from a timecode
+    Free(Timecode),
+    /// Follows a timeline whose values are seconds
+    Timeline(clasp_core::TimelinePlayer),
+}
+
+/// Produces LTC audio samples
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+struct LtcEncoder {
+    sample_rate: f64,
+    rate: TimecodeRate,
+    clock: GeneratorClock,
+    /// Bits of the frame being played
+    bits: u128,
+    /// Position in the current frame, in bits
+    position: f64,
+}
+
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+impl LtcEncoder {
+    fn new(sample_rate: u32, rate: TimecodeRate) -> Self {
+        Self {
+            sample_rate: sample_rate as f64,
+            rate,
+            clock: GeneratorClock::Stopped,
+            bits: 0,
+            position: LTC_FRAME_BITS as f64,
+        }
+    }
+
+    /// Pick the next frame to play, or None to play silence
+    fn next_frame(&mut self) -> Option<Timecode> {
+        match &mut self.clock {
+            GeneratorClock::Stopped => None,
+            GeneratorClock::Free(tc) => {
+                let current = *tc;
+                *tc = tc.next();
+                Some(current)
+            }
+            GeneratorClock::Timeline(player) => match player.sample(clasp_core::time::now()) {
+                Some(Value::Float(seconds)) => Some(Timecode::from_seconds(seconds, self.rate)),
+                Some(Value::Int(seconds)) => {
+                    Some(Timecode::from_seconds(seconds as f64, self.rate))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let step = self.rate.fps() * LTC_FRAME_BITS as f64 / self.sample_rate;
+        if self.position >= LTC_FRAME_BITS as f64 {
+            self.position -= LTC_FRAME_BITS as f64;
+            match self.next_frame() {
+                Some(tc) => self.bits = ltc_frame_bits(&tc),
+                None => {
+                    self.bits = 0;
+                    self.position = LTC_FRAME_BITS as f64;
+                    return 0.0;
+                }
+            }
+        }
+
+        // Biphase mark: the level flips at the start of every bit, and in the
+        // middle of a one. With an even number of zeros per frame each frame
+        // starts on the same edge, so the level is a function of position.
+        let bit_index = self.position as usize;
+        let mut flips = 0;
+        for i in 0..=bit_index {
+            flips += 1;
+            let one = (self.bits >> i) & 1 == 1;
+            if one && (i < bit_index || self.position.fract() >= 0.5) {
+                flips += 1;
+            }
+        }
+        self.position += step;
+        if flips % 2 == 0 {
+            0.5
+        } else {
+            -0.5
+        }
+    }
+}
+
+/// Where timecode is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimecodeSource {
+    /// ArtTimeCode packets on `bind_addr`
+    #[default]
+    ArtNet,
+    /// LTC on an audio input (needs the `audio` feature)
+    Ltc,
+    /// Read nothing, for generating only
+    None,
+}
+
+/// Timecode bridge configuration
+#[derive(Debug, Clone)]
+pub struct TimecodeBridgeConfig {
+    /// Address namespace
+    pub namespace: String,
+    /// Where timecode is read from
+    pub source: TimecodeSource,
+    /// Art-Net bind address
+    pub bind_addr: String,
+    /// LTC input device name (or part of it); the default input when unset
+    pub input_device: Option<String>,
+    /// Generate LTC on an audio output (needs the `audio` feature)
+    pub generate: bool,
+    /// LTC output device name (or part of it); the default output when unset
+    pub output_device: Option<String>,
+    /// Frame rate of generated LTC
+    pub rate: TimecodeRate,
+}
+
+impl Default for TimecodeBridgeConfig {
+    fn default() -> Self {
+        Self {
+            namespace: "/timecode".to_string(),
+            source: TimecodeSource::ArtNet,
+            bind_addr: "0.0.0.0:6454".to_string(),
+            input_device: None,
+            generate: false,
+            output_device: None,
+            rate: TimecodeRate::Ebu25,
+        }
+    }
+}
+
+/// Turns timecodes into CLASP streams
+struct TimecodePublisher {
+    namespace: String,
+    last: Option<Timecode>,
+}
+
+impl TimecodePublisher {
+    fn new(namespace: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            last: None,
+        }
+    }
+
+    fn messages(&mut self, tc: Timecode) -> Vec<Message> {
+        let last = self.last.replace(tc);
+        let mut messages = Vec::with_capacity(5);
+        let fields = [
+            ("hours", tc.hours, last.map(|l| l.hours)),
+            ("minutes", tc.minutes, last.map(|l| l.minutes)),
+            ("seconds", tc.seconds, last.map(|l| l.seconds)),
+        ];
+        for (name, value, previous) in fields {
+            if previous != Some(value) {
+                messages.push(self.stream(name, value as i64));
+            }
+        }
+        messages.push(self.stream("frames", tc.frames as i64));
+        messages.push(self.stream("frame_count", tc.frame_count() as i64));
+        messages
+    }
+
+    fn stream(&self, name: &str, value: i64) -> Message {
+        Message::Publish(PublishMessage {
+            address: format!("{}/{}", self.namespace, name),
+            signal: Some(SignalType::Stream),
+            value: Some(Value::Int(value)),
+            payload: None,
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: Some(clasp_core::time::now()),
+            timeline: None,
+        })
+    }
+}
+
+/// Timecode to CLASP bridge
+pub struct TimecodeBridge {
+    config: BridgeConfig,
+    timecode_config: TimecodeBridgeConfig,
+    running: Arc<Mutex<bool>>,
+    /// Signals the Art-Net receiver to stop
+    shutdown_tx: Option<mpsc::Sender<()>>,
+    /// The LTC generator, when enabled
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    encoder: Option<Arc<Mutex<LtcEncoder>>>,
+    /// Handles to the audio threads
+    _audio_threads: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl TimecodeBridge {
+    pub fn new(timecode_config: TimecodeBridgeConfig) -> Self {
+        let config = BridgeConfig {
+            name: "Timecode Bridge".to_string(),
+            protocol: "timecode".to_string(),
+            bidirectional: timecode_config.generate,
+            ..Default::default()
+        };
+
+        Self {
+            config,
+            timecode_config,
+            running: Arc::new(Mutex::new(false)),
+            shutdown_tx: None,
+            encoder: None,
+            _audio_threads: Vec::new(),
+        }
+    }
+
+    async fn start_artnet(&mut self, tx: mpsc::Sender<BridgeEvent>) -> Result<()> {
+        let socket = UdpSocket::bind(&self.timecode_config.bind_addr)
+            .await
+            .map_err(|e| BridgeError::ConnectionFailed(e.to_string()))?;
+        info!(
+            "Timecode bridge listening for Art-Net on {}",
+            self.timecode_config.bind_addr
+        );
+
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+        self.shutdown_tx = Some(shutdown_tx);
+        let mut publisher = TimecodePublisher::new(&self.timecode_config.namespace);
+
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1024];
+            loop {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    result = socket.recv_from(&mut buf) => match result {
+                        Ok((len, _)) => {
+                            let Some(tc) = parse_artnet_timecode(&buf[..len]) else {
+                                continue;
+                            };
+                            for msg in publisher.messages(tc) {
+                                let _ = tx.try_send(BridgeEvent::ToClasp(msg));
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Art-Net timecode receive error: {}", e);
+                        }
+                    }
+                }
+            }
+            debug!("Art-Net timecode receiver stopped");
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "audio")]
+    async fn start_ltc_input(&mut self, tx: mpsc::Sender<BridgeEvent>) -> Result<()> {
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+        let device = self.timecode_config.input_device.clone();
+        let namespace = self.timecode_config.namespace.clone();
+        let running = self.running.clone();
+
+        // cpal streams are not Send, so each lives on its own thread
+        self._audio_threads.push(std::thread::spawn(move || {
+            let opened = crate::audio::input_device(device.as_deref()).and_then(
+                |(device, config, format)| {
+                    let mut decoder = LtcDecoder::new(config.sample_rate.0);
+                    let mut publisher = TimecodePublisher::new(&namespace);
+                    crate::audio::start_input(&device, &config, format, move |sample| {
+                        if let Some(tc) = decoder.push(sample) {
+                            for msg in publisher.messages(tc) {
+                                let _ = tx.try_send(BridgeEvent::ToClasp(msg));
+                            }
+                        }
+                    })
+                },
+            );
+            let _stream = match opened {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+            while *running.lock() {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }));
+
+        ready_rx
+            .await
+            .map_err(|_| BridgeError::Other("LTC input thread exited".to_string()))?
+    }
+
+    #[cfg(feature = "audio")]
+    async fn start_ltc_output(&mut self) -> Result<()> {
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+        let device = self.timecode_config.output_device.clone();
+        let rate = self.timecode_config.rate;
+        let running = self.running.clone();
+
+        self._audio_threads.push(std::thread::spawn(move || {
+            let opened = crate::audio::output_device(device.as_deref()).and_then(
+                |(device, config, format)| {
+                    let encoder = Arc::new(Mutex::new(LtcEncoder::new(config.sample_rate.0, rate)));
+                    let output = encoder.clone();
+                    let stream = crate::audio::start_output(&device, &config, format, move || {
+                        output.lock().next_sample()
+                    })?;
+                    Ok((stream, encoder))
+                },
+            );
+            let _stream = match opened {
+                Ok((stream, encoder)) => {
+                    let _ = ready_tx.send(Ok(encoder));
+                    stream
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            while *running.lock() {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }));
+
+        let encoder = ready_rx
+            .await
+            .map_err(|_| BridgeError::Other("LTC output thread exited".to_string()))??;
+        self.encoder = Some(encoder);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "audio"))]
+    async fn start_ltc_input(&mut self, _tx: mpsc::Sender<BridgeEvent>) -> Result<()> {
+        Err(BridgeError::Other(
+            "LTC input needs the audio feature".to_string(),
+        ))
+    }
+
+    #[cfg(not(feature = "audio"))]
+    async fn start_ltc_output(&mut self) -> Result<()> {
+        Err(BridgeError::Other(
+            "LTC generation needs the audio feature".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Bridge for TimecodeBridge {
+    fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    async fn start(&mut self) -> Result<mpsc::Receiver<BridgeEvent>> {
+        if *self.running.lock() {
+            return Err(BridgeError::Other("Bridge already running".to_string()));
+        }
+
+        let (tx, rx) = mpsc::channel(100);
+        *self.running.lock() = true;
+
+        let started = async {
+            match self.timecode_config.source {
+                TimecodeSource::ArtNet => self.start_artnet(tx.clone()).await?,
+                TimecodeSource::Ltc => self.start_ltc_input(tx.clone()).await?,
+                TimecodeSource::None => {}
+            }
+            if self.timecode_config.generate {
+                self.start_ltc_output().await?;
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = started {
+            self.stop().await?;
+            return Err(e);
+        }
+
+        let _ = tx.send(BridgeEvent::Connected).await;
+        info!("Timecode bridge started");
+        Ok(rx)
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        *self.running.lock() = false;
+        if let Some(shutdown_tx) = self.shutdown_tx.take() {
+            let _ = shutdown_tx.send(()).await;
+        }
+        self.encoder = None;
+        // Audio threads close their streams and exit once running is false
+        self._audio_threads.clear();
+        info!("Timecode bridge stopped");
+        Ok(())
+    }
+
+    /// Control the LTC generator through `<namespace>/generate`: a timecode
+    /// string or a number of seconds locates and runs, null or false stops,
+    /// and a timeline whose values are seconds is followed
+    #[cfg(feature = "audio")]
+    async fn send(&self, message: Message) -> Result<()> {
+        let generate = format!("{}/generate", self.timecode_config.namespace);
+        let rate = self.timecode_config.rate;
+
+        let clock = match message {
+            Message::Publish(PublishMessage {
+                address,
+                timeline: Some(timeline),
+                ..
+            }) if address == generate => {
+                let mut player = clasp_core::TimelinePlayer::new(timeline);
+                player.start(clasp_core::time::now());
+                GeneratorClock::Timeline(player)
+            }
+            Message::Set(set) if set.address == generate => match set.value {
+                Value::String(s) => {
+                    let mut tc: Timecode = s.parse()?;
+                    tc.rate = rate;
+                    GeneratorClock::Free(tc)
+                }
+                Value::Float(seconds) => {
+                    GeneratorClock::Free(Timecode::from_seconds(seconds, rate))
+                }
+                Value::Int(seconds) => {
+                    GeneratorClock::Free(Timecode::from_seconds(seconds as f64, rate))
+                }
+                Value::Null | Value::Bool(false) => GeneratorClock::Stopped,
+                other => {
+                    return Err(BridgeError::Mapping(format!(
+                        "Cannot generate timecode from {:?}",
+                        other
+                    )))
+                }
+            },
+            _ => return Ok(()),
+        };
+        self.encoder
+            .as_ref()
+            .ok_or_else(|| BridgeError::Send("LTC generator is not enabled".to_string()))?
+            .lock()
+            .clock = clock;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "audio"))]
+    async fn send(&self, _message: Message) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock()
+    }
+
+    fn namespace(&self) -> &str {
+        &self.timecode_config.namespace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_count_round_trip() {
+        for rate in [
+            TimecodeRate::Film24,
+            TimecodeRate::Ebu25,
+            TimecodeRate::Df2997,
+            TimecodeRate::Smpte30,
+        ] {
+            for count in [0, 1, 1799, 1800, 17_981, 17_982, 107_892, 2_000_000] {
+                let tc = Timecode::from_frame_count(count, rate);
+                assert_eq!(tc.frame_count(), count, "{} at {:?}", tc, rate);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_frame_skips_frames() {
+        let tc = Timecode::new(0, 0, 59, 29, TimecodeRate::Df2997);
+        assert_eq!(tc.next().to_string(), "00:01:00;02");
+        // Every tenth minute keeps frames 0 and 1
+        let tc = Timecode::new(0, 9, 59, 29, TimecodeRate::Df2997);
+        assert_eq!(tc.next().to_string(), "00:10:00;00");
+        // A drop-frame hour is 107892 frames
+        assert_eq!(
+            Timecode::new(1, 0, 0, 0, TimecodeRate::Df2997).frame_count(),
+            107_892
+        );
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let tc: Timecode = "01:02:03:04".parse().unwrap();
+        assert_eq!(tc, Timecode::new(1, 2, 3, 4, TimecodeRate::Ebu25));
+        assert_eq!(tc.to_string(), "01:02:03:04");
+        let tc: Timecode = "10:00:00;02".parse().unwrap();
+        assert_eq!(tc.rate, TimecodeRate::Df2997);
+        assert!("25:00:00:00".parse::<Timecode>().is_err());
+        assert!("01:00:00".parse::<Timecode>().is_err());
+    }
+
+    #[test]
+    fn test_artnet_timecode() {
+        let tc = Timecode::new(10, 20, 30, 12, TimecodeRate::Smpte30);
+        let packet = artnet_timecode_packet(&tc);
+        assert_eq!(packet.len(), 19);
+        assert_eq!(parse_artnet_timecode(&packet), Some(tc));
+
+        let mut other = packet.clone();
+        other[9] = 0x50; // ArtDmx
+        assert_eq!(parse_artnet_timecode(&other), None);
+    }
+
+    #[test]
+    fn test_ltc_frame_bits() {
+        let tc = Timecode::new(23, 59, 58, 24, TimecodeRate::Ebu25);
+        let bits = ltc_frame_bits(&tc);
+        assert_eq!((bits >> 64) as u16, LTC_SYNC_WORD);
+        assert_eq!((LTC_FRAME_BITS as u32 - bits.count_ones()) % 2, 0);
+        assert_eq!(ltc_frame_timecode(bits, TimecodeRate::Ebu25), tc);
+    }
+
+    #[test]
+    fn test_ltc_round_trip() {
+        for (rate, sample_rate) in [
+            (TimecodeRate::Ebu25, 48_000),
+            (TimecodeRate::Smpte30, 44_100),
+            (TimecodeRate::Df2997, 48_000),
+            (TimecodeRate::Film24, 48_000),
+        ] {
+            let start = Timecode::new(1, 0, 59, 20, rate);
+            let mut encoder = LtcEncoder::new(sample_rate, rate);
+            encoder.clock = GeneratorClock::Free(start);
+            let mut decoder = LtcDecoder::new(sample_rate);
+
+            // About 20 frames; the first may be missed while the decoder locks
+            let mut decoded = Vec::new();
+            for _ in 0..sample_rate * 20 / 24 {
+                if let Some(tc) = decoder.push(encoder.next_sample()) {
+                    decoded.push(tc);
+                }
+            }
+            assert!(decoded.len() >= 15, "{:?}: {:?}", rate, decoded);
+            for pair in decoded.windows(2) {
+                assert_eq!(pair[1], pair[0].next(), "{:?}", rate);
+            }
+            assert!(decoded[0].frame_count() <= start.frame_count() + 1);
+        }
+    }
+
+    #[test]
+    fn test_stopped_encoder_is_silent() {
+        let mut encoder = LtcEncoder::new(48_000, TimecodeRate::Ebu25);
+        assert!((0..4800).all(|_| encoder.next_sample() == 0.0));
+    }
+
+    #[test]
+    fn test_publisher_only_sends_changes() {
+        let mut publisher = TimecodePublisher::new("/timecode");
+        let addresses = |messages: Vec<Message>| -> Vec<String> {
+            messages
+                .into_iter()
+                .map(|m| match m {
+                    Message::Publish(p) => p.address,
+                    other => panic!("Expected stream, got {:?}", other),
+                })
+                .collect()
+        };
+
+        let tc = Timecode::new(1, 0, 0, 0, TimecodeRate::Ebu25);
+        assert_eq!(
+            addresses(publisher.messages(tc)),
+            vec![
+                "/timecode/hours",
+                "/timecode/minutes",
+                "/timecode/seconds",
+                "/timecode/frames",
+                "/timecode/frame_count"
+            ]
+        );
+        assert_eq!(
+            addresses(publisher.messages(tc.next())),
+            vec!["/timecode/frames", "/timecode/frame_count"]
+        );
+    }
+}
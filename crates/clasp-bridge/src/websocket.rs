@@ -2,9 +2,15 @@
 //!
 //! Provides bidirectional WebSocket connectivity for CLASP.
 //! Supports both client and server modes.
+//!
+//! Messages are converted by a [`WsCodec`]: one of the built-in formats, a
+//! [`WsJsonMapping`] for third-party JSON APIs, or a custom codec installed
+//! with [`WebSocketBridge::with_codec`].
 
+use crate::mapping::render_json_template;
 use crate::{Bridge, BridgeConfig, BridgeError, BridgeEvent, Result};
 use async_trait::async_trait;
+use clasp_core::address::Pattern;
 use clasp_core::{Message, PublishMessage, SetMessage, SignalType, Value};
 use futures::{SinkExt, StreamExt};
 use parking_lot::Mutex;
@@ -31,6 +37,225 @@ pub enum WsMessageFormat {
     MsgPack,
     /// Raw binary/text passthrough
     Raw,
+    /// Binary passthrough: every frame is carried as bytes
+    Binary,
+    /// JSON translated by the configured [`WsJsonMapping`]
+    Mapped,
+}
+
+/// Mapping for JSON APIs that don't use the `{"address", "value"}` shape
+///
+/// Incoming messages take the address and value of the first `incoming` rule
+/// they match; outgoing messages use the template of the first `outgoing`
+/// rule whose pattern matches their address. Anything no rule matches is
+/// dropped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WsJsonMapping {
+    /// Rules for messages from the WebSocket peer
+    #[serde(default)]
+    pub incoming: Vec<WsIncomingRule>,
+    /// Rules for CLASP signals sent to the WebSocket peer
+    #[serde(default)]
+    pub outgoing: Vec<WsOutgoingRule>,
+}
+
+/// Rule turning an incoming JSON message into a CLASP signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsIncomingRule {
+    /// Values the message must contain, keyed by JSON Pointer (e.g., `"/op": 5`)
+    #[serde(default)]
+    pub when: HashMap<String, serde_json::Value>,
+    /// Address under the bridge namespace; `{{/json/pointer}}` is replaced by
+    /// the string or number at that pointer (e.g., "/obs/{{/d/eventType}}")
+    pub address: String,
+    /// JSON Pointer to the value (default: the whole message)
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Publish an event instead of setting a param
+    #[serde(default)]
+    pub event: bool,
+}
+
+/// Rule turning an outgoing CLASP signal into a JSON message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsOutgoingRule {
+    /// CLASP address pattern (e.g., "/ws/obs/scene")
+    pub pattern: String,
+    /// Message template; `{{address}}`, `{{value}}` and `{{type}}` (`"set"`
+    /// or `"event"`) are filled in
+    pub template: serde_json::Value,
+}
+
+impl WsIncomingRule {
+    /// Convert `json` if it matches this rule
+    fn apply(&self, json: &serde_json::Value, namespace: &str) -> Option<Message> {
+        if !self
+            .when
+            .iter()
+            .all(|(pointer, expected)| json.pointer(pointer) == Some(expected))
+        {
+            return None;
+        }
+
+        let address = format!(
+            "{}{}",
+            namespace,
+            interpolate_pointers(&self.address, json)?
+        );
+        let value = match &self.value {
+            Some(pointer) => json.pointer(pointer)?.clone(),
+            None => json.clone(),
+        };
+        let value = WebSocketBridge::json_to_value(value);
+
+        Some(if self.event {
+            Message::Publish(PublishMessage {
+                address,
+                signal: Some(SignalType::Event),
+                value: Some(value),
+                payload: None,
+                samples: None,
+                rate: None,
+                id: None,
+                phase: None,
+                timestamp: None,
+                timeline: None,
+            })
+        } else {
+            Message::Set(SetMessage {
+                address,
+                value,
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            })
+        })
+    }
+}
+
+/// Fill `{{/json/pointer}}` placeholders in `template` from `json`
+fn interpolate_pointers(template: &str, json: &serde_json::Value) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = start + rest[start..].find("}}")?;
+        out.push_str(&rest[..start]);
+        match json.pointer(rest[start + 2..end].trim())? {
+            serde_json::Value::String(s) => out.push_str(s),
+            serde_json::Value::Number(n) => out.push_str(&n.to_string()),
+            serde_json::Value::Bool(b) => out.push_str(&b.to_string()),
+            _ => return None,
+        }
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// A WebSocket data frame as seen by a [`WsCodec`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsFrame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<WsFrame> for WsMessage {
+    fn from(frame: WsFrame) -> Self {
+        match frame {
+            WsFrame::Text(text) => WsMessage::Text(text),
+            WsFrame::Binary(data) => WsMessage::Binary(data),
+        }
+    }
+}
+
+/// Converts between WebSocket frames and CLASP messages
+///
+/// Implement this for APIs a [`WsJsonMapping`] can't describe, such as
+/// binary protocols, and install it with [`WebSocketBridge::with_codec`].
+pub trait WsCodec: Send + Sync {
+    /// Convert an incoming frame; `namespace` is the bridge's CLASP namespace
+    fn decode(&self, frame: &WsFrame, namespace: &str) -> Option<Message>;
+
+    /// Convert an outgoing CLASP message
+    fn encode(&self, msg: &Message) -> Option<WsFrame>;
+}
+
+/// Codec for the built-in formats
+struct FormatCodec(WsMessageFormat);
+
+impl WsCodec for FormatCodec {
+    fn decode(&self, frame: &WsFrame, namespace: &str) -> Option<Message> {
+        WebSocketBridge::parse_message(frame, self.0, namespace)
+    }
+
+    fn encode(&self, msg: &Message) -> Option<WsFrame> {
+        WebSocketBridge::message_to_ws(msg, self.0)
+    }
+}
+
+/// Codec for [`WsMessageFormat::Mapped`]
+struct MappingCodec {
+    mapping: WsJsonMapping,
+    outgoing: Vec<Pattern>,
+}
+
+impl MappingCodec {
+    fn new(mapping: WsJsonMapping) -> Result<Self> {
+        let outgoing = mapping
+            .outgoing
+            .iter()
+            .map(|rule| {
+                Pattern::compile(&rule.pattern).map_err(|e| {
+                    BridgeError::Other(format!("Invalid mapping pattern '{}': {}", rule.pattern, e))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { mapping, outgoing })
+    }
+}
+
+impl WsCodec for MappingCodec {
+    fn decode(&self, frame: &WsFrame, namespace: &str) -> Option<Message> {
+        let json: serde_json::Value = match frame {
+            WsFrame::Text(text) => serde_json::from_str(text).ok()?,
+            WsFrame::Binary(data) => serde_json::from_slice(data).ok()?,
+        };
+        let msg = self
+            .mapping
+            .incoming
+            .iter()
+            .find_map(|rule| rule.apply(&json, namespace));
+        if msg.is_none() {
+            debug!("No mapping rule matched incoming WebSocket message");
+        }
+        msg
+    }
+
+    fn encode(&self, msg: &Message) -> Option<WsFrame> {
+        let (address, value, kind) = match msg {
+            Message::Set(set) => (&set.address, &set.value, "set"),
+            Message::Publish(pub_msg) => (&pub_msg.address, pub_msg.value.as_ref()?, "event"),
+            _ => return None,
+        };
+        let rule = self
+            .mapping
+            .outgoing
+            .iter()
+            .zip(&self.outgoing)
+            .find(|(_, pattern)| pattern.matches(address))?
+            .0;
+
+        let vars = [
+            ("address", serde_json::Value::String(address.clone())),
+            ("value", serde_json::to_value(value).ok()?),
+            ("type", serde_json::Value::String(kind.to_string())),
+        ];
+        Some(WsFrame::Text(
+            render_json_template(&rule.template, &vars).to_string(),
+        ))
+    }
 }
 
 /// WebSocket bridge mode
@@ -73,6 +298,9 @@ pub struct WebSocketBridgeConfig {
     /// CLASP namespace prefix for incoming messages
     #[serde(default = "default_namespace")]
     pub namespace: String,
+    /// Mapping used by the `mapped` format
+    #[serde(default)]
+    pub mapping: Option<WsJsonMapping>,
 }
 
 fn default_true() -> bool {
@@ -103,6 +331,7 @@ impl Default for WebSocketBridgeConfig {
             reconnect_delay_secs: 5,
             headers: HashMap::new(),
             namespace: "/ws".to_string(),
+            mapping: None,
         }
     }
 }
@@ -123,6 +352,7 @@ pub struct WebSocketBridge {
     running: Arc<Mutex<bool>>,
     send_tx: Option<mpsc::Sender<WsMessage>>,
     shutdown_tx: Option<mpsc::Sender<()>>,
+    codec: Option<Arc<dyn WsCodec>>,
 }
 
 impl WebSocketBridge {
@@ -141,14 +371,22 @@ impl WebSocketBridge {
             running: Arc::new(Mutex::new(false)),
             send_tx: None,
             shutdown_tx: None,
+            codec: None,
         }
     }
 
+    /// Use a custom codec instead of the configured format
+    pub fn with_codec(mut self, codec: impl WsCodec + 'static) -> Self {
+        self.codec = Some(Arc::new(codec));
+        self
+    }
+
     /// Parse incoming WebSocket message to CLASP
-    fn parse_message(msg: &WsMessage, format: WsMessageFormat, prefix: &str) -> Option<Message> {
+    fn parse_message(msg: &WsFrame, format: WsMessageFormat, prefix: &str) -> Option<Message> {
         match msg {
-            WsMessage::Text(text) => match format {
-                WsMessageFormat::Json | WsMessageFormat::Raw => {
+            WsFrame::Text(text) => match format {
+                // Mapped messages go through MappingCodec instead
+                WsMessageFormat::Json | WsMessageFormat::Raw | WsMessageFormat::Mapped => {
                     // Try to parse as JSON
                     if let Ok(json) = serde_json::from_str::<serde_json::Value>(text) {
                         let address = json
@@ -169,6 +407,8 @@ impl WebSocketBridge {
                             revision: None,
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         }))
                     } else {
                         // Plain text message
@@ -178,6 +418,8 @@ impl WebSocketBridge {
                             revision: None,
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         }))
                     }
                 }
@@ -196,6 +438,8 @@ impl WebSocketBridge {
                                 revision: None,
                                 lock: false,
                                 unlock: false,
+                                op: None,
+                                correlation_id: None,
                             }))
                         } else {
                             // Wrap text as a message for the namespace
@@ -205,6 +449,8 @@ impl WebSocketBridge {
                                 revision: None,
                                 lock: false,
                                 unlock: false,
+                                op: None,
+                                correlation_id: None,
                             }))
                         }
                     } else {
@@ -215,11 +461,22 @@ impl WebSocketBridge {
                             revision: None,
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         }))
                     }
                 }
+                WsMessageFormat::Binary => Some(Message::Set(SetMessage {
+                    address: format!("{}/binary", prefix),
+                    value: Value::Bytes(text.clone().into_bytes()),
+                    revision: None,
+                    lock: false,
+                    unlock: false,
+                    op: None,
+                    correlation_id: None,
+                })),
             },
-            WsMessage::Binary(data) => match format {
+            WsFrame::Binary(data) => match format {
                 WsMessageFormat::MsgPack => {
                     // Try to decode as CLASP message
                     if let Ok((msg, _)) = clasp_core::codec::decode(data) {
@@ -232,21 +489,37 @@ impl WebSocketBridge {
                             revision: None,
                             lock: false,
                             unlock: false,
+                            op: None,
+                            correlation_id: None,
                         }))
                     }
                 }
-                WsMessageFormat::Raw | WsMessageFormat::Json => Some(Message::Set(SetMessage {
+                WsMessageFormat::Raw
+                | WsMessageFormat::Json
+                | WsMessageFormat::Binary
+                | WsMessageFormat::Mapped => Some(Message::Set(SetMessage {
                     address: format!("{}/binary", prefix),
                     value: Value::Bytes(data.clone()),
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 })),
             },
-            _ => None,
         }
     }
 
+    /// Decode a data frame with `codec`, ignoring control frames
+    fn decode_ws(codec: &dyn WsCodec, msg: &WsMessage, namespace: &str) -> Option<Message> {
+        let frame = match msg {
+            WsMessage::Text(text) => WsFrame::Text(text.clone()),
+            WsMessage::Binary(data) => WsFrame::Binary(data.clone()),
+            _ => return None,
+        };
+        codec.decode(&frame, namespace)
+    }
+
     /// Convert JSON value to CLASP Value
     fn json_to_value(json: serde_json::Value) -> Value {
         match json {
@@ -276,7 +549,7 @@ impl WebSocketBridge {
     }
 
     /// Convert CLASP message to WebSocket message
-    fn message_to_ws(msg: &Message, format: WsMessageFormat) -> Option<WsMessage> {
+    fn message_to_ws(msg: &Message, format: WsMessageFormat) -> Option<WsFrame> {
         let (address, value) = match msg {
             Message::Set(set) => (Some(&set.address), Some(&set.value)),
             Message::Publish(pub_msg) => (Some(&pub_msg.address), pub_msg.value.as_ref()),
@@ -284,16 +557,16 @@ impl WebSocketBridge {
         };
 
         match format {
-            WsMessageFormat::Json => {
+            WsMessageFormat::Json | WsMessageFormat::Mapped => {
                 let json = serde_json::json!({
                     "address": address,
                     "value": value,
                 });
-                Some(WsMessage::Text(json.to_string()))
+                Some(WsFrame::Text(json.to_string()))
             }
             WsMessageFormat::MsgPack => {
                 if let Ok(encoded) = clasp_core::codec::encode(msg) {
-                    Some(WsMessage::Binary(encoded.to_vec()))
+                    Some(WsFrame::Binary(encoded.to_vec()))
                 } else {
                     None
                 }
@@ -301,24 +574,29 @@ impl WebSocketBridge {
             WsMessageFormat::Raw => {
                 if let Some(val) = value {
                     match val {
-                        Value::String(s) => Some(WsMessage::Text(s.clone())),
-                        Value::Bytes(b) => Some(WsMessage::Binary(b.clone())),
+                        Value::String(s) => Some(WsFrame::Text(s.clone())),
+                        Value::Bytes(b) => Some(WsFrame::Binary(b.clone())),
                         _ => {
                             let json = serde_json::to_string(val).ok()?;
-                            Some(WsMessage::Text(json))
+                            Some(WsFrame::Text(json))
                         }
                     }
                 } else {
                     None
                 }
             }
+            WsMessageFormat::Binary => match value? {
+                Value::Bytes(b) => Some(WsFrame::Binary(b.clone())),
+                Value::String(s) => Some(WsFrame::Binary(s.clone().into_bytes())),
+                _ => None,
+            },
         }
     }
 
     /// Run client mode
     async fn run_client(
         url: String,
-        format: WsMessageFormat,
+        codec: Arc<dyn WsCodec>,
         namespace: String,
         auto_reconnect: bool,
         reconnect_delay: u32,
@@ -367,7 +645,7 @@ impl WebSocketBridge {
                                                 }
                                             }
                                             _ => {
-                                                if let Some(clasp_msg) = Self::parse_message(&ws_msg, format, &namespace) {
+                                                if let Some(clasp_msg) = Self::decode_ws(&*codec, &ws_msg, &namespace) {
                                                     let _ = event_tx.send(BridgeEvent::ToClasp(clasp_msg)).await;
                                                 }
                                             }
@@ -450,7 +728,7 @@ impl WebSocketBridge {
     /// Run server mode
     async fn run_server(
         addr: SocketAddr,
-        format: WsMessageFormat,
+        codec: Arc<dyn WsCodec>,
         namespace: String,
         ping_interval_secs: u32,
         event_tx: mpsc::Sender<BridgeEvent>,
@@ -489,7 +767,7 @@ impl WebSocketBridge {
                             let client_id = next_client_id.fetch_add(1, Ordering::SeqCst);
                             info!("WebSocket client {} connected: {}", client_id, peer_addr);
 
-                            let format = format;
+                            let codec = codec.clone();
                             let namespace = namespace.clone();
                             let event_tx = event_tx.clone();
                             let clients = clients.clone();
@@ -530,7 +808,7 @@ impl WebSocketBridge {
                                                                 }
                                                             }
                                                             _ => {
-                                                                if let Some(clasp_msg) = Self::parse_message(&ws_msg, format, &namespace) {
+                                                                if let Some(clasp_msg) = Self::decode_ws(&*codec, &ws_msg, &namespace) {
                                                                     let _ = event_tx.send(BridgeEvent::ToClasp(clasp_msg)).await;
                                                                 }
                                                             }
@@ -624,6 +902,18 @@ impl Bridge for WebSocketBridge {
             return Err(BridgeError::Other("Bridge already running".to_string()));
         }
 
+        let codec = match (&self.codec, self.ws_config.format) {
+            (Some(codec), _) => codec.clone(),
+            (None, WsMessageFormat::Mapped) => {
+                let mapping = self.ws_config.mapping.clone().ok_or_else(|| {
+                    BridgeError::Other("Mapped format requires a mapping".to_string())
+                })?;
+                Arc::new(MappingCodec::new(mapping)?) as Arc<dyn WsCodec>
+            }
+            (None, format) => Arc::new(FormatCodec(format)),
+        };
+        self.codec = Some(codec.clone());
+
         let (event_tx, event_rx) = mpsc::channel(100);
         let (send_tx, send_rx) = mpsc::channel(100);
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
@@ -638,7 +928,7 @@ impl Bridge for WebSocketBridge {
             WsMode::Client => {
                 tokio::spawn(Self::run_client(
                     ws_config.url,
-                    ws_config.format,
+                    codec,
                     ws_config.namespace,
                     ws_config.auto_reconnect,
                     ws_config.reconnect_delay_secs,
@@ -657,7 +947,7 @@ impl Bridge for WebSocketBridge {
 
                 tokio::spawn(Self::run_server(
                     addr,
-                    ws_config.format,
+                    codec,
                     ws_config.namespace,
                     ws_config.ping_interval_secs,
                     event_tx,
@@ -688,9 +978,10 @@ impl Bridge for WebSocketBridge {
             .as_ref()
             .ok_or_else(|| BridgeError::Other("Not connected".to_string()))?;
 
-        if let Some(ws_msg) = Self::message_to_ws(&msg, self.ws_config.format) {
+        let frame = self.codec.as_ref().and_then(|codec| codec.encode(&msg));
+        if let Some(frame) = frame {
             send_tx
-                .send(ws_msg)
+                .send(frame.into())
                 .await
                 .map_err(|e| BridgeError::Other(format!("WebSocket send failed: {}", e)))?;
         }
@@ -723,18 +1014,133 @@ mod tests {
         let prefix = "/ws";
 
         // JSON text message
-        let ws_msg = WsMessage::Text(r#"{"address": "/test", "value": 42}"#.to_string());
+        let ws_msg = WsFrame::Text(r#"{"address": "/test", "value": 42}"#.to_string());
         let clasp = WebSocketBridge::parse_message(&ws_msg, WsMessageFormat::Json, prefix);
         assert!(clasp.is_some());
 
         // Plain text
-        let ws_msg = WsMessage::Text("hello".to_string());
+        let ws_msg = WsFrame::Text("hello".to_string());
         let clasp = WebSocketBridge::parse_message(&ws_msg, WsMessageFormat::Json, prefix);
         assert!(clasp.is_some());
 
         // Binary
-        let ws_msg = WsMessage::Binary(vec![1, 2, 3]);
+        let ws_msg = WsFrame::Binary(vec![1, 2, 3]);
         let clasp = WebSocketBridge::parse_message(&ws_msg, WsMessageFormat::Raw, prefix);
         assert!(clasp.is_some());
     }
+
+    #[test]
+    fn test_binary_passthrough() {
+        let codec = FormatCodec(WsMessageFormat::Binary);
+
+        match codec.decode(&WsFrame::Text("hi".to_string()), "/ws") {
+            Some(Message::Set(set)) => {
+                assert_eq!(set.address, "/ws/binary");
+                assert_eq!(set.value, Value::Bytes(b"hi".to_vec()));
+            }
+            other => panic!("Expected SET, got {:?}", other),
+        }
+
+        let msg = Message::Set(SetMessage {
+            address: "/ws/binary".to_string(),
+            value: Value::Bytes(vec![0, 255]),
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        });
+        assert_eq!(codec.encode(&msg), Some(WsFrame::Binary(vec![0, 255])));
+    }
+
+    fn obs_mapping() -> MappingCodec {
+        let mapping: WsJsonMapping = serde_json::from_value(serde_json::json!({
+            "incoming": [
+                {
+                    "when": { "/op": 5 },
+                    "address": "/obs/{{/d/eventType}}",
+                    "value": "/d/eventData",
+                    "event": true
+                },
+                { "address": "/obs/op/{{/op}}" }
+            ],
+            "outgoing": [{
+                "pattern": "/ws/obs/scene",
+                "template": {
+                    "op": 6,
+                    "d": {
+                        "requestType": "SetCurrentProgramScene",
+                        "requestData": { "sceneName": "{{value}}" }
+                    }
+                }
+            }]
+        }))
+        .unwrap();
+        MappingCodec::new(mapping).unwrap()
+    }
+
+    #[test]
+    fn test_mapping_incoming() {
+        let codec = obs_mapping();
+
+        let event = r#"{"op":5,"d":{"eventType":"CurrentProgramSceneChanged","eventData":{"sceneName":"Live"}}}"#;
+        match codec.decode(&WsFrame::Text(event.to_string()), "/ws") {
+            Some(Message::Publish(publish)) => {
+                assert_eq!(publish.address, "/ws/obs/CurrentProgramSceneChanged");
+                assert_eq!(publish.signal, Some(SignalType::Event));
+                let Some(Value::Map(data)) = publish.value else {
+                    panic!("Expected map value");
+                };
+                assert_eq!(data["sceneName"], Value::String("Live".to_string()));
+            }
+            other => panic!("Expected PUBLISH, got {:?}", other),
+        }
+
+        // Falls through to the second rule
+        match codec.decode(&WsFrame::Text(r#"{"op":2,"d":{}}"#.to_string()), "/ws") {
+            Some(Message::Set(set)) => assert_eq!(set.address, "/ws/obs/op/2"),
+            other => panic!("Expected SET, got {:?}", other),
+        }
+
+        // Unresolvable pointer and non-JSON are dropped
+        assert!(codec
+            .decode(&WsFrame::Text("{}".to_string()), "/ws")
+            .is_none());
+        assert!(codec
+            .decode(&WsFrame::Text("hello".to_string()), "/ws")
+            .is_none());
+    }
+
+    #[test]
+    fn test_mapping_outgoing() {
+        let codec = obs_mapping();
+        let set = |address: &str| {
+            Message::Set(SetMessage {
+                address: address.to_string(),
+                value: Value::String("Intermission".to_string()),
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            })
+        };
+
+        let Some(WsFrame::Text(text)) = codec.encode(&set("/ws/obs/scene")) else {
+            panic!("Expected text frame");
+        };
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "op": 6,
+                "d": {
+                    "requestType": "SetCurrentProgramScene",
+                    "requestData": { "sceneName": "Intermission" }
+                }
+            })
+        );
+
+        assert!(codec.encode(&set("/ws/obs/volume")).is_none());
+    }
 }
@@ -0,0 +1,104 @@
+//! DMX Bridge Tests
+//!
+//! Drives the DMX bridge's Art-Net loopback interface and checks the
+//! frames arriving on a local UDP socket. No hardware required.
+
+use clasp_bridge::{Bridge, BridgeEvent, DmxBridge, DmxBridgeConfig, DmxInterfaceType};
+use clasp_core::{Message, SetMessage, Value};
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+fn set(address: &str, value: i64) -> Message {
+    Message::Set(SetMessage {
+        address: address.to_string(),
+        value: Value::Int(value),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
+/// Receive ArtDmx packets until one satisfies `check`
+fn wait_for_frame(socket: &UdpSocket, check: impl Fn(&[u8]) -> bool) -> Vec<u8> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut buf = [0u8; 1024];
+    while Instant::now() < deadline {
+        let Ok(len) = socket.recv(&mut buf) else {
+            continue;
+        };
+        let packet = &buf[..len];
+        assert_eq!(&packet[..8], b"Art-Net\0", "Not an Art-Net packet");
+        if check(packet) {
+            return packet.to_vec();
+        }
+    }
+    panic!("No matching Art-Net frame received");
+}
+
+/// Test: SETs on the bridge's universe come out as Art-Net frames
+#[tokio::test]
+async fn test_artnet_loopback_output() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+
+    let mut bridge = DmxBridge::new(DmxBridgeConfig {
+        port: Some(receiver.local_addr().unwrap().to_string()),
+        interface_type: DmxInterfaceType::ArtNetLoopback,
+        universe: 3,
+        ..Default::default()
+    });
+    let mut events = bridge.start().await.unwrap();
+    assert!(matches!(events.recv().await, Some(BridgeEvent::Connected)));
+
+    bridge.send(set("/dmx/3/1", 255)).await.unwrap();
+    bridge.send(set("/dmx/3/512", 64)).await.unwrap();
+    // Other universes are ignored
+    bridge.send(set("/dmx/4/2", 128)).await.unwrap();
+
+    let packet = wait_for_frame(&receiver, |p| p[18] == 255 && p[18 + 511] == 64);
+    assert_eq!(&packet[14..16], &[3, 0], "Wrong universe");
+    assert_eq!(packet[19], 0);
+
+    // Frames keep flowing at the refresh rate, with rolling sequence numbers
+    let next = wait_for_frame(&receiver, |p| p[12] != packet[12]);
+    assert_eq!(next[18], 255);
+
+    bridge.stop().await.unwrap();
+}
+
+/// Test: the refresh rate can be changed while running
+#[tokio::test]
+async fn test_refresh_rate_control() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+
+    let mut bridge = DmxBridge::new(DmxBridgeConfig {
+        port: Some(receiver.local_addr().unwrap().to_string()),
+        interface_type: DmxInterfaceType::ArtNetLoopback,
+        refresh_rate: 40.0,
+        ..Default::default()
+    });
+    let _events = bridge.start().await.unwrap();
+
+    bridge.set_refresh_rate(5.0);
+    // Drain what was sent at the old rate
+    std::thread::sleep(Duration::from_millis(300));
+    while receiver.recv(&mut [0u8; 1024]).is_ok() {}
+
+    let start = Instant::now();
+    let mut frames = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        if receiver.recv(&mut [0u8; 1024]).is_ok() {
+            frames += 1;
+        }
+    }
+    assert!((3..=8).contains(&frames), "{} frames in 1s at 5 Hz", frames);
+
+    bridge.stop().await.unwrap();
+}
@@ -0,0 +1,226 @@
+//! HTTP Webhook Tests
+//!
+//! Runs the HTTP bridge in webhook mode against a local receiver and checks
+//! pattern matching, templated bodies, signing and retries.
+
+use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+use clasp_bridge::http::WEBHOOK_SIGNATURE_HEADER;
+use clasp_bridge::{Bridge, BridgeEvent, HttpBridge, HttpBridgeConfig, HttpMode, WebhookConfig};
+use clasp_core::{Message, PublishMessage, SetMessage, SignalType, Value};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// A delivery seen by the receiver
+struct Delivery {
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct Receiver {
+    deliveries: mpsc::Sender<Delivery>,
+    /// Requests to answer with 500 before accepting
+    failures: Arc<AtomicU32>,
+}
+
+async fn receive(
+    State(receiver): State<Receiver>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> StatusCode {
+    let _ = receiver
+        .deliveries
+        .send(Delivery {
+            headers,
+            body: body.to_vec(),
+        })
+        .await;
+
+    let failing = receiver
+        .failures
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok();
+    if failing {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::OK
+    }
+}
+
+/// Start a receiver that fails the first `failures` requests
+async fn start_receiver(failures: u32) -> (String, mpsc::Receiver<Delivery>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel(16);
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(Receiver {
+            deliveries: tx,
+            failures: Arc::new(AtomicU32::new(failures)),
+        });
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+    (url, rx)
+}
+
+async fn start_bridge(webhooks: Vec<WebhookConfig>) -> (HttpBridge, mpsc::Receiver<BridgeEvent>) {
+    let mut bridge = HttpBridge::new(HttpBridgeConfig {
+        mode: HttpMode::Webhook,
+        webhooks,
+        ..Default::default()
+    });
+    let mut events = bridge.start().await.unwrap();
+    assert!(matches!(events.recv().await, Some(BridgeEvent::Connected)));
+    (bridge, events)
+}
+
+async fn next_delivery(deliveries: &mut mpsc::Receiver<Delivery>) -> Delivery {
+    timeout(Duration::from_secs(5), deliveries.recv())
+        .await
+        .expect("Timed out waiting for webhook")
+        .expect("Receiver closed")
+}
+
+fn set(address: &str, value: Value) -> Message {
+    Message::Set(SetMessage {
+        address: address.to_string(),
+        value,
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
+/// Test: matching signals are posted with the templated body and a signature
+#[tokio::test]
+async fn test_webhook_templated_and_signed() {
+    let (url, mut deliveries) = start_receiver(0).await;
+    let mut webhook = WebhookConfig::new(url, "/lights/**");
+    webhook.secret = Some("s3cret".to_string());
+    webhook.template = Some(serde_json::json!({
+        "text": "{{address}} -> {{value}}",
+        "level": "{{value}}",
+        "kind": "{{type}}",
+        "venue": "main-hall"
+    }));
+    webhook
+        .headers
+        .insert("X-Venue".to_string(), "main-hall".to_string());
+    let (mut bridge, _events) = start_bridge(vec![webhook.clone()]).await;
+
+    // Not matching the pattern, so never delivered
+    bridge
+        .send(set("/audio/master", Value::Float(1.0)))
+        .await
+        .unwrap();
+    bridge
+        .send(set("/lights/1/dimmer", Value::Float(0.5)))
+        .await
+        .unwrap();
+
+    let delivery = next_delivery(&mut deliveries).await;
+    let body: serde_json::Value = serde_json::from_slice(&delivery.body).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "text": "/lights/1/dimmer -> 0.5",
+            "level": 0.5,
+            "kind": "set",
+            "venue": "main-hall"
+        })
+    );
+    assert_eq!(delivery.headers["x-venue"], "main-hall");
+    assert_eq!(delivery.headers["content-type"], "application/json");
+    assert_eq!(
+        delivery.headers[WEBHOOK_SIGNATURE_HEADER].to_str().unwrap(),
+        webhook.signature(&delivery.body).unwrap()
+    );
+
+    bridge
+        .send(Message::Publish(PublishMessage {
+            address: "/lights/go".to_string(),
+            signal: Some(SignalType::Event),
+            value: Some(Value::Bool(true)),
+            payload: None,
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        }))
+        .await
+        .unwrap();
+    let body: serde_json::Value =
+        serde_json::from_slice(&next_delivery(&mut deliveries).await.body).unwrap();
+    assert_eq!(body["kind"], "event");
+    assert_eq!(body["level"], true);
+
+    bridge.stop().await.unwrap();
+}
+
+/// Test: server errors are retried until the receiver accepts
+#[tokio::test]
+async fn test_webhook_retries_server_errors() {
+    let (url, mut deliveries) = start_receiver(2).await;
+    let mut webhook = WebhookConfig::new(url, "/**");
+    webhook.retry_backoff_ms = 10;
+    let (mut bridge, mut events) = start_bridge(vec![webhook]).await;
+
+    bridge.send(set("/scene", Value::Int(3))).await.unwrap();
+
+    for _ in 0..3 {
+        let body: serde_json::Value =
+            serde_json::from_slice(&next_delivery(&mut deliveries).await.body).unwrap();
+        assert_eq!(body["address"], "/scene");
+        assert_eq!(body["value"], 3);
+        assert!(body["timestamp"].as_u64().unwrap() > 0);
+    }
+
+    // Accepted on the third attempt, so nothing is reported
+    assert!(timeout(Duration::from_millis(200), events.recv())
+        .await
+        .is_err());
+
+    bridge.stop().await.unwrap();
+}
+
+/// Test: a delivery that keeps failing is reported once retries run out
+#[tokio::test]
+async fn test_webhook_gives_up_after_retries() {
+    let (url, mut deliveries) = start_receiver(u32::MAX).await;
+    let mut webhook = WebhookConfig::new(url, "/**");
+    webhook.max_retries = 1;
+    webhook.retry_backoff_ms = 10;
+    let (mut bridge, mut events) = start_bridge(vec![webhook]).await;
+
+    bridge.send(set("/scene", Value::Int(3))).await.unwrap();
+    next_delivery(&mut deliveries).await;
+    next_delivery(&mut deliveries).await;
+
+    match timeout(Duration::from_secs(5), events.recv())
+        .await
+        .unwrap()
+    {
+        Some(BridgeEvent::Error(e)) => assert!(e.contains("after 2 attempt(s)"), "{}", e),
+        other => panic!("Expected error event, got {:?}", other),
+    }
+
+    bridge.stop().await.unwrap();
+}
+
+/// Test: an invalid pattern is rejected when the bridge starts
+#[tokio::test]
+async fn test_webhook_invalid_pattern() {
+    let mut bridge = HttpBridge::new(HttpBridgeConfig {
+        mode: HttpMode::Webhook,
+        webhooks: vec![WebhookConfig::new("http://127.0.0.1:1/hook", "lights")],
+        ..Default::default()
+    });
+    assert!(bridge.start().await.is_err());
+}
@@ -5,6 +5,7 @@
 //! - CLASP -> MQTT message translation
 //! - Topic -> Address mapping
 //! - QoS level mapping
+//! - Birth messages and retained params
 //!
 //! Note: These tests require an MQTT broker. They will skip if:
 //! - No broker is available at localhost:1883
@@ -14,7 +15,7 @@
 //!   docker run -d -p 1883:1883 eclipse-mosquitto:latest
 //!   CLASP_TEST_BROKERS=1 cargo test --test mqtt_tests
 
-use clasp_bridge::mqtt::{MqttBridge, MqttBridgeConfig};
+use clasp_bridge::mqtt::{MqttBridge, MqttBridgeConfig, MqttStatusMessage};
 use clasp_bridge::{Bridge, BridgeEvent};
 use clasp_client::ClaspBuilder;
 use clasp_core::Value;
@@ -61,6 +62,7 @@ impl TestEnv {
             qos: 0,
             keep_alive_secs: 60,
             namespace: "/mqtt".to_string(),
+            ..Default::default()
         };
 
         let mut bridge = MqttBridge::new(config);
@@ -268,3 +270,61 @@ async fn test_mqtt_qos_mapping() {
     let _rx1 = bridge1.start().await.expect("Failed to start QoS 1 bridge");
     bridge1.stop().await.expect("Failed to stop QoS 1 bridge");
 }
+
+#[tokio::test]
+async fn test_mqtt_birth_and_retained_params() {
+    if !is_broker_available() {
+        eprintln!("Skipping test: MQTT broker not available (set CLASP_TEST_BROKERS=1 or start mosquitto)");
+        return;
+    }
+
+    let suffix = uuid::Uuid::new_v4().to_string();
+    let status_topic = format!("test/status/{}", suffix);
+    let mut bridge = MqttBridge::new(MqttBridgeConfig {
+        subscribe_topics: vec![],
+        birth: Some(MqttStatusMessage::new(&status_topic, "online")),
+        last_will: Some(MqttStatusMessage::new(&status_topic, "offline")),
+        ..Default::default()
+    });
+    let _rx = bridge.start().await.expect("Failed to start bridge");
+    sleep(Duration::from_millis(500)).await;
+
+    bridge
+        .send(clasp_core::Message::Set(clasp_core::SetMessage {
+            address: format!("/mqtt/test/retained/{}", suffix),
+            value: Value::Float(0.5),
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        }))
+        .await
+        .expect("Bridge send failed");
+    sleep(Duration::from_millis(200)).await;
+
+    // A subscriber that arrives later still sees the birth and the param
+    let mut mqttoptions = MqttOptions::new("test-late-subscriber", "localhost", 1883);
+    mqttoptions.set_keep_alive(Duration::from_secs(5));
+    let (mqtt_client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
+    mqtt_client
+        .subscribe(format!("test/+/{}", suffix), QoS::AtLeastOnce)
+        .await
+        .expect("MQTT subscribe failed");
+
+    let mut retained = Vec::new();
+    let _ = tokio::time::timeout(Duration::from_secs(2), async {
+        while retained.len() < 2 {
+            if let Ok(Event::Incoming(Packet::Publish(publish))) = eventloop.poll().await {
+                if publish.retain {
+                    retained.push(String::from_utf8_lossy(&publish.payload).to_string());
+                }
+            }
+        }
+    })
+    .await;
+    retained.sort();
+
+    bridge.stop().await.expect("Failed to stop bridge");
+    assert_eq!(retained, vec!["0.5".to_string(), "online".to_string()]);
+}
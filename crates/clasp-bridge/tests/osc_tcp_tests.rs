@@ -0,0 +1,203 @@
+//! OSC over TCP Tests
+//!
+//! Runs the OSC bridge over localhost TCP with both framings, as a server
+//! that peers connect to and as a client of a remote such as a console.
+
+use clasp_bridge::{
+    Bridge, BridgeEvent, OscBridge, OscBridgeConfig, OscFrameDecoder, OscFraming, OscTransport,
+};
+use clasp_core::{Message, SetMessage, Value};
+use rosc::{OscMessage, OscPacket, OscType};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+async fn find_available_tcp_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn osc_frame(framing: OscFraming, address: &str, value: f32) -> Vec<u8> {
+    let packet = rosc::encoder::encode(&OscPacket::Message(OscMessage {
+        addr: address.to_string(),
+        args: vec![OscType::Float(value)],
+    }))
+    .unwrap();
+    framing.encode(&packet)
+}
+
+fn set(address: &str, value: f64) -> Message {
+    Message::Set(SetMessage {
+        address: address.to_string(),
+        value: Value::Float(value),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
+async fn next_event(events: &mut mpsc::Receiver<BridgeEvent>) -> BridgeEvent {
+    timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("Timed out waiting for bridge event")
+        .expect("Bridge event channel closed")
+}
+
+/// Read from `stream` until one whole OSC message arrives
+async fn read_osc(stream: &mut TcpStream, framing: OscFraming) -> OscMessage {
+    let mut decoder = OscFrameDecoder::new(framing);
+    let mut buf = [0u8; 1024];
+    loop {
+        let len = timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .expect("Timed out waiting for OSC")
+            .unwrap();
+        assert!(len > 0, "Connection closed");
+        if let Some(packet) = decoder.push(&buf[..len]).unwrap().into_iter().next() {
+            match rosc::decoder::decode_udp(&packet).unwrap().1 {
+                OscPacket::Message(msg) => return msg,
+                OscPacket::Bundle(_) => panic!("Expected message"),
+            }
+        }
+    }
+}
+
+async fn assert_receives_set(events: &mut mpsc::Receiver<BridgeEvent>, address: &str, value: f64) {
+    match next_event(events).await {
+        BridgeEvent::ToClasp(Message::Set(set)) => {
+            assert_eq!(set.address, address);
+            assert_eq!(set.value, Value::Float(value));
+        }
+        other => panic!("Expected SET, got {:?}", other),
+    }
+}
+
+/// Test: peers connect to the bridge with packet-length framing
+#[tokio::test]
+async fn test_tcp_server_packet_length() {
+    let framing = OscFraming::PacketLength;
+    let addr = format!("127.0.0.1:{}", find_available_tcp_port().await);
+    let mut bridge = OscBridge::new(OscBridgeConfig {
+        bind_addr: addr.clone(),
+        transport: OscTransport::Tcp(framing),
+        ..Default::default()
+    });
+    let mut events = bridge.start().await.unwrap();
+    assert!(matches!(
+        next_event(&mut events).await,
+        BridgeEvent::Connected
+    ));
+
+    let mut peer = TcpStream::connect(&addr).await.unwrap();
+    peer.write_all(&osc_frame(framing, "/eos/fader/1", 0.5))
+        .await
+        .unwrap();
+    assert_receives_set(&mut events, "/osc/eos/fader/1", 0.5).await;
+
+    bridge.send(set("/osc/eos/chan/1", 0.25)).await.unwrap();
+    let msg = read_osc(&mut peer, framing).await;
+    assert_eq!(msg.addr, "/eos/chan/1");
+    assert_eq!(msg.args, vec![OscType::Double(0.25)]);
+
+    bridge.stop().await.unwrap();
+    assert!(!bridge.is_running());
+}
+
+/// Test: the bridge connects out to a remote using SLIP framing
+#[tokio::test]
+async fn test_tcp_client_slip() {
+    let framing = OscFraming::Slip;
+    let console = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut bridge = OscBridge::new(OscBridgeConfig {
+        remote_addr: Some(console.local_addr().unwrap().to_string()),
+        transport: OscTransport::Tcp(framing),
+        ..Default::default()
+    });
+    let mut events = bridge.start().await.unwrap();
+    let (mut stream, _) = console.accept().await.unwrap();
+    assert!(matches!(
+        next_event(&mut events).await,
+        BridgeEvent::Connected
+    ));
+
+    // Two packets in one write, the second with bytes SLIP has to escape
+    let mut frames = osc_frame(framing, "/eos/out/cmd", 1.0);
+    frames.extend(osc_frame(
+        framing,
+        "/eos/out/level",
+        f32::from_bits(0x3F00_C0DB),
+    ));
+    stream.write_all(&frames).await.unwrap();
+    assert_receives_set(&mut events, "/osc/eos/out/cmd", 1.0).await;
+    assert_receives_set(
+        &mut events,
+        "/osc/eos/out/level",
+        f32::from_bits(0x3F00_C0DB) as f64,
+    )
+    .await;
+
+    bridge.send(set("/osc/eos/key/go", 1.0)).await.unwrap();
+    let msg = read_osc(&mut stream, framing).await;
+    assert_eq!(msg.addr, "/eos/key/go");
+
+    bridge.stop().await.unwrap();
+}
+
+/// Test: a dropped remote connection is re-established
+#[tokio::test]
+async fn test_tcp_client_reconnects() {
+    let framing = OscFraming::PacketLength;
+    let console = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut bridge = OscBridge::new(OscBridgeConfig {
+        remote_addr: Some(console.local_addr().unwrap().to_string()),
+        transport: OscTransport::Tcp(framing),
+        ..Default::default()
+    });
+    let mut events = bridge.start().await.unwrap();
+    let (stream, _) = console.accept().await.unwrap();
+    assert!(matches!(
+        next_event(&mut events).await,
+        BridgeEvent::Connected
+    ));
+
+    drop(stream);
+    assert!(matches!(
+        next_event(&mut events).await,
+        BridgeEvent::Disconnected { reason: Some(_) }
+    ));
+
+    let (mut stream, _) = timeout(Duration::from_secs(5), console.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        next_event(&mut events).await,
+        BridgeEvent::Connected
+    ));
+    stream
+        .write_all(&osc_frame(framing, "/eos/fader/2", 0.75))
+        .await
+        .unwrap();
+    assert_receives_set(&mut events, "/osc/eos/fader/2", 0.75).await;
+
+    bridge.stop().await.unwrap();
+}
+
+/// Test: sending with no TCP peer connected fails instead of dropping
+#[tokio::test]
+async fn test_tcp_send_without_peer() {
+    let addr = format!("127.0.0.1:{}", find_available_tcp_port().await);
+    let mut bridge = OscBridge::new(OscBridgeConfig {
+        bind_addr: addr,
+        transport: OscTransport::Tcp(OscFraming::Slip),
+        ..Default::default()
+    });
+    let _events = bridge.start().await.unwrap();
+
+    assert!(bridge.send(set("/osc/eos/chan/1", 0.5)).await.is_err());
+    bridge.stop().await.unwrap();
+}
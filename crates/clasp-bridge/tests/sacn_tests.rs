@@ -0,0 +1,122 @@
+//! sACN Receiver Tests
+//!
+//! Sends E1.31 packets from several fake consoles to the bridge over
+//! localhost unicast and checks the merged output.
+
+#![cfg(feature = "sacn")]
+
+use clasp_bridge::{Bridge, BridgeEvent, SacnBridge, SacnBridgeConfig, SacnMode};
+use clasp_core::{Message, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// Build an E1.31 data packet for universe 1
+fn data_packet(cid: u8, priority: u8, sequence: u8, start_code: u8, slots: &[u8]) -> Vec<u8> {
+    let mut buf = vec![0u8; 126 + slots.len()];
+    buf[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+    buf[4..16].copy_from_slice(b"ASC-E1.17\0\0\0");
+    buf[18..22].copy_from_slice(&4u32.to_be_bytes());
+    buf[22..38].copy_from_slice(&[cid; 16]);
+    buf[40..44].copy_from_slice(&2u32.to_be_bytes());
+    let name = format!("Console {}", cid);
+    buf[44..44 + name.len()].copy_from_slice(name.as_bytes());
+    buf[108] = priority;
+    buf[111] = sequence;
+    buf[113..115].copy_from_slice(&1u16.to_be_bytes());
+    buf[117] = 0x02;
+    buf[118] = 0xa1;
+    buf[121..123].copy_from_slice(&1u16.to_be_bytes());
+    buf[123..125].copy_from_slice(&((slots.len() + 1) as u16).to_be_bytes());
+    buf[125] = start_code;
+    buf[126..].copy_from_slice(slots);
+    buf
+}
+
+async fn find_available_udp_port() -> u16 {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.local_addr().unwrap().port()
+}
+
+/// Collect SETs until `address` is set to `expected`, returning everything seen
+async fn wait_for(
+    events: &mut mpsc::Receiver<BridgeEvent>,
+    seen: &mut HashMap<String, Value>,
+    address: &str,
+    expected: impl Fn(&Value) -> bool,
+) {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(BridgeEvent::ToClasp(Message::Set(set))) = events.recv().await {
+                seen.insert(set.address.clone(), set.value.clone());
+                if set.address == address && expected(&set.value) {
+                    return;
+                }
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("Timed out waiting for {}", address));
+}
+
+/// Test: two consoles on one universe, with per-address priority
+#[tokio::test]
+async fn test_receiver_merges_sources() {
+    let port = find_available_udp_port().await;
+    let mut bridge = SacnBridge::new(SacnBridgeConfig {
+        mode: SacnMode::Receiver,
+        universes: vec![1],
+        bind_address: Some(format!("127.0.0.1:{}", port)),
+        ..Default::default()
+    });
+    let mut events = bridge.start().await.unwrap();
+    assert!(matches!(
+        timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap(),
+        Some(BridgeEvent::Connected)
+    ));
+
+    let console = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = format!("127.0.0.1:{}", port);
+    let mut seen = HashMap::new();
+
+    console
+        .send_to(&data_packet(1, 100, 0, 0x00, &[10, 20]), &target)
+        .await
+        .unwrap();
+    wait_for(
+        &mut events,
+        &mut seen,
+        "/sacn/1/source",
+        |v| matches!(v, Value::Map(m) if m["name"] == Value::String("Console 1".to_string())),
+    )
+    .await;
+    assert_eq!(seen["/sacn/1/1"], Value::Int(10));
+
+    // A higher priority console takes over
+    console
+        .send_to(&data_packet(2, 150, 0, 0x00, &[99, 98]), &target)
+        .await
+        .unwrap();
+    wait_for(&mut events, &mut seen, "/sacn/1/2", |v| {
+        *v == Value::Int(98)
+    })
+    .await;
+    assert_eq!(seen["/sacn/1/1"], Value::Int(99));
+
+    // ...except for channel 2, which it gives up with a 0xDD priority of 0
+    console
+        .send_to(&data_packet(2, 150, 1, 0xDD, &[150, 0]), &target)
+        .await
+        .unwrap();
+    wait_for(&mut events, &mut seen, "/sacn/1/2", |v| {
+        *v == Value::Int(20)
+    })
+    .await;
+    assert_eq!(seen["/sacn/1/1"], Value::Int(99));
+
+    bridge.stop().await.unwrap();
+}
@@ -3,9 +3,11 @@
 //! Tests cover:
 //! - WebSocket -> CLASP message translation (JSON text)
 //! - CLASP -> WebSocket JSON messages
+//! - JSON Pointer mappings and custom codecs
 
 use clasp_bridge::{
-    Bridge, BridgeEvent, WebSocketBridge, WebSocketBridgeConfig, WsMessageFormat, WsMode,
+    Bridge, BridgeEvent, WebSocketBridge, WebSocketBridgeConfig, WsCodec, WsFrame, WsJsonMapping,
+    WsMessageFormat, WsMode,
 };
 use clasp_core::{Message, SetMessage, Value};
 use clasp_test_utils::find_available_port;
@@ -120,6 +122,8 @@ async fn test_clasp_set_to_websocket_json() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     bridge
@@ -160,3 +164,170 @@ async fn test_clasp_set_to_websocket_json() {
     let int_val = value.as_i64().expect("Value is not an integer");
     assert_eq!(int_val, 7, "Wrong value in JSON: {}", int_val);
 }
+
+/// Start a server-mode bridge and connect a WebSocket client to it
+async fn connect_to_bridge(
+    mut bridge: WebSocketBridge,
+    addr: &str,
+) -> (
+    WebSocketBridge,
+    mpsc::Receiver<BridgeEvent>,
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+) {
+    let mut rx = bridge.start().await.expect("Failed to start bridge");
+    assert!(matches!(rx.recv().await, Some(BridgeEvent::Connected)));
+    let (ws_stream, _) = connect_async(format!("ws://{}", addr))
+        .await
+        .expect("Failed to connect WebSocket client");
+    (bridge, rx, ws_stream)
+}
+
+async fn next_to_clasp(rx: &mut mpsc::Receiver<BridgeEvent>) -> Message {
+    match tokio::time::timeout(Duration::from_secs(2), rx.recv()).await {
+        Ok(Some(BridgeEvent::ToClasp(msg))) => msg,
+        other => panic!("Expected ToClasp event, got {:?}", other),
+    }
+}
+
+fn set(address: &str, value: Value) -> Message {
+    Message::Set(SetMessage {
+        address: address.to_string(),
+        value,
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
+#[tokio::test]
+async fn test_websocket_json_mapping() {
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+
+    let mapping: WsJsonMapping = serde_json::from_value(serde_json::json!({
+        "incoming": [{
+            "when": { "/type": "variable" },
+            "address": "/companion/{{/name}}",
+            "value": "/data/value"
+        }],
+        "outgoing": [{
+            "pattern": "/ws/companion/**",
+            "template": { "type": "set", "path": "{{address}}", "data": "{{value}}" }
+        }]
+    }))
+    .unwrap();
+    let bridge = WebSocketBridge::new(WebSocketBridgeConfig {
+        mode: WsMode::Server,
+        url: addr.clone(),
+        format: WsMessageFormat::Mapped,
+        mapping: Some(mapping),
+        ping_interval_secs: 0,
+        ..WebSocketBridgeConfig::default()
+    });
+    let (mut bridge, mut rx, mut ws_stream) = connect_to_bridge(bridge, &addr).await;
+
+    let incoming =
+        serde_json::json!({ "type": "variable", "name": "tally", "data": { "value": 3 } });
+    ws_stream
+        .send(WsMessage::Text(incoming.to_string()))
+        .await
+        .unwrap();
+    match next_to_clasp(&mut rx).await {
+        Message::Set(set) => {
+            assert_eq!(set.address, "/ws/companion/tally");
+            assert_eq!(set.value, Value::Int(3));
+        }
+        other => panic!("Expected SET, got {:?}", other),
+    }
+
+    bridge
+        .send(set("/ws/companion/page", Value::Int(2)))
+        .await
+        .unwrap();
+    let text = match tokio::time::timeout(Duration::from_secs(2), ws_stream.next()).await {
+        Ok(Some(Ok(WsMessage::Text(text)))) => text,
+        other => panic!("Expected text message, got {:?}", other),
+    };
+    let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "type": "set", "path": "/ws/companion/page", "data": 2 })
+    );
+
+    bridge.stop().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_websocket_mapped_format_requires_mapping() {
+    let mut bridge = WebSocketBridge::new(WebSocketBridgeConfig {
+        mode: WsMode::Server,
+        url: "127.0.0.1:0".to_string(),
+        format: WsMessageFormat::Mapped,
+        ..WebSocketBridgeConfig::default()
+    });
+    assert!(bridge.start().await.is_err());
+}
+
+/// Codec for a line protocol of `<name>=<integer>`
+struct KeyValueCodec;
+
+impl WsCodec for KeyValueCodec {
+    fn decode(&self, frame: &WsFrame, namespace: &str) -> Option<Message> {
+        let WsFrame::Text(text) = frame else {
+            return None;
+        };
+        let (name, value) = text.split_once('=')?;
+        Some(set(
+            &format!("{}/{}", namespace, name),
+            Value::Int(value.parse().ok()?),
+        ))
+    }
+
+    fn encode(&self, msg: &Message) -> Option<WsFrame> {
+        let Message::Set(set) = msg else {
+            return None;
+        };
+        let name = set.address.rsplit('/').next()?;
+        Some(WsFrame::Text(format!("{}={}", name, set.value.as_i64()?)))
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_custom_codec() {
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+
+    let bridge = WebSocketBridge::new(WebSocketBridgeConfig {
+        mode: WsMode::Server,
+        url: addr.clone(),
+        ping_interval_secs: 0,
+        ..WebSocketBridgeConfig::default()
+    })
+    .with_codec(KeyValueCodec);
+    let (mut bridge, mut rx, mut ws_stream) = connect_to_bridge(bridge, &addr).await;
+
+    ws_stream
+        .send(WsMessage::Text("fader=42".to_string()))
+        .await
+        .unwrap();
+    match next_to_clasp(&mut rx).await {
+        Message::Set(set) => {
+            assert_eq!(set.address, "/ws/fader");
+            assert_eq!(set.value, Value::Int(42));
+        }
+        other => panic!("Expected SET, got {:?}", other),
+    }
+
+    bridge
+        .send(set("/ws/out/level", Value::Int(9)))
+        .await
+        .unwrap();
+    match tokio::time::timeout(Duration::from_secs(2), ws_stream.next()).await {
+        Ok(Some(Ok(WsMessage::Text(text)))) => assert_eq!(text, "level=9"),
+        other => panic!("Expected text message, got {:?}", other),
+    }
+
+    bridge.stop().await.unwrap();
+}
@@ -0,0 +1,213 @@
+//! Device discovery commands
+//!
+//! Backs `clasp discover` and the `--device <name-or-id>` option, which
+//! resolves an endpoint by name so operators don't have to chase DHCP
+//! addresses.
+
+use anyhow::{bail, Result};
+use clasp_discovery::{Device, Discovery, DiscoveryConfig, DiscoveryEvent};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// A line of `--watch --json` output
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum WatchEvent<'a> {
+    Found { device: &'a Device },
+    Lost { id: &'a str },
+}
+
+fn discovery(timeout: Duration) -> Discovery {
+    Discovery::with_config(DiscoveryConfig {
+        timeout,
+        ..Default::default()
+    })
+}
+
+/// Run a single discovery round and print the results
+pub async fn run_discover(timeout: Duration, json: bool) -> Result<()> {
+    let devices = discovery(timeout).discover_all().await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&devices)?);
+        return Ok(());
+    }
+
+    if devices.is_empty() {
+        println!("No devices found");
+        return Ok(());
+    }
+
+    println!(
+        "{} Found {} device(s):\n",
+        "CLASP".cyan().bold(),
+        devices.len()
+    );
+    for device in &devices {
+        print_device(device);
+    }
+
+    Ok(())
+}
+
+/// Continuously report devices as they appear and disappear
+pub async fn watch_discover(
+    timeout: Duration,
+    json: bool,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+) -> Result<()> {
+    let mut discovery = discovery(timeout);
+    let mut known: HashMap<String, Device> = HashMap::new();
+
+    if !json {
+        println!(
+            "{} Watching for devices (Ctrl+C to stop)\n",
+            "CLASP".cyan().bold()
+        );
+    }
+
+    loop {
+        let mut rx = discovery.start().await?;
+
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Some(DiscoveryEvent::Found(device)) => {
+                        let unchanged = known.get(&device.id).is_some_and(|d| {
+                            d.endpoints == device.endpoints && d.name == device.name
+                        });
+                        if !unchanged {
+                            if json {
+                                let event = WatchEvent::Found { device: &device };
+                                println!("{}", serde_json::to_string(&event)?);
+                            } else {
+                                print!("{} ", "+".green().bold());
+                                print_device(&device);
+                            }
+                            known.insert(device.id.clone(), device);
+                        }
+                    }
+                    Some(DiscoveryEvent::Lost(id)) => {
+                        if let Some(device) = known.remove(&id) {
+                            if json {
+                                let event = WatchEvent::Lost { id: &id };
+                                println!("{}", serde_json::to_string(&event)?);
+                            } else {
+                                let name = device.name;
+                                println!("{} {} ({})\n", "-".red().bold(), name, id.dimmed());
+                            }
+                        }
+                    }
+                    Some(DiscoveryEvent::Error(e)) => {
+                        tracing::warn!("Discovery error: {}", e);
+                    }
+                    // All one-shot backends finished; browse again after a pause
+                    None => break,
+                },
+                _ = shutdown_rx.recv() => {
+                    discovery.stop().await?;
+                    return Ok(());
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(timeout) => {}
+            _ = shutdown_rx.recv() => return Ok(()),
+        }
+    }
+}
+
+/// Resolve `--device <name-or-id>` to a connection URL
+pub async fn resolve_device(name_or_id: &str, timeout: Duration) -> Result<String> {
+    let devices = discovery(timeout).discover_all().await?;
+    let device = find_device(&devices, name_or_id)?;
+
+    match device
+        .ws_url()
+        .or_else(|| device.endpoints.values().next().map(|s| s.as_str()))
+    {
+        Some(url) => {
+            tracing::info!("Resolved '{}' to {}", name_or_id, url);
+            Ok(url.to_string())
+        }
+        None => bail!("Device '{}' has no endpoints", device.name),
+    }
+}
+
+/// Find a device by exact ID, or by case-insensitive name
+fn find_device<'a>(devices: &'a [Device], name_or_id: &str) -> Result<&'a Device> {
+    if let Some(device) = devices.iter().find(|d| d.id == name_or_id) {
+        return Ok(device);
+    }
+
+    let matches: Vec<&Device> = devices
+        .iter()
+        .filter(|d| d.name.eq_ignore_ascii_case(name_or_id))
+        .collect();
+
+    match matches.as_slice() {
+        [device] => Ok(device),
+        [] => bail!("No device named '{}' found", name_or_id),
+        _ => bail!(
+            "Multiple devices named '{}', use an ID instead: {}",
+            name_or_id,
+            matches
+                .iter()
+                .map(|d| d.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn print_device(device: &Device) {
+    println!("{} ({})", device.name.green(), device.id.dimmed());
+    let mut endpoints: Vec<_> = device.endpoints.iter().collect();
+    endpoints.sort();
+    for (transport, address) in endpoints {
+        println!("    {}: {}", transport, address);
+    }
+    if !device.info.features.is_empty() {
+        println!("    Features: {}", device.info.features.join(", "));
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices() -> Vec<Device> {
+        vec![
+            Device::new("id-1".to_string(), "Stage Left".to_string())
+                .with_ws_endpoint("ws://10.0.0.5:7330/clasp"),
+            Device::new("id-2".to_string(), "Booth".to_string()),
+            Device::new("id-3".to_string(), "Booth".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_find_by_id() {
+        let devices = devices();
+        assert_eq!(find_device(&devices, "id-2").unwrap().id, "id-2");
+    }
+
+    #[test]
+    fn test_find_by_name_case_insensitive() {
+        let devices = devices();
+        let device = find_device(&devices, "stage left").unwrap();
+        assert_eq!(device.ws_url(), Some("ws://10.0.0.5:7330/clasp"));
+    }
+
+    #[test]
+    fn test_find_ambiguous_or_missing() {
+        let devices = devices();
+        let err = find_device(&devices, "Booth").unwrap_err().to_string();
+        assert!(err.contains("id-2") && err.contains("id-3"));
+        assert!(find_device(&devices, "Nowhere").is_err());
+    }
+}
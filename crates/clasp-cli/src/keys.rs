@@ -0,0 +1,102 @@
+//! Device keys
+//!
+//! Backs `clasp key`: generating the Ed25519 key pair a device
+//! authenticates with, and registering or revoking public keys on a router
+//! through `/$sys/keys` (needs admin scope).
+
+use anyhow::{Context, Result};
+use clasp_client::Clasp;
+use clasp_core::{DeviceIdentity, Value};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Address prefix the router manages device keys under
+const KEYS_PREFIX: &str = "/$sys/keys";
+
+/// Get the default device key file path
+pub fn default_key_file() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("clasp")
+        .join("device.key")
+}
+
+/// Load the key pair at `path`, creating it if missing, and print its
+/// public key
+pub fn generate(path: &Path) -> Result<()> {
+    let existed = path.exists();
+    let identity = DeviceIdentity::load_or_generate(path)
+        .with_context(|| format!("Failed to load or create {}", path.display()))?;
+
+    println!("{}", identity.public_key());
+    if existed {
+        eprintln!("Key pair already exists at {}", path.display());
+    } else {
+        eprintln!(
+            "{} Key pair saved to: {}",
+            "OK".green().bold(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Print the public key of the key pair at `path`
+pub fn show(path: &Path) -> Result<()> {
+    let secret = std::fs::read_to_string(path)
+        .with_context(|| format!("No key pair at {}", path.display()))?;
+    let identity = DeviceIdentity::from_secret_hex(&secret)
+        .with_context(|| format!("Invalid key file {}", path.display()))?;
+    println!("{}", identity.public_key());
+    Ok(())
+}
+
+/// Options for `clasp key register` and `clasp key revoke`
+pub struct KeyAdminOptions {
+    pub server: String,
+    pub token: Option<String>,
+    /// Hex public key
+    pub public_key: String,
+    /// Scopes to register the key with; None revokes it
+    pub scopes: Option<String>,
+    pub subject: Option<String>,
+}
+
+/// Register or revoke a public key on a router
+pub async fn administer(options: KeyAdminOptions) -> Result<()> {
+    let mut builder = Clasp::builder(&options.server)
+        .name("clasp-key")
+        .reconnect(false);
+    if let Some(token) = &options.token {
+        builder = builder.token(token);
+    }
+    let client = builder
+        .connect()
+        .await
+        .with_context(|| format!("Failed to connect to {}", options.server))?;
+
+    let address = format!("{}/{}", KEYS_PREFIX, options.public_key);
+    let value = match &options.scopes {
+        Some(scopes) => {
+            let mut map = HashMap::new();
+            map.insert("scopes".to_string(), Value::String(scopes.clone()));
+            if let Some(subject) = &options.subject {
+                map.insert("subject".to_string(), Value::String(subject.clone()));
+            }
+            Value::Map(map)
+        }
+        None => Value::Null,
+    };
+    let result = client.set_with_ack(&address, value).await;
+    client.close().await;
+    result.with_context(|| format!("Router rejected {}", address))?;
+
+    let action = if options.scopes.is_some() {
+        "Registered"
+    } else {
+        "Revoked"
+    };
+    println!("{} {} {}", "OK".green().bold(), action, options.public_key);
+    Ok(())
+}
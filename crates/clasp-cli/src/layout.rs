@@ -0,0 +1,595 @@
+//! Control surface layout export
+//!
+//! Backs `clasp layout export-touchosc`, which reads a router's namespace
+//! (current values from a snapshot plus signal metadata from QUERY) and
+//! generates a TouchOSC or Open Stage Control layout whose controls are
+//! already wired to the right OSC addresses and ranges.
+//!
+//! Controls talk OSC to a `clasp osc` bridge, so CLASP addresses are mapped
+//! back to OSC by stripping the bridge namespace (`/osc` by default).
+
+use anyhow::{bail, Context, Result};
+use clasp_client::Clasp;
+use clasp_core::{SignalDefinition, SignalType, Value};
+use colored::Colorize;
+use flate2::{write::ZlibEncoder, Compression};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Controls per row
+const COLUMNS: usize = 8;
+/// Grid cell size in pixels
+const CELL_WIDTH: u32 = 120;
+const CELL_HEIGHT: u32 = 240;
+/// Padding inside each cell
+const PADDING: u32 = 10;
+
+/// Layout file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LayoutFormat {
+    /// TouchOSC (.tosc)
+    Touchosc,
+    /// Open Stage Control session (.json)
+    OpenStageControl,
+}
+
+impl LayoutFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            LayoutFormat::Touchosc => "tosc",
+            LayoutFormat::OpenStageControl => "json",
+        }
+    }
+}
+
+/// Kind of widget generated for a signal
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlKind {
+    /// Continuous value
+    Fader { min: f64, max: f64, integer: bool },
+    /// Latching on/off
+    Toggle,
+    /// Momentary trigger (events)
+    Button,
+    /// Two-axis pad for `[x, y]` values
+    Xy { min: f64, max: f64 },
+    /// Read-only text display
+    Text,
+}
+
+/// A control bound to an OSC address
+#[derive(Debug, Clone, PartialEq)]
+pub struct Control {
+    /// OSC address the control sends and receives on
+    pub address: String,
+    /// Display label
+    pub label: String,
+    pub kind: ControlKind,
+}
+
+/// Options for `clasp layout export-touchosc`
+pub struct ExportOptions {
+    pub server: String,
+    pub token: Option<String>,
+    pub pattern: String,
+    pub namespace: String,
+    pub format: LayoutFormat,
+    pub output: Option<PathBuf>,
+}
+
+/// Read the router namespace and write a layout file
+pub async fn export_layout(options: ExportOptions) -> Result<()> {
+    let mut builder = Clasp::builder(&options.server)
+        .name("clasp-layout")
+        .reconnect(false);
+    if let Some(token) = &options.token {
+        builder = builder.token(token);
+    }
+    let client = builder
+        .connect()
+        .await
+        .with_context(|| format!("Failed to connect to {}", options.server))?;
+
+    let values = Arc::new(Mutex::new(BTreeMap::new()));
+    let collected = Arc::clone(&values);
+    client
+        .subscribe(&options.pattern, move |value, address| {
+            if let Ok(mut values) = collected.lock() {
+                values.insert(address.to_string(), value);
+            }
+        })
+        .await?;
+
+    // The router answers in order, so by the time the RESULT arrives the
+    // subscription snapshot has been applied.
+    let signals = client.query(&options.pattern).await?;
+    client.close().await;
+
+    let values = values.lock().map(|v| v.clone()).unwrap_or_default();
+    let (controls, skipped) = build_controls(&signals, &values, &options.namespace);
+    if controls.is_empty() {
+        bail!(
+            "No signals under '{}' match {}",
+            options.namespace,
+            options.pattern
+        );
+    }
+    if skipped > 0 {
+        tracing::warn!(
+            "Skipped {} signal(s) outside namespace '{}' or without a usable type",
+            skipped,
+            options.namespace
+        );
+    }
+
+    let output = options
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("layout.{}", options.format.extension())));
+    write_layout(&controls, options.format, &output)?;
+
+    println!(
+        "{} Wrote {} control(s) to {}",
+        "CLASP".cyan().bold(),
+        controls.len(),
+        output.display().to_string().yellow()
+    );
+    Ok(())
+}
+
+fn write_layout(controls: &[Control], format: LayoutFormat, path: &Path) -> Result<()> {
+    let bytes = match format {
+        LayoutFormat::Touchosc => {
+            // .tosc files are zlib-compressed XML
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(touchosc_xml(controls).as_bytes())?;
+            encoder.finish()?
+        }
+        LayoutFormat::OpenStageControl => {
+            serde_json::to_vec_pretty(&open_stage_control_session(controls))?
+        }
+    };
+    std::fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Map signal definitions and current values to controls.
+///
+/// Definitions win over values when both exist for an address. Returns the
+/// controls sorted by address and the number of signals that were skipped.
+pub fn build_controls(
+    signals: &[SignalDefinition],
+    values: &BTreeMap<String, Value>,
+    namespace: &str,
+) -> (Vec<Control>, usize) {
+    let mut kinds: BTreeMap<&str, ControlKind> = BTreeMap::new();
+    for (address, value) in values {
+        if let Some(kind) = kind_for_value(value) {
+            kinds.insert(address, kind);
+        }
+    }
+    for signal in signals {
+        if let Some(kind) = kind_for_signal(signal, values.get(&signal.address)) {
+            kinds.insert(&signal.address, kind);
+        }
+    }
+
+    let mut addresses: BTreeSet<&str> = values.keys().map(String::as_str).collect();
+    addresses.extend(signals.iter().map(|s| s.address.as_str()));
+    let mut skipped = addresses.len() - kinds.len();
+
+    let mut controls = Vec::with_capacity(kinds.len());
+    for (address, kind) in kinds {
+        match osc_address(address, namespace) {
+            Some(osc) => controls.push(Control {
+                label: label_for(&osc),
+                address: osc,
+                kind,
+            }),
+            None => skipped += 1,
+        }
+    }
+    (controls, skipped)
+}
+
+/// Strip the OSC bridge namespace from a CLASP address
+fn osc_address(address: &str, namespace: &str) -> Option<String> {
+    let namespace = namespace.trim_end_matches('/');
+    if namespace.is_empty() {
+        return Some(address.to_string());
+    }
+    match address.strip_prefix(namespace) {
+        Some(rest) if rest.starts_with('/') => Some(rest.to_string()),
+        _ => None,
+    }
+}
+
+fn label_for(address: &str) -> String {
+    address
+        .rsplit('/')
+        .find(|s| !s.is_empty())
+        .unwrap_or(address)
+        .to_string()
+}
+
+fn kind_for_signal(signal: &SignalDefinition, current: Option<&Value>) -> Option<ControlKind> {
+    if signal.signal_type == SignalType::Event {
+        return Some(ControlKind::Button);
+    }
+
+    let range = signal.meta.as_ref().and_then(|m| m.range);
+    let datatype = signal.datatype.as_deref().unwrap_or_default();
+    match datatype {
+        "bool" | "boolean" => Some(ControlKind::Toggle),
+        "string" | "str" => Some(ControlKind::Text),
+        "int" | "i32" | "i64" | "u8" | "u16" | "u32" | "integer" => {
+            let (min, max) = range.unwrap_or((0.0, 127.0));
+            Some(ControlKind::Fader {
+                min,
+                max,
+                integer: true,
+            })
+        }
+        "float" | "f32" | "f64" | "number" => {
+            let (min, max) = range.unwrap_or((0.0, 1.0));
+            Some(ControlKind::Fader {
+                min,
+                max,
+                integer: false,
+            })
+        }
+        "vec2" | "xy" => {
+            let (min, max) = range.unwrap_or((0.0, 1.0));
+            Some(ControlKind::Xy { min, max })
+        }
+        // Unknown datatype: infer from the current value, applying any range
+        _ => match (current.and_then(kind_for_value), range) {
+            (Some(ControlKind::Fader { integer, .. }), Some((min, max))) => {
+                Some(ControlKind::Fader { min, max, integer })
+            }
+            (Some(ControlKind::Xy { .. }), Some((min, max))) => Some(ControlKind::Xy { min, max }),
+            (kind @ Some(_), _) => kind,
+            (None, Some((min, max))) => Some(ControlKind::Fader {
+                min,
+                max,
+                integer: false,
+            }),
+            (None, None) => None,
+        },
+    }
+}
+
+fn kind_for_value(value: &Value) -> Option<ControlKind> {
+    match value {
+        Value::Bool(_) => Some(ControlKind::Toggle),
+        Value::Int(i) => Some(ControlKind::Fader {
+            min: (*i as f64).min(0.0),
+            max: (*i as f64).max(127.0),
+            integer: true,
+        }),
+        Value::Float(f) => Some(ControlKind::Fader {
+            min: f.min(0.0),
+            max: f.max(1.0),
+            integer: false,
+        }),
+        Value::String(_) => Some(ControlKind::Text),
+        Value::Array(items) if items.len() == 2 => {
+            let x = items[0].as_f64()?;
+            let y = items[1].as_f64()?;
+            Some(ControlKind::Xy {
+                min: x.min(y).min(0.0),
+                max: x.max(y).max(1.0),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Top-left corner of the grid cell for the nth control
+fn cell(index: usize) -> (u32, u32) {
+    let col = (index % COLUMNS) as u32;
+    let row = (index / COLUMNS) as u32;
+    (col * CELL_WIDTH + PADDING, row * CELL_HEIGHT + PADDING)
+}
+
+const WIDGET_WIDTH: u32 = CELL_WIDTH - 2 * PADDING;
+const WIDGET_HEIGHT: u32 = CELL_HEIGHT - 2 * PADDING;
+
+/// Build an Open Stage Control session
+pub fn open_stage_control_session(controls: &[Control]) -> serde_json::Value {
+    let widgets: Vec<_> = controls
+        .iter()
+        .enumerate()
+        .map(|(i, control)| {
+            let (left, top) = cell(i);
+            let mut widget = json!({
+                "id": format!("clasp_{}", i + 1),
+                "label": control.label,
+                "address": control.address,
+                "left": left,
+                "top": top,
+                "width": WIDGET_WIDTH,
+                "height": WIDGET_HEIGHT,
+            });
+            let extra = match &control.kind {
+                ControlKind::Fader { min, max, integer } => json!({
+                    "type": "fader",
+                    "range": { "min": min, "max": max },
+                    "step": if *integer { json!(1) } else { json!(false) },
+                    "typeTags": if *integer { "i" } else { "f" },
+                }),
+                ControlKind::Toggle => json!({
+                    "type": "button",
+                    "mode": "toggle",
+                    "on": true,
+                    "off": false,
+                }),
+                ControlKind::Button => json!({
+                    "type": "button",
+                    "mode": "tap",
+                    "on": 1,
+                    "off": 0,
+                }),
+                ControlKind::Xy { min, max } => json!({
+                    "type": "xy",
+                    "rangeX": { "min": min, "max": max },
+                    "rangeY": { "min": min, "max": max },
+                }),
+                ControlKind::Text => json!({ "type": "text" }),
+            };
+            if let (Some(widget), serde_json::Value::Object(extra)) =
+                (widget.as_object_mut(), extra)
+            {
+                widget.extend(extra);
+            }
+            widget
+        })
+        .collect();
+
+    json!({
+        "createdWith": "CLASP",
+        "version": env!("CARGO_PKG_VERSION"),
+        "type": "session",
+        "content": {
+            "type": "root",
+            "id": "root",
+            "tabs": [{
+                "type": "tab",
+                "id": "clasp",
+                "label": "CLASP",
+                "widgets": widgets,
+            }],
+        },
+    })
+}
+
+/// Build the (uncompressed) TouchOSC document XML
+pub fn touchosc_xml(controls: &[Control]) -> String {
+    let rows = controls.len().div_ceil(COLUMNS) as u32;
+    let mut xml = String::from("<?xml version='1.0' encoding='UTF-8'?>\n<lexml version='3'>\n");
+
+    xml.push_str(&format!(
+        "<node ID='{}' type='GROUP'>\n<properties>\n",
+        uuid::Uuid::new_v4()
+    ));
+    push_string_property(&mut xml, "name", "root");
+    push_frame_property(
+        &mut xml,
+        0,
+        0,
+        COLUMNS as u32 * CELL_WIDTH,
+        rows.max(1) * CELL_HEIGHT,
+    );
+    xml.push_str("</properties>\n<children>\n");
+
+    for (i, control) in controls.iter().enumerate() {
+        push_touchosc_node(&mut xml, i, control);
+    }
+
+    xml.push_str("</children>\n</node>\n</lexml>\n");
+    xml
+}
+
+fn push_touchosc_node(xml: &mut String, index: usize, control: &Control) {
+    let node_type = match control.kind {
+        ControlKind::Fader { .. } => "FADER",
+        ControlKind::Toggle | ControlKind::Button => "BUTTON",
+        ControlKind::Xy { .. } => "XY",
+        ControlKind::Text => "LABEL",
+    };
+    let (x, y) = cell(index);
+
+    xml.push_str(&format!(
+        "<node ID='{}' type='{}'>\n<properties>\n",
+        uuid::Uuid::new_v4(),
+        node_type
+    ));
+    push_string_property(xml, "name", &control.label);
+    push_frame_property(xml, x, y, WIDGET_WIDTH, WIDGET_HEIGHT);
+    match control.kind {
+        // 0 = momentary, 1 = toggle on press
+        ControlKind::Toggle => push_int_property(xml, "buttonType", 1),
+        ControlKind::Button => push_int_property(xml, "buttonType", 0),
+        ControlKind::Text => push_string_property(xml, "text", &control.label),
+        _ => {}
+    }
+    xml.push_str("</properties>\n<messages>\n");
+
+    let (send, args): (bool, Vec<(&str, &str, f64, f64)>) = match control.kind {
+        ControlKind::Fader { min, max, integer } => {
+            let conversion = if integer { "INTEGER" } else { "FLOAT" };
+            (true, vec![("x", conversion, min, max)])
+        }
+        ControlKind::Toggle => (true, vec![("x", "BOOLEAN", 0.0, 1.0)]),
+        ControlKind::Button => (true, vec![("x", "INTEGER", 0.0, 1.0)]),
+        ControlKind::Xy { min, max } => (
+            true,
+            vec![("x", "FLOAT", min, max), ("y", "FLOAT", min, max)],
+        ),
+        ControlKind::Text => (false, vec![("text", "STRING", 0.0, 1.0)]),
+    };
+
+    xml.push_str(&format!(
+        "<osc>\n<enabled>1</enabled>\n<send>{}</send>\n<receive>1</receive>\n<feedback>0</feedback>\n<connections>00001</connections>\n",
+        send as u8
+    ));
+    xml.push_str(&format!(
+        "<triggers>\n<trigger>\n<var><![CDATA[{}]]></var>\n<condition>ANY</condition>\n</trigger>\n</triggers>\n",
+        args[0].0
+    ));
+    xml.push_str(&format!(
+        "<path>\n<partial>\n<type>CONSTANT</type>\n<conversion>STRING</conversion>\n<value><![CDATA[{}]]></value>\n<scaleMin>0</scaleMin>\n<scaleMax>1</scaleMax>\n</partial>\n</path>\n<arguments>\n",
+        cdata(&control.address)
+    ));
+    for (var, conversion, min, max) in args {
+        xml.push_str(&format!(
+            "<partial>\n<type>VALUE</type>\n<conversion>{}</conversion>\n<value><![CDATA[{}]]></value>\n<scaleMin>{}</scaleMin>\n<scaleMax>{}</scaleMax>\n</partial>\n",
+            conversion, var, min, max
+        ));
+    }
+    xml.push_str("</arguments>\n</osc>\n</messages>\n</node>\n");
+}
+
+fn push_string_property(xml: &mut String, key: &str, value: &str) {
+    xml.push_str(&format!(
+        "<property type='s'>\n<key><![CDATA[{}]]></key>\n<value><![CDATA[{}]]></value>\n</property>\n",
+        key,
+        cdata(value)
+    ));
+}
+
+fn push_int_property(xml: &mut String, key: &str, value: i64) {
+    xml.push_str(&format!(
+        "<property type='i'>\n<key><![CDATA[{}]]></key>\n<value>{}</value>\n</property>\n",
+        key, value
+    ));
+}
+
+fn push_frame_property(xml: &mut String, x: u32, y: u32, w: u32, h: u32) {
+    xml.push_str(&format!(
+        "<property type='r'>\n<key><![CDATA[frame]]></key>\n<value>\n<x>{}</x>\n<y>{}</y>\n<w>{}</w>\n<h>{}</h>\n</value>\n</property>\n",
+        x, y, w, h
+    ));
+}
+
+/// Escape the one sequence that can terminate a CDATA section
+fn cdata(s: &str) -> String {
+    s.replace("]]>", "]]]]><![CDATA[>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clasp_core::SignalMeta;
+
+    fn signal(
+        address: &str,
+        datatype: Option<&str>,
+        range: Option<(f64, f64)>,
+    ) -> SignalDefinition {
+        SignalDefinition {
+            address: address.to_string(),
+            signal_type: SignalType::Param,
+            datatype: datatype.map(str::to_string),
+            access: None,
+            meta: range.map(|range| SignalMeta {
+                unit: None,
+                range: Some(range),
+                default: None,
+                description: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_build_controls_maps_ranges_and_namespace() {
+        let mut values = BTreeMap::new();
+        values.insert("/osc/mixer/fader1".to_string(), Value::Float(0.5));
+        values.insert("/osc/mixer/mute".to_string(), Value::Bool(false));
+        values.insert("/lights/dimmer".to_string(), Value::Float(0.2));
+
+        let signals = vec![
+            signal("/osc/mixer/fader1", Some("f32"), Some((-60.0, 12.0))),
+            SignalDefinition {
+                signal_type: SignalType::Event,
+                ..signal("/osc/cue/go", None, None)
+            },
+        ];
+
+        let (controls, skipped) = build_controls(&signals, &values, "/osc");
+        assert_eq!(skipped, 1); // /lights/dimmer is outside the namespace
+        assert_eq!(controls.len(), 3);
+
+        let fader = controls
+            .iter()
+            .find(|c| c.address == "/mixer/fader1")
+            .unwrap();
+        assert_eq!(fader.label, "fader1");
+        assert_eq!(
+            fader.kind,
+            ControlKind::Fader {
+                min: -60.0,
+                max: 12.0,
+                integer: false
+            }
+        );
+
+        let go = controls.iter().find(|c| c.address == "/cue/go").unwrap();
+        assert_eq!(go.kind, ControlKind::Button);
+        let mute = controls
+            .iter()
+            .find(|c| c.address == "/mixer/mute")
+            .unwrap();
+        assert_eq!(mute.kind, ControlKind::Toggle);
+    }
+
+    #[test]
+    fn test_range_applies_to_inferred_kind() {
+        let mut values = BTreeMap::new();
+        values.insert("/dmx/1".to_string(), Value::Int(40));
+        let signals = vec![signal("/dmx/1", None, Some((0.0, 255.0)))];
+
+        let (controls, _) = build_controls(&signals, &values, "");
+        assert_eq!(
+            controls[0].kind,
+            ControlKind::Fader {
+                min: 0.0,
+                max: 255.0,
+                integer: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_stage_control_session() {
+        let controls = vec![Control {
+            address: "/mixer/fader1".to_string(),
+            label: "fader1".to_string(),
+            kind: ControlKind::Fader {
+                min: 0.0,
+                max: 10.0,
+                integer: false,
+            },
+        }];
+        let session = open_stage_control_session(&controls);
+        let widget = &session["content"]["tabs"][0]["widgets"][0];
+        assert_eq!(widget["type"], "fader");
+        assert_eq!(widget["address"], "/mixer/fader1");
+        assert_eq!(widget["range"]["max"], 10.0);
+    }
+
+    #[test]
+    fn test_touchosc_xml() {
+        let controls = vec![Control {
+            address: "/pad".to_string(),
+            label: "pad".to_string(),
+            kind: ControlKind::Xy { min: 0.0, max: 2.0 },
+        }];
+        let xml = touchosc_xml(&controls);
+        assert!(xml.contains("type='XY'"));
+        assert!(xml.contains("<![CDATA[/pad]]>"));
+        assert!(xml.contains("<scaleMax>2</scaleMax>"));
+        assert_eq!(cdata("a]]>b"), "a]]]]><![CDATA[>b");
+    }
+}
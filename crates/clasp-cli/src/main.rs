@@ -2,13 +2,19 @@
 //!
 //! Start protocol servers, bridges, and manage CLASP signals from the command line.
 
+mod discover;
+mod keys;
+mod layout;
+mod manifest;
 mod server;
+mod stats;
 mod tokens;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use colored::Colorize;
 use std::path::PathBuf;
+use std::time::Duration;
 use tokens::{create_token, default_token_file, format_timestamp, TokenStore};
 use tokio::sync::mpsc;
 use tracing::{info, warn};
@@ -65,13 +71,18 @@ enum Commands {
 
     /// Start an OSC server
     Osc {
-        /// UDP port to listen on
+        /// Port to listen on
         #[arg(short, long, default_value = "9000")]
         port: u16,
 
         /// Bind address
         #[arg(short, long, default_value = "0.0.0.0")]
         bind: String,
+
+        /// Use TCP instead of UDP, with packet-length (OSC 1.0) or SLIP
+        /// (OSC 1.1) framing
+        #[arg(long, value_name = "FRAMING", value_parser = ["length", "slip"])]
+        tcp: Option<String>,
     },
 
     /// Start an MQTT broker connection
@@ -125,6 +136,10 @@ enum Commands {
         #[arg(short, long, default_value = "quic://localhost:7331")]
         server: String,
 
+        /// Discover the server by device name or ID instead of a URL
+        #[arg(short, long, conflicts_with = "server")]
+        device: Option<String>,
+
         /// Signal address
         address: String,
 
@@ -138,11 +153,81 @@ enum Commands {
         #[arg(short, long, default_value = "quic://localhost:7331")]
         server: String,
 
+        /// Discover the server by device name or ID instead of a URL
+        #[arg(short, long, conflicts_with = "server")]
+        device: Option<String>,
+
         /// Address pattern to subscribe to
         #[arg(default_value = "/**")]
         pattern: String,
     },
 
+    /// Discover CLASP devices on the network
+    Discover {
+        /// Discovery timeout in seconds
+        #[arg(short, long, default_value = "3")]
+        timeout: u64,
+
+        /// Output as JSON (one event per line with --watch)
+        #[arg(long)]
+        json: bool,
+
+        /// Keep running and report devices as they appear and disappear
+        #[arg(short, long)]
+        watch: bool,
+    },
+
+    /// Generate control surface layouts from a router's namespace
+    Layout {
+        #[command(subcommand)]
+        action: LayoutAction,
+    },
+
+    /// Show the namespace manifests applications have published
+    Manifest {
+        /// CLASP router URL
+        #[arg(short, long, default_value = "ws://localhost:7330")]
+        server: String,
+
+        /// Discover the router by device name or ID instead of a URL
+        #[arg(short, long, conflicts_with = "server")]
+        device: Option<String>,
+
+        /// Auth token for routers that require one
+        #[arg(long, env = "CLASP_TOKEN")]
+        token: Option<String>,
+
+        /// Only show this application's manifest
+        app: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show subscription match counts, hot addresses and unused patterns
+    Stats {
+        /// CLASP router URL
+        #[arg(short, long, default_value = "ws://localhost:7330")]
+        server: String,
+
+        /// Discover the router by device name or ID instead of a URL
+        #[arg(short, long, conflicts_with = "server")]
+        device: Option<String>,
+
+        /// Auth token (needs admin scope on routers with authentication)
+        #[arg(long, env = "CLASP_TOKEN")]
+        token: Option<String>,
+
+        /// Number of patterns and addresses to list
+        #[arg(short = 'n', long, default_value = "20")]
+        top: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Show version and system info
     Info,
 
@@ -155,6 +240,51 @@ enum Commands {
         #[command(subcommand)]
         action: TokenAction,
     },
+
+    /// Manage device key pairs for key-based authentication
+    Key {
+        /// Key pair file path (default: ~/.config/clasp/device.key)
+        #[arg(long)]
+        file: Option<String>,
+
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+}
+
+/// Layout generation actions
+#[derive(Subcommand)]
+enum LayoutAction {
+    /// Export a TouchOSC (or Open Stage Control) layout
+    ExportTouchosc {
+        /// CLASP router URL
+        #[arg(short, long, default_value = "ws://localhost:7330")]
+        server: String,
+
+        /// Discover the router by device name or ID instead of a URL
+        #[arg(short, long, conflicts_with = "server")]
+        device: Option<String>,
+
+        /// Auth token for routers that require one
+        #[arg(long, env = "CLASP_TOKEN")]
+        token: Option<String>,
+
+        /// Address pattern to include
+        #[arg(short, long, default_value = "/**")]
+        pattern: String,
+
+        /// OSC bridge namespace stripped from addresses ("" to keep them as-is)
+        #[arg(short, long, default_value = "/osc")]
+        namespace: String,
+
+        /// Layout format
+        #[arg(short, long, value_enum, default_value = "touchosc")]
+        format: layout::LayoutFormat,
+
+        /// Output file (default: layout.tosc or layout.json)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 /// Token management actions
@@ -198,6 +328,52 @@ enum TokenAction {
     Prune,
 }
 
+/// Device key actions
+#[derive(Subcommand)]
+enum KeyAction {
+    /// Create a key pair (if the file doesn't exist) and print its public key
+    Generate,
+
+    /// Print the public key of the key pair
+    Show,
+
+    /// Register a device's public key on a router (needs admin scope)
+    Register {
+        /// Hex public key, as printed by `clasp key generate`
+        public_key: String,
+
+        /// Scopes (comma-separated, e.g., "write:/sensors/**")
+        #[arg(long)]
+        scopes: String,
+
+        /// Subject identifying the device
+        #[arg(long)]
+        subject: Option<String>,
+
+        /// CLASP router URL
+        #[arg(short, long, default_value = "ws://localhost:7330")]
+        server: String,
+
+        /// Admin token
+        #[arg(long, env = "CLASP_TOKEN")]
+        token: Option<String>,
+    },
+
+    /// Revoke a device's public key on a router and disconnect it
+    Revoke {
+        /// Hex public key
+        public_key: String,
+
+        /// CLASP router URL
+        #[arg(short, long, default_value = "ws://localhost:7330")]
+        server: String,
+
+        /// Admin token
+        #[arg(long, env = "CLASP_TOKEN")]
+        token: Option<String>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -230,14 +406,15 @@ async fn main() -> Result<()> {
             run_bridge(&bridge_type, opt, &mut shutdown_rx).await?;
         }
 
-        Commands::Osc { port, bind } => {
+        Commands::Osc { port, bind, tcp } => {
             println!(
-                "{} Starting OSC server on {}:{}",
+                "{} Starting OSC server on {}:{}{}",
                 "CLASP".cyan().bold(),
                 bind,
-                port
+                port,
+                if tcp.is_some() { " (TCP)" } else { "" }
             );
-            run_osc_server(&bind, port, &mut shutdown_rx).await?;
+            run_osc_server(&bind, port, tcp.as_deref(), &mut shutdown_rx).await?;
         }
 
         Commands::Mqtt {
@@ -281,9 +458,11 @@ async fn main() -> Result<()> {
 
         Commands::Pub {
             server,
+            device,
             address,
             value,
         } => {
+            let server = resolve_server(server, device).await?;
             println!(
                 "{} Publishing to {} -> {}",
                 "CLASP".cyan().bold(),
@@ -293,7 +472,12 @@ async fn main() -> Result<()> {
             publish_value(&server, &address, &value).await?;
         }
 
-        Commands::Sub { server, pattern } => {
+        Commands::Sub {
+            server,
+            device,
+            pattern,
+        } => {
+            let server = resolve_server(server, device).await?;
             println!(
                 "{} Subscribing to {} on {}",
                 "CLASP".cyan().bold(),
@@ -303,6 +487,76 @@ async fn main() -> Result<()> {
             subscribe_pattern(&server, &pattern, &mut shutdown_rx).await?;
         }
 
+        Commands::Discover {
+            timeout,
+            json,
+            watch,
+        } => {
+            let timeout = Duration::from_secs(timeout);
+            if watch {
+                discover::watch_discover(timeout, json, &mut shutdown_rx).await?;
+            } else {
+                discover::run_discover(timeout, json).await?;
+            }
+        }
+
+        Commands::Layout { action } => match action {
+            LayoutAction::ExportTouchosc {
+                server,
+                device,
+                token,
+                pattern,
+                namespace,
+                format,
+                output,
+            } => {
+                let server = resolve_server(server, device).await?;
+                layout::export_layout(layout::ExportOptions {
+                    server,
+                    token,
+                    pattern,
+                    namespace,
+                    format,
+                    output,
+                })
+                .await?;
+            }
+        },
+
+        Commands::Manifest {
+            server,
+            device,
+            token,
+            app,
+            json,
+        } => {
+            let server = resolve_server(server, device).await?;
+            manifest::show_manifests(manifest::ManifestOptions {
+                server,
+                token,
+                app,
+                json,
+            })
+            .await?;
+        }
+
+        Commands::Stats {
+            server,
+            device,
+            token,
+            top,
+            json,
+        } => {
+            let server = resolve_server(server, device).await?;
+            stats::show_stats(stats::StatsOptions {
+                server,
+                token,
+                top,
+                json,
+            })
+            .await?;
+        }
+
         Commands::Info => {
             print_info();
         }
@@ -450,11 +704,60 @@ async fn main() -> Result<()> {
                 }
             }
         }
+
+        Commands::Key { file, action } => {
+            let key_path = file
+                .map(PathBuf::from)
+                .unwrap_or_else(keys::default_key_file);
+
+            match action {
+                KeyAction::Generate => keys::generate(&key_path)?,
+                KeyAction::Show => keys::show(&key_path)?,
+                KeyAction::Register {
+                    public_key,
+                    scopes,
+                    subject,
+                    server,
+                    token,
+                } => {
+                    keys::administer(keys::KeyAdminOptions {
+                        server,
+                        token,
+                        public_key,
+                        scopes: Some(scopes),
+                        subject,
+                    })
+                    .await?
+                }
+                KeyAction::Revoke {
+                    public_key,
+                    server,
+                    token,
+                } => {
+                    keys::administer(keys::KeyAdminOptions {
+                        server,
+                        token,
+                        public_key,
+                        scopes: None,
+                        subject: None,
+                    })
+                    .await?
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Use the discovered endpoint for `--device`, otherwise the `--server` URL
+async fn resolve_server(server: String, device: Option<String>) -> Result<String> {
+    match device {
+        Some(device) => discover::resolve_device(&device, Duration::from_secs(3)).await,
+        None => Ok(server),
+    }
+}
+
 fn setup_logging(level: &str, json: bool) -> Result<()> {
     let filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new(level))
@@ -525,11 +828,22 @@ async fn run_bridge(
     Ok(())
 }
 
-async fn run_osc_server(bind: &str, port: u16, shutdown_rx: &mut mpsc::Receiver<()>) -> Result<()> {
-    use clasp_bridge::{Bridge, OscBridge, OscBridgeConfig};
+async fn run_osc_server(
+    bind: &str,
+    port: u16,
+    tcp: Option<&str>,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+) -> Result<()> {
+    use clasp_bridge::{Bridge, OscBridge, OscBridgeConfig, OscFraming, OscTransport};
 
+    let transport = match tcp {
+        Some("slip") => OscTransport::Tcp(OscFraming::Slip),
+        Some(_) => OscTransport::Tcp(OscFraming::PacketLength),
+        None => OscTransport::Udp,
+    };
     let config = OscBridgeConfig {
         bind_addr: format!("{}:{}", bind, port),
+        transport,
         ..Default::default()
     };
 
@@ -737,6 +1051,8 @@ fn print_info() {
     println!("  - HTTP/REST (request-response API)");
     println!();
     println!("{}", "Examples:".green());
+    println!("  clasp discover --watch           # Watch for devices");
+    println!("  clasp layout export-touchosc     # Generate a TouchOSC layout");
     println!("  clasp osc --port 9000            # Start OSC server");
     println!("  clasp mqtt --host broker.local   # Connect to MQTT broker");
     println!("  clasp http --bind 0.0.0.0:3000   # Start HTTP REST API");
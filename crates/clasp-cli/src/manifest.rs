@@ -0,0 +1,145 @@
+//! Namespace manifests
+//!
+//! Backs `clasp manifest`, which reads the manifests applications have
+//! published under `/$manifest/` and prints each namespace as a tree of
+//! declared addresses with their types, ranges, units and descriptions.
+
+use anyhow::{bail, Context, Result};
+use clasp_client::Clasp;
+use clasp_core::{Manifest, SignalDefinition, MANIFEST_PREFIX};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Options for `clasp manifest`
+pub struct ManifestOptions {
+    pub server: String,
+    pub token: Option<String>,
+    /// Only show this app's manifest
+    pub app: Option<String>,
+    /// Print the manifests as JSON instead of trees
+    pub json: bool,
+}
+
+pub async fn show_manifests(options: ManifestOptions) -> Result<()> {
+    let mut builder = Clasp::builder(&options.server)
+        .name("clasp-manifest")
+        .reconnect(false);
+    if let Some(token) = &options.token {
+        builder = builder.token(token);
+    }
+    let client = builder
+        .connect()
+        .await
+        .with_context(|| format!("Failed to connect to {}", options.server))?;
+
+    let manifests = match &options.app {
+        Some(app) => vec![client
+            .manifest(app)
+            .await
+            .with_context(|| format!("No manifest published for '{}'", app))?],
+        None => {
+            let values = Arc::new(Mutex::new(BTreeMap::new()));
+            let collected = Arc::clone(&values);
+            let pattern = format!("{}*", MANIFEST_PREFIX);
+            client
+                .subscribe(&pattern, move |value, address| {
+                    if let Ok(mut values) = collected.lock() {
+                        values.insert(address.to_string(), value);
+                    }
+                })
+                .await?;
+            // The router answers in order, so by the time the RESULT
+            // arrives the subscription snapshot has been applied.
+            client.query(&pattern).await?;
+
+            let values = values.lock().map(|v| v.clone()).unwrap_or_default();
+            let mut manifests = Vec::new();
+            for (address, value) in values {
+                match Manifest::from_value(&value) {
+                    Ok(manifest) => manifests.push(manifest),
+                    Err(e) => tracing::warn!("Skipping {}: {}", address, e),
+                }
+            }
+            manifests
+        }
+    };
+    client.close().await;
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&manifests)?);
+        return Ok(());
+    }
+    if manifests.is_empty() {
+        bail!("No manifests published");
+    }
+    for manifest in &manifests {
+        print_manifest(manifest);
+    }
+    Ok(())
+}
+
+fn print_manifest(manifest: &Manifest) {
+    print!(
+        "{} {}",
+        manifest.app.cyan().bold(),
+        manifest.namespace.yellow()
+    );
+    match &manifest.description {
+        Some(description) => println!("  {}", description.dimmed()),
+        None => println!(),
+    }
+
+    let mut signals: Vec<&SignalDefinition> = manifest.signals.iter().collect();
+    signals.sort_by(|a, b| a.address.cmp(&b.address));
+    let mut printed: Vec<&str> = Vec::new();
+    for signal in signals {
+        let relative = signal
+            .address
+            .strip_prefix(manifest.namespace.trim_end_matches('/'))
+            .unwrap_or(&signal.address);
+        let segments: Vec<&str> = relative.split('/').filter(|s| !s.is_empty()).collect();
+
+        // Print the branch segments not already shown by the previous signal
+        let shared = printed
+            .iter()
+            .zip(&segments)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for (depth, segment) in segments.iter().enumerate().skip(shared) {
+            let indent = "  ".repeat(depth + 1);
+            if depth + 1 < segments.len() {
+                println!("{}{}", indent, segment);
+            } else {
+                println!("{}{}  {}", indent, segment.bold(), details(signal).dimmed());
+            }
+        }
+        printed = segments;
+    }
+    println!();
+}
+
+/// Type, range, unit, access and description of a declaration
+fn details(signal: &SignalDefinition) -> String {
+    let mut parts = vec![format!("{:?}", signal.signal_type).to_lowercase()];
+    if let Some(datatype) = &signal.datatype {
+        parts.push(datatype.clone());
+    }
+    if let Some(meta) = &signal.meta {
+        if let Some((min, max)) = meta.range {
+            parts.push(format!("{}..{}", min, max));
+        }
+        if let Some(unit) = &meta.unit {
+            parts.push(unit.clone());
+        }
+    }
+    if let Some(access) = &signal.access {
+        parts.push(access.clone());
+    }
+    let mut text = parts.join(", ");
+    if let Some(description) = signal.meta.as_ref().and_then(|m| m.description.as_ref()) {
+        text.push_str(" - ");
+        text.push_str(description);
+    }
+    text
+}
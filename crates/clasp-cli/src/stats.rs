@@ -0,0 +1,105 @@
+//! Subscription statistics
+//!
+//! Backs `clasp stats`, which reads `/$sys/stats/subscriptions` from a
+//! router and prints how often each subscription pattern matched, the
+//! busiest addresses, and patterns that never matched anything. Reading
+//! the stats needs admin scope on routers with authentication.
+
+use anyhow::{Context, Result};
+use clasp_client::Clasp;
+use colored::Colorize;
+use serde_json::Value;
+
+/// Address the router publishes subscription statistics under
+const STATS_ADDRESS: &str = "/$sys/stats/subscriptions";
+
+/// Options for `clasp stats`
+pub struct StatsOptions {
+    pub server: String,
+    pub token: Option<String>,
+    /// Number of pattern and address rows to show
+    pub top: usize,
+    /// Print the raw stats as JSON instead of tables
+    pub json: bool,
+}
+
+pub async fn show_stats(options: StatsOptions) -> Result<()> {
+    let mut builder = Clasp::builder(&options.server)
+        .name("clasp-stats")
+        .reconnect(false);
+    if let Some(token) = &options.token {
+        builder = builder.token(token);
+    }
+    let client = builder
+        .connect()
+        .await
+        .with_context(|| format!("Failed to connect to {}", options.server))?;
+
+    let stats = client
+        .get(STATS_ADDRESS)
+        .await
+        .context("Router did not return subscription stats")?;
+    client.close().await;
+    let stats = serde_json::to_value(&stats)?;
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} {} subscription(s) from {} session(s)",
+        "CLASP".cyan().bold(),
+        field_int(&stats, "subscriptions"),
+        field_int(&stats, "sessions")
+    );
+
+    let patterns = field_array(&stats, "patterns");
+    println!("\n{}", "Patterns".bold());
+    if patterns.is_empty() {
+        println!("  (none)");
+    }
+    for pattern in patterns.iter().take(options.top) {
+        println!(
+            "  {:>10}  {:>4} sub(s)  {}",
+            field_int(pattern, "hits"),
+            field_int(pattern, "subscriptions"),
+            field_str(pattern, "pattern").yellow()
+        );
+    }
+
+    let hot = field_array(&stats, "hot_addresses");
+    println!("\n{}", "Hot addresses".bold());
+    if hot.is_empty() {
+        println!("  (none)");
+    }
+    for address in hot.iter().take(options.top) {
+        println!(
+            "  {:>10} msg(s)  {:>10} delivered  {}",
+            field_int(address, "messages"),
+            field_int(address, "deliveries"),
+            field_str(address, "address").yellow()
+        );
+    }
+
+    let orphans = field_array(&stats, "orphans");
+    if !orphans.is_empty() {
+        println!("\n{}", "Never matched".bold());
+        for orphan in &orphans {
+            println!("  {}", orphan.as_str().unwrap_or_default().red());
+        }
+    }
+    Ok(())
+}
+
+fn field_int(value: &Value, key: &str) -> i64 {
+    value[key].as_i64().unwrap_or(0)
+}
+
+fn field_str<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap_or_default()
+}
+
+fn field_array(value: &Value, key: &str) -> Vec<Value> {
+    value[key].as_array().cloned().unwrap_or_default()
+}
@@ -1,6 +1,11 @@
 //! Client builder pattern
 
+use crate::client::DEFAULT_REQUEST_TIMEOUT;
+use crate::governor::Governor;
 use crate::{Clasp, Result};
+use clasp_core::DeviceIdentity;
+use clasp_transport::KeepaliveConfig;
+use std::time::Duration;
 
 /// Builder for Clasp client
 pub struct ClaspBuilder {
@@ -8,8 +13,14 @@ pub struct ClaspBuilder {
     name: String,
     features: Vec<String>,
     token: Option<String>,
+    identity: Option<DeviceIdentity>,
     reconnect: bool,
     reconnect_interval_ms: u64,
+    request_timeout: Duration,
+    keepalive: KeepaliveConfig,
+    governor: Option<Governor>,
+    incremental_snapshots: bool,
+    diff_resync: bool,
     #[cfg(feature = "p2p")]
     p2p_config: Option<clasp_core::P2PConfig>,
 }
@@ -24,10 +35,18 @@ impl ClaspBuilder {
                 "param".to_string(),
                 "event".to_string(),
                 "stream".to_string(),
+                "gesture".to_string(),
+                "timeline".to_string(),
             ],
             token: None,
+            identity: None,
             reconnect: true,
             reconnect_interval_ms: 5000,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            keepalive: KeepaliveConfig::default(),
+            governor: None,
+            incremental_snapshots: false,
+            diff_resync: false,
             #[cfg(feature = "p2p")]
             p2p_config: None,
         }
@@ -51,6 +70,16 @@ impl ClaspBuilder {
         self
     }
 
+    /// Authenticate with a device key pair instead of a token
+    ///
+    /// Every connect and reconnect sends a freshly signed proof; the router
+    /// must have the identity's public key registered. See
+    /// [`clasp_core::identity`].
+    pub fn identity(mut self, identity: DeviceIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
     /// Enable/disable auto-reconnect
     pub fn reconnect(mut self, enabled: bool) -> Self {
         self.reconnect = enabled;
@@ -63,6 +92,51 @@ impl ClaspBuilder {
         self
     }
 
+    /// Set how long GET, QUERY and confirmed requests wait for a reply
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Set how the connection is pinged and when it is considered dead
+    ///
+    /// A dead connection is closed and, with auto-reconnect on, re-dialled.
+    pub fn keepalive(mut self, keepalive: KeepaliveConfig) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// Rate limit outgoing `set` and `stream` calls per address
+    ///
+    /// Values sent faster than the limit are coalesced: only the latest one
+    /// goes out, once the address's interval has passed.
+    pub fn governor(mut self, governor: Governor) -> Self {
+        self.governor = Some(governor);
+        self
+    }
+
+    /// Deliver large, paged snapshots to callbacks page by page
+    ///
+    /// By default the pages of a snapshot are collected and applied together
+    /// once the last one arrives, so callbacks see a complete snapshot. With
+    /// this on, each page is applied as soon as it arrives, which gets the
+    /// first values to the UI sooner on very large namespaces.
+    pub fn incremental_snapshots(mut self, enabled: bool) -> Self {
+        self.incremental_snapshots = enabled;
+        self
+    }
+
+    /// Resync with a diff instead of a full snapshot after reconnecting
+    ///
+    /// The client remembers the state revision of the snapshot it got on
+    /// connect and, after a reconnect, asks the router for only the values
+    /// changed since then. Routers that can't diff that far back (values
+    /// were removed, or the router restarted) send a full snapshot instead.
+    pub fn diff_resync(mut self, enabled: bool) -> Self {
+        self.diff_resync = enabled;
+        self
+    }
+
     /// Set P2P configuration (requires p2p feature)
     #[cfg(feature = "p2p")]
     pub fn p2p_config(mut self, config: clasp_core::P2PConfig) -> Self {
@@ -80,6 +154,16 @@ impl ClaspBuilder {
             self.reconnect,
             self.reconnect_interval_ms,
         );
+        client.set_request_timeout(self.request_timeout);
+        client.set_keepalive(self.keepalive);
+        client.set_incremental_snapshots(self.incremental_snapshots);
+        client.set_diff_resync(self.diff_resync);
+        if let Some(identity) = self.identity {
+            client.set_identity(identity);
+        }
+        if let Some(governor) = self.governor {
+            client.set_governor(governor);
+        }
 
         // Set P2P config if provided
         #[cfg(feature = "p2p")]
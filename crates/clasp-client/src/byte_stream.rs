@@ -0,0 +1,92 @@
+//! Flow-controlled byte streams
+//!
+//! [`Clasp::subscribe_bytes`](crate::Clasp::subscribe_bytes) subscribes with
+//! a credit window: the router sends at most that many deliveries ahead of
+//! what the application has consumed, and drops the rest. A [`ByteStream`]
+//! hands out raw [`Bytes`] payloads and grants the router more credit as
+//! they are taken, so a fast producer can't run a slow consumer out of
+//! memory. Deliveries that don't carry bytes are consumed and skipped.
+
+use bytes::Bytes;
+use clasp_core::Value;
+use futures::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Credit window used by [`Clasp::subscribe_bytes`](crate::Clasp::subscribe_bytes)
+pub const DEFAULT_CREDIT_WINDOW: u32 = 64;
+
+/// Byte payloads of a flow-controlled subscription
+///
+/// Dropping the stream unsubscribes.
+pub struct ByteStream {
+    id: u32,
+    window: u32,
+    consumed: u32,
+    values: mpsc::UnboundedReceiver<Value>,
+    grants: mpsc::UnboundedSender<u32>,
+}
+
+impl ByteStream {
+    pub(crate) fn new(
+        id: u32,
+        window: u32,
+        values: mpsc::UnboundedReceiver<Value>,
+        grants: mpsc::UnboundedSender<u32>,
+    ) -> Self {
+        Self {
+            id,
+            window,
+            consumed: 0,
+            values,
+            grants,
+        }
+    }
+
+    /// ID of the underlying subscription
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Credit window the subscription was made with
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// Count one delivery as consumed, granting credit back every half window
+    fn consume(&mut self) {
+        self.consumed += 1;
+        if self.consumed >= (self.window / 2).max(1) {
+            let _ = self.grants.send(self.consumed);
+            self.consumed = 0;
+        }
+    }
+}
+
+impl Stream for ByteStream {
+    type Item = Bytes;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Bytes>> {
+        loop {
+            let value = match self.values.poll_recv(cx) {
+                Poll::Ready(Some(value)) => value,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            self.consume();
+            if let Value::Bytes(data) = value {
+                return Poll::Ready(Some(Bytes::from(data)));
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ByteStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ByteStream")
+            .field("id", &self.id)
+            .field("window", &self.window)
+            .finish_non_exhaustive()
+    }
+}
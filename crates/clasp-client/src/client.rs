@@ -2,37 +2,151 @@
 
 use bytes::Bytes;
 use clasp_core::{
-    codec, time::ClockSync, BundleMessage, ErrorMessage, GesturePhase, GetMessage, HelloMessage,
-    Message, PublishMessage, SetMessage, SignalDefinition, SignalType, SubscribeMessage,
-    SubscribeOptions, TimelineData, UnsubscribeMessage, Value, PROTOCOL_VERSION,
+    codec, time::ClockSync, AckMessage, BundleMessage, ChannelMessage, CreditMessage,
+    DeviceIdentity, ErrorMessage, GesturePhase, GetMessage, HelloMessage, Manifest, Message,
+    Origin, ParamValue, PublishMessage, QueryMessage, SetMessage, SetOp, SignalDefinition,
+    SignalType, SnapshotMessage, SubscribeMessage, SubscribeOptions, TimelineData,
+    UnsubscribeMessage, Value, WelcomeMessage, PROTOCOL_VERSION, SESSION_ORIGIN_ADDRESS,
+    SESSION_TOKEN_ADDRESS,
 };
+use clasp_transport::websocket::{WebSocketReceiver, WebSocketSender};
 use clasp_transport::{
-    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+    KeepaliveConfig, TransportEvent, TransportReceiver, TransportSender, WebSocketConfig,
+    WebSocketTransport,
 };
 use dashmap::DashMap;
-use parking_lot::RwLock;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use parking_lot::{Mutex, RwLock};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot, Notify};
 use tracing::{debug, error, info, warn};
 
 use crate::builder::ClaspBuilder;
+use crate::byte_stream::{ByteStream, DEFAULT_CREDIT_WINDOW};
 use crate::error::{ClientError, Result};
+use crate::governor::{Governor, Offer, Throttle};
 #[cfg(feature = "p2p")]
 use crate::p2p;
 #[cfg(feature = "p2p")]
 use clasp_core::{P2PConfig, P2P_SIGNAL_PREFIX};
 
+/// Default time to wait for a reply to a request
+pub(crate) const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Subscription callback type
 pub type SubscriptionCallback = Box<dyn Fn(Value, &str) + Send + Sync>;
 
+type PendingQueries = Mutex<VecDeque<oneshot::Sender<Vec<SignalDefinition>>>>;
+
+/// Requests awaiting an ACK, SNAPSHOT or ERROR, by correlation id
+type PendingRequests = DashMap<u32, oneshot::Sender<std::result::Result<Message, ErrorMessage>>>;
+
+/// Outgoing frames of a connection, shared with its sub-clients
+type SharedSender = Arc<RwLock<Option<mpsc::Sender<Bytes>>>>;
+
+/// Sub-clients on a connection, by channel id
+type SubClients = DashMap<u16, SubClient>;
+
+/// What the receiver task needs to know about a sub-client
+struct SubClient {
+    inbox: Inbox,
+    connected: Arc<RwLock<bool>>,
+    /// Completed by the WELCOME (or ERROR) answering the sub-client's HELLO
+    welcome: Option<oneshot::Sender<std::result::Result<WelcomeMessage, ErrorMessage>>>,
+}
+
+/// Pages of a snapshot received so far
+#[derive(Default)]
+struct PageBuffer {
+    /// Sequence number expected next
+    next: u32,
+    params: Vec<ParamValue>,
+}
+
+/// Router state revision the cache is known to be current to
+#[derive(Debug, Default)]
+struct Resync {
+    /// Revision of the last connect-time snapshot
+    revision: Option<u64>,
+    /// The next snapshot carrying a revision is the connect-time one
+    awaiting: bool,
+}
+
+/// State shared with the receiver task
+#[derive(Clone)]
+struct Inbox {
+    params: Arc<DashMap<String, Value>>,
+    subscriptions: Arc<DashMap<u32, (String, SubscriptionCallback)>>,
+    pending_gets: Arc<DashMap<String, oneshot::Sender<Value>>>,
+    pending_queries: Arc<PendingQueries>,
+    pending_requests: Arc<PendingRequests>,
+    signals: Arc<DashMap<String, SignalDefinition>>,
+    last_error: Arc<RwLock<Option<ErrorMessage>>>,
+    sub_clients: Arc<SubClients>,
+    snapshot_pages: Arc<Mutex<PageBuffer>>,
+    incremental_snapshots: bool,
+    resync: Arc<Mutex<Resync>>,
+    origins: Arc<DashMap<String, Origin>>,
+}
+
+/// Where outgoing frames of a client go, detached from the client itself
+struct Outbox {
+    sender: SharedSender,
+    channel: Option<u16>,
+    sub_clients: Arc<SubClients>,
+}
+
+impl Outbox {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        // A sub-client's frames travel on its channel of the shared connection
+        let data = match self.channel {
+            Some(channel) if !self.sub_clients.contains_key(&channel) => {
+                return Err(ClientError::NotConnected)
+            }
+            Some(channel) => codec::encode_channel_frame(&data, channel)?,
+            None => data,
+        };
+
+        // Clone the sender to avoid holding the lock across await
+        let tx = {
+            let sender = self.sender.read();
+            sender.as_ref().cloned()
+        };
+
+        if let Some(tx) = tx {
+            tx.send(data)
+                .await
+                .map_err(|e| ClientError::SendFailed(e.to_string()))?;
+            Ok(())
+        } else {
+            Err(ClientError::NotConnected)
+        }
+    }
+}
+
+impl Inbox {
+    /// The shared connection is gone, and with it every sub-client
+    fn close_sub_clients(&self) {
+        for entry in self.sub_clients.iter() {
+            *entry.connected.write() = false;
+        }
+        self.sub_clients.clear();
+    }
+}
+
 /// A Clasp client
 pub struct Clasp {
     url: String,
     name: String,
     features: Vec<String>,
-    token: Option<String>,
+    /// Token presented on (re)connect; replaced by [`Clasp::reauthenticate`]
+    token: RwLock<Option<String>>,
+    /// Key pair that signs a fresh proof for every HELLO, in place of the token
+    identity: Option<DeviceIdentity>,
+    /// Origin declared with [`Clasp::declare_origin`], replayed on reconnect
+    declared_origin: RwLock<Option<Origin>>,
     reconnect: bool,
     reconnect_interval_ms: u64,
 
@@ -43,7 +157,16 @@ pub struct Clasp {
     connected: Arc<RwLock<bool>>,
 
     /// Sender for outgoing messages
-    sender: RwLock<Option<mpsc::Sender<Bytes>>>,
+    sender: SharedSender,
+
+    /// Channel of a sub-client on its parent's connection
+    channel: Option<u16>,
+
+    /// Sub-clients sharing this connection
+    sub_clients: Arc<SubClients>,
+
+    /// Channel ID counter, shared with sub-clients
+    next_channel: Arc<AtomicU16>,
 
     /// Local param cache
     params: Arc<DashMap<String, Value>>,
@@ -51,6 +174,9 @@ pub struct Clasp {
     /// Subscriptions
     subscriptions: Arc<DashMap<u32, (String, SubscriptionCallback)>>,
 
+    /// Options of each subscription, replayed on reconnect
+    subscription_options: Arc<DashMap<u32, SubscribeOptions>>,
+
     /// Subscription ID counter
     next_sub_id: AtomicU32,
 
@@ -60,6 +186,39 @@ pub struct Clasp {
     /// Pending get requests
     pending_gets: Arc<DashMap<String, oneshot::Sender<Value>>>,
 
+    /// Pending query requests, answered in order by RESULT messages
+    pending_queries: Arc<PendingQueries>,
+
+    /// Correlation ID counter
+    next_correlation_id: AtomicU32,
+
+    /// How long GET, QUERY and confirmed requests wait for a reply
+    request_timeout: Duration,
+
+    /// Ping and idle detection for the router connection
+    keepalive: KeepaliveConfig,
+
+    /// Outbound rate limits for set/stream (None = unlimited)
+    throttle: Option<Arc<Throttle>>,
+
+    /// Paged snapshot being reassembled
+    snapshot_pages: Arc<Mutex<PageBuffer>>,
+
+    /// Apply snapshot pages as they arrive instead of after the last one
+    incremental_snapshots: bool,
+
+    /// Ask for changes since the last synced revision when reconnecting
+    diff_resync: bool,
+
+    /// Revision of the last connect-time snapshot
+    resync: Arc<Mutex<Resync>>,
+
+    /// Origin of the last enveloped delivery per address
+    origins: Arc<DashMap<String, Origin>>,
+
+    /// Pending correlated requests
+    pending_requests: Arc<PendingRequests>,
+
     /// Announced signals (from server)
     signals: Arc<DashMap<String, SignalDefinition>>,
 
@@ -101,17 +260,34 @@ impl Clasp {
             url: url.to_string(),
             name,
             features,
-            token,
+            token: RwLock::new(token),
+            identity: None,
+            declared_origin: RwLock::new(None),
             reconnect,
             reconnect_interval_ms,
             session_id: RwLock::new(None),
             connected: Arc::new(RwLock::new(false)),
-            sender: RwLock::new(None),
+            sender: Arc::new(RwLock::new(None)),
+            channel: None,
+            sub_clients: Arc::new(DashMap::new()),
+            next_channel: Arc::new(AtomicU16::new(1)),
             params: Arc::new(DashMap::new()),
             subscriptions: Arc::new(DashMap::new()),
+            subscription_options: Arc::new(DashMap::new()),
             next_sub_id: AtomicU32::new(1),
             clock: RwLock::new(ClockSync::new()),
             pending_gets: Arc::new(DashMap::new()),
+            pending_queries: Arc::new(Mutex::new(VecDeque::new())),
+            next_correlation_id: AtomicU32::new(1),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            keepalive: KeepaliveConfig::default(),
+            throttle: None,
+            snapshot_pages: Arc::new(Mutex::new(PageBuffer::default())),
+            incremental_snapshots: false,
+            diff_resync: false,
+            resync: Arc::new(Mutex::new(Resync::default())),
+            origins: Arc::new(DashMap::new()),
+            pending_requests: Arc::new(DashMap::new()),
             signals: Arc::new(DashMap::new()),
             last_error: Arc::new(RwLock::new(None)),
             reconnect_attempts: Arc::new(AtomicU32::new(0)),
@@ -125,17 +301,96 @@ impl Clasp {
         }
     }
 
+    /// Set the request timeout (internal, called by builder)
+    pub(crate) fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
+    }
+
+    /// Set the keepalive (internal, called by builder)
+    pub(crate) fn set_keepalive(&mut self, keepalive: KeepaliveConfig) {
+        self.keepalive = keepalive;
+    }
+
+    /// Set how paged snapshots are delivered (internal, called by builder)
+    pub(crate) fn set_incremental_snapshots(&mut self, incremental: bool) {
+        self.incremental_snapshots = incremental;
+    }
+
+    /// Set the device identity (internal, called by builder)
+    pub(crate) fn set_identity(&mut self, identity: DeviceIdentity) {
+        self.identity = Some(identity);
+    }
+
+    /// Token for a HELLO: a fresh device proof, or the configured token
+    fn hello_token(&self) -> Option<String> {
+        match &self.identity {
+            Some(identity) => Some(identity.proof()),
+            None => self.token.read().clone(),
+        }
+    }
+
+    /// Set whether reconnects resync with a diff (internal, called by builder)
+    pub(crate) fn set_diff_resync(&mut self, enabled: bool) {
+        self.diff_resync = enabled;
+    }
+
+    /// Revision to resync from after a reconnect, if diff resync is on
+    fn resync_since(&self) -> Option<u64> {
+        if self.diff_resync {
+            self.resync.lock().revision
+        } else {
+            None
+        }
+    }
+
+    /// Set the outbound governor (internal, called by builder)
+    pub(crate) fn set_governor(&mut self, governor: Governor) {
+        self.throttle = Some(Arc::new(Throttle::new(governor)));
+    }
+
+    /// Open the WebSocket to the router
+    async fn open_transport(&self) -> Result<(WebSocketSender, WebSocketReceiver)> {
+        let config = WebSocketConfig {
+            keepalive: self.keepalive,
+            ..Default::default()
+        };
+        Ok(WebSocketTransport::connect_with_config(&self.url, config).await?)
+    }
+
     /// Set P2P configuration (internal, called by builder)
     #[cfg(feature = "p2p")]
     pub(crate) fn set_p2p_config(&mut self, config: P2PConfig) {
         self.p2p_config = Some(config);
     }
 
+    fn inbox(&self) -> Inbox {
+        Inbox {
+            params: Arc::clone(&self.params),
+            subscriptions: Arc::clone(&self.subscriptions),
+            pending_gets: Arc::clone(&self.pending_gets),
+            pending_queries: Arc::clone(&self.pending_queries),
+            pending_requests: Arc::clone(&self.pending_requests),
+            signals: Arc::clone(&self.signals),
+            last_error: Arc::clone(&self.last_error),
+            sub_clients: Arc::clone(&self.sub_clients),
+            snapshot_pages: Arc::clone(&self.snapshot_pages),
+            incremental_snapshots: self.incremental_snapshots,
+            resync: Arc::clone(&self.resync),
+            origins: Arc::clone(&self.origins),
+        }
+    }
+
     /// Create a builder
     pub fn builder(url: &str) -> ClaspBuilder {
         ClaspBuilder::new(url)
     }
 
+    /// Create a builder for a router-less LAN mesh node
+    #[cfg(feature = "mesh")]
+    pub fn mesh_builder() -> crate::mesh::MeshBuilder {
+        crate::mesh::MeshBuilder::new()
+    }
+
     /// Connect to server (convenience method)
     pub async fn connect_to(url: &str) -> Result<Self> {
         ClaspBuilder::new(url).connect().await
@@ -150,7 +405,7 @@ impl Clasp {
         info!("Connecting to {}", self.url);
 
         // Connect WebSocket
-        let (sender, mut receiver) = <WebSocketTransport as Transport>::connect(&self.url).await?;
+        let (sender, mut receiver) = self.open_transport().await?;
 
         // Create send channel
         let (tx, mut rx) = mpsc::channel::<Bytes>(100);
@@ -176,7 +431,8 @@ impl Clasp {
             name: self.name.clone(),
             features: self.features.clone(),
             capabilities: None,
-            token: self.token.clone(),
+            token: self.hello_token(),
+            since: None,
         });
 
         self.send_message(&hello).await?;
@@ -189,6 +445,7 @@ impl Clasp {
                         Ok((Message::Welcome(welcome), _)) => {
                             *self.session_id.write() = Some(welcome.session.clone());
                             *connected.write() = true;
+                            self.resync.lock().awaiting = true;
 
                             // Sync clock
                             self.clock.write().process_sync(
@@ -244,6 +501,10 @@ impl Clasp {
                             info!("Connected, session: {}", welcome.session);
                             break;
                         }
+                        Ok((Message::Error(error), _)) => {
+                            warn!("Connection rejected: {} ({})", error.message, error.code);
+                            return Err(error.into());
+                        }
                         Ok((msg, _)) => {
                             debug!("Received during handshake: {:?}", msg);
                         }
@@ -274,11 +535,7 @@ impl Clasp {
         self.intentionally_closed.store(false, Ordering::SeqCst);
 
         // Spawn receiver task
-        let params = Arc::clone(&self.params);
-        let subscriptions = Arc::clone(&self.subscriptions);
-        let pending_gets = Arc::clone(&self.pending_gets);
-        let signals = Arc::clone(&self.signals);
-        let last_error = Arc::clone(&self.last_error);
+        let inbox = self.inbox();
         let connected_clone = Arc::clone(&self.connected);
         let reconnect_notify = Arc::clone(&self.reconnect_notify);
         let intentionally_closed = Arc::clone(&self.intentionally_closed);
@@ -296,19 +553,13 @@ impl Clasp {
                                 // Forward P2P signals to P2P manager (handled in subscription callback)
                                 // P2P announce is also handled in subscription
                             }
-                            handle_message(
-                                &msg,
-                                &params,
-                                &subscriptions,
-                                &pending_gets,
-                                &signals,
-                                &last_error,
-                            );
+                            handle_message(&msg, &inbox);
                         }
                     }
                     TransportEvent::Disconnected { reason } => {
                         info!("Disconnected: {:?}", reason);
                         *connected_clone.write() = false;
+                        inbox.close_sub_clients();
 
                         // Trigger reconnect if enabled and not intentionally closed
                         if reconnect_enabled && !intentionally_closed.load(Ordering::SeqCst) {
@@ -376,6 +627,14 @@ impl Clasp {
                             info!("Reconnected successfully");
                             client.reconnect_attempts.store(0, Ordering::SeqCst);
 
+                            let declared = client.declared_origin.read().clone();
+                            if let Some(origin) = declared {
+                                let msg = origin_message(&origin, None);
+                                if let Err(e) = client.send_message(&msg).await {
+                                    warn!("Failed to redeclare origin: {}", e);
+                                }
+                            }
+
                             // Resubscribe to all patterns
                             if let Err(e) = client.resubscribe_all().await {
                                 warn!("Failed to resubscribe: {}", e);
@@ -396,7 +655,7 @@ impl Clasp {
         info!("Attempting to reconnect to {}", self.url);
 
         // Connect WebSocket
-        let (sender, mut receiver) = <WebSocketTransport as Transport>::connect(&self.url).await?;
+        let (sender, mut receiver) = self.open_transport().await?;
 
         // Create send channel
         let (tx, mut rx) = mpsc::channel::<Bytes>(100);
@@ -420,7 +679,8 @@ impl Clasp {
             name: self.name.clone(),
             features: self.features.clone(),
             capabilities: None,
-            token: self.token.clone(),
+            token: self.hello_token(),
+            since: self.resync_since(),
         });
 
         self.send_message(&hello).await?;
@@ -435,6 +695,7 @@ impl Clasp {
                     Ok((Message::Welcome(welcome), _)) => {
                         *self.session_id.write() = Some(welcome.session.clone());
                         *self.connected.write() = true;
+                        self.resync.lock().awaiting = true;
 
                         self.clock.write().process_sync(
                             clasp_core::time::now(),
@@ -446,6 +707,9 @@ impl Clasp {
                         info!("Reconnected, session: {}", welcome.session);
                         break;
                     }
+                    Ok((Message::Error(error), _)) => {
+                        return Err(error.into());
+                    }
                     Ok((msg, _)) => {
                         debug!("Received during reconnect handshake: {:?}", msg);
                     }
@@ -474,11 +738,7 @@ impl Clasp {
         }
 
         // Spawn new receiver task
-        let params = Arc::clone(&self.params);
-        let subscriptions = Arc::clone(&self.subscriptions);
-        let pending_gets = Arc::clone(&self.pending_gets);
-        let signals = Arc::clone(&self.signals);
-        let last_error = Arc::clone(&self.last_error);
+        let inbox = self.inbox();
         let connected_clone = Arc::clone(&self.connected);
         let reconnect_notify = Arc::clone(&self.reconnect_notify);
         let intentionally_closed = Arc::clone(&self.intentionally_closed);
@@ -489,19 +749,13 @@ impl Clasp {
                 match event {
                     TransportEvent::Data(data) => {
                         if let Ok((msg, _)) = codec::decode(&data) {
-                            handle_message(
-                                &msg,
-                                &params,
-                                &subscriptions,
-                                &pending_gets,
-                                &signals,
-                                &last_error,
-                            );
+                            handle_message(&msg, &inbox);
                         }
                     }
                     TransportEvent::Disconnected { reason } => {
                         info!("Disconnected: {:?}", reason);
                         *connected_clone.write() = false;
+                        inbox.close_sub_clients();
 
                         if reconnect_enabled && !intentionally_closed.load(Ordering::SeqCst) {
                             reconnect_notify.notify_one();
@@ -528,12 +782,21 @@ impl Clasp {
             .map(|entry| (*entry.key(), entry.value().0.clone()))
             .collect();
 
+        let since = self.resync_since();
         for (id, pattern) in subs {
+            let mut options = self
+                .subscription_options
+                .get(&id)
+                .map(|o| o.value().clone())
+                .unwrap_or_default();
+            // A since given at subscribe time is stale by now
+            options.since = since;
             let msg = Message::Subscribe(SubscribeMessage {
                 id,
                 pattern: pattern.clone(),
                 types: vec![],
-                options: Some(SubscribeOptions::default()),
+                options: Some(options),
+                correlation_id: None,
             });
 
             self.send_message(&msg).await?;
@@ -553,6 +816,15 @@ impl Clasp {
         self.session_id.read().clone()
     }
 
+    /// Router state revision the local cache was last synced to
+    ///
+    /// Set from the snapshot the router sends after each (re)connect. With
+    /// [`ClaspBuilder::diff_resync`](crate::ClaspBuilder::diff_resync) on,
+    /// reconnects ask for only the changes since this revision.
+    pub fn synced_revision(&self) -> Option<u64> {
+        self.resync.lock().revision
+    }
+
     /// Get current server time (microseconds)
     pub fn time(&self) -> u64 {
         self.clock.read().server_time()
@@ -566,24 +838,71 @@ impl Clasp {
 
     /// Send raw bytes
     async fn send_raw(&self, data: Bytes) -> Result<()> {
-        // Clone the sender to avoid holding the lock across await
-        let tx = {
-            let sender = self.sender.read();
-            sender.as_ref().cloned()
+        self.outbox().send(data).await
+    }
+
+    fn outbox(&self) -> Outbox {
+        Outbox {
+            sender: Arc::clone(&self.sender),
+            channel: self.channel,
+            sub_clients: Arc::clone(&self.sub_clients),
+        }
+    }
+
+    /// Send a message to `address` through the governor, if any
+    ///
+    /// Held-back messages are replaced by newer ones and go out once the
+    /// address's interval has passed.
+    async fn send_governed(&self, address: &str, message: &Message) -> Result<()> {
+        let data = codec::encode(message)?;
+        let Some(throttle) = &self.throttle else {
+            return self.send_raw(data).await;
         };
+        if !self.is_connected() {
+            return Err(ClientError::NotConnected);
+        }
 
-        if let Some(tx) = tx {
-            tx.send(data)
-                .await
-                .map_err(|e| ClientError::SendFailed(e.to_string()))?;
-            Ok(())
-        } else {
-            Err(ClientError::NotConnected)
+        match throttle.offer(address, data) {
+            Offer::Send(data) => self.send_raw(data).await,
+            Offer::Held(None) => Ok(()),
+            Offer::Held(Some(delay)) => {
+                let throttle = Arc::clone(throttle);
+                let outbox = self.outbox();
+                let address = address.to_string();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    if let Some(data) = throttle.take(&address) {
+                        if let Err(e) = outbox.send(data).await {
+                            debug!("Dropped coalesced update to {}: {}", address, e);
+                        }
+                    }
+                });
+                Ok(())
+            }
         }
     }
 
     /// Subscribe to an address pattern
     pub async fn subscribe<F>(&self, pattern: &str, callback: F) -> Result<u32>
+    where
+        F: Fn(Value, &str) + Send + Sync + 'static,
+    {
+        self.subscribe_with_options(pattern, SubscribeOptions::default(), callback)
+            .await
+    }
+
+    /// Subscribe with options, e.g. a `batch` window for dashboards
+    ///
+    /// With a batch window the router delivers param changes as one SNAPSHOT
+    /// per window; `callback` still runs once per changed address. A `qos`
+    /// of [`QoS::Fire`](clasp_core::QoS::Fire) takes deliveries lossily, so a
+    /// slow monitor has them dropped quietly instead of reported.
+    pub async fn subscribe_with_options<F>(
+        &self,
+        pattern: &str,
+        options: SubscribeOptions,
+        callback: F,
+    ) -> Result<u32>
     where
         F: Fn(Value, &str) + Send + Sync + 'static,
     {
@@ -592,13 +911,15 @@ impl Clasp {
         // Store callback
         self.subscriptions
             .insert(id, (pattern.to_string(), Box::new(callback)));
+        self.subscription_options.insert(id, options.clone());
 
         // Send subscribe message
         let msg = Message::Subscribe(SubscribeMessage {
             id,
             pattern: pattern.to_string(),
             types: vec![],
-            options: Some(SubscribeOptions::default()),
+            options: Some(options),
+            correlation_id: None,
         });
 
         self.send_message(&msg).await?;
@@ -607,6 +928,58 @@ impl Clasp {
         Ok(id)
     }
 
+    /// Subscribe to raw byte payloads with flow control
+    ///
+    /// The router keeps at most [`DEFAULT_CREDIT_WINDOW`] deliveries in
+    /// flight ahead of what the returned stream has handed out, dropping
+    /// anything beyond that. Dropping the stream unsubscribes.
+    pub async fn subscribe_bytes(&self, pattern: &str) -> Result<ByteStream> {
+        self.subscribe_bytes_with_window(pattern, DEFAULT_CREDIT_WINDOW)
+            .await
+    }
+
+    /// Subscribe to raw byte payloads with a credit window of `window`
+    /// deliveries
+    pub async fn subscribe_bytes_with_window(
+        &self,
+        pattern: &str,
+        window: u32,
+    ) -> Result<ByteStream> {
+        let window = window.max(1);
+        let (values_tx, values) = mpsc::unbounded_channel();
+        let options = SubscribeOptions {
+            credits: Some(window),
+            ..Default::default()
+        };
+        let id = self
+            .subscribe_with_options(pattern, options, move |value, _| {
+                let _ = values_tx.send(value);
+            })
+            .await?;
+
+        // Grants go out from a task of their own since the stream is polled
+        // synchronously; it unsubscribes once the stream is gone.
+        let (grants, mut grants_rx) = mpsc::unbounded_channel();
+        let outbox = self.outbox();
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let subscription_options = Arc::clone(&self.subscription_options);
+        tokio::spawn(async move {
+            while let Some(credits) = grants_rx.recv().await {
+                let grant = Message::Credit(CreditMessage { id, credits });
+                if let Ok(data) = codec::encode(&grant) {
+                    let _ = outbox.send(data).await;
+                }
+            }
+            subscriptions.remove(&id);
+            subscription_options.remove(&id);
+            if let Ok(data) = codec::encode(&Message::Unsubscribe(UnsubscribeMessage { id })) {
+                let _ = outbox.send(data).await;
+            }
+        });
+
+        Ok(ByteStream::new(id, window, values, grants))
+    }
+
     /// Shorthand for subscribe
     pub async fn on<F>(&self, pattern: &str, callback: F) -> Result<u32>
     where
@@ -618,6 +991,7 @@ impl Clasp {
     /// Unsubscribe
     pub async fn unsubscribe(&self, id: u32) -> Result<()> {
         self.subscriptions.remove(&id);
+        self.subscription_options.remove(&id);
 
         let msg = Message::Unsubscribe(UnsubscribeMessage { id });
         self.send_message(&msg).await?;
@@ -626,6 +1000,9 @@ impl Clasp {
     }
 
     /// Set a parameter value
+    ///
+    /// With a [`Governor`] configured, a fast series of SETs to one address
+    /// is thinned out to its rate limit; the latest value always goes out.
     pub async fn set(&self, address: &str, value: impl Into<Value>) -> Result<()> {
         let msg = Message::Set(SetMessage {
             address: address.to_string(),
@@ -633,9 +1010,140 @@ impl Clasp {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
-        self.send_message(&msg).await
+        self.send_governed(address, &msg).await
+    }
+
+    /// Set a parameter value and wait for the router to acknowledge it.
+    ///
+    /// Resolves with the ACK for this SET (carrying the new revision), or
+    /// with the typed error the router rejected it with.
+    pub async fn set_with_ack(&self, address: &str, value: impl Into<Value>) -> Result<AckMessage> {
+        let value = value.into();
+        let reply = self
+            .request(|correlation_id| {
+                Message::Set(SetMessage {
+                    address: address.to_string(),
+                    value,
+                    revision: None,
+                    lock: false,
+                    unlock: false,
+                    op: None,
+                    correlation_id: Some(correlation_id),
+                })
+            })
+            .await?;
+
+        match reply {
+            Message::Ack(ack) => Ok(ack),
+            other => Err(ClientError::Other(format!("Unexpected reply: {:?}", other))),
+        }
+    }
+
+    /// Set a parameter value and wait until the router has applied it.
+    ///
+    /// Returns the new revision. Fails with [`ClientError::Timeout`] if no
+    /// ACK arrives within the request timeout, or with the typed error the
+    /// router rejected the SET with. Plain [`Clasp::set`] stays
+    /// fire-and-forget.
+    pub async fn set_confirmed(&self, address: &str, value: impl Into<Value>) -> Result<u64> {
+        let ack = self.set_with_ack(address, value).await?;
+        Ok(ack.revision.unwrap_or_default())
+    }
+
+    /// Set a param only if it is still at `expected_revision`.
+    ///
+    /// Pass the revision last seen for the param (from an ACK or snapshot),
+    /// or 0 to create it only if it doesn't exist. Returns the new revision.
+    /// If another writer got there first, fails with
+    /// [`ClientError::RevisionConflict`] carrying the revision and value the
+    /// router holds now, ready to merge and retry.
+    pub async fn compare_and_set(
+        &self,
+        address: &str,
+        expected_revision: u64,
+        value: impl Into<Value>,
+    ) -> Result<u64> {
+        let value = value.into();
+        let reply = self
+            .request(|correlation_id| {
+                Message::Set(SetMessage {
+                    address: address.to_string(),
+                    value,
+                    revision: Some(expected_revision),
+                    lock: false,
+                    unlock: false,
+                    op: None,
+                    correlation_id: Some(correlation_id),
+                })
+            })
+            .await?;
+
+        match reply {
+            Message::Ack(ack) => Ok(ack.revision.unwrap_or_default()),
+            other => Err(ClientError::Other(format!("Unexpected reply: {:?}", other))),
+        }
+    }
+
+    /// Set every retained param matching `pattern` to `value`.
+    ///
+    /// The router expands the pattern (e.g. `/lumen/scene/0/layer/*/opacity`)
+    /// against the addresses it holds and applies one SET per match, so
+    /// subscribers see ordinary changes. Resolves once the router has applied
+    /// them. Fails if the router has wildcard writes disabled or the pattern
+    /// matches more addresses than its limit; params locked by other
+    /// sessions are left unchanged.
+    pub async fn set_matching(&self, pattern: &str, value: impl Into<Value>) -> Result<()> {
+        self.set_with_ack(pattern, value).await.map(|_| ())
+    }
+
+    /// Update a param relative to the value the router holds.
+    ///
+    /// The router applies `op` atomically and resolves with the resulting
+    /// value, so concurrent updates (several encoder knobs sending ticks) are
+    /// never lost. Subscribers receive the resulting value as a plain SET.
+    /// Fails with [`ClientError::InvalidAddress`] if the param holds no value,
+    /// or with an InvalidValue error if `op` doesn't apply to it.
+    pub async fn set_op(
+        &self,
+        address: &str,
+        op: SetOp,
+        operand: impl Into<Value>,
+    ) -> Result<Value> {
+        let operand = operand.into();
+        let reply = self
+            .request(|correlation_id| {
+                Message::Set(SetMessage {
+                    address: address.to_string(),
+                    value: operand,
+                    revision: None,
+                    lock: false,
+                    unlock: false,
+                    op: Some(op),
+                    correlation_id: Some(correlation_id),
+                })
+            })
+            .await?;
+
+        match reply {
+            Message::Ack(AckMessage {
+                value: Some(value), ..
+            }) => Ok(value),
+            other => Err(ClientError::Other(format!("Unexpected reply: {:?}", other))),
+        }
+    }
+
+    /// Add `delta` to a numeric param (negative to decrement)
+    pub async fn increment(&self, address: &str, delta: impl Into<Value>) -> Result<Value> {
+        self.set_op(address, SetOp::Add, delta).await
+    }
+
+    /// Invert a bool param
+    pub async fn toggle(&self, address: &str) -> Result<Value> {
+        self.set_op(address, SetOp::Toggle, Value::Null).await
     }
 
     /// Set with lock
@@ -646,6 +1154,8 @@ impl Clasp {
             revision: None,
             lock: true,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         self.send_message(&msg).await
@@ -659,6 +1169,8 @@ impl Clasp {
             revision: None,
             lock: false,
             unlock: true,
+            op: None,
+            correlation_id: None,
         });
 
         self.send_message(&msg).await
@@ -671,30 +1183,33 @@ impl Clasp {
             return Ok(value.clone());
         }
 
-        // Request from server
+        // Request from server. Routers that echo the correlation id also
+        // answer unknown addresses with an ERROR; older ones only reply with
+        // a SNAPSHOT, which still resolves the request by address.
         let (tx, rx) = oneshot::channel();
         let address_key = address.to_string();
         self.pending_gets.insert(address_key.clone(), tx);
 
-        let msg = Message::Get(GetMessage {
-            address: address.to_string(),
-        });
-        self.send_message(&msg).await?;
+        let result = tokio::select! {
+            reply = self.request(|correlation_id| {
+                Message::Get(GetMessage {
+                    address: address.to_string(),
+                    correlation_id: Some(correlation_id),
+                })
+            }) => reply.and_then(|reply| match reply {
+                Message::Snapshot(snapshot) => snapshot
+                    .params
+                    .into_iter()
+                    .find(|param| param.address == address)
+                    .map(|param| param.value)
+                    .ok_or_else(|| ClientError::InvalidAddress(address.to_string())),
+                other => Err(ClientError::Other(format!("Unexpected reply: {:?}", other))),
+            }),
+            value = rx => value.map_err(|_| ClientError::Other("Get cancelled".to_string())),
+        };
 
-        // Wait for response (with timeout)
-        match tokio::time::timeout(std::time::Duration::from_secs(5), rx).await {
-            Ok(Ok(value)) => Ok(value),
-            Ok(Err(_)) => {
-                // Cancelled - remove from pending
-                self.pending_gets.remove(&address_key);
-                Err(ClientError::Other("Get cancelled".to_string()))
-            }
-            Err(_) => {
-                // Timeout - remove from pending to prevent memory leak
-                self.pending_gets.remove(&address_key);
-                Err(ClientError::Timeout)
-            }
-        }
+        self.pending_gets.remove(&address_key);
+        result
     }
 
     /// Emit an event
@@ -715,7 +1230,31 @@ impl Clasp {
         self.send_message(&msg).await
     }
 
+    /// Emit an event and wait until the router has accepted it.
+    ///
+    /// The event travels as a single-message bundle so the router can
+    /// acknowledge it; subscribers receive an ordinary event.
+    pub async fn emit_confirmed(&self, address: &str, payload: impl Into<Value>) -> Result<()> {
+        let msg = Message::Publish(PublishMessage {
+            address: address.to_string(),
+            signal: Some(SignalType::Event),
+            value: None,
+            payload: Some(payload.into()),
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: Some(self.time()),
+            timeline: None,
+        });
+
+        self.bundle_confirmed(vec![msg]).await.map(|_| ())
+    }
+
     /// Send stream sample
+    ///
+    /// Rate limited and coalesced like [`Clasp::set`] when a [`Governor`] is
+    /// configured.
     pub async fn stream(&self, address: &str, value: impl Into<Value>) -> Result<()> {
         let msg = Message::Publish(PublishMessage {
             address: address.to_string(),
@@ -730,7 +1269,43 @@ impl Clasp {
             timeline: None,
         });
 
-        self.send_message(&msg).await
+        self.send_governed(address, &msg).await
+    }
+
+    /// Send stream sample that the router drops if it can't deliver it
+    /// within `ttl`
+    ///
+    /// For high-rate positional data, where a late sample is worse than none.
+    pub async fn stream_with_ttl(
+        &self,
+        address: &str,
+        value: impl Into<Value>,
+        ttl: Duration,
+    ) -> Result<()> {
+        let msg = Message::Publish(PublishMessage {
+            address: address.to_string(),
+            signal: Some(SignalType::Stream),
+            value: Some(value.into()),
+            payload: None,
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: Some(self.time()),
+            timeline: None,
+        });
+
+        self.send_with_ttl(&msg, ttl).await
+    }
+
+    /// Send a message with a time-to-live
+    ///
+    /// Copies the router is still holding for a slow subscriber once `ttl`
+    /// has passed are dropped instead of delivered. A SET still updates the
+    /// router's state.
+    pub async fn send_with_ttl(&self, message: &Message, ttl: Duration) -> Result<()> {
+        let data = codec::encode_with_ttl(message, ttl)?;
+        self.send_raw(data).await
     }
 
     /// Send gesture input
@@ -818,16 +1393,40 @@ impl Clasp {
         let msg = Message::Bundle(BundleMessage {
             timestamp: None,
             messages,
+            correlation_id: None,
         });
 
         self.send_message(&msg).await
     }
 
+    /// Send an atomic bundle and wait until the router has applied it.
+    ///
+    /// The router validates the whole bundle before applying any of it, so
+    /// an error means none of the messages took effect. Returns the ACK,
+    /// whose revision is that of the last SET in the bundle.
+    pub async fn bundle_confirmed(&self, messages: Vec<Message>) -> Result<AckMessage> {
+        let reply = self
+            .request(|correlation_id| {
+                Message::Bundle(BundleMessage {
+                    timestamp: None,
+                    messages,
+                    correlation_id: Some(correlation_id),
+                })
+            })
+            .await?;
+
+        match reply {
+            Message::Ack(ack) => Ok(ack),
+            other => Err(ClientError::Other(format!("Unexpected reply: {:?}", other))),
+        }
+    }
+
     /// Send scheduled bundle
     pub async fn bundle_at(&self, messages: Vec<Message>, time: u64) -> Result<()> {
         let msg = Message::Bundle(BundleMessage {
             timestamp: Some(time),
             messages,
+            correlation_id: None,
         });
 
         self.send_message(&msg).await
@@ -843,7 +1442,132 @@ impl Clasp {
     pub async fn close(&self) {
         self.intentionally_closed.store(true, Ordering::SeqCst);
         *self.connected.write() = false;
-        *self.sender.write() = None;
+
+        // A sub-client only closes its channel; the connection stays up
+        if let Some(channel) = self.channel {
+            if self.sub_clients.remove(&channel).is_some() {
+                let tx = self.sender.read().clone();
+                let close = codec::encode(&Message::Channel(ChannelMessage::close(channel)));
+                if let (Some(tx), Ok(close)) = (tx, close) {
+                    let _ = tx.send(close).await;
+                }
+            }
+            return;
+        }
+
+        // Let the router drop the sub-sessions along with this one
+        let channels: Vec<u16> = self.sub_clients.iter().map(|e| *e.key()).collect();
+        self.inbox().close_sub_clients();
+        let tx = self.sender.write().take();
+        if let Some(tx) = tx {
+            for channel in channels {
+                if let Ok(close) = codec::encode(&Message::Channel(ChannelMessage::close(channel)))
+                {
+                    let _ = tx.send(close).await;
+                }
+            }
+        }
+    }
+
+    /// Open a logical client that shares this client's connection
+    ///
+    /// The sub-client gets its own session on the router, with its own name,
+    /// subscriptions and param cache, but no socket of its own; the router
+    /// must allow connection sharing. Sub-clients close with the shared
+    /// connection and are not reopened after a reconnect.
+    pub async fn sub_client(&self, name: &str) -> Result<Clasp> {
+        self.open_sub_client(name, None).await
+    }
+
+    /// Open a sub-client that authenticates with its own token
+    pub async fn sub_client_with_token(&self, name: &str, token: &str) -> Result<Clasp> {
+        self.open_sub_client(name, Some(token.to_string())).await
+    }
+
+    async fn open_sub_client(&self, name: &str, token: Option<String>) -> Result<Clasp> {
+        if !self.is_connected() {
+            return Err(ClientError::NotConnected);
+        }
+        let channel = (0..=u16::MAX)
+            .map(|_| self.next_channel.fetch_add(1, Ordering::SeqCst))
+            .find(|id| !self.sub_clients.contains_key(id))
+            .ok_or_else(|| ClientError::Other("no free channel".to_string()))?;
+
+        let mut client = Clasp::new(
+            &self.url,
+            name.to_string(),
+            self.features.clone(),
+            token,
+            false,
+            self.reconnect_interval_ms,
+        );
+        client.request_timeout = self.request_timeout;
+        client.incremental_snapshots = self.incremental_snapshots;
+        client.throttle = self
+            .throttle
+            .as_ref()
+            .map(|throttle| Arc::new(Throttle::new(throttle.governor().clone())));
+        client.sender = Arc::clone(&self.sender);
+        client.channel = Some(channel);
+        client.sub_clients = Arc::clone(&self.sub_clients);
+        client.next_channel = Arc::clone(&self.next_channel);
+
+        let (welcome_tx, welcome_rx) = oneshot::channel();
+        self.sub_clients.insert(
+            channel,
+            SubClient {
+                inbox: client.inbox(),
+                connected: Arc::clone(&client.connected),
+                welcome: Some(welcome_tx),
+            },
+        );
+        let hello = Message::Hello(HelloMessage {
+            version: PROTOCOL_VERSION,
+            name: client.name.clone(),
+            features: client.features.clone(),
+            capabilities: None,
+            token: client.hello_token(),
+            since: None,
+        });
+        if let Err(e) = client.send_message(&hello).await {
+            self.sub_clients.remove(&channel);
+            return Err(e);
+        }
+
+        let welcome = match tokio::time::timeout(self.request_timeout, welcome_rx).await {
+            Ok(Ok(Ok(welcome))) => welcome,
+            Ok(Ok(Err(error))) => {
+                warn!("Sub-client rejected: {} ({})", error.message, error.code);
+                self.sub_clients.remove(&channel);
+                return Err(error.into());
+            }
+            Ok(Err(_)) => {
+                return Err(ClientError::ConnectionFailed("Channel closed".to_string()));
+            }
+            Err(_) => {
+                client.close().await;
+                return Err(ClientError::Timeout);
+            }
+        };
+
+        *client.session_id.write() = Some(welcome.session.clone());
+        *client.connected.write() = true;
+        client.clock.write().process_sync(
+            clasp_core::time::now(),
+            welcome.time,
+            welcome.time,
+            clasp_core::time::now(),
+        );
+        info!(
+            "Sub-client {} opened on channel {}, session: {}",
+            client.name, channel, welcome.session
+        );
+        Ok(client)
+    }
+
+    /// Channel of this sub-client on the shared connection
+    pub fn channel(&self) -> Option<u16> {
+        self.channel
     }
 
     /// Get all announced signals
@@ -860,6 +1584,121 @@ impl Clasp {
             .collect()
     }
 
+    /// Ask the server for signal definitions matching a pattern.
+    ///
+    /// Returned signals are also merged into [`Clasp::signals`].
+    pub async fn query(&self, pattern: &str) -> Result<Vec<SignalDefinition>> {
+        let (tx, rx) = oneshot::channel();
+        self.pending_queries.lock().push_back(tx);
+
+        let msg = Message::Query(QueryMessage {
+            pattern: pattern.to_string(),
+        });
+        if let Err(e) = self.send_message(&msg).await {
+            self.pending_queries.lock().pop_back();
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.request_timeout, rx).await {
+            Ok(Ok(signals)) => Ok(signals),
+            Ok(Err(_)) => Err(ClientError::Other("Query cancelled".to_string())),
+            Err(_) => {
+                // Drop our abandoned slot so later results stay in step
+                self.pending_queries.lock().retain(|tx| !tx.is_closed());
+                Err(ClientError::Timeout)
+            }
+        }
+    }
+
+    /// Publish a manifest of the addresses this application owns.
+    ///
+    /// The manifest is set at `/$manifest/<app>`, where tools can read it.
+    /// The router registers its signals for [`Clasp::query`] and, with
+    /// manifest validation on, checks writes under the namespace against
+    /// it.
+    pub async fn publish_manifest(&self, manifest: &Manifest) -> Result<()> {
+        manifest.validate()?;
+        self.set_with_ack(&manifest.address(), manifest.to_value())
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch the manifest an application published
+    pub async fn manifest(&self, app: &str) -> Result<Manifest> {
+        let address = format!("{}{}", clasp_core::MANIFEST_PREFIX, app);
+        let value = self.get(&address).await?;
+        Ok(Manifest::from_value(&value)?)
+    }
+
+    /// Present a new token for the current session.
+    ///
+    /// The router validates the token and swaps the session's scopes and
+    /// expiry in place, so a token can be rotated before the old one expires
+    /// without reconnecting. The new token is also used for later reconnects.
+    /// On failure the session keeps its current token.
+    pub async fn reauthenticate(&self, token: &str) -> Result<()> {
+        self.request(|correlation_id| {
+            Message::Set(SetMessage {
+                address: SESSION_TOKEN_ADDRESS.to_string(),
+                value: Value::String(token.to_string()),
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: Some(correlation_id),
+            })
+        })
+        .await?;
+
+        *self.token.write() = Some(token.to_string());
+        Ok(())
+    }
+
+    /// Tag this session's traffic with the protocol it relays.
+    ///
+    /// For bridges forwarding another protocol over a CLASP connection:
+    /// subscribers filtering by origin, envelopes and the router's audit log
+    /// then see e.g. `mqtt(sensor-7)` instead of a native client. The origin
+    /// is declared again after a reconnect.
+    pub async fn declare_origin(&self, origin: Origin) -> Result<()> {
+        self.request(|correlation_id| origin_message(&origin, Some(correlation_id)))
+            .await?;
+
+        *self.declared_origin.write() = Some(origin);
+        Ok(())
+    }
+
+    /// Origin of the last delivery for `address`, if it came in an envelope
+    ///
+    /// Only subscriptions with [`SubscribeOptions::envelope`] set receive
+    /// envelopes.
+    pub fn last_origin(&self, address: &str) -> Option<Origin> {
+        self.origins.get(address).map(|o| o.value().clone())
+    }
+
+    /// Send a request tagged with a fresh correlation id and wait for the
+    /// ACK, SNAPSHOT or ERROR that echoes it
+    async fn request(&self, build: impl FnOnce(u32) -> Message) -> Result<Message> {
+        let correlation_id = self.next_correlation_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending_requests.insert(correlation_id, tx);
+
+        if let Err(e) = self.send_message(&build(correlation_id)).await {
+            self.pending_requests.remove(&correlation_id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.request_timeout, rx).await {
+            Ok(Ok(Ok(reply))) => Ok(reply),
+            Ok(Ok(Err(error))) => Err(error.into()),
+            Ok(Err(_)) => Err(ClientError::Other("Request cancelled".to_string())),
+            Err(_) => {
+                self.pending_requests.remove(&correlation_id);
+                Err(ClientError::Timeout)
+            }
+        }
+    }
+
     /// Get the last error received from server
     pub fn last_error(&self) -> Option<ErrorMessage> {
         self.last_error.read().clone()
@@ -977,15 +1816,73 @@ impl Clasp {
     }
 }
 
+/// Cache a (complete or incremental) snapshot and notify subscribers
+fn apply_snapshot(snapshot: &SnapshotMessage, inbox: &Inbox) {
+    if let Some(sync) = snapshot.sync {
+        let mut resync = inbox.resync.lock();
+        if resync.awaiting {
+            resync.awaiting = false;
+            resync.revision = Some(sync.revision);
+        }
+    }
+
+    for param in &snapshot.params {
+        inbox
+            .params
+            .insert(param.address.clone(), param.value.clone());
+
+        // Complete pending gets
+        if let Some((_, tx)) = inbox.pending_gets.remove(&param.address) {
+            let _ = tx.send(param.value.clone());
+        }
+
+        // Notify subscribers
+        for entry in inbox.subscriptions.iter() {
+            let (pattern, callback) = entry.value();
+            if clasp_core::address::glob_match(pattern, &param.address) {
+                callback(param.value.clone(), &param.address);
+            }
+        }
+    }
+
+    if let Some((_, tx)) = snapshot
+        .correlation_id
+        .and_then(|id| inbox.pending_requests.remove(&id))
+    {
+        let _ = tx.send(Ok(Message::Snapshot(snapshot.clone())));
+    }
+}
+
+/// SET declaring a session's origin
+fn origin_message(origin: &Origin, correlation_id: Option<u32>) -> Message {
+    Message::Set(SetMessage {
+        address: SESSION_ORIGIN_ADDRESS.to_string(),
+        value: origin.to_value(),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id,
+    })
+}
+
 /// Handle incoming message
-fn handle_message(
-    msg: &Message,
-    params: &Arc<DashMap<String, Value>>,
-    subscriptions: &Arc<DashMap<u32, (String, SubscriptionCallback)>>,
-    pending_gets: &Arc<DashMap<String, oneshot::Sender<Value>>>,
-    signals: &Arc<DashMap<String, SignalDefinition>>,
-    last_error: &Arc<RwLock<Option<ErrorMessage>>>,
-) {
+fn handle_message(msg: &Message, inbox: &Inbox) {
+    let Inbox {
+        params,
+        subscriptions,
+        pending_gets: _,
+        pending_queries,
+        pending_requests,
+        signals,
+        last_error,
+        sub_clients,
+        snapshot_pages,
+        incremental_snapshots,
+        resync: _,
+        origins,
+    } = inbox;
+
     match msg {
         Message::Set(set) => {
             // Update cache
@@ -1000,24 +1897,35 @@ fn handle_message(
             }
         }
 
-        Message::Snapshot(snapshot) => {
-            for param in &snapshot.params {
-                params.insert(param.address.clone(), param.value.clone());
-
-                // Complete pending gets
-                if let Some((_, tx)) = pending_gets.remove(&param.address) {
-                    let _ = tx.send(param.value.clone());
-                }
-
-                // Notify subscribers
-                for entry in subscriptions.iter() {
-                    let (pattern, callback) = entry.value();
-                    if clasp_core::address::glob_match(pattern, &param.address) {
-                        callback(param.value.clone(), &param.address);
+        Message::Snapshot(snapshot) => match snapshot.page {
+            Some(page) if !*incremental_snapshots => {
+                let complete = {
+                    let mut buffer = snapshot_pages.lock();
+                    if page.sequence == 0 {
+                        buffer.params.clear();
+                    } else if page.sequence != buffer.next {
+                        warn!(
+                            "Snapshot page {} arrived, expected {}; snapshot is incomplete",
+                            page.sequence, buffer.next
+                        );
                     }
-                }
+                    buffer.next = page.sequence.wrapping_add(1);
+                    buffer.params.extend(snapshot.params.iter().cloned());
+                    if page.more {
+                        return;
+                    }
+                    buffer.next = 0;
+                    SnapshotMessage {
+                        params: std::mem::take(&mut buffer.params),
+                        correlation_id: snapshot.correlation_id,
+                        page: None,
+                        sync: snapshot.sync,
+                    }
+                };
+                apply_snapshot(&complete, inbox);
             }
-        }
+            _ => apply_snapshot(snapshot, inbox),
+        },
 
         Message::Publish(pub_msg) => {
             #[cfg(feature = "p2p")]
@@ -1053,14 +1961,27 @@ fn handle_message(
                 error.code, error.message, error.address
             );
             *last_error.write() = Some(error.clone());
+
+            if let Some((_, tx)) = error
+                .correlation_id
+                .and_then(|id| pending_requests.remove(&id))
+            {
+                let _ = tx.send(Err(error.clone()));
+            }
         }
 
         Message::Ack(ack) => {
-            // Log acknowledgment (could be extended to track pending requests)
             debug!(
                 "Received ACK for {:?} (revision: {:?})",
                 ack.address, ack.revision
             );
+
+            if let Some((_, tx)) = ack
+                .correlation_id
+                .and_then(|id| pending_requests.remove(&id))
+            {
+                let _ = tx.send(Ok(msg.clone()));
+            }
         }
 
         Message::Announce(announce) => {
@@ -1090,6 +2011,9 @@ fn handle_message(
             for signal in &result.signals {
                 signals.insert(signal.address.clone(), signal.clone());
             }
+            if let Some(tx) = pending_queries.lock().pop_front() {
+                let _ = tx.send(result.signals.clone());
+            }
         }
 
         // Messages that are typically client-initiated, not expected from server
@@ -1097,6 +2021,7 @@ fn handle_message(
         | Message::Welcome(_)
         | Message::Subscribe(_)
         | Message::Unsubscribe(_)
+        | Message::Credit(_)
         | Message::Get(_)
         | Message::Query(_) => {
             debug!("Received unexpected client-type message: {:?}", msg);
@@ -1105,14 +2030,7 @@ fn handle_message(
         // Bundle: process contained messages recursively
         Message::Bundle(bundle) => {
             for inner_msg in &bundle.messages {
-                handle_message(
-                    inner_msg,
-                    params,
-                    subscriptions,
-                    pending_gets,
-                    signals,
-                    last_error,
-                );
+                handle_message(inner_msg, inbox);
             }
         }
 
@@ -1126,5 +2044,57 @@ fn handle_message(
         Message::Pong => {
             debug!("Received PONG from server");
         }
+
+        // Traffic of a sub-client sharing this connection
+        Message::Channel(channel) => {
+            let Some(inner) = &channel.message else {
+                debug!("Channel {} closed by server", channel.channel);
+                if let Some((_, sub)) = sub_clients.remove(&channel.channel) {
+                    *sub.connected.write() = false;
+                }
+                return;
+            };
+
+            let handshake = match inner.as_ref() {
+                Message::Welcome(welcome) => Some(Ok(welcome.clone())),
+                Message::Error(error) => Some(Err(error.clone())),
+                _ => None,
+            };
+            if let Some(result) = handshake {
+                let pending = sub_clients
+                    .get_mut(&channel.channel)
+                    .and_then(|mut sub| sub.welcome.take());
+                if let Some(tx) = pending {
+                    let _ = tx.send(result);
+                    return;
+                }
+            }
+
+            // Don't hold the map while callbacks run
+            let inbox = sub_clients
+                .get(&channel.channel)
+                .map(|sub| sub.inbox.clone());
+            match inbox {
+                Some(inbox) => handle_message(inner, &inbox),
+                None => debug!("Message for unknown channel {}", channel.channel),
+            }
+        }
+
+        // A delivery tagged with where it entered the router
+        Message::Envelope(envelope) => {
+            if let Some(address) = delivery_address(&envelope.message) {
+                origins.insert(address.to_string(), envelope.origin.clone());
+            }
+            handle_message(&envelope.message, inbox);
+        }
+    }
+}
+
+/// Address of a SET or PUBLISH
+fn delivery_address(msg: &Message) -> Option<&str> {
+    match msg {
+        Message::Set(set) => Some(&set.address),
+        Message::Publish(publish) => Some(&publish.address),
+        _ => None,
     }
 }
@@ -1,5 +1,6 @@
 //! Client error types
 
+use clasp_core::{ErrorCode, ErrorMessage, Value};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, ClientError>;
@@ -27,9 +28,93 @@ pub enum ClientError {
     #[error("transport error: {0}")]
     Transport(#[from] clasp_transport::TransportError),
 
+    #[error("authentication failed: {0}")]
+    AuthFailed(String),
+
+    #[error("token expired")]
+    TokenExpired,
+
+    #[error("permission denied: {0}")]
+    Forbidden(String),
+
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+
+    #[error("state conflict: {0}")]
+    Conflict(String),
+
+    /// A compare-and-set lost: the param is at another revision
+    #[error("revision conflict: param is at revision {revision}")]
+    RevisionConflict {
+        /// Current revision (0 = the param doesn't exist)
+        revision: u64,
+        /// Current value, None if the param doesn't exist
+        value: Option<Value>,
+    },
+
+    #[error("payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("server error {code}: {message}")]
+    Server { code: u16, message: String },
+
     #[error("P2P not connected to peer: {0}")]
     P2PNotConnected(String),
 
     #[error("client error: {0}")]
     Other(String),
 }
+
+impl ClientError {
+    /// Protocol error code, for errors reported by the server
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            ClientError::AuthFailed(_) => Some(ErrorCode::Unauthorized),
+            ClientError::TokenExpired => Some(ErrorCode::TokenExpired),
+            ClientError::Forbidden(_) => Some(ErrorCode::Forbidden),
+            ClientError::RateLimited(_) => Some(ErrorCode::RateLimited),
+            ClientError::PayloadTooLarge(_) => Some(ErrorCode::PayloadTooLarge),
+            ClientError::RevisionConflict { .. } => Some(ErrorCode::RevisionConflict),
+            ClientError::Server { code, .. } => ErrorCode::from_u16(*code),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorMessage> for ClientError {
+    fn from(error: ErrorMessage) -> Self {
+        let message = error.message;
+        match ErrorCode::from_u16(error.code) {
+            Some(ErrorCode::RevisionConflict) if error.revision.is_some() => {
+                ClientError::RevisionConflict {
+                    revision: error.revision.unwrap_or_default(),
+                    value: error.value,
+                }
+            }
+            Some(ErrorCode::Unauthorized) => ClientError::AuthFailed(message),
+            Some(ErrorCode::TokenExpired) => ClientError::TokenExpired,
+            Some(ErrorCode::Forbidden) => ClientError::Forbidden(message),
+            Some(ErrorCode::RateLimited) => ClientError::RateLimited(message),
+            Some(ErrorCode::PayloadTooLarge) => ClientError::PayloadTooLarge(message),
+            Some(
+                ErrorCode::InvalidAddress | ErrorCode::AddressNotFound | ErrorCode::PatternError,
+            ) => ClientError::InvalidAddress(message),
+            Some(ErrorCode::UnsupportedVersion | ErrorCode::UnsupportedFeature) => {
+                ClientError::Unsupported(message)
+            }
+            Some(
+                ErrorCode::RevisionConflict | ErrorCode::LockHeld | ErrorCode::ConflictRejected,
+            ) => ClientError::Conflict(message),
+            _ => ClientError::Server {
+                code: error.code,
+                message,
+            },
+        }
+    }
+}
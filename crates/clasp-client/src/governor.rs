@@ -0,0 +1,196 @@
+//! Outbound rate limiting with latest-value coalescing
+//!
+//! A UI slider can easily produce hundreds of changes per second, which
+//! trips the router's per-client rate limit and floods slow links. With a
+//! [`Governor`] configured, [`Clasp::set`](crate::Clasp::set) and
+//! [`Clasp::stream`](crate::Clasp::stream) send at most `rate` messages per
+//! second for each address. Anything in between replaces the value waiting
+//! to go out, so the last value always arrives, just a little later.
+//!
+//! Limits apply per address; the longest matching prefix picks the rate, and
+//! addresses without a matching prefix use the default rate (if any).
+//! Confirmed, locking and bundled sends are never delayed.
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Outbound rate limits for a client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Governor {
+    default_rate: Option<f64>,
+    prefixes: Vec<(String, f64)>,
+}
+
+impl Governor {
+    /// No limits; add some with [`Governor::with_max_rate`] or
+    /// [`Governor::with_prefix_rate`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit every address to `per_second` messages per second
+    pub fn with_max_rate(mut self, per_second: f64) -> Self {
+        self.default_rate = Some(per_second);
+        self
+    }
+
+    /// Limit addresses starting with `prefix` to `per_second` messages per
+    /// second, overriding the default rate
+    ///
+    /// A rate of 0 exempts the prefix from limiting.
+    pub fn with_prefix_rate(mut self, prefix: impl Into<String>, per_second: f64) -> Self {
+        self.prefixes.push((prefix.into(), per_second));
+        self
+    }
+
+    /// Minimum time between two messages to `address`, if it is limited
+    pub fn interval_for(&self, address: &str) -> Option<Duration> {
+        let rate = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| address.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, rate)| *rate)
+            .or(self.default_rate)?;
+        (rate > 0.0 && rate.is_finite()).then(|| Duration::from_secs_f64(1.0 / rate))
+    }
+}
+
+/// What to do with an outgoing frame
+#[derive(Debug)]
+pub(crate) enum Offer {
+    /// Send it right away
+    Send(Bytes),
+    /// Held back; schedule a flush after this long, unless one is pending
+    Held(Option<Duration>),
+}
+
+#[derive(Debug)]
+struct Slot {
+    last_sent: Instant,
+    pending: Option<Bytes>,
+}
+
+/// Per-address send times and held-back frames
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    governor: Governor,
+    slots: Mutex<HashMap<String, Slot>>,
+}
+
+impl Throttle {
+    pub(crate) fn new(governor: Governor) -> Self {
+        Self {
+            governor,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn governor(&self) -> &Governor {
+        &self.governor
+    }
+
+    /// Admit `data` for `address` now, or hold it as the latest value
+    pub(crate) fn offer(&self, address: &str, data: Bytes) -> Offer {
+        let Some(interval) = self.governor.interval_for(address) else {
+            return Offer::Send(data);
+        };
+        let now = Instant::now();
+        let mut slots = self.slots.lock();
+        let Some(slot) = slots.get_mut(address) else {
+            slots.insert(
+                address.to_string(),
+                Slot {
+                    last_sent: now,
+                    pending: None,
+                },
+            );
+            return Offer::Send(data);
+        };
+
+        if slot.pending.is_some() {
+            // A flush is already scheduled; it will send this instead
+            slot.pending = Some(data);
+            return Offer::Held(None);
+        }
+        let elapsed = now.duration_since(slot.last_sent);
+        if elapsed >= interval {
+            slot.last_sent = now;
+            return Offer::Send(data);
+        }
+        slot.pending = Some(data);
+        Offer::Held(Some(interval - elapsed))
+    }
+
+    /// Take the held value for `address`, marking it sent
+    pub(crate) fn take(&self, address: &str) -> Option<Bytes> {
+        let mut slots = self.slots.lock();
+        let slot = slots.get_mut(address)?;
+        let data = slot.pending.take()?;
+        slot.last_sent = Instant::now();
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_for() {
+        let governor = Governor::new()
+            .with_max_rate(100.0)
+            .with_prefix_rate("/ui/", 20.0)
+            .with_prefix_rate("/ui/meters/", 0.0);
+
+        assert_eq!(
+            governor.interval_for("/lights/1"),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(
+            governor.interval_for("/ui/slider"),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(governor.interval_for("/ui/meters/left"), None);
+        assert_eq!(Governor::new().interval_for("/anything"), None);
+    }
+
+    #[test]
+    fn test_offer_coalesces_latest() {
+        let throttle = Throttle::new(Governor::new().with_max_rate(10.0));
+
+        assert!(matches!(
+            throttle.offer("/a", Bytes::from_static(b"1")),
+            Offer::Send(_)
+        ));
+        // Other addresses have their own budget
+        assert!(matches!(
+            throttle.offer("/b", Bytes::from_static(b"1")),
+            Offer::Send(_)
+        ));
+        match throttle.offer("/a", Bytes::from_static(b"2")) {
+            Offer::Held(Some(delay)) => assert!(delay <= Duration::from_millis(100)),
+            other => panic!("expected a flush to be scheduled, got {:?}", other),
+        }
+        assert!(matches!(
+            throttle.offer("/a", Bytes::from_static(b"3")),
+            Offer::Held(None)
+        ));
+
+        assert_eq!(throttle.take("/a"), Some(Bytes::from_static(b"3")));
+        assert_eq!(throttle.take("/a"), None);
+    }
+
+    #[test]
+    fn test_unlimited_passes_through() {
+        let throttle = Throttle::new(Governor::new().with_prefix_rate("/ui/", 1.0));
+        for _ in 0..3 {
+            assert!(matches!(
+                throttle.offer("/other", Bytes::from_static(b"x")),
+                Offer::Send(_)
+            ));
+        }
+    }
+}
@@ -14,8 +14,12 @@
 //! - **Parameters**: Get/set persistent values with caching
 //! - **Events**: Fire-and-forget event emission
 //! - **Streams**: High-rate data streaming (QoS fire)
+//! - **Byte streams**: Flow-controlled binary subscriptions ([`Clasp::subscribe_bytes`])
 //! - **Bundles**: Atomic multi-message operations
 //! - **Time sync**: Automatic clock synchronization with server
+//! - **Connection sharing**: Several logical clients over one connection ([`Clasp::sub_client`])
+//! - **Typed addresses**: Compile-checked address constructors ([`clasp_addresses!`])
+//! - **Outbound governor**: Per-address rate limits with latest-value coalescing ([`Governor`])
 //!
 //! ## Quick Start
 //!
@@ -68,6 +72,20 @@
 //! - `*` - matches exactly one segment: `/path/*/value` matches `/path/foo/value`
 //! - `**` - matches any number of segments: `/path/**` matches `/path/a/b/c`
 //!
+//! Addresses a program uses can be declared with [`clasp_addresses!`],
+//! which generates typed constructors so a misspelled segment fails to
+//! compile instead of silently addressing nothing:
+//!
+//! ```ignore
+//! use clasp_client::clasp_addresses;
+//!
+//! clasp_addresses! {
+//!     lumen { scene(u8) { layer(u8) { opacity } } }
+//! }
+//!
+//! client.set(&lumen().scene(0).layer(3).opacity(), 0.75).await?;
+//! ```
+//!
 //! ## Signal Types
 //!
 //! | Type | Use Case | Persistence | QoS |
@@ -77,6 +95,75 @@
 //! | `stream()` | High-rate sensor data | Not persisted | Fire |
 //! | `gesture()` | Touch/pen/motion input | Phase only | Fire |
 //!
+//! ## Large Snapshots
+//!
+//! Routers split snapshots that don't fit in one frame into pages. By
+//! default the client collects them and applies the complete snapshot at
+//! once; [`ClaspBuilder::incremental_snapshots`] applies each page as it
+//! arrives instead.
+//!
+//! Every snapshot the router sends on connect carries its state revision.
+//! With [`ClaspBuilder::diff_resync`] on, a reconnecting client asks for only
+//! what changed since that revision rather than the whole namespace.
+//!
+//! ## Rate Limiting
+//!
+//! A [`Governor`] caps how often `set()` and `stream()` send to each address.
+//! Calls in between replace the pending value, so a slider dragged at 500
+//! events/sec reaches the router at the configured rate and still ends on
+//! its final position:
+//!
+//! ```ignore
+//! use clasp_client::{Clasp, Governor};
+//!
+//! let client = Clasp::builder("ws://localhost:7330")
+//!     .governor(
+//!         Governor::new()
+//!             .with_max_rate(60.0)                // every address: 60/s
+//!             .with_prefix_rate("/ui/", 30.0)     // UI controls: 30/s
+//!             .with_prefix_rate("/cue/", 0.0),    // cues: unlimited
+//!     )
+//!     .connect()
+//!     .await?;
+//! ```
+//!
+//! ## Byte Streams
+//!
+//! [`Clasp::subscribe_bytes`] returns a [`ByteStream`] of raw payloads. The
+//! router keeps at most a window of deliveries in flight ahead of what the
+//! stream has handed out and drops the rest, so a fast producer can't outrun
+//! a slow consumer:
+//!
+//! ```ignore
+//! use futures::StreamExt;
+//!
+//! let mut frames = client.subscribe_bytes("/camera/meta").await?;
+//! while let Some(data) = frames.next().await {
+//!     println!("{} bytes", data.len());
+//! }
+//! ```
+//!
+//! ## Device Keys
+//!
+//! Instead of a token, a client can authenticate with a
+//! [`DeviceIdentity`] key pair whose public key is registered on the
+//! router. A fresh signed proof is sent on every connect:
+//!
+//! ```ignore
+//! let identity = DeviceIdentity::load_or_generate("/var/lib/sensor/device.key")?;
+//! let client = Clasp::builder("wss://router.example:7330")
+//!     .identity(identity)
+//!     .connect()
+//!     .await?;
+//! ```
+//!
+//! ## Namespace Manifests
+//!
+//! [`Clasp::publish_manifest`] publishes a [`clasp_core::Manifest`]
+//! describing the addresses an application owns to `/$manifest/<app>`, and
+//! [`Clasp::manifest`] reads one back. Routers can be configured to reject
+//! writes that don't fit a published manifest.
+//!
 //! ## Error Handling
 //!
 //! All async methods return `Result<T, ClientError>`. Common errors:
@@ -84,20 +171,31 @@
 //! - `ClientError::NotConnected` - Operation requires active connection
 //! - `ClientError::SendFailed` - Message could not be sent
 //! - `ClientError::Timeout` - Operation timed out
+//! - `ClientError::RevisionConflict` - A [`Clasp::compare_and_set`] lost to
+//!   another writer; carries the param's current revision and value
 //!
 //! ## Crate Features
 //!
 //! - `p2p` - Enable peer-to-peer mesh networking support
+//! - `mesh` - Router-less LAN mesh with mDNS discovery ([`Clasp::mesh_builder`])
 
 pub mod builder;
+pub mod byte_stream;
 pub mod client;
 pub mod error;
+pub mod governor;
+#[cfg(feature = "mesh")]
+pub mod mesh;
 #[cfg(feature = "p2p")]
 pub mod p2p;
 
 pub use builder::ClaspBuilder;
+pub use byte_stream::{ByteStream, DEFAULT_CREDIT_WINDOW};
 pub use client::Clasp;
 pub use error::{ClientError, Result};
+pub use governor::Governor;
+#[cfg(feature = "mesh")]
+pub use mesh::{Mesh, MeshBuilder, MeshEvent, MeshPeer};
 #[cfg(feature = "p2p")]
 pub use p2p::{P2PEvent, P2PManager, SendResult};
 
@@ -119,5 +217,12 @@ pub mod prelude {
     };
 }
 
+/// Typed address constructors, see [`clasp_macros`]
+pub use clasp_macros::clasp_addresses;
+
 // Re-export types for convenience
-pub use clasp_core::{EasingType, GesturePhase, TimelineData, TimelineKeyframe};
+pub use clasp_core::{
+    DeviceIdentity, EasingType, GesturePhase, Origin, QoS, SetOp, SubscribeOptions, TimelineData,
+    TimelineKeyframe,
+};
+pub use clasp_transport::KeepaliveConfig;
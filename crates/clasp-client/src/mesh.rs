@@ -0,0 +1,670 @@
+//! Router-less LAN mesh
+//!
+//! For ad-hoc setups (two laptops on a switch) running a router is overkill.
+//! A [`Mesh`] node finds other nodes on the LAN via mDNS (or a list of static
+//! peers), talks to them over UDP, and keeps a replicated copy of the retained
+//! param state:
+//!
+//! - a SET is applied locally and sent to every peer
+//! - every gossip interval each node sends its full state to its peers, so
+//!   nodes that missed a datagram or joined late converge
+//! - concurrent writes are resolved last-writer-wins on a Lamport revision
+//!   (`clock << 16 | node tag`), which gives every node the same answer
+//!
+//! Events and streams are delivered to direct peers only and are not
+//! retained.
+//!
+//! ```ignore
+//! use clasp_client::Clasp;
+//!
+//! let mesh = Clasp::mesh_builder().name("laptop-a").join().await?;
+//! mesh.subscribe("/lights/**", |value, address| {
+//!     println!("{} = {:?}", address, value);
+//! });
+//! mesh.set("/lights/1", 0.5).await?;
+//! ```
+
+use clasp_core::{
+    codec, HelloMessage, Message, ParamValue, PublishMessage, SetMessage, SignalType,
+    SnapshotMessage, Value, WelcomeMessage, PROTOCOL_VERSION,
+};
+use clasp_discovery::mdns::MdnsBackend;
+use clasp_discovery::{Announcement, DiscoveryBackend, DiscoveryEvent};
+use clasp_transport::{TransportEvent, UdpTransport};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::client::SubscriptionCallback;
+use crate::error::Result;
+
+/// mDNS TXT `role` of mesh nodes (routers don't set one)
+const MESH_ROLE: &str = "mesh";
+
+/// Params per gossiped SNAPSHOT, to stay well below the UDP datagram limit
+const SNAPSHOT_CHUNK: usize = 32;
+
+/// Callback for mesh events
+pub type MeshEventCallback = Box<dyn Fn(MeshEvent) + Send + Sync>;
+
+/// A node the mesh has completed a handshake with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshPeer {
+    /// The peer's node id
+    pub id: String,
+    /// The peer's name
+    pub name: String,
+    /// Where the peer's datagrams come from
+    pub addr: SocketAddr,
+}
+
+/// Mesh membership events
+#[derive(Debug, Clone)]
+pub enum MeshEvent {
+    /// A peer completed the handshake
+    PeerJoined(MeshPeer),
+    /// A peer stopped answering and was dropped
+    PeerLeft(MeshPeer),
+}
+
+/// Builder for a [`Mesh`] node, see [`Clasp::mesh_builder`](crate::Clasp::mesh_builder)
+pub struct MeshBuilder {
+    name: String,
+    bind: String,
+    port: u16,
+    discovery: bool,
+    peers: Vec<SocketAddr>,
+    gossip_interval: Duration,
+    peer_timeout: Duration,
+}
+
+impl MeshBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Self {
+            name: "Clasp Mesh".to_string(),
+            bind: "0.0.0.0".to_string(),
+            port: 0,
+            discovery: true,
+            peers: Vec::new(),
+            gossip_interval: Duration::from_secs(1),
+            peer_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Set node name
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the local address to bind (default `0.0.0.0`)
+    pub fn bind(mut self, addr: &str) -> Self {
+        self.bind = addr.to_string();
+        self
+    }
+
+    /// Set the UDP port (default: any free port, advertised via mDNS)
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Enable/disable mDNS discovery and advertisement
+    pub fn discovery(mut self, enabled: bool) -> Self {
+        self.discovery = enabled;
+        self
+    }
+
+    /// Add a static peer, for networks where mDNS is blocked
+    pub fn peer(mut self, addr: SocketAddr) -> Self {
+        self.peers.push(addr);
+        self
+    }
+
+    /// Set how often state and keepalives are gossiped
+    pub fn gossip_interval(mut self, interval: Duration) -> Self {
+        self.gossip_interval = interval;
+        self
+    }
+
+    /// Set how long a silent peer is kept before it is dropped
+    pub fn peer_timeout(mut self, timeout: Duration) -> Self {
+        self.peer_timeout = timeout;
+        self
+    }
+
+    /// Bind, start discovery and join the mesh
+    pub async fn join(self) -> Result<Mesh> {
+        let socket = UdpTransport::bind(&format!("{}:{}", self.bind, self.port)).await?;
+        let local_addr = socket.local_addr()?;
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let tag = u64::from_str_radix(&id[..4], 16).unwrap_or(0);
+
+        let inner = Arc::new(MeshInner {
+            id,
+            name: self.name,
+            local_addr,
+            socket,
+            tag,
+            clock: AtomicU64::new(0),
+            params: DashMap::new(),
+            peers: DashMap::new(),
+            seeds: DashMap::new(),
+            subscriptions: DashMap::new(),
+            next_sub_id: AtomicU32::new(1),
+            event_callback: RwLock::new(None),
+            peer_timeout: self.peer_timeout,
+            discovery: if self.discovery {
+                Some(MdnsBackend::new())
+            } else {
+                None
+            },
+            tasks: Mutex::new(Vec::new()),
+        });
+
+        info!(
+            "Mesh node {} ({}) listening on {}",
+            inner.name, inner.id, local_addr
+        );
+
+        let mut tasks = vec![
+            tokio::spawn(Arc::clone(&inner).receive_loop()),
+            tokio::spawn(Arc::clone(&inner).gossip_loop(self.gossip_interval)),
+        ];
+        if let Some(task) = inner.start_discovery().await {
+            tasks.push(task);
+        }
+        *inner.tasks.lock() = tasks;
+
+        for addr in self.peers {
+            inner.seeds.insert(addr, ());
+            inner.hello(addr).await;
+        }
+
+        Ok(Mesh { inner })
+    }
+}
+
+impl Default for MeshBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A peer we sent HELLO to or got HELLO from
+struct PeerState {
+    /// Node id and name, once WELCOME arrived
+    identity: Option<(String, String)>,
+    last_seen: Instant,
+}
+
+struct MeshInner {
+    id: String,
+    name: String,
+    local_addr: SocketAddr,
+    socket: UdpTransport,
+    /// Low bits of every revision this node writes
+    tag: u64,
+    /// Lamport clock
+    clock: AtomicU64,
+    /// Retained params: address -> (value, revision)
+    params: DashMap<String, (Value, u64)>,
+    peers: DashMap<SocketAddr, PeerState>,
+    /// Static and discovered addresses to (re)connect to
+    seeds: DashMap<SocketAddr, ()>,
+    subscriptions: DashMap<u32, (String, SubscriptionCallback)>,
+    next_sub_id: AtomicU32,
+    event_callback: RwLock<Option<MeshEventCallback>>,
+    peer_timeout: Duration,
+    discovery: Option<MdnsBackend>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl MeshInner {
+    fn next_revision(&self) -> u64 {
+        let clock = self.clock.fetch_add(1, Ordering::SeqCst) + 1;
+        (clock << 16) | self.tag
+    }
+
+    /// Store a param if `revision` is newer than ours; returns whether it was
+    fn apply(&self, address: &str, value: Value, revision: u64) -> bool {
+        self.clock.fetch_max(revision >> 16, Ordering::SeqCst);
+
+        let applied = match self.params.entry(address.to_string()) {
+            Entry::Occupied(entry) if entry.get().1 >= revision => false,
+            Entry::Occupied(mut entry) => {
+                entry.insert((value.clone(), revision));
+                true
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((value.clone(), revision));
+                true
+            }
+        };
+        if applied {
+            self.notify(address, value);
+        }
+        applied
+    }
+
+    fn notify(&self, address: &str, value: Value) {
+        for entry in self.subscriptions.iter() {
+            let (pattern, callback) = entry.value();
+            if clasp_core::address::glob_match(pattern, address) {
+                callback(value.clone(), address);
+            }
+        }
+    }
+
+    fn emit_event(&self, event: MeshEvent) {
+        if let Some(callback) = self.event_callback.read().as_ref() {
+            callback(event);
+        }
+    }
+
+    fn identified_peers(&self) -> Vec<MeshPeer> {
+        self.peers
+            .iter()
+            .filter_map(|entry| {
+                entry.identity.as_ref().map(|(id, name)| MeshPeer {
+                    id: id.clone(),
+                    name: name.clone(),
+                    addr: *entry.key(),
+                })
+            })
+            .collect()
+    }
+
+    async fn send_to(&self, msg: &Message, addr: SocketAddr) {
+        match codec::encode(msg) {
+            Ok(data) => {
+                if let Err(e) = self.socket.send_to(&data, addr).await {
+                    debug!("Mesh send to {} failed: {}", addr, e);
+                }
+            }
+            Err(e) => warn!("Mesh encode failed: {}", e),
+        }
+    }
+
+    async fn broadcast(&self, msg: &Message) -> Result<()> {
+        let data = codec::encode(msg)?;
+        for peer in self.identified_peers() {
+            if let Err(e) = self.socket.send_to(&data, peer.addr).await {
+                debug!("Mesh send to {} failed: {}", peer.addr, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn hello(&self, addr: SocketAddr) {
+        if addr == self.local_addr {
+            return;
+        }
+        self.peers.entry(addr).or_insert_with(|| PeerState {
+            identity: None,
+            last_seen: Instant::now(),
+        });
+        let hello = Message::Hello(HelloMessage {
+            version: PROTOCOL_VERSION,
+            name: self.name.clone(),
+            features: mesh_features(),
+            capabilities: None,
+            token: None,
+            since: None,
+        });
+        self.send_to(&hello, addr).await;
+    }
+
+    fn snapshots(&self) -> Vec<Message> {
+        let params: Vec<ParamValue> = self
+            .params
+            .iter()
+            .map(|entry| ParamValue {
+                address: entry.key().clone(),
+                value: entry.0.clone(),
+                revision: entry.1,
+                writer: None,
+                timestamp: None,
+            })
+            .collect();
+        params
+            .chunks(SNAPSHOT_CHUNK)
+            .map(|chunk| {
+                Message::Snapshot(SnapshotMessage {
+                    params: chunk.to_vec(),
+                    correlation_id: None,
+                    page: None,
+                    sync: None,
+                })
+            })
+            .collect()
+    }
+
+    async fn handle(&self, msg: Message, from: SocketAddr) {
+        if let Some(mut peer) = self.peers.get_mut(&from) {
+            peer.last_seen = Instant::now();
+        }
+
+        match msg {
+            Message::Hello(hello) => {
+                let welcome = Message::Welcome(WelcomeMessage {
+                    version: PROTOCOL_VERSION,
+                    session: self.id.clone(),
+                    name: self.name.clone(),
+                    features: mesh_features(),
+                    time: clasp_core::time::now(),
+                    token: None,
+                });
+                self.send_to(&welcome, from).await;
+
+                // Say hello back so the handshake completes in both directions
+                if !self.peers.contains_key(&from) {
+                    debug!("Mesh HELLO from {} ({})", hello.name, from);
+                    self.hello(from).await;
+                }
+            }
+            Message::Welcome(welcome) => {
+                if welcome.session == self.id {
+                    // Discovered ourselves
+                    self.peers.remove(&from);
+                    self.seeds.remove(&from);
+                    return;
+                }
+                let duplicate = self.peers.iter().any(|entry| {
+                    *entry.key() != from
+                        && entry
+                            .identity
+                            .as_ref()
+                            .is_some_and(|(id, _)| *id == welcome.session)
+                });
+                if duplicate {
+                    // Same node reachable on another address
+                    self.peers.remove(&from);
+                    return;
+                }
+
+                let joined = match self.peers.get_mut(&from) {
+                    Some(mut peer) if peer.identity.is_none() => {
+                        peer.identity = Some((welcome.session.clone(), welcome.name.clone()));
+                        true
+                    }
+                    _ => false,
+                };
+                if joined {
+                    info!("Mesh peer {} joined from {}", welcome.name, from);
+                    for snapshot in self.snapshots() {
+                        self.send_to(&snapshot, from).await;
+                    }
+                    self.emit_event(MeshEvent::PeerJoined(MeshPeer {
+                        id: welcome.session,
+                        name: welcome.name,
+                        addr: from,
+                    }));
+                }
+            }
+            Message::Set(set) => {
+                if let Some(revision) = set.revision {
+                    self.apply(&set.address, set.value, revision);
+                }
+            }
+            Message::Snapshot(snapshot) => {
+                for param in snapshot.params {
+                    self.apply(&param.address, param.value, param.revision);
+                }
+            }
+            Message::Publish(publish) => {
+                if let Some(value) = publish.value.or(publish.payload) {
+                    self.notify(&publish.address, value);
+                }
+            }
+            Message::Ping => self.send_to(&Message::Pong, from).await,
+            Message::Pong => {}
+            other => debug!("Mesh ignoring {:?} from {}", other, from),
+        }
+    }
+
+    async fn receive_loop(self: Arc<Self>) {
+        let mut receiver = self.socket.start_receiver();
+        while let Some((event, from)) = receiver.recv_from().await {
+            if let TransportEvent::Data(data) = event {
+                match codec::decode(&data) {
+                    Ok((msg, _)) => self.handle(msg, from).await,
+                    Err(e) => debug!("Mesh dropped bad datagram from {}: {}", from, e),
+                }
+            }
+        }
+    }
+
+    async fn gossip_loop(self: Arc<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let stale: Vec<SocketAddr> = self
+                .peers
+                .iter()
+                .filter(|entry| entry.last_seen.elapsed() > self.peer_timeout)
+                .map(|entry| *entry.key())
+                .collect();
+            for addr in stale {
+                if let Some((
+                    _,
+                    PeerState {
+                        identity: Some((id, name)),
+                        ..
+                    },
+                )) = self.peers.remove(&addr)
+                {
+                    info!("Mesh peer {} left", name);
+                    self.emit_event(MeshEvent::PeerLeft(MeshPeer { id, name, addr }));
+                }
+            }
+
+            let seeds: Vec<SocketAddr> = self.seeds.iter().map(|entry| *entry.key()).collect();
+            for addr in seeds {
+                if !self.peers.contains_key(&addr) {
+                    self.hello(addr).await;
+                }
+            }
+
+            let _ = self.broadcast(&Message::Ping).await;
+            for snapshot in self.snapshots() {
+                let _ = self.broadcast(&snapshot).await;
+            }
+        }
+    }
+
+    /// Advertise this node via mDNS and say hello to every mesh node found
+    async fn start_discovery(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let backend = self.discovery.as_ref()?;
+
+        let announcement = Announcement::new(
+            &format!("{}-{}", self.name, &self.id[..8]),
+            self.local_addr.port(),
+        )
+        .with_endpoint("udp", &self.local_addr.to_string())
+        .with_metadata("role", MESH_ROLE)
+        .with_metadata("node", &self.id);
+        if let Err(e) = backend.announce(&announcement).await {
+            warn!("Mesh mDNS announce failed: {}", e);
+        }
+
+        let (tx, mut rx) = mpsc::channel(32);
+        let inner = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            let browse = async {
+                if let Some(backend) = inner.discovery.as_ref() {
+                    if let Err(e) = backend.browse(tx).await {
+                        warn!("Mesh mDNS browse failed: {}", e);
+                    }
+                }
+            };
+            let found = async {
+                while let Some(event) = rx.recv().await {
+                    let DiscoveryEvent::Found(device) = event else {
+                        continue;
+                    };
+                    let meta = &device.info.meta;
+                    if meta.get("role").map(String::as_str) != Some(MESH_ROLE)
+                        || meta.get("node") == Some(&inner.id)
+                    {
+                        continue;
+                    }
+                    if let Some(addr) = device.udp_addr() {
+                        debug!("Mesh discovered {} at {}", device.name, addr);
+                        inner.seeds.insert(addr, ());
+                        inner.hello(addr).await;
+                    }
+                }
+            };
+            tokio::join!(browse, found);
+        }))
+    }
+}
+
+fn mesh_features() -> Vec<String> {
+    vec![
+        "param".to_string(),
+        "event".to_string(),
+        "stream".to_string(),
+    ]
+}
+
+/// A node in a router-less LAN mesh
+///
+/// Cheap to clone; all clones share the same node.
+#[derive(Clone)]
+pub struct Mesh {
+    inner: Arc<MeshInner>,
+}
+
+impl Mesh {
+    /// This node's id
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// This node's name
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// The UDP address this node listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.inner.local_addr
+    }
+
+    /// Peers this node has completed a handshake with
+    pub fn peers(&self) -> Vec<MeshPeer> {
+        self.inner.identified_peers()
+    }
+
+    /// Set a callback for mesh events
+    pub fn on_event<F>(&self, callback: F)
+    where
+        F: Fn(MeshEvent) + Send + Sync + 'static,
+    {
+        *self.inner.event_callback.write() = Some(Box::new(callback));
+    }
+
+    /// Subscribe to an address pattern.
+    ///
+    /// The callback fires for params written by peers and for their events
+    /// and stream samples, not for this node's own writes.
+    pub fn subscribe<F>(&self, pattern: &str, callback: F) -> u32
+    where
+        F: Fn(Value, &str) + Send + Sync + 'static,
+    {
+        let id = self.inner.next_sub_id.fetch_add(1, Ordering::SeqCst);
+        self.inner
+            .subscriptions
+            .insert(id, (pattern.to_string(), Box::new(callback)));
+        id
+    }
+
+    /// Unsubscribe
+    pub fn unsubscribe(&self, id: u32) {
+        self.inner.subscriptions.remove(&id);
+    }
+
+    /// Set a param on every node
+    pub async fn set(&self, address: &str, value: impl Into<Value>) -> Result<()> {
+        let value = value.into();
+        let revision = self.inner.next_revision();
+        self.inner
+            .params
+            .insert(address.to_string(), (value.clone(), revision));
+
+        let msg = Message::Set(SetMessage {
+            address: address.to_string(),
+            value,
+            revision: Some(revision),
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        });
+        self.inner.broadcast(&msg).await
+    }
+
+    /// Current value of a param, as replicated to this node
+    pub fn get(&self, address: &str) -> Option<Value> {
+        self.inner.params.get(address).map(|entry| entry.0.clone())
+    }
+
+    /// Addresses and values of every param matching `pattern`
+    pub fn params(&self, pattern: &str) -> Vec<(String, Value)> {
+        self.inner
+            .params
+            .iter()
+            .filter(|entry| clasp_core::address::glob_match(pattern, entry.key()))
+            .map(|entry| (entry.key().clone(), entry.0.clone()))
+            .collect()
+    }
+
+    /// Send an event to every peer
+    pub async fn emit(&self, address: &str, payload: impl Into<Value>) -> Result<()> {
+        self.publish(address, SignalType::Event, payload.into())
+            .await
+    }
+
+    /// Send a stream sample to every peer
+    pub async fn stream(&self, address: &str, value: impl Into<Value>) -> Result<()> {
+        self.publish(address, SignalType::Stream, value.into())
+            .await
+    }
+
+    async fn publish(&self, address: &str, signal: SignalType, value: Value) -> Result<()> {
+        let msg = Message::Publish(PublishMessage {
+            address: address.to_string(),
+            signal: Some(signal),
+            value: Some(value),
+            payload: None,
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        });
+        self.inner.broadcast(&msg).await
+    }
+
+    /// Leave the mesh: stop gossiping and withdraw the mDNS advertisement
+    pub async fn close(&self) {
+        for task in self.inner.tasks.lock().drain(..) {
+            task.abort();
+        }
+        if let Some(backend) = self.inner.discovery.as_ref() {
+            let _ = backend.stop().await;
+        }
+    }
+}
@@ -9,7 +9,7 @@
 //! - Negative tests and edge cases
 //! - Value type coverage
 
-use clasp_client::{Clasp, ClaspBuilder};
+use clasp_client::{clasp_addresses, Clasp, ClaspBuilder, Governor};
 use clasp_core::{Message, SetMessage, Value};
 use clasp_test_utils::{TestRouter, ValueCollector};
 use std::time::Duration;
@@ -385,6 +385,57 @@ async fn test_cached_value() {
     client.close().await;
 }
 
+#[tokio::test]
+async fn test_query_signals() {
+    let router = TestRouter::start().await;
+    let client = Clasp::connect_to(&router.url())
+        .await
+        .expect("Connect failed");
+
+    // Nothing announced yet: the router still answers with an empty RESULT
+    let signals = timeout(Duration::from_secs(2), client.query("/**"))
+        .await
+        .expect("Query timed out")
+        .expect("Query failed");
+    assert!(signals.is_empty());
+
+    client.close().await;
+}
+
+clasp_addresses! {
+    lumen { scene(u8) { layer(u8) { opacity } } }
+}
+
+#[tokio::test]
+async fn test_typed_addresses() {
+    let router = TestRouter::start().await;
+    let client = Clasp::connect_to(&router.url())
+        .await
+        .expect("Connect failed");
+
+    let collector = ValueCollector::new();
+    client
+        .subscribe(
+            &lumen().scene(0).layer(3).opacity(),
+            collector.callback_ref(),
+        )
+        .await
+        .expect("Subscribe failed");
+
+    client
+        .set(&lumen().scene(0).layer(3).opacity(), 0.75)
+        .await
+        .expect("Set failed");
+
+    assert!(collector.wait_for_count(1, Duration::from_secs(2)).await);
+    assert_eq!(
+        collector.values_for("/lumen/scene/0/layer/3/opacity"),
+        vec![Value::Float(0.75)]
+    );
+
+    client.close().await;
+}
+
 // ============================================================================
 // Event Operations Tests
 // ============================================================================
@@ -478,6 +529,8 @@ async fn test_bundle() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
         Message::Set(SetMessage {
             address: "/bundle/b".to_string(),
@@ -485,6 +538,8 @@ async fn test_bundle() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
         Message::Set(SetMessage {
             address: "/bundle/c".to_string(),
@@ -492,6 +547,8 @@ async fn test_bundle() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
     ];
 
@@ -527,6 +584,8 @@ async fn test_bundle_atomicity() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             })
         })
         .collect();
@@ -554,6 +613,8 @@ async fn test_bundle_at() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     })];
 
     let future_time = client.time() + 100_000; // 100ms in microseconds
@@ -881,6 +942,93 @@ async fn test_bidirectional_communication() {
     client2.close().await;
 }
 
+// ============================================================================
+// Outbound Governor Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_governor_coalesces_to_latest() {
+    let router = TestRouter::start().await;
+
+    let watcher = Clasp::connect_to(&router.url())
+        .await
+        .expect("Connect failed");
+    let collector = ValueCollector::new();
+    watcher
+        .subscribe("/ui/**", collector.callback_ref())
+        .await
+        .expect("Subscribe failed");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let slider = ClaspBuilder::new(&router.url())
+        .governor(Governor::new().with_prefix_rate("/ui/", 10.0))
+        .connect()
+        .await
+        .expect("Connect failed");
+
+    // 100 moves in well under one interval
+    for i in 0..100 {
+        slider
+            .set("/ui/slider", i as f64)
+            .await
+            .expect("Set failed");
+    }
+    // Unlimited addresses are not held back
+    slider.set("/ui-free", 1.0).await.expect("Set failed");
+
+    assert!(collector.wait_for_count(2, Duration::from_secs(2)).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        collector.values_for("/ui/slider"),
+        vec![Value::Float(0.0), Value::Float(99.0)]
+    );
+
+    slider.close().await;
+    watcher.close().await;
+}
+
+#[tokio::test]
+async fn test_governor_limits_stream_rate() {
+    let router = TestRouter::start().await;
+
+    let watcher = Clasp::connect_to(&router.url())
+        .await
+        .expect("Connect failed");
+    let collector = ValueCollector::new();
+    watcher
+        .subscribe("/sensor/**", collector.callback_ref())
+        .await
+        .expect("Subscribe failed");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let sensor = ClaspBuilder::new(&router.url())
+        .governor(Governor::new().with_max_rate(20.0))
+        .connect()
+        .await
+        .expect("Connect failed");
+
+    // ~500/s for 300ms; at 20/s that is about 6 samples
+    for i in 0..150 {
+        sensor
+            .stream("/sensor/x", i as f64)
+            .await
+            .expect("Stream failed");
+        tokio::time::sleep(Duration::from_millis(2)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let values = collector.values_for("/sensor/x");
+    assert!(
+        (2..=12).contains(&values.len()),
+        "received {} samples",
+        values.len()
+    );
+    assert_eq!(values.last(), Some(&Value::Float(149.0)));
+
+    sensor.close().await;
+    watcher.close().await;
+}
+
 // ============================================================================
 // Concurrent Operations Tests
 // ============================================================================
@@ -0,0 +1,110 @@
+//! Router-less LAN mesh tests (clasp-client, `mesh` feature)
+//!
+//! Nodes are wired with static peers on localhost; mDNS is disabled so the
+//! tests don't depend on multicast being available.
+
+#![cfg(feature = "mesh")]
+
+use clasp_client::{Clasp, Mesh, MeshEvent};
+use clasp_core::Value;
+use clasp_test_utils::{wait_for, ValueCollector};
+use parking_lot::Mutex;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn node(name: &str, peers: &[SocketAddr]) -> Mesh {
+    let mut builder = Clasp::mesh_builder()
+        .name(name)
+        .bind("127.0.0.1")
+        .discovery(false)
+        .gossip_interval(Duration::from_millis(50))
+        .peer_timeout(Duration::from_millis(300));
+    for peer in peers {
+        builder = builder.peer(*peer);
+    }
+    builder.join().await.unwrap()
+}
+
+async fn wait_until(check: impl Fn() -> bool) -> bool {
+    wait_for(
+        || {
+            let ok = check();
+            async move { ok }
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(3),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_mesh_replicates_state() {
+    let a = node("a", &[]).await;
+    let b = node("b", &[a.local_addr()]).await;
+    assert!(wait_until(|| a.peers().len() == 1 && b.peers().len() == 1).await);
+    assert_eq!(a.peers()[0].name, "b");
+
+    let values = ValueCollector::new();
+    b.subscribe("/lights/**", values.callback_ref());
+    a.set("/lights/1", 0.5).await.unwrap();
+    assert!(values.wait_for_count(1, Duration::from_secs(2)).await);
+    assert_eq!(b.get("/lights/1"), Some(Value::Float(0.5)));
+
+    // A late joiner that only knows b still gets a's state via gossip
+    let c = node("c", &[b.local_addr()]).await;
+    assert!(wait_until(|| c.get("/lights/1") == Some(Value::Float(0.5))).await);
+
+    a.close().await;
+    b.close().await;
+    c.close().await;
+}
+
+#[tokio::test]
+async fn test_mesh_concurrent_writes_converge() {
+    let a = node("a", &[]).await;
+    let b = node("b", &[a.local_addr()]).await;
+    assert!(wait_until(|| a.peers().len() == 1 && b.peers().len() == 1).await);
+
+    let (ra, rb) = tokio::join!(a.set("/mode", "a"), b.set("/mode", "b"));
+    ra.unwrap();
+    rb.unwrap();
+
+    assert!(wait_until(|| a.get("/mode").is_some() && a.get("/mode") == b.get("/mode")).await);
+
+    // A write after seeing the other side's value always wins
+    b.set("/mode", "final").await.unwrap();
+    assert!(wait_until(|| a.get("/mode") == Some(Value::String("final".into()))).await);
+
+    a.close().await;
+    b.close().await;
+}
+
+#[tokio::test]
+async fn test_mesh_events_and_membership() {
+    let a = node("a", &[]).await;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&events);
+    a.on_event(move |event| {
+        log.lock().push(match event {
+            MeshEvent::PeerJoined(peer) => format!("joined {}", peer.name),
+            MeshEvent::PeerLeft(peer) => format!("left {}", peer.name),
+        });
+    });
+
+    let b = node("b", &[a.local_addr()]).await;
+    assert!(wait_until(|| a.peers().len() == 1).await);
+
+    let cues = ValueCollector::new();
+    a.subscribe("/cue/*", cues.callback_ref());
+    b.emit("/cue/go", 1).await.unwrap();
+    assert!(cues.wait_for_count(1, Duration::from_secs(2)).await);
+    // Events are not retained
+    assert_eq!(a.get("/cue/go"), None);
+
+    b.close().await;
+    assert!(wait_until(|| a.peers().is_empty()).await);
+    assert_eq!(*events.lock(), vec!["joined b", "left b"]);
+
+    a.close().await;
+}
@@ -0,0 +1,162 @@
+//! Client tests against the scripted mock router (clasp-test-utils)
+//!
+//! These exercise client behaviour the real router only shows under
+//! specific conditions: rejected writes, unanswered requests, slow and
+//! lossy links.
+
+use clasp_client::{Clasp, ClaspBuilder, ClientError};
+use clasp_core::{ErrorCode, Message, Value};
+use clasp_test_utils::{Faults, MockRouter, Reply, ValueCollector};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn test_mock_acks_and_records_sets() {
+    let mock = MockRouter::start().await;
+    let client = Clasp::connect_to(&mock.url()).await.unwrap();
+
+    let ack = client.set_with_ack("/mixer/gain", 0.5).await.unwrap();
+    assert_eq!(ack.revision, Some(1));
+
+    let sets = mock.sets();
+    assert_eq!(sets.len(), 1);
+    assert_eq!(sets[0].address, "/mixer/gain");
+    assert_eq!(mock.param("/mixer/gain"), Some(Value::Float(0.5)));
+    mock.assert_received(
+        |m| matches!(m, Message::Hello(_)),
+        Duration::from_secs(1),
+        "HELLO",
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_mock_initial_params_reach_cache() {
+    let mock = MockRouter::builder()
+        .param("/scene/current", "intro")
+        .start()
+        .await;
+    let client = Clasp::connect_to(&mock.url()).await.unwrap();
+
+    let value = client.get("/scene/current").await.unwrap();
+    assert_eq!(value, Value::String("intro".to_string()));
+}
+
+#[tokio::test]
+async fn test_mock_scripted_error() {
+    let mock = MockRouter::builder()
+        .on_set(
+            "/locked/**",
+            Reply::Error(ErrorCode::Forbidden, "read only".to_string()),
+        )
+        .start()
+        .await;
+    let client = Clasp::connect_to(&mock.url()).await.unwrap();
+
+    assert!(client.set_with_ack("/locked/a", 1).await.is_err());
+    assert!(client.set_with_ack("/open/a", 1).await.is_ok());
+    assert_eq!(mock.param("/locked/a"), None);
+}
+
+#[tokio::test]
+async fn test_mock_ignored_request_times_out() {
+    let mock = MockRouter::builder()
+        .on_get("/slow/**", Reply::Ignore)
+        .start()
+        .await;
+    let client = ClaspBuilder::new(&mock.url())
+        .request_timeout(Duration::from_millis(200))
+        .connect()
+        .await
+        .unwrap();
+
+    let result = client.get("/slow/value").await;
+    assert!(matches!(result, Err(ClientError::Timeout)));
+}
+
+#[tokio::test]
+async fn test_mock_rejected_hello() {
+    let mock = MockRouter::builder()
+        .reject_hello(ErrorCode::Unauthorized, "token required")
+        .start()
+        .await;
+
+    assert!(Clasp::connect_to(&mock.url()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_mock_pushes_to_subscribers() {
+    let mock = MockRouter::start().await;
+    let client = Clasp::connect_to(&mock.url()).await.unwrap();
+
+    let collector = ValueCollector::new();
+    client
+        .subscribe("/lights/**", collector.callback_ref())
+        .await
+        .unwrap();
+    mock.wait_for(
+        |m| matches!(m, Message::Subscribe(_)),
+        Duration::from_secs(1),
+    )
+    .await
+    .unwrap();
+
+    mock.set("/lights/1/level", 0.8);
+    assert!(collector.wait_for_count(1, Duration::from_secs(2)).await);
+    assert_eq!(
+        collector.values_for("/lights/1/level"),
+        vec![Value::Float(0.8)]
+    );
+}
+
+#[tokio::test]
+async fn test_mock_latency() {
+    let mock = MockRouter::start().await;
+    let client = Clasp::connect_to(&mock.url()).await.unwrap();
+    mock.set_faults(Faults {
+        latency: Duration::from_millis(150),
+        ..Default::default()
+    });
+
+    let start = Instant::now();
+    client.set_with_ack("/a", 1).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn test_mock_drops() {
+    let mock = MockRouter::start().await;
+    let client = ClaspBuilder::new(&mock.url())
+        .request_timeout(Duration::from_millis(200))
+        .connect()
+        .await
+        .unwrap();
+
+    mock.set_faults(Faults {
+        drop_rate: 1.0,
+        ..Default::default()
+    });
+    assert!(matches!(
+        client.set_with_ack("/a", 1).await,
+        Err(ClientError::Timeout)
+    ));
+
+    mock.set_faults(Faults::default());
+    assert!(client.set_with_ack("/a", 2).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_mock_disconnect() {
+    let mock = MockRouter::start().await;
+    let client = Clasp::connect_to(&mock.url()).await.unwrap();
+    assert_eq!(mock.connections(), 1);
+
+    mock.disconnect_all();
+    let disconnected = clasp_test_utils::wait_for(
+        || async { !client.is_connected() },
+        Duration::from_millis(10),
+        Duration::from_secs(2),
+    )
+    .await;
+    assert!(disconnected, "client never noticed the hang-up");
+}
@@ -10,6 +10,8 @@ fn encode_benchmark(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     c.bench_function("encode_set_message", |b| {
@@ -24,6 +26,8 @@ fn decode_benchmark(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     let encoded = codec::encode(&msg).unwrap();
 
@@ -50,6 +54,8 @@ fn roundtrip_benchmark(c: &mut Criterion) {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     c.bench_function("roundtrip_complex_message", |b| {
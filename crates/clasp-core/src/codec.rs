@@ -18,13 +18,19 @@ use std::collections::HashMap;
 /// Encoding version (1 = binary encoding, 0 = MessagePack legacy)
 pub const ENCODING_VERSION: u8 = 1;
 
+/// SUBSCRIBE's QoS byte when only a later trailer (`since`) is present
+const NO_QOS_OVERRIDE: u8 = 0xFF;
+
 /// Message type codes
 pub mod msg {
     pub const HELLO: u8 = 0x01;
     pub const WELCOME: u8 = 0x02;
     pub const ANNOUNCE: u8 = 0x03;
+    pub const CHANNEL: u8 = 0x04;
+    pub const ENVELOPE: u8 = 0x05;
     pub const SUBSCRIBE: u8 = 0x10;
     pub const UNSUBSCRIBE: u8 = 0x11;
+    pub const CREDIT: u8 = 0x12;
     pub const PUBLISH: u8 = 0x20;
     pub const SET: u8 = 0x21;
     pub const GET: u8 = 0x22;
@@ -64,6 +70,13 @@ pub mod sig {
     pub const TIMELINE: u8 = 4;
 }
 
+/// SET operation codes (trailing byte, absent for plain SETs)
+pub mod op {
+    pub const ADD: u8 = 1;
+    pub const MUL: u8 = 2;
+    pub const TOGGLE: u8 = 3;
+}
+
 /// Gesture phase codes
 pub mod phase {
     pub const START: u8 = 0;
@@ -153,6 +166,21 @@ pub fn encode_with_options(
     frame.encode()
 }
 
+/// Encode a message with a time-to-live (binary encoding)
+///
+/// The router drops the message instead of delivering it if it is still
+/// queued for a subscriber once `ttl` has passed. TTLs are rounded to whole
+/// milliseconds, at least one.
+pub fn encode_with_ttl(message: &Message, ttl: std::time::Duration) -> Result<Bytes> {
+    let payload = encode_message(message)?;
+    let ttl_ms = ttl.as_millis().clamp(1, u32::MAX as u128) as u32;
+    let mut frame = Frame::new(payload)
+        .with_qos(message.default_qos())
+        .with_ttl(ttl_ms);
+    frame.flags.version = 1; // binary encoding (1 = binary, 0 = MessagePack legacy)
+    frame.encode()
+}
+
 /// Decode a frame and extract the message
 #[inline]
 pub fn decode(bytes: &[u8]) -> Result<(Message, Frame)> {
@@ -172,6 +200,95 @@ pub fn decode_payload(bytes: &[u8]) -> Result<Message> {
     decode_message(bytes)
 }
 
+/// Rewrap an encoded frame as a CHANNEL frame for `channel`
+///
+/// QoS and timestamp of the original frame are kept. Used to route frames
+/// of a logical client over a shared connection without decoding them.
+pub fn encode_channel_frame(frame: &[u8], channel: u16) -> Result<Bytes> {
+    let frame = Frame::decode(frame)?;
+    let inner = match frame.payload.first() {
+        Some(&first) if is_msgpack_map(first) => {
+            encode_message(&decode_v2_msgpack(&frame.payload)?)?
+        }
+        _ => frame.payload,
+    };
+
+    let mut payload = BytesMut::with_capacity(3 + inner.len());
+    payload.put_u8(msg::CHANNEL);
+    payload.put_u16(channel);
+    payload.extend_from_slice(&inner);
+
+    let mut wrapped = Frame::new(payload.freeze()).with_qos(frame.flags.qos);
+    wrapped.flags.version = ENCODING_VERSION;
+    if let Some(ts) = frame.timestamp {
+        wrapped = wrapped.with_timestamp(ts);
+    }
+    if let Some(ttl) = frame.ttl {
+        wrapped = wrapped.with_ttl(ttl);
+    }
+    wrapped.encode()
+}
+
+/// Split a snapshot into pages whose payloads fit in `max_payload` bytes
+///
+/// A snapshot that fits is returned unchanged, without page info, so small
+/// snapshots look the same to peers that predate paging. Otherwise every
+/// page is numbered and all but the last are marked `more`; the correlation
+/// id and sync trailer go on the last page. A single param larger than `max_payload` still
+/// gets a page of its own (and will fail to encode).
+pub fn paginate_snapshot(snapshot: SnapshotMessage, max_payload: usize) -> Vec<SnapshotMessage> {
+    // Type byte, param count, correlation id, page and sync trailers
+    const OVERHEAD: usize = 1 + 2 + 4 + 5 + 16;
+
+    let mut pages: Vec<Vec<ParamValue>> = Vec::new();
+    let mut current = Vec::new();
+    let mut size = OVERHEAD;
+    let mut scratch = BytesMut::new();
+    for param in snapshot.params {
+        scratch.clear();
+        let param_size = match encode_param_value(&mut scratch, &param) {
+            Ok(()) => scratch.len(),
+            Err(_) => max_payload,
+        };
+        let full = size + param_size > max_payload || current.len() == u16::MAX as usize;
+        if full && !current.is_empty() {
+            pages.push(std::mem::take(&mut current));
+            size = OVERHEAD;
+        }
+        size += param_size;
+        current.push(param);
+    }
+    pages.push(current);
+
+    if pages.len() == 1 {
+        return vec![SnapshotMessage {
+            params: pages.pop().unwrap_or_default(),
+            correlation_id: snapshot.correlation_id,
+            page: None,
+            sync: snapshot.sync,
+        }];
+    }
+
+    let last = pages.len() - 1;
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, params)| SnapshotMessage {
+            params,
+            correlation_id: if i == last {
+                snapshot.correlation_id
+            } else {
+                None
+            },
+            page: Some(SnapshotPage {
+                sequence: i as u32,
+                more: i != last,
+            }),
+            sync: if i == last { snapshot.sync } else { None },
+        })
+        .collect()
+}
+
 // ============================================================================
 // BINARY ENCODING
 // ============================================================================
@@ -201,11 +318,15 @@ fn encode_message_to_buf(buf: &mut BytesMut, msg: &Message) -> Result<()> {
         Message::Error(m) => encode_error(buf, m),
         Message::Query(m) => encode_query(buf, m),
         Message::Result(m) => encode_result(buf, m),
+        Message::Channel(m) => encode_channel(buf, m),
+        Message::Envelope(m) => encode_envelope(buf, m),
+        Message::Credit(m) => encode_credit(buf, m),
     }
 }
 
 /// SET (0x21) - Parameter Update
-/// Flags: [has_rev:1][lock:1][unlock:1][rsv:1][vtype:4]
+/// Flags: [has_rev:1][lock:1][unlock:1][has_corr:1][vtype:4]
+/// A relative SET ends with its op code.
 #[inline]
 fn encode_set(buf: &mut BytesMut, msg: &SetMessage) -> Result<()> {
     buf.put_u8(msg::SET);
@@ -221,6 +342,9 @@ fn encode_set(buf: &mut BytesMut, msg: &SetMessage) -> Result<()> {
     if msg.unlock {
         flags |= 0x20;
     }
+    if msg.correlation_id.is_some() {
+        flags |= 0x10;
+    }
     buf.put_u8(flags);
 
     // Address
@@ -234,6 +358,16 @@ fn encode_set(buf: &mut BytesMut, msg: &SetMessage) -> Result<()> {
         buf.put_u64(rev);
     }
 
+    // Optional correlation id
+    if let Some(corr) = msg.correlation_id {
+        buf.put_u32(corr);
+    }
+
+    // Optional op
+    if let Some(set_op) = msg.op {
+        buf.put_u8(set_op_code(set_op));
+    }
+
     Ok(())
 }
 
@@ -330,6 +464,11 @@ fn encode_hello(buf: &mut BytesMut, msg: &HelloMessage) -> Result<()> {
         buf.put_u16(0);
     }
 
+    // Optional trailing store revision to resync from
+    if let Some(since) = msg.since {
+        buf.put_u64(since);
+    }
+
     Ok(())
 }
 
@@ -477,6 +616,18 @@ fn encode_subscribe(buf: &mut BytesMut, msg: &SubscribeMessage) -> Result<()> {
         if opts.window.is_some() {
             opt_flags |= 0x08;
         }
+        if opts.batch.is_some() {
+            opt_flags |= 0x20;
+        }
+        if opts.origins.is_some() {
+            opt_flags |= 0x40;
+        }
+        if opts.envelope {
+            opt_flags |= 0x80;
+        }
+        if msg.correlation_id.is_some() {
+            opt_flags |= 0x10;
+        }
         buf.put_u8(opt_flags);
 
         if let Some(rate) = opts.max_rate {
@@ -491,10 +642,46 @@ fn encode_subscribe(buf: &mut BytesMut, msg: &SubscribeMessage) -> Result<()> {
         if let Some(win) = opts.window {
             buf.put_u32(win);
         }
+        if let Some(ms) = opts.batch {
+            buf.put_u32(ms);
+        }
+        if let Some(ref origins) = opts.origins {
+            if origins.len() > u8::MAX as usize {
+                return Err(Error::PayloadTooLarge(origins.len()));
+            }
+            buf.put_u8(origins.len() as u8);
+            for protocol in origins {
+                encode_string(buf, protocol)?;
+            }
+        }
+    } else if msg.correlation_id.is_some() {
+        buf.put_u8(0x10); // Correlation id only
     } else {
         buf.put_u8(0); // No options
     }
 
+    if let Some(corr) = msg.correlation_id {
+        buf.put_u32(corr);
+    }
+
+    // Optional delivery QoS override, then optional store revision to
+    // resync from, then optional flow control window. Each trailer needs
+    // the ones before it: the QoS byte is 0xFF and `since` 0 when unset.
+    let qos = msg.options.as_ref().and_then(|opts| opts.qos);
+    let since = msg.options.as_ref().and_then(|opts| opts.since);
+    let credits = msg.options.as_ref().and_then(|opts| opts.credits);
+    if let Some(qos) = qos {
+        buf.put_u8(qos as u8);
+    } else if since.is_some() || credits.is_some() {
+        buf.put_u8(NO_QOS_OVERRIDE);
+    }
+    if since.is_some() || credits.is_some() {
+        buf.put_u64(since.unwrap_or(0));
+    }
+    if let Some(credits) = credits {
+        buf.put_u32(credits);
+    }
+
     Ok(())
 }
 
@@ -505,10 +692,22 @@ fn encode_unsubscribe(buf: &mut BytesMut, msg: &UnsubscribeMessage) -> Result<()
     Ok(())
 }
 
+/// CREDIT (0x12)
+fn encode_credit(buf: &mut BytesMut, msg: &CreditMessage) -> Result<()> {
+    buf.put_u8(msg::CREDIT);
+    buf.put_u32(msg.id);
+    buf.put_u32(msg.credits);
+    Ok(())
+}
+
 /// GET (0x22)
 fn encode_get(buf: &mut BytesMut, msg: &GetMessage) -> Result<()> {
     buf.put_u8(msg::GET);
     encode_string(buf, &msg.address)?;
+    // Optional trailing correlation id
+    if let Some(corr) = msg.correlation_id {
+        buf.put_u32(corr);
+    }
     Ok(())
 }
 
@@ -518,28 +717,47 @@ fn encode_snapshot(buf: &mut BytesMut, msg: &SnapshotMessage) -> Result<()> {
     buf.put_u16(msg.params.len() as u16);
 
     for param in &msg.params {
-        encode_string(buf, &param.address)?;
-        buf.put_u8(value_type_code(&param.value));
-        encode_value_data(buf, &param.value)?;
-        buf.put_u64(param.revision);
+        encode_param_value(buf, param)?;
+    }
 
-        let mut opt_flags: u8 = 0;
-        if param.writer.is_some() {
-            opt_flags |= 0x01;
-        }
-        if param.timestamp.is_some() {
-            opt_flags |= 0x02;
-        }
-        buf.put_u8(opt_flags);
+    // Optional trailing correlation id, then optional page trailer
+    if let Some(corr) = msg.correlation_id {
+        buf.put_u32(corr);
+    }
+    if let Some(page) = msg.page {
+        buf.put_u32(page.sequence);
+        buf.put_u8(if page.more { 0x01 } else { 0x00 });
+    }
+    // Optional sync trailer: revision, then diff base (0 = full snapshot)
+    if let Some(sync) = msg.sync {
+        buf.put_u64(sync.revision);
+        buf.put_u64(sync.since.unwrap_or(0));
+    }
 
-        if let Some(ref writer) = param.writer {
-            encode_string(buf, writer)?;
-        }
-        if let Some(ts) = param.timestamp {
-            buf.put_u64(ts);
-        }
+    Ok(())
+}
+
+fn encode_param_value(buf: &mut BytesMut, param: &ParamValue) -> Result<()> {
+    encode_string(buf, &param.address)?;
+    buf.put_u8(value_type_code(&param.value));
+    encode_value_data(buf, &param.value)?;
+    buf.put_u64(param.revision);
+
+    let mut opt_flags: u8 = 0;
+    if param.writer.is_some() {
+        opt_flags |= 0x01;
     }
+    if param.timestamp.is_some() {
+        opt_flags |= 0x02;
+    }
+    buf.put_u8(opt_flags);
 
+    if let Some(ref writer) = param.writer {
+        encode_string(buf, writer)?;
+    }
+    if let Some(ts) = param.timestamp {
+        buf.put_u64(ts);
+    }
     Ok(())
 }
 
@@ -551,6 +769,9 @@ fn encode_bundle(buf: &mut BytesMut, msg: &BundleMessage) -> Result<()> {
     if msg.timestamp.is_some() {
         flags |= 0x80;
     }
+    if msg.correlation_id.is_some() {
+        flags |= 0x40;
+    }
     buf.put_u8(flags);
 
     buf.put_u16(msg.messages.len() as u16);
@@ -558,6 +779,9 @@ fn encode_bundle(buf: &mut BytesMut, msg: &BundleMessage) -> Result<()> {
     if let Some(ts) = msg.timestamp {
         buf.put_u64(ts);
     }
+    if let Some(corr) = msg.correlation_id {
+        buf.put_u32(corr);
+    }
 
     // Each message prefixed with length
     for inner_msg in &msg.messages {
@@ -614,6 +838,9 @@ fn encode_ack(buf: &mut BytesMut, msg: &AckMessage) -> Result<()> {
     if msg.correlation_id.is_some() {
         flags |= 0x10;
     }
+    if msg.value.is_some() {
+        flags |= 0x20;
+    }
     buf.put_u8(flags);
 
     if let Some(ref addr) = msg.address {
@@ -631,6 +858,10 @@ fn encode_ack(buf: &mut BytesMut, msg: &AckMessage) -> Result<()> {
     if let Some(corr) = msg.correlation_id {
         buf.put_u32(corr);
     }
+    if let Some(ref value) = msg.value {
+        buf.put_u8(value_type_code(value));
+        encode_value_data(buf, value)?;
+    }
 
     Ok(())
 }
@@ -648,6 +879,12 @@ fn encode_error(buf: &mut BytesMut, msg: &ErrorMessage) -> Result<()> {
     if msg.correlation_id.is_some() {
         flags |= 0x02;
     }
+    if msg.revision.is_some() {
+        flags |= 0x04;
+    }
+    if msg.value.is_some() {
+        flags |= 0x08;
+    }
     buf.put_u8(flags);
 
     if let Some(ref addr) = msg.address {
@@ -656,6 +893,13 @@ fn encode_error(buf: &mut BytesMut, msg: &ErrorMessage) -> Result<()> {
     if let Some(corr) = msg.correlation_id {
         buf.put_u32(corr);
     }
+    if let Some(rev) = msg.revision {
+        buf.put_u64(rev);
+    }
+    if let Some(ref value) = msg.value {
+        buf.put_u8(value_type_code(value));
+        encode_value_data(buf, value)?;
+    }
 
     Ok(())
 }
@@ -696,6 +940,32 @@ fn encode_result(buf: &mut BytesMut, msg: &ResultMessage) -> Result<()> {
     Ok(())
 }
 
+/// CHANNEL (0x04)
+/// Channel id, then the wrapped message (nothing when closing)
+fn encode_channel(buf: &mut BytesMut, msg: &ChannelMessage) -> Result<()> {
+    buf.put_u8(msg::CHANNEL);
+    buf.put_u16(msg.channel);
+    if let Some(ref inner) = msg.message {
+        encode_message_to_buf(buf, inner)?;
+    }
+    Ok(())
+}
+
+/// ENVELOPE (0x05)
+/// Origin protocol, [has_remote:u8][remote], then the wrapped message
+fn encode_envelope(buf: &mut BytesMut, msg: &EnvelopeMessage) -> Result<()> {
+    buf.put_u8(msg::ENVELOPE);
+    encode_string(buf, &msg.origin.protocol)?;
+    match msg.origin.remote {
+        Some(ref remote) => {
+            buf.put_u8(1);
+            encode_string(buf, remote)?;
+        }
+        None => buf.put_u8(0),
+    }
+    encode_message_to_buf(buf, &msg.message)
+}
+
 // ============================================================================
 // VALUE ENCODING HELPERS
 // ============================================================================
@@ -793,6 +1063,7 @@ fn decode_v3_binary(bytes: &[u8]) -> Result<Message> {
         msg::ANNOUNCE => decode_announce(&mut buf),
         msg::SUBSCRIBE => decode_subscribe(&mut buf),
         msg::UNSUBSCRIBE => decode_unsubscribe(&mut buf),
+        msg::CREDIT => decode_credit(&mut buf),
         msg::PUBLISH => decode_publish(&mut buf),
         msg::SET => decode_set(&mut buf),
         msg::GET => decode_get(&mut buf),
@@ -805,6 +1076,8 @@ fn decode_v3_binary(bytes: &[u8]) -> Result<Message> {
         msg::ERROR => decode_error(&mut buf),
         msg::QUERY => decode_query(&mut buf),
         msg::RESULT => decode_result(&mut buf),
+        msg::CHANNEL => decode_channel(&mut buf),
+        msg::ENVELOPE => decode_envelope(&mut buf),
         _ => Err(Error::UnknownMessageType(msg_type)),
     }
 }
@@ -816,11 +1089,18 @@ fn decode_set(buf: &mut &[u8]) -> Result<Message> {
     let has_rev = (flags & 0x80) != 0;
     let lock = (flags & 0x40) != 0;
     let unlock = (flags & 0x20) != 0;
+    let has_corr = (flags & 0x10) != 0;
 
     let address = decode_string(buf)?;
     let value = decode_value_data(buf, vtype)?;
 
     let revision = if has_rev { Some(buf.get_u64()) } else { None };
+    let correlation_id = if has_corr { Some(buf.get_u32()) } else { None };
+    let op = if buf.has_remaining() {
+        Some(set_op_from_code(buf.get_u8())?)
+    } else {
+        None
+    };
 
     Ok(Message::Set(SetMessage {
         address,
@@ -828,6 +1108,8 @@ fn decode_set(buf: &mut &[u8]) -> Result<Message> {
         revision,
         lock,
         unlock,
+        op,
+        correlation_id,
     }))
 }
 
@@ -916,12 +1198,19 @@ fn decode_hello(buf: &mut &[u8]) -> Result<Message> {
         Some(token_str)
     };
 
+    let since = if buf.remaining() >= 8 {
+        Some(buf.get_u64())
+    } else {
+        None
+    };
+
     Ok(Message::Hello(HelloMessage {
         version,
         name,
         features,
         capabilities: None,
         token,
+        since,
     }))
 }
 
@@ -1068,7 +1357,7 @@ fn decode_subscribe(buf: &mut &[u8]) -> Result<Message> {
     }
 
     let opt_flags = buf.get_u8();
-    let options = if opt_flags != 0 {
+    let mut options = if opt_flags & 0xEF != 0 {
         let max_rate = if opt_flags & 0x01 != 0 {
             Some(buf.get_u32())
         } else {
@@ -1089,22 +1378,71 @@ fn decode_subscribe(buf: &mut &[u8]) -> Result<Message> {
         } else {
             None
         };
+        let batch = if opt_flags & 0x20 != 0 {
+            Some(buf.get_u32())
+        } else {
+            None
+        };
+        let origins = if opt_flags & 0x40 != 0 {
+            let count = buf.get_u8() as usize;
+            let mut origins = Vec::with_capacity(count);
+            for _ in 0..count {
+                origins.push(decode_string(buf)?);
+            }
+            Some(origins)
+        } else {
+            None
+        };
 
         Some(SubscribeOptions {
             max_rate,
             epsilon,
             history,
             window,
+            batch,
+            origins,
+            envelope: opt_flags & 0x80 != 0,
+            qos: None,
+            since: None,
+            credits: None,
         })
     } else {
         None
     };
 
+    let correlation_id = if opt_flags & 0x10 != 0 {
+        Some(buf.get_u32())
+    } else {
+        None
+    };
+
+    if buf.has_remaining() {
+        let code = buf.get_u8();
+        if code != NO_QOS_OVERRIDE {
+            let qos = QoS::from_u8(code)
+                .ok_or_else(|| Error::DecodeError(format!("unknown QoS: {}", code)))?;
+            options.get_or_insert_with(SubscribeOptions::default).qos = Some(qos);
+        }
+    }
+    if buf.remaining() >= 8 {
+        let since = buf.get_u64();
+        if since != 0 {
+            options.get_or_insert_with(SubscribeOptions::default).since = Some(since);
+        }
+    }
+    if buf.remaining() >= 4 {
+        let credits = buf.get_u32();
+        options
+            .get_or_insert_with(SubscribeOptions::default)
+            .credits = Some(credits);
+    }
+
     Ok(Message::Subscribe(SubscribeMessage {
         id,
         pattern,
         types,
         options,
+        correlation_id,
     }))
 }
 
@@ -1113,9 +1451,23 @@ fn decode_unsubscribe(buf: &mut &[u8]) -> Result<Message> {
     Ok(Message::Unsubscribe(UnsubscribeMessage { id }))
 }
 
+fn decode_credit(buf: &mut &[u8]) -> Result<Message> {
+    let id = buf.get_u32();
+    let credits = buf.get_u32();
+    Ok(Message::Credit(CreditMessage { id, credits }))
+}
+
 fn decode_get(buf: &mut &[u8]) -> Result<Message> {
     let address = decode_string(buf)?;
-    Ok(Message::Get(GetMessage { address }))
+    let correlation_id = if buf.remaining() >= 4 {
+        Some(buf.get_u32())
+    } else {
+        None
+    };
+    Ok(Message::Get(GetMessage {
+        address,
+        correlation_id,
+    }))
 }
 
 fn decode_snapshot(buf: &mut &[u8]) -> Result<Message> {
@@ -1149,15 +1501,49 @@ fn decode_snapshot(buf: &mut &[u8]) -> Result<Message> {
         });
     }
 
-    Ok(Message::Snapshot(SnapshotMessage { params }))
+    // Trailers: correlation id (4 bytes), page (5 bytes) and sync (16 bytes),
+    // told apart by the length left; without sync at most 9 bytes remain
+    let has_sync = buf.remaining() >= 16;
+    let rest = buf.remaining() - if has_sync { 16 } else { 0 };
+    let correlation_id = if matches!(rest, 4 | 9) {
+        Some(buf.get_u32())
+    } else {
+        None
+    };
+    let page = if rest >= 5 {
+        let sequence = buf.get_u32();
+        let more = buf.get_u8() & 0x01 != 0;
+        Some(SnapshotPage { sequence, more })
+    } else {
+        None
+    };
+    let sync = if has_sync {
+        let revision = buf.get_u64();
+        let since = buf.get_u64();
+        Some(SnapshotSync {
+            revision,
+            since: (since != 0).then_some(since),
+        })
+    } else {
+        None
+    };
+
+    Ok(Message::Snapshot(SnapshotMessage {
+        params,
+        correlation_id,
+        page,
+        sync,
+    }))
 }
 
 fn decode_bundle(buf: &mut &[u8]) -> Result<Message> {
     let flags = buf.get_u8();
     let has_ts = (flags & 0x80) != 0;
+    let has_corr = (flags & 0x40) != 0;
     let count = buf.get_u16() as usize;
 
     let timestamp = if has_ts { Some(buf.get_u64()) } else { None };
+    let correlation_id = if has_corr { Some(buf.get_u32()) } else { None };
 
     let mut messages = Vec::with_capacity(count);
     for _ in 0..count {
@@ -1170,6 +1556,7 @@ fn decode_bundle(buf: &mut &[u8]) -> Result<Message> {
     Ok(Message::Bundle(BundleMessage {
         timestamp,
         messages,
+        correlation_id,
     }))
 }
 
@@ -1218,12 +1605,19 @@ fn decode_ack(buf: &mut &[u8]) -> Result<Message> {
     } else {
         None
     };
+    let value = if flags & 0x20 != 0 {
+        let vtype = buf.get_u8();
+        Some(decode_value_data(buf, vtype)?)
+    } else {
+        None
+    };
 
     Ok(Message::Ack(AckMessage {
         address,
         revision,
         locked,
         holder,
+        value,
         correlation_id,
     }))
 }
@@ -1243,12 +1637,25 @@ fn decode_error(buf: &mut &[u8]) -> Result<Message> {
     } else {
         None
     };
+    let revision = if flags & 0x04 != 0 {
+        Some(buf.get_u64())
+    } else {
+        None
+    };
+    let value = if flags & 0x08 != 0 {
+        let vtype = buf.get_u8();
+        Some(decode_value_data(buf, vtype)?)
+    } else {
+        None
+    };
 
     Ok(Message::Error(ErrorMessage {
         code,
         message,
         address,
         correlation_id,
+        revision,
+        value,
     }))
 }
 
@@ -1294,6 +1701,39 @@ fn decode_result(buf: &mut &[u8]) -> Result<Message> {
 // ============================================================================
 
 #[inline(always)]
+fn decode_channel(buf: &mut &[u8]) -> Result<Message> {
+    if buf.remaining() < 2 {
+        return Err(Error::BufferTooSmall {
+            needed: 2,
+            have: buf.remaining(),
+        });
+    }
+    let channel = buf.get_u16();
+    let message = if buf.is_empty() {
+        None
+    } else {
+        Some(Box::new(decode_v3_binary(buf)?))
+    };
+    Ok(Message::Channel(ChannelMessage { channel, message }))
+}
+
+fn decode_envelope(buf: &mut &[u8]) -> Result<Message> {
+    let protocol = decode_string(buf)?;
+    if buf.remaining() < 1 {
+        return Err(Error::BufferTooSmall { needed: 1, have: 0 });
+    }
+    let remote = if buf.get_u8() != 0 {
+        Some(decode_string(buf)?)
+    } else {
+        None
+    };
+    let message = decode_v3_binary(buf)?;
+    Ok(Message::Envelope(EnvelopeMessage {
+        origin: Origin { protocol, remote },
+        message: Box::new(message),
+    }))
+}
+
 fn decode_string(buf: &mut &[u8]) -> Result<String> {
     if buf.remaining() < 2 {
         return Err(Error::BufferTooSmall {
@@ -1405,6 +1845,23 @@ fn signal_type_from_code(code: u8) -> SignalType {
     }
 }
 
+fn set_op_code(set_op: SetOp) -> u8 {
+    match set_op {
+        SetOp::Add => op::ADD,
+        SetOp::Mul => op::MUL,
+        SetOp::Toggle => op::TOGGLE,
+    }
+}
+
+fn set_op_from_code(code: u8) -> Result<SetOp> {
+    match code {
+        op::ADD => Ok(SetOp::Add),
+        op::MUL => Ok(SetOp::Mul),
+        op::TOGGLE => Ok(SetOp::Toggle),
+        _ => Err(Error::DecodeError(format!("unknown SET op: {}", code))),
+    }
+}
+
 fn gesture_phase_from_code(code: u8) -> GesturePhase {
     match code {
         phase::START => GesturePhase::Start,
@@ -1444,6 +1901,7 @@ mod tests {
             features: vec!["param".to_string(), "event".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
 
         let encoded = encode(&msg).unwrap();
@@ -1471,6 +1929,8 @@ mod tests {
             revision: Some(42),
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = encode(&msg).unwrap();
@@ -1496,6 +1956,8 @@ mod tests {
             revision: Some(1),
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         // Binary encoding
@@ -1536,6 +1998,8 @@ mod tests {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }),
                 Message::Set(SetMessage {
                     address: "/light/2".to_string(),
@@ -1543,8 +2007,11 @@ mod tests {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }),
             ],
+            correlation_id: None,
         });
 
         let encoded = encode(&msg).unwrap();
@@ -1577,6 +2044,8 @@ mod tests {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
 
             let encoded = encode(&msg).unwrap();
@@ -1600,6 +2069,8 @@ mod tests {
             revision: Some(1),
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         };
 
         // Encode as v2 (MessagePack with named keys)
@@ -1667,7 +2138,14 @@ mod tests {
                 epsilon: Some(0.01),
                 history: None,
                 window: None,
+                batch: Some(16),
+                origins: Some(vec!["clasp".to_string(), "osc".to_string()]),
+                envelope: true,
+                qos: Some(QoS::Fire),
+                since: None,
+                credits: None,
             }),
+            correlation_id: Some(7),
         });
 
         let encoded = encode(&msg).unwrap();
@@ -1680,8 +2158,444 @@ mod tests {
                 assert!(sub.types.contains(&SignalType::Param));
                 assert!(sub.types.contains(&SignalType::Stream));
                 assert_eq!(sub.options.as_ref().unwrap().max_rate, Some(60));
+                assert_eq!(sub.options.as_ref().unwrap().batch, Some(16));
+                assert_eq!(
+                    sub.options.as_ref().unwrap().origins.as_deref(),
+                    Some(&["clasp".to_string(), "osc".to_string()][..])
+                );
+                assert!(sub.options.as_ref().unwrap().envelope);
+                assert_eq!(sub.options.as_ref().unwrap().qos, Some(QoS::Fire));
+                assert_eq!(sub.correlation_id, Some(7));
             }
             _ => panic!("Expected Subscribe message"),
         }
+
+        // A QoS override alone still round-trips as options
+        let msg = Message::Subscribe(SubscribeMessage {
+            id: 43,
+            pattern: "/meters/**".to_string(),
+            types: vec![],
+            options: Some(SubscribeOptions {
+                qos: Some(QoS::Confirm),
+                ..Default::default()
+            }),
+            correlation_id: None,
+        });
+        let (decoded, _) = decode(&encode(&msg).unwrap()).unwrap();
+        let Message::Subscribe(sub) = decoded else {
+            panic!("Expected Subscribe message");
+        };
+        assert_eq!(sub.options.unwrap().qos, Some(QoS::Confirm));
+    }
+
+    #[test]
+    fn test_set_op_roundtrip() {
+        let msg = Message::Set(SetMessage {
+            address: "/mixer/gain".to_string(),
+            value: Value::Int(-2),
+            revision: Some(4),
+            lock: false,
+            unlock: false,
+            op: Some(SetOp::Add),
+            correlation_id: Some(9),
+        });
+        let (decoded, _) = decode(&encode(&msg).unwrap()).unwrap();
+        let Message::Set(set) = decoded else {
+            panic!("Expected Set message");
+        };
+        assert_eq!(set.op, Some(SetOp::Add));
+        assert_eq!(set.value, Value::Int(-2));
+        assert_eq!(set.revision, Some(4));
+        assert_eq!(set.correlation_id, Some(9));
+
+        let ack = Message::Ack(AckMessage {
+            address: Some("/mixer/gain".to_string()),
+            revision: Some(5),
+            locked: None,
+            holder: None,
+            value: Some(Value::Float(0.25)),
+            correlation_id: Some(9),
+        });
+        let (decoded, _) = decode(&encode(&ack).unwrap()).unwrap();
+        let Message::Ack(ack) = decoded else {
+            panic!("Expected Ack message");
+        };
+        assert_eq!(ack.value, Some(Value::Float(0.25)));
+        assert_eq!(ack.correlation_id, Some(9));
+    }
+
+    #[test]
+    fn test_channel_roundtrip() {
+        let set = Message::Set(SetMessage {
+            address: "/ui/fader".to_string(),
+            value: Value::Float(0.5),
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        });
+        let msg = Message::Channel(ChannelMessage::new(3, set.clone()));
+        let encoded = encode(&msg).unwrap();
+        match decode(&encoded).unwrap() {
+            (
+                Message::Channel(ChannelMessage {
+                    channel: 3,
+                    message: Some(inner),
+                }),
+                frame,
+            ) => {
+                assert!(matches!(*inner, Message::Set(ref s) if s.address == "/ui/fader"));
+                assert_eq!(frame.flags.qos, QoS::Confirm);
+            }
+            other => panic!("Expected Channel message, got {:?}", other),
+        }
+
+        // Rewrapping an encoded frame gives the same bytes
+        let rewrapped = encode_channel_frame(&encode(&set).unwrap(), 3).unwrap();
+        assert_eq!(rewrapped, encoded);
+
+        let close = encode(&Message::Channel(ChannelMessage::close(3))).unwrap();
+        assert!(matches!(
+            decode(&close).unwrap().0,
+            Message::Channel(ChannelMessage {
+                channel: 3,
+                message: None
+            })
+        ));
+    }
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let publish = Message::Publish(PublishMessage {
+            address: "/sensors/temp".to_string(),
+            signal: Some(SignalType::Event),
+            value: None,
+            payload: Some(Value::Float(21.5)),
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        });
+        for origin in [
+            Origin::new("mqtt").with_remote("sensor-7"),
+            Origin::native(),
+        ] {
+            let msg = Message::Envelope(EnvelopeMessage::new(origin.clone(), publish.clone()));
+            let encoded = encode(&msg).unwrap();
+            match decode(&encoded).unwrap().0 {
+                Message::Envelope(envelope) => {
+                    assert_eq!(envelope.origin, origin);
+                    assert!(
+                        matches!(*envelope.message, Message::Publish(ref p) if p.address == "/sensors/temp")
+                    );
+                }
+                other => panic!("Expected Envelope message, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_ttl_frames() {
+        let stream = Message::Publish(PublishMessage {
+            address: "/tracker/1/pos".to_string(),
+            signal: Some(SignalType::Stream),
+            value: Some(Value::Float(0.25)),
+            payload: None,
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        });
+
+        let encoded = encode_with_ttl(&stream, std::time::Duration::from_millis(40)).unwrap();
+        let (decoded, frame) = decode(&encoded).unwrap();
+        assert!(matches!(decoded, Message::Publish(ref p) if p.address == "/tracker/1/pos"));
+        assert_eq!(frame.ttl, Some(40));
+        assert!(frame.flags.is_binary_encoding());
+
+        // Sub-millisecond TTLs round up rather than becoming "no TTL"
+        let encoded = encode_with_ttl(&stream, std::time::Duration::from_micros(10)).unwrap();
+        assert_eq!(decode(&encoded).unwrap().1.ttl, Some(1));
+
+        // A channel keeps the TTL of the frame it wraps
+        let wrapped = encode_channel_frame(&encoded, 2).unwrap();
+        assert_eq!(decode(&wrapped).unwrap().1.ttl, Some(1));
+    }
+
+    #[test]
+    fn test_correlation_id_roundtrip() {
+        let requests = [
+            Message::Set(SetMessage {
+                address: "/a".to_string(),
+                value: Value::Int(1),
+                revision: Some(3),
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: Some(7),
+            }),
+            Message::Get(GetMessage {
+                address: "/a".to_string(),
+                correlation_id: Some(8),
+            }),
+            Message::Subscribe(SubscribeMessage {
+                id: 1,
+                pattern: "/a/**".to_string(),
+                types: vec![],
+                options: None,
+                correlation_id: Some(9),
+            }),
+            Message::Snapshot(SnapshotMessage {
+                params: vec![ParamValue {
+                    address: "/a".to_string(),
+                    value: Value::Int(1),
+                    revision: 3,
+                    writer: None,
+                    timestamp: None,
+                }],
+                correlation_id: Some(10),
+                page: None,
+                sync: None,
+            }),
+            Message::Bundle(BundleMessage {
+                timestamp: Some(1000),
+                messages: vec![],
+                correlation_id: Some(11),
+            }),
+        ];
+
+        for (msg, expected) in requests.iter().zip(7u32..) {
+            let (decoded, _) = decode(&encode(msg).unwrap()).unwrap();
+            let correlation_id = match decoded {
+                Message::Set(m) => m.correlation_id,
+                Message::Get(m) => m.correlation_id,
+                Message::Subscribe(m) => {
+                    assert!(m.options.is_none());
+                    m.correlation_id
+                }
+                Message::Snapshot(m) => m.correlation_id,
+                Message::Bundle(m) => {
+                    assert_eq!(m.timestamp, Some(1000));
+                    m.correlation_id
+                }
+                other => panic!("unexpected {:?}", other),
+            };
+            assert_eq!(correlation_id, Some(expected));
+        }
+
+        // Without an id the encoding is unchanged
+        let get = Message::Get(GetMessage {
+            address: "/a".to_string(),
+            correlation_id: None,
+        });
+        let (decoded, _) = decode(&encode(&get).unwrap()).unwrap();
+        assert!(matches!(decoded, Message::Get(m) if m.correlation_id.is_none()));
+    }
+
+    #[test]
+    fn test_snapshot_paging() {
+        let params: Vec<ParamValue> = (0..2000)
+            .map(|i| ParamValue {
+                address: format!("/big/namespace/param/{}", i),
+                value: Value::Float(i as f64),
+                revision: i,
+                writer: Some("writer".to_string()),
+                timestamp: Some(1000),
+            })
+            .collect();
+        let snapshot = SnapshotMessage {
+            params: params.clone(),
+            correlation_id: Some(42),
+            page: None,
+            sync: None,
+        };
+
+        let pages = paginate_snapshot(snapshot, 8192);
+        assert!(pages.len() > 1);
+
+        let mut reassembled = Vec::new();
+        for (i, page) in pages.iter().enumerate() {
+            let last = i == pages.len() - 1;
+            let bytes = encode(&Message::Snapshot(page.clone())).unwrap();
+            // 4-byte frame header
+            assert!(
+                bytes.len() - 4 <= 8192,
+                "page {} is {} bytes",
+                i,
+                bytes.len()
+            );
+
+            let (decoded, _) = decode(&bytes).unwrap();
+            let Message::Snapshot(decoded) = decoded else {
+                panic!("expected snapshot");
+            };
+            assert_eq!(
+                decoded.page,
+                Some(SnapshotPage {
+                    sequence: i as u32,
+                    more: !last
+                })
+            );
+            assert_eq!(decoded.correlation_id, last.then_some(42));
+            reassembled.extend(decoded.params);
+        }
+        assert_eq!(reassembled.len(), params.len());
+        assert_eq!(reassembled[1999].address, "/big/namespace/param/1999");
+
+        // Page trailer without a correlation id
+        let (decoded, _) = decode(&encode(&Message::Snapshot(pages[0].clone())).unwrap()).unwrap();
+        assert!(matches!(decoded, Message::Snapshot(m) if m.correlation_id.is_none()));
+    }
+
+    #[test]
+    fn test_small_snapshot_not_paged() {
+        let snapshot = SnapshotMessage {
+            params: vec![ParamValue {
+                address: "/a".to_string(),
+                value: Value::Int(1),
+                revision: 1,
+                writer: None,
+                timestamp: None,
+            }],
+            correlation_id: Some(3),
+            page: None,
+            sync: None,
+        };
+        let pages = paginate_snapshot(snapshot, 1024);
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].page.is_none());
+        assert_eq!(pages[0].correlation_id, Some(3));
+    }
+
+    #[test]
+    fn test_snapshot_sync_roundtrip() {
+        let hello = Message::Hello(HelloMessage {
+            version: 1,
+            name: "Resync".to_string(),
+            features: vec!["param".to_string()],
+            capabilities: None,
+            token: None,
+            since: Some(1_700_000_000_000_123),
+        });
+        let (decoded, _) = decode(&encode(&hello).unwrap()).unwrap();
+        assert!(matches!(decoded, Message::Hello(h) if h.since == Some(1_700_000_000_000_123)));
+
+        // `since` without a QoS override
+        let subscribe = Message::Subscribe(SubscribeMessage {
+            id: 4,
+            pattern: "/mixer/**".to_string(),
+            types: vec![],
+            options: Some(SubscribeOptions {
+                since: Some(77),
+                ..Default::default()
+            }),
+            correlation_id: None,
+        });
+        let (decoded, _) = decode(&encode(&subscribe).unwrap()).unwrap();
+        let Message::Subscribe(decoded) = decoded else {
+            panic!("expected subscribe");
+        };
+        let options = decoded.options.unwrap();
+        assert_eq!(options.since, Some(77));
+        assert_eq!(options.qos, None);
+
+        let params: Vec<ParamValue> = (0..2000)
+            .map(|i| ParamValue {
+                address: format!("/big/namespace/param/{}", i),
+                value: Value::Int(i as i64),
+                revision: i,
+                writer: None,
+                timestamp: None,
+            })
+            .collect();
+        let sync = SnapshotSync {
+            revision: 900,
+            since: Some(500),
+        };
+        let pages = paginate_snapshot(
+            SnapshotMessage {
+                params,
+                correlation_id: Some(9),
+                page: None,
+                sync: Some(sync),
+            },
+            8192,
+        );
+        assert!(pages.len() > 1);
+        for (i, page) in pages.iter().enumerate() {
+            let last = i == pages.len() - 1;
+            let (decoded, _) = decode(&encode(&Message::Snapshot(page.clone())).unwrap()).unwrap();
+            let Message::Snapshot(decoded) = decoded else {
+                panic!("expected snapshot");
+            };
+            // Only the last page carries the revision
+            assert_eq!(decoded.sync, last.then_some(sync));
+            assert_eq!(decoded.correlation_id, last.then_some(9));
+        }
+
+        // A full snapshot encodes `since` as 0
+        let full = Message::Snapshot(SnapshotMessage {
+            params: vec![],
+            correlation_id: None,
+            page: None,
+            sync: Some(SnapshotSync {
+                revision: 900,
+                since: None,
+            }),
+        });
+        let (decoded, _) = decode(&encode(&full).unwrap()).unwrap();
+        let Message::Snapshot(decoded) = decoded else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(decoded.sync.unwrap().since, None);
+        assert!(decoded.page.is_none());
+    }
+
+    #[test]
+    fn test_revision_conflict_error_roundtrip() {
+        let error = Message::Error(
+            ErrorMessage::new(crate::ErrorCode::RevisionConflict, "Revision conflict")
+                .with_address("/doc/title")
+                .with_current(4, Some(Value::String("Final".to_string()))),
+        );
+        let (decoded, _) = decode(&encode(&error).unwrap()).unwrap();
+        let Message::Error(decoded) = decoded else {
+            panic!("expected error");
+        };
+        assert_eq!(decoded.address.as_deref(), Some("/doc/title"));
+        assert_eq!(decoded.revision, Some(4));
+        assert_eq!(decoded.value, Some(Value::String("Final".to_string())));
+    }
+
+    #[test]
+    fn test_credit_roundtrip() {
+        let grant = Message::Credit(CreditMessage { id: 9, credits: 32 });
+        let (decoded, _) = decode(&encode(&grant).unwrap()).unwrap();
+        assert!(matches!(decoded, Message::Credit(c) if c.id == 9 && c.credits == 32));
+
+        // A window without `since` or a QoS override
+        let subscribe = Message::Subscribe(SubscribeMessage {
+            id: 9,
+            pattern: "/cam/**".to_string(),
+            types: vec![],
+            options: Some(SubscribeOptions {
+                credits: Some(64),
+                ..Default::default()
+            }),
+            correlation_id: None,
+        });
+        let (decoded, _) = decode(&encode(&subscribe).unwrap()).unwrap();
+        let Message::Subscribe(decoded) = decoded else {
+            panic!("expected subscribe");
+        };
+        let options = decoded.options.unwrap();
+        assert_eq!(options.credits, Some(64));
+        assert_eq!(options.since, None);
+        assert_eq!(options.qos, None);
     }
 }
@@ -82,54 +82,171 @@ impl From<rmp_serde::decode::Error> for Error {
 }
 
 /// Protocol error codes (for ERROR messages)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The canonical set shared by the router, clients and bindings. Codes are
+/// grouped by range so unknown codes can still be classified with
+/// [`ErrorCode::category`]:
+///
+/// - 100-199: Protocol errors
+/// - 200-299: Address errors
+/// - 300-399: Permission errors
+/// - 400-499: State errors
+/// - 500-599: Server errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u16)]
 pub enum ErrorCode {
     // 100-199: Protocol errors
+    /// Frame could not be parsed
     InvalidFrame = 100,
+    /// Message is malformed or not allowed here
     InvalidMessage = 101,
+    /// Protocol version not supported
     UnsupportedVersion = 102,
+    /// Signal type or capability not negotiated
+    UnsupportedFeature = 103,
+    /// Message exceeds a size limit
+    PayloadTooLarge = 104,
 
     // 200-299: Address errors
+    /// Address is not valid
     InvalidAddress = 200,
+    /// Address (or the session/token it names) does not exist
     AddressNotFound = 201,
+    /// Subscription pattern is not valid
     PatternError = 202,
 
     // 300-399: Permission errors
+    /// Authentication failed (missing or invalid token)
     Unauthorized = 300,
+    /// Token lacks the scope for this operation
     Forbidden = 301,
+    /// Token has expired
     TokenExpired = 302,
 
     // 400-499: State errors
+    /// Expected revision does not match
     RevisionConflict = 400,
+    /// Param is locked by another session
     LockHeld = 401,
+    /// Value is out of range or of the wrong type
     InvalidValue = 402,
+    /// Rejected by the param's conflict strategy
+    ConflictRejected = 403,
 
     // 500-599: Server errors
+    /// Unexpected server failure
     InternalError = 500,
+    /// Server cannot handle the request right now
     ServiceUnavailable = 501,
+    /// Operation timed out
     Timeout = 502,
+    /// Too many messages per second
+    RateLimited = 503,
+    /// Session, subscription or state capacity reached
+    LimitExceeded = 504,
+    /// Messages to this session are being dropped (slow consumer)
+    BufferOverflow = 505,
+    /// Write dropped to break a feedback loop between sessions
+    FeedbackLoop = 506,
+}
+
+/// Error code range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    Protocol,
+    Address,
+    Permission,
+    State,
+    Server,
 }
 
 impl ErrorCode {
+    /// All defined codes
+    pub const ALL: [ErrorCode; 22] = [
+        ErrorCode::InvalidFrame,
+        ErrorCode::InvalidMessage,
+        ErrorCode::UnsupportedVersion,
+        ErrorCode::UnsupportedFeature,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::InvalidAddress,
+        ErrorCode::AddressNotFound,
+        ErrorCode::PatternError,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::TokenExpired,
+        ErrorCode::RevisionConflict,
+        ErrorCode::LockHeld,
+        ErrorCode::InvalidValue,
+        ErrorCode::ConflictRejected,
+        ErrorCode::InternalError,
+        ErrorCode::ServiceUnavailable,
+        ErrorCode::Timeout,
+        ErrorCode::RateLimited,
+        ErrorCode::LimitExceeded,
+        ErrorCode::BufferOverflow,
+        ErrorCode::FeedbackLoop,
+    ];
+
     pub fn from_u16(code: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_u16() == code)
+    }
+
+    /// Numeric code sent on the wire
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+
+    /// Range a numeric code falls in, including codes this version does not
+    /// know about
+    pub fn category_of(code: u16) -> Option<ErrorCategory> {
         match code {
-            100 => Some(ErrorCode::InvalidFrame),
-            101 => Some(ErrorCode::InvalidMessage),
-            102 => Some(ErrorCode::UnsupportedVersion),
-            200 => Some(ErrorCode::InvalidAddress),
-            201 => Some(ErrorCode::AddressNotFound),
-            202 => Some(ErrorCode::PatternError),
-            300 => Some(ErrorCode::Unauthorized),
-            301 => Some(ErrorCode::Forbidden),
-            302 => Some(ErrorCode::TokenExpired),
-            400 => Some(ErrorCode::RevisionConflict),
-            401 => Some(ErrorCode::LockHeld),
-            402 => Some(ErrorCode::InvalidValue),
-            500 => Some(ErrorCode::InternalError),
-            501 => Some(ErrorCode::ServiceUnavailable),
-            502 => Some(ErrorCode::Timeout),
+            100..=199 => Some(ErrorCategory::Protocol),
+            200..=299 => Some(ErrorCategory::Address),
+            300..=399 => Some(ErrorCategory::Permission),
+            400..=499 => Some(ErrorCategory::State),
+            500..=599 => Some(ErrorCategory::Server),
             _ => None,
         }
     }
+
+    pub fn category(self) -> ErrorCategory {
+        Self::category_of(self.as_u16()).expect("defined codes are in range")
+    }
+}
+
+impl From<ErrorCode> for u16 {
+    fn from(code: ErrorCode) -> Self {
+        code.as_u16()
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ({})", self, self.as_u16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_round_trip() {
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_u16(code.as_u16()), Some(code));
+        }
+        assert_eq!(ErrorCode::from_u16(999), None);
+    }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(
+            ErrorCode::UnsupportedFeature.category(),
+            ErrorCategory::Protocol
+        );
+        assert_eq!(ErrorCode::Forbidden.category(), ErrorCategory::Permission);
+        assert_eq!(ErrorCode::RateLimited.category(), ErrorCategory::Server);
+        assert_eq!(ErrorCode::category_of(418), Some(ErrorCategory::State));
+        assert_eq!(ErrorCode::category_of(42), None);
+    }
 }
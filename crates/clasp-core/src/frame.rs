@@ -9,10 +9,12 @@
 //! │             [5]   Timestamp present                             │
 //! │             [4]   Encrypted                                     │
 //! │             [3]   Compressed                                    │
-//! │             [2:0] Reserved                                      │
+//! │             [2]   TTL present                                   │
+//! │             [1:0] Encoding (00=msgpack/legacy, 01=binary)       │
 //! │ Byte 2-3:   Payload Length (uint16 big-endian, max 65535)       │
 //! ├─────────────────────────────────────────────────────────────────┤
-//! │ [If timestamp flag] Bytes 4-11: Timestamp (uint64 µs)           │
+//! │ [If timestamp flag] 8 bytes: Timestamp (uint64 µs)              │
+//! │ [If TTL flag]       4 bytes: TTL (uint32 ms)                    │
 //! ├─────────────────────────────────────────────────────────────────┤
 //! │ Payload (MessagePack encoded)                                   │
 //! └─────────────────────────────────────────────────────────────────┘
@@ -27,6 +29,9 @@ pub const HEADER_SIZE: usize = 4;
 /// Frame header size with timestamp
 pub const HEADER_SIZE_WITH_TS: usize = 12;
 
+/// Size of the TTL extension
+pub const TTL_SIZE: usize = 4;
+
 /// Maximum payload size
 pub const MAX_PAYLOAD_SIZE: usize = 65535;
 
@@ -37,6 +42,8 @@ pub struct FrameFlags {
     pub has_timestamp: bool,
     pub encrypted: bool,
     pub compressed: bool,
+    /// A TTL follows the header (and timestamp)
+    pub has_ttl: bool,
     /// Encoding version: 0 = legacy (MessagePack named), 1+ = compact binary
     pub version: u8,
 }
@@ -54,8 +61,11 @@ impl FrameFlags {
         if self.compressed {
             flags |= 0x08;
         }
-        // Version in bits 0-2 (0 = legacy MessagePack, 1+ = compact binary)
-        flags |= self.version & 0x07;
+        if self.has_ttl {
+            flags |= 0x04;
+        }
+        // Version in bits 0-1 (0 = legacy MessagePack, 1+ = compact binary)
+        flags |= self.version & 0x03;
         flags
     }
 
@@ -65,7 +75,8 @@ impl FrameFlags {
             has_timestamp: (byte & 0x20) != 0,
             encrypted: (byte & 0x10) != 0,
             compressed: (byte & 0x08) != 0,
-            version: byte & 0x07,
+            has_ttl: (byte & 0x04) != 0,
+            version: byte & 0x03,
         }
     }
 
@@ -73,6 +84,20 @@ impl FrameFlags {
     pub fn is_binary_encoding(&self) -> bool {
         self.version >= 1
     }
+
+    /// Header size, including the timestamp and TTL when present
+    pub fn header_size(&self) -> usize {
+        let header = if self.has_timestamp {
+            HEADER_SIZE_WITH_TS
+        } else {
+            HEADER_SIZE
+        };
+        if self.has_ttl {
+            header + TTL_SIZE
+        } else {
+            header
+        }
+    }
 }
 
 /// A Clasp frame
@@ -80,6 +105,9 @@ impl FrameFlags {
 pub struct Frame {
     pub flags: FrameFlags,
     pub timestamp: Option<u64>,
+    /// How long the message stays useful, in milliseconds. The router drops
+    /// it rather than deliver it once this has passed.
+    pub ttl: Option<u32>,
     pub payload: Bytes,
 }
 
@@ -89,6 +117,7 @@ impl Frame {
         Self {
             flags: FrameFlags::default(),
             timestamp: None,
+            ttl: None,
             payload: payload.into(),
         }
     }
@@ -106,6 +135,13 @@ impl Frame {
         self
     }
 
+    /// Create a frame with a time-to-live in milliseconds
+    pub fn with_ttl(mut self, ttl_ms: u32) -> Self {
+        self.ttl = Some(ttl_ms);
+        self.flags.has_ttl = true;
+        self
+    }
+
     /// Create a frame with encryption flag
     pub fn with_encrypted(mut self, encrypted: bool) -> Self {
         self.flags.encrypted = encrypted;
@@ -120,12 +156,7 @@ impl Frame {
 
     /// Calculate the total frame size
     pub fn size(&self) -> usize {
-        let header = if self.flags.has_timestamp {
-            HEADER_SIZE_WITH_TS
-        } else {
-            HEADER_SIZE
-        };
-        header + self.payload.len()
+        self.flags.header_size() + self.payload.len()
     }
 
     /// Encode frame to bytes
@@ -150,6 +181,11 @@ impl Frame {
             buf.put_u64(ts);
         }
 
+        // TTL (if present)
+        if let Some(ttl) = self.ttl {
+            buf.put_u32(ttl);
+        }
+
         // Payload
         buf.extend_from_slice(&self.payload);
 
@@ -178,12 +214,8 @@ impl Frame {
         let payload_len = buf.get_u16() as usize;
 
         // Calculate required size
-        let header_size = if flags.has_timestamp {
-            HEADER_SIZE_WITH_TS
-        } else {
-            HEADER_SIZE
-        };
-        let total_remaining = if flags.has_timestamp { 8 } else { 0 } + payload_len;
+        let header_size = flags.header_size();
+        let total_remaining = header_size - HEADER_SIZE + payload_len;
 
         if buf.remaining() < total_remaining {
             return Err(Error::BufferTooSmall {
@@ -199,12 +231,20 @@ impl Frame {
             None
         };
 
+        // TTL
+        let ttl = if flags.has_ttl {
+            Some(buf.get_u32())
+        } else {
+            None
+        };
+
         // Payload
         let payload = buf.copy_to_bytes(payload_len);
 
         Ok(Self {
             flags,
             timestamp,
+            ttl,
             payload,
         })
     }
@@ -222,13 +262,7 @@ impl Frame {
         let flags = FrameFlags::from_byte(buf[1]);
         let payload_len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
 
-        let header_size = if flags.has_timestamp {
-            HEADER_SIZE_WITH_TS
-        } else {
-            HEADER_SIZE
-        };
-
-        let total_size = header_size + payload_len;
+        let total_size = flags.header_size() + payload_len;
 
         if buf.len() >= total_size {
             Some(total_size)
@@ -264,6 +298,7 @@ mod tests {
             has_timestamp: true,
             encrypted: true,
             compressed: false,
+            has_ttl: false,
             version: 1, // v3 binary encoding
         };
 
@@ -367,4 +402,29 @@ mod tests {
         let truncated = &encoded[..encoded.len() - 1];
         assert_eq!(Frame::check_complete(truncated), None);
     }
+
+    #[test]
+    fn test_ttl_roundtrip() {
+        let frame = Frame::new(b"pos".as_slice())
+            .with_timestamp(7)
+            .with_ttl(250);
+        let encoded = frame.encode().unwrap();
+        assert_eq!(encoded.len(), HEADER_SIZE_WITH_TS + TTL_SIZE + 3);
+        assert_eq!(Frame::check_complete(&encoded), Some(encoded.len()));
+
+        let decoded = Frame::decode(&encoded[..]).unwrap();
+        assert!(decoded.flags.has_ttl);
+        assert_eq!(decoded.flags.version, frame.flags.version);
+        assert_eq!(decoded.timestamp, Some(7));
+        assert_eq!(decoded.ttl, Some(250));
+        assert_eq!(decoded.payload.as_ref(), b"pos");
+
+        // Without a timestamp the TTL follows the basic header
+        let encoded = Frame::new(b"pos".as_slice()).with_ttl(5).encode().unwrap();
+        assert_eq!(
+            &encoded[HEADER_SIZE..HEADER_SIZE + TTL_SIZE],
+            &5u32.to_be_bytes()
+        );
+        assert_eq!(Frame::decode(&encoded[..]).unwrap().ttl, Some(5));
+    }
 }
@@ -0,0 +1,453 @@
+//! Device identity: key-pair authentication
+//!
+//! Instead of a shared secret, a device holds an Ed25519 key pair and the
+//! router knows only its public key. Nothing secret is ever distributed, so
+//! a fleet can be provisioned by collecting public keys, and a leaked router
+//! config doesn't let anyone impersonate a device.
+//!
+//! The device proves it holds the key by sending a signed proof as its HELLO
+//! token:
+//!
+//! ```text
+//! key_<public-key-hex>.<unix-millis>.<nonce>.<signature-hex>
+//! ```
+//!
+//! The signature covers the public key, timestamp and nonce. The router
+//! accepts a proof once, and only within [`DeviceKeyValidator::max_skew`] of
+//! its own clock, so a captured proof can't be replayed later. Devices need a
+//! roughly synchronized clock (SNTP is enough).
+//!
+//! ```
+//! use clasp_core::identity::{DeviceIdentity, DeviceKeyValidator};
+//! use clasp_core::{Scope, TokenInfo, TokenValidator, ValidationResult};
+//!
+//! // On the device, once; keep the secret in persistent storage
+//! let identity = DeviceIdentity::generate();
+//!
+//! // On the router, register the public key with its scopes
+//! let keys = DeviceKeyValidator::new();
+//! let scopes = vec![Scope::parse("write:/sensors/**").unwrap()];
+//! keys.register(&identity.public_key(), TokenInfo::new(identity.public_key(), scopes))
+//!     .unwrap();
+//!
+//! // Every connection sends a fresh proof
+//! assert!(matches!(keys.validate(&identity.proof()), ValidationResult::Valid(_)));
+//! ```
+
+use crate::security::{TokenInfo, TokenValidator, ValidationResult};
+use crate::{Error, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Domain separator for signed proofs
+const PROOF_CONTEXT: &str = "clasp-device-v1";
+
+/// A device's Ed25519 key pair
+#[derive(Clone)]
+pub struct DeviceIdentity {
+    key: SigningKey,
+}
+
+impl DeviceIdentity {
+    /// Token prefix for device key proofs
+    pub const PREFIX: &'static str = "key_";
+
+    /// Generate a new key pair from the operating system's random source
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        getrandom::getrandom(&mut secret).expect("operating system random source unavailable");
+        Self::from_secret(secret)
+    }
+
+    /// Restore a key pair from its 32-byte secret
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(&secret),
+        }
+    }
+
+    /// Restore a key pair from its secret as hex
+    pub fn from_secret_hex(secret: &str) -> Result<Self> {
+        Ok(Self::from_secret(decode_hex(secret.trim(), "secret key")?))
+    }
+
+    /// The 32-byte secret as hex, for storage on the device
+    pub fn secret_hex(&self) -> String {
+        hex::encode(self.key.to_bytes())
+    }
+
+    /// The public key as hex; this is what the router registers
+    pub fn public_key(&self) -> String {
+        hex::encode(self.key.verifying_key().to_bytes())
+    }
+
+    /// Load the key pair stored at `path`, or generate one and store it there
+    ///
+    /// The file holds the secret as hex. On Unix it is created readable by
+    /// the owner only.
+    pub fn load_or_generate(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(secret) => Self::from_secret_hex(&secret)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let identity = Self::generate();
+                identity.save(path)?;
+                Ok(identity)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Store the secret at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        file.write_all(format!("{}\n", self.secret_hex()).as_bytes())
+    }
+
+    /// A fresh signed proof to send as the HELLO token
+    pub fn proof(&self) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let nonce = uuid::Uuid::new_v4().as_simple().to_string();
+        self.proof_at(timestamp, &nonce)
+    }
+
+    fn proof_at(&self, timestamp: u64, nonce: &str) -> String {
+        let public_key = self.public_key();
+        let signature = self
+            .key
+            .sign(signed_message(&public_key, timestamp, nonce).as_bytes());
+        format!(
+            "{}{}.{}.{}.{}",
+            Self::PREFIX,
+            public_key,
+            timestamp,
+            nonce,
+            hex::encode(signature.to_bytes())
+        )
+    }
+}
+
+impl std::fmt::Debug for DeviceIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceIdentity")
+            .field("public_key", &self.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The public key a device proof claims, without checking the signature
+///
+/// Useful to find the sessions a device key authenticated.
+pub fn proof_public_key(token: &str) -> Option<&str> {
+    token
+        .strip_prefix(DeviceIdentity::PREFIX)?
+        .split('.')
+        .next()
+}
+
+/// Validates device proofs against registered public keys
+///
+/// Each key is registered with the [`TokenInfo`] (scopes, subject, expiry)
+/// its sessions get, like a CPSK token.
+pub struct DeviceKeyValidator {
+    keys: RwLock<HashMap<String, TokenInfo>>,
+    /// Nonces of accepted proofs, until they're too old to be accepted anyway
+    seen: Mutex<HashMap<String, SystemTime>>,
+    max_skew: Duration,
+}
+
+impl DeviceKeyValidator {
+    /// Default tolerance between the device's clock and the router's
+    pub const DEFAULT_MAX_SKEW: Duration = Duration::from_secs(300);
+
+    /// Create a validator with no registered keys
+    pub fn new() -> Self {
+        Self {
+            keys: RwLock::new(HashMap::new()),
+            seen: Mutex::new(HashMap::new()),
+            max_skew: Self::DEFAULT_MAX_SKEW,
+        }
+    }
+
+    /// Accept proofs signed up to `max_skew` before or after now
+    pub fn with_max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// How far a proof's timestamp may be from the router's clock
+    pub fn max_skew(&self) -> Duration {
+        self.max_skew
+    }
+
+    /// Register a public key (hex) with the given token info
+    pub fn register(&self, public_key: &str, info: TokenInfo) -> Result<()> {
+        let key = parse_public_key(public_key)?;
+        self.keys
+            .write()
+            .unwrap()
+            .insert(hex::encode(key.to_bytes()), info);
+        Ok(())
+    }
+
+    /// Revoke a public key
+    pub fn revoke(&self, public_key: &str) -> bool {
+        self.keys
+            .write()
+            .unwrap()
+            .remove(&public_key.to_ascii_lowercase())
+            .is_some()
+    }
+
+    /// Get the info registered for a public key
+    pub fn get(&self, public_key: &str) -> Option<TokenInfo> {
+        self.keys
+            .read()
+            .unwrap()
+            .get(&public_key.to_ascii_lowercase())
+            .cloned()
+    }
+
+    /// Get the number of registered keys
+    pub fn len(&self) -> usize {
+        self.keys.read().unwrap().len()
+    }
+
+    /// Check if no keys are registered
+    pub fn is_empty(&self) -> bool {
+        self.keys.read().unwrap().is_empty()
+    }
+
+    /// List all public keys with their info (for admin purposes)
+    pub fn entries(&self) -> Vec<(String, TokenInfo)> {
+        self.keys
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, info)| (key.clone(), info.clone()))
+            .collect()
+    }
+
+    /// Reject proofs already seen, and remember this one
+    fn check_replay(&self, nonce: &str, timestamp: SystemTime) -> bool {
+        let now = SystemTime::now();
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, signed| {
+            now.duration_since(*signed).unwrap_or_default() <= self.max_skew
+                && signed.duration_since(now).unwrap_or_default() <= self.max_skew
+        });
+        seen.insert(nonce.to_string(), timestamp).is_none()
+    }
+}
+
+impl Default for DeviceKeyValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenValidator for DeviceKeyValidator {
+    fn validate(&self, token: &str) -> ValidationResult {
+        let Some(proof) = token.strip_prefix(DeviceIdentity::PREFIX) else {
+            return ValidationResult::NotMyToken;
+        };
+        let parts: Vec<&str> = proof.split('.').collect();
+        let [public_key, timestamp, nonce, signature] = parts[..] else {
+            return ValidationResult::Invalid("malformed device proof".to_string());
+        };
+
+        let public_key = public_key.to_ascii_lowercase();
+        let Some(info) = self.get(&public_key) else {
+            return ValidationResult::Invalid("unknown device key".to_string());
+        };
+        let (Ok(key), Ok(signature), Ok(millis)) = (
+            parse_public_key(&public_key),
+            decode_hex::<64>(signature, "signature"),
+            timestamp.parse::<u64>(),
+        ) else {
+            return ValidationResult::Invalid("malformed device proof".to_string());
+        };
+        let message = signed_message(&public_key, millis, nonce);
+        if key
+            .verify(message.as_bytes(), &Signature::from_bytes(&signature))
+            .is_err()
+        {
+            return ValidationResult::Invalid("bad signature".to_string());
+        }
+
+        let signed_at = UNIX_EPOCH + Duration::from_millis(millis);
+        let now = SystemTime::now();
+        let skew = now
+            .duration_since(signed_at)
+            .or_else(|_| signed_at.duration_since(now))
+            .unwrap_or_default();
+        if skew > self.max_skew {
+            return ValidationResult::Invalid(
+                "proof timestamp outside the accepted window (check the device clock)".to_string(),
+            );
+        }
+        if !self.check_replay(nonce, signed_at) {
+            return ValidationResult::Invalid("proof already used".to_string());
+        }
+
+        if info.is_expired() {
+            ValidationResult::Expired
+        } else {
+            ValidationResult::Valid(info)
+        }
+    }
+
+    fn name(&self) -> &str {
+        "DeviceKey"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+fn signed_message(public_key: &str, timestamp: u64, nonce: &str) -> String {
+    format!("{}.{}.{}.{}", PROOF_CONTEXT, public_key, timestamp, nonce)
+}
+
+fn parse_public_key(public_key: &str) -> Result<VerifyingKey> {
+    let bytes = decode_hex(public_key, "public key")?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|_| Error::DecodeError(format!("'{}' is not an Ed25519 public key", public_key)))
+}
+
+fn decode_hex<const N: usize>(s: &str, what: &str) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(s, &mut bytes)
+        .map_err(|_| Error::DecodeError(format!("{} must be {} hex bytes", what, N)))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scope;
+
+    fn validator_for(identity: &DeviceIdentity) -> DeviceKeyValidator {
+        let validator = DeviceKeyValidator::new();
+        let info = TokenInfo::new(
+            identity.public_key(),
+            vec![Scope::parse("write:/sensors/**").unwrap()],
+        )
+        .with_subject("sensor-1");
+        validator.register(&identity.public_key(), info).unwrap();
+        validator
+    }
+
+    fn now_millis() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+
+    #[test]
+    fn test_valid_proof() {
+        let identity = DeviceIdentity::generate();
+        let validator = validator_for(&identity);
+
+        match validator.validate(&identity.proof()) {
+            ValidationResult::Valid(info) => {
+                assert_eq!(info.subject.as_deref(), Some("sensor-1"));
+            }
+            other => panic!("expected a valid proof, got {:?}", other),
+        }
+        assert!(matches!(
+            validator.validate("cpsk_abc"),
+            ValidationResult::NotMyToken
+        ));
+    }
+
+    #[test]
+    fn test_rejected_proofs() {
+        let identity = DeviceIdentity::generate();
+        let validator = validator_for(&identity);
+
+        // Replayed
+        let proof = identity.proof();
+        assert!(matches!(
+            validator.validate(&proof),
+            ValidationResult::Valid(_)
+        ));
+        assert!(matches!(
+            validator.validate(&proof),
+            ValidationResult::Invalid(_)
+        ));
+
+        // Signed by a key the router doesn't know
+        let stranger = DeviceIdentity::generate();
+        assert!(matches!(
+            validator.validate(&stranger.proof()),
+            ValidationResult::Invalid(_)
+        ));
+
+        // Too old
+        let stale = identity.proof_at(now_millis() - 10 * 60 * 1000, "stale");
+        assert!(matches!(
+            validator.validate(&stale),
+            ValidationResult::Invalid(_)
+        ));
+
+        // Tampered: a different nonce than the one signed
+        let proof = identity.proof();
+        let (signed, signature) = proof.rsplit_once('.').unwrap();
+        let (prefix, _) = signed.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}.{}", prefix, "forged", signature);
+        assert!(matches!(
+            validator.validate(&forged),
+            ValidationResult::Invalid(_)
+        ));
+
+        assert!(validator.revoke(&identity.public_key()));
+        assert!(matches!(
+            validator.validate(&identity.proof()),
+            ValidationResult::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn test_identity_persistence() {
+        let dir = std::env::temp_dir().join(format!("clasp-identity-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("device.key");
+
+        let identity = DeviceIdentity::load_or_generate(&path).unwrap();
+        let reloaded = DeviceIdentity::load_or_generate(&path).unwrap();
+        assert_eq!(identity.public_key(), reloaded.public_key());
+        assert_eq!(
+            proof_public_key(&reloaded.proof()),
+            Some(identity.public_key().as_str())
+        );
+
+        assert!(DeviceIdentity::from_secret_hex("not hex").is_err());
+        assert!(DeviceKeyValidator::new()
+            .register("abcd", TokenInfo::new("abcd".into(), vec![]))
+            .is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
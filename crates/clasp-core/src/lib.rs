@@ -14,6 +14,8 @@
 //! - Binary frame encoding/decoding ([`Frame`], [`codec`])
 //! - Address parsing and wildcard matching ([`Address`])
 //! - State management primitives ([`ParamState`])
+//! - Namespace manifests ([`Manifest`])
+//! - Device key authentication ([`DeviceIdentity`], [`DeviceKeyValidator`])
 //! - Timing utilities ([`Timestamp`])
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -26,6 +28,10 @@ pub mod codec;
 pub mod error;
 pub mod frame;
 #[cfg(feature = "std")]
+pub mod identity;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
 pub mod p2p;
 #[cfg(feature = "std")]
 pub mod security;
@@ -37,9 +43,13 @@ pub mod types;
 
 pub use address::Address;
 pub use codec::{decode, encode};
-pub use error::{Error, Result};
+pub use error::{Error, ErrorCategory, ErrorCode, Result};
 pub use frame::Frame;
 #[cfg(feature = "std")]
+pub use identity::{DeviceIdentity, DeviceKeyValidator};
+#[cfg(feature = "std")]
+pub use manifest::{Manifest, ManifestViolation, MANIFEST_PREFIX};
+#[cfg(feature = "std")]
 pub use p2p::{
     extract_target_session, is_p2p_address, is_p2p_signal_address, signal_address, P2PAnnounce,
     P2PConfig, P2PConnectionState, P2PSignal, RoutingMode, TurnServer, P2P_ANNOUNCE, P2P_NAMESPACE,
@@ -48,7 +58,7 @@ pub use p2p::{
 #[cfg(feature = "std")]
 pub use security::{
     Action, CpskValidator, Scope, SecurityMode, TokenInfo, TokenValidator, ValidationResult,
-    ValidatorChain,
+    ValidatorChain, SESSION_TOKEN_ADDRESS,
 };
 pub use state::ParamState;
 pub use time::Timestamp;
@@ -0,0 +1,341 @@
+//! Namespace manifests
+//!
+//! A manifest is an application's machine-readable description of the
+//! addresses it owns: their signal types, value types, ranges, units and
+//! descriptions. Applications publish it as a SET to `/$manifest/<app>`
+//! ([`Manifest::address`]); tools read it back to label and lay out
+//! controls, and routers can check writes under the namespace against it.
+//!
+//! ```
+//! use clasp_core::{Manifest, SignalDefinition, SignalMeta, SignalType};
+//!
+//! let manifest = Manifest::new("lumen", "/lumen")
+//!     .with_description("Lumen video compositor")
+//!     .with_signal(SignalDefinition {
+//!         address: "/lumen/scene/*/layer/*/opacity".to_string(),
+//!         signal_type: SignalType::Param,
+//!         datatype: Some("float".to_string()),
+//!         access: None,
+//!         meta: Some(SignalMeta {
+//!             unit: None,
+//!             range: Some((0.0, 1.0)),
+//!             default: None,
+//!             description: Some("Layer opacity".to_string()),
+//!         }),
+//!     });
+//!
+//! assert_eq!(manifest.address(), "/$manifest/lumen");
+//! assert!(manifest.check("/lumen/scene/0/layer/3/opacity", &0.5.into(), false).is_ok());
+//! assert!(manifest.check("/lumen/scene/0/layer/3/opacity", &2.0.into(), false).is_err());
+//! ```
+
+use crate::address::glob_match;
+use crate::{Address, Error, Result, SignalDefinition, Value};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Address prefix manifests are published under
+pub const MANIFEST_PREFIX: &str = "/$manifest/";
+
+/// Description of the addresses an application owns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Application name, the last segment of the manifest's address
+    pub app: String,
+    /// Address prefix the application owns, e.g. `/lumen`
+    pub namespace: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Declared addresses; may use `*` and `**` wildcards
+    #[serde(default)]
+    pub signals: Vec<SignalDefinition>,
+}
+
+/// Why a write doesn't fit a manifest
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestViolation {
+    /// The address is under the namespace but not declared
+    Undeclared,
+    /// The signal is read-only for sessions other than the owner
+    ReadOnly,
+    /// The value doesn't have the declared type
+    WrongType { expected: String },
+    /// The value is outside the declared range
+    OutOfRange { min: f64, max: f64 },
+}
+
+impl fmt::Display for ManifestViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestViolation::Undeclared => write!(f, "address is not declared in the manifest"),
+            ManifestViolation::ReadOnly => write!(f, "address is read-only"),
+            ManifestViolation::WrongType { expected } => write!(f, "expected a {} value", expected),
+            ManifestViolation::OutOfRange { min, max } => {
+                write!(f, "value is outside the range {} to {}", min, max)
+            }
+        }
+    }
+}
+
+impl Manifest {
+    pub fn new(app: impl Into<String>, namespace: impl Into<String>) -> Self {
+        Self {
+            app: app.into(),
+            namespace: namespace.into(),
+            description: None,
+            signals: Vec::new(),
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_signal(mut self, signal: SignalDefinition) -> Self {
+        self.signals.push(signal);
+        self
+    }
+
+    /// The address this manifest is published under
+    pub fn address(&self) -> String {
+        format!("{}{}", MANIFEST_PREFIX, self.app)
+    }
+
+    /// The app a manifest address names, e.g. `lumen` for `/$manifest/lumen`
+    pub fn app_for(address: &str) -> Option<&str> {
+        address
+            .strip_prefix(MANIFEST_PREFIX)
+            .filter(|app| !app.is_empty() && !app.contains('/'))
+    }
+
+    /// Check the manifest is well formed: a plain app name, a valid
+    /// namespace, and every signal inside it
+    pub fn validate(&self) -> Result<()> {
+        if self.app.is_empty() || self.app.contains('/') || self.app.contains('*') {
+            return Err(Error::InvalidAddress(format!(
+                "manifest app name '{}' must be a single segment",
+                self.app
+            )));
+        }
+        Address::parse(&self.namespace)?;
+        if self.namespace.trim_end_matches('/').is_empty() {
+            return Err(Error::InvalidAddress(
+                "manifest namespace can't be the root".to_string(),
+            ));
+        }
+        if self.namespace.contains('*') {
+            return Err(Error::InvalidAddress(format!(
+                "manifest namespace '{}' can't contain wildcards",
+                self.namespace
+            )));
+        }
+        for signal in &self.signals {
+            if !self.owns(&signal.address) {
+                return Err(Error::InvalidAddress(format!(
+                    "signal '{}' is outside the namespace '{}'",
+                    signal.address, self.namespace
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `address` is under the manifest's namespace
+    pub fn owns(&self, address: &str) -> bool {
+        let namespace = self.namespace.trim_end_matches('/');
+        address == namespace
+            || address
+                .strip_prefix(namespace)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    /// The declaration covering `address`, preferring an exact match over
+    /// a wildcard one
+    pub fn find(&self, address: &str) -> Option<&SignalDefinition> {
+        self.signals
+            .iter()
+            .find(|signal| signal.address == address)
+            .or_else(|| {
+                self.signals
+                    .iter()
+                    .find(|signal| glob_match(&signal.address, address))
+            })
+    }
+
+    /// Check that `address`, which must be under the namespace, is declared
+    /// and writable. `owner` writes may update read-only signals.
+    pub fn check_access(
+        &self,
+        address: &str,
+        owner: bool,
+    ) -> std::result::Result<&SignalDefinition, ManifestViolation> {
+        let signal = self.find(address).ok_or(ManifestViolation::Undeclared)?;
+        if !owner && is_read_only(signal.access.as_deref()) {
+            return Err(ManifestViolation::ReadOnly);
+        }
+        Ok(signal)
+    }
+
+    /// Check a write of `value` to `address`: [access](Manifest::check_access),
+    /// then the declared type and range
+    pub fn check(
+        &self,
+        address: &str,
+        value: &Value,
+        owner: bool,
+    ) -> std::result::Result<(), ManifestViolation> {
+        let signal = self.check_access(address, owner)?;
+        if let Some(datatype) = signal.datatype.as_deref() {
+            if !type_matches(datatype, value) {
+                return Err(ManifestViolation::WrongType {
+                    expected: datatype.to_string(),
+                });
+            }
+        }
+        if let (Some((min, max)), Some(number)) =
+            (signal.meta.as_ref().and_then(|m| m.range), value.as_f64())
+        {
+            if number < min || number > max {
+                return Err(ManifestViolation::OutOfRange { min, max });
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode as the value published at [`Manifest::address`]
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self)
+            .and_then(serde_json::from_value)
+            .unwrap_or(Value::Null)
+    }
+
+    /// Decode a published manifest and [validate](Manifest::validate) it
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let manifest: Manifest = serde_json::to_value(value)
+            .and_then(serde_json::from_value)
+            .map_err(|e| Error::DecodeError(format!("invalid manifest: {}", e)))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+}
+
+fn is_read_only(access: Option<&str>) -> bool {
+    matches!(access, Some("r" | "ro" | "read" | "readonly" | "read-only"))
+}
+
+/// Whether `value` fits a declared datatype; unknown datatypes accept
+/// anything
+fn type_matches(datatype: &str, value: &Value) -> bool {
+    match datatype {
+        "bool" | "boolean" => matches!(value, Value::Bool(_)),
+        "string" | "str" => matches!(value, Value::String(_)),
+        "int" | "i32" | "i64" | "u8" | "u16" | "u32" | "integer" => matches!(value, Value::Int(_)),
+        "float" | "f32" | "f64" | "number" => matches!(value, Value::Int(_) | Value::Float(_)),
+        "vec2" | "xy" => match value {
+            Value::Array(items) => items.len() == 2 && items.iter().all(|v| v.as_f64().is_some()),
+            _ => false,
+        },
+        "bytes" | "blob" => matches!(value, Value::Bytes(_)),
+        "map" | "object" => matches!(value, Value::Map(_)),
+        "array" | "list" => matches!(value, Value::Array(_)),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SignalMeta, SignalType};
+
+    fn signal(address: &str, datatype: &str, access: Option<&str>) -> SignalDefinition {
+        SignalDefinition {
+            address: address.to_string(),
+            signal_type: SignalType::Param,
+            datatype: Some(datatype.to_string()),
+            access: access.map(str::to_string),
+            meta: Some(SignalMeta {
+                unit: None,
+                range: Some((0.0, 10.0)),
+                default: None,
+                description: Some("test".to_string()),
+            }),
+        }
+    }
+
+    fn manifest() -> Manifest {
+        Manifest::new("mixer", "/mixer")
+            .with_signal(signal("/mixer/*/gain", "float", None))
+            .with_signal(signal("/mixer/1/gain", "int", None))
+            .with_signal(signal("/mixer/meter", "float", Some("r")))
+    }
+
+    #[test]
+    fn test_check() {
+        let manifest = manifest();
+        assert!(manifest
+            .check("/mixer/2/gain", &Value::Float(0.5), false)
+            .is_ok());
+        assert_eq!(
+            manifest.check("/mixer/2/gain", &Value::Float(11.0), false),
+            Err(ManifestViolation::OutOfRange {
+                min: 0.0,
+                max: 10.0
+            })
+        );
+        // The exact declaration wins over the wildcard one
+        assert_eq!(
+            manifest.check("/mixer/1/gain", &Value::Float(0.5), false),
+            Err(ManifestViolation::WrongType {
+                expected: "int".to_string()
+            })
+        );
+        assert_eq!(
+            manifest.check("/mixer/2/mute", &Value::Bool(true), false),
+            Err(ManifestViolation::Undeclared)
+        );
+        assert_eq!(
+            manifest.check("/mixer/meter", &Value::Float(1.0), false),
+            Err(ManifestViolation::ReadOnly)
+        );
+        assert!(manifest
+            .check("/mixer/meter", &Value::Float(1.0), true)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_owns() {
+        let manifest = manifest();
+        assert!(manifest.owns("/mixer"));
+        assert!(manifest.owns("/mixer/1/gain"));
+        assert!(!manifest.owns("/mixers/1"));
+        assert!(!manifest.owns("/lights/1"));
+    }
+
+    #[test]
+    fn test_value_round_trip() {
+        let manifest = manifest().with_description("Main mixer");
+        let decoded = Manifest::from_value(&manifest.to_value()).unwrap();
+        assert_eq!(decoded.app, "mixer");
+        assert_eq!(decoded.description.as_deref(), Some("Main mixer"));
+        assert_eq!(decoded.signals.len(), 3);
+        assert_eq!(
+            decoded.signals[0].meta.as_ref().unwrap().range,
+            Some((0.0, 10.0))
+        );
+        assert_eq!(Manifest::app_for(&manifest.address()), Some("mixer"));
+        assert_eq!(Manifest::app_for("/$manifest/a/b"), None);
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        assert!(Manifest::from_value(&Value::Int(1)).is_err());
+        assert!(Manifest::new("a/b", "/a").validate().is_err());
+        assert!(Manifest::new("a", "/a/*").validate().is_err());
+        assert!(Manifest::new("a", "/").validate().is_err());
+        assert!(Manifest::new("a", "/a")
+            .with_signal(signal("/b/x", "float", None))
+            .validate()
+            .is_err());
+    }
+}
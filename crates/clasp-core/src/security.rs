@@ -233,6 +233,11 @@ impl TokenInfo {
     }
 }
 
+/// Address a client SETs a fresh token on to re-authenticate without
+/// reconnecting. The router replies with ACK, or ERROR (keeping the previous
+/// token) if the new one is rejected.
+pub const SESSION_TOKEN_ADDRESS: &str = "/$sys/session/token";
+
 /// Result of token validation
 #[derive(Debug)]
 pub enum ValidationResult {
@@ -307,6 +312,21 @@ impl CpskValidator {
         self.tokens.read().unwrap().keys().cloned().collect()
     }
 
+    /// Get the info registered for a token
+    pub fn get(&self, token: &str) -> Option<TokenInfo> {
+        self.tokens.read().unwrap().get(token).cloned()
+    }
+
+    /// List all tokens with their info (for admin purposes)
+    pub fn entries(&self) -> Vec<(String, TokenInfo)> {
+        self.tokens
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(token, info)| (token.clone(), info.clone()))
+            .collect()
+    }
+
     /// Generate a new CPSK token string using cryptographically secure randomness
     pub fn generate_token() -> String {
         let uuid = uuid::Uuid::new_v4();
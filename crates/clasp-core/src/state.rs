@@ -66,6 +66,8 @@ pub struct ParamState {
     pub value: Value,
     /// Monotonic revision number
     pub revision: u64,
+    /// Store revision of the last write (see [`StateStore::revision`])
+    pub changed: u64,
     /// Session ID of last writer
     pub writer: String,
     /// Timestamp of last write (microseconds)
@@ -95,6 +97,7 @@ impl ParamState {
         Self {
             value,
             revision: 1,
+            changed: 0,
             writer,
             timestamp: now,
             last_accessed: now,
@@ -141,6 +144,7 @@ impl ParamState {
                 return Err(UpdateError::RevisionConflict {
                     expected,
                     actual: self.revision,
+                    current: Some(self.value.clone()),
                 });
             }
         }
@@ -222,17 +226,31 @@ impl ParamState {
 /// Errors that can occur during state updates
 #[derive(Debug, Clone)]
 pub enum UpdateError {
-    RevisionConflict { expected: u64, actual: u64 },
-    LockHeld { holder: String },
+    /// A compare-and-set expected another revision (0 = absent)
+    RevisionConflict {
+        expected: u64,
+        actual: u64,
+        /// Value held at `actual`, None if the param doesn't exist
+        current: Option<Value>,
+    },
+    LockHeld {
+        holder: String,
+    },
     ConflictRejected,
     OutOfRange,
     AtCapacity,
+    /// A relative update targeted a param that holds no value
+    Missing,
+    /// A relative update doesn't apply to the param's value
+    InvalidOperand,
 }
 
 impl std::fmt::Display for UpdateError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::RevisionConflict { expected, actual } => {
+            Self::RevisionConflict {
+                expected, actual, ..
+            } => {
                 write!(
                     f,
                     "Revision conflict: expected {}, got {}",
@@ -251,6 +269,12 @@ impl std::fmt::Display for UpdateError {
             Self::AtCapacity => {
                 write!(f, "State store at capacity")
             }
+            Self::Missing => {
+                write!(f, "No value to update")
+            }
+            Self::InvalidOperand => {
+                write!(f, "Operation does not apply to the current value")
+            }
         }
     }
 }
@@ -274,14 +298,16 @@ impl std::error::Error for CapacityError {}
 pub struct StateStore {
     params: HashMap<String, ParamState>,
     config: StateStoreConfig,
+    /// Store revision of the latest write
+    revision: u64,
+    /// Oldest revision a diff can start from: the store's creation or its
+    /// latest removal, whichever is newer
+    diff_floor: u64,
 }
 
 impl Default for StateStore {
     fn default() -> Self {
-        Self {
-            params: HashMap::new(),
-            config: StateStoreConfig::unlimited(), // Backwards compatible default
-        }
+        Self::with_config(StateStoreConfig::unlimited()) // Backwards compatible default
     }
 }
 
@@ -293,9 +319,12 @@ impl StateStore {
 
     /// Create a new state store with the specified config
     pub fn with_config(config: StateStoreConfig) -> Self {
+        let now = current_timestamp();
         Self {
             params: HashMap::new(),
             config,
+            revision: now,
+            diff_floor: now,
         }
     }
 
@@ -329,6 +358,9 @@ impl StateStore {
     }
 
     /// Set a param value, creating if necessary
+    ///
+    /// With `revision` the write is a compare-and-set: it only applies if
+    /// the param is at that revision, 0 meaning it doesn't exist yet.
     pub fn set(
         &mut self,
         address: &str,
@@ -337,10 +369,38 @@ impl StateStore {
         revision: Option<u64>,
         lock: bool,
         unlock: bool,
+    ) -> Result<u64, UpdateError> {
+        let result = self.insert_or_update(address, value, writer, revision, lock, unlock);
+        if result.is_ok() {
+            let changed = self.next_revision();
+            if let Some(param) = self.params.get_mut(address) {
+                param.changed = changed;
+            }
+        }
+        result
+    }
+
+    fn insert_or_update(
+        &mut self,
+        address: &str,
+        value: Value,
+        writer: &str,
+        revision: Option<u64>,
+        lock: bool,
+        unlock: bool,
     ) -> Result<u64, UpdateError> {
         if let Some(param) = self.params.get_mut(address) {
             param.try_update(value, writer, revision, lock, unlock)
         } else {
+            // Revision 0 is "absent": only a create-only compare-and-set fits
+            if let Some(expected) = revision.filter(|&r| r != 0) {
+                return Err(UpdateError::RevisionConflict {
+                    expected,
+                    actual: 0,
+                    current: None,
+                });
+            }
+
             // Check capacity before creating new param
             if let Some(max) = self.config.max_params {
                 if self.params.len() >= max {
@@ -369,6 +429,39 @@ impl StateStore {
         }
     }
 
+    /// Store revision of the latest write
+    ///
+    /// Revisions are microsecond timestamps made strictly increasing, so a
+    /// revision handed out before a restart is always older than the store
+    /// that replaced it.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Params matching `pattern` written after revision `since`
+    ///
+    /// `None` when the store can't tell: `since` predates the store or a
+    /// removal, or is newer than anything it has handed out. The caller then
+    /// needs a full snapshot.
+    pub fn changed_since(&self, pattern: &str, since: u64) -> Option<Vec<(&str, &ParamState)>> {
+        if since < self.diff_floor || since > self.revision {
+            return None;
+        }
+        let mut changed = self.get_matching(pattern);
+        changed.retain(|(_, state)| state.changed > since);
+        Some(changed)
+    }
+
+    fn next_revision(&mut self) -> u64 {
+        self.revision = (self.revision + 1).max(current_timestamp());
+        self.revision
+    }
+
+    /// Note that params went away, which a diff cannot express
+    fn removed(&mut self) {
+        self.diff_floor = self.next_revision();
+    }
+
     /// Evict the least recently accessed param
     fn evict_lru(&mut self) {
         if let Some(oldest_key) = self
@@ -378,6 +471,7 @@ impl StateStore {
             .map(|(k, _)| k.clone())
         {
             self.params.remove(&oldest_key);
+            self.removed();
         }
     }
 
@@ -390,6 +484,7 @@ impl StateStore {
             .map(|(k, _)| k.clone())
         {
             self.params.remove(&oldest_key);
+            self.removed();
         }
     }
 
@@ -402,7 +497,11 @@ impl StateStore {
 
         let before = self.params.len();
         self.params.retain(|_, v| v.last_accessed >= cutoff);
-        before - self.params.len()
+        let removed = before - self.params.len();
+        if removed > 0 {
+            self.removed();
+        }
+        removed
     }
 
     /// Run cleanup using the configured TTL (if any)
@@ -443,12 +542,17 @@ impl StateStore {
 
     /// Remove a param
     pub fn remove(&mut self, address: &str) -> Option<ParamState> {
-        self.params.remove(address)
+        let removed = self.params.remove(address);
+        if removed.is_some() {
+            self.removed();
+        }
+        removed
     }
 
     /// Clear all params
     pub fn clear(&mut self) {
         self.params.clear();
+        self.removed();
     }
 }
 
@@ -685,6 +789,81 @@ mod tests {
         assert!(store.is_empty());
     }
 
+    #[test]
+    fn test_compare_and_set() {
+        let mut store = StateStore::new();
+
+        // Create-only: expected revision 0 means absent
+        let missing = store.set("/doc/title", Value::Int(1), "s1", Some(3), false, false);
+        assert!(matches!(
+            missing,
+            Err(UpdateError::RevisionConflict {
+                expected: 3,
+                actual: 0,
+                current: None
+            })
+        ));
+        let rev = store
+            .set("/doc/title", Value::Int(1), "s1", Some(0), false, false)
+            .unwrap();
+        assert_eq!(rev, 1);
+
+        // Stale writers learn the current revision and value
+        let stale = store.set("/doc/title", Value::Int(2), "s2", Some(0), false, false);
+        match stale {
+            Err(UpdateError::RevisionConflict {
+                actual, current, ..
+            }) => {
+                assert_eq!(actual, 1);
+                assert_eq!(current, Some(Value::Int(1)));
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+
+        let rev = store
+            .set("/doc/title", Value::Int(2), "s2", Some(1), false, false)
+            .unwrap();
+        assert_eq!(rev, 2);
+        assert_eq!(store.get_value("/doc/title"), Some(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let mut store = StateStore::new();
+        store
+            .set("/mixer/1", Value::Float(0.1), "s1", None, false, false)
+            .unwrap();
+        store
+            .set("/mixer/2", Value::Float(0.2), "s1", None, false, false)
+            .unwrap();
+        let synced = store.revision();
+
+        store
+            .set("/mixer/2", Value::Float(0.3), "s1", None, false, false)
+            .unwrap();
+        store
+            .set("/lights/1", Value::Float(1.0), "s1", None, false, false)
+            .unwrap();
+        assert!(store.revision() > synced);
+
+        let changed = store.changed_since("/mixer/**", synced).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].0, "/mixer/2");
+        assert!(store
+            .changed_since("/mixer/**", store.revision())
+            .unwrap()
+            .is_empty());
+
+        // Revisions from the future or before the store existed can't be diffed
+        assert!(store.changed_since("**", store.revision() + 1).is_none());
+        assert!(store.changed_since("**", 1).is_none());
+
+        // Nor can anything across a removal
+        store.remove("/lights/1");
+        assert!(store.changed_since("**", synced).is_none());
+        assert!(store.changed_since("**", store.revision()).is_some());
+    }
+
     #[test]
     fn test_last_accessed_tracking() {
         let mut state = ParamState::new(Value::Float(0.5), "session1".to_string());
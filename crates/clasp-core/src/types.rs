@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error::ErrorCode;
+
 /// Message type codes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -10,8 +12,11 @@ pub enum MessageType {
     Hello = 0x01,
     Welcome = 0x02,
     Announce = 0x03,
+    Channel = 0x04,
+    Envelope = 0x05,
     Subscribe = 0x10,
     Unsubscribe = 0x11,
+    Credit = 0x12,
     Publish = 0x20,
     Set = 0x21,
     Get = 0x22,
@@ -32,8 +37,11 @@ impl MessageType {
             0x01 => Some(MessageType::Hello),
             0x02 => Some(MessageType::Welcome),
             0x03 => Some(MessageType::Announce),
+            0x04 => Some(MessageType::Channel),
+            0x05 => Some(MessageType::Envelope),
             0x10 => Some(MessageType::Subscribe),
             0x11 => Some(MessageType::Unsubscribe),
+            0x12 => Some(MessageType::Credit),
             0x20 => Some(MessageType::Publish),
             0x21 => Some(MessageType::Set),
             0x22 => Some(MessageType::Get),
@@ -51,8 +59,9 @@ impl MessageType {
     }
 }
 
-/// Quality of Service levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Quality of Service levels, ordered from least to most reliable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum QoS {
     /// Best effort, no confirmation
@@ -320,6 +329,9 @@ pub enum Message {
     #[serde(rename = "UNSUBSCRIBE")]
     Unsubscribe(UnsubscribeMessage),
 
+    #[serde(rename = "CREDIT")]
+    Credit(CreditMessage),
+
     #[serde(rename = "PUBLISH")]
     Publish(PublishMessage),
 
@@ -355,6 +367,12 @@ pub enum Message {
 
     #[serde(rename = "RESULT")]
     Result(ResultMessage),
+
+    #[serde(rename = "CHANNEL")]
+    Channel(ChannelMessage),
+
+    #[serde(rename = "ENVELOPE")]
+    Envelope(EnvelopeMessage),
 }
 
 /// HELLO message - connection initiation
@@ -368,6 +386,10 @@ pub struct HelloMessage {
     pub capabilities: Option<Capabilities>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
+    /// Store revision the client's cache is current to; the router's initial
+    /// snapshot then holds only what changed after it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
 }
 
 /// WELCOME message - connection accepted
@@ -438,6 +460,9 @@ pub struct SubscribeMessage {
     pub types: Vec<SignalType>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub options: Option<SubscribeOptions>,
+    /// Echoed on the ACK/ERROR reply so the sender can match it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<u32>,
 }
 
 /// Subscription options
@@ -451,6 +476,29 @@ pub struct SubscribeOptions {
     pub history: Option<u32>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub window: Option<u32>,
+    /// Coalesce param changes for this many milliseconds and deliver them
+    /// as one SNAPSHOT (latest value per address)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u32>,
+    /// Only deliver traffic that entered through these protocols
+    /// (e.g. `["clasp"]` for native clients only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origins: Option<Vec<String>>,
+    /// Wrap deliveries in an ENVELOPE carrying their origin
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub envelope: bool,
+    /// Deliver with this QoS instead of the publisher's (e.g. `Fire` to take
+    /// a param stream lossily on a monitoring dashboard)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos: Option<QoS>,
+    /// Only send matching params changed after this store revision in the
+    /// initial snapshot (see [`SnapshotSync`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// Flow control: the router sends at most this many PUBLISH deliveries
+    /// through the subscription until the client grants more with CREDIT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credits: Option<u32>,
 }
 
 /// UNSUBSCRIBE message
@@ -459,6 +507,17 @@ pub struct UnsubscribeMessage {
     pub id: u32,
 }
 
+/// CREDIT message - let a flow-controlled subscription receive more
+///
+/// Adds `credits` deliveries to the window of the session's subscription
+/// `id` (see [`SubscribeOptions::credits`]). Deliveries that find the window
+/// empty are dropped, so a slow consumer only ever sees what it asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreditMessage {
+    pub id: u32,
+    pub credits: u32,
+}
+
 /// PUBLISH message - for events, streams, gestures, timelines
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublishMessage {
@@ -497,18 +556,98 @@ pub struct SetMessage {
     pub lock: bool,
     #[serde(default)]
     pub unlock: bool,
+    /// Apply `value` to the retained value instead of replacing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<SetOp>,
+    /// Echoed on the ACK/ERROR reply so the sender can match it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<u32>,
+}
+
+/// Relative update of a param, applied by the router to the value it holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SetOp {
+    /// Add the operand (negative to decrement)
+    Add,
+    /// Multiply by the operand
+    Mul,
+    /// Invert a bool (the operand is ignored)
+    Toggle,
+}
+
+impl SetOp {
+    /// The updated value, or None if the op doesn't apply to these types
+    ///
+    /// Ints stay ints (saturating); mixing an int with a float gives a float.
+    pub fn apply(self, current: &Value, operand: &Value) -> Option<Value> {
+        match (self, current, operand) {
+            (SetOp::Toggle, Value::Bool(b), _) => Some(Value::Bool(!b)),
+            (SetOp::Toggle, _, _) => None,
+            (SetOp::Add, Value::Int(a), Value::Int(b)) => Some(Value::Int(a.saturating_add(*b))),
+            (SetOp::Mul, Value::Int(a), Value::Int(b)) => Some(Value::Int(a.saturating_mul(*b))),
+            (op, a, b) => {
+                let (a, b) = (a.as_f64()?, b.as_f64()?);
+                Some(Value::Float(match op {
+                    SetOp::Add => a + b,
+                    _ => a * b,
+                }))
+            }
+        }
+    }
 }
 
 /// GET message - request current value
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetMessage {
     pub address: String,
+    /// Echoed on the SNAPSHOT/ERROR reply so the sender can match it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<u32>,
 }
 
 /// SNAPSHOT message - current state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotMessage {
     pub params: Vec<ParamValue>,
+    /// Correlation id of the GET this answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<u32>,
+    /// Position in a snapshot split across several frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<SnapshotPage>,
+    /// Store revision the snapshot is current to, and whether it is a diff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SnapshotSync>,
+}
+
+/// One page of a snapshot too large for a single frame
+///
+/// Pages of one snapshot are sent back to back, numbered from 0; the last
+/// one has `more == false` and carries the correlation id, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotPage {
+    /// Page number, starting at 0
+    pub sequence: u32,
+    /// Further pages follow
+    pub more: bool,
+}
+
+/// Where a snapshot stands in the router's change history
+///
+/// The store revision increases with every param write, across the whole
+/// namespace. A client holding a snapshot's `revision` can later ask for
+/// `since: revision` (in HELLO or SUBSCRIBE) and receive only the params
+/// written after it. When the router can't answer with a diff (the revision
+/// predates a restart, or params were removed since) it sends the full
+/// snapshot with `since: None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotSync {
+    /// Store revision the snapshot is current to
+    pub revision: u64,
+    /// Revision the params are a diff against; `None` for a full snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
 }
 
 /// Parameter value in snapshot
@@ -529,6 +668,9 @@ pub struct BundleMessage {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<u64>,
     pub messages: Vec<Message>,
+    /// Echoed on the ACK/ERROR reply so the sender can match it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<u32>,
 }
 
 /// SYNC message - clock synchronization
@@ -552,6 +694,9 @@ pub struct AckMessage {
     pub locked: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub holder: Option<String>,
+    /// Value a relative SET resulted in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub correlation_id: Option<u32>,
 }
@@ -565,6 +710,44 @@ pub struct ErrorMessage {
     pub address: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub correlation_id: Option<u32>,
+    /// Current revision of `address`, on a revision conflict (0 = absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
+    /// Current value of `address`, on a revision conflict
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl ErrorMessage {
+    /// Create an ERROR with a canonical code
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code.as_u16(),
+            message: message.into(),
+            address: None,
+            correlation_id: None,
+            revision: None,
+            value: None,
+        }
+    }
+
+    /// Set the address the error relates to
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// Report the revision and value the address currently holds
+    pub fn with_current(mut self, revision: u64, value: Option<Value>) -> Self {
+        self.revision = Some(revision);
+        self.value = value;
+        self
+    }
+
+    /// The canonical code, if this version knows it
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::from_u16(self.code)
+    }
 }
 
 /// QUERY message - introspection
@@ -579,6 +762,137 @@ pub struct ResultMessage {
     pub signals: Vec<SignalDefinition>,
 }
 
+/// CHANNEL message - traffic of a logical client sharing a connection
+///
+/// A logical client opens its own session with a HELLO wrapped in a CHANNEL;
+/// everything to and from that session then travels in CHANNELs with the
+/// same id. A CHANNEL without a message closes the logical client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelMessage {
+    pub channel: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Box<Message>>,
+}
+
+impl ChannelMessage {
+    /// Wrap a message for a channel
+    pub fn new(channel: u16, message: Message) -> Self {
+        Self {
+            channel,
+            message: Some(Box::new(message)),
+        }
+    }
+
+    /// Close a channel
+    pub fn close(channel: u16) -> Self {
+        Self {
+            channel,
+            message: None,
+        }
+    }
+}
+
+/// Protocol of sessions connected directly over CLASP
+pub const NATIVE_PROTOCOL: &str = "clasp";
+
+/// Address a bridge SETs its [`Origin`] on, so traffic it relays over a CLASP
+/// connection is tagged with the protocol it came from. The value is a map
+/// `{protocol, remote?}` or a bare protocol string.
+pub const SESSION_ORIGIN_ADDRESS: &str = "/$sys/session/origin";
+
+/// Where traffic entered the router
+///
+/// Adapters and bridges tag their sessions with the protocol they speak and,
+/// where there is one, the remote peer's id (MQTT client id, OSC source
+/// address, ...). Native clients have protocol `clasp`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Origin {
+    pub protocol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+}
+
+impl Origin {
+    pub fn new(protocol: impl Into<String>) -> Self {
+        Self {
+            protocol: protocol.into(),
+            remote: None,
+        }
+    }
+
+    /// Origin of a native CLASP client
+    pub fn native() -> Self {
+        Self::new(NATIVE_PROTOCOL)
+    }
+
+    pub fn with_remote(mut self, remote: impl Into<String>) -> Self {
+        self.remote = Some(remote.into());
+        self
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.protocol == NATIVE_PROTOCOL
+    }
+
+    /// The origin as a map value (`{protocol, remote?}`)
+    pub fn to_value(&self) -> Value {
+        let mut map = HashMap::new();
+        map.insert("protocol".to_string(), Value::String(self.protocol.clone()));
+        if let Some(ref remote) = self.remote {
+            map.insert("remote".to_string(), Value::String(remote.clone()));
+        }
+        Value::Map(map)
+    }
+
+    /// Parse an origin from a map value, or from a bare protocol string
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(protocol) if !protocol.is_empty() => Some(Self::new(protocol.clone())),
+            Value::Map(map) => {
+                let protocol = map.get("protocol")?.as_str().filter(|p| !p.is_empty())?;
+                let mut origin = Self::new(protocol);
+                origin.remote = map.get("remote").and_then(Value::as_str).map(String::from);
+                Some(origin)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for Origin {
+    fn default() -> Self {
+        Self::native()
+    }
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.remote {
+            Some(ref remote) => write!(f, "{}({})", self.protocol, remote),
+            None => f.write_str(&self.protocol),
+        }
+    }
+}
+
+/// ENVELOPE message - a delivery tagged with where it entered the router
+///
+/// Only sent to subscribers that asked for it with
+/// [`SubscribeOptions::envelope`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeMessage {
+    pub origin: Origin,
+    pub message: Box<Message>,
+}
+
+impl EnvelopeMessage {
+    pub fn new(origin: Origin, message: Message) -> Self {
+        Self {
+            origin,
+            message: Box::new(message),
+        }
+    }
+}
+
 impl Message {
     /// Get the message type code
     pub fn type_code(&self) -> MessageType {
@@ -588,6 +902,7 @@ impl Message {
             Message::Announce(_) => MessageType::Announce,
             Message::Subscribe(_) => MessageType::Subscribe,
             Message::Unsubscribe(_) => MessageType::Unsubscribe,
+            Message::Credit(_) => MessageType::Credit,
             Message::Publish(_) => MessageType::Publish,
             Message::Set(_) => MessageType::Set,
             Message::Get(_) => MessageType::Get,
@@ -600,6 +915,8 @@ impl Message {
             Message::Error(_) => MessageType::Error,
             Message::Query(_) => MessageType::Query,
             Message::Result(_) => MessageType::Result,
+            Message::Channel(_) => MessageType::Channel,
+            Message::Envelope(_) => MessageType::Envelope,
         }
     }
 
@@ -610,6 +927,11 @@ impl Message {
             Message::Publish(p) => p.signal.map(|s| s.default_qos()).unwrap_or(QoS::Fire),
             Message::Bundle(_) => QoS::Commit,
             Message::Subscribe(_) | Message::Unsubscribe(_) => QoS::Confirm,
+            Message::Channel(ChannelMessage {
+                message: Some(inner),
+                ..
+            }) => inner.default_qos(),
+            Message::Envelope(envelope) => envelope.message.default_qos(),
             _ => QoS::Fire,
         }
     }
@@ -14,6 +14,7 @@ fn test_encode_decode_hello() {
         features: vec!["param".to_string(), "event".to_string()],
         capabilities: None,
         token: None,
+        since: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -60,6 +61,8 @@ fn test_encode_decode_set() {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -111,6 +114,7 @@ fn test_encode_decode_subscribe() {
         pattern: "/test/*".to_string(),
         types: vec![SignalType::Param, SignalType::Event],
         options: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -156,6 +160,8 @@ fn test_value_types() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).expect("encode failed");
@@ -187,6 +193,8 @@ fn test_v3_set_message_size() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -209,6 +217,8 @@ fn test_v3_set_message_with_revision() {
         revision: Some(42),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -230,6 +240,8 @@ fn test_v3_set_message_with_lock() {
         revision: None,
         lock: true,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -252,6 +264,8 @@ fn test_v3_set_message_string_value() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).expect("encode failed");
@@ -275,6 +289,8 @@ fn test_v3_encoding_starts_with_message_type() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&set_msg).expect("encode failed");
@@ -289,6 +305,7 @@ fn test_v3_encoding_starts_with_message_type() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
 
     let encoded = codec::encode(&hello_msg).expect("encode failed");
@@ -307,6 +324,8 @@ fn test_v3_benchmark_set_encoding() {
         revision: Some(1),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let iterations = 100_000;
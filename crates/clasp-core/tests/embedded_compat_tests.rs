@@ -46,12 +46,15 @@ fn test_core_set_to_embedded_decode() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     };
 
     // Encode using core
     let frame = Frame {
         flags: FrameFlags::default(),
         timestamp: None,
+        ttl: None,
         payload: codec::encode_message(&Message::Set(set_msg)).unwrap(),
     };
     let encoded = frame.encode().unwrap();
@@ -67,6 +67,7 @@ async fn complete_handshake(
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     if sender.send(codec::encode(&hello).unwrap()).await.is_err() {
         return false;
@@ -156,6 +157,7 @@ async fn test_truncated_message_returns_error_400() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     let bytes = codec::encode(&hello).expect("Failed to encode");
     // Truncate to just 3 bytes (incomplete frame)
@@ -202,6 +204,7 @@ async fn test_wrong_protocol_version_returns_error_505() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender
         .send(codec::encode(&hello).expect("Failed to encode"))
@@ -253,6 +256,8 @@ async fn test_message_before_hello_returns_error_401() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&set).expect("Failed to encode"))
@@ -301,6 +306,7 @@ async fn test_duplicate_hello() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender
         .send(codec::encode(&hello).expect("Failed to encode"))
@@ -329,6 +335,7 @@ async fn test_duplicate_hello() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender
         .send(codec::encode(&hello2).expect("Failed to encode"))
@@ -356,6 +363,7 @@ async fn test_very_long_address() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender
         .send(codec::encode(&hello).expect("Failed to encode"))
@@ -384,6 +392,8 @@ async fn test_very_long_address() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&set).expect("Failed to encode"))
@@ -418,6 +428,8 @@ async fn test_empty_address_returns_error_400() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&set).expect("Failed to encode"))
@@ -477,6 +489,8 @@ async fn test_invalid_address_returns_error_400() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         sender
             .send(codec::encode(&set).expect("Failed to encode"))
@@ -527,6 +541,7 @@ async fn test_rapid_disconnect_reconnect() {
             features: vec![],
             capabilities: None,
             token: None,
+            since: None,
         });
         sender
             .send(codec::encode(&hello).expect("Failed to encode"))
@@ -574,6 +589,7 @@ async fn test_special_characters_in_address() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender
         .send(codec::encode(&hello).expect("Failed to encode"))
@@ -610,6 +626,8 @@ async fn test_special_characters_in_address() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         sender
             .send(codec::encode(&set).expect("Failed to encode"))
@@ -649,6 +667,8 @@ async fn test_unauthorized_write_to_locked_address_returns_error_403() {
         revision: None,
         lock: true, // Acquire lock
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     owner_sender
         .send(codec::encode(&set_locked).expect("Failed to encode"))
@@ -679,6 +699,8 @@ async fn test_unauthorized_write_to_locked_address_returns_error_403() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     intruder_sender
         .send(codec::encode(&set_intruder).expect("Failed to encode"))
@@ -739,6 +761,7 @@ async fn test_subscribe_invalid_pattern_returns_error_400() {
             pattern: pattern.to_string(),
             types: vec![],
             options: None,
+            correlation_id: None,
         });
         sender
             .send(codec::encode(&subscribe).expect("Failed to encode"))
@@ -792,6 +815,7 @@ async fn test_duplicate_subscription_id() {
         pattern: "/test/a".to_string(),
         types: vec![],
         options: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&subscribe1).expect("Failed to encode"))
@@ -807,6 +831,7 @@ async fn test_duplicate_subscription_id() {
         pattern: "/test/b".to_string(),
         types: vec![],
         options: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&subscribe2).expect("Failed to encode"))
@@ -0,0 +1,514 @@
+//! Property-Based Round-Trip Tests
+//!
+//! Generated messages must survive:
+//! - clasp-core encode -> clasp-core decode
+//! - clasp-embedded encode -> clasp-core decode, for the subset the embedded
+//!   encoder supports
+//! - clasp-core encode -> clasp-embedded decode, likewise
+//!
+//! The two encoders are written by hand; these properties catch the two
+//! drifting apart.
+//!
+//! Messages are compared through their JSON form, which doesn't depend on
+//! map iteration order.
+
+use clasp_core::{
+    codec, AckMessage, BundleMessage, ErrorMessage, GesturePhase, GetMessage, HelloMessage,
+    Message, ParamValue, PublishMessage, QueryMessage, SetMessage, SignalType, SnapshotMessage,
+    SubscribeMessage, SubscribeOptions, SyncMessage, UnsubscribeMessage, Value, WelcomeMessage,
+};
+use clasp_embedded as embedded;
+use proptest::collection::{hash_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+
+// ============================================================================
+// Generators
+// ============================================================================
+
+fn address() -> impl Strategy<Value = String> {
+    vec("[a-z0-9_]{1,8}", 1..5).prop_map(|segments| format!("/{}", segments.join("/")))
+}
+
+fn pattern() -> impl Strategy<Value = String> {
+    vec(prop_oneof!["[a-z0-9_]{1,8}", Just("*".to_string())], 1..4)
+        .prop_map(|segments| format!("/{}/**", segments.join("/")))
+}
+
+/// Finite floats (NaN never compares equal to itself)
+fn float() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        any::<f64>().prop_filter("finite", |f| f.is_finite()),
+        Just(0.0),
+        Just(-0.0),
+        Just(f64::MIN_POSITIVE),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::Int),
+        float().prop_map(Value::Float),
+        ".{0,24}".prop_map(Value::String),
+        vec(any::<u8>(), 0..32).prop_map(Value::Bytes),
+    ];
+    leaf.prop_recursive(3, 24, 6, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..6).prop_map(Value::Array),
+            hash_map("[a-z]{1,6}", inner, 0..6).prop_map(Value::Map),
+        ]
+    })
+}
+
+fn set() -> impl Strategy<Value = SetMessage> {
+    (
+        address(),
+        value(),
+        option::of(any::<u64>()),
+        any::<bool>(),
+        any::<bool>(),
+        option::of(any::<u32>()),
+    )
+        .prop_map(
+            |(address, value, revision, lock, unlock, correlation_id)| SetMessage {
+                address,
+                value,
+                revision,
+                lock,
+                unlock,
+                op: None,
+                correlation_id,
+            },
+        )
+}
+
+fn publish() -> impl Strategy<Value = PublishMessage> {
+    let event = (address(), value()).prop_map(|(address, payload)| PublishMessage {
+        address,
+        signal: Some(SignalType::Event),
+        value: None,
+        payload: Some(payload),
+        samples: None,
+        rate: None,
+        id: None,
+        phase: None,
+        timestamp: None,
+        timeline: None,
+    });
+    let stream = (address(), float()).prop_map(|(address, sample)| PublishMessage {
+        address,
+        signal: Some(SignalType::Stream),
+        value: Some(Value::Float(sample)),
+        payload: None,
+        samples: None,
+        rate: None,
+        id: None,
+        phase: None,
+        timestamp: None,
+        timeline: None,
+    });
+    let gesture = (
+        address(),
+        any::<u32>(),
+        prop_oneof![
+            Just(GesturePhase::Start),
+            Just(GesturePhase::Move),
+            Just(GesturePhase::End),
+            Just(GesturePhase::Cancel),
+        ],
+        value(),
+    )
+        .prop_map(|(address, id, phase, payload)| PublishMessage {
+            address,
+            signal: Some(SignalType::Gesture),
+            value: None,
+            payload: Some(payload),
+            samples: None,
+            rate: None,
+            id: Some(id),
+            phase: Some(phase),
+            timestamp: None,
+            timeline: None,
+        });
+    prop_oneof![event, stream, gesture]
+}
+
+fn signal_type() -> impl Strategy<Value = SignalType> {
+    prop_oneof![
+        Just(SignalType::Param),
+        Just(SignalType::Event),
+        Just(SignalType::Stream),
+        Just(SignalType::Gesture),
+        Just(SignalType::Timeline),
+    ]
+}
+
+/// Signal types as a set, in the order the codec's bit flags decode to
+fn signal_types() -> impl Strategy<Value = Vec<SignalType>> {
+    vec(signal_type(), 0..3).prop_map(|mut types| {
+        types.sort_by_key(|t| *t as u8);
+        types.dedup();
+        types
+    })
+}
+
+fn subscribe() -> impl Strategy<Value = SubscribeMessage> {
+    (
+        any::<u32>(),
+        pattern(),
+        signal_types(),
+        option::of((
+            option::of(any::<u32>()),
+            option::of(float()),
+            option::of(any::<u32>()),
+            option::of(any::<u32>()),
+            option::of(any::<u32>()),
+        )),
+        option::of(any::<u32>()),
+    )
+        .prop_map(
+            |(id, pattern, types, options, correlation_id)| SubscribeMessage {
+                id,
+                pattern,
+                types,
+                options: options.map(|(max_rate, epsilon, history, window, batch)| {
+                    SubscribeOptions {
+                        max_rate,
+                        epsilon,
+                        history,
+                        window,
+                        batch,
+                        ..Default::default()
+                    }
+                }),
+                correlation_id,
+            },
+        )
+}
+
+fn param() -> impl Strategy<Value = ParamValue> {
+    (address(), value(), any::<u64>()).prop_map(|(address, value, revision)| ParamValue {
+        address,
+        value,
+        revision,
+        writer: None,
+        timestamp: None,
+    })
+}
+
+fn message() -> impl Strategy<Value = Message> {
+    let simple = prop_oneof![
+        ("[ -~]{0,16}", signal_types()).prop_map(|(name, features)| {
+            Message::Hello(HelloMessage {
+                version: 3,
+                name,
+                features: features
+                    .iter()
+                    .map(|f| format!("{:?}", f).to_lowercase())
+                    .collect(),
+                capabilities: None,
+                token: None,
+                since: None,
+            })
+        }),
+        ("[a-z0-9-]{1,36}", "[ -~]{0,16}", any::<u64>()).prop_map(|(session, name, time)| {
+            Message::Welcome(WelcomeMessage {
+                version: 3,
+                session,
+                name,
+                features: vec!["param".to_string()],
+                time,
+                token: None,
+            })
+        }),
+        subscribe().prop_map(Message::Subscribe),
+        any::<u32>().prop_map(|id| Message::Unsubscribe(UnsubscribeMessage { id })),
+        publish().prop_map(Message::Publish),
+        set().prop_map(Message::Set),
+        (address(), option::of(any::<u32>())).prop_map(|(address, correlation_id)| {
+            Message::Get(GetMessage {
+                address,
+                correlation_id,
+            })
+        }),
+        (vec(param(), 0..4), option::of(any::<u32>())).prop_map(|(params, correlation_id)| {
+            Message::Snapshot(SnapshotMessage {
+                params,
+                correlation_id,
+                page: None,
+                sync: None,
+            })
+        }),
+        (
+            any::<u64>(),
+            option::of(any::<u64>()),
+            option::of(any::<u64>())
+        )
+            .prop_map(|(t1, t2, t3)| Message::Sync(SyncMessage { t1, t2, t3 })),
+        Just(Message::Ping),
+        Just(Message::Pong),
+        (
+            option::of(address()),
+            option::of(any::<u64>()),
+            option::of(any::<u32>())
+        )
+            .prop_map(|(address, revision, correlation_id)| {
+                Message::Ack(AckMessage {
+                    address,
+                    revision,
+                    locked: None,
+                    holder: None,
+                    value: None,
+                    correlation_id,
+                })
+            }),
+        (
+            any::<u16>(),
+            ".{0,24}",
+            option::of(address()),
+            option::of(any::<u32>())
+        )
+            .prop_map(|(code, message, address, correlation_id)| {
+                Message::Error(ErrorMessage {
+                    code,
+                    message,
+                    address,
+                    correlation_id,
+                    revision: None,
+                    value: None,
+                })
+            }),
+        pattern().prop_map(|pattern| Message::Query(QueryMessage { pattern })),
+    ];
+
+    let bundled = prop_oneof![
+        set().prop_map(Message::Set),
+        publish().prop_map(Message::Publish)
+    ];
+    let bundle = (
+        option::of(any::<u64>()),
+        vec(bundled, 0..4),
+        option::of(any::<u32>()),
+    )
+        .prop_map(|(timestamp, messages, correlation_id)| {
+            Message::Bundle(BundleMessage {
+                timestamp,
+                messages,
+                correlation_id,
+            })
+        });
+
+    prop_oneof![9 => simple, 1 => bundle]
+}
+
+/// Values the embedded encoder can represent
+fn embedded_value() -> impl Strategy<Value = embedded::Value> {
+    prop_oneof![
+        Just(embedded::Value::Null),
+        any::<bool>().prop_map(embedded::Value::Bool),
+        any::<i64>().prop_map(embedded::Value::Int),
+        float().prop_map(embedded::Value::Float),
+    ]
+}
+
+fn to_core(value: embedded::Value) -> Value {
+    match value {
+        embedded::Value::Null => Value::Null,
+        embedded::Value::Bool(b) => Value::Bool(b),
+        embedded::Value::Int(i) => Value::Int(i),
+        embedded::Value::Float(f) => Value::Float(f),
+    }
+}
+
+fn json(msg: &Message) -> serde_json::Value {
+    serde_json::to_value(msg).unwrap()
+}
+
+/// The form a message takes after a binary round trip.
+///
+/// PUBLISH carries a single value slot, so an event payload comes back as
+/// `value`, and a missing gesture phase is written as `start`. SUBSCRIBE
+/// options with nothing set are not written at all.
+fn canonical(msg: &Message) -> Message {
+    match msg {
+        Message::Subscribe(m) => {
+            let mut m = m.clone();
+            m.options = m.options.filter(|o| {
+                o.max_rate.is_some()
+                    || o.epsilon.is_some()
+                    || o.history.is_some()
+                    || o.window.is_some()
+                    || o.batch.is_some()
+            });
+            Message::Subscribe(m)
+        }
+        Message::Publish(m) => {
+            let mut m = m.clone();
+            m.value = m.value.take().or(m.payload.take());
+            m.phase = m.phase.or(Some(GesturePhase::Start));
+            Message::Publish(m)
+        }
+        Message::Bundle(m) => Message::Bundle(BundleMessage {
+            messages: m.messages.iter().map(canonical).collect(),
+            ..m.clone()
+        }),
+        other => other.clone(),
+    }
+}
+
+/// Payload of a core-encoded frame, checked with the embedded header parser
+fn embedded_payload(frame: &[u8]) -> &[u8] {
+    let (_, len) = embedded::decode_header(frame).expect("embedded header");
+    &frame[embedded::HEADER_SIZE..embedded::HEADER_SIZE + len]
+}
+
+// ============================================================================
+// Properties
+// ============================================================================
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn core_roundtrip(msg in message()) {
+        let bytes = codec::encode(&msg).unwrap();
+        let (decoded, _) = codec::decode(&bytes).unwrap();
+        prop_assert_eq!(json(&decoded), json(&canonical(&msg)));
+    }
+
+    #[test]
+    fn embedded_set_decodes_in_core(address in address(), value in embedded_value()) {
+        let mut buf = [0u8; 256];
+        let len = embedded::encode_set_frame(&mut buf, &address, &value);
+        prop_assert!(len > 0);
+
+        let (decoded, _) = codec::decode(&buf[..len]).unwrap();
+        let expected = Message::Set(SetMessage {
+            address,
+            value: to_core(value),
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        });
+        prop_assert_eq!(json(&decoded), json(&expected));
+    }
+
+    #[test]
+    fn core_set_decodes_in_embedded(
+        address in address(),
+        value in embedded_value(),
+        revision in option::of(any::<u64>()),
+        lock in any::<bool>(),
+        correlation_id in option::of(any::<u32>()),
+    ) {
+        let msg = Message::Set(SetMessage {
+            address: address.clone(),
+            value: to_core(value),
+            revision,
+            lock,
+            unlock: false,
+            op: None,
+            correlation_id,
+        });
+        let bytes = codec::encode(&msg).unwrap();
+
+        match embedded::decode_message(embedded_payload(&bytes)) {
+            Some(embedded::Message::Set { address: a, value: v }) => {
+                prop_assert_eq!(a, address.as_str());
+                prop_assert_eq!(v, value);
+            }
+            other => prop_assert!(false, "expected SET, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn embedded_subscribe_and_hello_decode_in_core(
+        pattern in pattern(),
+        name in "[ -~]{0,32}",
+    ) {
+        let mut buf = [0u8; 256];
+        let len = embedded::encode_subscribe_frame(&mut buf, &pattern);
+        prop_assert!(len > 0);
+        match codec::decode(&buf[..len]).unwrap().0 {
+            Message::Subscribe(sub) => prop_assert_eq!(sub.pattern, pattern),
+            other => prop_assert!(false, "expected SUBSCRIBE, got {:?}", other),
+        }
+
+        let len = embedded::encode_hello_frame(&mut buf, &name);
+        prop_assert!(len > 0);
+        match codec::decode(&buf[..len]).unwrap().0 {
+            Message::Hello(hello) => prop_assert_eq!(hello.name, name),
+            other => prop_assert!(false, "expected HELLO, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn core_messages_decode_in_embedded(
+        id in any::<u32>(),
+        pattern in pattern(),
+        code in any::<u16>(),
+        text in "[ -~]{0,32}",
+        address in address(),
+    ) {
+        let subscribe = Message::Subscribe(SubscribeMessage {
+            id,
+            pattern: pattern.clone(),
+            types: vec![],
+            options: None,
+            correlation_id: None,
+        });
+        let bytes = codec::encode(&subscribe).unwrap();
+        match embedded::decode_message(embedded_payload(&bytes)) {
+            Some(embedded::Message::Subscribe { id: i, pattern: p }) => {
+                prop_assert_eq!(i, id);
+                prop_assert_eq!(p, pattern.as_str());
+            }
+            other => prop_assert!(false, "expected SUBSCRIBE, got {:?}", other),
+        }
+
+        let bytes = codec::encode(&Message::Unsubscribe(UnsubscribeMessage { id })).unwrap();
+        match embedded::decode_message(embedded_payload(&bytes)) {
+            Some(embedded::Message::Unsubscribe { id: i }) => prop_assert_eq!(i, id),
+            other => prop_assert!(false, "expected UNSUBSCRIBE, got {:?}", other),
+        }
+
+        let error = Message::Error(ErrorMessage {
+            code,
+            message: text.clone(),
+            address: None,
+            correlation_id: None,
+            revision: None,
+            value: None,
+        });
+        let bytes = codec::encode(&error).unwrap();
+        match embedded::decode_message(embedded_payload(&bytes)) {
+            Some(embedded::Message::Error { code: c, message: m }) => {
+                prop_assert_eq!(c, code);
+                prop_assert_eq!(m, text.as_str());
+            }
+            other => prop_assert!(false, "expected ERROR, got {:?}", other),
+        }
+
+        let event = Message::Publish(PublishMessage {
+            address: address.clone(),
+            signal: Some(SignalType::Event),
+            value: None,
+            payload: Some(Value::Int(1)),
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        });
+        let bytes = codec::encode(&event).unwrap();
+        match embedded::decode_message(embedded_payload(&bytes)) {
+            Some(embedded::Message::Publish { address: a }) => prop_assert_eq!(a, address.as_str()),
+            other => prop_assert!(false, "expected PUBLISH, got {:?}", other),
+        }
+    }
+}
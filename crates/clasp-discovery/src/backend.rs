@@ -0,0 +1,99 @@
+//! Pluggable discovery backends
+//!
+//! Every discovery mechanism (mDNS, UDP broadcast, rendezvous, or anything an
+//! integrator brings along such as SSDP or a company device registry)
+//! implements [`DiscoveryBackend`]. [`crate::Discovery`] aggregates any number
+//! of backends and merges their results.
+
+use crate::{DiscoveryEvent, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+/// Information about the local service to announce
+#[derive(Debug, Clone)]
+pub struct Announcement {
+    /// Human-readable service name
+    pub name: String,
+    /// WebSocket port
+    pub port: u16,
+    /// Supported features (param, event, stream, gesture, timeline)
+    pub features: Vec<String>,
+    /// Endpoints (transport -> address)
+    pub endpoints: HashMap<String, String>,
+    /// Tags for filtering
+    pub tags: Vec<String>,
+    /// Additional metadata
+    pub metadata: HashMap<String, String>,
+}
+
+impl Announcement {
+    /// Create an announcement for a service on the given WebSocket port
+    pub fn new(name: &str, port: u16) -> Self {
+        Self {
+            name: name.to_string(),
+            port,
+            features: vec![
+                "param".to_string(),
+                "event".to_string(),
+                "stream".to_string(),
+            ],
+            endpoints: HashMap::new(),
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn with_features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn with_endpoint(mut self, transport: &str, address: &str) -> Self {
+        self.endpoints
+            .insert(transport.to_string(), address.to_string());
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// A discovery mechanism
+///
+/// Backends are shared between the [`crate::Discovery`] aggregator and the
+/// tasks it spawns, so all methods take `&self`; use interior mutability for
+/// any state (e.g. an active advertisement).
+#[async_trait]
+pub trait DiscoveryBackend: Send + Sync {
+    /// Short name used in logs (e.g. "mdns")
+    fn name(&self) -> &str;
+
+    /// Prepare the backend. Called once before the first browse.
+    async fn start(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Stop browsing and withdraw any active announcement
+    async fn stop(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Make the local service discoverable through this backend
+    async fn announce(&self, _announcement: &Announcement) -> Result<()> {
+        Ok(())
+    }
+
+    /// Browse for devices, sending events to `tx`.
+    ///
+    /// Returns when the backend has nothing more to report or the receiver is
+    /// dropped.
+    async fn browse(&self, tx: mpsc::Sender<DiscoveryEvent>) -> Result<()>;
+}
@@ -1,9 +1,13 @@
 //! UDP broadcast discovery
 
+use crate::backend::{Announcement, DiscoveryBackend};
 use crate::{Device, DeviceInfo, DiscoveryError, DiscoveryEvent, Result};
+use async_trait::async_trait;
 use clasp_core::{codec, HelloMessage, Message, PROTOCOL_VERSION};
 use clasp_transport::UdpTransport;
+use std::sync::Mutex;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tokio::time::{timeout, Duration};
 use tracing::{debug, info, warn};
 
@@ -31,6 +35,7 @@ pub async fn discover(port: u16, tx: mpsc::Sender<DiscoveryEvent>) -> Result<()>
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
 
     let hello_bytes = codec::encode(&hello).map_err(|e| DiscoveryError::Network(e.to_string()))?;
@@ -158,3 +163,59 @@ impl BroadcastResponder {
         Ok(())
     }
 }
+
+/// UDP broadcast discovery backend
+///
+/// Browsing sends a HELLO to the broadcast address; announcing runs a
+/// [`BroadcastResponder`] on the same port.
+pub struct BroadcastBackend {
+    port: u16,
+    responder: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BroadcastBackend {
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            responder: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for BroadcastBackend {
+    fn name(&self) -> &str {
+        "broadcast"
+    }
+
+    async fn stop(&self) -> Result<()> {
+        if let Some(task) = self.responder.lock().unwrap().take() {
+            task.abort();
+        }
+        Ok(())
+    }
+
+    async fn announce(&self, announcement: &Announcement) -> Result<()> {
+        let responder = BroadcastResponder::bind(
+            self.port,
+            announcement.name.clone(),
+            announcement.features.clone(),
+        )
+        .await?;
+
+        let task = tokio::spawn(async move {
+            if let Err(e) = responder.run().await {
+                warn!("Broadcast responder error: {}", e);
+            }
+        });
+
+        if let Some(previous) = self.responder.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    async fn browse(&self, tx: mpsc::Sender<DiscoveryEvent>) -> Result<()> {
+        discover(self.port, tx).await
+    }
+}
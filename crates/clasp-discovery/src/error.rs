@@ -18,6 +18,9 @@ pub enum DiscoveryError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("store error: {0}")]
+    Store(String),
+
     #[error("discovery error: {0}")]
     Other(String),
 }
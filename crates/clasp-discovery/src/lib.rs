@@ -5,7 +5,9 @@
 //! - UDP broadcast fallback
 //! - Rendezvous server for WAN discovery
 //! - Manual registration
+//! - Custom mechanisms via the [`DiscoveryBackend`] trait
 
+pub mod backend;
 pub mod device;
 pub mod error;
 
@@ -18,11 +20,14 @@ pub mod broadcast;
 #[cfg(feature = "rendezvous")]
 pub mod rendezvous;
 
+pub use backend::{Announcement, DiscoveryBackend};
 pub use device::{Device, DeviceInfo};
 pub use error::{DiscoveryError, Result};
 
 #[cfg(feature = "rendezvous")]
-pub use rendezvous::{DeviceRegistration, RendezvousClient, RendezvousConfig, RendezvousServer};
+pub use rendezvous::{
+    DeviceRegistration, RendezvousBackend, RendezvousClient, RendezvousConfig, RendezvousServer,
+};
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -69,6 +74,8 @@ pub struct DiscoveryConfig {
     pub rendezvous_refresh_interval: Duration,
     /// Filter tag for rendezvous discovery
     pub rendezvous_tag: Option<String>,
+    /// Bearer token for rendezvous servers that require authentication
+    pub rendezvous_token: Option<String>,
 }
 
 impl Default for DiscoveryConfig {
@@ -81,136 +88,122 @@ impl Default for DiscoveryConfig {
             rendezvous_url: None,
             rendezvous_refresh_interval: Duration::from_secs(120), // 2 minutes (< 5 min default TTL)
             rendezvous_tag: None,
+            rendezvous_token: None,
         }
     }
 }
 
-/// Rendezvous keepalive state
-#[cfg(feature = "rendezvous")]
-struct RendezvousKeepalive {
-    client: rendezvous::RendezvousClient,
-    registration: rendezvous::DeviceRegistration,
-    device_id: parking_lot::RwLock<Option<String>>,
-    refresh_interval: Duration,
+/// Discover Clasp devices
+///
+/// Aggregates any number of [`DiscoveryBackend`]s. The built-in mDNS,
+/// broadcast and rendezvous backends are installed from the
+/// [`DiscoveryConfig`]; custom ones can be added with
+/// [`Discovery::add_backend`].
+pub struct Discovery {
+    config: DiscoveryConfig,
+    devices: std::collections::HashMap<String, Device>,
+    backends: Vec<Arc<dyn DiscoveryBackend>>,
+    #[cfg(feature = "rendezvous")]
+    rendezvous: Option<Arc<rendezvous::RendezvousBackend>>,
 }
 
-#[cfg(feature = "rendezvous")]
-impl RendezvousKeepalive {
-    fn new(
-        url: &str,
-        registration: rendezvous::DeviceRegistration,
-        refresh_interval: Duration,
-    ) -> Self {
-        Self {
-            client: rendezvous::RendezvousClient::new(url),
-            registration,
-            device_id: parking_lot::RwLock::new(None),
-            refresh_interval,
-        }
+impl Discovery {
+    pub fn new() -> Self {
+        Self::with_config(DiscoveryConfig::default())
     }
 
-    async fn register(&self) -> Result<()> {
-        let response = self
-            .client
-            .register(self.registration.clone())
-            .await
-            .map_err(|e| DiscoveryError::Other(format!("Rendezvous registration failed: {}", e)))?;
+    pub fn with_config(config: DiscoveryConfig) -> Self {
+        let mut discovery = Self {
+            config,
+            devices: std::collections::HashMap::new(),
+            backends: Vec::new(),
+            #[cfg(feature = "rendezvous")]
+            rendezvous: None,
+        };
+
+        #[cfg(feature = "mdns")]
+        if discovery.config.mdns {
+            discovery.add_backend(mdns::MdnsBackend::new());
+        }
 
-        *self.device_id.write() = Some(response.id);
-        tracing::info!("Registered with rendezvous server (TTL: {}s)", response.ttl);
-        Ok(())
-    }
+        #[cfg(feature = "broadcast")]
+        if discovery.config.broadcast {
+            discovery.add_backend(broadcast::BroadcastBackend::new(
+                discovery.config.broadcast_port,
+            ));
+        }
 
-    async fn refresh(&self) -> Result<bool> {
-        let device_id: Option<String> = self.device_id.read().clone();
-        if let Some(ref id) = device_id {
-            let success =
-                self.client.refresh(id).await.map_err(|e| {
-                    DiscoveryError::Other(format!("Rendezvous refresh failed: {}", e))
-                })?;
-
-            if !success {
-                // Device was removed, re-register
-                tracing::warn!("Rendezvous registration expired, re-registering");
-                *self.device_id.write() = None;
-                self.register().await?;
+        #[cfg(feature = "rendezvous")]
+        if let Some(ref url) = discovery.config.rendezvous_url {
+            let mut backend = rendezvous::RendezvousBackend::new(
+                url,
+                discovery.config.rendezvous_tag.clone(),
+                discovery.config.rendezvous_refresh_interval,
+            );
+            if let Some(ref token) = discovery.config.rendezvous_token {
+                backend = backend.with_token(token);
             }
-            Ok(true)
-        } else {
-            // Not registered yet, register now
-            self.register().await?;
-            Ok(true)
+            let backend = Arc::new(backend);
+            discovery.backends.push(backend.clone());
+            discovery.rendezvous = Some(backend);
         }
+
+        discovery
     }
 
-    async fn unregister(&self) -> Result<()> {
-        let device_id: Option<String> = self.device_id.write().take();
-        if let Some(ref id) = device_id {
-            let _ = self.client.unregister(id).await;
-            tracing::info!("Unregistered from rendezvous server");
-        }
-        Ok(())
+    /// Add a discovery backend
+    pub fn add_backend<B: DiscoveryBackend + 'static>(&mut self, backend: B) {
+        self.backends.push(Arc::new(backend));
     }
 
-    /// Start the keepalive loop
-    fn start_keepalive(self: Arc<Self>) {
-        let keepalive = Arc::clone(&self);
-        tokio::spawn(async move {
-            // Initial registration
-            if let Err(e) = keepalive.register().await {
-                tracing::error!("Initial rendezvous registration failed: {}", e);
-            }
+    /// Add a discovery backend (builder style)
+    pub fn with_backend<B: DiscoveryBackend + 'static>(mut self, backend: B) -> Self {
+        self.add_backend(backend);
+        self
+    }
 
-            // Refresh loop
-            let mut interval = tokio::time::interval(keepalive.refresh_interval);
-            loop {
-                interval.tick().await;
-                if let Err(e) = keepalive.refresh().await {
-                    tracing::warn!("Rendezvous refresh failed: {}", e);
-                }
-            }
-        });
+    /// Names of the installed backends
+    pub fn backends(&self) -> impl Iterator<Item = &str> {
+        self.backends.iter().map(|b| b.name())
     }
-}
 
-/// Discover Clasp devices
-pub struct Discovery {
-    config: DiscoveryConfig,
-    devices: std::collections::HashMap<String, Device>,
-    #[cfg(feature = "rendezvous")]
-    rendezvous_keepalive: Option<Arc<RendezvousKeepalive>>,
-}
+    /// Announce the local service through every backend.
+    ///
+    /// Fails only if no backend could announce.
+    pub async fn announce(&self, announcement: &Announcement) -> Result<()> {
+        let mut last_error = None;
+        let mut announced = false;
+        for backend in &self.backends {
+            match backend.announce(announcement).await {
+                Ok(()) => announced = true,
+                Err(e) => {
+                    tracing::warn!("{} announce failed: {}", backend.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
 
-impl Discovery {
-    pub fn new() -> Self {
-        Self {
-            config: DiscoveryConfig::default(),
-            devices: std::collections::HashMap::new(),
-            #[cfg(feature = "rendezvous")]
-            rendezvous_keepalive: None,
+        match last_error {
+            Some(e) if !announced => Err(e),
+            _ => Ok(()),
         }
     }
 
-    pub fn with_config(config: DiscoveryConfig) -> Self {
-        Self {
-            config,
-            devices: std::collections::HashMap::new(),
-            #[cfg(feature = "rendezvous")]
-            rendezvous_keepalive: None,
+    /// Stop every backend and withdraw announcements
+    pub async fn stop(&self) -> Result<()> {
+        for backend in &self.backends {
+            if let Err(e) = backend.stop().await {
+                tracing::warn!("{} stop failed: {}", backend.name(), e);
+            }
         }
+        Ok(())
     }
 
     /// Register this device with the rendezvous server and start keepalive
     #[cfg(feature = "rendezvous")]
     pub fn register_with_rendezvous(&mut self, registration: rendezvous::DeviceRegistration) {
-        if let Some(ref url) = self.config.rendezvous_url {
-            let keepalive = Arc::new(RendezvousKeepalive::new(
-                url,
-                registration,
-                self.config.rendezvous_refresh_interval,
-            ));
-            keepalive.clone().start_keepalive();
-            self.rendezvous_keepalive = Some(keepalive);
+        if let Some(ref backend) = self.rendezvous {
+            backend.register(registration);
         } else {
             tracing::warn!("Cannot register with rendezvous: no URL configured");
         }
@@ -219,86 +212,21 @@ impl Discovery {
     /// Discover devices from the rendezvous server (WAN discovery)
     #[cfg(feature = "rendezvous")]
     pub async fn discover_wan(&self) -> Result<Vec<Device>> {
-        let url = self
-            .config
-            .rendezvous_url
+        let backend = self
+            .rendezvous
             .as_ref()
             .ok_or_else(|| DiscoveryError::Other("No rendezvous URL configured".to_string()))?;
 
-        let client = rendezvous::RendezvousClient::new(url);
-        let tag = self.config.rendezvous_tag.as_deref();
-        let registered_devices = client
-            .discover(tag)
-            .await
-            .map_err(|e| DiscoveryError::Other(format!("Rendezvous discovery failed: {}", e)))?;
-
-        // Convert RegisteredDevice to Device
-        let devices: Vec<Device> = registered_devices
-            .into_iter()
-            .map(|rd| {
-                let mut meta = rd.metadata.clone();
-                // Add tags to metadata
-                if !rd.tags.is_empty() {
-                    meta.insert("tags".to_string(), rd.tags.join(","));
-                }
-
-                let info = DeviceInfo {
-                    version: clasp_core::PROTOCOL_VERSION,
-                    features: rd.features,
-                    bridge: false,
-                    bridge_protocol: None,
-                    meta,
-                };
-
-                let now = std::time::Instant::now();
-                Device {
-                    id: rd.id,
-                    name: rd.name,
-                    info,
-                    endpoints: rd.endpoints,
-                    discovered_at: now,
-                    last_seen: now,
-                }
-            })
-            .collect();
-
-        Ok(devices)
+        backend.discover().await
     }
 
-    /// Discover all devices using all available methods (cascade discovery)
-    /// Tries: mDNS → broadcast → rendezvous
-    /// Returns devices from all successful discovery methods
+    /// Discover all devices using every backend
+    /// Returns the devices reported before the configured timeout, deduplicated by ID
     pub async fn discover_all(&mut self) -> Result<Vec<Device>> {
-        let (tx, mut rx) = mpsc::channel(100);
+        let mut rx = self.start().await?;
         let mut all_devices = Vec::new();
         let mut seen_ids = std::collections::HashSet::new();
-
-        // Start LAN discovery
-        #[cfg(feature = "mdns")]
-        if self.config.mdns {
-            let tx_clone = tx.clone();
-            tokio::spawn(async move {
-                if let Err(e) = mdns::discover(tx_clone).await {
-                    tracing::warn!("mDNS discovery error: {}", e);
-                }
-            });
-        }
-
-        #[cfg(feature = "broadcast")]
-        if self.config.broadcast {
-            let tx_clone = tx.clone();
-            let port = self.config.broadcast_port;
-            tokio::spawn(async move {
-                if let Err(e) = broadcast::discover(port, tx_clone).await {
-                    tracing::warn!("Broadcast discovery error: {}", e);
-                }
-            });
-        }
-
-        // Collect LAN results with timeout
-        let timeout = self.config.timeout;
-        let deadline = tokio::time::Instant::now() + timeout;
-        drop(tx); // Close sender so rx completes when all spawned tasks finish
+        let deadline = tokio::time::Instant::now() + self.config.timeout;
 
         loop {
             tokio::select! {
@@ -313,109 +241,38 @@ impl Discovery {
                         Some(DiscoveryEvent::Error(e)) => {
                             tracing::warn!("Discovery error: {}", e);
                         }
-                        Some(DiscoveryEvent::Lost(_)) | None => break,
+                        Some(DiscoveryEvent::Lost(_)) => {}
+                        None => break,
                     }
                 }
                 _ = tokio::time::sleep_until(deadline) => {
-                    tracing::debug!("LAN discovery timeout");
+                    tracing::debug!("Discovery timeout");
                     break;
                 }
             }
         }
 
-        // Try WAN discovery if configured
-        #[cfg(feature = "rendezvous")]
-        if self.config.rendezvous_url.is_some() {
-            match self.discover_wan().await {
-                Ok(wan_devices) => {
-                    for device in wan_devices {
-                        if seen_ids.insert(device.id.clone()) {
-                            self.devices.insert(device.id.clone(), device.clone());
-                            all_devices.push(device);
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("WAN discovery failed: {}", e);
-                }
-            }
-        }
-
         Ok(all_devices)
     }
 
     /// Start discovery and return a receiver for events
+    ///
+    /// Each backend browses in its own task; the receiver closes once all of
+    /// them have finished.
     pub async fn start(&mut self) -> Result<mpsc::Receiver<DiscoveryEvent>> {
         let (tx, rx) = mpsc::channel(100);
 
-        #[cfg(feature = "mdns")]
-        if self.config.mdns {
-            let tx_clone = tx.clone();
-            tokio::spawn(async move {
-                if let Err(e) = mdns::discover(tx_clone).await {
-                    tracing::warn!("mDNS discovery error: {}", e);
-                }
-            });
-        }
-
-        #[cfg(feature = "broadcast")]
-        if self.config.broadcast {
-            let tx_clone = tx.clone();
-            let port = self.config.broadcast_port;
-            tokio::spawn(async move {
-                if let Err(e) = broadcast::discover(port, tx_clone).await {
-                    tracing::warn!("Broadcast discovery error: {}", e);
-                }
-            });
-        }
+        for backend in &self.backends {
+            if let Err(e) = backend.start().await {
+                tracing::warn!("{} start failed: {}", backend.name(), e);
+                continue;
+            }
 
-        // Start WAN discovery if configured
-        #[cfg(feature = "rendezvous")]
-        if self.config.rendezvous_url.is_some() {
-            let tx_clone = tx.clone();
-            let config = self.config.clone();
+            let backend = Arc::clone(backend);
+            let tx = tx.clone();
             tokio::spawn(async move {
-                let url = config.rendezvous_url.as_ref().unwrap();
-                let client = rendezvous::RendezvousClient::new(url);
-                let tag = config.rendezvous_tag.as_deref();
-
-                match client.discover(tag).await {
-                    Ok(devices) => {
-                        for rd in devices {
-                            let mut meta = rd.metadata.clone();
-                            if !rd.tags.is_empty() {
-                                meta.insert("tags".to_string(), rd.tags.join(","));
-                            }
-
-                            let info = DeviceInfo {
-                                version: clasp_core::PROTOCOL_VERSION,
-                                features: rd.features,
-                                bridge: false,
-                                bridge_protocol: None,
-                                meta,
-                            };
-
-                            let now = std::time::Instant::now();
-                            let device = Device {
-                                id: rd.id,
-                                name: rd.name,
-                                info,
-                                endpoints: rd.endpoints,
-                                discovered_at: now,
-                                last_seen: now,
-                            };
-                            let _ = tx_clone.send(DiscoveryEvent::Found(device)).await;
-                        }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Rendezvous discovery error: {}", e);
-                        let _ = tx_clone
-                            .send(DiscoveryEvent::Error(format!(
-                                "Rendezvous discovery failed: {}",
-                                e
-                            )))
-                            .await;
-                    }
+                if let Err(e) = backend.browse(tx).await {
+                    tracing::warn!("{} discovery error: {}", backend.name(), e);
                 }
             });
         }
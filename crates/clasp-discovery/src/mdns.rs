@@ -1,13 +1,20 @@
 //! mDNS/Bonjour discovery
 
+use crate::backend::{Announcement, DiscoveryBackend};
 use crate::{Device, DeviceInfo, DiscoveryError, DiscoveryEvent, Result};
+use async_trait::async_trait;
 use mdns_sd::{ServiceDaemon, ServiceEvent};
+use std::sync::Mutex;
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
 /// mDNS service type for Clasp
 const SERVICE_TYPE: &str = "_clasp._tcp.local.";
 
+/// TXT properties with a dedicated meaning; anything else ends up in
+/// [`DeviceInfo::meta`]
+const KNOWN_PROPERTIES: &[&str] = &["version", "name", "features", "ws", "udp"];
+
 /// Discover Clasp devices via mDNS
 pub async fn discover(tx: mpsc::Sender<DiscoveryEvent>) -> Result<()> {
     // Create mDNS daemon
@@ -22,7 +29,7 @@ pub async fn discover(tx: mpsc::Sender<DiscoveryEvent>) -> Result<()> {
 
     // Process discovery events
     loop {
-        match receiver.recv() {
+        match receiver.recv_async().await {
             Ok(event) => match event {
                 ServiceEvent::ServiceResolved(info) => {
                     debug!("mDNS resolved: {:?}", info);
@@ -67,13 +74,31 @@ pub async fn discover(tx: mpsc::Sender<DiscoveryEvent>) -> Result<()> {
                         .and_then(|val| String::from_utf8_lossy(val).parse().ok())
                         .unwrap_or(clasp_core::DEFAULT_WS_PORT);
 
+                    // UDP port, if the service takes datagrams (e.g. mesh peers)
+                    let udp_port: Option<u16> = properties
+                        .get("udp")
+                        .and_then(|v| v.val())
+                        .and_then(|val| String::from_utf8_lossy(val).parse().ok());
+
                     // Build WebSocket URL
                     if let Some(addr) = info.get_addresses().iter().next() {
                         let ws_url = format!("ws://{}:{}/clasp", addr, ws_port);
                         device = device.with_ws_endpoint(&ws_url);
+                        if let Some(port) = udp_port {
+                            device = device.with_udp_endpoint((*addr, port).into());
+                        }
                     }
 
                     device.info = DeviceInfo::default().with_features(features);
+                    for property in properties.iter() {
+                        let key = property.key();
+                        if !KNOWN_PROPERTIES.contains(&key) {
+                            device
+                                .info
+                                .meta
+                                .insert(key.to_string(), property.val_str().to_string());
+                        }
+                    }
 
                     info!(
                         "Discovered device: {} at {:?}",
@@ -127,6 +152,17 @@ impl ServiceAdvertiser {
 
     /// Advertise a Clasp service
     pub fn advertise(&mut self, name: &str, port: u16, features: &[&str]) -> Result<()> {
+        self.advertise_with(name, port, features, &[])
+    }
+
+    /// Advertise a Clasp service with extra TXT properties
+    pub fn advertise_with(
+        &mut self,
+        name: &str,
+        port: u16,
+        features: &[&str],
+        extra: &[(&str, &str)],
+    ) -> Result<()> {
         use mdns_sd::ServiceInfo;
 
         // Build feature string
@@ -144,19 +180,20 @@ impl ServiceAdvertiser {
 
         // Create service info
         let port_str = port.to_string();
-        let properties: &[(&str, &str)] = &[
+        let mut properties: Vec<(&str, &str)> = vec![
             ("version", "2"),
             ("name", name),
             ("features", &feat_str),
             ("ws", &port_str),
         ];
+        properties.extend_from_slice(extra);
         let service_info = ServiceInfo::new(
             SERVICE_TYPE,
             name,
             &format!("{}.local.", hostname::get().unwrap().to_string_lossy()),
             "",
             port,
-            properties,
+            &properties[..],
         )
         .map_err(|e| DiscoveryError::Mdns(e.to_string()))?;
 
@@ -193,3 +230,56 @@ impl Drop for ServiceAdvertiser {
         let _ = self.stop();
     }
 }
+
+/// mDNS/Bonjour discovery backend
+#[derive(Default)]
+pub struct MdnsBackend {
+    advertiser: Mutex<Option<ServiceAdvertiser>>,
+}
+
+impl MdnsBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for MdnsBackend {
+    fn name(&self) -> &str {
+        "mdns"
+    }
+
+    async fn stop(&self) -> Result<()> {
+        if let Some(mut advertiser) = self.advertiser.lock().unwrap().take() {
+            advertiser.stop()?;
+        }
+        Ok(())
+    }
+
+    async fn announce(&self, announcement: &Announcement) -> Result<()> {
+        let features: Vec<&str> = announcement.features.iter().map(|f| f.as_str()).collect();
+        let udp_port = announcement
+            .endpoints
+            .get("udp")
+            .and_then(|addr| addr.parse::<std::net::SocketAddr>().ok())
+            .map(|addr| addr.port().to_string());
+        let mut extra: Vec<(&str, &str)> = announcement
+            .metadata
+            .iter()
+            .filter(|(key, _)| !KNOWN_PROPERTIES.contains(&key.as_str()))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        if let Some(ref port) = udp_port {
+            extra.push(("udp", port));
+        }
+
+        let mut advertiser = ServiceAdvertiser::new()?;
+        advertiser.advertise_with(&announcement.name, announcement.port, &features, &extra)?;
+        *self.advertiser.lock().unwrap() = Some(advertiser);
+        Ok(())
+    }
+
+    async fn browse(&self, tx: mpsc::Sender<DiscoveryEvent>) -> Result<()> {
+        discover(tx).await
+    }
+}
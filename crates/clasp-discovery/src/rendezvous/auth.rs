@@ -0,0 +1,97 @@
+//! Token authentication for the rendezvous server
+//!
+//! When a [`TokenValidator`] is configured, every API call except health
+//! must carry `Authorization: Bearer <token>`. Token scopes are checked
+//! against per-tag pseudo-addresses (see [`tag_address`]):
+//!
+//! - `write` on a tag's address allows registering, refreshing and
+//!   unregistering devices carrying that tag
+//! - `read` (or `write`) on a tag's address allows discovering devices
+//!   carrying that tag
+//!
+//! For example `read:/rendezvous/tags/studio` can only see studio devices,
+//! while `admin:/rendezvous/**` can manage the whole registry.
+
+use axum::http::{header, HeaderMap, StatusCode};
+use clasp_core::security::{Action, TokenInfo, TokenValidator, ValidationResult};
+
+use super::RegisteredDevice;
+
+/// Pseudo-address prefix that rendezvous scopes are matched against
+pub const SCOPE_PREFIX: &str = "/rendezvous";
+
+/// Scope address for a tag
+pub fn tag_address(tag: &str) -> String {
+    format!("{}/tags/{}", SCOPE_PREFIX, tag)
+}
+
+/// Scope address for devices registered without tags
+pub fn untagged_address() -> String {
+    format!("{}/untagged", SCOPE_PREFIX)
+}
+
+/// Authenticated caller; `None` when the server runs without a validator
+pub(super) type Caller = Option<TokenInfo>;
+
+/// Validate the bearer token in `headers`
+pub(super) fn authenticate(
+    validator: Option<&dyn TokenValidator>,
+    headers: &HeaderMap,
+) -> Result<Caller, (StatusCode, String)> {
+    let Some(validator) = validator else {
+        return Ok(None);
+    };
+
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                "Authentication required".to_string(),
+            )
+        })?;
+
+    match validator.validate(token) {
+        ValidationResult::Valid(info) => Ok(Some(info)),
+        ValidationResult::Expired => {
+            Err((StatusCode::UNAUTHORIZED, "Token has expired".to_string()))
+        }
+        ValidationResult::Invalid(reason) => Err((
+            StatusCode::UNAUTHORIZED,
+            format!("Invalid token: {}", reason),
+        )),
+        ValidationResult::NotMyToken => Err((
+            StatusCode::UNAUTHORIZED,
+            "Unrecognized token format".to_string(),
+        )),
+    }
+}
+
+/// Check that the caller may perform `action` on every tag in `tags`
+pub(super) fn allows_tags(caller: &Caller, action: Action, tags: &[String]) -> bool {
+    let Some(info) = caller else {
+        return true;
+    };
+    if tags.is_empty() {
+        return info.has_scope(action, &untagged_address());
+    }
+    tags.iter()
+        .all(|tag| info.has_scope(action, &tag_address(tag)))
+}
+
+/// Check that the caller may see a device (read on any of its tags)
+pub(super) fn can_discover(caller: &Caller, device: &RegisteredDevice) -> bool {
+    let Some(info) = caller else {
+        return true;
+    };
+    if device.tags.is_empty() {
+        return info.has_scope(Action::Read, &untagged_address());
+    }
+    device
+        .tags
+        .iter()
+        .any(|tag| info.has_scope(Action::Read, &tag_address(tag)))
+}
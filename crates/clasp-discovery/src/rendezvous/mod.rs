@@ -0,0 +1,938 @@
+//! Rendezvous Server for WAN Discovery
+//!
+//! A simple HTTP REST API that enables CLASP devices to discover each other
+//! across the internet when mDNS/broadcast discovery is not available.
+//!
+//! ## Protocol
+//!
+//! Per the CLASP Protocol specification (§3.1.3), the rendezvous server provides:
+//!
+//! - `POST /api/v1/register` - Register a device with its endpoints
+//! - `GET /api/v1/discover` - Discover registered devices (optionally filtered by tag)
+//! - `DELETE /api/v1/unregister/{id}` - Unregister a device
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use clasp_discovery::rendezvous::{RendezvousServer, RendezvousConfig};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = RendezvousServer::new(RendezvousConfig::default());
+//! server.serve("0.0.0.0:7340").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Persistence
+//!
+//! Registrations live in a [`RendezvousStore`]. The default [`MemoryStore`]
+//! is lost on restart; with the `rendezvous-redis` feature, [`RedisStore`]
+//! keeps registrations in Redis (expiring via key TTLs) so several server
+//! instances can share them behind a load balancer:
+//!
+//! ```ignore
+//! use clasp_discovery::rendezvous::{RedisStore, RendezvousConfig, RendezvousServer};
+//!
+//! let store = RedisStore::connect("redis://127.0.0.1/").await?;
+//! let server = RendezvousServer::with_store(RendezvousConfig::default(), store);
+//! server.serve("0.0.0.0:7340").await?;
+//! ```
+//!
+//! ## Client Usage
+//!
+//! ```no_run
+//! use clasp_discovery::rendezvous::{RendezvousClient, DeviceRegistration};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = RendezvousClient::new("https://relay.clasp.to");
+//!
+//! // Register this device
+//! client.register(DeviceRegistration {
+//!     name: "My Device".to_string(),
+//!     public_key: None,
+//!     features: vec!["param".to_string(), "event".to_string()],
+//!     endpoints: [("ws".to_string(), "wss://my-device.local:7330".to_string())].into(),
+//!     tags: vec!["studio".to_string()],
+//!     ..Default::default()
+//! }).await?;
+//!
+//! // Discover other devices
+//! let devices = client.discover(Some("studio")).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::trace::TraceLayer;
+use tracing::{debug, info, warn};
+
+mod auth;
+mod store;
+
+pub use auth::{tag_address, untagged_address, SCOPE_PREFIX};
+#[cfg(feature = "rendezvous-redis")]
+pub use store::RedisStore;
+pub use store::{MemoryStore, RendezvousStore};
+
+use auth::Caller;
+use clasp_core::security::{Action, TokenValidator};
+
+use crate::backend::{Announcement, DiscoveryBackend};
+use crate::error::Result;
+use crate::{Device, DeviceInfo, DiscoveryError, DiscoveryEvent};
+
+/// Default rendezvous port
+pub const DEFAULT_RENDEZVOUS_PORT: u16 = 7340;
+
+/// Device registration request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceRegistration {
+    /// Human-readable device name
+    pub name: String,
+    /// Optional public key for secure identification (base64 encoded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Supported features (param, event, stream, gesture, timeline)
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Available endpoints (transport -> URL mapping)
+    pub endpoints: HashMap<String, String>,
+    /// Tags for filtering (e.g., "studio", "live", "dev")
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Device metadata
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl Default for DeviceRegistration {
+    fn default() -> Self {
+        Self {
+            name: "CLASP Device".to_string(),
+            public_key: None,
+            features: vec!["param".to_string(), "event".to_string()],
+            endpoints: HashMap::new(),
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+/// Registration response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrationResponse {
+    /// Assigned device ID (for unregistration)
+    pub id: String,
+    /// Server timestamp
+    pub timestamp: u64,
+    /// TTL in seconds (device should re-register before expiry)
+    pub ttl: u64,
+}
+
+/// Registered device (includes server-side metadata)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredDevice {
+    /// Device ID
+    pub id: String,
+    /// Device name
+    pub name: String,
+    /// Public key (if provided)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Supported features
+    pub features: Vec<String>,
+    /// Available endpoints
+    pub endpoints: HashMap<String, String>,
+    /// Tags
+    pub tags: Vec<String>,
+    /// Device metadata
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    /// Registration timestamp
+    pub registered_at: u64,
+    /// Last seen timestamp
+    pub last_seen: u64,
+}
+
+/// Discovery query parameters
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscoverQuery {
+    /// Filter by tag
+    pub tag: Option<String>,
+    /// Filter by feature
+    pub feature: Option<String>,
+    /// Maximum results
+    pub limit: Option<usize>,
+}
+
+/// Rendezvous server configuration
+#[derive(Debug, Clone)]
+pub struct RendezvousConfig {
+    /// Time-to-live for registrations (seconds)
+    pub ttl: u64,
+    /// Maximum devices per registration source
+    pub max_devices_per_source: usize,
+    /// Maximum total devices
+    pub max_total_devices: usize,
+    /// Cleanup interval (seconds)
+    pub cleanup_interval: u64,
+}
+
+impl Default for RendezvousConfig {
+    fn default() -> Self {
+        Self {
+            ttl: 300,                   // 5 minutes
+            max_devices_per_source: 10, // 10 devices per IP
+            max_total_devices: 10000,   // 10k total devices
+            cleanup_interval: 60,       // Clean up every minute
+        }
+    }
+}
+
+/// Shared server state
+struct ServerState {
+    config: RendezvousConfig,
+    store: Arc<dyn RendezvousStore>,
+    validator: Option<Arc<dyn TokenValidator>>,
+}
+
+impl ServerState {
+    fn new(
+        config: RendezvousConfig,
+        store: Arc<dyn RendezvousStore>,
+        validator: Option<Arc<dyn TokenValidator>>,
+    ) -> Self {
+        Self {
+            config,
+            store,
+            validator,
+        }
+    }
+
+    fn authenticate(
+        &self,
+        headers: &HeaderMap,
+    ) -> std::result::Result<Caller, (StatusCode, String)> {
+        auth::authenticate(self.validator.as_deref(), headers)
+    }
+
+    /// Check that the caller may write to an existing registration
+    async fn authorize_device(
+        &self,
+        caller: &Caller,
+        id: &str,
+    ) -> std::result::Result<(), StatusCode> {
+        if caller.is_none() {
+            return Ok(());
+        }
+        match self.store.get(id).await {
+            Ok(Some(device)) if auth::allows_tags(caller, Action::Write, &device.tags) => Ok(()),
+            Ok(Some(_)) => Err(StatusCode::FORBIDDEN),
+            Ok(None) => Err(StatusCode::NOT_FOUND),
+            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl)
+    }
+
+    async fn register(&self, registration: DeviceRegistration) -> Result<RegistrationResponse> {
+        // Check capacity
+        if self.store.len().await? >= self.config.max_total_devices {
+            // Remove oldest device to make room
+            let oldest = self
+                .store
+                .list()
+                .await?
+                .into_iter()
+                .min_by_key(|device| device.last_seen)
+                .map(|device| device.id);
+            if let Some(id) = oldest {
+                self.store.remove(&id).await?;
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = clasp_core::time::now();
+
+        let device = RegisteredDevice {
+            id: id.clone(),
+            name: registration.name,
+            public_key: registration.public_key,
+            features: registration.features,
+            endpoints: registration.endpoints,
+            tags: registration.tags,
+            metadata: registration.metadata,
+            registered_at: now,
+            last_seen: now,
+        };
+
+        self.store.put(device, self.ttl()).await?;
+
+        Ok(RegistrationResponse {
+            id,
+            timestamp: now,
+            ttl: self.config.ttl,
+        })
+    }
+
+    async fn unregister(&self, id: &str) -> Result<bool> {
+        self.store.remove(id).await
+    }
+
+    async fn discover(
+        &self,
+        query: &DiscoverQuery,
+        caller: &Caller,
+    ) -> Result<Vec<RegisteredDevice>> {
+        let limit = query.limit.unwrap_or(100).min(1000);
+
+        Ok(self
+            .store
+            .list()
+            .await?
+            .into_iter()
+            .filter(|device| {
+                // Only devices the caller may see
+                if !auth::can_discover(caller, device) {
+                    return false;
+                }
+                // Filter by tag
+                if let Some(ref tag) = query.tag {
+                    if !device.tags.contains(tag) {
+                        return false;
+                    }
+                }
+                // Filter by feature
+                if let Some(ref feature) = query.feature {
+                    if !device.features.contains(feature) {
+                        return false;
+                    }
+                }
+                true
+            })
+            .take(limit)
+            .collect())
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        self.store.cleanup_expired().await
+    }
+
+    async fn refresh(&self, id: &str) -> Result<bool> {
+        self.store.refresh(id, self.ttl()).await
+    }
+}
+
+/// Rendezvous HTTP server
+pub struct RendezvousServer {
+    config: RendezvousConfig,
+    store: Arc<dyn RendezvousStore>,
+    validator: Option<Arc<dyn TokenValidator>>,
+}
+
+impl RendezvousServer {
+    /// Create a server with an in-memory store
+    pub fn new(config: RendezvousConfig) -> Self {
+        Self::with_store(config, MemoryStore::new())
+    }
+
+    /// Create a server backed by a custom store (e.g. [`RedisStore`] for
+    /// persistence and horizontal scaling)
+    pub fn with_store<S: RendezvousStore + 'static>(config: RendezvousConfig, store: S) -> Self {
+        Self {
+            config,
+            store: Arc::new(store),
+            validator: None,
+        }
+    }
+
+    /// Require a bearer token on all API calls (except health), validated
+    /// and scope-checked by `validator`
+    pub fn with_validator<V: TokenValidator + 'static>(mut self, validator: V) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Create the Axum router
+    pub fn router(&self) -> Router {
+        let state = Arc::new(ServerState::new(
+            self.config.clone(),
+            Arc::clone(&self.store),
+            self.validator.clone(),
+        ));
+
+        // Start cleanup task
+        let cleanup_state = Arc::clone(&state);
+        let cleanup_interval = self.config.cleanup_interval;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(cleanup_interval));
+            loop {
+                interval.tick().await;
+                if let Err(e) = cleanup_state.cleanup_expired().await {
+                    warn!("Rendezvous cleanup failed: {}", e);
+                    continue;
+                }
+                if let Ok(count) = cleanup_state.store.len().await {
+                    debug!("Cleanup: {} devices registered", count);
+                }
+            }
+        });
+
+        Router::new()
+            .route("/api/v1/register", post(handle_register))
+            .route("/api/v1/discover", get(handle_discover))
+            .route("/api/v1/unregister/:id", delete(handle_unregister))
+            .route("/api/v1/refresh/:id", post(handle_refresh))
+            .route("/api/v1/health", get(handle_health))
+            .layer(
+                CorsLayer::new()
+                    .allow_origin(Any)
+                    .allow_methods(Any)
+                    .allow_headers(Any),
+            )
+            .layer(TraceLayer::new_for_http())
+            .with_state(state)
+    }
+
+    /// Start the server
+    pub async fn serve(&self, addr: &str) -> Result<()> {
+        let addr: SocketAddr = addr
+            .parse()
+            .map_err(|e| crate::error::DiscoveryError::Other(format!("Invalid address: {}", e)))?;
+
+        info!("Rendezvous server listening on {}", addr);
+
+        let router = self.router();
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|e| crate::error::DiscoveryError::Io(e))?;
+
+        axum::serve(listener, router)
+            .await
+            .map_err(|e| crate::error::DiscoveryError::Other(format!("Server error: {}", e)))
+    }
+}
+
+impl Default for RendezvousServer {
+    fn default() -> Self {
+        Self::new(RendezvousConfig::default())
+    }
+}
+
+// === HTTP Handlers ===
+
+async fn handle_register(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(registration): Json<DeviceRegistration>,
+) -> std::result::Result<(StatusCode, Json<RegistrationResponse>), (StatusCode, String)> {
+    debug!("Registering device: {}", registration.name);
+
+    let caller = state.authenticate(&headers)?;
+    if !auth::allows_tags(&caller, Action::Write, &registration.tags) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Token does not allow registering with these tags".to_string(),
+        ));
+    }
+
+    match state.register(registration).await {
+        Ok(response) => Ok((StatusCode::CREATED, Json(response))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+async fn handle_discover(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Query(query): Query<DiscoverQuery>,
+) -> std::result::Result<Json<Vec<RegisteredDevice>>, (StatusCode, String)> {
+    debug!(
+        "Discovery query: tag={:?}, feature={:?}",
+        query.tag, query.feature
+    );
+
+    let caller = state.authenticate(&headers)?;
+    if let Some(ref tag) = query.tag {
+        if !auth::allows_tags(&caller, Action::Read, std::slice::from_ref(tag)) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                format!("Token does not allow discovering tag '{}'", tag),
+            ));
+        }
+    }
+
+    state
+        .discover(&query, &caller)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn handle_unregister(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> StatusCode {
+    let caller = match state.authenticate(&headers) {
+        Ok(caller) => caller,
+        Err((status, _)) => return status,
+    };
+    if let Err(status) = state.authorize_device(&caller, &id).await {
+        return status;
+    }
+
+    match state.unregister(&id).await {
+        Ok(true) => {
+            debug!("Unregistered device: {}", id);
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn handle_refresh(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> StatusCode {
+    let caller = match state.authenticate(&headers) {
+        Ok(caller) => caller,
+        Err((status, _)) => return status,
+    };
+    if let Err(status) = state.authorize_device(&caller, &id).await {
+        return status;
+    }
+
+    match state.refresh(&id).await {
+        Ok(true) => {
+            debug!("Refreshed device: {}", id);
+            StatusCode::OK
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn handle_health() -> &'static str {
+    "OK"
+}
+
+// === Client ===
+
+/// Rendezvous client for device registration and discovery
+#[derive(Clone)]
+pub struct RendezvousClient {
+    base_url: String,
+    client: reqwest::Client,
+    token: Option<String>,
+}
+
+impl RendezvousClient {
+    /// Create a new rendezvous client
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            token: None,
+        }
+    }
+
+    /// Authenticate requests with a bearer token (CPSK or external)
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match self.token {
+            Some(ref token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Register this device with the rendezvous server
+    pub async fn register(
+        &self,
+        registration: DeviceRegistration,
+    ) -> std::result::Result<RegistrationResponse, reqwest::Error> {
+        let url = format!("{}/api/v1/register", self.base_url);
+        self.request(reqwest::Method::POST, &url)
+            .json(&registration)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Discover devices from the rendezvous server
+    pub async fn discover(
+        &self,
+        tag: Option<&str>,
+    ) -> std::result::Result<Vec<RegisteredDevice>, reqwest::Error> {
+        let mut url = format!("{}/api/v1/discover", self.base_url);
+        if let Some(t) = tag {
+            url = format!("{}?tag={}", url, t);
+        }
+        self.request(reqwest::Method::GET, &url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Unregister a device
+    pub async fn unregister(&self, id: &str) -> std::result::Result<bool, reqwest::Error> {
+        let url = format!("{}/api/v1/unregister/{}", self.base_url, id);
+        let response = self.request(reqwest::Method::DELETE, &url).send().await?;
+        Ok(response.status().is_success())
+    }
+
+    /// Refresh registration (extend TTL)
+    pub async fn refresh(&self, id: &str) -> std::result::Result<bool, reqwest::Error> {
+        let url = format!("{}/api/v1/refresh/{}", self.base_url, id);
+        let response = self.request(reqwest::Method::POST, &url).send().await?;
+        Ok(response.status().is_success())
+    }
+}
+
+// === Discovery Backend ===
+
+/// Registration keepalive state
+struct RendezvousKeepalive {
+    client: RendezvousClient,
+    registration: DeviceRegistration,
+    device_id: parking_lot::RwLock<Option<String>>,
+    refresh_interval: Duration,
+}
+
+impl RendezvousKeepalive {
+    async fn register(&self) -> Result<()> {
+        let response = self
+            .client
+            .register(self.registration.clone())
+            .await
+            .map_err(|e| DiscoveryError::Other(format!("Rendezvous registration failed: {}", e)))?;
+
+        *self.device_id.write() = Some(response.id);
+        info!("Registered with rendezvous server (TTL: {}s)", response.ttl);
+        Ok(())
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        let device_id: Option<String> = self.device_id.read().clone();
+        if let Some(ref id) = device_id {
+            let success =
+                self.client.refresh(id).await.map_err(|e| {
+                    DiscoveryError::Other(format!("Rendezvous refresh failed: {}", e))
+                })?;
+
+            if !success {
+                // Device was removed, re-register
+                warn!("Rendezvous registration expired, re-registering");
+                *self.device_id.write() = None;
+                self.register().await?;
+            }
+            Ok(())
+        } else {
+            // Not registered yet, register now
+            self.register().await
+        }
+    }
+
+    async fn unregister(&self) {
+        let device_id: Option<String> = self.device_id.write().take();
+        if let Some(ref id) = device_id {
+            let _ = self.client.unregister(id).await;
+            info!("Unregistered from rendezvous server");
+        }
+    }
+
+    /// Start the keepalive loop
+    fn start(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            // Initial registration
+            if let Err(e) = self.register().await {
+                tracing::error!("Initial rendezvous registration failed: {}", e);
+            }
+
+            // Refresh loop
+            let mut interval = tokio::time::interval(self.refresh_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh().await {
+                    warn!("Rendezvous refresh failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Rendezvous (WAN) discovery backend
+///
+/// Browsing queries the server once; announcing registers this device and
+/// keeps the registration alive until [`DiscoveryBackend::stop`].
+pub struct RendezvousBackend {
+    client: RendezvousClient,
+    tag: Option<String>,
+    refresh_interval: Duration,
+    keepalive: parking_lot::Mutex<Option<(Arc<RendezvousKeepalive>, tokio::task::JoinHandle<()>)>>,
+}
+
+impl RendezvousBackend {
+    /// Create a backend for the server at `url`, optionally filtering
+    /// discovery results by `tag`
+    pub fn new(url: &str, tag: Option<String>, refresh_interval: Duration) -> Self {
+        Self {
+            client: RendezvousClient::new(url),
+            tag,
+            refresh_interval,
+            keepalive: parking_lot::Mutex::new(None),
+        }
+    }
+
+    /// Authenticate with the server using a bearer token
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.client = self.client.with_token(token);
+        self
+    }
+
+    /// Register with the server and start the keepalive loop, replacing any
+    /// previous registration
+    pub fn register(&self, registration: DeviceRegistration) {
+        let keepalive = Arc::new(RendezvousKeepalive {
+            client: self.client.clone(),
+            registration,
+            device_id: parking_lot::RwLock::new(None),
+            refresh_interval: self.refresh_interval,
+        });
+        let task = keepalive.clone().start();
+
+        if let Some((_, previous)) = self.keepalive.lock().replace((keepalive, task)) {
+            previous.abort();
+        }
+    }
+
+    /// Query the server for registered devices
+    pub async fn discover(&self) -> Result<Vec<Device>> {
+        let registered = self
+            .client
+            .discover(self.tag.as_deref())
+            .await
+            .map_err(|e| DiscoveryError::Other(format!("Rendezvous discovery failed: {}", e)))?;
+
+        Ok(registered.into_iter().map(Device::from).collect())
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for RendezvousBackend {
+    fn name(&self) -> &str {
+        "rendezvous"
+    }
+
+    async fn stop(&self) -> Result<()> {
+        let keepalive = self.keepalive.lock().take();
+        if let Some((keepalive, task)) = keepalive {
+            task.abort();
+            keepalive.unregister().await;
+        }
+        Ok(())
+    }
+
+    async fn announce(&self, announcement: &Announcement) -> Result<()> {
+        self.register(DeviceRegistration {
+            name: announcement.name.clone(),
+            public_key: None,
+            features: announcement.features.clone(),
+            endpoints: announcement.endpoints.clone(),
+            tags: announcement.tags.clone(),
+            metadata: announcement.metadata.clone(),
+        });
+        Ok(())
+    }
+
+    async fn browse(&self, tx: mpsc::Sender<DiscoveryEvent>) -> Result<()> {
+        match self.discover().await {
+            Ok(devices) => {
+                for device in devices {
+                    if tx.send(DiscoveryEvent::Found(device)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Err(e) => {
+                let _ = tx.send(DiscoveryEvent::Error(e.to_string())).await;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl From<RegisteredDevice> for Device {
+    fn from(rd: RegisteredDevice) -> Self {
+        let mut meta = rd.metadata;
+        // Add tags to metadata
+        if !rd.tags.is_empty() {
+            meta.insert("tags".to_string(), rd.tags.join(","));
+        }
+
+        let info = DeviceInfo {
+            version: clasp_core::PROTOCOL_VERSION,
+            features: rd.features,
+            bridge: false,
+            bridge_protocol: None,
+            meta,
+        };
+
+        let now = Instant::now();
+        Device {
+            id: rd.id,
+            name: rd.name,
+            info,
+            endpoints: rd.endpoints,
+            discovered_at: now,
+            last_seen: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_registration_default() {
+        let reg = DeviceRegistration::default();
+        assert_eq!(reg.name, "CLASP Device");
+        assert!(reg.endpoints.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_state_register() {
+        let state = ServerState::new(
+            RendezvousConfig::default(),
+            Arc::new(MemoryStore::new()),
+            None,
+        );
+        let registration = DeviceRegistration {
+            name: "Test Device".to_string(),
+            endpoints: [("ws".to_string(), "ws://localhost:7330".to_string())].into(),
+            ..Default::default()
+        };
+
+        let response = state.register(registration).await.unwrap();
+        assert!(!response.id.is_empty());
+        assert!(response.ttl > 0);
+    }
+
+    #[tokio::test]
+    async fn test_server_state_discover() {
+        let state = ServerState::new(
+            RendezvousConfig::default(),
+            Arc::new(MemoryStore::new()),
+            None,
+        );
+
+        // Register two devices with different tags
+        state
+            .register(DeviceRegistration {
+                name: "Studio Device".to_string(),
+                tags: vec!["studio".to_string()],
+                endpoints: [("ws".to_string(), "ws://studio:7330".to_string())].into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        state
+            .register(DeviceRegistration {
+                name: "Live Device".to_string(),
+                tags: vec!["live".to_string()],
+                endpoints: [("ws".to_string(), "ws://live:7330".to_string())].into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // Discover all
+        let all = state
+            .discover(
+                &DiscoverQuery {
+                    tag: None,
+                    feature: None,
+                    limit: None,
+                },
+                &None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+
+        // Discover by tag
+        let studio = state
+            .discover(
+                &DiscoverQuery {
+                    tag: Some("studio".to_string()),
+                    feature: None,
+                    limit: None,
+                },
+                &None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(studio.len(), 1);
+        assert_eq!(studio[0].name, "Studio Device");
+    }
+
+    #[tokio::test]
+    async fn test_server_state_unregister() {
+        let state = ServerState::new(
+            RendezvousConfig::default(),
+            Arc::new(MemoryStore::new()),
+            None,
+        );
+        let response = state.register(DeviceRegistration::default()).await.unwrap();
+
+        assert!(state.unregister(&response.id).await.unwrap());
+        assert!(!state.unregister(&response.id).await.unwrap()); // Already removed
+    }
+
+    #[tokio::test]
+    async fn test_server_state_refresh() {
+        let state = ServerState::new(
+            RendezvousConfig::default(),
+            Arc::new(MemoryStore::new()),
+            None,
+        );
+        let response = state.register(DeviceRegistration::default()).await.unwrap();
+
+        assert!(state.refresh(&response.id).await.unwrap());
+        assert!(!state.refresh("nonexistent").await.unwrap());
+    }
+}
@@ -0,0 +1,354 @@
+//! Registration storage for the rendezvous server
+//!
+//! [`MemoryStore`] keeps registrations in-process and is the default.
+//! [`RedisStore`] (feature `rendezvous-redis`) persists them in Redis so they
+//! survive restarts and can be shared by several rendezvous instances behind
+//! a load balancer. Expiry is delegated to the store: Redis keys carry the
+//! registration TTL natively.
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+use super::RegisteredDevice;
+use crate::error::Result;
+
+/// Backing store for rendezvous registrations
+#[async_trait]
+pub trait RendezvousStore: Send + Sync {
+    /// Insert or replace a registration that expires after `ttl`
+    async fn put(&self, device: RegisteredDevice, ttl: Duration) -> Result<()>;
+
+    /// Extend a registration's expiry and update its last-seen time.
+    /// Returns false if the registration is unknown or already expired.
+    async fn refresh(&self, id: &str, ttl: Duration) -> Result<bool>;
+
+    /// Get an unexpired registration by ID
+    async fn get(&self, id: &str) -> Result<Option<RegisteredDevice>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|device| device.id == id))
+    }
+
+    /// Remove a registration. Returns false if it did not exist.
+    async fn remove(&self, id: &str) -> Result<bool>;
+
+    /// All unexpired registrations
+    async fn list(&self) -> Result<Vec<RegisteredDevice>>;
+
+    /// Number of unexpired registrations
+    async fn len(&self) -> Result<usize> {
+        Ok(self.list().await?.len())
+    }
+
+    /// Check if there are no unexpired registrations
+    async fn is_empty(&self) -> Result<bool> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Drop expired registrations and return how many were removed.
+    /// Stores with native expiry may leave this as a no-op.
+    async fn cleanup_expired(&self) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+/// In-memory entry with its expiry deadline
+struct MemoryEntry {
+    device: RegisteredDevice,
+    expires_at: Instant,
+}
+
+/// In-process registration store (lost on restart)
+#[derive(Default)]
+pub struct MemoryStore {
+    devices: DashMap<String, MemoryEntry>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RendezvousStore for MemoryStore {
+    async fn put(&self, device: RegisteredDevice, ttl: Duration) -> Result<()> {
+        self.devices.insert(
+            device.id.clone(),
+            MemoryEntry {
+                device,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+        Ok(())
+    }
+
+    async fn refresh(&self, id: &str, ttl: Duration) -> Result<bool> {
+        let now = Instant::now();
+        match self.devices.get_mut(id) {
+            Some(mut entry) if entry.expires_at > now => {
+                entry.expires_at = now + ttl;
+                entry.device.last_seen = clasp_core::time::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<RegisteredDevice>> {
+        let now = Instant::now();
+        Ok(self
+            .devices
+            .get(id)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.device.clone()))
+    }
+
+    async fn remove(&self, id: &str) -> Result<bool> {
+        Ok(self.devices.remove(id).is_some())
+    }
+
+    async fn list(&self) -> Result<Vec<RegisteredDevice>> {
+        let now = Instant::now();
+        Ok(self
+            .devices
+            .iter()
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.device.clone())
+            .collect())
+    }
+
+    async fn len(&self) -> Result<usize> {
+        let now = Instant::now();
+        Ok(self
+            .devices
+            .iter()
+            .filter(|entry| entry.expires_at > now)
+            .count())
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        let now = Instant::now();
+        let before = self.devices.len();
+        self.devices.retain(|_, entry| entry.expires_at > now);
+        Ok(before - self.devices.len())
+    }
+}
+
+/// Redis-backed registration store
+///
+/// Each registration is a JSON string under `{prefix}:device:{id}` with the
+/// TTL set as the key's expiry. A set at `{prefix}:devices` indexes the IDs
+/// and is pruned lazily as keys expire.
+#[cfg(feature = "rendezvous-redis")]
+pub struct RedisStore {
+    conn: redis::aio::ConnectionManager,
+    prefix: String,
+}
+
+#[cfg(feature = "rendezvous-redis")]
+impl RedisStore {
+    /// Default key prefix
+    pub const DEFAULT_PREFIX: &'static str = "clasp:rendezvous";
+
+    /// Connect to Redis (e.g. "redis://127.0.0.1/")
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).map_err(store_error)?;
+        let conn = redis::aio::ConnectionManager::new(client)
+            .await
+            .map_err(store_error)?;
+        Ok(Self {
+            conn,
+            prefix: Self::DEFAULT_PREFIX.to_string(),
+        })
+    }
+
+    /// Use a custom key prefix (to share one Redis between deployments)
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    fn device_key(&self, id: &str) -> String {
+        format!("{}:device:{}", self.prefix, id)
+    }
+
+    fn index_key(&self) -> String {
+        format!("{}:devices", self.prefix)
+    }
+
+    /// Load all live registrations, pruning expired IDs from the index
+    async fn load(&self) -> Result<(Vec<RegisteredDevice>, usize)> {
+        let mut conn = self.conn.clone();
+        let ids: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(self.index_key())
+            .query_async(&mut conn)
+            .await
+            .map_err(store_error)?;
+        if ids.is_empty() {
+            return Ok((Vec::new(), 0));
+        }
+
+        let keys: Vec<String> = ids.iter().map(|id| self.device_key(id)).collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut conn)
+            .await
+            .map_err(store_error)?;
+
+        let mut devices = Vec::with_capacity(values.len());
+        let mut expired = Vec::new();
+        for (id, value) in ids.into_iter().zip(values) {
+            match value.and_then(|json| serde_json::from_str(&json).ok()) {
+                Some(device) => devices.push(device),
+                None => expired.push(id),
+            }
+        }
+
+        if !expired.is_empty() {
+            let _: () = redis::cmd("SREM")
+                .arg(self.index_key())
+                .arg(&expired)
+                .query_async(&mut conn)
+                .await
+                .map_err(store_error)?;
+        }
+
+        Ok((devices, expired.len()))
+    }
+}
+
+#[cfg(feature = "rendezvous-redis")]
+#[async_trait]
+impl RendezvousStore for RedisStore {
+    async fn put(&self, device: RegisteredDevice, ttl: Duration) -> Result<()> {
+        let json = serde_json::to_string(&device).map_err(store_error)?;
+        let mut conn = self.conn.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(self.device_key(&device.id))
+            .arg(json)
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .ignore()
+            .cmd("SADD")
+            .arg(self.index_key())
+            .arg(&device.id)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(store_error)?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<RegisteredDevice>> {
+        let mut conn = self.conn.clone();
+        let json: Option<String> = redis::cmd("GET")
+            .arg(self.device_key(id))
+            .query_async(&mut conn)
+            .await
+            .map_err(store_error)?;
+        Ok(json.and_then(|j| serde_json::from_str(&j).ok()))
+    }
+
+    async fn refresh(&self, id: &str, ttl: Duration) -> Result<bool> {
+        let Some(mut device) = self.get(id).await? else {
+            return Ok(false);
+        };
+        device.last_seen = clasp_core::time::now();
+        self.put(device, ttl).await?;
+        Ok(true)
+    }
+
+    async fn remove(&self, id: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let (removed,): (usize,) = redis::pipe()
+            .atomic()
+            .cmd("DEL")
+            .arg(self.device_key(id))
+            .cmd("SREM")
+            .arg(self.index_key())
+            .arg(id)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(store_error)?;
+        Ok(removed > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<RegisteredDevice>> {
+        Ok(self.load().await?.0)
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        Ok(self.load().await?.1)
+    }
+}
+
+#[cfg(feature = "rendezvous-redis")]
+fn store_error(e: impl std::fmt::Display) -> crate::error::DiscoveryError {
+    crate::error::DiscoveryError::Store(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str) -> RegisteredDevice {
+        RegisteredDevice {
+            id: id.to_string(),
+            name: id.to_string(),
+            public_key: None,
+            features: vec![],
+            endpoints: Default::default(),
+            tags: vec![],
+            metadata: Default::default(),
+            registered_at: 0,
+            last_seen: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_expiry() {
+        let store = MemoryStore::new();
+        store
+            .put(device("short"), Duration::from_millis(10))
+            .await
+            .unwrap();
+        store
+            .put(device("long"), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.len().await.unwrap(), 2);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(store.len().await.unwrap(), 1);
+        assert!(!store
+            .refresh("short", Duration::from_secs(60))
+            .await
+            .unwrap());
+        assert_eq!(store.cleanup_expired().await.unwrap(), 1);
+
+        let devices = store.list().await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id, "long");
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_refresh_updates_last_seen() {
+        let store = MemoryStore::new();
+        store
+            .put(device("a"), Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert!(store.refresh("a", Duration::from_secs(60)).await.unwrap());
+        assert!(store.list().await.unwrap()[0].last_seen > 0);
+        assert!(store.remove("a").await.unwrap());
+        assert!(!store.remove("a").await.unwrap());
+    }
+}
@@ -4,11 +4,16 @@
 //! - Device struct creation and management
 //! - DeviceInfo configuration
 //! - Discovery struct operations
+//! - Custom discovery backends
 //! - UDP broadcast discovery
 //! - Note: mDNS tests require network access and are marked as such
 
-use clasp_discovery::{Device, DeviceInfo, Discovery, DiscoveryConfig, DiscoveryEvent};
+use async_trait::async_trait;
+use clasp_discovery::{
+    Announcement, Device, DeviceInfo, Discovery, DiscoveryBackend, DiscoveryConfig, DiscoveryEvent,
+};
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 // ============================================================================
@@ -263,6 +268,114 @@ async fn test_discovery_overwrite_device() {
     );
 }
 
+// ============================================================================
+// Custom Backend Tests
+// ============================================================================
+
+/// Backend that reports a fixed device list and records announcements
+struct StaticBackend {
+    devices: Vec<Device>,
+    announced: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl DiscoveryBackend for StaticBackend {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    async fn announce(&self, announcement: &Announcement) -> clasp_discovery::Result<()> {
+        self.announced
+            .lock()
+            .unwrap()
+            .push(announcement.name.clone());
+        Ok(())
+    }
+
+    async fn stop(&self) -> clasp_discovery::Result<()> {
+        self.announced.lock().unwrap().clear();
+        Ok(())
+    }
+
+    async fn browse(
+        &self,
+        tx: tokio::sync::mpsc::Sender<DiscoveryEvent>,
+    ) -> clasp_discovery::Result<()> {
+        for device in &self.devices {
+            let _ = tx.send(DiscoveryEvent::Found(device.clone())).await;
+        }
+        Ok(())
+    }
+}
+
+fn lan_disabled() -> DiscoveryConfig {
+    DiscoveryConfig {
+        mdns: false,
+        broadcast: false,
+        timeout: Duration::from_secs(1),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_custom_backend_discover_all() {
+    let backend_a = StaticBackend {
+        devices: vec![
+            Device::new("a".to_string(), "A".to_string()),
+            Device::new("shared".to_string(), "Shared".to_string()),
+        ],
+        announced: Arc::default(),
+    };
+    let backend_b = StaticBackend {
+        devices: vec![Device::new("shared".to_string(), "Shared".to_string())],
+        announced: Arc::default(),
+    };
+
+    let mut discovery = Discovery::with_config(lan_disabled())
+        .with_backend(backend_a)
+        .with_backend(backend_b);
+    assert_eq!(discovery.backends().count(), 2);
+
+    let devices = discovery.discover_all().await.unwrap();
+    assert_eq!(devices.len(), 2, "Devices should be deduplicated by ID");
+    assert!(discovery.get("a").is_some());
+    assert!(discovery.get("shared").is_some());
+}
+
+#[tokio::test]
+async fn test_custom_backend_start_closes_when_done() {
+    let mut discovery = Discovery::with_config(lan_disabled());
+    discovery.add_backend(StaticBackend {
+        devices: vec![Device::new("x".to_string(), "X".to_string())],
+        announced: Arc::default(),
+    });
+
+    let mut rx = discovery.start().await.unwrap();
+    match rx.recv().await {
+        Some(DiscoveryEvent::Found(d)) => assert_eq!(d.id, "x"),
+        other => panic!("Expected Found event, got {:?}", other),
+    }
+    assert!(rx.recv().await.is_none(), "Receiver should close");
+}
+
+#[tokio::test]
+async fn test_custom_backend_announce_and_stop() {
+    let announced = Arc::new(Mutex::new(Vec::new()));
+    let discovery = Discovery::with_config(lan_disabled()).with_backend(StaticBackend {
+        devices: vec![],
+        announced: announced.clone(),
+    });
+
+    discovery
+        .announce(&Announcement::new("My Router", 7330))
+        .await
+        .unwrap();
+    assert_eq!(*announced.lock().unwrap(), vec!["My Router".to_string()]);
+
+    discovery.stop().await.unwrap();
+    assert!(announced.lock().unwrap().is_empty());
+}
+
 // ============================================================================
 // DiscoveryEvent Tests
 // ============================================================================
@@ -513,4 +513,128 @@ mod tests {
 
         server_handle.abort();
     }
+
+    /// Helper to create a CPSK validator from (token, scope) pairs
+    fn cpsk_validator(tokens: &[(&str, &str)]) -> clasp_core::security::CpskValidator {
+        use clasp_core::security::{CpskValidator, Scope, TokenInfo};
+
+        let validator = CpskValidator::new();
+        for (token, scope) in tokens {
+            validator.register(
+                token.to_string(),
+                TokenInfo::new(token.to_string(), vec![Scope::parse(scope).unwrap()]),
+            );
+        }
+        validator
+    }
+
+    /// Test: Authenticated server rejects anonymous and out-of-scope requests
+    #[tokio::test]
+    async fn test_auth_required() {
+        let port = find_available_port().await;
+        let addr = format!("127.0.0.1:{}", port);
+
+        let validator = cpsk_validator(&[
+            ("cpsk_studio", "write:/rendezvous/tags/studio"),
+            ("cpsk_admin", "admin:/rendezvous/**"),
+        ]);
+
+        let server = RendezvousServer::new(RendezvousConfig::default()).with_validator(validator);
+        let addr_clone = addr.clone();
+        let server_handle = tokio::spawn(async move {
+            let _ = server.serve(&addr_clone).await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let url = format!("http://{}", addr);
+        let anonymous = RendezvousClient::new(&url);
+        let studio = RendezvousClient::new(&url).with_token("cpsk_studio");
+        let admin = RendezvousClient::new(&url).with_token("cpsk_admin");
+
+        // No token / bad token
+        assert!(anonymous
+            .register(make_test_device("Anon", "studio"))
+            .await
+            .is_err());
+        assert!(anonymous.discover(None).await.is_err());
+        assert!(RendezvousClient::new(&url)
+            .with_token("cpsk_bogus")
+            .discover(None)
+            .await
+            .is_err());
+
+        // Write scope is limited to the studio tag
+        let response = studio
+            .register(make_test_device("Studio1", "studio"))
+            .await
+            .unwrap();
+        assert!(studio
+            .register(make_test_device("Live1", "live"))
+            .await
+            .is_err());
+
+        // Write implies read, but only for the studio tag
+        assert_eq!(studio.discover(Some("studio")).await.unwrap().len(), 1);
+        assert!(studio.discover(Some("live")).await.is_err());
+
+        // Only callers with write on the device's tags can refresh/unregister
+        let other = admin
+            .register(make_test_device("Live1", "live"))
+            .await
+            .unwrap();
+        assert!(!studio.refresh(&other.id).await.unwrap());
+        assert!(!studio.unregister(&other.id).await.unwrap());
+        assert!(studio.refresh(&response.id).await.unwrap());
+
+        let devices = admin.discover(None).await.unwrap();
+        assert_eq!(devices.len(), 2);
+
+        assert!(studio.unregister(&response.id).await.unwrap());
+
+        server_handle.abort();
+    }
+
+    /// Test: Discovery only returns devices in the token's readable tags
+    #[tokio::test]
+    async fn test_tag_scoped_discovery() {
+        let port = find_available_port().await;
+        let addr = format!("127.0.0.1:{}", port);
+
+        let validator = cpsk_validator(&[
+            ("cpsk_writer", "write:/rendezvous/**"),
+            ("cpsk_reader", "read:/rendezvous/tags/studio"),
+        ]);
+
+        let server = RendezvousServer::new(RendezvousConfig::default()).with_validator(validator);
+        let addr_clone = addr.clone();
+        let server_handle = tokio::spawn(async move {
+            let _ = server.serve(&addr_clone).await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let url = format!("http://{}", addr);
+        let writer = RendezvousClient::new(&url).with_token("cpsk_writer");
+        let reader = RendezvousClient::new(&url).with_token("cpsk_reader");
+
+        writer
+            .register(make_test_device("Studio1", "studio"))
+            .await
+            .unwrap();
+        writer
+            .register(make_test_device("Live1", "live"))
+            .await
+            .unwrap();
+
+        // Unfiltered discovery is narrowed to readable tags
+        let devices = reader.discover(None).await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "Studio1");
+
+        // Explicitly asking for an unreadable tag is forbidden
+        assert!(reader.discover(Some("live")).await.is_err());
+
+        server_handle.abort();
+    }
 }
@@ -1391,6 +1391,7 @@ pub mod server {
 
             offset += encode_string(&mut self.tx_buf[offset..], "embedded");
             offset += encode_string(&mut self.tx_buf[offset..], "MiniRouter");
+            offset += encode_string(&mut self.tx_buf[offset..], ""); // no token
 
             let payload_len = offset - payload_start;
             encode_header(&mut self.tx_buf, 0, payload_len);
@@ -0,0 +1,314 @@
+//! # CLASP Macros
+//!
+//! Compile-checked address constructors for CLASP namespaces.
+//!
+//! Addresses are plain strings on the wire, so a typo in
+//! `"/lumen/scene/0/layr/3/opacity"` only shows up at runtime as a value
+//! nobody receives. [`clasp_addresses!`] describes a namespace once and
+//! generates a typed constructor for every address in it:
+//!
+//! ```
+//! use clasp_macros::clasp_addresses;
+//!
+//! clasp_addresses! {
+//!     lumen {
+//!         scene(u8) {
+//!             layer(u8) {
+//!                 opacity,
+//!                 #[segment = "blend-mode"]
+//!                 blend_mode,
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let address = lumen().scene(0).layer(3).opacity();
+//! assert_eq!(address, "/lumen/scene/0/layer/3/opacity");
+//! assert_eq!(lumen().scene(1).layer(0).blend_mode(), "/lumen/scene/1/layer/0/blend-mode");
+//! ```
+//!
+//! The generated paths dereference to `&str`, so they go straight into the
+//! client: `client.set(&lumen().scene(0).layer(3).opacity(), 0.5)`.
+//! `clasp-client` re-exports the macro.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parenthesized, parse_macro_input, Attribute, Expr, Ident, Lit, Token, Type};
+
+/// Generate typed constructors for a tree of addresses
+///
+/// Each node is an address segment, written as an identifier. A node with a
+/// type in parentheses takes a parameter that becomes the following segment,
+/// so `scene(u8)` covers `/scene/0` through `/scene/255`. Children go in
+/// braces, separated by optional commas. `#[segment = "..."]` spells a
+/// segment that isn't a valid identifier, and doc comments are carried over
+/// to the generated methods.
+///
+/// Every top-level node becomes a function and a module of the same name.
+/// Every node's module holds a `Path` type for its address, with a method
+/// per child; `lumen().scene(0)` is a `lumen::scene::Path`. Parameter
+/// types are formatted with `Display` and resolved from the macro's call
+/// site.
+#[proc_macro]
+pub fn clasp_addresses(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as Tree).expand().into()
+}
+
+/// The macro input: a list of top-level nodes
+struct Tree {
+    roots: Vec<Node>,
+}
+
+/// One address segment, its optional parameter and its children
+struct Node {
+    name: Ident,
+    segment: String,
+    param: Option<Type>,
+    docs: Vec<Attribute>,
+    children: Vec<Node>,
+}
+
+impl Parse for Tree {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Tree {
+            roots: parse_nodes(input)?,
+        })
+    }
+}
+
+fn parse_nodes(input: ParseStream) -> syn::Result<Vec<Node>> {
+    let mut nodes: Vec<Node> = Vec::new();
+    while !input.is_empty() {
+        let node: Node = input.parse()?;
+        if let Some(existing) = nodes
+            .iter()
+            .find(|n| n.name == node.name || n.segment == node.segment)
+        {
+            let what = if existing.name == node.name {
+                format!("duplicate address node `{}`", node.name.unraw())
+            } else {
+                format!("duplicate address segment `{}`", node.segment)
+            };
+            return Err(syn::Error::new(node.name.span(), what));
+        }
+        nodes.push(node);
+        if !input.is_empty() && input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok(nodes)
+}
+
+impl Parse for Node {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut docs = Vec::new();
+        let mut rename = None;
+        for attr in input.call(Attribute::parse_outer)? {
+            if attr.path().is_ident("doc") {
+                docs.push(attr);
+            } else if attr.path().is_ident("segment") {
+                let value = &attr.meta.require_name_value()?.value;
+                match value {
+                    Expr::Lit(expr) => match &expr.lit {
+                        Lit::Str(lit) => rename = Some((lit.value(), lit.span())),
+                        other => return Err(syn::Error::new(other.span(), "expected a string")),
+                    },
+                    other => return Err(syn::Error::new_spanned(other, "expected a string")),
+                }
+            } else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "only doc comments and #[segment = \"...\"] are allowed here",
+                ));
+            }
+        }
+
+        let name = input.call(Ident::parse_any)?;
+        let (segment, segment_span) =
+            rename.unwrap_or_else(|| (name.unraw().to_string(), name.span()));
+        validate_segment(&segment, segment_span)?;
+
+        let param = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Some(content.parse()?)
+        } else {
+            None
+        };
+
+        let children = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            parse_nodes(&content)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Node {
+            name,
+            segment,
+            param,
+            docs,
+            children,
+        })
+    }
+}
+
+/// Reject segments that would change the address structure or read as a
+/// wildcard
+fn validate_segment(segment: &str, span: Span) -> syn::Result<()> {
+    if segment.is_empty() {
+        return Err(syn::Error::new(span, "address segments can't be empty"));
+    }
+    if let Some(c) = segment
+        .chars()
+        .find(|c| matches!(c, '/' | '*' | '?' | '[' | ']' | '{' | '}') || c.is_whitespace())
+    {
+        return Err(syn::Error::new(
+            span,
+            format!("`{}` isn't allowed in an address segment", c),
+        ));
+    }
+    Ok(())
+}
+
+impl Tree {
+    fn expand(&self) -> TokenStream2 {
+        let mut tokens = TokenStream2::new();
+        for root in &self.roots {
+            let constructor = root.constructor(None, "");
+            let module = root.module("");
+            tokens.extend(quote! {
+                #constructor
+                #module
+            });
+        }
+        tokens
+    }
+}
+
+impl Node {
+    /// The address template shown in docs, e.g. `/scene/{scene}`
+    fn template(&self, parent: &str) -> String {
+        match &self.param {
+            Some(_) => format!("{}/{}/{{{}}}", parent, self.segment, self.name.unraw()),
+            None => format!("{}/{}", parent, self.segment),
+        }
+    }
+
+    /// The function (for a root) or method (for a child) building this
+    /// node's path; `parent` is the expression holding the parent address
+    fn constructor(&self, parent: Option<TokenStream2>, parent_template: &str) -> TokenStream2 {
+        let name = &self.name;
+        let docs = &self.docs;
+        let default_doc = if docs.is_empty() {
+            let doc = format!("`{}`", self.template(parent_template));
+            quote! { #[doc = #doc] }
+        } else {
+            TokenStream2::new()
+        };
+        let receiver = parent.as_ref().map(|_| quote! { &self, });
+        let prefix = parent.unwrap_or_else(|| quote! { "" });
+        let segment = &self.segment;
+
+        match &self.param {
+            Some(ty) => {
+                let arg = format_ident!("{}", name.unraw(), span = name.span());
+                quote! {
+                    #(#docs)*
+                    #default_doc
+                    #[allow(dead_code)]
+                    pub fn #name(#receiver #arg: #ty) -> #name::Path {
+                        #name::Path(::std::format!("{}/{}/{}", #prefix, #segment, #arg))
+                    }
+                }
+            }
+            None => quote! {
+                #(#docs)*
+                #default_doc
+                #[allow(dead_code)]
+                pub fn #name(#receiver) -> #name::Path {
+                    #name::Path(::std::format!("{}/{}", #prefix, #segment))
+                }
+            },
+        }
+    }
+
+    /// The module holding this node's `Path` type and its children
+    fn module(&self, parent: &str) -> TokenStream2 {
+        let name = &self.name;
+        let template = self.template(parent);
+        let module_doc = format!("Addresses under `{}`", template);
+        let path_doc = format!("The address `{}`", template);
+
+        let methods = self
+            .children
+            .iter()
+            .map(|child| child.constructor(Some(quote! { self.0 }), &template));
+        let modules = self.children.iter().map(|child| child.module(&template));
+        quote! {
+            #[doc = #module_doc]
+            #[allow(dead_code)]
+            pub mod #name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #[doc = #path_doc]
+                #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+                pub struct Path(pub(super) ::std::string::String);
+
+                impl Path {
+                    /// The address as a string slice
+                    pub fn as_str(&self) -> &str {
+                        &self.0
+                    }
+
+                    #(#methods)*
+                }
+
+                impl ::std::ops::Deref for Path {
+                    type Target = str;
+
+                    fn deref(&self) -> &str {
+                        &self.0
+                    }
+                }
+
+                impl ::std::convert::AsRef<str> for Path {
+                    fn as_ref(&self) -> &str {
+                        &self.0
+                    }
+                }
+
+                impl ::std::fmt::Display for Path {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        f.write_str(&self.0)
+                    }
+                }
+
+                impl ::std::convert::From<Path> for ::std::string::String {
+                    fn from(path: Path) -> Self {
+                        path.0
+                    }
+                }
+
+                impl ::std::cmp::PartialEq<str> for Path {
+                    fn eq(&self, other: &str) -> bool {
+                        self.0 == other
+                    }
+                }
+
+                impl ::std::cmp::PartialEq<&str> for Path {
+                    fn eq(&self, other: &&str) -> bool {
+                        self.0 == *other
+                    }
+                }
+
+                #(#modules)*
+            }
+        }
+    }
+}
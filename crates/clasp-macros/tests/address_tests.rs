@@ -0,0 +1,85 @@
+//! Typed address tests (`clasp_addresses!`)
+
+use clasp_macros::clasp_addresses;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Left => f.write_str("left"),
+            Side::Right => f.write_str("right"),
+        }
+    }
+}
+
+clasp_addresses! {
+    lumen {
+        scene(u8) {
+            layer(u8) {
+                /// Layer opacity, 0.0 to 1.0
+                opacity
+                #[segment = "blend-mode"]
+                blend_mode
+            }
+        },
+        master,
+    }
+
+    mixer {
+        channel(u16) { gain, mute, pan(Side) }
+        r#type
+    }
+
+    cue(&str)
+}
+
+#[test]
+fn test_nested_addresses() {
+    assert_eq!(lumen(), "/lumen");
+    assert_eq!(lumen().scene(2), "/lumen/scene/2");
+    assert_eq!(
+        lumen().scene(0).layer(3).opacity(),
+        "/lumen/scene/0/layer/3/opacity"
+    );
+    assert_eq!(lumen().master().as_str(), "/lumen/master");
+}
+
+#[test]
+fn test_segment_spellings() {
+    assert_eq!(
+        lumen().scene(1).layer(0).blend_mode(),
+        "/lumen/scene/1/layer/0/blend-mode"
+    );
+    assert_eq!(mixer().r#type(), "/mixer/type");
+}
+
+#[test]
+fn test_parameter_types() {
+    assert_eq!(
+        mixer().channel(12).pan(Side::Left),
+        "/mixer/channel/12/pan/left"
+    );
+    assert_eq!(
+        mixer().channel(1).pan(Side::Right).to_string(),
+        "/mixer/channel/1/pan/right"
+    );
+    assert_eq!(cue("intro"), "/cue/intro");
+}
+
+#[test]
+fn test_paths_are_distinct_types() {
+    let gain: mixer::channel::gain::Path = mixer().channel(1).gain();
+    let mute: mixer::channel::mute::Path = mixer().channel(1).mute();
+    let as_str: &str = &gain;
+    assert_eq!(as_str, "/mixer/channel/1/gain");
+    assert_eq!(String::from(mute), "/mixer/channel/1/mute");
+
+    let unique: HashSet<_> = (0..4).map(|i| mixer().channel(i % 2).gain()).collect();
+    assert_eq!(unique.len(), 2);
+}
@@ -0,0 +1,500 @@
+//! Starting and stopping protocol adapters at runtime
+//!
+//! The MQTT, OSC and dashboard adapters can be started, reconfigured and
+//! stopped while the router keeps serving its WebSocket/QUIC clients, either
+//! with [`Router::start_adapter`] and [`Router::stop_adapter`] or over the
+//! protocol by clients with `admin` scope on `/$sys/adapters/**`:
+//!
+//! | Message | Effect |
+//! |---------|--------|
+//! | `GET /$sys/adapters` | SNAPSHOT of the running adapters and their config |
+//! | `SET /$sys/adapters/<kind> <config>` | Start the adapter, restarting it if it is running |
+//! | `SET /$sys/adapters/<kind> null` | Stop the adapter and end its sessions |
+//!
+//! `<kind>` is `mqtt`, `osc` or `dashboard`. `<config>` is a bind address
+//! (`"0.0.0.0:9000"`), `true` for the defaults, or a map of config fields
+//! applied over the defaults (`{"bind_addr": "0.0.0.0:9000", "namespace":
+//! "/guest"}`).
+//!
+//! Stopping an adapter closes its listener, disconnects its MQTT clients and
+//! ends the sessions of its OSC peers. Adapters started by
+//! [`Router::serve_all`] can be stopped and restarted the same way.
+//!
+//! [`Router::start_adapter`]: crate::Router::start_adapter
+//! [`Router::stop_adapter`]: crate::Router::stop_adapter
+//! [`Router::serve_all`]: crate::Router::serve_all
+
+use clasp_core::security::TokenValidator;
+use clasp_core::{ParamValue, SnapshotMessage, Value};
+use dashmap::DashMap;
+use futures::future::BoxFuture;
+use parking_lot::{Mutex, RwLock};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::error::{Result, RouterError};
+use crate::features::FeatureStats;
+use crate::health::{self, AdapterStatus};
+use crate::session::{Session, SessionId};
+use crate::state::RouterState;
+use crate::subscription::SubscriptionManager;
+
+/// Address prefix for adapter management
+pub const ADAPTERS_PREFIX: &str = "/$sys/adapters";
+
+/// How long a stopping adapter may take before its task is aborted
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether `address` is an adapter management address
+pub fn is_adapter_address(address: &str) -> bool {
+    address == ADAPTERS_PREFIX
+        || address
+            .strip_prefix(ADAPTERS_PREFIX)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Extract the kind from `/$sys/adapters/<kind>`
+pub(crate) fn kind_from_address(address: &str) -> Option<AdapterKind> {
+    AdapterKind::from_id(address.strip_prefix(ADAPTERS_PREFIX)?.strip_prefix('/')?)
+}
+
+/// A protocol adapter that can run alongside the router's transports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AdapterKind {
+    /// MQTT clients (feature `mqtt-server`)
+    Mqtt,
+    /// OSC over UDP (feature `osc-server`)
+    Osc,
+    /// Web dashboard (feature `dashboard`)
+    Dashboard,
+}
+
+impl AdapterKind {
+    /// Every kind, whether or not it was compiled in
+    pub const ALL: [AdapterKind; 3] = [AdapterKind::Mqtt, AdapterKind::Osc, AdapterKind::Dashboard];
+
+    /// Name used in logs and [`HealthReport::adapters`](crate::HealthReport)
+    pub fn name(self) -> &'static str {
+        match self {
+            AdapterKind::Mqtt => "MQTT",
+            AdapterKind::Osc => "OSC",
+            AdapterKind::Dashboard => "Dashboard",
+        }
+    }
+
+    /// Address segment under `/$sys/adapters`
+    pub fn id(self) -> &'static str {
+        match self {
+            AdapterKind::Mqtt => "mqtt",
+            AdapterKind::Osc => "osc",
+            AdapterKind::Dashboard => "dashboard",
+        }
+    }
+
+    /// Parse an address segment (`mqtt`, `osc` or `dashboard`)
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+}
+
+/// Configuration of an adapter to start
+#[derive(Debug, Clone)]
+pub enum AdapterConfig {
+    /// MQTT server
+    #[cfg(feature = "mqtt-server")]
+    Mqtt(crate::adapters::MqttServerConfig),
+    /// OSC server
+    #[cfg(feature = "osc-server")]
+    Osc(crate::adapters::OscServerConfig),
+    /// Web dashboard
+    #[cfg(feature = "dashboard")]
+    Dashboard(crate::adapters::DashboardConfig),
+}
+
+impl AdapterConfig {
+    /// Which adapter this configures
+    pub fn kind(&self) -> AdapterKind {
+        match *self {
+            #[cfg(feature = "mqtt-server")]
+            AdapterConfig::Mqtt(_) => AdapterKind::Mqtt,
+            #[cfg(feature = "osc-server")]
+            AdapterConfig::Osc(_) => AdapterKind::Osc,
+            #[cfg(feature = "dashboard")]
+            AdapterConfig::Dashboard(_) => AdapterKind::Dashboard,
+        }
+    }
+
+    /// Build the config for a SET on `/$sys/adapters/<kind>`
+    pub(crate) fn from_value(kind: AdapterKind, value: &Value) -> Result<Self> {
+        match kind {
+            #[cfg(feature = "mqtt-server")]
+            AdapterKind::Mqtt => config_from_value(value).map(AdapterConfig::Mqtt),
+            #[cfg(feature = "osc-server")]
+            AdapterKind::Osc => config_from_value(value).map(AdapterConfig::Osc),
+            #[cfg(feature = "dashboard")]
+            AdapterKind::Dashboard => config_from_value(value).map(AdapterConfig::Dashboard),
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = value;
+                Err(RouterError::Config(format!(
+                    "{} adapter not enabled at compile time",
+                    kind.name()
+                )))
+            }
+        }
+    }
+
+    /// The config as a map value
+    fn to_value(&self) -> Value {
+        match *self {
+            #[cfg(feature = "mqtt-server")]
+            AdapterConfig::Mqtt(ref config) => config_value(config),
+            #[cfg(feature = "osc-server")]
+            AdapterConfig::Osc(ref config) => config_value(config),
+            #[cfg(feature = "dashboard")]
+            AdapterConfig::Dashboard(ref config) => config_value(config),
+        }
+    }
+}
+
+/// Apply a SET value (bind address, `true` or a map of fields) over the
+/// default config
+#[cfg(any(feature = "mqtt-server", feature = "osc-server", feature = "dashboard"))]
+fn config_from_value<T>(value: &Value) -> Result<T>
+where
+    T: Default + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let invalid = |msg: String| RouterError::InvalidMessage(msg);
+    let mut config = serde_json::to_value(T::default()).map_err(|e| invalid(e.to_string()))?;
+    match value {
+        Value::Bool(true) => {}
+        Value::String(addr) => config["bind_addr"] = addr.clone().into(),
+        Value::Map(fields) => {
+            for (field, value) in fields {
+                if config.get(field).is_none() {
+                    return Err(invalid(format!("unknown field '{}'", field)));
+                }
+                config[field] = serde_json::to_value(value).map_err(|e| invalid(e.to_string()))?;
+            }
+        }
+        _ => {
+            return Err(invalid(
+                "expected a bind address, true or a map of config fields".to_string(),
+            ))
+        }
+    }
+    serde_json::from_value(config).map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(any(feature = "mqtt-server", feature = "osc-server", feature = "dashboard"))]
+fn config_value<T: serde::Serialize>(config: &T) -> Value {
+    serde_json::to_value(config)
+        .and_then(serde_json::from_value)
+        .unwrap_or(Value::Null)
+}
+
+type StopFn = Box<dyn Fn() + Send + Sync>;
+
+/// An adapter task started by the registry
+struct RunningAdapter {
+    config: AdapterConfig,
+    stop: StopFn,
+    task: JoinHandle<Result<()>>,
+}
+
+impl RunningAdapter {
+    /// Signal the adapter and wait for it to close its listener
+    async fn shut_down(self) {
+        (self.stop)();
+        let mut task = self.task;
+        if tokio::time::timeout(STOP_TIMEOUT, &mut task).await.is_err() {
+            warn!(
+                "{} adapter did not stop within {:?}; aborting it",
+                self.config.kind().name(),
+                STOP_TIMEOUT
+            );
+            task.abort();
+        }
+    }
+}
+
+/// Adapters running alongside the router's transports
+///
+/// Shares the router's core state, so adapters started at any time see the
+/// same sessions, subscriptions and params.
+#[cfg_attr(
+    not(any(feature = "mqtt-server", feature = "osc-server", feature = "dashboard")),
+    allow(dead_code)
+)]
+pub(crate) struct AdapterRegistry {
+    sessions: Arc<DashMap<SessionId, Arc<Session>>>,
+    subscriptions: Arc<SubscriptionManager>,
+    state: Arc<RouterState>,
+    #[cfg_attr(not(feature = "dashboard"), allow(dead_code))]
+    feature_stats: Arc<FeatureStats>,
+    /// Status of each transport and adapter (shared with the router)
+    statuses: Arc<DashMap<String, AdapterStatus>>,
+    /// Validator handed to adapters that authenticate clients
+    validator: RwLock<Option<Arc<dyn TokenValidator>>>,
+    running: Mutex<BTreeMap<AdapterKind, RunningAdapter>>,
+    /// Serializes starts and stops
+    changing: tokio::sync::Mutex<()>,
+    /// Bumped whenever an adapter task ends
+    ended: Arc<watch::Sender<()>>,
+}
+
+impl AdapterRegistry {
+    pub(crate) fn new(
+        sessions: Arc<DashMap<SessionId, Arc<Session>>>,
+        subscriptions: Arc<SubscriptionManager>,
+        state: Arc<RouterState>,
+        feature_stats: Arc<FeatureStats>,
+        statuses: Arc<DashMap<String, AdapterStatus>>,
+    ) -> Self {
+        Self {
+            sessions,
+            subscriptions,
+            state,
+            feature_stats,
+            statuses,
+            validator: RwLock::new(None),
+            running: Mutex::new(BTreeMap::new()),
+            changing: tokio::sync::Mutex::new(()),
+            ended: Arc::new(watch::channel(()).0),
+        }
+    }
+
+    pub(crate) fn set_validator(&self, validator: Option<Arc<dyn TokenValidator>>) {
+        *self.validator.write() = validator;
+    }
+
+    /// Create a dashboard sharing the router's state and validator
+    #[cfg(feature = "dashboard")]
+    pub(crate) fn dashboard(
+        &self,
+        config: crate::adapters::DashboardConfig,
+    ) -> crate::adapters::DashboardAdapter {
+        let adapter = crate::adapters::DashboardAdapter::new(
+            config,
+            Arc::clone(&self.sessions),
+            Arc::clone(&self.subscriptions),
+            Arc::clone(&self.state),
+        )
+        .with_feature_stats(Arc::clone(&self.feature_stats));
+        match self.validator.read().clone() {
+            Some(validator) => adapter.with_validator(validator),
+            None => adapter,
+        }
+    }
+
+    /// Bind and start an adapter, stopping a running one of the same kind
+    /// first so it can rebind the same address
+    pub(crate) async fn start(&self, config: AdapterConfig) -> Result<()> {
+        let kind = config.kind();
+        let _changing = self.changing.lock().await;
+
+        let previous = self.running.lock().remove(&kind);
+        if let Some(previous) = previous {
+            info!("Restarting {} adapter", kind.name());
+            previous.shut_down().await;
+        }
+
+        let (stop, serve) = match self.launch(config.clone()).await {
+            Ok(launched) => launched,
+            Err(e) => {
+                self.statuses.insert(
+                    kind.name().to_string(),
+                    AdapterStatus::Failed(e.to_string()),
+                );
+                return Err(e);
+            }
+        };
+
+        let statuses = Arc::clone(&self.statuses);
+        let ended = Arc::clone(&self.ended);
+        let task = tokio::spawn(async move {
+            let result = health::track(&statuses, kind.name(), serve).await;
+            if let Err(e) = &result {
+                error!("{} adapter error: {}", kind.name(), e);
+            }
+            ended.send_replace(());
+            result
+        });
+        self.running
+            .lock()
+            .insert(kind, RunningAdapter { config, stop, task });
+        Ok(())
+    }
+
+    /// Bind the adapter's listener and build its serve future
+    async fn launch(
+        &self,
+        config: AdapterConfig,
+    ) -> Result<(StopFn, BoxFuture<'static, Result<()>>)> {
+        match config {
+            #[cfg(feature = "mqtt-server")]
+            AdapterConfig::Mqtt(config) => {
+                info!("Starting MQTT server on {}", config.bind_addr);
+                let mut adapter = crate::adapters::MqttServerAdapter::new(
+                    config,
+                    Arc::clone(&self.sessions),
+                    Arc::clone(&self.subscriptions),
+                    Arc::clone(&self.state),
+                );
+                if let Some(validator) = self.validator.read().clone() {
+                    adapter = adapter.with_validator(validator);
+                }
+                let adapter = Arc::new(adapter);
+                let listener = adapter.bind().await?;
+                let stopping = Arc::clone(&adapter);
+                Ok((
+                    Box::new(move || stopping.stop()),
+                    Box::pin(async move { adapter.serve_on(listener).await }),
+                ))
+            }
+            #[cfg(feature = "osc-server")]
+            AdapterConfig::Osc(config) => {
+                info!("Starting OSC server on {}", config.bind_addr);
+                let adapter = Arc::new(crate::adapters::OscServerAdapter::new(
+                    config,
+                    Arc::clone(&self.sessions),
+                    Arc::clone(&self.subscriptions),
+                    Arc::clone(&self.state),
+                ));
+                let socket = adapter.bind().await?;
+                let stopping = Arc::clone(&adapter);
+                Ok((
+                    Box::new(move || stopping.stop()),
+                    Box::pin(async move { adapter.serve_on(socket).await }),
+                ))
+            }
+            #[cfg(feature = "dashboard")]
+            AdapterConfig::Dashboard(config) => {
+                info!("Starting dashboard on {}", config.bind_addr);
+                let adapter = self.dashboard(config);
+                let listener = adapter.bind().await?;
+                let stopping = adapter.clone();
+                Ok((
+                    Box::new(move || stopping.stop()),
+                    Box::pin(async move { adapter.serve_on(listener).await }),
+                ))
+            }
+        }
+    }
+
+    /// Stop an adapter; false if it wasn't running
+    pub(crate) async fn stop(&self, kind: AdapterKind) -> bool {
+        let _changing = self.changing.lock().await;
+        let running = self.running.lock().remove(&kind);
+        match running {
+            Some(running) => {
+                info!("Stopping {} adapter", kind.name());
+                running.shut_down().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop every adapter
+    pub(crate) async fn stop_all(&self) {
+        for kind in AdapterKind::ALL {
+            self.stop(kind).await;
+        }
+    }
+
+    /// Configs of the adapters currently serving
+    pub(crate) fn configs(&self) -> Vec<AdapterConfig> {
+        self.running
+            .lock()
+            .values()
+            .filter(|running| !running.task.is_finished())
+            .map(|running| running.config.clone())
+            .collect()
+    }
+
+    /// Whether no adapter is serving
+    pub(crate) fn is_idle(&self) -> bool {
+        self.running
+            .lock()
+            .values()
+            .all(|running| running.task.is_finished())
+    }
+
+    /// Changes whenever an adapter task ends
+    pub(crate) fn subscribe_ended(&self) -> watch::Receiver<()> {
+        self.ended.subscribe()
+    }
+
+    /// Snapshot of the running adapters for a GET address
+    pub(crate) fn snapshot(&self, address: &str) -> SnapshotMessage {
+        let only = kind_from_address(address);
+        SnapshotMessage {
+            params: self
+                .configs()
+                .into_iter()
+                .filter(|config| only.is_none_or(|kind| kind == config.kind()))
+                .map(|config| ParamValue {
+                    address: format!("{}/{}", ADAPTERS_PREFIX, config.kind().id()),
+                    value: config.to_value(),
+                    revision: 0,
+                    writer: None,
+                    timestamp: None,
+                })
+                .collect(),
+            correlation_id: None,
+            page: None,
+            sync: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapter_addresses() {
+        assert!(is_adapter_address("/$sys/adapters"));
+        assert!(is_adapter_address("/$sys/adapters/osc"));
+        assert!(!is_adapter_address("/$sys/adaptersx"));
+        assert_eq!(
+            kind_from_address("/$sys/adapters/mqtt"),
+            Some(AdapterKind::Mqtt)
+        );
+        assert_eq!(kind_from_address("/$sys/adapters/midi"), None);
+        assert_eq!(kind_from_address("/$sys/adapters"), None);
+    }
+
+    #[cfg(feature = "osc-server")]
+    #[test]
+    fn test_config_from_value() {
+        let AdapterConfig::Osc(config) =
+            AdapterConfig::from_value(AdapterKind::Osc, &Value::String("0.0.0.0:9000".into()))
+                .unwrap()
+        else {
+            unreachable!()
+        };
+        assert_eq!(config.bind_addr, "0.0.0.0:9000");
+        assert_eq!(config.namespace, "/osc");
+
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("namespace".to_string(), Value::String("/guest".into()));
+        let AdapterConfig::Osc(config) =
+            AdapterConfig::from_value(AdapterKind::Osc, &Value::Map(fields)).unwrap()
+        else {
+            unreachable!()
+        };
+        assert_eq!(config.bind_addr, "0.0.0.0:8000");
+        assert_eq!(config.namespace, "/guest");
+
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("port".to_string(), Value::Int(9000));
+        assert!(AdapterConfig::from_value(AdapterKind::Osc, &Value::Map(fields)).is_err());
+        assert!(AdapterConfig::from_value(AdapterKind::Osc, &Value::Int(1)).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+// CLASP router dashboard
+//
+// Polls the dashboard JSON API and renders metrics, sessions, the namespace
+// tree and subscriptions. Values in the tree are editable: entering JSON
+// (or a bare string) and pressing Enter sends a SET through the router.
+
+(() => {
+  'use strict';
+
+  const TOKEN_KEY = 'clasp-dashboard-token';
+  const METRICS_INTERVAL_MS = 1000;
+  const LISTS_INTERVAL_MS = 2000;
+  const HISTORY = 120;
+
+  const history = {};
+  let token = localStorage.getItem(TOKEN_KEY) || '';
+
+  const $ = (selector) => document.querySelector(selector);
+
+  function setStatus(text, ok) {
+    const el = $('#status');
+    el.textContent = text;
+    el.className = 'status ' + (ok ? 'ok' : 'error');
+  }
+
+  function promptToken() {
+    const value = prompt('Router token (leave empty for open routers)', token);
+    if (value === null) return;
+    token = value.trim();
+    localStorage.setItem(TOKEN_KEY, token);
+    refreshAll();
+  }
+
+  async function api(path, options = {}) {
+    const headers = Object.assign({}, options.headers);
+    if (token) headers['Authorization'] = 'Bearer ' + token;
+    const response = await fetch(path, Object.assign({}, options, { headers }));
+    if (!response.ok) {
+      let message = response.statusText;
+      try {
+        message = (await response.json()).error || message;
+      } catch (_) { /* non-JSON error body */ }
+      const error = new Error(message);
+      error.status = response.status;
+      throw error;
+    }
+    return response.json();
+  }
+
+  function formatDuration(secs) {
+    if (secs < 60) return secs + 's';
+    if (secs < 3600) return Math.floor(secs / 60) + 'm ' + (secs % 60) + 's';
+    const hours = Math.floor(secs / 3600);
+    return hours + 'h ' + Math.floor((secs % 3600) / 60) + 'm';
+  }
+
+  function formatValue(value) {
+    return typeof value === 'string' ? value : JSON.stringify(value);
+  }
+
+  function parseValue(text) {
+    try {
+      return JSON.parse(text);
+    } catch (_) {
+      return text;
+    }
+  }
+
+  function cell(row, text, className) {
+    const td = row.insertCell();
+    td.textContent = text;
+    if (className) td.className = className;
+    return td;
+  }
+
+  function showError(tbody, columns, error) {
+    tbody.innerHTML = '';
+    const row = tbody.insertRow();
+    const td = cell(row, error.status === 403 ? 'Requires admin scope' : error.message, 'muted');
+    td.colSpan = columns;
+  }
+
+  // --- Metrics -------------------------------------------------------------
+
+  function drawChart(canvas, values) {
+    const ratio = window.devicePixelRatio || 1;
+    canvas.width = canvas.clientWidth * ratio;
+    canvas.height = canvas.clientHeight * ratio;
+    const ctx = canvas.getContext('2d');
+    ctx.clearRect(0, 0, canvas.width, canvas.height);
+    if (values.length < 2) return;
+
+    const max = Math.max(1, ...values);
+    const step = canvas.width / (HISTORY - 1);
+    const offset = HISTORY - values.length;
+    ctx.strokeStyle = getComputedStyle(document.documentElement).getPropertyValue('--accent');
+    ctx.lineWidth = 1.5 * ratio;
+    ctx.beginPath();
+    values.forEach((v, i) => {
+      const x = (offset + i) * step;
+      const y = canvas.height - (v / max) * (canvas.height - 4 * ratio) - 2 * ratio;
+      if (i === 0) ctx.moveTo(x, y); else ctx.lineTo(x, y);
+    });
+    ctx.stroke();
+  }
+
+  async function refreshMetrics() {
+    let metrics;
+    try {
+      metrics = await api('api/metrics');
+    } catch (error) {
+      setStatus(error.message, false);
+      if (error.status === 401 && !token) promptToken();
+      return;
+    }
+    setStatus('live', true);
+
+    document.querySelectorAll('canvas[data-metric]').forEach((canvas) => {
+      const key = canvas.dataset.metric;
+      const values = (history[key] = history[key] || []);
+      values.push(metrics[key]);
+      if (values.length > HISTORY) values.shift();
+      drawChart(canvas, values);
+      canvas.parentElement.querySelector('b').textContent = metrics[key];
+    });
+    $('#uptime').textContent = formatDuration(metrics.uptime_secs);
+    $('#signals').textContent = metrics.signals;
+    $('#dropped').textContent = metrics.dropped_messages;
+  }
+
+  // --- Sessions and subscriptions -----------------------------------------
+
+  async function refreshSessions() {
+    const tbody = $('#sessions tbody');
+    let sessions;
+    try {
+      sessions = await api('api/sessions');
+    } catch (error) {
+      return showError(tbody, 8, error);
+    }
+    tbody.innerHTML = '';
+    for (const s of sessions) {
+      const row = tbody.insertRow();
+      cell(row, s.name);
+      cell(row, s.id.slice(0, 8), 'mono').title = s.id;
+      cell(row, s.subject || '–');
+      cell(row, s.subscriptions);
+      cell(row, s.messages_per_second);
+      cell(row, s.dropped_messages);
+      cell(row, formatDuration(s.connected_secs));
+      cell(row, formatDuration(Math.floor(s.idle_ms / 1000)));
+    }
+  }
+
+  async function refreshSubscriptions() {
+    const tbody = $('#subscriptions tbody');
+    let subscriptions;
+    try {
+      subscriptions = await api('api/subscriptions');
+    } catch (error) {
+      return showError(tbody, 4, error);
+    }
+    tbody.innerHTML = '';
+    for (const sub of subscriptions) {
+      const row = tbody.insertRow();
+      cell(row, sub.session_name || sub.session_id.slice(0, 8)).title = sub.session_id;
+      cell(row, sub.id);
+      cell(row, sub.pattern, 'mono');
+      cell(row, sub.types.length ? sub.types.join(', ') : 'all');
+    }
+  }
+
+  // --- Namespace tree ------------------------------------------------------
+
+  // Inputs are kept across refreshes so edits in progress aren't clobbered
+  const leaves = new Map();
+  const openGroups = new Set(['']);
+
+  function buildTree(params) {
+    const root = { children: new Map(), param: null };
+    for (const param of params) {
+      let node = root;
+      for (const segment of param.address.split('/').filter(Boolean)) {
+        if (!node.children.has(segment)) {
+          node.children.set(segment, { children: new Map(), param: null });
+        }
+        node = node.children.get(segment);
+      }
+      node.param = param;
+    }
+    return root;
+  }
+
+  // Declarations from published manifests, used to describe leaves
+  let declarations = [];
+
+  function globRegex(pattern) {
+    const escaped = pattern.replace(/[.+?^${}()|[\]\\]/g, '\\$&');
+    return new RegExp('^' + escaped.replace(/\*\*/g, '\0').replace(/\*/g, '[^/]*')
+      .replace(/\0/g, '.*') + '$');
+  }
+
+  function describe(address) {
+    const signal = declarations.find((d) => d.address === address)
+      || declarations.find((d) => d.regex.test(address));
+    if (!signal) return address;
+    const lines = [address];
+    const meta = signal.meta || {};
+    if (meta.description) lines.push(meta.description);
+    const type = [signal.datatype, meta.unit].filter(Boolean).join(', ');
+    if (type) lines.push(type);
+    if (meta.range) lines.push('range ' + meta.range[0] + ' to ' + meta.range[1]);
+    if (signal.access) lines.push('access ' + signal.access);
+    return lines.join('\n');
+  }
+
+  function leafFor(param) {
+    let leaf = leaves.get(param.address);
+    if (!leaf) {
+      const li = document.createElement('li');
+      li.className = 'leaf';
+      const name = document.createElement('span');
+      name.className = 'name mono';
+      const input = document.createElement('input');
+      input.spellcheck = false;
+      const rev = document.createElement('span');
+      rev.className = 'rev';
+      li.append(name, input, rev);
+
+      input.addEventListener('keydown', async (event) => {
+        if (event.key !== 'Enter') return;
+        li.classList.remove('saved', 'failed');
+        try {
+          const result = await api('api/params', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ address: param.address, value: parseValue(input.value) }),
+          });
+          rev.textContent = 'rev ' + result.revision;
+          li.classList.add('saved');
+          input.blur();
+        } catch (error) {
+          li.classList.add('failed');
+          input.title = error.message;
+        }
+      });
+
+      leaf = { li, name, input, rev };
+      leaves.set(param.address, leaf);
+    }
+    leaf.name.textContent = param.address.split('/').pop();
+    leaf.name.title = describe(param.address);
+    if (document.activeElement !== leaf.input) {
+      leaf.input.value = formatValue(param.value);
+    }
+    leaf.rev.textContent = 'rev ' + param.revision;
+    leaf.rev.title = 'last written by ' + param.writer;
+    return leaf.li;
+  }
+
+  function renderNode(node, path, ul) {
+    const names = [...node.children.keys()].sort();
+    for (const name of names) {
+      const child = node.children.get(name);
+      const childPath = path + '/' + name;
+      if (child.param && child.children.size === 0) {
+        ul.append(leafFor(child.param));
+        continue;
+      }
+      const li = document.createElement('li');
+      const details = document.createElement('details');
+      details.open = openGroups.has(childPath);
+      details.addEventListener('toggle', () => {
+        if (details.open) openGroups.add(childPath); else openGroups.delete(childPath);
+      });
+      const summary = document.createElement('summary');
+      summary.textContent = name;
+      summary.className = 'mono';
+      const nested = document.createElement('ul');
+      if (child.param) nested.append(leafFor(child.param));
+      renderNode(child, childPath, nested);
+      details.append(summary, nested);
+      li.append(details);
+      ul.append(li);
+    }
+  }
+
+  async function refreshNamespace() {
+    const tree = $('#tree');
+    const pattern = $('#pattern').value.trim() || '/**';
+    let params;
+    try {
+      params = await api('api/params?pattern=' + encodeURIComponent(pattern));
+    } catch (error) {
+      tree.innerHTML = '';
+      const li = document.createElement('li');
+      li.className = 'muted';
+      li.textContent = error.message;
+      tree.append(li);
+      return;
+    }
+    try {
+      const manifests = await api('api/manifests');
+      declarations = manifests.flatMap((m) => m.signals)
+        .map((signal) => ({ ...signal, regex: globRegex(signal.address) }));
+    } catch (error) {
+      declarations = [];
+    }
+    const seen = new Set(params.map((p) => p.address));
+    for (const address of leaves.keys()) {
+      if (!seen.has(address)) leaves.delete(address);
+    }
+    const ul = document.createElement('ul');
+    ul.className = 'tree';
+    ul.id = 'tree';
+    renderNode(buildTree(params), '', ul);
+    tree.replaceWith(ul);
+  }
+
+  // --- Wiring --------------------------------------------------------------
+
+  function refreshLists() {
+    refreshSessions();
+    refreshSubscriptions();
+    // Don't rebuild the tree while the user is typing a value
+    if (!(document.activeElement && document.activeElement.closest('#tree'))) {
+      refreshNamespace();
+    }
+  }
+
+  function refreshAll() {
+    refreshMetrics();
+    refreshLists();
+  }
+
+  $('#token-button').addEventListener('click', promptToken);
+  $('#filter').addEventListener('submit', (event) => {
+    event.preventDefault();
+    refreshNamespace();
+  });
+
+  refreshAll();
+  setInterval(refreshMetrics, METRICS_INTERVAL_MS);
+  setInterval(refreshLists, LISTS_INTERVAL_MS);
+})();
@@ -0,0 +1,565 @@
+//! Web Dashboard Adapter
+//!
+//! Serves an embedded web UI over HTTP for observing and overriding a running
+//! router: live sessions, the namespace tree with editable values,
+//! subscription lists and metrics charts. Useful for headless deployments
+//! where there is otherwise no way to see what the router is doing.
+//!
+//! ## Endpoints
+//!
+//! | Method | Path | Scope |
+//! |--------|------|-------|
+//! | GET | `/` (and static assets) | none |
+//! | GET | `/api/metrics` | any valid token |
+//! | GET | `/api/sessions` | `admin:/**` |
+//! | GET | `/api/subscriptions` | `admin:/**` |
+//! | GET | `/api/subscriptions/stats?hot=20` | `admin:/**` |
+//! | GET | `/api/params?pattern=/**` | `read` (results filtered per address) |
+//! | POST | `/api/params` `{"address", "value"}` | `write` on the address |
+//! | GET | `/api/manifests` | `read` on `/$manifest/<app>` |
+//!
+//! ## Authentication
+//!
+//! When a [`TokenValidator`] is attached, API calls must carry
+//! `Authorization: Bearer <token>`. The UI prompts for a token and keeps it
+//! in the browser's local storage. Without a validator the API is open, so
+//! only bind it to trusted interfaces in that case.
+
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Json;
+use clasp_core::security::{Action, TokenInfo, TokenValidator, ValidationResult};
+use clasp_core::{Manifest, Message, Origin, SetMessage, SignalType, Value};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::error::{Result, RouterError};
+use crate::features::{FeatureStats, FeatureUsage};
+use crate::router::OriginFrames;
+use crate::session::{Session, SessionId};
+use crate::state::RouterState;
+use crate::subscription::{SubscriptionManager, SubscriptionStats, STATS_HOT_ADDRESSES};
+
+const INDEX_HTML: &str = include_str!("assets/index.html");
+const APP_JS: &str = include_str!("assets/app.js");
+const STYLE_CSS: &str = include_str!("assets/style.css");
+
+/// Writer ID recorded on values set from the dashboard
+const DASHBOARD_WRITER: &str = "dashboard";
+
+/// Dashboard configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardConfig {
+    /// HTTP bind address (e.g., "0.0.0.0:7380")
+    pub bind_addr: String,
+    /// Maximum params returned by a single namespace request
+    #[serde(default = "default_max_params")]
+    pub max_params: usize,
+}
+
+fn default_max_params() -> usize {
+    5000
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0:7380".to_string(),
+            max_params: default_max_params(),
+        }
+    }
+}
+
+/// Web dashboard adapter
+///
+/// Shares the router's sessions, subscriptions and state, like the protocol
+/// adapters do.
+#[derive(Clone)]
+pub struct DashboardAdapter {
+    config: DashboardConfig,
+    /// Reference to router sessions
+    sessions: Arc<DashMap<SessionId, Arc<Session>>>,
+    /// Reference to router subscriptions
+    subscriptions: Arc<SubscriptionManager>,
+    /// Reference to router state
+    state: Arc<RouterState>,
+    /// Token validator (None = open access)
+    validator: Option<Arc<dyn TokenValidator>>,
+    /// When the dashboard was created (for uptime)
+    started_at: Instant,
+    /// Per-feature message counters
+    feature_stats: Arc<FeatureStats>,
+    /// Running flag; cleared by [`DashboardAdapter::stop`]
+    running: Arc<watch::Sender<bool>>,
+}
+
+impl DashboardAdapter {
+    /// Create a new dashboard adapter
+    pub fn new(
+        config: DashboardConfig,
+        sessions: Arc<DashMap<SessionId, Arc<Session>>>,
+        subscriptions: Arc<SubscriptionManager>,
+        state: Arc<RouterState>,
+    ) -> Self {
+        Self {
+            config,
+            sessions,
+            subscriptions,
+            state,
+            validator: None,
+            started_at: Instant::now(),
+            feature_stats: Arc::new(FeatureStats::new()),
+            running: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// Report these per-feature counters in `/api/metrics`
+    pub fn with_feature_stats(mut self, stats: Arc<FeatureStats>) -> Self {
+        self.feature_stats = stats;
+        self
+    }
+
+    /// Require bearer tokens checked by this validator
+    pub fn with_validator(mut self, validator: Arc<dyn TokenValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Build the HTTP service (for embedding in another axum app)
+    pub fn app(&self) -> axum::Router {
+        axum::Router::new()
+            .route("/", get(index))
+            .route("/app.js", get(app_js))
+            .route("/style.css", get(style_css))
+            .route("/api/metrics", get(metrics))
+            .route("/api/sessions", get(sessions))
+            .route("/api/subscriptions", get(subscriptions))
+            .route("/api/subscriptions/stats", get(subscription_stats))
+            .route("/api/params", get(params).post(set_param))
+            .route("/api/manifests", get(manifests))
+            .with_state(Arc::new(self.clone()))
+    }
+
+    /// Start serving the dashboard
+    pub async fn serve(&self) -> Result<()> {
+        let listener = self.bind().await?;
+        self.serve_on(listener).await
+    }
+
+    /// Bind the listener, so bind errors surface before serving
+    pub(crate) async fn bind(&self) -> Result<TcpListener> {
+        TcpListener::bind(&self.config.bind_addr)
+            .await
+            .map_err(|e| RouterError::Config(format!("dashboard bind failed: {}", e)))
+    }
+
+    /// Serve requests on `listener` until [`DashboardAdapter::stop`]
+    pub(crate) async fn serve_on(&self, listener: TcpListener) -> Result<()> {
+        if self.validator.is_none() {
+            warn!(
+                "Dashboard on {} has no token validator; the API is unauthenticated",
+                self.config.bind_addr
+            );
+        }
+        info!("Dashboard listening on http://{}", self.config.bind_addr);
+        self.running.send_replace(true);
+
+        let mut stopped = self.running.subscribe();
+        axum::serve(listener, self.app())
+            .with_graceful_shutdown(async move {
+                let _ = stopped.wait_for(|running| !running).await;
+            })
+            .await
+            .map_err(|e| RouterError::Config(format!("dashboard server error: {}", e)))
+    }
+
+    /// Stop serving; in-flight requests are finished first
+    pub fn stop(&self) {
+        self.running.send_replace(false);
+    }
+
+    /// Validate the bearer token in `headers`
+    fn authenticate(&self, headers: &HeaderMap) -> std::result::Result<Caller, ApiError> {
+        let Some(validator) = &self.validator else {
+            return Ok(None);
+        };
+
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or(ApiError::Unauthorized("Authentication required"))?;
+
+        match validator.validate(token) {
+            ValidationResult::Valid(info) => Ok(Some(info)),
+            ValidationResult::Expired => Err(ApiError::Unauthorized("Token has expired")),
+            ValidationResult::Invalid(_) | ValidationResult::NotMyToken => {
+                Err(ApiError::Unauthorized("Invalid token"))
+            }
+        }
+    }
+
+    /// Validate the token and require `action` on `address`
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        action: Action,
+        address: &str,
+    ) -> std::result::Result<Caller, ApiError> {
+        let caller = self.authenticate(headers)?;
+        if allows(&caller, action, address) {
+            Ok(caller)
+        } else {
+            Err(ApiError::Forbidden)
+        }
+    }
+}
+
+/// Authenticated caller; `None` when the dashboard runs without a validator
+type Caller = Option<TokenInfo>;
+
+fn allows(caller: &Caller, action: Action, address: &str) -> bool {
+    match caller {
+        Some(info) => info.has_scope(action, address),
+        None => true,
+    }
+}
+
+/// API error response
+enum ApiError {
+    Unauthorized(&'static str),
+    Forbidden,
+    BadRequest(String),
+    Conflict(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.to_string()),
+            ApiError::Forbidden => (StatusCode::FORBIDDEN, "Insufficient scope".to_string()),
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+type Shared = State<Arc<DashboardAdapter>>;
+
+async fn index() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+        INDEX_HTML,
+    )
+}
+
+async fn app_js() -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "application/javascript; charset=utf-8",
+        )],
+        APP_JS,
+    )
+}
+
+async fn style_css() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/css; charset=utf-8")],
+        STYLE_CSS,
+    )
+}
+
+/// Router-wide counters, sampled by the UI to draw charts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DashboardMetrics {
+    pub uptime_secs: u64,
+    pub sessions: usize,
+    pub subscriptions: usize,
+    pub params: usize,
+    pub signals: usize,
+    /// Sum of per-session inbound message rates
+    pub messages_per_second: u32,
+    /// Messages dropped to slow clients since they connected
+    pub dropped_messages: u64,
+    /// Messages per HELLO feature (accepted/rejected)
+    pub features: BTreeMap<String, FeatureUsage>,
+}
+
+async fn metrics(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+) -> std::result::Result<Json<DashboardMetrics>, ApiError> {
+    dashboard.authenticate(&headers)?;
+
+    let (messages_per_second, dropped_messages) =
+        dashboard
+            .sessions
+            .iter()
+            .fold((0u32, 0u64), |(rate, drops), entry| {
+                let session = entry.value();
+                (
+                    rate.saturating_add(session.messages_per_second()),
+                    drops.saturating_add(session.total_drops()),
+                )
+            });
+
+    Ok(Json(DashboardMetrics {
+        uptime_secs: dashboard.started_at.elapsed().as_secs(),
+        sessions: dashboard.sessions.len(),
+        subscriptions: dashboard.subscriptions.len(),
+        params: dashboard.state.len(),
+        signals: dashboard.state.signal_count(),
+        messages_per_second,
+        dropped_messages,
+        features: dashboard.feature_stats.snapshot(),
+    }))
+}
+
+/// A connected session as shown in the dashboard
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub name: String,
+    pub features: Vec<String>,
+    pub authenticated: bool,
+    pub subject: Option<String>,
+    pub subscriptions: usize,
+    pub connected_secs: u64,
+    pub idle_ms: u64,
+    pub messages_per_second: u32,
+    pub dropped_messages: u64,
+}
+
+async fn sessions(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<SessionInfo>>, ApiError> {
+    dashboard.authorize(&headers, Action::Admin, "/**")?;
+
+    let mut sessions: Vec<SessionInfo> = dashboard
+        .sessions
+        .iter()
+        .map(|entry| {
+            let session = entry.value();
+            SessionInfo {
+                id: session.id.clone(),
+                name: session.name.clone(),
+                features: session.features.clone(),
+                authenticated: session.authenticated,
+                subject: session.subject(),
+                subscriptions: session.subscriptions().len(),
+                connected_secs: session.created_at.elapsed().as_secs(),
+                idle_ms: session.idle_duration().as_millis() as u64,
+                messages_per_second: session.messages_per_second(),
+                dropped_messages: session.total_drops(),
+            }
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(Json(sessions))
+}
+
+/// A subscription as shown in the dashboard
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionInfo {
+    pub session_id: String,
+    pub session_name: Option<String>,
+    pub id: u32,
+    pub pattern: String,
+    pub types: Vec<SignalType>,
+}
+
+async fn subscriptions(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<SubscriptionInfo>>, ApiError> {
+    dashboard.authorize(&headers, Action::Admin, "/**")?;
+
+    let mut subscriptions: Vec<SubscriptionInfo> = dashboard
+        .subscriptions
+        .list()
+        .into_iter()
+        .map(|sub| SubscriptionInfo {
+            session_name: dashboard
+                .sessions
+                .get(&sub.session_id)
+                .map(|s| s.name.clone()),
+            session_id: sub.session_id,
+            id: sub.id,
+            pattern: sub.pattern.address().as_str().to_string(),
+            types: sub.types.into_iter().collect(),
+        })
+        .collect();
+    subscriptions.sort_by(|a, b| {
+        a.session_id
+            .cmp(&b.session_id)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    Ok(Json(subscriptions))
+}
+
+#[derive(Debug, Deserialize)]
+struct StatsQuery {
+    #[serde(default = "default_hot")]
+    hot: usize,
+}
+
+fn default_hot() -> usize {
+    STATS_HOT_ADDRESSES
+}
+
+async fn subscription_stats(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+    Query(query): Query<StatsQuery>,
+) -> std::result::Result<Json<SubscriptionStats>, ApiError> {
+    dashboard.authorize(&headers, Action::Admin, "/**")?;
+    Ok(Json(dashboard.subscriptions.stats(query.hot)))
+}
+
+#[derive(Debug, Deserialize)]
+struct ParamsQuery {
+    #[serde(default = "default_pattern")]
+    pattern: String,
+}
+
+fn default_pattern() -> String {
+    "/**".to_string()
+}
+
+/// A param value as shown in the dashboard
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParamInfo {
+    pub address: String,
+    pub value: Value,
+    pub revision: u64,
+    pub writer: String,
+    pub timestamp: u64,
+}
+
+async fn params(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+    Query(query): Query<ParamsQuery>,
+) -> std::result::Result<Json<Vec<ParamInfo>>, ApiError> {
+    let caller = dashboard.authenticate(&headers)?;
+
+    let mut params: Vec<ParamInfo> = dashboard
+        .state
+        .get_matching(&query.pattern)
+        .into_iter()
+        .filter(|(address, _)| allows(&caller, Action::Read, address))
+        .map(|(address, param)| ParamInfo {
+            address,
+            value: param.value,
+            revision: param.revision,
+            writer: param.writer,
+            timestamp: param.timestamp,
+        })
+        .collect();
+    params.sort_by(|a, b| a.address.cmp(&b.address));
+    params.truncate(dashboard.config.max_params);
+
+    Ok(Json(params))
+}
+
+async fn manifests(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<Manifest>>, ApiError> {
+    let caller = dashboard.authenticate(&headers)?;
+    let manifests = dashboard
+        .state
+        .manifests()
+        .into_iter()
+        .filter(|manifest| allows(&caller, Action::Read, &manifest.address()))
+        .collect();
+    Ok(Json(manifests))
+}
+
+/// Body of a value override from the dashboard
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetParamRequest {
+    pub address: String,
+    pub value: Value,
+}
+
+async fn set_param(
+    State(dashboard): Shared,
+    headers: HeaderMap,
+    Json(request): Json<SetParamRequest>,
+) -> std::result::Result<Json<ParamInfo>, ApiError> {
+    if !request.address.starts_with('/') || request.address.contains('*') {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid address: {}",
+            request.address
+        )));
+    }
+    let caller = dashboard.authorize(&headers, Action::Write, &request.address)?;
+    let writer = caller
+        .and_then(|info| info.subject)
+        .map(|subject| format!("{}:{}", DASHBOARD_WRITER, subject))
+        .unwrap_or_else(|| DASHBOARD_WRITER.to_string());
+
+    let set_msg = SetMessage {
+        address: request.address.clone(),
+        value: request.value.clone(),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    };
+    let revision = dashboard
+        .state
+        .apply_set(&set_msg, &writer)
+        .map_err(|e| ApiError::Conflict(e.to_string()))?;
+
+    // Broadcast to subscribers as if a client had sent the SET
+    let origin = Origin::new("dashboard");
+    let subscribers =
+        dashboard
+            .subscriptions
+            .find_recipients(&request.address, Some(SignalType::Param), &origin);
+    let broadcast = Message::Set(SetMessage {
+        revision: Some(revision),
+        ..set_msg
+    });
+    let frames = OriginFrames::new(&broadcast, &origin);
+    for recipient in subscribers {
+        // Flow-controlled subscriptions out of credit skip it
+        if !recipient.take_credit() {
+            continue;
+        }
+        if let Some(session) = dashboard.sessions.get(&recipient.session_id) {
+            if let Some(bytes) = frames.for_recipient(&recipient) {
+                let _ = session.try_send(bytes);
+            }
+        }
+    }
+    info!(
+        "Dashboard set {} (rev {}) by {}",
+        request.address, revision, writer
+    );
+
+    Ok(Json(ParamInfo {
+        address: request.address,
+        value: request.value,
+        revision,
+        writer,
+        timestamp: clasp_core::time::now(),
+    }))
+}
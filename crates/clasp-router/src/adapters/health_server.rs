@@ -0,0 +1,87 @@
+//! HTTP health check endpoints
+//!
+//! Serves `/healthz` (liveness) and `/readyz` (readiness) on their own port so
+//! orchestrators like Kubernetes or DigitalOcean App Platform can probe the
+//! router without speaking CLASP. Both return the router's
+//! [`HealthReport`](crate::HealthReport) as JSON:
+//!
+//! - `/healthz` is 200 as long as the process is serving HTTP
+//! - `/readyz` is 200 when the router is ready for new clients, 503 otherwise
+//!   (not yet listening, draining, at the session limit, or an adapter failed)
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::error::{Result, RouterError};
+use crate::router::Router;
+
+/// Health endpoint configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthServerConfig {
+    /// HTTP bind address (e.g., "0.0.0.0:7390")
+    pub bind_addr: String,
+}
+
+impl Default for HealthServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0:7390".to_string(),
+        }
+    }
+}
+
+/// `/healthz` and `/readyz` HTTP server
+///
+/// Created with [`Router::health_server`].
+pub struct HealthServer {
+    config: HealthServerConfig,
+    router: Arc<Router>,
+}
+
+impl HealthServer {
+    pub(crate) fn new(config: HealthServerConfig, router: Router) -> Self {
+        Self {
+            config,
+            router: Arc::new(router),
+        }
+    }
+
+    /// Build the HTTP service (for embedding in another axum app)
+    pub fn app(&self) -> axum::Router {
+        axum::Router::new()
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz))
+            .with_state(Arc::clone(&self.router))
+    }
+
+    /// Start serving the health endpoints
+    pub async fn serve(&self) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(&self.config.bind_addr)
+            .await
+            .map_err(|e| RouterError::Config(format!("health bind failed: {}", e)))?;
+        info!(
+            "Health endpoints listening on http://{}",
+            self.config.bind_addr
+        );
+
+        axum::serve(listener, self.app())
+            .await
+            .map_err(|e| RouterError::Config(format!("health server error: {}", e)))
+    }
+}
+
+async fn healthz(State(router): State<Arc<Router>>) -> impl IntoResponse {
+    Json(router.health())
+}
+
+async fn readyz(State(router): State<Arc<Router>>) -> impl IntoResponse {
+    let report = router.health();
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
@@ -8,6 +8,8 @@
 //!
 //! - [`MqttServerAdapter`] - Accept MQTT clients on port 1883/8883
 //! - [`OscServerAdapter`] - Accept OSC clients via UDP with session tracking
+//! - [`DashboardAdapter`] - Embedded web UI for monitoring and manual overrides
+//! - [`HealthServer`] - `/healthz` and `/readyz` for orchestrator probes
 //!
 //! ## Architecture
 //!
@@ -31,8 +33,20 @@
 //! Adapters share the router's core state (sessions, subscriptions, state storage)
 //! and translate between their native protocol and CLASP semantics.
 
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+#[cfg(feature = "health")]
+pub mod health_server;
+#[cfg(feature = "mqtt-server")]
 pub mod mqtt_server;
+#[cfg(feature = "osc-server")]
 pub mod osc_server;
 
+#[cfg(feature = "dashboard")]
+pub use dashboard::{DashboardAdapter, DashboardConfig};
+#[cfg(feature = "health")]
+pub use health_server::{HealthServer, HealthServerConfig};
+#[cfg(feature = "mqtt-server")]
 pub use mqtt_server::{MqttServerAdapter, MqttServerConfig};
+#[cfg(feature = "osc-server")]
 pub use osc_server::{OscServerAdapter, OscServerConfig};
@@ -13,7 +13,7 @@
 //! | Username/Password | Token auth |
 
 use bytes::{Bytes, BytesMut};
-use clasp_core::{codec, Message, SetMessage, SignalType, Value};
+use clasp_core::{codec, Message, Origin, SetMessage, SignalType, Value};
 use dashmap::DashMap;
 use mqttbytes::v4::{
     ConnAck, ConnectReturnCode, Packet, PingResp, PubAck, Publish, SubAck, SubscribeReasonCode,
@@ -28,10 +28,11 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tracing::{debug, error, info, warn};
 
 use crate::error::{Result, RouterError};
+use crate::router::OriginFrames;
 use crate::session::{Session, SessionId};
 use crate::state::RouterState;
 use crate::subscription::{Subscription, SubscriptionManager};
@@ -155,8 +156,8 @@ pub struct MqttServerAdapter {
     state: Arc<RouterState>,
     /// MQTT sessions by client_id
     mqtt_sessions: Arc<DashMap<String, Arc<MqttSession>>>,
-    /// Running flag
-    running: Arc<RwLock<bool>>,
+    /// Running flag; cleared by [`MqttServerAdapter::stop`]
+    running: Arc<watch::Sender<bool>>,
     /// Token validator for authentication (if require_auth is true)
     validator: Option<Arc<dyn TokenValidator>>,
     /// TLS acceptor (if configured)
@@ -178,7 +179,7 @@ impl MqttServerAdapter {
             subscriptions,
             state,
             mqtt_sessions: Arc::new(DashMap::new()),
-            running: Arc::new(RwLock::new(false)),
+            running: Arc::new(watch::channel(false).0),
             validator: None,
             #[cfg(feature = "mqtts")]
             tls_acceptor: None,
@@ -197,18 +198,32 @@ impl MqttServerAdapter {
 
     /// Start the MQTT server
     pub async fn serve(&self) -> Result<()> {
-        let listener = TcpListener::bind(&self.config.bind_addr)
+        let listener = self.bind().await?;
+        self.serve_on(listener).await
+    }
+
+    /// Bind the listener, so bind errors surface before serving
+    pub(crate) async fn bind(&self) -> Result<TcpListener> {
+        TcpListener::bind(&self.config.bind_addr)
             .await
-            .map_err(|e| RouterError::Transport(e.into()))?;
+            .map_err(|e| RouterError::Transport(e.into()))
+    }
 
+    /// Accept clients on `listener` until [`MqttServerAdapter::stop`]
+    pub(crate) async fn serve_on(&self, listener: TcpListener) -> Result<()> {
         info!("MQTT server listening on {}", self.config.bind_addr);
-        *self.running.write() = true;
+        self.running.send_replace(true);
 
         // Start session cleanup task
         self.start_cleanup_task();
 
-        while *self.running.read() {
-            match listener.accept().await {
+        let mut stopped = self.running.subscribe();
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = stopped.wait_for(|running| !running) => break,
+            };
+            match accepted {
                 Ok((stream, peer_addr)) => {
                     // Check max clients
                     if self.config.max_clients > 0
@@ -230,6 +245,8 @@ impl MqttServerAdapter {
             }
         }
 
+        // Connections see the flag and close their sessions themselves
+        info!("MQTT server on {} stopped", self.config.bind_addr);
         Ok(())
     }
 
@@ -247,7 +264,7 @@ impl MqttServerAdapter {
             loop {
                 tokio::time::sleep(check_interval).await;
 
-                if !*running.read() {
+                if !*running.borrow() {
                     break;
                 }
 
@@ -303,9 +320,9 @@ impl MqttServerAdapter {
         });
     }
 
-    /// Stop the MQTT server
+    /// Stop the MQTT server and disconnect its clients
     pub fn stop(&self) {
-        *self.running.write() = false;
+        self.running.send_replace(false);
     }
 
     /// Get connected client count
@@ -323,7 +340,7 @@ async fn handle_mqtt_connection(
     subscriptions: Arc<SubscriptionManager>,
     state: Arc<RouterState>,
     mqtt_sessions: Arc<DashMap<String, Arc<MqttSession>>>,
-    running: Arc<RwLock<bool>>,
+    running: Arc<watch::Sender<bool>>,
     validator: Option<Arc<dyn TokenValidator>>,
 ) -> Result<()> {
     let mut read_buf = BytesMut::with_capacity(4096);
@@ -333,7 +350,7 @@ async fn handle_mqtt_connection(
 
     // Wait for CONNECT packet
     let connect = loop {
-        if !*running.read() {
+        if !*running.borrow() {
             return Ok(());
         }
 
@@ -438,11 +455,14 @@ async fn handle_mqtt_connection(
 
     // Create CLASP session (using a transport sender that writes to our channel)
     let mqtt_sender = MqttTransportSender::new(tx.clone());
-    let clasp_session = Arc::new(Session::new(
-        Arc::new(mqtt_sender),
-        format!("mqtt:{}", client_id),
-        vec!["mqtt".to_string()],
-    ));
+    let clasp_session = Arc::new(
+        Session::new(
+            Arc::new(mqtt_sender),
+            format!("mqtt:{}", client_id),
+            vec!["mqtt".to_string()],
+        )
+        .with_origin(Origin::new("mqtt").with_remote(client_id.clone())),
+    );
     let clasp_session_id = clasp_session.id.clone();
     clasp_sessions.insert(clasp_session_id.clone(), clasp_session);
 
@@ -470,12 +490,16 @@ async fn handle_mqtt_connection(
     );
 
     // Main loop: handle incoming packets and outgoing messages
+    let mut stopped = running.subscribe();
     loop {
-        if !*running.read() {
-            break;
-        }
-
         tokio::select! {
+            _ = stopped.changed() => {
+                if !*stopped.borrow() {
+                    info!("Closing MQTT client {}: server stopped", client_id);
+                    break;
+                }
+            }
+
             // Read from MQTT client
             result = stream.read_buf(&mut read_buf) => {
                 match result {
@@ -639,24 +663,33 @@ async fn handle_mqtt_packet(
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             };
 
             if let Ok(revision) = state.apply_set(&set_msg, &mqtt_session.clasp_session_id) {
                 // Broadcast to CLASP subscribers
+                let origin = Origin::new("mqtt").with_remote(mqtt_session.client_id.clone());
                 let subscribers =
-                    subscriptions.find_subscribers(&clasp_address, Some(SignalType::Param));
+                    subscriptions.find_recipients(&clasp_address, Some(SignalType::Param), &origin);
 
                 let mut updated_set = set_msg.clone();
                 updated_set.revision = Some(revision);
                 let broadcast_msg = Message::Set(updated_set);
+                let frames = OriginFrames::new(&broadcast_msg, &origin);
 
-                if let Ok(bytes) = codec::encode(&broadcast_msg) {
-                    for sub_session_id in subscribers {
-                        // Don't send back to the MQTT sender
-                        if sub_session_id != mqtt_session.clasp_session_id {
-                            if let Some(sub_session) = clasp_sessions.get(&sub_session_id) {
-                                let _ = sub_session.try_send(bytes.clone());
-                            }
+                for recipient in subscribers {
+                    // Don't send back to the MQTT sender
+                    if recipient.session_id == mqtt_session.clasp_session_id {
+                        continue;
+                    }
+                    // Flow-controlled subscriptions out of credit skip it
+                    if !recipient.take_credit() {
+                        continue;
+                    }
+                    if let Some(sub_session) = clasp_sessions.get(&recipient.session_id) {
+                        if let Some(bytes) = frames.for_recipient(&recipient) {
+                            let _ = sub_session.try_send(bytes);
                         }
                     }
                 }
@@ -20,7 +20,7 @@
 //! they are converted back to OSC and sent to the subscribed UDP clients.
 
 use bytes::Bytes;
-use clasp_core::{codec, Message, SetMessage, SignalType, Value};
+use clasp_core::{codec, Message, Origin, SetMessage, SignalType, Value};
 use dashmap::DashMap;
 use parking_lot::RwLock;
 use rosc::{OscBundle, OscMessage, OscPacket, OscType};
@@ -31,9 +31,11 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::net::UdpSocket;
+use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 
 use crate::error::{Result, RouterError};
+use crate::router::OriginFrames;
 use crate::session::{Session, SessionId};
 use crate::state::RouterState;
 use crate::subscription::{Subscription, SubscriptionManager};
@@ -125,8 +127,8 @@ pub struct OscServerAdapter {
     state: Arc<RouterState>,
     /// OSC sessions by peer address
     osc_sessions: Arc<DashMap<SocketAddr, Arc<OscSession>>>,
-    /// Running flag
-    running: Arc<RwLock<bool>>,
+    /// Running flag; cleared by [`OscServerAdapter::stop`]
+    running: Arc<watch::Sender<bool>>,
     /// UDP socket for sending replies
     socket: Arc<RwLock<Option<Arc<UdpSocket>>>>,
 }
@@ -145,22 +147,32 @@ impl OscServerAdapter {
             subscriptions,
             state,
             osc_sessions: Arc::new(DashMap::new()),
-            running: Arc::new(RwLock::new(false)),
+            running: Arc::new(watch::channel(false).0),
             socket: Arc::new(RwLock::new(None)),
         }
     }
 
     /// Start the OSC server
     pub async fn serve(&self) -> Result<()> {
-        let socket = UdpSocket::bind(&self.config.bind_addr)
+        let socket = self.bind().await?;
+        self.serve_on(socket).await
+    }
+
+    /// Bind the socket, so bind errors surface before serving
+    pub(crate) async fn bind(&self) -> Result<UdpSocket> {
+        UdpSocket::bind(&self.config.bind_addr)
             .await
-            .map_err(|e| RouterError::Transport(e.into()))?;
+            .map_err(|e| RouterError::Transport(e.into()))
+    }
 
+    /// Receive on `socket` until [`OscServerAdapter::stop`], then end the
+    /// sessions of every peer seen
+    pub(crate) async fn serve_on(&self, socket: UdpSocket) -> Result<()> {
         let socket = Arc::new(socket);
         *self.socket.write() = Some(Arc::clone(&socket));
 
         info!("OSC server listening on {}", self.config.bind_addr);
-        *self.running.write() = true;
+        self.running.send_replace(true);
 
         // Start session cleanup task
         self.start_cleanup_task();
@@ -170,8 +182,13 @@ impl OscServerAdapter {
 
         let mut buf = vec![0u8; 65535];
 
-        while *self.running.read() {
-            match socket.recv_from(&mut buf).await {
+        let mut stopped = self.running.subscribe();
+        loop {
+            let received = tokio::select! {
+                received = socket.recv_from(&mut buf) => received,
+                _ = stopped.wait_for(|running| !running) => break,
+            };
+            match received {
                 Ok((len, peer_addr)) => {
                     let data = &buf[..len];
 
@@ -195,6 +212,18 @@ impl OscServerAdapter {
             }
         }
 
+        // UDP peers never disconnect, so their sessions end with the server
+        let peers: Vec<SocketAddr> = self.osc_sessions.iter().map(|e| *e.key()).collect();
+        for peer_addr in peers {
+            if let Some((_, osc_session)) = self.osc_sessions.remove(&peer_addr) {
+                self.sessions.remove(&osc_session.clasp_session_id);
+                self.subscriptions
+                    .remove_session(&osc_session.clasp_session_id);
+            }
+        }
+        *self.socket.write() = None;
+
+        info!("OSC server on {} stopped", self.config.bind_addr);
         Ok(())
     }
 
@@ -206,11 +235,14 @@ impl OscServerAdapter {
 
         // Create new OSC session
         let osc_sender = OscTransportSender::new(peer_addr, Arc::clone(&self.socket));
-        let clasp_session = Arc::new(Session::new(
-            Arc::new(osc_sender),
-            format!("osc:{}", peer_addr),
-            vec!["osc".to_string()],
-        ));
+        let clasp_session = Arc::new(
+            Session::new(
+                Arc::new(osc_sender),
+                format!("osc:{}", peer_addr),
+                vec!["osc".to_string()],
+            )
+            .with_origin(Origin::new("osc").with_remote(peer_addr.to_string())),
+        );
         let clasp_session_id = clasp_session.id.clone();
         self.sessions
             .insert(clasp_session_id.clone(), Arc::clone(&clasp_session));
@@ -272,6 +304,8 @@ impl OscServerAdapter {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         };
 
         if let Ok(revision) = self
@@ -279,21 +313,30 @@ impl OscServerAdapter {
             .apply_set(&set_msg, &osc_session.clasp_session_id)
         {
             // Broadcast to CLASP subscribers
-            let subscribers = self
-                .subscriptions
-                .find_subscribers(&clasp_address, Some(SignalType::Param));
+            let origin = Origin::new("osc").with_remote(osc_session.peer_addr.to_string());
+            let subscribers = self.subscriptions.find_recipients(
+                &clasp_address,
+                Some(SignalType::Param),
+                &origin,
+            );
 
             let mut updated_set = set_msg.clone();
             updated_set.revision = Some(revision);
             let broadcast_msg = Message::Set(updated_set);
+            let frames = OriginFrames::new(&broadcast_msg, &origin);
 
-            if let Ok(bytes) = codec::encode(&broadcast_msg) {
-                for sub_session_id in subscribers {
-                    // Don't send back to the OSC sender
-                    if sub_session_id != osc_session.clasp_session_id {
-                        if let Some(sub_session) = self.sessions.get(&sub_session_id) {
-                            let _ = sub_session.try_send(bytes.clone());
-                        }
+            for recipient in subscribers {
+                // Don't send back to the OSC sender
+                if recipient.session_id == osc_session.clasp_session_id {
+                    continue;
+                }
+                // Flow-controlled subscriptions out of credit skip it
+                if !recipient.take_credit() {
+                    continue;
+                }
+                if let Some(sub_session) = self.sessions.get(&recipient.session_id) {
+                    if let Some(bytes) = frames.for_recipient(&recipient) {
+                        let _ = sub_session.try_send(bytes);
                     }
                 }
             }
@@ -321,7 +364,7 @@ impl OscServerAdapter {
             loop {
                 tokio::time::sleep(check_interval).await;
 
-                if !*running.read() {
+                if !*running.borrow() {
                     break;
                 }
 
@@ -354,9 +397,9 @@ impl OscServerAdapter {
         // which converts CLASP messages to OSC and sends them
     }
 
-    /// Stop the OSC server
+    /// Stop the OSC server and end its sessions
     pub fn stop(&self) {
-        *self.running.write() = false;
+        self.running.send_replace(false);
     }
 
     /// Get connected session count
@@ -0,0 +1,539 @@
+//! Connection throttling and the ban list
+//!
+//! Public routers see handshake floods and token guessing from hosts that
+//! never become useful clients. The router keeps, per remote IP:
+//!
+//! - a count of connection attempts in the current window; attempts past
+//!   [`ConnectionGuard::max_connects`] are dropped before the handshake
+//! - a count of failed authentications; an IP reaching
+//!   [`ConnectionGuard::max_auth_failures`] within a window is banned for
+//!   [`ConnectionGuard::ban_duration`]
+//! - the ban list itself, which admins manage over the protocol:
+//!
+//! | Message | Effect |
+//! |---------|--------|
+//! | `GET /$sys/security/bans` | SNAPSHOT of the banned IPs |
+//! | `SET /$sys/security/bans/<ip> <ban>` | Ban the IP and close its sessions |
+//! | `SET /$sys/security/bans/<ip> null` | Lift the ban |
+//!
+//! `<ban>` is `true` (until lifted), a duration (seconds, or a string like
+//! `"30m"`), or a map with optional `duration` and `reason`.
+//!
+//! Bans, lifted bans and throttled IPs are reported as events on
+//! [`SECURITY_ADDRESS`]. Connections refused because of a ban are not
+//! reported, since a banned host retrying would flood subscribers.
+
+use clasp_core::security::parse_duration;
+use clasp_core::{Message, ParamValue, PublishMessage, SignalType, SnapshotMessage, Value};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::session::SessionId;
+
+/// Address security events are published to
+pub const SECURITY_ADDRESS: &str = "/$sys/security";
+
+/// Address prefix for ban list management
+pub const BANS_PREFIX: &str = "/$sys/security/bans";
+
+/// Checks between sweeps of stale counters
+const SWEEP_INTERVAL: usize = 1024;
+
+/// Per-IP limits (off by default)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionGuard {
+    /// Connection attempts per IP in a window (0 = unlimited)
+    pub max_connects: u32,
+    /// Failed authentications per IP in a window before it is banned
+    /// (0 = never ban automatically)
+    pub max_auth_failures: u32,
+    /// Window both counts are kept over
+    pub window: Duration,
+    /// How long automatic bans last
+    pub ban_duration: Duration,
+}
+
+impl ConnectionGuard {
+    /// Limit connection attempts to `max_connects` per `window`
+    pub fn throttle(mut self, max_connects: u32, window: Duration) -> Self {
+        self.max_connects = max_connects;
+        self.window = window;
+        self
+    }
+
+    /// Ban IPs for `duration` after `max_failures` failed authentications
+    pub fn ban_after(mut self, max_failures: u32, duration: Duration) -> Self {
+        self.max_auth_failures = max_failures;
+        self.ban_duration = duration;
+        self
+    }
+}
+
+impl Default for ConnectionGuard {
+    fn default() -> Self {
+        Self {
+            max_connects: 0,
+            max_auth_failures: 0,
+            window: Duration::from_secs(60),
+            ban_duration: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Whether to accept a new connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum Admission {
+    Accept,
+    /// The IP is banned
+    Banned,
+    /// The IP is over its connection limit; `event` is set the first time in
+    /// a window
+    Throttled {
+        event: Option<SecurityEvent>,
+    },
+}
+
+/// A ban on the list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ban {
+    pub reason: String,
+    /// When the ban lapses (`None` = until lifted)
+    pub expires: Option<Instant>,
+}
+
+impl Ban {
+    fn is_active(&self, now: Instant) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+
+    /// The ban as a map value (reason, expires_in seconds)
+    pub fn to_value(&self) -> Value {
+        let mut map = HashMap::new();
+        map.insert("reason".to_string(), Value::String(self.reason.clone()));
+        if let Some(expires) = self.expires {
+            let left = expires.saturating_duration_since(Instant::now());
+            map.insert("expires_in".to_string(), Value::Int(left.as_secs() as i64));
+        }
+        Value::Map(map)
+    }
+}
+
+/// A security event, as published on [`SECURITY_ADDRESS`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecurityEvent {
+    Banned {
+        ip: IpAddr,
+        reason: String,
+        duration: Option<Duration>,
+    },
+    Unbanned {
+        ip: IpAddr,
+    },
+    Throttled {
+        ip: IpAddr,
+        attempts: u32,
+    },
+}
+
+impl SecurityEvent {
+    /// The IP the event is about
+    pub fn ip(&self) -> IpAddr {
+        match self {
+            Self::Banned { ip, .. } | Self::Unbanned { ip } | Self::Throttled { ip, .. } => *ip,
+        }
+    }
+
+    /// The event as a PUBLISH
+    pub fn to_message(&self) -> Message {
+        let (event, mut payload) = match self {
+            Self::Banned {
+                reason, duration, ..
+            } => {
+                let mut payload =
+                    HashMap::from([("reason".to_string(), Value::String(reason.clone()))]);
+                if let Some(duration) = duration {
+                    payload.insert(
+                        "duration".to_string(),
+                        Value::Int(duration.as_secs() as i64),
+                    );
+                }
+                ("banned", payload)
+            }
+            Self::Unbanned { .. } => ("unbanned", HashMap::new()),
+            Self::Throttled { attempts, .. } => (
+                "throttled",
+                HashMap::from([("attempts".to_string(), Value::Int(*attempts as i64))]),
+            ),
+        };
+        payload.insert("event".to_string(), Value::String(event.to_string()));
+        payload.insert("ip".to_string(), Value::String(self.ip().to_string()));
+        Message::Publish(PublishMessage {
+            address: SECURITY_ADDRESS.to_string(),
+            signal: Some(SignalType::Event),
+            value: None,
+            payload: Some(Value::Map(payload)),
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        })
+    }
+}
+
+/// Events in the current window for one IP
+struct Counter {
+    start: Instant,
+    count: u32,
+    reported: bool,
+}
+
+impl Counter {
+    /// Count one more event, starting a new window if the last one ended
+    fn hit(&mut self, window: Duration, now: Instant) -> u32 {
+        if now.duration_since(self.start) >= window {
+            *self = Counter::new(now);
+        }
+        self.count += 1;
+        self.count
+    }
+
+    fn new(now: Instant) -> Self {
+        Self {
+            start: now,
+            count: 0,
+            reported: false,
+        }
+    }
+}
+
+/// Per-IP connection counts, auth failures and bans
+pub struct BanList {
+    config: ConnectionGuard,
+    bans: DashMap<IpAddr, Ban>,
+    connects: DashMap<IpAddr, Counter>,
+    failures: DashMap<IpAddr, Counter>,
+    /// Remote IP of each connected session, to close them on a ban
+    peers: DashMap<SessionId, IpAddr>,
+    checks: AtomicUsize,
+}
+
+impl BanList {
+    pub fn new(config: ConnectionGuard) -> Self {
+        Self {
+            config,
+            bans: DashMap::new(),
+            connects: DashMap::new(),
+            failures: DashMap::new(),
+            peers: DashMap::new(),
+            checks: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &ConnectionGuard {
+        &self.config
+    }
+
+    /// Check a connection attempt from `ip`, counting it
+    pub fn admit(&self, ip: IpAddr) -> Admission {
+        let now = Instant::now();
+        if self.checks.fetch_add(1, Ordering::Relaxed) % SWEEP_INTERVAL == SWEEP_INTERVAL - 1 {
+            self.sweep(now);
+        }
+        if self.is_banned_at(ip, now) {
+            return Admission::Banned;
+        }
+        if self.config.max_connects == 0 {
+            return Admission::Accept;
+        }
+
+        let mut counter = self.connects.entry(ip).or_insert_with(|| Counter::new(now));
+        let attempts = counter.hit(self.config.window, now);
+        if attempts <= self.config.max_connects {
+            return Admission::Accept;
+        }
+        let event = (!counter.reported).then(|| {
+            counter.reported = true;
+            SecurityEvent::Throttled { ip, attempts }
+        });
+        Admission::Throttled { event }
+    }
+
+    /// Count a failed authentication from `ip`; returns the ban event if it
+    /// pushed the IP over the limit
+    pub fn auth_failed(&self, ip: IpAddr) -> Option<SecurityEvent> {
+        if self.config.max_auth_failures == 0 {
+            return None;
+        }
+        let now = Instant::now();
+        let failures = self
+            .failures
+            .entry(ip)
+            .or_insert_with(|| Counter::new(now))
+            .hit(self.config.window, now);
+        if failures < self.config.max_auth_failures {
+            return None;
+        }
+        self.failures.remove(&ip);
+        Some(self.ban(
+            ip,
+            Some(self.config.ban_duration),
+            format!("{} failed authentications", failures),
+        ))
+    }
+
+    /// Ban `ip`, replacing any ban it already has
+    pub fn ban(
+        &self,
+        ip: IpAddr,
+        duration: Option<Duration>,
+        reason: impl Into<String>,
+    ) -> SecurityEvent {
+        let reason = reason.into();
+        self.bans.insert(
+            ip,
+            Ban {
+                reason: reason.clone(),
+                expires: duration.map(|duration| Instant::now() + duration),
+            },
+        );
+        SecurityEvent::Banned {
+            ip,
+            reason,
+            duration,
+        }
+    }
+
+    /// Lift the ban on `ip`; `None` if it wasn't banned
+    pub fn unban(&self, ip: IpAddr) -> Option<SecurityEvent> {
+        let (_, ban) = self.bans.remove(&ip)?;
+        self.connects.remove(&ip);
+        ban.is_active(Instant::now())
+            .then_some(SecurityEvent::Unbanned { ip })
+    }
+
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        self.is_banned_at(ip, Instant::now())
+    }
+
+    fn is_banned_at(&self, ip: IpAddr, now: Instant) -> bool {
+        let active = match self.bans.get(&ip) {
+            Some(ban) => ban.is_active(now),
+            None => return false,
+        };
+        if !active {
+            self.bans.remove(&ip);
+        }
+        active
+    }
+
+    /// The ban on `ip`, if it is banned
+    pub fn get(&self, ip: IpAddr) -> Option<Ban> {
+        self.bans
+            .get(&ip)
+            .map(|ban| ban.clone())
+            .filter(|ban| ban.is_active(Instant::now()))
+    }
+
+    /// Active bans
+    pub fn bans(&self) -> Vec<(IpAddr, Ban)> {
+        let now = Instant::now();
+        self.bans
+            .iter()
+            .filter(|entry| entry.is_active(now))
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Remember the remote IP of a connected session
+    pub fn track(&self, session_id: &SessionId, ip: IpAddr) {
+        self.peers.insert(session_id.clone(), ip);
+    }
+
+    /// Forget a session that has disconnected
+    pub fn untrack(&self, session_id: &SessionId) {
+        self.peers.remove(session_id);
+    }
+
+    /// Connected sessions whose remote IP is `ip`
+    pub fn sessions_from(&self, ip: IpAddr) -> Vec<SessionId> {
+        self.peers
+            .iter()
+            .filter(|entry| *entry.value() == ip)
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// Drop lapsed bans and counters from past windows
+    fn sweep(&self, now: Instant) {
+        let window = self.config.window;
+        self.bans.retain(|_, ban| ban.is_active(now));
+        self.connects
+            .retain(|_, counter| now.duration_since(counter.start) < window);
+        self.failures
+            .retain(|_, counter| now.duration_since(counter.start) < window);
+    }
+
+    /// SNAPSHOT of the bans under `address`
+    pub fn snapshot(&self, address: &str) -> SnapshotMessage {
+        let params = self
+            .bans()
+            .into_iter()
+            .map(|(ip, ban)| (format!("{}/{}", BANS_PREFIX, ip), ban))
+            .filter(|(ban_address, _)| address == BANS_PREFIX || ban_address.as_str() == address)
+            .map(|(address, ban)| ParamValue {
+                address,
+                value: ban.to_value(),
+                revision: 0,
+                writer: None,
+                timestamp: None,
+            })
+            .collect();
+        SnapshotMessage {
+            params,
+            correlation_id: None,
+            page: None,
+            sync: None,
+        }
+    }
+}
+
+/// Whether `address` is the ban list or one of its entries
+pub fn is_ban_address(address: &str) -> bool {
+    address
+        .strip_prefix(BANS_PREFIX)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Extract the IP from `/$sys/security/bans/<ip>`
+pub fn ip_from_address(address: &str) -> Option<IpAddr> {
+    address
+        .strip_prefix(BANS_PREFIX)?
+        .strip_prefix('/')?
+        .parse()
+        .ok()
+}
+
+/// Read the duration and reason of a ban from a SET value
+pub fn ban_from_value(value: &Value) -> Result<(Option<Duration>, String), String> {
+    const MANUAL: &str = "banned by an admin";
+    let duration = |value: Option<&Value>| -> Result<Option<Duration>, String> {
+        match value {
+            None | Some(Value::Null) | Some(Value::Bool(true)) => Ok(None),
+            Some(Value::String(s)) => parse_duration(s).map(Some).map_err(|e| e.to_string()),
+            Some(other) => other
+                .as_i64()
+                .filter(|secs| *secs > 0)
+                .map(|secs| Some(Duration::from_secs(secs as u64)))
+                .ok_or_else(|| "invalid ban duration".to_string()),
+        }
+    };
+    match value {
+        Value::Map(map) => {
+            let reason = map
+                .get("reason")
+                .and_then(Value::as_str)
+                .unwrap_or(MANUAL)
+                .to_string();
+            Ok((duration(map.get("duration"))?, reason))
+        }
+        other => Ok((duration(Some(other))?, MANUAL.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_throttle_reports_once_per_window() {
+        let bans = BanList::new(ConnectionGuard::default().throttle(2, Duration::from_millis(50)));
+        let host = ip("10.0.0.1");
+        assert_eq!(bans.admit(host), Admission::Accept);
+        assert_eq!(bans.admit(host), Admission::Accept);
+        assert!(matches!(
+            bans.admit(host),
+            Admission::Throttled {
+                event: Some(SecurityEvent::Throttled { attempts: 3, .. })
+            }
+        ));
+        assert_eq!(bans.admit(host), Admission::Throttled { event: None });
+        // Other hosts have their own count
+        assert_eq!(bans.admit(ip("10.0.0.2")), Admission::Accept);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(bans.admit(host), Admission::Accept);
+    }
+
+    #[test]
+    fn test_auth_failures_ban() {
+        let bans = BanList::new(ConnectionGuard::default().ban_after(2, Duration::from_millis(50)));
+        let host = ip("::1");
+        assert_eq!(bans.auth_failed(host), None);
+        assert!(matches!(
+            bans.auth_failed(host),
+            Some(SecurityEvent::Banned {
+                duration: Some(_),
+                ..
+            })
+        ));
+        assert_eq!(bans.admit(host), Admission::Banned);
+        assert_eq!(bans.bans().len(), 1);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(bans.admit(host), Admission::Accept);
+        assert!(bans.bans().is_empty());
+    }
+
+    #[test]
+    fn test_manual_ban() {
+        let bans = BanList::new(ConnectionGuard::default());
+        let host = ip("192.168.1.5");
+        bans.ban(host, None, "abuse");
+        assert!(bans.is_banned(host));
+        assert_eq!(bans.get(host).unwrap().reason, "abuse");
+
+        bans.track(&"s1".to_string(), host);
+        bans.track(&"s2".to_string(), ip("192.168.1.6"));
+        assert_eq!(bans.sessions_from(host), vec!["s1".to_string()]);
+
+        assert_eq!(bans.unban(host), Some(SecurityEvent::Unbanned { ip: host }));
+        assert_eq!(bans.unban(host), None);
+        assert_eq!(bans.admit(host), Admission::Accept);
+    }
+
+    #[test]
+    fn test_addresses_and_values() {
+        assert!(is_ban_address("/$sys/security/bans"));
+        assert!(is_ban_address("/$sys/security/bans/10.0.0.1"));
+        assert!(!is_ban_address("/$sys/security"));
+        assert_eq!(
+            ip_from_address("/$sys/security/bans/fe80::1"),
+            Some(ip("fe80::1"))
+        );
+        assert_eq!(ip_from_address("/$sys/security/bans/host"), None);
+
+        assert_eq!(ban_from_value(&Value::Bool(true)).unwrap().0, None);
+        assert_eq!(
+            ban_from_value(&Value::String("30m".into())).unwrap().0,
+            Some(Duration::from_secs(1800))
+        );
+        let map = Value::Map(HashMap::from([
+            ("duration".to_string(), Value::Int(60)),
+            ("reason".to_string(), Value::String("scanner".into())),
+        ]));
+        assert_eq!(
+            ban_from_value(&map).unwrap(),
+            (Some(Duration::from_secs(60)), "scanner".to_string())
+        );
+        assert!(ban_from_value(&Value::Int(-5)).is_err());
+    }
+}
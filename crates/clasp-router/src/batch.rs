@@ -0,0 +1,127 @@
+//! Batched param delivery
+//!
+//! A dashboard subscribed to `/**` would otherwise get one SET frame per
+//! change. Subscriptions with [`SubscribeOptions::batch`] set instead
+//! collect changes for the batch window and receive them as one SNAPSHOT,
+//! keeping only the latest value of each address. Events, streams and
+//! gestures are always delivered immediately.
+//!
+//! [`SubscribeOptions::batch`]: clasp_core::SubscribeOptions::batch
+
+use crate::budget::value_heap_bytes;
+use clasp_core::ParamValue;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Longest batch window a subscription may ask for
+pub const MAX_BATCH_WINDOW: Duration = Duration::from_secs(1);
+
+/// Batch window for a subscription's `batch` option
+///
+/// Returns `None` (deliver immediately) for a missing or zero window.
+pub fn batch_window(batch_ms: Option<u32>) -> Option<Duration> {
+    match batch_ms {
+        None | Some(0) => None,
+        Some(ms) => Some(Duration::from_millis(ms as u64).min(MAX_BATCH_WINDOW)),
+    }
+}
+
+/// Param changes waiting for the end of a batch window
+#[derive(Debug, Default)]
+pub struct ParamBatch {
+    params: Vec<ParamValue>,
+    /// Position of each address in `params`
+    index: HashMap<String, usize>,
+    /// Estimated bytes held, for the memory budget
+    bytes: usize,
+}
+
+impl ParamBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a change, replacing any pending change to the same address
+    ///
+    /// Returns true when the batch was empty, i.e. a flush needs scheduling.
+    pub fn push(&mut self, param: ParamValue) -> bool {
+        let was_empty = self.params.is_empty();
+        self.bytes += value_heap_bytes(&param.value);
+        match self.index.get(&param.address) {
+            Some(&i) => {
+                self.bytes -= value_heap_bytes(&self.params[i].value);
+                self.params[i] = param;
+            }
+            None => {
+                self.bytes += std::mem::size_of::<ParamValue>() + 2 * param.address.len();
+                self.index.insert(param.address.clone(), self.params.len());
+                self.params.push(param);
+            }
+        }
+        was_empty
+    }
+
+    /// Take all pending changes in first-change order
+    pub fn take(&mut self) -> Vec<ParamValue> {
+        self.index.clear();
+        self.bytes = 0;
+        std::mem::take(&mut self.params)
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Estimated bytes held by pending changes
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clasp_core::Value;
+
+    fn param(address: &str, value: f64, revision: u64) -> ParamValue {
+        ParamValue {
+            address: address.to_string(),
+            value: Value::Float(value),
+            revision,
+            writer: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_latest_value_wins() {
+        let mut batch = ParamBatch::new();
+        assert!(batch.push(param("/a", 0.1, 1)));
+        assert!(!batch.push(param("/b", 0.2, 1)));
+        assert!(!batch.push(param("/a", 0.3, 2)));
+        assert_eq!(batch.len(), 2);
+        assert!(batch.bytes() > 0);
+
+        let params = batch.take();
+        assert_eq!(batch.bytes(), 0);
+        assert_eq!(params[0].address, "/a");
+        assert_eq!(params[0].value, Value::Float(0.3));
+        assert_eq!(params[0].revision, 2);
+        assert_eq!(params[1].address, "/b");
+
+        assert!(batch.is_empty());
+        assert!(batch.push(param("/a", 0.4, 3)));
+    }
+
+    #[test]
+    fn test_batch_window() {
+        assert_eq!(batch_window(None), None);
+        assert_eq!(batch_window(Some(0)), None);
+        assert_eq!(batch_window(Some(16)), Some(Duration::from_millis(16)));
+        assert_eq!(batch_window(Some(60_000)), Some(MAX_BATCH_WINDOW));
+    }
+}
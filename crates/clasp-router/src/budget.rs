@@ -0,0 +1,262 @@
+//! Memory budget for retained state
+//!
+//! A runaway publisher writing to ever-new addresses grows the router's
+//! retained state without bound. With a [`MemoryBudget`] configured, the
+//! router keeps an estimate of the bytes held by retained params and by
+//! changes waiting in session batch queues; when the total goes over the
+//! budget it evicts retained params until usage is back under it, with some
+//! headroom so the next few SETs don't each trigger another pass.
+//!
+//! Victims are picked by priority first (lowest evicted first, see
+//! [`EvictionPriority`]) and by [`EvictionPolicy`] within a priority. Locked
+//! params are never evicted. Each pass is reported as an event on
+//! [`EVICTIONS_ADDRESS`].
+//!
+//! Sizes are estimates (address, writer, value and bookkeeping), not exact
+//! allocator figures; set the budget with some margin.
+
+use clasp_core::state::ParamState;
+use clasp_core::{address::glob_match, Message, PublishMessage, SignalType, Value};
+use std::collections::HashMap;
+
+/// Address eviction reports are published to
+pub const EVICTIONS_ADDRESS: &str = "/$sys/evictions";
+
+/// Fraction of the budget eviction frees up beyond the overshoot
+const HEADROOM_DIVISOR: usize = 10;
+
+/// Which params go first within the same priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Least recently read or written (default)
+    #[default]
+    Lru,
+    /// Least recently written
+    OldestFirst,
+}
+
+/// Eviction priority for addresses matching a pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvictionPriority {
+    /// Address pattern, e.g. `/scratch/**`
+    pub pattern: String,
+    /// Lower priorities are evicted first; unmatched addresses have 0
+    pub priority: i32,
+}
+
+impl EvictionPriority {
+    pub fn new(pattern: impl Into<String>, priority: i32) -> Self {
+        Self {
+            pattern: pattern.into(),
+            priority,
+        }
+    }
+}
+
+/// Memory budget configuration (no limit by default)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Bytes of retained state and queued changes (None = unlimited)
+    pub max_bytes: Option<usize>,
+    /// Order of eviction within a priority
+    pub policy: EvictionPolicy,
+    priorities: Vec<EvictionPriority>,
+}
+
+impl MemoryBudget {
+    /// Budget of `max_bytes`, evicting least recently used params first
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes: Some(max_bytes),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_policy(mut self, policy: EvictionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Add a priority rule; earlier rules take precedence
+    pub fn with_priority(mut self, priority: EvictionPriority) -> Self {
+        self.priorities.push(priority);
+        self
+    }
+
+    /// The configured priority rules
+    pub fn priorities(&self) -> &[EvictionPriority] {
+        &self.priorities
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_bytes.is_some()
+    }
+
+    /// Eviction priority of `address`
+    pub fn priority_for(&self, address: &str) -> i32 {
+        self.priorities
+            .iter()
+            .find(|rule| glob_match(&rule.pattern, address))
+            .map_or(0, |rule| rule.priority)
+    }
+
+    /// Bytes of retained state to evict down to, if `usage` is over budget
+    ///
+    /// `queued` is the part of `usage` held in queues, which eviction can't
+    /// free.
+    pub fn eviction_target(&self, usage: usize, queued: usize) -> Option<usize> {
+        let max = self.max_bytes?;
+        if usage <= max {
+            return None;
+        }
+        Some((max - max / HEADROOM_DIVISOR).saturating_sub(queued))
+    }
+
+    /// Sort key of a param; params with smaller keys are evicted first
+    pub(crate) fn eviction_key(&self, address: &str, state: &ParamState) -> (i32, u64) {
+        let age = match self.policy {
+            EvictionPolicy::Lru => state.last_accessed,
+            EvictionPolicy::OldestFirst => state.timestamp,
+        };
+        (self.priority_for(address), age)
+    }
+}
+
+/// One eviction pass, as published on [`EVICTIONS_ADDRESS`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvictionReport {
+    /// Evicted addresses
+    pub addresses: Vec<String>,
+    /// Estimated bytes freed
+    pub freed_bytes: usize,
+    /// Estimated usage that triggered the pass
+    pub usage_bytes: usize,
+    /// The configured budget
+    pub budget_bytes: usize,
+}
+
+impl EvictionReport {
+    /// The report as an event
+    pub fn to_message(&self) -> Message {
+        let payload = HashMap::from([
+            (
+                "addresses".to_string(),
+                Value::Array(
+                    self.addresses
+                        .iter()
+                        .map(|address| Value::String(address.clone()))
+                        .collect(),
+                ),
+            ),
+            ("count".to_string(), Value::Int(self.addresses.len() as i64)),
+            ("freed".to_string(), Value::Int(self.freed_bytes as i64)),
+            ("usage".to_string(), Value::Int(self.usage_bytes as i64)),
+            ("budget".to_string(), Value::Int(self.budget_bytes as i64)),
+        ]);
+        Message::Publish(PublishMessage {
+            address: EVICTIONS_ADDRESS.to_string(),
+            signal: Some(SignalType::Event),
+            value: None,
+            payload: Some(Value::Map(payload)),
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        })
+    }
+}
+
+/// Estimated bytes held by a retained param
+pub fn param_bytes(address: &str, state: &ParamState) -> usize {
+    std::mem::size_of::<ParamState>()
+        + address.len()
+        + state.writer.len()
+        + state.lock_holder.as_ref().map_or(0, String::len)
+        + value_heap_bytes(&state.value)
+}
+
+/// Estimated heap bytes owned by a value (its inline size is not included)
+pub fn value_heap_bytes(value: &Value) -> usize {
+    match value {
+        Value::Null | Value::Bool(_) | Value::Int(_) | Value::Float(_) => 0,
+        Value::String(s) => s.len(),
+        Value::Bytes(b) => b.len(),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| std::mem::size_of::<Value>() + value_heap_bytes(v))
+            .sum(),
+        Value::Map(map) => map
+            .iter()
+            .map(|(k, v)| {
+                std::mem::size_of::<String>()
+                    + k.len()
+                    + std::mem::size_of::<Value>()
+                    + value_heap_bytes(v)
+            })
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priorities() {
+        let budget = MemoryBudget::new(1024)
+            .with_priority(EvictionPriority::new("/show/cue", 20))
+            .with_priority(EvictionPriority::new("/show/**", 10))
+            .with_priority(EvictionPriority::new("/scratch/**", -5));
+
+        assert_eq!(budget.priority_for("/show/cue"), 20);
+        assert_eq!(budget.priority_for("/show/fader/1"), 10);
+        assert_eq!(budget.priority_for("/scratch/x"), -5);
+        assert_eq!(budget.priority_for("/other"), 0);
+    }
+
+    #[test]
+    fn test_eviction_target() {
+        assert_eq!(MemoryBudget::default().eviction_target(1 << 30, 0), None);
+
+        let budget = MemoryBudget::new(1000);
+        assert_eq!(budget.eviction_target(1000, 0), None);
+        assert_eq!(budget.eviction_target(1001, 0), Some(900));
+        assert_eq!(budget.eviction_target(1200, 100), Some(800));
+        assert_eq!(budget.eviction_target(2000, 1500), Some(0));
+    }
+
+    #[test]
+    fn test_value_sizes() {
+        assert_eq!(value_heap_bytes(&Value::Float(1.0)), 0);
+        assert_eq!(value_heap_bytes(&Value::String("abcd".into())), 4);
+        assert_eq!(value_heap_bytes(&Value::Bytes(vec![0; 100])), 100);
+
+        let nested = Value::Array(vec![Value::String("ab".into()), Value::Int(1)]);
+        assert_eq!(
+            value_heap_bytes(&nested),
+            2 * std::mem::size_of::<Value>() + 2
+        );
+    }
+
+    #[test]
+    fn test_report_message() {
+        let report = EvictionReport {
+            addresses: vec!["/a".into(), "/b".into()],
+            freed_bytes: 300,
+            usage_bytes: 1100,
+            budget_bytes: 1000,
+        };
+        let Message::Publish(publish) = report.to_message() else {
+            panic!("expected publish");
+        };
+        assert_eq!(publish.address, EVICTIONS_ADDRESS);
+        assert_eq!(publish.signal, Some(SignalType::Event));
+        let Some(Value::Map(payload)) = publish.payload else {
+            panic!("expected map payload");
+        };
+        assert_eq!(payload["count"], Value::Int(2));
+        assert_eq!(payload["freed"], Value::Int(300));
+    }
+}
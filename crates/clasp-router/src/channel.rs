@@ -0,0 +1,152 @@
+//! Connection sharing
+//!
+//! Several logical clients can share one physical connection, e.g. the
+//! independent modules of one desktop app. Each opens its own session with a
+//! HELLO wrapped in a CHANNEL message and gets its own name, token and
+//! subscriptions; the router tracks it as a sub-session of the connection's
+//! main session. A sub-session's [`ChannelSender`] wraps everything sent to
+//! it in a CHANNEL with its id, so delivery treats it like any other session.
+
+use crate::session::Session;
+use async_trait::async_trait;
+use bytes::Bytes;
+use clasp_core::{codec, ChannelMessage, ErrorMessage, Message};
+use clasp_transport::{TransportError, TransportSender};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+type Result<T> = std::result::Result<T, TransportError>;
+
+/// Sender for one channel of a shared connection
+pub struct ChannelSender {
+    channel: u16,
+    inner: Arc<dyn TransportSender>,
+    closed: AtomicBool,
+}
+
+impl ChannelSender {
+    pub fn new(channel: u16, inner: Arc<dyn TransportSender>) -> Self {
+        Self {
+            channel,
+            inner,
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Channel id on the shared connection
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Mark the channel closed without telling the client, e.g. because the
+    /// client closed it
+    pub fn detach(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    fn wrap(&self, data: &[u8]) -> Result<Bytes> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(TransportError::ConnectionClosed);
+        }
+        codec::encode_channel_frame(data, self.channel)
+            .map_err(|e| TransportError::SendFailed(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl TransportSender for ChannelSender {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        let data = self.wrap(&data)?;
+        self.inner.send(data).await
+    }
+
+    fn try_send(&self, data: Bytes) -> Result<()> {
+        let data = self.wrap(&data)?;
+        self.inner.try_send(data)
+    }
+
+    fn try_send_expiring(&self, data: Bytes, expires_at: Instant) -> Result<()> {
+        let data = self.wrap(&data)?;
+        self.inner.try_send_expiring(data, expires_at)
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.closed.load(Ordering::SeqCst) && self.inner.is_connected()
+    }
+
+    /// Close this channel only; the shared connection stays open
+    async fn close(&self) -> Result<()> {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let notice = codec::encode(&Message::Channel(ChannelMessage::close(self.channel)))
+            .map_err(|e| TransportError::SendFailed(e.to_string()))?;
+        self.inner.send(notice).await
+    }
+}
+
+/// Sub-sessions of one connection, by channel id
+#[derive(Default)]
+pub(crate) struct Channels {
+    open: HashMap<u16, (Arc<Session>, Arc<ChannelSender>)>,
+}
+
+impl Channels {
+    /// The sub-session on `channel`, if it is open
+    pub fn get(&self, channel: u16) -> Option<(Arc<Session>, Arc<ChannelSender>)> {
+        self.open
+            .get(&channel)
+            .filter(|(_, sender)| sender.is_connected())
+            .cloned()
+    }
+
+    pub fn insert(&mut self, channel: u16, session: Arc<Session>, sender: Arc<ChannelSender>) {
+        self.open.insert(channel, (session, sender));
+    }
+
+    /// Forget a channel, returning its sub-session for cleanup
+    pub fn remove(&mut self, channel: u16) -> Option<Arc<Session>> {
+        let (session, sender) = self.open.remove(&channel)?;
+        sender.detach();
+        Some(session)
+    }
+
+    /// Forget channels the router has closed, e.g. after a takeover
+    pub fn remove_closed(&mut self) -> Vec<Arc<Session>> {
+        let closed: Vec<u16> = self
+            .open
+            .iter()
+            .filter(|(_, (_, sender))| !sender.is_connected())
+            .map(|(&channel, _)| channel)
+            .collect();
+        closed
+            .into_iter()
+            .filter_map(|channel| self.remove(channel))
+            .collect()
+    }
+
+    /// Forget all channels, e.g. when the connection goes away
+    pub fn drain(&mut self) -> Vec<Arc<Session>> {
+        self.open
+            .drain()
+            .map(|(_, (session, sender))| {
+                sender.detach();
+                session
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.open.len()
+    }
+}
+
+/// Refuse a channel with an ERROR, then close it
+pub(crate) async fn refuse(sender: &ChannelSender, error: ErrorMessage) {
+    if let Ok(bytes) = codec::encode(&Message::Error(error)) {
+        let _ = sender.send(bytes).await;
+    }
+    let _ = sender.close().await;
+}
@@ -0,0 +1,144 @@
+//! Duplicate SET suppression
+//!
+//! Bridges that poll hardware re-send the same value every tick. With
+//! [`SetDedup`] configured, a SET whose value matches what the router already
+//! holds for that address is acknowledged but neither applied nor forwarded,
+//! so subscribers only see actual changes.
+//!
+//! Rules are glob patterns; the first matching rule wins. A rule may carry an
+//! epsilon, in which case numbers (and arrays of numbers) within that distance
+//! of the retained value count as unchanged. Since suppressed SETs don't
+//! update the retained value, slow drift is still forwarded once it adds up
+//! to more than the epsilon.
+
+use clasp_core::{address::glob_match, Value};
+
+/// A pattern whose unchanged SETs are suppressed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DedupRule {
+    /// Address pattern, e.g. `/lights/**`
+    pub pattern: String,
+    /// Numbers closer than this to the retained value count as equal
+    pub epsilon: Option<f64>,
+}
+
+impl DedupRule {
+    /// Suppress exact repeats on `pattern`
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            epsilon: None,
+        }
+    }
+
+    /// Also treat numbers within `epsilon` of the retained value as repeats
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = Some(epsilon.abs());
+        self
+    }
+
+    /// Whether `new` is a repeat of `current` under this rule
+    pub fn is_duplicate(&self, current: &Value, new: &Value) -> bool {
+        match self.epsilon {
+            Some(epsilon) => approx_eq(current, new, epsilon),
+            None => current == new,
+        }
+    }
+}
+
+/// Duplicate suppression rules (empty = disabled)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SetDedup {
+    rules: Vec<DedupRule>,
+}
+
+impl SetDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule; earlier rules take precedence
+    pub fn with_rule(mut self, rule: DedupRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The configured rules
+    pub fn rules(&self) -> &[DedupRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rule that applies to `address`, if any
+    pub fn rule_for(&self, address: &str) -> Option<&DedupRule> {
+        self.rules
+            .iter()
+            .find(|rule| glob_match(&rule.pattern, address))
+    }
+
+    /// Whether a SET of `new` to `address` repeats the retained `current`
+    pub fn is_duplicate(&self, address: &str, current: &Value, new: &Value) -> bool {
+        self.rule_for(address)
+            .is_some_and(|rule| rule.is_duplicate(current, new))
+    }
+}
+
+fn approx_eq(a: &Value, b: &Value, epsilon: f64) -> bool {
+    if let (Some(x), Some(y)) = (a.as_f64(), b.as_f64()) {
+        return (x - y).abs() <= epsilon;
+    }
+    match (a, b) {
+        (Value::Array(xs), Value::Array(ys)) => {
+            xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| approx_eq(x, y, epsilon))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_rule() {
+        let dedup = SetDedup::new().with_rule(DedupRule::new("/sensors/**"));
+
+        assert!(dedup.is_duplicate("/sensors/a", &Value::Int(1), &Value::Int(1)));
+        assert!(!dedup.is_duplicate("/sensors/a", &Value::Int(1), &Value::Int(2)));
+        assert!(!dedup.is_duplicate("/sensors/a", &Value::Float(0.5), &Value::Float(0.50001)));
+        // Not covered by any rule
+        assert!(!dedup.is_duplicate("/other", &Value::Int(1), &Value::Int(1)));
+    }
+
+    #[test]
+    fn test_epsilon_rule() {
+        let rule = DedupRule::new("/fader/*").with_epsilon(0.01);
+
+        assert!(rule.is_duplicate(&Value::Float(0.5), &Value::Float(0.505)));
+        assert!(!rule.is_duplicate(&Value::Float(0.5), &Value::Float(0.52)));
+        assert!(rule.is_duplicate(&Value::Int(1), &Value::Float(1.001)));
+        assert!(rule.is_duplicate(
+            &Value::Array(vec![Value::Float(0.1), Value::Float(0.2)]),
+            &Value::Array(vec![Value::Float(0.1), Value::Float(0.205)]),
+        ));
+        assert!(!rule.is_duplicate(
+            &Value::Array(vec![Value::Float(0.1)]),
+            &Value::Array(vec![Value::Float(0.1), Value::Float(0.2)]),
+        ));
+        assert!(rule.is_duplicate(&Value::String("a".into()), &Value::String("a".into())));
+        assert!(!rule.is_duplicate(&Value::String("1".into()), &Value::Int(1)));
+    }
+
+    #[test]
+    fn test_first_rule_wins() {
+        let dedup = SetDedup::new()
+            .with_rule(DedupRule::new("/mix/master"))
+            .with_rule(DedupRule::new("/mix/**").with_epsilon(0.1));
+
+        assert!(!dedup.is_duplicate("/mix/master", &Value::Float(0.5), &Value::Float(0.55)));
+        assert!(dedup.is_duplicate("/mix/ch1", &Value::Float(0.5), &Value::Float(0.55)));
+    }
+}
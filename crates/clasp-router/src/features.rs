@@ -0,0 +1,261 @@
+//! HELLO/WELCOME feature enforcement
+//!
+//! Clients declare the signal types they speak in HELLO (`param`, `event`,
+//! `stream`, `gesture`, `timeline`) and the router advertises its own in
+//! WELCOME. With [`RouterConfig::enforce_features`](crate::RouterConfig) set,
+//! a SET, PUBLISH or BUNDLE carrying a signal type that was not negotiated by
+//! both sides is rejected with an ERROR naming the missing feature.
+//!
+//! [`FeatureStats`] counts accepted and rejected messages per feature whether
+//! or not enforcement is on, which makes it easy to see what a client
+//! actually uses before turning enforcement on.
+
+use clasp_core::{Message, PublishMessage, SignalType};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// HELLO feature name for a signal type
+pub fn feature_name(signal: SignalType) -> &'static str {
+    match signal {
+        SignalType::Param => "param",
+        SignalType::Event => "event",
+        SignalType::Stream => "stream",
+        SignalType::Gesture => "gesture",
+        SignalType::Timeline => "timeline",
+    }
+}
+
+/// Signal type of a PUBLISH, inferred from its fields when not set
+pub fn publish_signal_type(msg: &PublishMessage) -> SignalType {
+    msg.signal.unwrap_or(if msg.phase.is_some() {
+        SignalType::Gesture
+    } else if msg.timeline.is_some() {
+        SignalType::Timeline
+    } else if msg.samples.is_some() {
+        SignalType::Stream
+    } else {
+        SignalType::Event
+    })
+}
+
+/// Signal types a client message sends (empty for non-signal messages)
+///
+/// SETs to the reserved `/$sys/` namespace are control messages, not params.
+pub fn signal_types(msg: &Message) -> Vec<SignalType> {
+    match msg {
+        Message::Set(set) if set.address.starts_with("/$sys/") => Vec::new(),
+        Message::Set(_) => vec![SignalType::Param],
+        Message::Publish(publish) => vec![publish_signal_type(publish)],
+        Message::Bundle(bundle) => bundle.messages.iter().flat_map(signal_types).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Why a message was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureDenied {
+    /// The client did not declare the feature in HELLO
+    NotDeclared(SignalType),
+    /// The router does not advertise the feature
+    NotSupported(SignalType),
+}
+
+impl FeatureDenied {
+    /// The missing feature
+    pub fn signal(&self) -> SignalType {
+        match self {
+            FeatureDenied::NotDeclared(signal) | FeatureDenied::NotSupported(signal) => *signal,
+        }
+    }
+
+    /// Human-readable reason for the ERROR message
+    pub fn message(&self) -> String {
+        match self {
+            FeatureDenied::NotDeclared(signal) => format!(
+                "Feature '{}' required (not declared in HELLO)",
+                feature_name(*signal)
+            ),
+            FeatureDenied::NotSupported(signal) => format!(
+                "Feature '{}' required (not supported by this router)",
+                feature_name(*signal)
+            ),
+        }
+    }
+}
+
+/// Message counts for one feature
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureUsage {
+    /// Messages that used the feature
+    pub accepted: u64,
+    /// Messages rejected because the feature was not negotiated
+    pub rejected: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// Router-wide per-feature message counters
+#[derive(Debug, Default)]
+pub struct FeatureStats {
+    counters: DashMap<SignalType, Counter>,
+}
+
+impl FeatureStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check `msg` against the negotiated features and count it.
+    ///
+    /// Returns the first missing feature when `enforce` is set; otherwise the
+    /// message is always accepted.
+    pub fn check(
+        &self,
+        msg: &Message,
+        client_features: &[String],
+        server_features: &[String],
+        enforce: bool,
+    ) -> Result<(), FeatureDenied> {
+        let signals = signal_types(msg);
+
+        if enforce {
+            let has = |features: &[String], signal: SignalType| {
+                features.iter().any(|f| f == feature_name(signal))
+            };
+            let denied = signals.iter().find_map(|&signal| {
+                if !has(server_features, signal) {
+                    Some(FeatureDenied::NotSupported(signal))
+                } else if !has(client_features, signal) {
+                    Some(FeatureDenied::NotDeclared(signal))
+                } else {
+                    None
+                }
+            });
+            if let Some(denied) = denied {
+                self.counters
+                    .entry(denied.signal())
+                    .or_default()
+                    .rejected
+                    .fetch_add(1, Ordering::Relaxed);
+                return Err(denied);
+            }
+        }
+
+        for signal in signals {
+            self.counters
+                .entry(signal)
+                .or_default()
+                .accepted
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Counts per feature name
+    pub fn snapshot(&self) -> BTreeMap<String, FeatureUsage> {
+        self.counters
+            .iter()
+            .map(|entry| {
+                (
+                    feature_name(*entry.key()).to_string(),
+                    FeatureUsage {
+                        accepted: entry.accepted.load(Ordering::Relaxed),
+                        rejected: entry.rejected.load(Ordering::Relaxed),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clasp_core::{BundleMessage, SetMessage, Value};
+
+    fn features(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn gesture() -> Message {
+        Message::Publish(PublishMessage {
+            address: "/touch".to_string(),
+            signal: None,
+            value: None,
+            payload: None,
+            samples: None,
+            rate: None,
+            id: Some(1),
+            phase: Some(clasp_core::GesturePhase::Start),
+            timestamp: None,
+            timeline: None,
+        })
+    }
+
+    fn set() -> Message {
+        Message::Set(SetMessage {
+            address: "/a".to_string(),
+            value: Value::Int(1),
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        })
+    }
+
+    #[test]
+    fn test_infers_publish_signal_type() {
+        assert_eq!(signal_types(&gesture()), vec![SignalType::Gesture]);
+        let bundle = Message::Bundle(BundleMessage {
+            timestamp: None,
+            messages: vec![set(), gesture()],
+            correlation_id: None,
+        });
+        assert_eq!(
+            signal_types(&bundle),
+            vec![SignalType::Param, SignalType::Gesture]
+        );
+    }
+
+    #[test]
+    fn test_enforcement() {
+        let stats = FeatureStats::new();
+        let server = features(&["param", "event", "gesture"]);
+        let client = features(&["param", "event"]);
+
+        assert!(stats.check(&set(), &client, &server, true).is_ok());
+        assert_eq!(
+            stats.check(&gesture(), &client, &server, true),
+            Err(FeatureDenied::NotDeclared(SignalType::Gesture))
+        );
+        assert_eq!(
+            stats.check(&gesture(), &server, &client, true),
+            Err(FeatureDenied::NotSupported(SignalType::Gesture))
+        );
+        // Counted but allowed when not enforcing
+        assert!(stats.check(&gesture(), &client, &server, false).is_ok());
+
+        let snapshot = stats.snapshot();
+        assert_eq!(
+            snapshot["param"],
+            FeatureUsage {
+                accepted: 1,
+                rejected: 0
+            }
+        );
+        assert_eq!(
+            snapshot["gesture"],
+            FeatureUsage {
+                accepted: 1,
+                rejected: 2
+            }
+        );
+    }
+}
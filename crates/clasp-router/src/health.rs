@@ -0,0 +1,81 @@
+//! Router health reporting
+//!
+//! [`HealthReport`] summarizes whether the router is accepting connections,
+//! the state of each transport and adapter, and session counts. It backs the
+//! `/healthz` and `/readyz` endpoints (feature `health`) and can be used
+//! directly by embedders with their own health checks.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use crate::error::Result;
+
+/// Lifecycle state of a transport or adapter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", content = "error", rename_all = "lowercase")]
+pub enum AdapterStatus {
+    /// Listening and accepting clients
+    Running,
+    /// Shut down cleanly
+    Stopped,
+    /// Exited with an error
+    Failed(String),
+}
+
+/// Point-in-time router health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Ready to take traffic (see [`HealthReport::is_ready`])
+    pub ready: bool,
+    /// At least one transport is accepting connections
+    pub accepting: bool,
+    /// Shutdown has begun; existing sessions are draining
+    pub draining: bool,
+    /// Connected sessions
+    pub sessions: usize,
+    /// Session limit
+    pub max_sessions: usize,
+    /// Active subscriptions
+    pub subscriptions: usize,
+    /// Stored params
+    pub params: usize,
+    /// Seconds since the router was created
+    pub uptime_secs: u64,
+    /// Status per transport/adapter (e.g. "WebSocket", "MQTT")
+    pub adapters: BTreeMap<String, AdapterStatus>,
+}
+
+impl HealthReport {
+    /// Ready when accepting, not draining, below the session limit and no
+    /// adapter has failed
+    pub fn is_ready(&self) -> bool {
+        self.accepting
+            && !self.draining
+            && self.sessions < self.max_sessions
+            && !self
+                .adapters
+                .values()
+                .any(|s| matches!(s, AdapterStatus::Failed(_)))
+    }
+}
+
+/// Record an adapter as running while `serve` runs, then as stopped or failed
+pub(crate) async fn track<F>(
+    statuses: &DashMap<String, AdapterStatus>,
+    name: &str,
+    serve: F,
+) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    statuses.insert(name.to_string(), AdapterStatus::Running);
+    let result = serve.await;
+    let status = match &result {
+        Ok(()) => AdapterStatus::Stopped,
+        Err(e) => AdapterStatus::Failed(e.to_string()),
+    };
+    statuses.insert(name.to_string(), status);
+    result
+}
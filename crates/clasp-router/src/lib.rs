@@ -83,35 +83,87 @@
 //!
 //! - [`router`] - Main Router struct and message handling
 //! - [`session`] - Client session management
+//! - [`batch`] - Batched param delivery for subscriptions with a batch window
+//! - [`channel`] - Logical clients sharing one connection
 //! - [`state`] - Parameter state storage
 //! - [`subscription`] - Pattern-based subscription matching
 //! - [`p2p`] - Peer-to-peer mesh networking support
+//! - [`profile`] - Preset configurations for live shows, IoT and development
 //! - [`gesture`] - Gesture move coalescing for bandwidth optimization
+//! - [`health`] - Health reporting for orchestrator probes
+//! - [`middleware`] - Hooks for custom per-session and per-message behavior
+//! - [`naming`] - Session id strategies, name collision and duplicate-session policies
+//! - [`token_admin`] - Runtime token management via `/$sys/tokens`
+//! - [`adapter_admin`] - Starting and stopping protocol adapters at runtime via `/$sys/adapters`
+//! - [`budget`] - Memory budget for retained state, reported on `/$sys/evictions`
+//! - [`loops`] - Feedback loop detection, reported on `/$sys/loops`
+//! - [`ban`] - Per-IP connection throttling and the ban list, reported on `/$sys/security`
+//! - [`wildcard`] - SETs and PUBLISHes fanned out to the retained addresses a pattern matches
 //! - [`error`] - Error types
 
+pub mod adapter_admin;
+pub mod ban;
+pub mod batch;
+pub mod budget;
+pub mod channel;
+pub mod dedup;
 pub mod error;
+pub mod features;
 pub mod gesture;
+pub mod health;
+pub mod loops;
+pub mod middleware;
+pub mod naming;
 pub mod p2p;
+pub mod profile;
 pub mod router;
 pub mod session;
 pub mod state;
 pub mod subscription;
+pub mod token_admin;
+pub mod wildcard;
 
 // Protocol adapters (feature-gated)
-#[cfg(any(feature = "mqtt-server", feature = "osc-server"))]
+#[cfg(any(
+    feature = "mqtt-server",
+    feature = "osc-server",
+    feature = "dashboard",
+    feature = "health"
+))]
 pub mod adapters;
 
+pub use adapter_admin::{AdapterConfig, AdapterKind};
+pub use ban::{Admission, Ban, BanList, ConnectionGuard, SecurityEvent};
+pub use batch::ParamBatch;
+pub use budget::{EvictionPolicy, EvictionPriority, EvictionReport, MemoryBudget};
+pub use channel::ChannelSender;
+pub use dedup::{DedupRule, SetDedup};
 pub use error::{Result, RouterError};
+pub use features::{FeatureStats, FeatureUsage};
 pub use gesture::{GestureRegistry, GestureResult};
+pub use health::{AdapterStatus, HealthReport};
+pub use loops::{Hop, LoopDetection, LoopReport, LoopTracker};
+pub use middleware::{MiddlewareChain, RouterMiddleware, Verdict};
+pub use naming::{
+    DuplicateAction, DuplicateSessions, NameCollision, SessionIdStrategy, SessionIdentity,
+};
 pub use p2p::{analyze_address, P2PAddressType, P2PCapabilities};
+pub use profile::RouterProfile;
 #[cfg(feature = "quic")]
 pub use router::QuicServerConfig;
 pub use router::{MultiProtocolConfig, Router, RouterConfig, RouterConfigBuilder, TransportConfig};
 pub use session::{Session, SessionId};
-pub use state::{RouterState, RouterStateConfig};
-pub use subscription::SubscriptionManager;
+pub use state::{ManifestEntry, RouterState, RouterStateConfig};
+pub use subscription::{
+    AddressStats, CreditWindow, PatternStats, Recipient, SubscriptionManager, SubscriptionStats,
+};
+pub use wildcard::WildcardWrites;
 
 // Re-export adapter configs
+#[cfg(feature = "dashboard")]
+pub use adapters::{DashboardAdapter, DashboardConfig};
+#[cfg(feature = "health")]
+pub use adapters::{HealthServer, HealthServerConfig};
 #[cfg(feature = "mqtt-server")]
 pub use adapters::{MqttServerAdapter, MqttServerConfig};
 #[cfg(feature = "osc-server")]
@@ -0,0 +1,379 @@
+//! Feedback loop detection
+//!
+//! A value bounced between bridges (an MQTT bridge and an OSC bridge mapping
+//! the same address, or two federated routers subscribed to each other) is
+//! written back to the router as soon as it is delivered, forever. The router
+//! counts those hops per address: a write of the value an address already
+//! holds, by a different session that is subscribed to the address and
+//! within [`LoopDetection::window`] of the previous write, is one more hop of
+//! the same value. Once a value has made more than
+//! [`LoopDetection::max_hops`] hops, writes of it are dropped with ERROR 506
+//! until the address goes quiet for a window or gets a different value,
+//! which breaks the cycle, and the loop is reported once as an event on
+//! [`LOOPS_ADDRESS`].
+//!
+//! SETs and event PUBLISHes are tracked; streams and gestures repeat values
+//! by nature and are not, and neither are relative SETs (an encoder sending
+//! `+1` ticks). Bundles are not tracked either.
+
+use clasp_core::{Message, PublishMessage, SignalType, Value};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::session::{Session, SessionId};
+use crate::subscription::SubscriptionManager;
+
+/// Address loop reports are published to
+pub const LOOPS_ADDRESS: &str = "/$sys/loops";
+
+/// Distinct hops kept for a report
+const MAX_PATH: usize = 8;
+
+/// Writes between sweeps of stale trails
+const SWEEP_INTERVAL: usize = 1024;
+
+/// Loop detection configuration (on by default)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopDetection {
+    /// Hops of one value before its writes are dropped (0 = disabled)
+    pub max_hops: u32,
+    /// Longest gap between two hops of a value
+    pub window: Duration,
+}
+
+impl LoopDetection {
+    pub fn new(max_hops: u32, window: Duration) -> Self {
+        Self { max_hops, window }
+    }
+
+    /// Never drop writes
+    pub fn disabled() -> Self {
+        Self {
+            max_hops: 0,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_hops > 0
+    }
+}
+
+impl Default for LoopDetection {
+    fn default() -> Self {
+        Self {
+            max_hops: 16,
+            window: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A broken loop, as published on [`LOOPS_ADDRESS`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopReport {
+    /// Address the value was bouncing on
+    pub address: String,
+    /// Hops made before the loop was broken
+    pub hops: u32,
+    /// Sessions the value passed through, as `name [origin]`, in order
+    pub path: Vec<String>,
+}
+
+impl LoopReport {
+    /// The report as an event
+    pub fn to_message(&self) -> Message {
+        let payload = HashMap::from([
+            ("address".to_string(), Value::String(self.address.clone())),
+            ("hops".to_string(), Value::Int(self.hops as i64)),
+            (
+                "path".to_string(),
+                Value::Array(self.path.iter().cloned().map(Value::String).collect()),
+            ),
+        ]);
+        Message::Publish(PublishMessage {
+            address: LOOPS_ADDRESS.to_string(),
+            signal: Some(SignalType::Event),
+            value: None,
+            payload: Some(Value::Map(payload)),
+            samples: None,
+            rate: None,
+            id: None,
+            phase: None,
+            timestamp: None,
+            timeline: None,
+        })
+    }
+}
+
+/// Outcome of checking a write
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hop {
+    /// Not part of a loop (or not yet past the limit)
+    Forward,
+    /// Drop the write; `report` is set when the loop is first broken
+    Break { report: Option<LoopReport> },
+}
+
+/// The last value written to an address and how far it has travelled
+struct Trail {
+    value: Value,
+    writer: SessionId,
+    hops: u32,
+    last: Instant,
+    path: Vec<String>,
+    broken: bool,
+}
+
+impl Trail {
+    fn new(value: &Value, writer: &str, label: String, now: Instant) -> Self {
+        Self {
+            value: value.clone(),
+            writer: writer.to_string(),
+            hops: 0,
+            last: now,
+            path: vec![label],
+            broken: false,
+        }
+    }
+}
+
+/// Per-address hop tracking
+pub struct LoopTracker {
+    config: LoopDetection,
+    trails: DashMap<String, Trail>,
+    writes: AtomicUsize,
+}
+
+impl LoopTracker {
+    pub fn new(config: LoopDetection) -> Self {
+        Self {
+            config,
+            trails: DashMap::new(),
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &LoopDetection {
+        &self.config
+    }
+
+    /// Check a message a session is about to route
+    pub fn check(
+        &self,
+        msg: &Message,
+        session: &Session,
+        subscriptions: &SubscriptionManager,
+    ) -> Hop {
+        if !self.config.is_enabled() {
+            return Hop::Forward;
+        }
+        let (address, value) = match msg {
+            Message::Set(set) if !set.lock && !set.unlock && set.op.is_none() => {
+                (&set.address, &set.value)
+            }
+            Message::Publish(publish)
+                if matches!(publish.signal, None | Some(SignalType::Event)) =>
+            {
+                match publish.value.as_ref().or(publish.payload.as_ref()) {
+                    Some(value) => (&publish.address, value),
+                    None => return Hop::Forward,
+                }
+            }
+            _ => return Hop::Forward,
+        };
+        if address.starts_with("/$sys/") {
+            return Hop::Forward;
+        }
+
+        self.record(
+            address,
+            value,
+            &session.id,
+            || format!("{} [{}]", session.name, session.origin()),
+            || {
+                subscriptions
+                    .find_subscribers(address, None)
+                    .contains(&session.id)
+            },
+        )
+    }
+
+    /// Record a write of `value` to `address` by `writer`
+    ///
+    /// `label` names the writer in reports; `subscribed` tells whether the
+    /// writer receives the address.
+    pub fn record(
+        &self,
+        address: &str,
+        value: &Value,
+        writer: &SessionId,
+        label: impl Fn() -> String,
+        subscribed: impl FnOnce() -> bool,
+    ) -> Hop {
+        let now = Instant::now();
+        self.sweep(now);
+
+        let mut trail = match self.trails.get_mut(address) {
+            Some(trail) => trail,
+            None => {
+                self.trails
+                    .insert(address.to_string(), Trail::new(value, writer, label(), now));
+                return Hop::Forward;
+            }
+        };
+
+        let fresh = now.duration_since(trail.last) <= self.config.window;
+        if !fresh || trail.value != *value {
+            *trail = Trail::new(value, writer, label(), now);
+            return Hop::Forward;
+        }
+        if trail.broken {
+            trail.last = now;
+            return Hop::Break { report: None };
+        }
+        if trail.writer == *writer {
+            // A repeat by the same writer is not a hop
+            trail.last = now;
+            return Hop::Forward;
+        }
+        if !subscribed() {
+            *trail = Trail::new(value, writer, label(), now);
+            return Hop::Forward;
+        }
+
+        trail.hops += 1;
+        let hop = label();
+        if trail.path.len() < MAX_PATH && !trail.path.contains(&hop) {
+            trail.path.push(hop);
+        }
+        if trail.hops > self.config.max_hops {
+            trail.broken = true;
+            trail.last = now;
+            return Hop::Break {
+                report: Some(LoopReport {
+                    address: address.to_string(),
+                    hops: trail.hops,
+                    path: trail.path.clone(),
+                }),
+            };
+        }
+
+        trail.writer = writer.clone();
+        trail.last = now;
+        Hop::Forward
+    }
+
+    /// Number of addresses with a recent write
+    pub fn len(&self) -> usize {
+        self.trails.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trails.is_empty()
+    }
+
+    /// Every so often, forget trails that can no longer continue
+    fn sweep(&self, now: Instant) {
+        if self.writes.fetch_add(1, Ordering::Relaxed) % SWEEP_INTERVAL != SWEEP_INTERVAL - 1 {
+            return;
+        }
+        let window = self.config.window;
+        self.trails
+            .retain(|_, trail| now.duration_since(trail.last) <= window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounce(
+        tracker: &LoopTracker,
+        address: &str,
+        value: &Value,
+        writer: &str,
+        subscribed: bool,
+    ) -> Hop {
+        tracker.record(
+            address,
+            value,
+            &writer.to_string(),
+            || format!("{} [{}]", writer, writer.split('-').next().unwrap()),
+            || subscribed,
+        )
+    }
+
+    #[test]
+    fn test_bounce_is_broken() {
+        let tracker = LoopTracker::new(LoopDetection::new(3, Duration::from_secs(1)));
+        let value = Value::Float(0.5);
+
+        let outcomes: Vec<Hop> = (0..6)
+            .map(|i| {
+                let writer = if i % 2 == 0 {
+                    "mqtt-bridge"
+                } else {
+                    "osc-bridge"
+                };
+                bounce(&tracker, "/mixer/fader/1", &value, writer, true)
+            })
+            .collect();
+
+        assert!(outcomes[..4].iter().all(|hop| *hop == Hop::Forward));
+        let Hop::Break {
+            report: Some(report),
+        } = &outcomes[4]
+        else {
+            panic!("expected a report, got {:?}", outcomes[4]);
+        };
+        assert_eq!(report.hops, 4);
+        assert_eq!(report.path, vec!["mqtt-bridge [mqtt]", "osc-bridge [osc]"]);
+        // Reported once per loop
+        assert_eq!(outcomes[5], Hop::Break { report: None });
+    }
+
+    #[test]
+    fn test_changes_and_repeats_are_not_hops() {
+        let tracker = LoopTracker::new(LoopDetection::new(1, Duration::from_secs(1)));
+
+        // A new value restarts the trail
+        for i in 0..5 {
+            let writer = if i % 2 == 0 { "a" } else { "b" };
+            assert_eq!(
+                bounce(&tracker, "/x", &Value::Int(i), writer, true),
+                Hop::Forward
+            );
+        }
+
+        // A writer repeating itself, or not subscribed, makes no hop
+        let value = Value::Int(7);
+        for _ in 0..5 {
+            assert_eq!(bounce(&tracker, "/y", &value, "a", true), Hop::Forward);
+        }
+        for i in 0..5 {
+            let writer = if i % 2 == 0 { "a" } else { "b" };
+            assert_eq!(bounce(&tracker, "/z", &value, writer, false), Hop::Forward);
+        }
+    }
+
+    #[test]
+    fn test_window() {
+        let tracker = LoopTracker::new(LoopDetection::new(1, Duration::from_millis(10)));
+        let value = Value::Bool(true);
+        for writer in ["a", "b"] {
+            assert_eq!(bounce(&tracker, "/x", &value, writer, true), Hop::Forward);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(bounce(&tracker, "/x", &value, "a", true), Hop::Forward);
+        assert!(matches!(
+            bounce(&tracker, "/x", &value, "b", true),
+            Hop::Forward
+        ));
+        assert!(matches!(
+            bounce(&tracker, "/x", &value, "a", true),
+            Hop::Break { .. }
+        ));
+    }
+}
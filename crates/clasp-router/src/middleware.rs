@@ -0,0 +1,169 @@
+//! Router middleware pipeline
+//!
+//! A [`RouterMiddleware`] hooks into the life of every session: when it
+//! connects, for each message it sends, for each message delivered to it, and
+//! when it disconnects. Middlewares are registered on the router with
+//! [`Router::with_middleware`](crate::Router::with_middleware) and run in
+//! registration order. Each hook may modify the message in place and returns
+//! a [`Verdict`]; the first middleware that does not return
+//! [`Verdict::Continue`] stops the chain.
+//!
+//! ```
+//! use async_trait::async_trait;
+//! use clasp_core::{ErrorCode, Message};
+//! use clasp_router::middleware::{RouterMiddleware, Verdict};
+//! use clasp_router::Session;
+//! use std::sync::Arc;
+//!
+//! /// Refuse writes to anything under /admin
+//! struct ReadOnlyAdmin;
+//!
+//! #[async_trait]
+//! impl RouterMiddleware for ReadOnlyAdmin {
+//!     async fn on_message(&self, _session: &Arc<Session>, msg: &mut Message) -> Verdict {
+//!         match msg {
+//!             Message::Set(set) if set.address.starts_with("/admin/") => {
+//!                 Verdict::reject(ErrorCode::Forbidden, "Admin params are read-only")
+//!             }
+//!             _ => Verdict::Continue,
+//!         }
+//!     }
+//! }
+//! ```
+
+use async_trait::async_trait;
+use clasp_core::{ErrorCode, Message};
+use std::sync::Arc;
+
+use crate::session::Session;
+
+/// Outcome of a middleware hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Hand the (possibly modified) message to the next middleware
+    Continue,
+    /// Stop processing without telling the client
+    Drop,
+    /// Stop processing and send the client an ERROR
+    Reject { code: ErrorCode, message: String },
+}
+
+impl Verdict {
+    /// Reject with an ERROR
+    pub fn reject(code: ErrorCode, message: impl Into<String>) -> Self {
+        Verdict::Reject {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Whether processing continues
+    pub fn is_continue(&self) -> bool {
+        matches!(self, Verdict::Continue)
+    }
+}
+
+/// Hooks into session and message handling
+///
+/// All hooks default to doing nothing, so implementations only override the
+/// ones they need.
+#[async_trait]
+pub trait RouterMiddleware: Send + Sync {
+    /// A session completed its handshake. Anything but
+    /// [`Verdict::Continue`] closes the connection (after sending the ERROR
+    /// for [`Verdict::Reject`]).
+    async fn on_connect(&self, _session: &Arc<Session>) -> Verdict {
+        Verdict::Continue
+    }
+
+    /// A session sent a message. The message can be rewritten before the
+    /// router handles it; a rejected SET, GET or SUBSCRIBE gets an ERROR
+    /// carrying its address and correlation id.
+    async fn on_message(&self, _session: &Arc<Session>, _msg: &mut Message) -> Verdict {
+        Verdict::Continue
+    }
+
+    /// A SET or PUBLISH is about to be delivered to a subscribed session.
+    /// Changes only affect this recipient's copy; anything but
+    /// [`Verdict::Continue`] skips the delivery.
+    async fn on_deliver(&self, _session: &Arc<Session>, _msg: &mut Message) -> Verdict {
+        Verdict::Continue
+    }
+
+    /// A session disconnected (including after a vetoed `on_connect`)
+    async fn on_disconnect(&self, _session: &Arc<Session>) {}
+}
+
+/// Registered middlewares, run in order
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    middlewares: Vec<Arc<dyn RouterMiddleware>>,
+}
+
+impl MiddlewareChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a middleware to the end of the chain
+    pub fn push(&mut self, middleware: Arc<dyn RouterMiddleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    /// Number of registered middlewares
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    /// Run `on_connect` hooks until one does not continue
+    pub async fn connect(&self, session: &Arc<Session>) -> Verdict {
+        for middleware in &self.middlewares {
+            let verdict = middleware.on_connect(session).await;
+            if !verdict.is_continue() {
+                return verdict;
+            }
+        }
+        Verdict::Continue
+    }
+
+    /// Run `on_message` hooks until one does not continue
+    pub async fn message(&self, session: &Arc<Session>, msg: &mut Message) -> Verdict {
+        for middleware in &self.middlewares {
+            let verdict = middleware.on_message(session, msg).await;
+            if !verdict.is_continue() {
+                return verdict;
+            }
+        }
+        Verdict::Continue
+    }
+
+    /// Run `on_deliver` hooks until one does not continue
+    pub async fn deliver(&self, session: &Arc<Session>, msg: &mut Message) -> Verdict {
+        for middleware in &self.middlewares {
+            let verdict = middleware.on_deliver(session, msg).await;
+            if !verdict.is_continue() {
+                return verdict;
+            }
+        }
+        Verdict::Continue
+    }
+
+    /// Run every `on_disconnect` hook
+    pub async fn disconnect(&self, session: &Arc<Session>) {
+        for middleware in &self.middlewares {
+            middleware.on_disconnect(session).await;
+        }
+    }
+}
+
+impl std::fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareChain")
+            .field("len", &self.middlewares.len())
+            .finish()
+    }
+}
@@ -0,0 +1,255 @@
+//! Session ids and client names
+//!
+//! By default every session gets a random UUID and client names are taken
+//! as-is from HELLO. With several operators connected, logs are much easier
+//! to follow when ids say who they belong to, so [`SessionIdStrategy`] can
+//! derive them from the client name or the token subject instead, and
+//! [`NameCollision`] keeps client names unique.
+//!
+//! [`DuplicateSessions`] decides what happens when a client reconnects while
+//! its previous session is still registered, e.g. a kiosk that crashed and
+//! came back before the old connection timed out.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::session::SessionId;
+
+/// How session ids are generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionIdStrategy {
+    /// Random UUID, e.g. `0b6f9f0e-4c1d-4a9e-9d0a-3f1c2b7e8a55`
+    #[default]
+    Uuid,
+    /// Client name plus a short random suffix, e.g. `lighting-desk-3f2a`
+    Short,
+    /// Token subject plus a short random suffix, e.g. `alice-3f2a`
+    ///
+    /// Falls back to [`SessionIdStrategy::Short`] for sessions without a
+    /// subject.
+    Subject,
+}
+
+/// What to do when a client connects with a name already in use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameCollision {
+    /// Allow duplicate names
+    #[default]
+    Allow,
+    /// Refuse the connection
+    Reject,
+    /// Rename the newcomer to `name (2)`, `name (3)`, ...
+    Suffix,
+}
+
+/// What makes two sessions the same client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionIdentity {
+    /// Client name from HELLO
+    #[default]
+    Name,
+    /// Token subject; sessions without a subject never match
+    Subject,
+}
+
+impl SessionIdentity {
+    /// The identity of a client with this name and subject
+    pub fn key<'a>(&self, name: &'a str, subject: Option<&'a str>) -> Option<&'a str> {
+        match self {
+            SessionIdentity::Name => Some(name),
+            SessionIdentity::Subject => subject,
+        }
+    }
+}
+
+/// What to do when a client connects with the identity of a live session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateAction {
+    /// Keep both sessions
+    #[default]
+    Allow,
+    /// Refuse the new connection
+    Reject,
+    /// Close the old session and keep the new one
+    Takeover,
+}
+
+/// Duplicate-identity policy
+///
+/// ```
+/// use clasp_router::{DuplicateSessions, SessionIdentity};
+///
+/// // A reconnecting kiosk replaces its ghost session and keeps its subscriptions
+/// let policy = DuplicateSessions::takeover(SessionIdentity::Name).with_subscriptions();
+/// assert!(policy.transfer_subscriptions);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateSessions {
+    /// How sessions are matched
+    pub identity: SessionIdentity,
+    /// What happens on a match
+    pub action: DuplicateAction,
+    /// Move the old session's subscriptions to the new one on takeover
+    pub transfer_subscriptions: bool,
+}
+
+impl DuplicateSessions {
+    /// Keep every session (the default)
+    pub fn allow() -> Self {
+        Self::default()
+    }
+
+    /// Refuse clients whose identity is already connected
+    pub fn reject(identity: SessionIdentity) -> Self {
+        Self {
+            identity,
+            action: DuplicateAction::Reject,
+            transfer_subscriptions: false,
+        }
+    }
+
+    /// Close the existing session when its identity reconnects
+    pub fn takeover(identity: SessionIdentity) -> Self {
+        Self {
+            identity,
+            action: DuplicateAction::Takeover,
+            transfer_subscriptions: false,
+        }
+    }
+
+    /// Carry subscriptions over to the new session on takeover
+    pub fn with_subscriptions(mut self) -> Self {
+        self.transfer_subscriptions = true;
+        self
+    }
+}
+
+/// Longest prefix kept when deriving an id from a name or subject
+const MAX_PREFIX_LEN: usize = 24;
+
+/// Generate a session id that `taken` reports as unused
+pub fn session_id(
+    strategy: SessionIdStrategy,
+    name: &str,
+    subject: Option<&str>,
+    taken: impl Fn(&str) -> bool,
+) -> SessionId {
+    let prefix = match strategy {
+        SessionIdStrategy::Uuid => None,
+        SessionIdStrategy::Short => Some(slug(name)),
+        SessionIdStrategy::Subject => Some(subject.map(slug).unwrap_or_else(|| slug(name))),
+    };
+
+    loop {
+        let uuid = Uuid::new_v4();
+        let id = match &prefix {
+            None => uuid.to_string(),
+            Some(prefix) => format!("{}-{}", prefix, &uuid.simple().to_string()[..4]),
+        };
+        if !taken(&id) {
+            return id;
+        }
+    }
+}
+
+/// Apply the collision policy to a client name.
+///
+/// Returns `None` when the name is in use and the policy is
+/// [`NameCollision::Reject`].
+pub fn client_name(
+    policy: NameCollision,
+    name: &str,
+    taken: impl Fn(&str) -> bool,
+) -> Option<String> {
+    if policy == NameCollision::Allow || !taken(name) {
+        return Some(name.to_string());
+    }
+    match policy {
+        NameCollision::Reject => None,
+        _ => (2..)
+            .map(|n| format!("{} ({})", name, n))
+            .find(|candidate| !taken(candidate)),
+    }
+}
+
+/// Lowercase ASCII letters and digits joined by single dashes
+fn slug(s: &str) -> String {
+    let mut slug = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= MAX_PREFIX_LEN {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "session".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_ids() {
+        let id = session_id(SessionIdStrategy::Uuid, "Desk", None, |_| false);
+        assert!(Uuid::parse_str(&id).is_ok());
+
+        let id = session_id(SessionIdStrategy::Short, "Lighting Desk #2", None, |_| {
+            false
+        });
+        assert!(id.starts_with("lighting-desk-2-"), "{}", id);
+        assert_eq!(id.len(), "lighting-desk-2-".len() + 4);
+
+        let id = session_id(SessionIdStrategy::Subject, "Desk", Some("alice"), |_| false);
+        assert!(id.starts_with("alice-"), "{}", id);
+        let id = session_id(SessionIdStrategy::Subject, "Desk", None, |_| false);
+        assert!(id.starts_with("desk-"), "{}", id);
+
+        let id = session_id(SessionIdStrategy::Short, "???", None, |_| false);
+        assert!(id.starts_with("session-"), "{}", id);
+    }
+
+    #[test]
+    fn test_session_identity() {
+        assert_eq!(
+            SessionIdentity::Name.key("Desk", Some("alice")),
+            Some("Desk")
+        );
+        assert_eq!(
+            SessionIdentity::Subject.key("Desk", Some("alice")),
+            Some("alice")
+        );
+        assert_eq!(SessionIdentity::Subject.key("Desk", None), None);
+    }
+
+    #[test]
+    fn test_client_names() {
+        let taken = |name: &str| name == "Desk" || name == "Desk (2)";
+
+        assert_eq!(
+            client_name(NameCollision::Allow, "Desk", taken).as_deref(),
+            Some("Desk")
+        );
+        assert_eq!(client_name(NameCollision::Reject, "Desk", taken), None);
+        assert_eq!(
+            client_name(NameCollision::Reject, "Stage", taken).as_deref(),
+            Some("Stage")
+        );
+        assert_eq!(
+            client_name(NameCollision::Suffix, "Desk", taken).as_deref(),
+            Some("Desk (3)")
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Deployment profiles
+//!
+//! A [`RouterProfile`] is a starting [`RouterConfig`] tuned for a kind of
+//! deployment, so a router can be stood up without going through every
+//! field. Options set afterwards (or on the command line) refine it.
+//!
+//! - **Live**: show control. Fader banks and encoders may send thousands of
+//!   messages a second, and retained state never expires mid-show.
+//! - **Iot**: many slow devices. Tight per-client rate limits, unchanged
+//!   polled readings are not forwarded, idle values expire after a day and
+//!   dead connections are found by keepalive.
+//! - **Dev**: a local workbench. No rate limits, short-lived state, and
+//!   undeclared signal types are rejected so client bugs show up early.
+
+use clasp_core::state::StateStoreConfig;
+use clasp_transport::KeepaliveConfig;
+use std::time::Duration;
+
+use crate::dedup::{DedupRule, SetDedup};
+use crate::router::RouterConfig;
+use crate::state::RouterStateConfig;
+
+/// Preset router configuration for a kind of deployment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouterProfile {
+    /// Show control: high message rates, state kept for the whole show
+    Live,
+    /// Sensor networks: many sessions, low rates, deduplicated readings
+    Iot,
+    /// Local development: no limits, short TTLs, strict feature checks
+    Dev,
+}
+
+impl RouterProfile {
+    /// The configuration this profile starts from
+    pub fn config(self) -> RouterConfig {
+        match self {
+            RouterProfile::Live => RouterConfig {
+                max_sessions: 256,
+                max_messages_per_second: 5000,
+                gesture_coalescing: true,
+                gesture_coalesce_interval_ms: 16,
+                state_config: RouterStateConfig::unlimited(),
+                ..Default::default()
+            },
+            RouterProfile::Iot => RouterConfig {
+                max_sessions: 10_000,
+                max_messages_per_second: 100,
+                state_config: RouterStateConfig {
+                    param_config: StateStoreConfig::with_limits(100_000, 24 * 3600),
+                    signal_ttl: Some(Duration::from_secs(24 * 3600)),
+                    max_signals: Some(100_000),
+                },
+                keepalive: Some(KeepaliveConfig::new(Duration::from_secs(30))),
+                dedup: SetDedup::new().with_rule(DedupRule::new("/**")),
+                ..Default::default()
+            },
+            RouterProfile::Dev => RouterConfig {
+                rate_limiting_enabled: false,
+                state_config: RouterStateConfig {
+                    param_config: StateStoreConfig::with_limits(10_000, 600),
+                    signal_ttl: Some(Duration::from_secs(600)),
+                    max_signals: Some(10_000),
+                },
+                enforce_features: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Default log level for binaries running this profile
+    pub fn log_level(self) -> &'static str {
+        match self {
+            RouterProfile::Live => "warn",
+            RouterProfile::Iot => "info",
+            RouterProfile::Dev => "debug",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let live = RouterProfile::Live.config();
+        assert_eq!(live.max_messages_per_second, 5000);
+        assert_eq!(live.state_config.param_config.param_ttl, None);
+
+        let iot = RouterProfile::Iot.config();
+        assert!(iot.dedup.rule_for("/sensors/temp").is_some());
+        assert_eq!(
+            iot.state_config.param_config.param_ttl,
+            Some(Duration::from_secs(24 * 3600))
+        );
+
+        let dev = RouterProfile::Dev.config();
+        assert!(!dev.rate_limiting_enabled);
+        assert!(dev.enforce_features);
+        assert_eq!(RouterProfile::Dev.log_level(), "debug");
+    }
+}
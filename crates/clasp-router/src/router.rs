@@ -28,29 +28,51 @@
 
 use bytes::Bytes;
 use clasp_core::{
-    codec, AckMessage, Action, CpskValidator, ErrorMessage, Frame, Message, PublishMessage,
-    SecurityMode, SetMessage, SignalType, SnapshotMessage, TokenValidator, ValidationResult,
+    codec, state::UpdateError, AckMessage, Action, ChannelMessage, CpskValidator, DeviceIdentity,
+    DeviceKeyValidator, EnvelopeMessage, ErrorCode, ErrorMessage, Frame, Manifest,
+    ManifestViolation, Message, Origin, ParamValue, PublishMessage, QoS, SecurityMode, SetMessage,
+    SignalType, SnapshotMessage, TokenValidator, ValidationResult, Value, SESSION_ORIGIN_ADDRESS,
+    SESSION_TOKEN_ADDRESS,
+};
+use clasp_transport::{
+    KeepaliveConfig, TransportEvent, TransportReceiver, TransportSender, TransportServer,
 };
-use clasp_transport::{TransportEvent, TransportReceiver, TransportSender, TransportServer};
 use dashmap::DashMap;
 use parking_lot::RwLock;
-use std::net::SocketAddr;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 
 #[cfg(feature = "websocket")]
-use clasp_transport::WebSocketServer;
+use clasp_transport::{WebSocketConfig, WebSocketServer};
 
 #[cfg(feature = "quic")]
 use clasp_transport::{QuicConfig, QuicTransport};
 
 use crate::{
+    adapter_admin::{self, AdapterConfig, AdapterKind, AdapterRegistry},
+    ban::{self, Admission, BanList, ConnectionGuard, SecurityEvent},
+    budget::{EvictionReport, MemoryBudget},
+    channel::{self, ChannelSender, Channels},
+    dedup::{DedupRule, SetDedup},
     error::{Result, RouterError},
+    features::{FeatureStats, FeatureUsage},
     gesture::{GestureRegistry, GestureResult},
+    health::{self, AdapterStatus, HealthReport},
+    loops::{Hop, LoopDetection, LoopTracker},
+    middleware::{MiddlewareChain, RouterMiddleware, Verdict},
+    naming::{self, DuplicateAction, DuplicateSessions, NameCollision, SessionIdStrategy},
     p2p::{analyze_address, P2PAddressType, P2PCapabilities},
     session::{Session, SessionId},
     state::{RouterState, RouterStateConfig},
-    subscription::{Subscription, SubscriptionManager},
+    subscription::{self, Recipient, Subscription, SubscriptionManager, SubscriptionStats},
+    token_admin::{self, CredentialStore},
+    wildcard::{is_wildcard, WildcardWrites},
 };
 use std::time::Duration;
 
@@ -125,6 +147,14 @@ pub struct MultiProtocolConfig {
     /// OSC server configuration
     #[cfg(feature = "osc-server")]
     pub osc: Option<crate::adapters::OscServerConfig>,
+
+    /// Web dashboard configuration
+    #[cfg(feature = "dashboard")]
+    pub dashboard: Option<crate::adapters::DashboardConfig>,
+
+    /// Health check endpoints (`/healthz`, `/readyz`)
+    #[cfg(feature = "health")]
+    pub health: Option<crate::adapters::HealthServerConfig>,
 }
 
 /// QUIC server configuration
@@ -164,6 +194,30 @@ pub struct RouterConfig {
     pub rate_limiting_enabled: bool,
     /// State store configuration (TTL, limits)
     pub state_config: RouterStateConfig,
+    /// Reject signal types not negotiated in HELLO/WELCOME
+    pub enforce_features: bool,
+    /// How session ids are generated
+    pub session_ids: SessionIdStrategy,
+    /// What to do when a client name is already in use
+    pub name_collision: NameCollision,
+    /// What to do when a client reconnects while its old session is live
+    pub duplicate_sessions: DuplicateSessions,
+    /// Keepalive for built-in transports (`None` = each transport's default)
+    pub keepalive: Option<KeepaliveConfig>,
+    /// Logical clients that may share one connection (0 = sharing disabled)
+    pub max_channels: usize,
+    /// Patterns whose unchanged SETs are acknowledged but not forwarded
+    pub dedup: SetDedup,
+    /// Memory budget for retained state and queued changes
+    pub memory_budget: MemoryBudget,
+    /// Dropping values that bounce between sessions
+    pub loop_detection: LoopDetection,
+    /// Fanning out writes to patterns over the addresses they match
+    pub wildcard_writes: WildcardWrites,
+    /// Reject writes that don't fit the manifest owning their namespace
+    pub validate_manifests: bool,
+    /// Per-IP connection throttling and automatic bans
+    pub connection_guard: ConnectionGuard,
 }
 
 impl Default for RouterConfig {
@@ -186,6 +240,18 @@ impl Default for RouterConfig {
             max_messages_per_second: 1000, // 1000 msgs/sec default
             rate_limiting_enabled: true,
             state_config: RouterStateConfig::default(), // 1 hour TTL by default
+            enforce_features: false,
+            session_ids: SessionIdStrategy::default(),
+            name_collision: NameCollision::default(),
+            duplicate_sessions: DuplicateSessions::default(),
+            keepalive: None,
+            max_channels: 16,
+            dedup: SetDedup::default(),
+            memory_budget: MemoryBudget::default(),
+            loop_detection: LoopDetection::default(),
+            wildcard_writes: WildcardWrites::default(),
+            validate_manifests: false,
+            connection_guard: ConnectionGuard::default(),
         }
     }
 }
@@ -231,6 +297,66 @@ impl RouterConfigBuilder {
         self
     }
 
+    pub fn enforce_features(mut self, enabled: bool) -> Self {
+        self.config.enforce_features = enabled;
+        self
+    }
+
+    pub fn session_ids(mut self, strategy: SessionIdStrategy) -> Self {
+        self.config.session_ids = strategy;
+        self
+    }
+
+    pub fn name_collision(mut self, policy: NameCollision) -> Self {
+        self.config.name_collision = policy;
+        self
+    }
+
+    pub fn duplicate_sessions(mut self, policy: DuplicateSessions) -> Self {
+        self.config.duplicate_sessions = policy;
+        self
+    }
+
+    pub fn keepalive(mut self, keepalive: KeepaliveConfig) -> Self {
+        self.config.keepalive = Some(keepalive);
+        self
+    }
+
+    pub fn max_channels(mut self, max: usize) -> Self {
+        self.config.max_channels = max;
+        self
+    }
+
+    pub fn dedup(mut self, rule: DedupRule) -> Self {
+        self.config.dedup = self.config.dedup.with_rule(rule);
+        self
+    }
+
+    pub fn memory_budget(mut self, budget: MemoryBudget) -> Self {
+        self.config.memory_budget = budget;
+        self
+    }
+
+    pub fn loop_detection(mut self, detection: LoopDetection) -> Self {
+        self.config.loop_detection = detection;
+        self
+    }
+
+    pub fn wildcard_writes(mut self, writes: WildcardWrites) -> Self {
+        self.config.wildcard_writes = writes;
+        self
+    }
+
+    pub fn validate_manifests(mut self, enabled: bool) -> Self {
+        self.config.validate_manifests = enabled;
+        self
+    }
+
+    pub fn connection_guard(mut self, guard: ConnectionGuard) -> Self {
+        self.config.connection_guard = guard;
+        self
+    }
+
     pub fn build(self) -> RouterConfig {
         self.config
     }
@@ -249,10 +375,34 @@ pub struct Router {
     running: Arc<RwLock<bool>>,
     /// Token validator (None = always reject in authenticated mode)
     token_validator: Option<Arc<dyn TokenValidator>>,
+    /// Token file updated by `/$sys/tokens` changes
+    token_file: Option<Arc<PathBuf>>,
+    /// Device public keys accepted alongside the token validator
+    device_keys: Option<Arc<DeviceKeyValidator>>,
+    /// Key file updated by `/$sys/keys` changes
+    device_key_file: Option<Arc<PathBuf>>,
     /// P2P capabilities tracker
     p2p_capabilities: Arc<P2PCapabilities>,
     /// Gesture registry for move coalescing
     gesture_registry: Option<Arc<GestureRegistry>>,
+    /// Set once shutdown begins (readiness turns false)
+    draining: Arc<AtomicBool>,
+    /// Signals accept loops to stop
+    shutdown: Arc<watch::Sender<bool>>,
+    /// Status of each transport and adapter
+    adapter_status: Arc<DashMap<String, AdapterStatus>>,
+    /// Creation time (for uptime)
+    created_at: Instant,
+    /// Per-feature message counters
+    feature_stats: Arc<FeatureStats>,
+    /// Registered middlewares, run in order
+    middleware: Arc<MiddlewareChain>,
+    /// Hop tracking for feedback loop detection
+    loops: Arc<LoopTracker>,
+    /// Protocol adapters started at runtime or by `serve_all`
+    adapters: Arc<AdapterRegistry>,
+    /// Per-IP connection counts and the ban list
+    bans: Arc<BanList>,
 }
 
 impl Router {
@@ -267,36 +417,123 @@ impl Router {
         };
 
         let state = Arc::new(RouterState::with_config(config.state_config.clone()));
+        let loops = Arc::new(LoopTracker::new(config.loop_detection));
+        let bans = Arc::new(BanList::new(config.connection_guard));
+        let sessions = Arc::new(DashMap::new());
+        let subscriptions = Arc::new(SubscriptionManager::new());
+        let adapter_status = Arc::new(DashMap::new());
+        let feature_stats = Arc::new(FeatureStats::new());
+        let adapters = Arc::new(AdapterRegistry::new(
+            Arc::clone(&sessions),
+            Arc::clone(&subscriptions),
+            Arc::clone(&state),
+            Arc::clone(&feature_stats),
+            Arc::clone(&adapter_status),
+        ));
 
         Self {
             config,
-            sessions: Arc::new(DashMap::new()),
-            subscriptions: Arc::new(SubscriptionManager::new()),
+            sessions,
+            subscriptions,
             state,
             running: Arc::new(RwLock::new(false)),
             token_validator: None,
+            token_file: None,
+            device_keys: None,
+            device_key_file: None,
             p2p_capabilities: Arc::new(P2PCapabilities::new()),
             gesture_registry,
+            draining: Arc::new(AtomicBool::new(false)),
+            shutdown: Arc::new(watch::channel(false).0),
+            adapter_status,
+            created_at: Instant::now(),
+            feature_stats,
+            middleware: Arc::new(MiddlewareChain::new()),
+            loops,
+            adapters,
+            bans,
         }
     }
 
     /// Create a router with a token validator for authenticated mode
     pub fn with_validator<V: TokenValidator + 'static>(mut self, validator: V) -> Self {
-        self.token_validator = Some(Arc::new(validator));
+        self.set_validator(validator);
         self
     }
 
     /// Set the token validator
     pub fn set_validator<V: TokenValidator + 'static>(&mut self, validator: V) {
-        self.token_validator = Some(Arc::new(validator));
+        let validator: Arc<dyn TokenValidator> = Arc::new(validator);
+        self.adapters.set_validator(Some(Arc::clone(&validator)));
+        self.token_validator = Some(validator);
+    }
+
+    /// Persist runtime token changes (`/$sys/tokens`) to this token file
+    ///
+    /// See [`token_admin`] for the file format.
+    pub fn with_token_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.token_file = Some(Arc::new(path.into()));
+        self
+    }
+
+    /// Accept device key proofs (see [`clasp_core::identity`]) in
+    /// authenticated mode, alongside tokens checked by the token validator
+    pub fn with_device_keys(mut self, keys: DeviceKeyValidator) -> Self {
+        self.device_keys = Some(Arc::new(keys));
+        self
+    }
+
+    /// Persist runtime device key changes (`/$sys/keys`) to this file
+    ///
+    /// Uses the token file format with public keys in place of tokens.
+    pub fn with_device_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.device_key_file = Some(Arc::new(path.into()));
+        self
+    }
+
+    /// Register a middleware; middlewares run in registration order
+    ///
+    /// See [`crate::middleware`] for the hooks available.
+    pub fn with_middleware<M: RouterMiddleware + 'static>(mut self, middleware: M) -> Self {
+        self.add_middleware(middleware);
+        self
+    }
+
+    /// Register a middleware after the ones already registered
+    pub fn add_middleware<M: RouterMiddleware + 'static>(&mut self, middleware: M) {
+        Arc::make_mut(&mut self.middleware).push(Arc::new(middleware));
     }
 
     /// Get a reference to the CPSK validator if one is configured
     /// This allows adding tokens at runtime
     pub fn cpsk_validator(&self) -> Option<&CpskValidator> {
-        self.token_validator
-            .as_ref()
-            .and_then(|v| v.as_any().downcast_ref::<CpskValidator>())
+        cpsk_validator(&self.token_validator)
+    }
+
+    /// Get the device key validator if one is configured
+    pub fn device_keys(&self) -> Option<&DeviceKeyValidator> {
+        self.device_keys.as_deref()
+    }
+
+    /// Per-IP connection counts and the ban list
+    pub fn bans(&self) -> &BanList {
+        &self.bans
+    }
+
+    /// Ban an IP: its sessions are closed, new connections from it refused
+    /// and the ban is reported on [`ban::SECURITY_ADDRESS`]
+    pub fn ban(&self, ip: IpAddr, duration: Option<Duration>, reason: impl Into<String>) {
+        let event = self.bans.ban(ip, duration, reason);
+        apply_security_event(&event, &self.bans, &self.sessions, &self.subscriptions);
+    }
+
+    /// Lift the ban on an IP; false if it wasn't banned
+    pub fn unban(&self, ip: IpAddr) -> bool {
+        let Some(event) = self.bans.unban(ip) else {
+            return false;
+        };
+        apply_security_event(&event, &self.bans, &self.sessions, &self.subscriptions);
+        true
     }
 
     /// Get the security mode
@@ -348,9 +585,23 @@ impl Router {
         // Start state cleanup task (removes stale params and signals)
         self.start_state_cleanup_task();
 
-        while *self.running.read() {
-            match server.accept().await {
+        // Start memory budget task if a budget is configured
+        if self.config.memory_budget.is_enabled() {
+            self.start_memory_budget_task();
+        }
+
+        let mut shutdown = self.shutdown.subscribe();
+        while *self.running.read() && !*shutdown.borrow_and_update() {
+            let accepted = tokio::select! {
+                accepted = server.accept() => accepted,
+                _ = shutdown.changed() => break,
+            };
+            match accepted {
                 Ok((sender, receiver, addr)) => {
+                    if !self.admit(addr) {
+                        continue;
+                    }
+
                     // Enforce max_sessions limit
                     let current_sessions = self.sessions.len();
                     if current_sessions >= self.config.max_sessions {
@@ -371,9 +622,29 @@ impl Router {
             }
         }
 
+        let _ = server.close().await;
         Ok(())
     }
 
+    /// Check an accepted connection against the ban list and per-IP limits
+    fn admit(&self, addr: SocketAddr) -> bool {
+        match self.bans.admit(addr.ip()) {
+            Admission::Accept => true,
+            Admission::Banned => {
+                debug!("Refusing connection from {}: banned", addr);
+                false
+            }
+            Admission::Throttled { event } => {
+                if let Some(event) = event {
+                    warn!("Throttling connections from {}", addr.ip());
+                    apply_security_event(&event, &self.bans, &self.sessions, &self.subscriptions);
+                }
+                debug!("Refusing connection from {}: too many attempts", addr);
+                false
+            }
+        }
+    }
+
     /// Start background task to flush stale gesture moves
     fn start_gesture_flush_task(&self, registry: Arc<GestureRegistry>) {
         // Skip gesture flush task if coalescing is disabled (interval is 0)
@@ -385,6 +656,7 @@ impl Router {
         let subscriptions = Arc::clone(&self.subscriptions);
         let running = Arc::clone(&self.running);
         let flush_interval = Duration::from_millis(self.config.gesture_coalesce_interval_ms);
+        let middleware = Arc::clone(&self.middleware);
 
         tokio::spawn(async move {
             let mut ticker = tokio::time::interval(flush_interval);
@@ -398,22 +670,27 @@ impl Router {
 
                 // Flush any stale buffered moves
                 let to_flush = registry.flush_stale();
+                // Flushed moves don't carry their sender; gestures only come
+                // from CLASP clients, so they are delivered as native traffic
+                let origin = Origin::native();
                 for pub_msg in to_flush {
                     let msg = Message::Publish(pub_msg.clone());
-                    let subscribers =
-                        subscriptions.find_subscribers(&pub_msg.address, Some(SignalType::Gesture));
-
-                    if let Ok(bytes) = codec::encode(&msg) {
-                        for sub_session_id in subscribers {
-                            if let Some(sub_session) = sessions.get(&sub_session_id) {
-                                try_send_with_drop_tracking_sync(
-                                    sub_session.value(),
-                                    bytes.clone(),
-                                    &sub_session_id,
-                                );
-                            }
-                        }
-                    }
+                    let subscribers = subscriptions.find_recipients(
+                        &pub_msg.address,
+                        Some(SignalType::Gesture),
+                        &origin,
+                    );
+
+                    deliver(
+                        &msg,
+                        &origin,
+                        subscribers,
+                        None,
+                        None,
+                        &sessions,
+                        &middleware,
+                    )
+                    .await;
                 }
 
                 // Cleanup very old gestures (> 5 minutes with no end)
@@ -497,6 +774,40 @@ impl Router {
         });
     }
 
+    /// Start background task to measure queued bytes and enforce the memory
+    /// budget
+    ///
+    /// SETs trigger enforcement as they grow state; this also catches growth
+    /// in session queues and SETs applied by protocol adapters.
+    fn start_memory_budget_task(&self) {
+        let state = Arc::clone(&self.state);
+        let sessions = Arc::clone(&self.sessions);
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let running = Arc::clone(&self.running);
+        let budget = self.config.memory_budget.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+
+            loop {
+                ticker.tick().await;
+
+                if !*running.read() {
+                    break;
+                }
+
+                let queued = sessions
+                    .iter()
+                    .map(|entry| entry.value().queued_bytes())
+                    .sum();
+                state.record_queued_bytes(queued);
+                enforce_memory_budget(&budget, &state, &sessions, &subscriptions);
+            }
+
+            debug!("Memory budget task stopped");
+        });
+    }
+
     // =========================================================================
     // WebSocket Transport
     // =========================================================================
@@ -511,9 +822,18 @@ impl Router {
     /// Default port: 7330
     #[cfg(feature = "websocket")]
     pub async fn serve_websocket(&self, addr: &str) -> Result<()> {
-        let server = WebSocketServer::bind(addr).await?;
-        info!("WebSocket server listening on {}", addr);
-        self.serve_on(server).await
+        health::track(&self.adapter_status, "WebSocket", async {
+            let mut server = WebSocketServer::bind(addr).await?;
+            if let Some(keepalive) = self.config.keepalive {
+                server = server.with_config(WebSocketConfig {
+                    keepalive,
+                    ..Default::default()
+                });
+            }
+            info!("WebSocket server listening on {}", addr);
+            self.serve_on(server).await
+        })
+        .await
     }
 
     /// Backward-compatible alias for `serve_websocket`.
@@ -549,10 +869,17 @@ impl Router {
         cert_der: Vec<u8>,
         key_der: Vec<u8>,
     ) -> Result<()> {
-        let server = QuicTransport::new_server(addr, cert_der, key_der)
-            .map_err(|e| RouterError::Transport(e))?;
-        info!("QUIC server listening on {}", addr);
-        self.serve_quic_transport(server).await
+        health::track(&self.adapter_status, "QUIC", async {
+            let mut config = QuicConfig::default();
+            if let Some(keepalive) = self.config.keepalive {
+                config = config.with_keepalive(keepalive);
+            }
+            let server = QuicTransport::new_server_with_config(addr, cert_der, key_der, config)
+                .map_err(|e| RouterError::Transport(e))?;
+            info!("QUIC server listening on {}", addr);
+            self.serve_quic_transport(server).await
+        })
+        .await
     }
 
     /// Internal: Serve using a QuicTransport server.
@@ -563,10 +890,19 @@ impl Router {
     async fn serve_quic_transport(&self, server: QuicTransport) -> Result<()> {
         *self.running.write() = true;
 
-        while *self.running.read() {
-            match server.accept().await {
+        let mut shutdown = self.shutdown.subscribe();
+        while *self.running.read() && !*shutdown.borrow_and_update() {
+            let accepted = tokio::select! {
+                accepted = server.accept() => accepted,
+                _ = shutdown.changed() => break,
+            };
+            match accepted {
                 Ok(connection) => {
                     let addr = connection.remote_address();
+                    if !self.admit(addr) {
+                        connection.close(0, "connection refused");
+                        continue;
+                    }
                     info!("QUIC connection from {}", addr);
 
                     // Accept bidirectional stream for CLASP protocol
@@ -706,41 +1042,39 @@ impl Router {
             }));
         }
 
-        // MQTT server adapter
+        // Protocol adapters run in the registry, so they can be stopped and
+        // restarted without the transports
+        #[allow(unused_mut)]
+        let mut adapters: Vec<AdapterConfig> = vec![];
         #[cfg(feature = "mqtt-server")]
-        if let Some(mqtt_config) = config.mqtt {
-            info!("Starting MQTT server on {}", mqtt_config.bind_addr);
-            protocol_names.push("MQTT");
-            let adapter = crate::adapters::MqttServerAdapter::new(
-                mqtt_config,
-                Arc::clone(&self.sessions),
-                Arc::clone(&self.subscriptions),
-                Arc::clone(&self.state),
-            );
-            handles.push(tokio::spawn(async move { adapter.serve().await }));
-        }
-
-        // OSC server adapter
+        adapters.extend(config.mqtt.map(AdapterConfig::Mqtt));
         #[cfg(feature = "osc-server")]
-        if let Some(osc_config) = config.osc {
-            info!("Starting OSC server on {}", osc_config.bind_addr);
-            protocol_names.push("OSC");
-            let adapter = crate::adapters::OscServerAdapter::new(
-                osc_config,
-                Arc::clone(&self.sessions),
-                Arc::clone(&self.subscriptions),
-                Arc::clone(&self.state),
-            );
-            handles.push(tokio::spawn(async move { adapter.serve().await }));
+        adapters.extend(config.osc.map(AdapterConfig::Osc));
+        #[cfg(feature = "dashboard")]
+        adapters.extend(config.dashboard.map(AdapterConfig::Dashboard));
+        for adapter in adapters {
+            let kind = adapter.kind();
+            protocol_names.push(kind.name());
+            if let Err(e) = self.adapters.start(adapter).await {
+                error!("{} adapter failed to start: {}", kind.name(), e);
+            }
         }
 
-        if handles.is_empty() {
+        if protocol_names.is_empty() {
             return Err(RouterError::Config("No protocols configured".into()));
         }
 
+        // Health endpoints run alongside the protocols but don't count as one
+        #[cfg(feature = "health")]
+        let health_handle = config.health.map(|health_config| {
+            info!("Starting health endpoints on {}", health_config.bind_addr);
+            let server = self.health_server(health_config);
+            tokio::spawn(async move { server.serve().await })
+        });
+
         info!(
             "Multi-protocol server running with {} protocols: {}",
-            handles.len(),
+            protocol_names.len(),
             protocol_names.join(", ")
         );
 
@@ -759,14 +1093,41 @@ impl Router {
         // Start state cleanup task (removes stale params and signals)
         self.start_state_cleanup_task();
 
-        // Wait for any server to complete (usually due to error or shutdown)
+        // Start memory budget task if a budget is configured
+        if self.config.memory_budget.is_enabled() {
+            self.start_memory_budget_task();
+        }
+
+        // Run until shutdown, or until every transport and adapter has ended
+        let mut shutdown = self.shutdown.subscribe();
+        let mut adapter_ended = self.adapters.subscribe_ended();
         loop {
-            if handles.is_empty() {
+            if handles.is_empty() && self.adapters.is_idle() {
+                break;
+            }
+
+            if *shutdown.borrow_and_update() {
+                for handle in &handles {
+                    handle.abort();
+                }
+                self.adapters.stop_all().await;
                 break;
             }
 
-            let (result, _index, remaining) = select_all(handles).await;
-            handles = remaining;
+            let next = async {
+                if handles.is_empty() {
+                    std::future::pending().await
+                } else {
+                    let (result, index, _) = select_all(handles.iter_mut()).await;
+                    (result, index)
+                }
+            };
+            let (result, index) = tokio::select! {
+                next = next => next,
+                _ = shutdown.changed() => continue,
+                _ = adapter_ended.changed() => continue,
+            };
+            handles.remove(index);
 
             match result {
                 Ok(Ok(())) => {
@@ -784,6 +1145,11 @@ impl Router {
             }
         }
 
+        #[cfg(feature = "health")]
+        if let Some(handle) = health_handle {
+            handle.abort();
+        }
+
         Ok(())
     }
 
@@ -802,6 +1168,49 @@ impl Router {
         )
     }
 
+    /// Create a web dashboard sharing this router's state.
+    ///
+    /// The dashboard checks bearer tokens with the router's validator, if one
+    /// is configured.
+    #[cfg(feature = "dashboard")]
+    pub fn dashboard(
+        &self,
+        config: crate::adapters::DashboardConfig,
+    ) -> crate::adapters::DashboardAdapter {
+        self.adapters.dashboard(config)
+    }
+
+    /// Start a protocol adapter while the router keeps serving
+    ///
+    /// A running adapter of the same kind is stopped first, so this also
+    /// moves an adapter to a new address or config. Fails if the adapter
+    /// can't bind. See [`adapter_admin`] for managing adapters over the
+    /// protocol.
+    pub async fn start_adapter(&self, config: AdapterConfig) -> Result<()> {
+        self.adapters.start(config).await
+    }
+
+    /// Stop a protocol adapter, closing its listener and ending its sessions
+    ///
+    /// Returns false if the adapter wasn't running.
+    pub async fn stop_adapter(&self, kind: AdapterKind) -> bool {
+        self.adapters.stop(kind).await
+    }
+
+    /// Configs of the protocol adapters currently running
+    pub fn running_adapters(&self) -> Vec<AdapterConfig> {
+        self.adapters.configs()
+    }
+
+    /// Create `/healthz` and `/readyz` endpoints for this router
+    #[cfg(feature = "health")]
+    pub fn health_server(
+        &self,
+        config: crate::adapters::HealthServerConfig,
+    ) -> crate::adapters::HealthServer {
+        crate::adapters::HealthServer::new(config, self.clone_internal())
+    }
+
     /// Internal clone for spawning transport tasks.
     /// Shares all Arc state with the original.
     fn clone_internal(&self) -> Self {
@@ -812,8 +1221,20 @@ impl Router {
             state: Arc::clone(&self.state),
             running: Arc::clone(&self.running),
             token_validator: self.token_validator.clone(),
+            token_file: self.token_file.clone(),
+            device_keys: self.device_keys.clone(),
+            device_key_file: self.device_key_file.clone(),
             p2p_capabilities: Arc::clone(&self.p2p_capabilities),
             gesture_registry: self.gesture_registry.clone(),
+            draining: Arc::clone(&self.draining),
+            shutdown: Arc::clone(&self.shutdown),
+            adapter_status: Arc::clone(&self.adapter_status),
+            created_at: self.created_at,
+            feature_stats: Arc::clone(&self.feature_stats),
+            middleware: Arc::clone(&self.middleware),
+            loops: Arc::clone(&self.loops),
+            adapters: Arc::clone(&self.adapters),
+            bans: Arc::clone(&self.bans),
         }
     }
 
@@ -838,9 +1259,17 @@ impl Router {
         let config = self.config.clone();
         let running = Arc::clone(&self.running);
         let token_validator = self.token_validator.clone();
+        let token_file = self.token_file.clone();
+        let device_keys = self.device_keys.clone();
+        let device_key_file = self.device_key_file.clone();
         let security_mode = self.config.security_mode;
         let p2p_capabilities = Arc::clone(&self.p2p_capabilities);
         let gesture_registry = self.gesture_registry.clone();
+        let feature_stats = Arc::clone(&self.feature_stats);
+        let middleware = Arc::clone(&self.middleware);
+        let loops = Arc::clone(&self.loops);
+        let adapters = Arc::clone(&self.adapters);
+        let bans = Arc::clone(&self.bans);
 
         tokio::spawn(async move {
             let mut session: Option<Arc<Session>> = None;
@@ -913,24 +1342,40 @@ impl Router {
                     &config,
                     security_mode,
                     &token_validator,
+                    &token_file,
+                    &device_keys,
+                    &device_key_file,
+                    &adapters,
+                    &bans,
                     &p2p_capabilities,
                     &gesture_registry,
+                    &middleware,
                 )
                 .await
                 {
                     match response {
                         MessageResult::NewSession(s) => {
+                            bans.track(&s.id, addr.ip());
                             session = Some(s);
                             handshake_complete = true;
                         }
                         MessageResult::Send(bytes) => {
                             let _ = sender.send(bytes).await;
                         }
-                        MessageResult::Disconnect => {
+                        MessageResult::Unauthorized => {
                             info!(
                                 "Disconnecting client {} due to auth failure during handshake",
                                 addr
                             );
+                            if let Some(event) = bans.auth_failed(addr.ip()) {
+                                apply_security_event(&event, &bans, &sessions, &subscriptions);
+                            }
+                            let _ = sender.close().await;
+                            return;
+                        }
+                        MessageResult::Disconnect => {
+                            info!("Disconnecting client {} during handshake", addr);
+                            let _ = sender.close().await;
                             return;
                         }
                         _ => {}
@@ -943,8 +1388,26 @@ impl Router {
                 return;
             }
 
+            // Middlewares may refuse the session now that it is identified
+            let mut vetoed = false;
+            if let Some(ref s) = session {
+                let verdict = middleware.connect(s).await;
+                if !verdict.is_continue() {
+                    info!("Session {} refused by middleware", s.id);
+                    if let Verdict::Reject { code, message } = verdict {
+                        let error = Message::Error(ErrorMessage::new(code, message));
+                        if let Ok(bytes) = codec::encode(&error) {
+                            let _ = sender.send(bytes).await;
+                        }
+                    }
+                    let _ = sender.close().await;
+                    vetoed = true;
+                }
+            }
+
             // Phase 2: Main message loop (after successful handshake)
-            while *running.read() {
+            let mut channels = Channels::default();
+            while !vetoed && *running.read() {
                 match receiver.recv().await {
                     Some(TransportEvent::Data(data)) => {
                         // Check rate limit before processing
@@ -958,15 +1421,13 @@ impl Router {
                                         config.max_messages_per_second
                                     );
                                     // Send error and continue (don't disconnect for rate limiting)
-                                    let error = Message::Error(ErrorMessage {
-                                        code: 429, // Too Many Requests
-                                        message: format!(
+                                    let error = Message::Error(ErrorMessage::new(
+                                        ErrorCode::RateLimited,
+                                        format!(
                                             "Rate limit exceeded: {} messages/second",
                                             config.max_messages_per_second
                                         ),
-                                        address: None,
-                                        correlation_id: None,
-                                    });
+                                    ));
                                     if let Ok(bytes) = codec::encode(&error) {
                                         let _ = sender.send(bytes).await;
                                     }
@@ -978,44 +1439,331 @@ impl Router {
                         // Decode message
                         match codec::decode(&data) {
                             Ok((msg, frame)) => {
+                                // Logical clients sharing this connection
+                                // send and receive on their own channel
+                                let (mut msg, channel) = match msg {
+                                    Message::Channel(ChannelMessage { channel, message }) => {
+                                        for stale in channels.remove_closed() {
+                                            remove_session(
+                                                &stale,
+                                                &sessions,
+                                                &subscriptions,
+                                                &p2p_capabilities,
+                                                &middleware,
+                                            )
+                                            .await;
+                                        }
+                                        let Some(inner) = message else {
+                                            if let Some(s) = channels.remove(channel) {
+                                                debug!("Channel {} closed by {}", channel, addr);
+                                                remove_session(
+                                                    &s,
+                                                    &sessions,
+                                                    &subscriptions,
+                                                    &p2p_capabilities,
+                                                    &middleware,
+                                                )
+                                                .await;
+                                            }
+                                            continue;
+                                        };
+                                        (*inner, Some(channel))
+                                    }
+                                    msg => (msg, None),
+                                };
+
+                                let (target, reply_to, channel_sender) = match channel {
+                                    None => (session.clone(), Arc::clone(&sender), None),
+                                    Some(id) => {
+                                        let is_hello = matches!(msg, Message::Hello(_));
+                                        if is_hello {
+                                            // A new HELLO replaces the channel's session
+                                            if let Some(s) = channels.remove(id) {
+                                                remove_session(
+                                                    &s,
+                                                    &sessions,
+                                                    &subscriptions,
+                                                    &p2p_capabilities,
+                                                    &middleware,
+                                                )
+                                                .await;
+                                            }
+                                        }
+
+                                        let open = channels.get(id);
+                                        let channel_sender = match open {
+                                            Some((_, ref s)) => Arc::clone(s),
+                                            None => Arc::new(ChannelSender::new(
+                                                id,
+                                                Arc::clone(&sender),
+                                            )),
+                                        };
+                                        let refusal = if config.max_channels == 0 {
+                                            Some(ErrorMessage::new(
+                                                ErrorCode::UnsupportedFeature,
+                                                "Connection sharing is disabled",
+                                            ))
+                                        } else if !is_hello && open.is_none() {
+                                            Some(ErrorMessage::new(
+                                                ErrorCode::InvalidMessage,
+                                                format!("Channel {} is not open", id),
+                                            ))
+                                        } else if is_hello && channels.len() >= config.max_channels
+                                        {
+                                            Some(ErrorMessage::new(
+                                                ErrorCode::LimitExceeded,
+                                                format!(
+                                                    "Channel limit reached (max {})",
+                                                    config.max_channels
+                                                ),
+                                            ))
+                                        } else if is_hello && sessions.len() >= config.max_sessions
+                                        {
+                                            Some(ErrorMessage::new(
+                                                ErrorCode::LimitExceeded,
+                                                "Session limit reached",
+                                            ))
+                                        } else {
+                                            None
+                                        };
+                                        if let Some(error) = refusal {
+                                            debug!(
+                                                "Channel {} from {} refused: {}",
+                                                id, addr, error.message
+                                            );
+                                            channel::refuse(&channel_sender, error).await;
+                                            continue;
+                                        }
+
+                                        let reply_to: Arc<dyn TransportSender> =
+                                            channel_sender.clone();
+                                        (open.map(|(s, _)| s), reply_to, Some(channel_sender))
+                                    }
+                                };
+
+                                // Expired tokens are disconnected unless this
+                                // is the client presenting a fresh one
+                                if let Some(ref s) = target {
+                                    let refreshing = matches!(&msg, Message::Set(set) if set.address == SESSION_TOKEN_ADDRESS);
+                                    if s.is_expired() && !refreshing {
+                                        warn!("Session {} token expired, disconnecting", s.id);
+                                        let error = Message::Error(ErrorMessage::new(
+                                            ErrorCode::TokenExpired,
+                                            "Token has expired".to_string(),
+                                        ));
+                                        if let Ok(bytes) = codec::encode(&error) {
+                                            let _ = reply_to.send(bytes).await;
+                                        }
+                                        let _ = reply_to.close().await;
+                                        match channel {
+                                            None => break,
+                                            Some(id) => {
+                                                channels.remove(id);
+                                                remove_session(
+                                                    s,
+                                                    &sessions,
+                                                    &subscriptions,
+                                                    &p2p_capabilities,
+                                                    &middleware,
+                                                )
+                                                .await;
+                                                continue;
+                                            }
+                                        }
+                                    }
+
+                                    if let Err(denied) = feature_stats.check(
+                                        &msg,
+                                        &s.features,
+                                        &config.features,
+                                        config.enforce_features,
+                                    ) {
+                                        debug!("Session {} rejected: {}", s.id, denied.message());
+                                        let error = Message::Error(ErrorMessage {
+                                            address: message_address(&msg),
+                                            correlation_id: message_correlation_id(&msg),
+                                            ..ErrorMessage::new(
+                                                ErrorCode::UnsupportedFeature,
+                                                denied.message(),
+                                            )
+                                        });
+                                        if let Ok(bytes) = codec::encode(&error) {
+                                            let _ = reply_to.send(bytes).await;
+                                        }
+                                        continue;
+                                    }
+
+                                    match middleware.message(s, &mut msg).await {
+                                        Verdict::Continue => {}
+                                        Verdict::Drop => continue,
+                                        Verdict::Reject { code, message } => {
+                                            let error = Message::Error(ErrorMessage {
+                                                address: message_address(&msg),
+                                                correlation_id: message_correlation_id(&msg),
+                                                ..ErrorMessage::new(code, message)
+                                            });
+                                            if let Ok(bytes) = codec::encode(&error) {
+                                                let _ = reply_to.send(bytes).await;
+                                            }
+                                            continue;
+                                        }
+                                    }
+
+                                    // Values bouncing between sessions stop here
+                                    if let Hop::Break { report } =
+                                        loops.check(&msg, s, &subscriptions)
+                                    {
+                                        if let Some(report) = report {
+                                            warn!(
+                                                "Feedback loop on {} broken after {} hops: {}",
+                                                report.address,
+                                                report.hops,
+                                                report.path.join(" -> ")
+                                            );
+                                            publish_event(
+                                                &report.to_message(),
+                                                &sessions,
+                                                &subscriptions,
+                                            );
+                                        }
+                                        let error = Message::Error(ErrorMessage {
+                                            address: message_address(&msg),
+                                            correlation_id: message_correlation_id(&msg),
+                                            ..ErrorMessage::new(
+                                                ErrorCode::FeedbackLoop,
+                                                "Write dropped to break a feedback loop",
+                                            )
+                                        });
+                                        if let Ok(bytes) = codec::encode(&error) {
+                                            let _ = reply_to.send(bytes).await;
+                                        }
+                                        continue;
+                                    }
+                                }
+
                                 // Handle message
                                 if let Some(response) = handle_message(
                                     &msg,
                                     &frame,
-                                    &session,
-                                    &sender,
+                                    &target,
+                                    &reply_to,
                                     &sessions,
                                     &subscriptions,
                                     &state,
                                     &config,
                                     security_mode,
                                     &token_validator,
+                                    &token_file,
+                                    &device_keys,
+                                    &device_key_file,
+                                    &adapters,
+                                    &bans,
                                     &p2p_capabilities,
                                     &gesture_registry,
+                                    &middleware,
                                 )
                                 .await
                                 {
-                                    match response {
-                                        MessageResult::NewSession(s) => {
+                                    if matches!(response, MessageResult::Unauthorized) {
+                                        if let Some(event) = bans.auth_failed(addr.ip()) {
+                                            apply_security_event(
+                                                &event,
+                                                &bans,
+                                                &sessions,
+                                                &subscriptions,
+                                            );
+                                        }
+                                    }
+                                    match (response, channel_sender) {
+                                        (MessageResult::NewSession(s), None) => {
+                                            if let Some(ref old) = session {
+                                                bans.untrack(&old.id);
+                                            }
+                                            bans.track(&s.id, addr.ip());
                                             session = Some(s);
                                         }
-                                        MessageResult::Send(bytes) => {
-                                            if let Err(e) = sender.send(bytes).await {
+                                        (MessageResult::NewSession(s), Some(channel_sender)) => {
+                                            if let Some(ref parent) = session {
+                                                s.set_parent(parent.id.clone());
+                                            }
+                                            let verdict = middleware.connect(&s).await;
+                                            if let Verdict::Reject { code, message } = verdict {
+                                                info!("Session {} refused by middleware", s.id);
+                                                channel::refuse(
+                                                    &channel_sender,
+                                                    ErrorMessage::new(code, message),
+                                                )
+                                                .await;
+                                            } else if verdict.is_continue() {
+                                                debug!(
+                                                    "Session {} opened on channel {} of {}",
+                                                    s.id,
+                                                    channel_sender.channel(),
+                                                    addr
+                                                );
+                                                channels.insert(
+                                                    channel_sender.channel(),
+                                                    s,
+                                                    channel_sender,
+                                                );
+                                                continue;
+                                            } else {
+                                                let _ = channel_sender.close().await;
+                                            }
+                                            remove_session(
+                                                &s,
+                                                &sessions,
+                                                &subscriptions,
+                                                &p2p_capabilities,
+                                                &middleware,
+                                            )
+                                            .await;
+                                        }
+                                        (MessageResult::Send(bytes), channel_sender) => {
+                                            if let Err(e) = reply_to.send(bytes).await {
                                                 error!("Send error: {}", e);
-                                                break;
+                                                if channel_sender.is_none() {
+                                                    break;
+                                                }
                                             }
                                         }
-                                        MessageResult::Broadcast(bytes, exclude) => {
+                                        (MessageResult::Broadcast(bytes, exclude), _) => {
                                             broadcast_to_subscribers(&bytes, &sessions, &exclude);
                                         }
-                                        MessageResult::Disconnect => {
+                                        (
+                                            MessageResult::Disconnect | MessageResult::Unauthorized,
+                                            None,
+                                        ) => {
                                             info!(
                                                 "Disconnecting client {} due to auth failure",
                                                 addr
                                             );
                                             break;
                                         }
-                                        MessageResult::None => {}
+                                        (
+                                            MessageResult::Disconnect | MessageResult::Unauthorized,
+                                            Some(channel_sender),
+                                        ) => {
+                                            info!(
+                                                "Closing channel {} of {} due to auth failure",
+                                                channel_sender.channel(),
+                                                addr
+                                            );
+                                            let _ = channel_sender.close().await;
+                                            if let Some(s) =
+                                                channels.remove(channel_sender.channel())
+                                            {
+                                                remove_session(
+                                                    &s,
+                                                    &sessions,
+                                                    &subscriptions,
+                                                    &p2p_capabilities,
+                                                    &middleware,
+                                                )
+                                                .await;
+                                            }
+                                        }
+                                        (MessageResult::None, _) => {}
                                     }
                                 }
                             }
@@ -1039,19 +1787,102 @@ impl Router {
                 }
             }
 
-            // Cleanup session
-            if let Some(s) = session {
-                info!("Removing session {}", s.id);
-                sessions.remove(&s.id);
-                subscriptions.remove_session(&s.id);
-                p2p_capabilities.unregister(&s.id);
+            if let Some(ref s) = session {
+                bans.untrack(&s.id);
+            }
+
+            // Cleanup sub-sessions, then the session
+            for s in channels.drain().into_iter().chain(session) {
+                remove_session(
+                    &s,
+                    &sessions,
+                    &subscriptions,
+                    &p2p_capabilities,
+                    &middleware,
+                )
+                .await;
             }
         });
     }
 
     /// Stop the router
+    ///
+    /// Accept loops exit and multi-protocol adapters are stopped. Existing
+    /// sessions are left alone; use [`Router::shutdown`] to drain them first.
     pub fn stop(&self) {
         *self.running.write() = false;
+        self.shutdown.send_replace(true);
+    }
+
+    /// Gracefully shut down: stop reporting ready, wait up to `drain` for
+    /// clients to disconnect, close whatever is left, then [`stop`](Router::stop)
+    pub async fn shutdown(&self, drain: Duration) {
+        self.draining.store(true, Ordering::SeqCst);
+        info!(
+            "Draining {} sessions (timeout {:?})",
+            self.sessions.len(),
+            drain
+        );
+
+        let deadline = tokio::time::Instant::now() + drain;
+        while !self.sessions.is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let remaining: Vec<Arc<Session>> = self
+            .sessions
+            .iter()
+            .map(|s| Arc::clone(s.value()))
+            .collect();
+        if !remaining.is_empty() {
+            info!("Closing {} remaining sessions", remaining.len());
+        }
+        for session in remaining {
+            let _ = session.close().await;
+        }
+
+        self.adapters.stop_all().await;
+        self.stop();
+    }
+
+    /// Whether [`Router::shutdown`] has started
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Subscription statistics, with the `hot` busiest addresses
+    ///
+    /// Clients with admin scope read the same over the protocol with a GET
+    /// on [`subscription::STATS_ADDRESS`].
+    pub fn subscription_stats(&self, hot: usize) -> SubscriptionStats {
+        self.subscriptions.stats(hot)
+    }
+
+    /// Current health: accepting state, adapter status and counts
+    pub fn health(&self) -> HealthReport {
+        let adapters = self
+            .adapter_status
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let mut report = HealthReport {
+            ready: false,
+            accepting: *self.running.read() && !*self.shutdown.borrow(),
+            draining: self.is_draining(),
+            sessions: self.sessions.len(),
+            max_sessions: self.config.max_sessions,
+            subscriptions: self.subscriptions.len(),
+            params: self.state.len(),
+            uptime_secs: self.created_at.elapsed().as_secs(),
+            adapters,
+        };
+        report.ready = report.is_ready();
+        report
+    }
+
+    /// Accepted/rejected message counts per HELLO feature
+    pub fn feature_usage(&self) -> BTreeMap<String, FeatureUsage> {
+        self.feature_stats.snapshot()
     }
 
     /// Get session count
@@ -1059,6 +1890,14 @@ impl Router {
         self.sessions.len()
     }
 
+    /// Connected sessions
+    pub fn sessions(&self) -> Vec<Arc<Session>> {
+        self.sessions
+            .iter()
+            .map(|s| Arc::clone(s.value()))
+            .collect()
+    }
+
     /// Get state
     pub fn state(&self) -> &RouterState {
         &self.state
@@ -1082,60 +1921,620 @@ enum MessageResult {
     Send(Bytes),
     Broadcast(Bytes, SessionId),
     Disconnect,
+    /// Credentials were rejected; disconnect and count it against the peer
+    Unauthorized,
     None,
 }
 
-/// Maximum params per snapshot chunk to stay under frame size limit.
+/// Maximum params per batched-delivery SNAPSHOT to stay under frame size limit.
 /// Frame max payload is 65535 bytes. With ~44 bytes per param average,
 /// we target 800 params per chunk (~35KB) to leave headroom.
 const MAX_SNAPSHOT_CHUNK_SIZE: usize = 800;
 
-/// Send a snapshot, chunking if too large for a single frame.
-async fn send_chunked_snapshot(sender: &Arc<dyn TransportSender>, snapshot: SnapshotMessage) {
-    let param_count = snapshot.params.len();
+/// Payload budget of one snapshot page, leaving headroom under the 64KB
+/// frame limit for CHANNEL wrapping
+const SNAPSHOT_PAGE_BYTES: usize = 60_000;
+
+/// Tag a session's traffic with the origin a bridge declares for it
+fn handle_declare_origin(set: &SetMessage, session: &Arc<Session>) -> Message {
+    let Some(origin) = Origin::from_value(&set.value) else {
+        return Message::Error(
+            ErrorMessage::new(
+                ErrorCode::InvalidValue,
+                "Expected an origin: {protocol, remote?} or a protocol string".to_string(),
+            )
+            .with_address(&set.address),
+        );
+    };
 
-    if param_count <= MAX_SNAPSHOT_CHUNK_SIZE {
-        // Small enough to send in one frame
-        let msg = Message::Snapshot(snapshot);
-        if let Ok(bytes) = codec::encode(&msg) {
-            let _ = sender.send(bytes).await;
-        } else {
-            warn!("Failed to encode snapshot ({} params)", param_count);
+    info!(
+        target: AUDIT_LOG_TARGET,
+        "Session {} ({}) declared origin {}", session.id, session.name, origin
+    );
+    session.set_origin(origin);
+    Message::Ack(AckMessage {
+        address: Some(set.address.clone()),
+        revision: None,
+        locked: None,
+        holder: None,
+        value: None,
+        correlation_id: None,
+    })
+}
+
+/// Check a HELLO or re-authentication token: device key proofs go to the
+/// device keys, everything else to the token validator. None when neither
+/// is configured.
+fn validate_token(
+    token: &str,
+    token_validator: &Option<Arc<dyn TokenValidator>>,
+    device_keys: &Option<Arc<DeviceKeyValidator>>,
+) -> Option<ValidationResult> {
+    if let Some(keys) = device_keys {
+        if token.starts_with(DeviceIdentity::PREFIX) {
+            return Some(keys.validate(token));
         }
-        return;
     }
+    token_validator
+        .as_ref()
+        .map(|validator| validator.validate(token))
+}
 
-    // Chunk large snapshots
-    let chunks = snapshot.params.chunks(MAX_SNAPSHOT_CHUNK_SIZE);
-    let chunk_count = (param_count + MAX_SNAPSHOT_CHUNK_SIZE - 1) / MAX_SNAPSHOT_CHUNK_SIZE;
-
-    debug!(
-        "Chunking snapshot of {} params into {} chunks",
-        param_count, chunk_count
-    );
+/// Swap a session's credentials for the token in a SET to
+/// [`SESSION_TOKEN_ADDRESS`] and build the reply
+///
+/// On failure the session keeps its current token.
+fn handle_reauthenticate(
+    set: &SetMessage,
+    session: &Arc<Session>,
+    security_mode: SecurityMode,
+    token_validator: &Option<Arc<dyn TokenValidator>>,
+    device_keys: &Option<Arc<DeviceKeyValidator>>,
+) -> Message {
+    let error = |code: ErrorCode, message: String| {
+        Message::Error(ErrorMessage::new(code, message).with_address(&set.address))
+    };
+
+    let unsupported = || {
+        error(
+            ErrorCode::UnsupportedFeature,
+            "Router does not use token authentication".to_string(),
+        )
+    };
+    if security_mode != SecurityMode::Authenticated {
+        return unsupported();
+    }
+    let Some(token) = set.value.as_str() else {
+        return error(
+            ErrorCode::InvalidValue,
+            "Expected the new token as a string".to_string(),
+        );
+    };
+    let Some(result) = validate_token(token, token_validator, device_keys) else {
+        return unsupported();
+    };
 
-    for (i, chunk) in chunks.enumerate() {
-        let chunk_snapshot = SnapshotMessage {
-            params: chunk.to_vec(),
-        };
-        let msg = Message::Snapshot(chunk_snapshot);
-        match codec::encode(&msg) {
-            Ok(bytes) => {
-                if let Err(e) = sender.send(bytes).await {
-                    warn!(
-                        "Failed to send snapshot chunk {}/{}: {}",
-                        i + 1,
-                        chunk_count,
-                        e
-                    );
-                    break;
+    match result {
+        ValidationResult::Valid(info) => {
+            info!(
+                "Session {} re-authenticated (subject: {:?}, scopes: {})",
+                session.id,
+                info.subject,
+                info.scopes.len()
+            );
+            session.reauthenticate(token.to_string(), info);
+            Message::Ack(AckMessage {
+                address: Some(set.address.clone()),
+                revision: None,
+                locked: None,
+                holder: None,
+                value: None,
+                correlation_id: None,
+            })
+        }
+        ValidationResult::Expired => {
+            error(ErrorCode::TokenExpired, "Token has expired".to_string())
+        }
+        ValidationResult::Invalid(reason) => error(
+            ErrorCode::Unauthorized,
+            format!("Invalid token: {}", reason),
+        ),
+        ValidationResult::NotMyToken => error(
+            ErrorCode::Unauthorized,
+            "Unrecognized token format".to_string(),
+        ),
+    }
+}
+
+/// ERROR for a rejected SET, with the current revision and value on a conflict
+fn update_error(error: &UpdateError, address: &str) -> ErrorMessage {
+    let message =
+        ErrorMessage::new(update_error_code(error), error.to_string()).with_address(address);
+    match error {
+        UpdateError::RevisionConflict {
+            actual, current, ..
+        } => message.with_current(*actual, current.clone()),
+        _ => message,
+    }
+}
+
+/// Canonical error code for a rejected SET
+fn update_error_code(error: &UpdateError) -> ErrorCode {
+    match error {
+        UpdateError::RevisionConflict { .. } => ErrorCode::RevisionConflict,
+        UpdateError::LockHeld { .. } => ErrorCode::LockHeld,
+        UpdateError::ConflictRejected => ErrorCode::ConflictRejected,
+        UpdateError::OutOfRange => ErrorCode::InvalidValue,
+        UpdateError::AtCapacity => ErrorCode::LimitExceeded,
+        UpdateError::Missing => ErrorCode::AddressNotFound,
+        UpdateError::InvalidOperand => ErrorCode::InvalidValue,
+    }
+}
+
+/// Current revision if `set` only repeats the retained value on a dedup pattern
+///
+/// SETs that lock, unlock, carry an expected revision or an op always go
+/// through the state store, as does anything aimed at a param locked by someone else.
+fn duplicate_revision(
+    set: &SetMessage,
+    session: &Session,
+    state: &RouterState,
+    dedup: &SetDedup,
+) -> Option<u64> {
+    if dedup.is_empty() || set.lock || set.unlock || set.revision.is_some() || set.op.is_some() {
+        return None;
+    }
+    dedup.rule_for(&set.address)?;
+    let current = state.get_state(&set.address)?;
+    let locked_by_other = current
+        .lock_holder
+        .as_ref()
+        .is_some_and(|holder| *holder != session.id);
+    (!locked_by_other && dedup.is_duplicate(&set.address, &current.value, &set.value))
+        .then_some(current.revision)
+}
+
+/// ERROR for a wildcard write reaching an address the session may not write
+fn wildcard_scope_error(
+    session: &Session,
+    security_mode: SecurityMode,
+    addresses: &[String],
+) -> Option<Message> {
+    if security_mode != SecurityMode::Authenticated {
+        return None;
+    }
+    let denied = addresses
+        .iter()
+        .find(|address| !session.has_scope(Action::Write, address))?;
+    warn!(
+        "Session {} denied wildcard write reaching {} - insufficient scope",
+        session.id, denied
+    );
+    Some(Message::Error(
+        ErrorMessage::new(
+            ErrorCode::Forbidden,
+            "Insufficient scope for write operation".to_string(),
+        )
+        .with_address(denied),
+    ))
+}
+
+/// The SET delivered to subscribers once `set` has been applied
+///
+/// Relative SETs go out as the absolute value they resulted in.
+fn applied_set(set: &SetMessage, revision: u64, value: Value) -> SetMessage {
+    SetMessage {
+        address: set.address.clone(),
+        value,
+        revision: Some(revision),
+        lock: set.lock,
+        unlock: set.unlock,
+        op: None,
+        correlation_id: None,
+    }
+}
+
+/// Address a client message targets, for ERROR replies
+fn message_address(msg: &Message) -> Option<String> {
+    match msg {
+        Message::Set(set) => Some(set.address.clone()),
+        Message::Publish(publish) => Some(publish.address.clone()),
+        _ => None,
+    }
+}
+
+/// Correlation id of a client request, echoed in its ACK/ERROR/SNAPSHOT
+fn message_correlation_id(msg: &Message) -> Option<u32> {
+    match msg {
+        Message::Set(set) => set.correlation_id,
+        Message::Get(get) => get.correlation_id,
+        Message::Subscribe(sub) => sub.correlation_id,
+        Message::Bundle(bundle) => bundle.correlation_id,
+        _ => None,
+    }
+}
+
+/// Handle a SET or GET on `/$sys/adapters/**` and build the reply
+async fn handle_adapter_admin(
+    msg: &Message,
+    session: &Arc<Session>,
+    security_mode: SecurityMode,
+    adapters: &AdapterRegistry,
+) -> Message {
+    let address = match msg {
+        Message::Set(set) => set.address.as_str(),
+        Message::Get(get) => get.address.as_str(),
+        _ => unreachable!("adapter admin handles SET and GET only"),
+    };
+    let error = |code: ErrorCode, message: String| {
+        Message::Error(ErrorMessage::new(code, message).with_address(address))
+    };
+
+    if security_mode != SecurityMode::Authenticated || !session.has_scope(Action::Admin, address) {
+        warn!(
+            "Session {} denied adapter management on {} - requires admin scope",
+            session.id, address
+        );
+        return error(
+            ErrorCode::Forbidden,
+            "Adapter management requires admin scope".to_string(),
+        );
+    }
+
+    let set = match msg {
+        Message::Get(_) => return Message::Snapshot(adapters.snapshot(address)),
+        Message::Set(set) => set,
+        _ => unreachable!(),
+    };
+
+    let Some(kind) = adapter_admin::kind_from_address(address) else {
+        return error(
+            ErrorCode::InvalidAddress,
+            "Expected /$sys/adapters/mqtt, /osc or /dashboard".to_string(),
+        );
+    };
+
+    if matches!(set.value, Value::Null) {
+        if !adapters.stop(kind).await {
+            return error(
+                ErrorCode::AddressNotFound,
+                format!("{} adapter is not running", kind.name()),
+            );
+        }
+        info!("Session {} stopped the {} adapter", session.id, kind.name());
+    } else {
+        let config = match AdapterConfig::from_value(kind, &set.value) {
+            Ok(config) => config,
+            Err(e) => return error(ErrorCode::InvalidValue, e.to_string()),
+        };
+        if let Err(e) = adapters.start(config).await {
+            return error(ErrorCode::InternalError, e.to_string());
+        }
+        info!("Session {} started the {} adapter", session.id, kind.name());
+    }
+
+    Message::Ack(AckMessage {
+        address: Some(address.to_string()),
+        revision: None,
+        locked: None,
+        holder: None,
+        value: None,
+        correlation_id: None,
+    })
+}
+
+/// Handle a SET or GET on `/$sys/security/bans/**` and build the reply
+fn handle_ban_admin(
+    msg: &Message,
+    session: &Arc<Session>,
+    security_mode: SecurityMode,
+    bans: &BanList,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    subscriptions: &SubscriptionManager,
+) -> Message {
+    let address = match msg {
+        Message::Set(set) => set.address.as_str(),
+        Message::Get(get) => get.address.as_str(),
+        _ => unreachable!("ban admin handles SET and GET only"),
+    };
+    let error = |code: ErrorCode, message: String| {
+        Message::Error(ErrorMessage::new(code, message).with_address(address))
+    };
+
+    if security_mode != SecurityMode::Authenticated || !session.has_scope(Action::Admin, address) {
+        warn!(
+            "Session {} denied ban management on {} - requires admin scope",
+            session.id, address
+        );
+        return error(
+            ErrorCode::Forbidden,
+            "Ban management requires admin scope".to_string(),
+        );
+    }
+
+    let set = match msg {
+        Message::Get(_) => return Message::Snapshot(bans.snapshot(address)),
+        Message::Set(set) => set,
+        _ => unreachable!(),
+    };
+
+    let Some(ip) = ban::ip_from_address(address) else {
+        return error(
+            ErrorCode::InvalidAddress,
+            format!("Expected {}/<ip>", ban::BANS_PREFIX),
+        );
+    };
+
+    let event = if matches!(set.value, Value::Null) {
+        match bans.unban(ip) {
+            Some(event) => event,
+            None => return error(ErrorCode::AddressNotFound, format!("{} is not banned", ip)),
+        }
+    } else {
+        let (duration, reason) = match ban::ban_from_value(&set.value) {
+            Ok(ban) => ban,
+            Err(e) => return error(ErrorCode::InvalidValue, e),
+        };
+        info!("Session {} banned {}", session.id, ip);
+        bans.ban(ip, duration, reason)
+    };
+    apply_security_event(&event, bans, sessions, subscriptions);
+
+    Message::Ack(AckMessage {
+        address: Some(address.to_string()),
+        revision: None,
+        locked: None,
+        holder: None,
+        value: None,
+        correlation_id: None,
+    })
+}
+
+/// Answer a GET on the subscription stats address
+/// Check a SET to `/$manifest/<app>` before it's applied
+///
+/// The value must be a valid manifest for `app` (or null to withdraw it),
+/// and only the publishing session may replace a manifest while it's
+/// connected.
+fn manifest_publish_error(
+    set: &SetMessage,
+    app: &str,
+    session: &Arc<Session>,
+    state: &RouterState,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+) -> Option<Message> {
+    let error = |code: ErrorCode, message: String| {
+        Some(Message::Error(
+            ErrorMessage::new(code, message).with_address(&set.address),
+        ))
+    };
+    if set.op.is_some() {
+        return error(
+            ErrorCode::InvalidValue,
+            "Manifests can't be updated with relative SETs".to_string(),
+        );
+    }
+    if let Some(entry) = state.manifest(app) {
+        if entry.owner != session.id && sessions.contains_key(&entry.owner) {
+            return error(
+                ErrorCode::Forbidden,
+                format!("Manifest for '{}' is owned by another session", app),
+            );
+        }
+    }
+    if set.value == Value::Null {
+        return None;
+    }
+    match Manifest::from_value(&set.value) {
+        Ok(manifest) if manifest.app == app => None,
+        Ok(manifest) => error(
+            ErrorCode::InvalidValue,
+            format!("Manifest for '{}' can't be published here", manifest.app),
+        ),
+        Err(e) => error(ErrorCode::InvalidValue, e.to_string()),
+    }
+}
+
+/// ERROR for a write that doesn't fit its namespace's manifest
+fn manifest_violation_error(address: &str, violation: &ManifestViolation) -> Message {
+    let code = match violation {
+        ManifestViolation::ReadOnly => ErrorCode::Forbidden,
+        _ => ErrorCode::InvalidValue,
+    };
+    Message::Error(ErrorMessage::new(code, violation.to_string()).with_address(address))
+}
+
+fn subscription_stats_reply(
+    session: &Arc<Session>,
+    subscriptions: &SubscriptionManager,
+) -> Message {
+    let address = subscription::STATS_ADDRESS;
+    if !session.has_scope(Action::Admin, address) {
+        warn!(
+            "Session {} denied subscription stats - requires admin scope",
+            session.id
+        );
+        return Message::Error(
+            ErrorMessage::new(
+                ErrorCode::Forbidden,
+                "Subscription stats require admin scope",
+            )
+            .with_address(address),
+        );
+    }
+
+    let stats = subscriptions.stats(subscription::STATS_HOT_ADDRESSES);
+    Message::Snapshot(SnapshotMessage {
+        params: vec![ParamValue {
+            address: address.to_string(),
+            value: stats.to_value(),
+            revision: 0,
+            writer: None,
+            timestamp: None,
+        }],
+        correlation_id: None,
+        page: None,
+        sync: None,
+    })
+}
+
+/// The router's token validator, if it's a [`CpskValidator`]
+fn cpsk_validator(token_validator: &Option<Arc<dyn TokenValidator>>) -> Option<&CpskValidator> {
+    token_validator
+        .as_ref()
+        .and_then(|v| v.as_any().downcast_ref::<CpskValidator>())
+}
+
+/// Handle a SET or GET on `/$sys/tokens/**` or `/$sys/keys/**` and build the
+/// reply; `store` is the validator holding those credentials
+fn handle_token_admin<S: CredentialStore>(
+    msg: &Message,
+    session: &Arc<Session>,
+    sessions: &Arc<DashMap<SessionId, Arc<Session>>>,
+    security_mode: SecurityMode,
+    store: Option<&S>,
+    token_file: &Option<Arc<PathBuf>>,
+) -> Message {
+    let address = match msg {
+        Message::Set(set) => set.address.as_str(),
+        Message::Get(get) => get.address.as_str(),
+        _ => unreachable!("token admin handles SET and GET only"),
+    };
+    let error = |code: ErrorCode, message: String| {
+        Message::Error(ErrorMessage::new(code, message).with_address(address))
+    };
+
+    if security_mode != SecurityMode::Authenticated || !session.has_scope(Action::Admin, address) {
+        warn!(
+            "Session {} denied credential management on {} - requires admin scope",
+            session.id, address
+        );
+        return error(
+            ErrorCode::Forbidden,
+            "Token management requires admin scope".to_string(),
+        );
+    }
+    let Some(validator) = store else {
+        return error(
+            ErrorCode::InternalError,
+            format!("No validator manages {}", S::PREFIX),
+        );
+    };
+
+    let set = match msg {
+        Message::Get(_) => {
+            return Message::Snapshot(token_admin::token_snapshot(validator, address));
+        }
+        Message::Set(set) => set,
+        _ => unreachable!(),
+    };
+
+    let Some(token) = token_admin::credential_from_address(S::PREFIX, address) else {
+        return error(
+            ErrorCode::InvalidAddress,
+            format!("Expected {}/<credential>", S::PREFIX),
+        );
+    };
+
+    if matches!(set.value, Value::Null) {
+        if !validator.revoke_credential(token) {
+            return error(ErrorCode::AddressNotFound, "Token not found".to_string());
+        }
+        info!("Session {} revoked {}", session.id, address);
+
+        // Disconnect sessions still using the revoked credential
+        let revoked: Vec<Arc<Session>> = sessions
+            .iter()
+            .filter(|s| {
+                s.token()
+                    .is_some_and(|presented| S::authenticated_by(&presented, token))
+            })
+            .map(|s| Arc::clone(s.value()))
+            .collect();
+        if !revoked.is_empty() {
+            tokio::spawn(async move {
+                for session in revoked {
+                    info!("Closing session {} (credential revoked)", session.id);
+                    let _ = session.close().await;
+                }
+            });
+        }
+    } else {
+        let info = match token_admin::token_info_from_value(token, &set.value) {
+            Ok(info) => info,
+            Err(e) => return error(ErrorCode::InvalidValue, e.to_string()),
+        };
+        let (subject, scopes) = (info.subject.clone(), info.scopes.len());
+        if let Err(e) = validator.register_credential(token, info) {
+            return error(ErrorCode::InvalidAddress, e.to_string());
+        }
+        info!(
+            "Session {} registered {} (subject: {:?}, scopes: {})",
+            session.id, address, subject, scopes
+        );
+    }
+
+    if let Some(path) = token_file {
+        if let Err(e) = token_admin::save_token_file(path, validator) {
+            error!("Failed to persist tokens to {}: {}", path.display(), e);
+            return error(
+                ErrorCode::InternalError,
+                format!("Token updated but not persisted: {}", e),
+            );
+        }
+    }
+
+    Message::Ack(AckMessage {
+        address: Some(address.to_string()),
+        revision: None,
+        locked: None,
+        holder: None,
+        value: None,
+        correlation_id: None,
+    })
+}
+
+/// Tag a reply with the request's correlation id and encode it
+fn reply(mut msg: Message, correlation_id: Option<u32>) -> Option<MessageResult> {
+    match &mut msg {
+        Message::Ack(ack) => ack.correlation_id = correlation_id,
+        Message::Error(error) => error.correlation_id = correlation_id,
+        Message::Snapshot(snapshot) => snapshot.correlation_id = correlation_id,
+        _ => {}
+    }
+    Some(MessageResult::Send(codec::encode(&msg).ok()?))
+}
+
+/// Send a snapshot, split into pages if too large for a single frame
+async fn send_paged_snapshot(sender: &Arc<dyn TransportSender>, snapshot: SnapshotMessage) {
+    let param_count = snapshot.params.len();
+    let pages = codec::paginate_snapshot(snapshot, SNAPSHOT_PAGE_BYTES);
+    let page_count = pages.len();
+    if page_count > 1 {
+        debug!(
+            "Paging snapshot of {} params into {} pages",
+            param_count, page_count
+        );
+    }
+
+    for (i, page) in pages.into_iter().enumerate() {
+        match codec::encode(&Message::Snapshot(page)) {
+            Ok(bytes) => {
+                if let Err(e) = sender.send(bytes).await {
+                    warn!(
+                        "Failed to send snapshot page {}/{}: {}",
+                        i + 1,
+                        page_count,
+                        e
+                    );
+                    break;
                 }
             }
             Err(e) => {
                 warn!(
-                    "Failed to encode snapshot chunk {}/{}: {}",
+                    "Failed to encode snapshot page {}/{}: {}",
                     i + 1,
-                    chunk_count,
+                    page_count,
                     e
                 );
             }
@@ -1146,7 +2545,7 @@ async fn send_chunked_snapshot(sender: &Arc<dyn TransportSender>, snapshot: Snap
 /// Handle an incoming message
 async fn handle_message(
     msg: &Message,
-    _frame: &Frame,
+    frame: &Frame,
     session: &Option<Arc<Session>>,
     sender: &Arc<dyn TransportSender>,
     sessions: &Arc<DashMap<SessionId, Arc<Session>>>,
@@ -1155,16 +2554,27 @@ async fn handle_message(
     config: &RouterConfig,
     security_mode: SecurityMode,
     token_validator: &Option<Arc<dyn TokenValidator>>,
+    token_file: &Option<Arc<PathBuf>>,
+    device_keys: &Option<Arc<DeviceKeyValidator>>,
+    device_key_file: &Option<Arc<PathBuf>>,
+    adapters: &Arc<AdapterRegistry>,
+    bans: &BanList,
     p2p_capabilities: &Arc<P2PCapabilities>,
     gesture_registry: &Option<Arc<GestureRegistry>>,
+    middleware: &MiddlewareChain,
 ) -> Option<MessageResult> {
+    // Relayed copies still queued for a subscriber after the TTL are dropped
+    let expires_at = frame
+        .ttl
+        .map(|ttl| Instant::now() + Duration::from_millis(ttl as u64));
+
     match msg {
         Message::Hello(hello) => {
             // In authenticated mode, validate the token
-            let (authenticated, subject, scopes) = match security_mode {
+            let token_info = match security_mode {
                 SecurityMode::Open => {
                     // Open mode: no authentication required
-                    (false, None, Vec::new())
+                    None
                 }
                 SecurityMode::Authenticated => {
                     // Authenticated mode: require valid token
@@ -1172,104 +2582,171 @@ async fn handle_message(
                         Some(t) => t,
                         None => {
                             warn!("Connection rejected: no token provided in authenticated mode");
-                            let error = Message::Error(ErrorMessage {
-                                code: 300, // Unauthorized
-                                message: "Authentication required".to_string(),
-                                address: None,
-                                correlation_id: None,
-                            });
+                            let error = Message::Error(ErrorMessage::new(
+                                ErrorCode::Unauthorized,
+                                "Authentication required".to_string(),
+                            ));
                             let bytes = codec::encode(&error).ok()?;
                             let _ = sender.send(bytes).await;
-                            return Some(MessageResult::Disconnect);
+                            return Some(MessageResult::Unauthorized);
                         }
                     };
 
                     // Validate token
-                    let validator = match token_validator {
-                        Some(v) => v,
+                    let result = match validate_token(token, token_validator, device_keys) {
+                        Some(result) => result,
                         None => {
                             error!("Authenticated mode but no token validator configured");
-                            let error = Message::Error(ErrorMessage {
-                                code: 500, // Internal error
-                                message: "Server misconfiguration".to_string(),
-                                address: None,
-                                correlation_id: None,
-                            });
+                            let error = Message::Error(ErrorMessage::new(
+                                ErrorCode::InternalError,
+                                "Server misconfiguration".to_string(),
+                            ));
                             let bytes = codec::encode(&error).ok()?;
                             let _ = sender.send(bytes).await;
                             return Some(MessageResult::Disconnect);
                         }
                     };
 
-                    match validator.validate(token) {
+                    match result {
                         ValidationResult::Valid(info) => {
                             info!(
                                 "Token validated for subject: {:?}, scopes: {}",
                                 info.subject,
                                 info.scopes.len()
                             );
-                            (true, info.subject, info.scopes)
+                            Some(info)
                         }
                         ValidationResult::Expired => {
                             warn!("Connection rejected: token expired");
-                            let error = Message::Error(ErrorMessage {
-                                code: 302, // TokenExpired
-                                message: "Token has expired".to_string(),
-                                address: None,
-                                correlation_id: None,
-                            });
+                            let error = Message::Error(ErrorMessage::new(
+                                ErrorCode::TokenExpired,
+                                "Token has expired".to_string(),
+                            ));
                             let bytes = codec::encode(&error).ok()?;
                             let _ = sender.send(bytes).await;
-                            return Some(MessageResult::Disconnect);
+                            return Some(MessageResult::Unauthorized);
                         }
                         ValidationResult::Invalid(reason) => {
                             warn!("Connection rejected: invalid token - {}", reason);
-                            let error = Message::Error(ErrorMessage {
-                                code: 300, // Unauthorized
-                                message: format!("Invalid token: {}", reason),
-                                address: None,
-                                correlation_id: None,
-                            });
+                            let error = Message::Error(ErrorMessage::new(
+                                ErrorCode::Unauthorized,
+                                format!("Invalid token: {}", reason),
+                            ));
                             let bytes = codec::encode(&error).ok()?;
                             let _ = sender.send(bytes).await;
-                            return Some(MessageResult::Disconnect);
+                            return Some(MessageResult::Unauthorized);
                         }
                         ValidationResult::NotMyToken => {
                             warn!("Connection rejected: unrecognized token format");
-                            let error = Message::Error(ErrorMessage {
-                                code: 300, // Unauthorized
-                                message: "Unrecognized token format".to_string(),
-                                address: None,
-                                correlation_id: None,
-                            });
+                            let error = Message::Error(ErrorMessage::new(
+                                ErrorCode::Unauthorized,
+                                "Unrecognized token format".to_string(),
+                            ));
                             let bytes = codec::encode(&error).ok()?;
                             let _ = sender.send(bytes).await;
-                            return Some(MessageResult::Disconnect);
+                            return Some(MessageResult::Unauthorized);
+                        }
+                    }
+                }
+            };
+
+            // Sessions belonging to the same client
+            let policy = config.duplicate_sessions;
+            let subject = token_info.as_ref().and_then(|info| info.subject.clone());
+            let mut replaced = Vec::new();
+            if let Some(key) = policy.identity.key(&hello.name, subject.as_deref()) {
+                let duplicates: Vec<Arc<Session>> = sessions
+                    .iter()
+                    .filter(|s| {
+                        let existing = s.subject();
+                        policy.identity.key(&s.name, existing.as_deref()) == Some(key)
+                    })
+                    .map(|s| s.value().clone())
+                    .collect();
+
+                match policy.action {
+                    DuplicateAction::Allow => {}
+                    DuplicateAction::Reject if !duplicates.is_empty() => {
+                        warn!("Connection rejected: '{}' is already connected", key);
+                        let error = Message::Error(ErrorMessage::new(
+                            ErrorCode::InvalidMessage,
+                            format!("A session for '{}' already exists", key),
+                        ));
+                        let bytes = codec::encode(&error).ok()?;
+                        let _ = sender.send(bytes).await;
+                        return Some(MessageResult::Disconnect);
+                    }
+                    DuplicateAction::Reject => {}
+                    DuplicateAction::Takeover => {
+                        // Unregister now so the old name doesn't collide
+                        for old in &duplicates {
+                            sessions.remove(&old.id);
                         }
+                        replaced = duplicates;
                     }
                 }
+            }
+
+            let name_taken = |name: &str| sessions.iter().any(|s| s.name == name);
+            let Some(name) = naming::client_name(config.name_collision, &hello.name, name_taken)
+            else {
+                warn!("Connection rejected: client name '{}' in use", hello.name);
+                let error = Message::Error(ErrorMessage::new(
+                    ErrorCode::InvalidMessage,
+                    format!("Client name '{}' is already in use", hello.name),
+                ));
+                let bytes = codec::encode(&error).ok()?;
+                let _ = sender.send(bytes).await;
+                return Some(MessageResult::Disconnect);
             };
 
+            let session_id = naming::session_id(
+                config.session_ids,
+                &name,
+                token_info.as_ref().and_then(|info| info.subject.as_deref()),
+                |id| sessions.contains_key(id),
+            );
+
             // Create new session
             let mut new_session =
-                Session::new(sender.clone(), hello.name.clone(), hello.features.clone());
+                Session::new(sender.clone(), name, hello.features.clone()).with_id(session_id);
 
             // Set authentication state
-            if authenticated {
-                new_session.set_authenticated(
-                    hello.token.clone().unwrap_or_default(),
-                    subject,
-                    scopes,
-                );
+            if let Some(info) = token_info {
+                new_session.authenticate(hello.token.clone().unwrap_or_default(), info);
             }
 
             let new_session = Arc::new(new_session);
             let session_id = new_session.id.clone();
             sessions.insert(session_id.clone(), new_session.clone());
 
+            for old in replaced {
+                info!("Session {} taken over by {}", old.id, session_id);
+                if policy.transfer_subscriptions {
+                    for id in subscriptions.transfer_session(&old.id, &session_id) {
+                        new_session.add_subscription(id);
+                    }
+                } else {
+                    subscriptions.remove_session(&old.id);
+                }
+                p2p_capabilities.unregister(&old.id);
+
+                let notice = Message::Error(ErrorMessage::new(
+                    ErrorCode::ServiceUnavailable,
+                    "Session taken over by a new connection".to_string(),
+                ));
+                if let Ok(bytes) = codec::encode(&notice) {
+                    let _ = old.try_send(bytes);
+                }
+                // A ghost connection may not drain; don't hold up the newcomer
+                tokio::spawn(async move {
+                    let _ = old.close().await;
+                });
+            }
+
             info!(
                 "Session created: {} ({}) authenticated={}",
-                hello.name, session_id, new_session.authenticated
+                new_session.name, session_id, new_session.authenticated
             );
 
             // Send welcome
@@ -1279,9 +2756,10 @@ async fn handle_message(
             // Send welcome first
             let _ = sender.send(response).await;
 
-            // Send initial snapshot (chunked if too large)
-            let full_snapshot = state.full_snapshot();
-            send_chunked_snapshot(sender, full_snapshot).await;
+            // Send initial snapshot (paged if too large), or only what changed
+            // since the revision a reconnecting client is current to
+            let snapshot = state.snapshot_since("**", hello.since);
+            send_paged_snapshot(sender, snapshot).await;
 
             Some(MessageResult::NewSession(new_session))
         }
@@ -1297,14 +2775,14 @@ async fn handle_message(
                     "Session {} subscription limit reached ({}/{})",
                     session.id, current_subs, max_subs
                 );
-                let error = Message::Error(ErrorMessage {
-                    code: 429, // Too Many Requests
-                    message: format!("Subscription limit reached (max {})", max_subs),
-                    address: Some(sub.pattern.clone()),
-                    correlation_id: None,
-                });
-                let bytes = codec::encode(&error).ok()?;
-                return Some(MessageResult::Send(bytes));
+                let error = Message::Error(
+                    ErrorMessage::new(
+                        ErrorCode::LimitExceeded,
+                        format!("Subscription limit reached (max {})", max_subs),
+                    )
+                    .with_address(&sub.pattern),
+                );
+                return reply(error, sub.correlation_id);
             }
 
             // Check scope for read access (in authenticated mode)
@@ -1315,14 +2793,14 @@ async fn handle_message(
                     "Session {} denied SUBSCRIBE to {} - insufficient scope",
                     session.id, sub.pattern
                 );
-                let error = Message::Error(ErrorMessage {
-                    code: 301, // Forbidden
-                    message: "Insufficient scope for subscription".to_string(),
-                    address: Some(sub.pattern.clone()),
-                    correlation_id: None,
-                });
-                let bytes = codec::encode(&error).ok()?;
-                return Some(MessageResult::Send(bytes));
+                let error = Message::Error(
+                    ErrorMessage::new(
+                        ErrorCode::Forbidden,
+                        "Insufficient scope for subscription".to_string(),
+                    )
+                    .with_address(&sub.pattern),
+                );
+                return reply(error, sub.correlation_id);
             }
 
             // Create subscription
@@ -1339,22 +2817,35 @@ async fn handle_message(
 
                     debug!("Session {} subscribed to {}", session.id, sub.pattern);
 
-                    // Send matching current values (chunked if large)
-                    let snapshot = state.snapshot(&sub.pattern);
-                    if !snapshot.params.is_empty() {
-                        send_chunked_snapshot(sender, snapshot).await;
+                    // Send matching current values (paged if large); a resync
+                    // gets its snapshot even when nothing changed, to learn
+                    // the revision it is now current to
+                    let since = sub.options.as_ref().and_then(|opts| opts.since);
+                    let snapshot = state.snapshot_since(&sub.pattern, since);
+                    if !snapshot.params.is_empty() || since.is_some() {
+                        send_paged_snapshot(sender, snapshot).await;
+                    }
+
+                    // Confirm after the snapshot when the client awaits the reply
+                    if sub.correlation_id.is_some() {
+                        let ack = Message::Ack(AckMessage {
+                            address: Some(sub.pattern.clone()),
+                            revision: None,
+                            locked: None,
+                            holder: None,
+                            value: None,
+                            correlation_id: None,
+                        });
+                        return reply(ack, sub.correlation_id);
                     }
                 }
                 Err(e) => {
                     warn!("Invalid subscription pattern: {}", e);
-                    let error = Message::Error(ErrorMessage {
-                        code: 202,
-                        message: e.to_string(),
-                        address: Some(sub.pattern.clone()),
-                        correlation_id: None,
-                    });
-                    let bytes = codec::encode(&error).ok()?;
-                    return Some(MessageResult::Send(bytes));
+                    let error = Message::Error(
+                        ErrorMessage::new(ErrorCode::PatternError, e.to_string())
+                            .with_address(&sub.pattern),
+                    );
+                    return reply(error, sub.correlation_id);
                 }
             }
 
@@ -1368,9 +2859,142 @@ async fn handle_message(
             Some(MessageResult::None)
         }
 
+        Message::Credit(credit) => {
+            let session = session.as_ref()?;
+            if !subscriptions.grant(&session.id, credit.id, credit.credits) {
+                debug!(
+                    "Session {} sent CREDIT for subscription {}, which isn't flow-controlled",
+                    session.id, credit.id
+                );
+            }
+            Some(MessageResult::None)
+        }
+
         Message::Set(set) => {
             let session = session.as_ref()?;
 
+            if set.address == SESSION_TOKEN_ADDRESS {
+                let response = handle_reauthenticate(
+                    set,
+                    session,
+                    security_mode,
+                    token_validator,
+                    device_keys,
+                );
+                return reply(response, set.correlation_id);
+            }
+
+            if set.address == SESSION_ORIGIN_ADDRESS {
+                let response = handle_declare_origin(set, session);
+                return reply(response, set.correlation_id);
+            }
+
+            if token_admin::is_token_address(&set.address) {
+                let response = handle_token_admin(
+                    msg,
+                    session,
+                    sessions,
+                    security_mode,
+                    cpsk_validator(token_validator),
+                    token_file,
+                );
+                return reply(response, set.correlation_id);
+            }
+
+            if token_admin::is_key_address(&set.address) {
+                let response = handle_token_admin(
+                    msg,
+                    session,
+                    sessions,
+                    security_mode,
+                    device_keys.as_deref(),
+                    device_key_file,
+                );
+                return reply(response, set.correlation_id);
+            }
+
+            if adapter_admin::is_adapter_address(&set.address) {
+                let response = handle_adapter_admin(msg, session, security_mode, adapters).await;
+                return reply(response, set.correlation_id);
+            }
+
+            if ban::is_ban_address(&set.address) {
+                let response =
+                    handle_ban_admin(msg, session, security_mode, bans, sessions, subscriptions);
+                return reply(response, set.correlation_id);
+            }
+
+            if let Some(app) = Manifest::app_for(&set.address) {
+                if let Some(error) = manifest_publish_error(set, app, session, state, sessions) {
+                    return reply(error, set.correlation_id);
+                }
+            }
+
+            if is_wildcard(&set.address) {
+                if set.lock || set.unlock || set.revision.is_some() {
+                    let error = Message::Error(
+                        ErrorMessage::new(
+                            ErrorCode::PatternError,
+                            "Wildcard writes can't lock, unlock or check revisions",
+                        )
+                        .with_address(&set.address),
+                    );
+                    return reply(error, set.correlation_id);
+                }
+                let addresses = match config.wildcard_writes.expand(&set.address, state) {
+                    Ok(addresses) => addresses,
+                    Err(error) => return reply(Message::Error(error), set.correlation_id),
+                };
+                if let Some(error) = wildcard_scope_error(session, security_mode, &addresses) {
+                    return reply(error, set.correlation_id);
+                }
+
+                // Each match is applied as its own SET; params locked by
+                // other sessions are skipped
+                let origin = session.origin();
+                for address in addresses {
+                    let single = SetMessage {
+                        address,
+                        correlation_id: None,
+                        ..set.clone()
+                    };
+                    if duplicate_revision(&single, session, state, &config.dedup).is_some() {
+                        continue;
+                    }
+                    if config.validate_manifests
+                        && state
+                            .check_manifest(&single.address, Some(&single.value), &session.id)
+                            .is_err()
+                    {
+                        continue;
+                    }
+                    let Ok((revision, value)) = state.apply_set_value(&single, &session.id) else {
+                        continue;
+                    };
+                    audit(session, &origin, "SET", &single.address);
+                    deliver_set(
+                        applied_set(&single, revision, value),
+                        &origin,
+                        expires_at,
+                        subscriptions,
+                        sessions,
+                        middleware,
+                    )
+                    .await;
+                }
+                enforce_memory_budget(&config.memory_budget, state, sessions, subscriptions);
+
+                let ack = Message::Ack(AckMessage {
+                    address: Some(set.address.clone()),
+                    revision: None,
+                    locked: None,
+                    holder: None,
+                    value: None,
+                    correlation_id: None,
+                });
+                return reply(ack, set.correlation_id);
+            }
+
             // Check scope for write access (in authenticated mode)
             if security_mode == SecurityMode::Authenticated
                 && !session.has_scope(Action::Write, &set.address)
@@ -1379,62 +3003,85 @@ async fn handle_message(
                     "Session {} denied SET to {} - insufficient scope",
                     session.id, set.address
                 );
-                let error = Message::Error(ErrorMessage {
-                    code: 301, // Forbidden
-                    message: "Insufficient scope for write operation".to_string(),
+                let error = Message::Error(
+                    ErrorMessage::new(
+                        ErrorCode::Forbidden,
+                        "Insufficient scope for write operation".to_string(),
+                    )
+                    .with_address(&set.address),
+                );
+                return reply(error, set.correlation_id);
+            }
+
+            if config.validate_manifests {
+                let value = set.op.is_none().then_some(&set.value);
+                if let Err(violation) = state.check_manifest(&set.address, value, &session.id) {
+                    let error = manifest_violation_error(&set.address, &violation);
+                    return reply(error, set.correlation_id);
+                }
+            }
+
+            // Unchanged values on dedup patterns are acknowledged, not applied
+            if let Some(revision) = duplicate_revision(set, session, state, &config.dedup) {
+                debug!("Suppressed unchanged SET to {}", set.address);
+                let ack = Message::Ack(AckMessage {
                     address: Some(set.address.clone()),
+                    revision: Some(revision),
+                    locked: None,
+                    holder: None,
+                    value: None,
                     correlation_id: None,
                 });
-                let bytes = codec::encode(&error).ok()?;
-                return Some(MessageResult::Send(bytes));
+                return reply(ack, set.correlation_id);
             }
 
             // Apply to state
-            match state.apply_set(set, &session.id) {
-                Ok(revision) => {
-                    // Broadcast to subscribers
-                    let subscribers =
-                        subscriptions.find_subscribers(&set.address, Some(SignalType::Param));
-
-                    // Create updated SET message with revision
-                    let mut updated_set = set.clone();
-                    updated_set.revision = Some(revision);
-                    let broadcast_msg = Message::Set(updated_set);
-
-                    if let Ok(bytes) = codec::encode(&broadcast_msg) {
-                        // Send to all subscribers (including sender for confirmation)
-                        // Use try_send for non-blocking broadcast with backpressure
-                        for sub_session_id in subscribers {
-                            if let Some(sub_session) = sessions.get(&sub_session_id) {
-                                try_send_with_drop_tracking_sync(
-                                    sub_session.value(),
-                                    bytes.clone(),
-                                    &sub_session_id,
-                                );
+            match state.apply_set_value(set, &session.id) {
+                Ok((revision, value)) => {
+                    // Relative SETs report the value they resulted in
+                    let resulting = set.op.map(|_| value.clone());
+                    let updated_set = applied_set(set, revision, value);
+
+                    let origin = session.origin();
+                    audit(session, &origin, "SET", &set.address);
+
+                    if let Some(app) = Manifest::app_for(&set.address) {
+                        match Manifest::from_value(&set.value) {
+                            Ok(manifest) => state.register_manifest(manifest, &session.id),
+                            Err(_) => {
+                                state.remove_manifest(app);
                             }
                         }
                     }
 
+                    // Send to all subscribers (including sender for confirmation)
+                    deliver_set(
+                        updated_set,
+                        &origin,
+                        expires_at,
+                        subscriptions,
+                        sessions,
+                        middleware,
+                    )
+                    .await;
+                    enforce_memory_budget(&config.memory_budget, state, sessions, subscriptions);
+
                     // Send ACK to sender
                     let ack = Message::Ack(AckMessage {
                         address: Some(set.address.clone()),
                         revision: Some(revision),
                         locked: None,
                         holder: None,
+                        value: resulting,
                         correlation_id: None,
                     });
-                    let ack_bytes = codec::encode(&ack).ok()?;
-                    return Some(MessageResult::Send(ack_bytes));
+                    return reply(ack, set.correlation_id);
                 }
                 Err(e) => {
-                    let error = Message::Error(ErrorMessage {
-                        code: 400,
-                        message: format!("{:?}", e),
-                        address: Some(set.address.clone()),
-                        correlation_id: None,
-                    });
-                    let bytes = codec::encode(&error).ok()?;
-                    return Some(MessageResult::Send(bytes));
+                    return reply(
+                        Message::Error(update_error(&e, &set.address)),
+                        set.correlation_id,
+                    );
                 }
             }
         }
@@ -1442,6 +3089,46 @@ async fn handle_message(
         Message::Get(get) => {
             let session = session.as_ref()?;
 
+            if token_admin::is_token_address(&get.address) {
+                let response = handle_token_admin(
+                    msg,
+                    session,
+                    sessions,
+                    security_mode,
+                    cpsk_validator(token_validator),
+                    token_file,
+                );
+                return reply(response, get.correlation_id);
+            }
+
+            if token_admin::is_key_address(&get.address) {
+                let response = handle_token_admin(
+                    msg,
+                    session,
+                    sessions,
+                    security_mode,
+                    device_keys.as_deref(),
+                    device_key_file,
+                );
+                return reply(response, get.correlation_id);
+            }
+
+            if adapter_admin::is_adapter_address(&get.address) {
+                let response = handle_adapter_admin(msg, session, security_mode, adapters).await;
+                return reply(response, get.correlation_id);
+            }
+
+            if ban::is_ban_address(&get.address) {
+                let response =
+                    handle_ban_admin(msg, session, security_mode, bans, sessions, subscriptions);
+                return reply(response, get.correlation_id);
+            }
+
+            if get.address == subscription::STATS_ADDRESS {
+                let response = subscription_stats_reply(session, subscriptions);
+                return reply(response, get.correlation_id);
+            }
+
             // Check scope for read access (in authenticated mode)
             if security_mode == SecurityMode::Authenticated
                 && !session.has_scope(Action::Read, &get.address)
@@ -1450,14 +3137,14 @@ async fn handle_message(
                     "Session {} denied GET to {} - insufficient scope",
                     session.id, get.address
                 );
-                let error = Message::Error(ErrorMessage {
-                    code: 301, // Forbidden
-                    message: "Insufficient scope for read operation".to_string(),
-                    address: Some(get.address.clone()),
-                    correlation_id: None,
-                });
-                let bytes = codec::encode(&error).ok()?;
-                return Some(MessageResult::Send(bytes));
+                let error = Message::Error(
+                    ErrorMessage::new(
+                        ErrorCode::Forbidden,
+                        "Insufficient scope for read operation".to_string(),
+                    )
+                    .with_address(&get.address),
+                );
+                return reply(error, get.correlation_id);
             }
 
             if let Some(param_state) = state.get_state(&get.address) {
@@ -1469,9 +3156,21 @@ async fn handle_message(
                         writer: Some(param_state.writer),
                         timestamp: Some(param_state.timestamp),
                     }],
+                    correlation_id: None,
+                    page: None,
+                    sync: None,
                 });
-                let bytes = codec::encode(&snapshot).ok()?;
-                return Some(MessageResult::Send(bytes));
+                return reply(snapshot, get.correlation_id);
+            }
+
+            // Legacy GETs without a correlation id get no reply for unknown
+            // addresses; correlated ones are answered so the caller can resolve
+            if get.correlation_id.is_some() {
+                let error = Message::Error(
+                    ErrorMessage::new(ErrorCode::AddressNotFound, "Address not found")
+                        .with_address(&get.address),
+                );
+                return reply(error, get.correlation_id);
             }
 
             Some(MessageResult::None)
@@ -1480,6 +3179,45 @@ async fn handle_message(
         Message::Publish(pub_msg) => {
             let session = session.as_ref()?;
 
+            if is_wildcard(&pub_msg.address) {
+                let addresses = match config.wildcard_writes.expand(&pub_msg.address, state) {
+                    Ok(addresses) => addresses,
+                    Err(error) => return reply(Message::Error(error), None),
+                };
+                if let Some(error) = wildcard_scope_error(session, security_mode, &addresses) {
+                    return reply(error, None);
+                }
+
+                let origin = session.origin();
+                for address in addresses {
+                    if config.validate_manifests
+                        && state
+                            .check_manifest(&address, pub_msg.value.as_ref(), &session.id)
+                            .is_err()
+                    {
+                        continue;
+                    }
+                    audit(session, &origin, "PUBLISH", &address);
+                    let subscribers =
+                        subscriptions.find_recipients(&address, pub_msg.signal, &origin);
+                    let copy = Message::Publish(PublishMessage {
+                        address,
+                        ..pub_msg.clone()
+                    });
+                    deliver(
+                        &copy,
+                        &origin,
+                        subscribers,
+                        Some(&session.id),
+                        expires_at,
+                        sessions,
+                        middleware,
+                    )
+                    .await;
+                }
+                return Some(MessageResult::None);
+            }
+
             // Check scope for write access (in authenticated mode)
             if security_mode == SecurityMode::Authenticated
                 && !session.has_scope(Action::Write, &pub_msg.address)
@@ -1488,16 +3226,26 @@ async fn handle_message(
                     "Session {} denied PUBLISH to {} - insufficient scope",
                     session.id, pub_msg.address
                 );
-                let error = Message::Error(ErrorMessage {
-                    code: 301, // Forbidden
-                    message: "Insufficient scope for publish operation".to_string(),
-                    address: Some(pub_msg.address.clone()),
-                    correlation_id: None,
-                });
+                let error = Message::Error(
+                    ErrorMessage::new(
+                        ErrorCode::Forbidden,
+                        "Insufficient scope for publish operation".to_string(),
+                    )
+                    .with_address(&pub_msg.address),
+                );
                 let bytes = codec::encode(&error).ok()?;
                 return Some(MessageResult::Send(bytes));
             }
 
+            if config.validate_manifests {
+                if let Err(violation) =
+                    state.check_manifest(&pub_msg.address, pub_msg.value.as_ref(), &session.id)
+                {
+                    let error = manifest_violation_error(&pub_msg.address, &violation);
+                    return reply(error, None);
+                }
+            }
+
             // Check for P2P signaling addresses
             match analyze_address(&pub_msg.address) {
                 P2PAddressType::Signal { target_session } => {
@@ -1510,12 +3258,13 @@ async fn handle_message(
                         } else {
                             // Target session not found
                             warn!("P2P signal target session not found: {}", target_session);
-                            let error = Message::Error(ErrorMessage {
-                                code: 404,
-                                message: format!("Target session not found: {}", target_session),
-                                address: Some(pub_msg.address.clone()),
-                                correlation_id: None,
-                            });
+                            let error = Message::Error(
+                                ErrorMessage::new(
+                                    ErrorCode::AddressNotFound,
+                                    format!("Target session not found: {}", target_session),
+                                )
+                                .with_address(&pub_msg.address),
+                            );
                             let bytes = codec::encode(&error).ok()?;
                             return Some(MessageResult::Send(bytes));
                         }
@@ -1532,20 +3281,19 @@ async fn handle_message(
 
                     // Broadcast to subscribers of the announce address
                     // Use try_send for non-blocking broadcast
-                    let subscribers = subscriptions.find_subscribers(&pub_msg.address, None);
-                    if let Ok(bytes) = codec::encode(msg) {
-                        for sub_session_id in subscribers {
-                            if sub_session_id != session.id {
-                                if let Some(sub_session) = sessions.get(&sub_session_id) {
-                                    try_send_with_drop_tracking_sync(
-                                        sub_session.value(),
-                                        bytes.clone(),
-                                        &sub_session_id,
-                                    );
-                                }
-                            }
-                        }
-                    }
+                    let origin = session.origin();
+                    let subscribers =
+                        subscriptions.find_recipients(&pub_msg.address, None, &origin);
+                    deliver(
+                        msg,
+                        &origin,
+                        subscribers,
+                        Some(&session.id),
+                        expires_at,
+                        sessions,
+                        middleware,
+                    )
+                    .await;
 
                     return Some(MessageResult::None);
                 }
@@ -1556,6 +3304,8 @@ async fn handle_message(
 
             // Standard PUBLISH handling for non-P2P addresses
             let signal_type = pub_msg.signal;
+            let origin = session.origin();
+            audit(session, &origin, "PUBLISH", &pub_msg.address);
 
             // Check for gesture coalescing
             if let Some(registry) = gesture_registry {
@@ -1566,22 +3316,21 @@ async fn handle_message(
                             // Use try_send for non-blocking broadcast
                             for forward_msg in messages {
                                 let msg_to_send = Message::Publish(forward_msg.clone());
-                                let subscribers = subscriptions
-                                    .find_subscribers(&forward_msg.address, signal_type);
-                                if let Ok(bytes) = codec::encode(&msg_to_send) {
-                                    for sub_session_id in subscribers {
-                                        if sub_session_id != session.id {
-                                            if let Some(sub_session) = sessions.get(&sub_session_id)
-                                            {
-                                                try_send_with_drop_tracking_sync(
-                                                    sub_session.value(),
-                                                    bytes.clone(),
-                                                    &sub_session_id,
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
+                                let subscribers = subscriptions.find_recipients(
+                                    &forward_msg.address,
+                                    signal_type,
+                                    &origin,
+                                );
+                                deliver(
+                                    &msg_to_send,
+                                    &origin,
+                                    subscribers,
+                                    Some(&session.id),
+                                    expires_at,
+                                    sessions,
+                                    middleware,
+                                )
+                                .await;
                             }
                             return Some(MessageResult::None);
                         }
@@ -1597,22 +3346,19 @@ async fn handle_message(
             }
 
             // Find subscribers
-            let subscribers = subscriptions.find_subscribers(&pub_msg.address, signal_type);
+            let subscribers = subscriptions.find_recipients(&pub_msg.address, signal_type, &origin);
 
             // Broadcast using try_send for non-blocking delivery
-            if let Ok(bytes) = codec::encode(msg) {
-                for sub_session_id in subscribers {
-                    if sub_session_id != session.id {
-                        if let Some(sub_session) = sessions.get(&sub_session_id) {
-                            try_send_with_drop_tracking_sync(
-                                sub_session.value(),
-                                bytes.clone(),
-                                &sub_session_id,
-                            );
-                        }
-                    }
-                }
-            }
+            deliver(
+                msg,
+                &origin,
+                subscribers,
+                Some(&session.id),
+                expires_at,
+                sessions,
+                middleware,
+            )
+            .await;
 
             Some(MessageResult::None)
         }
@@ -1645,6 +3391,7 @@ async fn handle_message(
                 revision: None,
                 locked: None,
                 holder: None,
+                value: None,
                 correlation_id: None,
             });
             let bytes = codec::encode(&ack).ok()?;
@@ -1677,6 +3424,18 @@ async fn handle_message(
             let mut validated_pubs: Vec<&PublishMessage> = Vec::new();
 
             for inner_msg in &bundle.messages {
+                // Bundles apply to concrete addresses only
+                if let Some(address) = message_address(inner_msg).filter(|a| is_wildcard(a)) {
+                    let err = Message::Error(
+                        ErrorMessage::new(
+                            ErrorCode::PatternError,
+                            format!("Bundle rejected: wildcard address {}", address),
+                        )
+                        .with_address(&address),
+                    );
+                    return reply(err, bundle.correlation_id);
+                }
+
                 match inner_msg {
                     Message::Set(set) => {
                         // Check scope for write access (in authenticated mode)
@@ -1688,17 +3447,17 @@ async fn handle_message(
                                 session.id, set.address
                             );
                             // Return error for the entire bundle
-                            let err = Message::Error(ErrorMessage {
-                                code: 403,
-                                message: format!(
-                                    "Bundle rejected: insufficient scope for SET to {}",
-                                    set.address
-                                ),
-                                address: Some(set.address.clone()),
-                                correlation_id: None,
-                            });
-                            let err_bytes = codec::encode(&err).ok()?;
-                            return Some(MessageResult::Send(err_bytes));
+                            let err = Message::Error(
+                                ErrorMessage::new(
+                                    ErrorCode::Forbidden,
+                                    format!(
+                                        "Bundle rejected: insufficient scope for SET to {}",
+                                        set.address
+                                    ),
+                                )
+                                .with_address(&set.address),
+                            );
+                            return reply(err, bundle.correlation_id);
                         }
 
                         // Lock checks happen during apply_set - the state store
@@ -1714,17 +3473,17 @@ async fn handle_message(
                                 "Session {} denied bundled PUBLISH to {} - rejecting entire bundle",
                                 session.id, pub_msg.address
                             );
-                            let err = Message::Error(ErrorMessage {
-                                code: 403,
-                                message: format!(
-                                    "Bundle rejected: insufficient scope for PUBLISH to {}",
-                                    pub_msg.address
-                                ),
-                                address: Some(pub_msg.address.clone()),
-                                correlation_id: None,
-                            });
-                            let err_bytes = codec::encode(&err).ok()?;
-                            return Some(MessageResult::Send(err_bytes));
+                            let err = Message::Error(
+                                ErrorMessage::new(
+                                    ErrorCode::Forbidden,
+                                    format!(
+                                        "Bundle rejected: insufficient scope for PUBLISH to {}",
+                                        pub_msg.address
+                                    ),
+                                )
+                                .with_address(&pub_msg.address),
+                            );
+                            return reply(err, bundle.correlation_id);
                         }
                         validated_pubs.push(pub_msg);
                     }
@@ -1738,58 +3497,51 @@ async fn handle_message(
             // PHASE 2: Apply all validated changes atomically
             // Now that all validations passed, apply changes
             let mut applied_revisions: Vec<(String, u64)> = Vec::new();
+            let origin = session.origin();
 
             for set in &validated_sets {
-                match state.apply_set(set, &session.id) {
-                    Ok(revision) => {
+                match state.apply_set_value(set, &session.id) {
+                    Ok((revision, value)) => {
                         applied_revisions.push((set.address.clone(), revision));
 
-                        // Broadcast to subscribers
-                        let subscribers =
-                            subscriptions.find_subscribers(&set.address, Some(SignalType::Param));
-
-                        // Create updated SET message with revision
-                        let mut updated_set: SetMessage = (*set).clone();
-                        updated_set.revision = Some(revision);
-                        let broadcast_msg = Message::Set(updated_set);
-
-                        if let Ok(bytes) = codec::encode(&broadcast_msg) {
-                            for sub_session_id in subscribers {
-                                if let Some(sub_session) = sessions.get(&sub_session_id) {
-                                    try_send_with_drop_tracking_sync(
-                                        sub_session.value(),
-                                        bytes.clone(),
-                                        &sub_session_id,
-                                    );
-                                }
-                            }
-                        }
+                        audit(session, &origin, "SET", &set.address);
+                        deliver_set(
+                            applied_set(set, revision, value),
+                            &origin,
+                            expires_at,
+                            subscriptions,
+                            sessions,
+                            middleware,
+                        )
+                        .await;
                     }
                     Err(e) => {
-                        // This shouldn't happen after validation, but handle gracefully
-                        error!("Bundle SET apply failed after validation: {}", e);
+                        // Locks and relative SETs are only checked when applied
+                        warn!("Bundled SET to {} not applied: {}", set.address, e);
                     }
                 }
             }
+            if !validated_sets.is_empty() {
+                enforce_memory_budget(&config.memory_budget, state, sessions, subscriptions);
+            }
 
             // Process PUBLISH messages
             for pub_msg in &validated_pubs {
-                let subscribers = subscriptions.find_subscribers(&pub_msg.address, pub_msg.signal);
+                audit(session, &origin, "PUBLISH", &pub_msg.address);
+                let subscribers =
+                    subscriptions.find_recipients(&pub_msg.address, pub_msg.signal, &origin);
 
                 let inner_msg = Message::Publish((*pub_msg).clone());
-                if let Ok(bytes) = codec::encode(&inner_msg) {
-                    for sub_session_id in subscribers {
-                        if sub_session_id != session.id {
-                            if let Some(sub_session) = sessions.get(&sub_session_id) {
-                                try_send_with_drop_tracking_sync(
-                                    sub_session.value(),
-                                    bytes.clone(),
-                                    &sub_session_id,
-                                );
-                            }
-                        }
-                    }
-                }
+                deliver(
+                    &inner_msg,
+                    &origin,
+                    subscribers,
+                    Some(&session.id),
+                    expires_at,
+                    sessions,
+                    middleware,
+                )
+                .await;
             }
 
             // Send a single ACK for the entire bundle with count of applied operations
@@ -1798,20 +3550,256 @@ async fn handle_message(
                 revision: applied_revisions.last().map(|(_, r)| *r),
                 locked: None,
                 holder: None,
+                value: None,
                 correlation_id: None,
             });
-            let ack_bytes = codec::encode(&ack).ok()?;
-            Some(MessageResult::Send(ack_bytes))
+            reply(ack, bundle.correlation_id)
         }
 
         _ => Some(MessageResult::None),
     }
 }
 
+/// Forget a session that has gone away
+async fn remove_session(
+    session: &Arc<Session>,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    subscriptions: &SubscriptionManager,
+    p2p_capabilities: &P2PCapabilities,
+    middleware: &MiddlewareChain,
+) {
+    info!("Removing session {}", session.id);
+    sessions.remove(&session.id);
+    subscriptions.remove_session(&session.id);
+    p2p_capabilities.unregister(&session.id);
+    middleware.disconnect(session).await;
+}
+
+/// Log target of the per-message audit trail (SETs and PUBLISHes with
+/// their sender and origin), logged at debug level
+pub const AUDIT_LOG_TARGET: &str = "clasp_router::audit";
+
+/// Record an accepted SET or PUBLISH in the audit trail
+fn audit(session: &Session, origin: &Origin, kind: &str, address: &str) {
+    debug!(
+        target: AUDIT_LOG_TARGET,
+        session = %session.id,
+        name = %session.name,
+        origin = %origin,
+        "{} {}",
+        kind,
+        address
+    );
+}
+
+/// A message encoded on first use, plain and wrapped in an ENVELOPE, at
+/// each delivery QoS recipients ask for
+pub(crate) struct OriginFrames<'a> {
+    msg: &'a Message,
+    origin: &'a Origin,
+    /// Indexed by `[enveloped][qos]`
+    frames: [[std::cell::OnceCell<Option<Bytes>>; 3]; 2],
+}
+
+impl<'a> OriginFrames<'a> {
+    pub(crate) fn new(msg: &'a Message, origin: &'a Origin) -> Self {
+        Self {
+            msg,
+            origin,
+            frames: Default::default(),
+        }
+    }
+
+    /// The encoded frame for a recipient, or None if encoding failed
+    pub(crate) fn for_recipient(&self, recipient: &Recipient) -> Option<Bytes> {
+        let qos = delivery_qos(self.msg, recipient);
+        self.frames[recipient.envelope as usize][qos as usize]
+            .get_or_init(|| {
+                if recipient.envelope {
+                    codec::encode_with_options(&envelop(self.msg, self.origin), Some(qos), None)
+                        .ok()
+                } else {
+                    codec::encode_with_options(self.msg, Some(qos), None).ok()
+                }
+            })
+            .clone()
+    }
+}
+
+fn envelop(msg: &Message, origin: &Origin) -> Message {
+    Message::Envelope(EnvelopeMessage::new(origin.clone(), msg.clone()))
+}
+
+/// The QoS a message goes out to a recipient with: its subscription's
+/// override, else the publisher's
+fn delivery_qos(msg: &Message, recipient: &Recipient) -> QoS {
+    recipient.qos.unwrap_or_else(|| msg.default_qos())
+}
+
+/// Deliver a message from `origin` to subscribed sessions, skipping `exclude`.
+///
+/// Without middleware the message is encoded once (and once more for
+/// recipients taking envelopes) and fanned out; otherwise each recipient gets
+/// its own copy after the `on_deliver` hooks have run. Copies still queued
+/// for a session at `expires_at` are dropped.
+async fn deliver(
+    msg: &Message,
+    origin: &Origin,
+    recipients: Vec<Recipient>,
+    exclude: Option<&SessionId>,
+    expires_at: Option<Instant>,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    middleware: &MiddlewareChain,
+) {
+    let recipients = recipients
+        .into_iter()
+        .filter(|r| Some(&r.session_id) != exclude)
+        .filter_map(|r| {
+            sessions
+                .get(&r.session_id)
+                .map(|s| (r, Arc::clone(s.value())))
+        });
+
+    if middleware.is_empty() {
+        let frames = OriginFrames::new(msg, origin);
+        for (recipient, session) in recipients {
+            if let Some(bytes) = frames.for_recipient(&recipient) {
+                send_to_recipient(&session, bytes, &recipient, expires_at);
+            }
+        }
+        return;
+    }
+
+    let recipients: Vec<(Recipient, Arc<Session>)> = recipients.collect();
+    for (recipient, session) in recipients {
+        let mut copy = msg.clone();
+        if middleware.deliver(&session, &mut copy).await.is_continue() {
+            let qos = delivery_qos(&copy, &recipient);
+            if recipient.envelope {
+                copy = envelop(&copy, origin);
+            }
+            if let Ok(bytes) = codec::encode_with_options(&copy, Some(qos), None) {
+                send_to_recipient(&session, bytes, &recipient, expires_at);
+            }
+        }
+    }
+}
+
+/// Deliver an applied SET to the param's subscribers
+///
+/// Sessions whose matching subscriptions batch get the change folded into
+/// their pending batch, flushed as one SNAPSHOT when the window ends. A batch
+/// only holds the latest value, so batched changes ignore `expires_at`.
+async fn deliver_set(
+    set: SetMessage,
+    origin: &Origin,
+    expires_at: Option<Instant>,
+    subscriptions: &SubscriptionManager,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    middleware: &MiddlewareChain,
+) {
+    let (immediate, batched) = subscriptions.find_param_subscribers(&set.address, origin);
+    let msg = Message::Set(set);
+    deliver(
+        &msg, origin, immediate, None, expires_at, sessions, middleware,
+    )
+    .await;
+
+    for (session_id, window) in batched {
+        let Some(session) = sessions.get(&session_id).map(|s| Arc::clone(s.value())) else {
+            continue;
+        };
+        let mut copy = msg.clone();
+        if !middleware.is_empty() && !middleware.deliver(&session, &mut copy).await.is_continue() {
+            continue;
+        }
+        let Message::Set(set) = copy else {
+            // A middleware swapped the message; it can't be batched
+            if let Ok(bytes) = codec::encode(&copy) {
+                try_send_with_drop_tracking_sync(&session, bytes, &session.id, expires_at);
+            }
+            continue;
+        };
+
+        // Like the SET it replaces, a batched change doesn't name its writer
+        let param = ParamValue {
+            address: set.address,
+            value: set.value,
+            revision: set.revision.unwrap_or_default(),
+            writer: None,
+            timestamp: None,
+        };
+        if session.batch_param(param) {
+            tokio::spawn(async move {
+                tokio::time::sleep(window).await;
+                flush_batch(&session);
+            });
+        }
+    }
+}
+
+/// Send a session's pending param batch as SNAPSHOT frames
+fn flush_batch(session: &Arc<Session>) {
+    let params = session.take_batch();
+    if params.is_empty() || !session.is_connected() {
+        return;
+    }
+    for chunk in params.chunks(MAX_SNAPSHOT_CHUNK_SIZE) {
+        let snapshot = Message::Snapshot(SnapshotMessage {
+            params: chunk.to_vec(),
+            correlation_id: None,
+            page: None,
+            sync: None,
+        });
+        if let Ok(bytes) = codec::encode(&snapshot) {
+            try_send_with_drop_tracking_sync(session, bytes, &session.id, None);
+        }
+    }
+}
+
+/// Send a subscription delivery, following the recipient's queue policy
+///
+/// A recipient that asked for QoS `Fire` takes deliveries lossily: when its
+/// queue is full they are dropped and counted without warnings or
+/// BufferOverflow notices. Everyone else gets [`try_send_with_drop_tracking_sync`].
+/// Flow-controlled recipients out of credit are skipped the same quiet way.
+fn send_to_recipient(
+    session: &Arc<Session>,
+    data: Bytes,
+    recipient: &Recipient,
+    expires_at: Option<Instant>,
+) {
+    if !recipient.take_credit() {
+        session.record_lossy_drop();
+        return;
+    }
+    if recipient.qos != Some(QoS::Fire) {
+        try_send_with_drop_tracking_sync(session, data, &session.id, expires_at);
+        return;
+    }
+    let sent = match expires_at {
+        Some(expires_at) => session.try_send_expiring(data, expires_at),
+        None => session.try_send(data),
+    };
+    if let Err(e) = sent {
+        debug!("Dropped lossy delivery to {}: {}", session.id, e);
+        session.record_lossy_drop();
+    }
+}
+
 /// Try to send a message to a session with drop tracking.
 /// Records the drop and sends notification when threshold is exceeded.
-fn try_send_with_drop_tracking_sync(session: &Arc<Session>, data: Bytes, session_id: &SessionId) {
-    if let Err(e) = session.try_send(data) {
+fn try_send_with_drop_tracking_sync(
+    session: &Arc<Session>,
+    data: Bytes,
+    session_id: &SessionId,
+    expires_at: Option<Instant>,
+) {
+    let sent = match expires_at {
+        Some(expires_at) => session.try_send_expiring(data, expires_at),
+        None => session.try_send(data),
+    };
+    if let Err(e) = sent {
         warn!(
             "Failed to send to {}: {} (buffer full, dropping)",
             session_id, e
@@ -1824,15 +3812,13 @@ fn try_send_with_drop_tracking_sync(session: &Arc<Session>, data: Bytes, session
             let session_id = session_id.clone();
             let drops = session.drops_in_window();
             tokio::spawn(async move {
-                let error = Message::Error(ErrorMessage {
-                    code: 503, // Service Unavailable
-                    message: format!(
+                let error = Message::Error(ErrorMessage::new(
+                    ErrorCode::BufferOverflow,
+                    format!(
                         "Buffer overflow: messages being dropped ({} drops in last 10 seconds)",
                         drops
                     ),
-                    address: None,
-                    correlation_id: None,
-                });
+                ));
                 if let Ok(error_bytes) = codec::encode(&error) {
                     // Use send() not try_send() for the notification to ensure it gets through
                     if let Err(e) = session.send(error_bytes).await {
@@ -1849,6 +3835,98 @@ fn try_send_with_drop_tracking_sync(session: &Arc<Session>, data: Bytes, session
     }
 }
 
+/// Evict retained params if usage is over the memory budget, and report it
+/// on [`crate::budget::EVICTIONS_ADDRESS`]
+fn enforce_memory_budget(
+    budget: &MemoryBudget,
+    state: &RouterState,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    subscriptions: &SubscriptionManager,
+) {
+    let queued = state.queued_bytes();
+    let usage = state.retained_bytes() + queued;
+    let Some(target) = budget.eviction_target(usage, queued) else {
+        return;
+    };
+    let budget_bytes = budget.max_bytes.unwrap_or_default();
+
+    let evicted = state.evict_to(target, budget);
+    if evicted.is_empty() {
+        warn!(
+            "Memory budget exceeded ({} of {} bytes) with nothing left to evict",
+            usage, budget_bytes
+        );
+        return;
+    }
+
+    let report = EvictionReport {
+        freed_bytes: evicted.iter().map(|(_, bytes)| bytes).sum(),
+        addresses: evicted.into_iter().map(|(address, _)| address).collect(),
+        usage_bytes: usage,
+        budget_bytes,
+    };
+    warn!(
+        "Memory budget exceeded ({} of {} bytes): evicted {} params ({} bytes)",
+        usage,
+        budget_bytes,
+        report.addresses.len(),
+        report.freed_bytes
+    );
+
+    publish_event(&report.to_message(), sessions, subscriptions);
+}
+
+/// Report a security event on [`ban::SECURITY_ADDRESS`], closing the
+/// sessions of a banned IP
+fn apply_security_event(
+    event: &SecurityEvent,
+    bans: &BanList,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    subscriptions: &SubscriptionManager,
+) {
+    if let SecurityEvent::Banned { ip, reason, .. } = event {
+        warn!("Banned {}: {}", ip, reason);
+        let banned: Vec<Arc<Session>> = bans
+            .sessions_from(*ip)
+            .iter()
+            .filter_map(|id| sessions.get(id).map(|s| Arc::clone(s.value())))
+            .collect();
+        if !banned.is_empty() {
+            tokio::spawn(async move {
+                for session in banned {
+                    info!("Closing session {} (IP banned)", session.id);
+                    let _ = session.close().await;
+                }
+            });
+        }
+    } else if let SecurityEvent::Unbanned { ip } = event {
+        info!("Lifted ban on {}", ip);
+    }
+    publish_event(&event.to_message(), sessions, subscriptions);
+}
+
+/// Send a router-generated event to the subscribers of its address
+fn publish_event(
+    msg: &Message,
+    sessions: &DashMap<SessionId, Arc<Session>>,
+    subscriptions: &SubscriptionManager,
+) {
+    let Message::Publish(publish) = msg else {
+        return;
+    };
+    let subscribers = subscriptions.find_subscribers(&publish.address, publish.signal);
+    if subscribers.is_empty() {
+        return;
+    }
+    if let Ok(bytes) = codec::encode(msg) {
+        for session_id in subscribers {
+            if let Some(session) = sessions.get(&session_id) {
+                try_send_with_drop_tracking_sync(&session, bytes.clone(), &session_id, None);
+            }
+        }
+    }
+}
+
 /// Broadcast to all sessions except one (non-blocking)
 fn broadcast_to_subscribers(
     data: &Bytes,
@@ -1857,7 +3935,7 @@ fn broadcast_to_subscribers(
 ) {
     for entry in sessions.iter() {
         if entry.key() != exclude {
-            try_send_with_drop_tracking_sync(entry.value(), data.clone(), entry.key());
+            try_send_with_drop_tracking_sync(entry.value(), data.clone(), entry.key(), None);
         }
     }
 }
@@ -1,13 +1,16 @@
 //! Session management
 
+use crate::batch::ParamBatch;
 use bytes::Bytes;
-use clasp_core::{Action, Message, Scope, WelcomeMessage, PROTOCOL_VERSION};
+use clasp_core::{
+    Action, Message, Origin, ParamValue, Scope, TokenInfo, WelcomeMessage, PROTOCOL_VERSION,
+};
 use clasp_transport::TransportSender;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, OnceLock};
+use std::time::{Instant, SystemTime};
 use uuid::Uuid;
 
 /// Session identifier
@@ -18,6 +21,15 @@ const DROP_NOTIFICATION_THRESHOLD: u32 = 100; // Drops before notification
 const DROP_WINDOW_SECONDS: u64 = 10; // Time window for counting drops
 const DROP_NOTIFICATION_COOLDOWN_SECONDS: u64 = 10; // Min time between notifications
 
+/// Token-derived credentials, swapped as a unit on re-authentication
+#[derive(Default)]
+struct SessionAuth {
+    token: Option<String>,
+    subject: Option<String>,
+    scopes: Vec<Scope>,
+    expires_at: Option<SystemTime>,
+}
+
 /// A connected client session
 pub struct Session {
     /// Unique session ID
@@ -36,12 +48,8 @@ pub struct Session {
     pub last_activity: RwLock<Instant>,
     /// Is authenticated
     pub authenticated: bool,
-    /// Permission token (if any)
-    pub token: Option<String>,
-    /// Subject identifier from token (user, device, or service ID)
-    pub subject: Option<String>,
-    /// Scopes granted to this session
-    scopes: Vec<Scope>,
+    /// Token, subject, scopes and expiry
+    auth: RwLock<SessionAuth>,
     /// Messages received in the current second (for rate limiting)
     messages_this_second: AtomicU32,
     /// The second when the message count was last reset (Unix timestamp)
@@ -54,6 +62,12 @@ pub struct Session {
     last_drop_notification: AtomicU64,
     /// Total drops since session started
     total_drops: AtomicU64,
+    /// Param changes held for batched subscriptions
+    batch: Mutex<ParamBatch>,
+    /// Main session of the connection, for a logical client sharing it
+    parent: OnceLock<SessionId>,
+    /// Protocol and remote peer the session's traffic comes from
+    origin: RwLock<Origin>,
 }
 
 impl Session {
@@ -69,18 +83,52 @@ impl Session {
             created_at: now,
             last_activity: RwLock::new(now),
             authenticated: false,
-            token: None,
-            subject: None,
-            scopes: Vec::new(),
+            auth: RwLock::new(SessionAuth::default()),
             messages_this_second: AtomicU32::new(0),
             last_rate_limit_second: AtomicU64::new(0),
             drops_in_window: AtomicU32::new(0),
             drop_window_start: AtomicU64::new(0),
             last_drop_notification: AtomicU64::new(0),
             total_drops: AtomicU64::new(0),
+            batch: Mutex::new(ParamBatch::new()),
+            parent: OnceLock::new(),
+            origin: RwLock::new(Origin::native()),
         }
     }
 
+    /// Tag the session's traffic with where it comes from
+    pub fn with_origin(mut self, origin: Origin) -> Self {
+        *self.origin.get_mut() = origin;
+        self
+    }
+
+    /// Use a specific session id instead of a random UUID
+    pub fn with_id(mut self, id: SessionId) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Main session of the connection this sub-session shares, if any
+    pub fn parent(&self) -> Option<&SessionId> {
+        self.parent.get()
+    }
+
+    /// Record the main session of a shared connection
+    pub(crate) fn set_parent(&self, parent: SessionId) {
+        let _ = self.parent.set(parent);
+    }
+
+    /// Where the session's traffic comes from (native CLASP by default)
+    pub fn origin(&self) -> Origin {
+        self.origin.read().clone()
+    }
+
+    /// Replace the session's origin, e.g. when a bridge declares the
+    /// protocol it relays
+    pub fn set_origin(&self, origin: Origin) {
+        *self.origin.write() = origin;
+    }
+
     /// Set authentication info from a validated token
     pub fn set_authenticated(
         &mut self,
@@ -89,26 +137,71 @@ impl Session {
         scopes: Vec<Scope>,
     ) {
         self.authenticated = true;
-        self.token = Some(token);
-        self.subject = subject;
-        self.scopes = scopes;
+        *self.auth.get_mut() = SessionAuth {
+            token: Some(token),
+            subject,
+            scopes,
+            expires_at: None,
+        };
+    }
+
+    /// Authenticate with a validated token, including its expiry
+    pub fn authenticate(&mut self, token: String, info: TokenInfo) {
+        self.authenticated = true;
+        self.reauthenticate(token, info);
+    }
+
+    /// Replace the session's token, subject, scopes and expiry in one step
+    ///
+    /// Used when a client presents a fresh token on a live connection; the
+    /// new scopes apply to the next message.
+    pub fn reauthenticate(&self, token: String, info: TokenInfo) {
+        *self.auth.write() = SessionAuth {
+            token: Some(token),
+            subject: info.subject,
+            scopes: info.scopes,
+            expires_at: info.expires_at,
+        };
     }
 
     /// Check if this session has permission for the given action on the given address
     pub fn has_scope(&self, action: Action, address: &str) -> bool {
+        let auth = self.auth.read();
         // Unauthenticated sessions in open mode have no scope restrictions
         // (handled by router based on SecurityMode)
-        if self.scopes.is_empty() && !self.authenticated {
+        if auth.scopes.is_empty() && !self.authenticated {
             return true;
         }
-        self.scopes
+        auth.scopes
             .iter()
             .any(|scope| scope.allows(action, address))
     }
 
     /// Get the scopes for this session
-    pub fn scopes(&self) -> &[Scope] {
-        &self.scopes
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.auth.read().scopes.clone()
+    }
+
+    /// Permission token (if any)
+    pub fn token(&self) -> Option<String> {
+        self.auth.read().token.clone()
+    }
+
+    /// Subject identifier from the token (user, device, or service ID)
+    pub fn subject(&self) -> Option<String> {
+        self.auth.read().subject.clone()
+    }
+
+    /// When the session's token expires (if it does)
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.auth.read().expires_at
+    }
+
+    /// Whether the session's token has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at()
+            .map(|expires_at| SystemTime::now() > expires_at)
+            .unwrap_or(false)
     }
 
     /// Send a message to this session
@@ -126,6 +219,18 @@ impl Session {
         Ok(())
     }
 
+    /// Try to send data that is stale after `expires_at`; the transport
+    /// drops it if it is still queued by then
+    pub fn try_send_expiring(
+        &self,
+        data: Bytes,
+        expires_at: Instant,
+    ) -> Result<(), clasp_transport::TransportError> {
+        self.sender.try_send_expiring(data, expires_at)?;
+        *self.last_activity.write() = Instant::now();
+        Ok(())
+    }
+
     /// Send a Clasp message
     pub async fn send_message(&self, message: &Message) -> Result<(), clasp_core::Error> {
         let data = clasp_core::codec::encode(message)?;
@@ -162,11 +267,33 @@ impl Session {
         self.subscriptions.read().iter().cloned().collect()
     }
 
+    /// Hold a param change for batched delivery
+    ///
+    /// Returns true when this starts a new batch that needs a flush.
+    pub fn batch_param(&self, param: ParamValue) -> bool {
+        self.batch.lock().push(param)
+    }
+
+    /// Take the param changes held for batched delivery
+    pub fn take_batch(&self) -> Vec<ParamValue> {
+        self.batch.lock().take()
+    }
+
+    /// Estimated bytes held in this session's batch queue
+    pub fn queued_bytes(&self) -> usize {
+        self.batch.lock().bytes()
+    }
+
     /// Check if connected
     pub fn is_connected(&self) -> bool {
         self.sender.is_connected()
     }
 
+    /// Close the underlying connection
+    pub async fn close(&self) -> Result<(), clasp_transport::TransportError> {
+        self.sender.close().await
+    }
+
     /// Touch to update last activity
     pub fn touch(&self) {
         *self.last_activity.write() = Instant::now();
@@ -243,6 +370,12 @@ impl Session {
         false
     }
 
+    /// Record a dropped message the session's subscription asked to take
+    /// lossily (QoS `Fire`); it counts toward the total but is never reported
+    pub fn record_lossy_drop(&self) {
+        self.total_drops.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Get the total number of dropped messages for this session
     pub fn total_drops(&self) -> u64 {
         self.total_drops.load(Ordering::Relaxed)
@@ -261,8 +394,8 @@ impl std::fmt::Debug for Session {
             .field("name", &self.name)
             .field("features", &self.features)
             .field("authenticated", &self.authenticated)
-            .field("subject", &self.subject)
-            .field("scopes", &self.scopes.len())
+            .field("subject", &self.subject())
+            .field("scopes", &self.auth.read().scopes.len())
             .finish()
     }
 }
@@ -1,11 +1,16 @@
 //! Router state management
 
 use clasp_core::state::{ParamState, StateStore, StateStoreConfig, UpdateError};
-use clasp_core::{ParamValue, SetMessage, SignalDefinition, SnapshotMessage, Value};
+use clasp_core::{
+    Manifest, ManifestViolation, ParamValue, SetMessage, SignalDefinition, SnapshotMessage,
+    SnapshotSync, Value,
+};
 use dashmap::DashMap;
 use parking_lot::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
+use crate::budget::{param_bytes, MemoryBudget};
 use crate::SessionId;
 
 /// Signal entry with registration time for cleanup
@@ -19,6 +24,13 @@ pub struct SignalEntry {
     pub last_accessed: Instant,
 }
 
+/// A published manifest and the session that published it
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub manifest: Manifest,
+    pub owner: SessionId,
+}
+
 /// Configuration for router state management
 #[derive(Debug, Clone)]
 pub struct RouterStateConfig {
@@ -59,8 +71,14 @@ pub struct RouterState {
     listeners: DashMap<String, Vec<Box<dyn Fn(&str, &Value) + Send + Sync>>>,
     /// Signal registry (announced signals from clients) with timestamps
     signals: DashMap<String, SignalEntry>,
+    /// Published namespace manifests by app
+    manifests: DashMap<String, ManifestEntry>,
     /// Configuration
     config: RouterStateConfig,
+    /// Estimated bytes held by retained params
+    retained_bytes: AtomicUsize,
+    /// Estimated bytes waiting in session queues, as last measured
+    queued_bytes: AtomicUsize,
 }
 
 impl RouterState {
@@ -74,7 +92,10 @@ impl RouterState {
             params: RwLock::new(StateStore::with_config(config.param_config.clone())),
             listeners: DashMap::new(),
             signals: DashMap::new(),
+            manifests: DashMap::new(),
             config,
+            retained_bytes: AtomicUsize::new(0),
+            queued_bytes: AtomicUsize::new(0),
         }
     }
 
@@ -116,6 +137,68 @@ impl RouterState {
         self.signals.len()
     }
 
+    /// Store a published manifest and register its signals for QUERY
+    pub fn register_manifest(&self, manifest: Manifest, owner: &SessionId) {
+        self.register_signals(manifest.signals.clone());
+        self.manifests.insert(
+            manifest.app.clone(),
+            ManifestEntry {
+                manifest,
+                owner: owner.clone(),
+            },
+        );
+    }
+
+    /// Forget an app's manifest
+    pub fn remove_manifest(&self, app: &str) -> Option<ManifestEntry> {
+        self.manifests.remove(app).map(|(_, entry)| entry)
+    }
+
+    /// The manifest published for `app`
+    pub fn manifest(&self, app: &str) -> Option<ManifestEntry> {
+        self.manifests.get(app).map(|entry| entry.value().clone())
+    }
+
+    /// All published manifests, by app name
+    pub fn manifests(&self) -> Vec<Manifest> {
+        let mut manifests: Vec<Manifest> = self
+            .manifests
+            .iter()
+            .map(|entry| entry.value().manifest.clone())
+            .collect();
+        manifests.sort_by(|a, b| a.app.cmp(&b.app));
+        manifests
+    }
+
+    /// Check a write against the manifest owning `address`, if any
+    ///
+    /// The manifest with the longest namespace covering the address
+    /// applies. Without a `value` (relative SETs, valueless PUBLISHes) only
+    /// declaration and access are checked.
+    pub fn check_manifest(
+        &self,
+        address: &str,
+        value: Option<&Value>,
+        writer: &SessionId,
+    ) -> Result<(), ManifestViolation> {
+        if self.manifests.is_empty() {
+            return Ok(());
+        }
+        let Some(entry) = self
+            .manifests
+            .iter()
+            .filter(|entry| entry.manifest.owns(address))
+            .max_by_key(|entry| entry.manifest.namespace.len())
+        else {
+            return Ok(());
+        };
+        let owner = entry.owner == *writer;
+        match value {
+            Some(value) => entry.manifest.check(address, value, owner),
+            None => entry.manifest.check_access(address, owner).map(|_| ()),
+        }
+    }
+
     /// Remove stale signals that haven't been accessed within the TTL
     /// Returns the number of signals removed
     pub fn cleanup_stale_signals(&self, ttl: Duration) -> usize {
@@ -129,14 +212,19 @@ impl RouterState {
     /// Remove stale params using the configured TTL
     /// Returns the number of params removed
     pub fn cleanup_stale_params(&self, ttl: Duration) -> usize {
-        self.params.write().cleanup_stale(ttl)
+        let mut params = self.params.write();
+        let removed = params.cleanup_stale(ttl);
+        if removed > 0 {
+            self.recount_bytes(&params);
+        }
+        removed
     }
 
     /// Run all cleanup operations using configured TTLs
     /// Returns (params_removed, signals_removed)
     pub fn cleanup_stale(&self) -> (usize, usize) {
         let params_removed = if let Some(ttl) = self.config.param_config.param_ttl {
-            self.params.write().cleanup_stale(ttl)
+            self.cleanup_stale_params(ttl)
         } else {
             0
         };
@@ -170,33 +258,79 @@ impl RouterState {
         lock: bool,
         unlock: bool,
     ) -> Result<u64, UpdateError> {
-        let result =
-            self.params
-                .write()
-                .set(address, value.clone(), writer, revision, lock, unlock)?;
-
-        // Notify listeners
-        if let Some(listeners) = self.listeners.get(address) {
-            for listener in listeners.iter() {
-                listener(address, &value);
-            }
-        }
-
-        Ok(result)
+        self.write(address, writer, revision, lock, unlock, |_| Ok(value))
+            .map(|(revision, _)| revision)
     }
 
     /// Apply a SET message
     pub fn apply_set(&self, msg: &SetMessage, writer: &SessionId) -> Result<u64, UpdateError> {
-        self.set(
+        self.apply_set_value(msg, writer)
+            .map(|(revision, _)| revision)
+    }
+
+    /// Apply a SET message, returning the new revision and the value now held
+    ///
+    /// A relative SET (`op`) is resolved against the retained value under the
+    /// store's write lock, so concurrent increments are never lost.
+    pub fn apply_set_value(
+        &self,
+        msg: &SetMessage,
+        writer: &SessionId,
+    ) -> Result<(u64, Value), UpdateError> {
+        self.write(
             &msg.address,
-            msg.value.clone(),
             writer,
             msg.revision,
             msg.lock,
             msg.unlock,
+            |current| match msg.op {
+                None => Ok(msg.value.clone()),
+                Some(op) => op
+                    .apply(current.ok_or(UpdateError::Missing)?, &msg.value)
+                    .ok_or(UpdateError::InvalidOperand),
+            },
         )
     }
 
+    /// Write the value `resolve` computes from the current one
+    fn write(
+        &self,
+        address: &str,
+        writer: &SessionId,
+        revision: Option<u64>,
+        lock: bool,
+        unlock: bool,
+        resolve: impl FnOnce(Option<&Value>) -> Result<Value, UpdateError>,
+    ) -> Result<(u64, Value), UpdateError> {
+        let (result, value) = {
+            let mut params = self.params.write();
+            let value = resolve(params.get_value(address))?;
+            let len_before = params.len();
+            let old_bytes = params.get(address).map(|p| param_bytes(address, p));
+            let result = params.set(address, value.clone(), writer, revision, lock, unlock);
+            let new_bytes = params.get(address).map(|p| param_bytes(address, p));
+            if old_bytes.is_none() && new_bytes.is_some() && params.len() == len_before {
+                // The store evicted another param to stay under max_params
+                self.recount_bytes(&params);
+            } else {
+                self.retained_bytes
+                    .fetch_add(new_bytes.unwrap_or(0), Ordering::Relaxed);
+                self.retained_bytes
+                    .fetch_sub(old_bytes.unwrap_or(0), Ordering::Relaxed);
+            }
+            (result?, value)
+        };
+
+        // Notify listeners
+        if let Some(listeners) = self.listeners.get(address) {
+            for listener in listeners.iter() {
+                listener(address, &value);
+            }
+        }
+
+        Ok((result, value))
+    }
+
     /// Get all parameters matching a pattern
     pub fn get_matching(&self, pattern: &str) -> Vec<(String, ParamState)> {
         self.params
@@ -209,19 +343,41 @@ impl RouterState {
 
     /// Create a snapshot of all params matching a pattern
     pub fn snapshot(&self, pattern: &str) -> SnapshotMessage {
-        let params: Vec<ParamValue> = self
-            .get_matching(pattern)
-            .into_iter()
-            .map(|(address, state)| ParamValue {
-                address,
-                value: state.value,
-                revision: state.revision,
-                writer: Some(state.writer),
-                timestamp: Some(state.timestamp),
-            })
-            .collect();
+        self.snapshot_since(pattern, None)
+    }
+
+    /// Snapshot of the params matching a pattern written after store revision
+    /// `since`, or of all of them when `since` is `None` or can't be diffed
+    /// against (see [`StateStore::changed_since`])
+    pub fn snapshot_since(&self, pattern: &str, since: Option<u64>) -> SnapshotMessage {
+        let params = self.params.read();
+        let changed = since.and_then(|since| params.changed_since(pattern, since));
+        let since = since.filter(|_| changed.is_some());
+        let matching = changed.unwrap_or_else(|| params.get_matching(pattern));
+
+        SnapshotMessage {
+            params: matching
+                .into_iter()
+                .map(|(address, state)| ParamValue {
+                    address: address.to_string(),
+                    value: state.value.clone(),
+                    revision: state.revision,
+                    writer: Some(state.writer.clone()),
+                    timestamp: Some(state.timestamp),
+                })
+                .collect(),
+            correlation_id: None,
+            page: None,
+            sync: Some(SnapshotSync {
+                revision: params.revision(),
+                since,
+            }),
+        }
+    }
 
-        SnapshotMessage { params }
+    /// Store revision of the latest param write
+    pub fn revision(&self) -> u64 {
+        self.params.read().revision()
     }
 
     /// Create a full snapshot
@@ -242,6 +398,65 @@ impl RouterState {
     /// Clear all state
     pub fn clear(&self) {
         self.params.write().clear();
+        self.retained_bytes.store(0, Ordering::Relaxed);
+    }
+
+    /// Estimated bytes held by retained params
+    pub fn retained_bytes(&self) -> usize {
+        self.retained_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Estimated bytes waiting in session queues, as last recorded
+    pub fn queued_bytes(&self) -> usize {
+        self.queued_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Record the bytes currently waiting in session queues
+    pub fn record_queued_bytes(&self, bytes: usize) {
+        self.queued_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Evict params in `budget` order until retained state is at most
+    /// `target` bytes
+    ///
+    /// Locked params are skipped. Returns the evicted addresses with their
+    /// estimated sizes.
+    pub fn evict_to(&self, target: usize, budget: &MemoryBudget) -> Vec<(String, usize)> {
+        let mut params = self.params.write();
+        let mut candidates: Vec<_> = params
+            .snapshot()
+            .into_iter()
+            .filter(|(_, state)| state.lock_holder.is_none())
+            .map(|(address, state)| {
+                (
+                    budget.eviction_key(address, state),
+                    address.to_string(),
+                    param_bytes(address, state),
+                )
+            })
+            .collect();
+        candidates.sort_unstable_by_key(|(key, _, _)| *key);
+
+        let mut evicted = Vec::new();
+        for (_, address, bytes) in candidates {
+            if self.retained_bytes() <= target {
+                break;
+            }
+            params.remove(&address);
+            self.retained_bytes.fetch_sub(bytes, Ordering::Relaxed);
+            evicted.push((address, bytes));
+        }
+        evicted
+    }
+
+    /// Recompute the retained byte estimate from scratch
+    fn recount_bytes(&self, params: &StateStore) {
+        let total = params
+            .snapshot()
+            .into_iter()
+            .map(|(address, state)| param_bytes(address, state))
+            .sum();
+        self.retained_bytes.store(total, Ordering::Relaxed);
     }
 }
 
@@ -254,6 +469,7 @@ impl Default for RouterState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clasp_core::SetOp;
 
     #[test]
     fn test_basic_state() {
@@ -274,6 +490,58 @@ mod tests {
         assert_eq!(value, Value::Float(0.5));
     }
 
+    #[test]
+    fn test_relative_set() {
+        let state = RouterState::new();
+        let writer = "s1".to_string();
+        let op = |address: &str, op: SetOp, value: Value| SetMessage {
+            address: address.to_string(),
+            value,
+            revision: None,
+            lock: false,
+            unlock: false,
+            op: Some(op),
+            correlation_id: None,
+        };
+
+        assert!(matches!(
+            state.apply_set_value(&op("/gain", SetOp::Add, Value::Int(1)), &writer),
+            Err(UpdateError::Missing)
+        ));
+
+        state
+            .set("/gain", Value::Int(10), &writer, None, false, false)
+            .unwrap();
+        state
+            .set("/mute", Value::Bool(false), &writer, None, false, false)
+            .unwrap();
+
+        let (revision, value) = state
+            .apply_set_value(&op("/gain", SetOp::Add, Value::Int(-3)), &writer)
+            .unwrap();
+        assert_eq!((revision, value), (2, Value::Int(7)));
+        let (_, value) = state
+            .apply_set_value(&op("/gain", SetOp::Mul, Value::Float(0.5)), &writer)
+            .unwrap();
+        assert_eq!(value, Value::Float(3.5));
+        assert_eq!(state.get("/gain"), Some(Value::Float(3.5)));
+
+        let (_, value) = state
+            .apply_set_value(&op("/mute", SetOp::Toggle, Value::Null), &writer)
+            .unwrap();
+        assert_eq!(value, Value::Bool(true));
+
+        assert!(matches!(
+            state.apply_set_value(&op("/mute", SetOp::Add, Value::Int(1)), &writer),
+            Err(UpdateError::InvalidOperand)
+        ));
+        assert!(matches!(
+            state.apply_set_value(&op("/gain", SetOp::Toggle, Value::Null), &writer),
+            Err(UpdateError::InvalidOperand)
+        ));
+        assert_eq!(state.get("/mute"), Some(Value::Bool(true)));
+    }
+
     #[test]
     fn test_snapshot() {
         let state = RouterState::new();
@@ -421,4 +689,69 @@ mod tests {
         assert_eq!(state.signal_count(), 0);
         assert_eq!(state.len(), 1);
     }
+
+    #[test]
+    fn test_retained_bytes_tracking() {
+        let state = RouterState::new();
+        let writer = "s1".to_string();
+
+        state
+            .set("/a", Value::Int(1), &writer, None, false, false)
+            .unwrap();
+        let one = state.retained_bytes();
+        assert!(one > 0);
+
+        state
+            .set(
+                "/a",
+                Value::String("x".repeat(1000)),
+                &writer,
+                None,
+                false,
+                false,
+            )
+            .unwrap();
+        assert_eq!(state.retained_bytes(), one + 1000);
+
+        state
+            .set("/a", Value::Int(2), &writer, None, false, false)
+            .unwrap();
+        assert_eq!(state.retained_bytes(), one);
+
+        state.clear();
+        assert_eq!(state.retained_bytes(), 0);
+    }
+
+    #[test]
+    fn test_evict_to_budget() {
+        use crate::budget::EvictionPriority;
+
+        let state = RouterState::new();
+        let writer = "s1".to_string();
+        for address in ["/show/a", "/scratch/a", "/misc/a"] {
+            state
+                .set(address, Value::Int(1), &writer, None, false, false)
+                .unwrap();
+        }
+        // Locked params are never evicted
+        state
+            .set("/scratch/locked", Value::Int(1), &writer, None, true, false)
+            .unwrap();
+
+        let budget = MemoryBudget::new(1)
+            .with_priority(EvictionPriority::new("/show/**", 10))
+            .with_priority(EvictionPriority::new("/scratch/**", -10));
+
+        // Just over target: the unlocked scratch param goes first
+        let evicted = state.evict_to(state.retained_bytes() - 1, &budget);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0, "/scratch/a");
+
+        // Then the default priority, and the show param last
+        let evicted = state.evict_to(0, &budget);
+        let addresses: Vec<_> = evicted.iter().map(|(a, _)| a.as_str()).collect();
+        assert_eq!(addresses, ["/misc/a", "/show/a"]);
+        assert_eq!(state.len(), 1);
+        assert!(state.get("/scratch/locked").is_some());
+    }
 }
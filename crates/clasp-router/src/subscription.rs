@@ -1,11 +1,33 @@
 //! Subscription management
+//!
+//! The manager also keeps delivery statistics for operators: how often each
+//! subscription matched, which addresses fan out to the most sessions, and
+//! which patterns never matched anything (see [`SubscriptionStats`]).
 
-use clasp_core::{address::Pattern, SignalType, SubscribeOptions};
+use clasp_core::{address::Pattern, Origin, QoS, SignalType, SubscribeOptions, Value};
 use dashmap::DashMap;
-use std::collections::HashSet;
-
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::batch::batch_window;
 use crate::SessionId;
 
+/// Address subscription statistics are read from (GET, admin scope)
+pub const STATS_ADDRESS: &str = "/$sys/stats/subscriptions";
+
+/// Hot addresses included in stats read over the protocol
+pub const STATS_HOT_ADDRESSES: usize = 20;
+
+/// Addresses tracked for the hot address list
+///
+/// Beyond this many, the coldest one makes room for a new one and its counts
+/// carry over, so counts of long-tail addresses are upper bounds.
+const HOT_ADDRESS_SLOTS: usize = 1024;
+
 /// A subscription entry
 #[derive(Debug, Clone)]
 pub struct Subscription {
@@ -19,8 +41,56 @@ pub struct Subscription {
     pub types: HashSet<SignalType>,
     /// Subscription options
     pub options: SubscribeOptions,
+    /// Deliveries left before the client must grant more (flow control)
+    pub credits: Option<CreditWindow>,
+    /// Messages delivered through this subscription, shared by its copies
+    pub hits: Arc<AtomicU64>,
+}
+
+/// Remaining deliveries of a flow-controlled subscription
+///
+/// Shared by every copy of the subscription, so credits spent while
+/// delivering and credits granted by CREDIT meet in one counter.
+#[derive(Debug, Clone)]
+pub struct CreditWindow(Arc<Mutex<u32>>);
+
+impl CreditWindow {
+    pub fn new(credits: u32) -> Self {
+        Self(Arc::new(Mutex::new(credits)))
+    }
+
+    /// Spend one credit; false when none are left
+    pub fn take(&self) -> bool {
+        let mut credits = self.0.lock();
+        match credits.checked_sub(1) {
+            Some(left) => {
+                *credits = left;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add credits granted by the client
+    pub fn grant(&self, credits: u32) {
+        let mut left = self.0.lock();
+        *left = left.saturating_add(credits);
+    }
+
+    /// Credits left
+    pub fn remaining(&self) -> u32 {
+        *self.0.lock()
+    }
+}
+
+impl PartialEq for CreditWindow {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
+impl Eq for CreditWindow {}
+
 impl Subscription {
     pub fn new(
         id: u32,
@@ -36,7 +106,9 @@ impl Subscription {
             session_id,
             pattern,
             types: types.into_iter().collect(),
+            credits: options.credits.map(CreditWindow::new),
             options,
+            hits: Arc::new(AtomicU64::new(0)),
         })
     }
 
@@ -58,6 +130,64 @@ impl Subscription {
 
         true
     }
+
+    /// Check if this subscription accepts traffic from `origin`
+    pub fn accepts_origin(&self, origin: &Origin) -> bool {
+        match self.options.origins {
+            Some(ref protocols) => protocols.contains(&origin.protocol),
+            None => true,
+        }
+    }
+}
+
+/// A session to deliver to, and how its matching subscriptions want delivery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    pub session_id: SessionId,
+    pub envelope: bool,
+    /// Delivery QoS in place of the publisher's, set only when every
+    /// matching subscription overrides it (the most reliable one wins)
+    pub qos: Option<QoS>,
+    /// Credit windows of the matching subscriptions, set only when every
+    /// one of them is flow-controlled
+    pub credits: Option<Vec<CreditWindow>>,
+}
+
+impl Recipient {
+    fn from_subscription(sub: &Subscription) -> Self {
+        Self {
+            session_id: sub.session_id.clone(),
+            envelope: sub.options.envelope,
+            qos: sub.options.qos,
+            credits: sub.credits.clone().map(|window| vec![window]),
+        }
+    }
+
+    /// Fold in another matching subscription of the same session
+    fn merge(&mut self, sub: &Subscription) {
+        self.envelope |= sub.options.envelope;
+        self.qos = match (self.qos, sub.options.qos) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+        self.credits = match (self.credits.take(), &sub.credits) {
+            (Some(mut windows), Some(window)) => {
+                windows.push(window.clone());
+                Some(windows)
+            }
+            _ => None,
+        };
+    }
+
+    /// Spend a credit for one delivery; false if flow control holds it back
+    ///
+    /// Any matching subscription with credit left lets it through.
+    pub fn take_credit(&self) -> bool {
+        match self.credits {
+            Some(ref windows) => windows.iter().any(CreditWindow::take),
+            None => true,
+        }
+    }
 }
 
 /// Manages all subscriptions
@@ -66,6 +196,8 @@ pub struct SubscriptionManager {
     subscriptions: DashMap<(SessionId, u32), Subscription>,
     /// Index by address prefix for faster lookup
     by_prefix: DashMap<String, Vec<(SessionId, u32)>>,
+    /// Delivery counts of the busiest addresses
+    hot: DashMap<String, AddressStats>,
 }
 
 impl SubscriptionManager {
@@ -73,6 +205,7 @@ impl SubscriptionManager {
         Self {
             subscriptions: DashMap::new(),
             by_prefix: DashMap::new(),
+            hot: DashMap::new(),
         }
     }
 
@@ -147,6 +280,46 @@ impl SubscriptionManager {
         self.by_prefix.retain(|_, v| !v.is_empty());
     }
 
+    /// Grant more deliveries to a flow-controlled subscription
+    ///
+    /// Returns false if the session has no such subscription, or it isn't
+    /// flow-controlled.
+    pub fn grant(&self, session_id: &SessionId, id: u32, credits: u32) -> bool {
+        match self.subscriptions.get(&(session_id.clone(), id)) {
+            Some(sub) => match sub.credits {
+                Some(ref window) => {
+                    window.grant(credits);
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Move all subscriptions of one session to another
+    ///
+    /// Returns the ids of the moved subscriptions.
+    pub fn transfer_session(&self, from: &SessionId, to: &SessionId) -> Vec<u32> {
+        let moved: Vec<Subscription> = self
+            .subscriptions
+            .iter()
+            .filter(|entry| entry.key().0 == *from)
+            .map(|entry| entry.value().clone())
+            .collect();
+        self.remove_session(from);
+
+        moved
+            .into_iter()
+            .map(|mut sub| {
+                let id = sub.id;
+                sub.session_id = to.clone();
+                self.add(sub);
+                id
+            })
+            .collect()
+    }
+
     /// Find all sessions subscribed to an address
     pub fn find_subscribers(
         &self,
@@ -154,7 +327,84 @@ impl SubscriptionManager {
         signal_type: Option<SignalType>,
     ) -> Vec<SessionId> {
         let mut subscribers = HashSet::new();
+        self.for_each_match(address, signal_type, |sub| {
+            subscribers.insert(sub.session_id.clone());
+        });
+        subscribers.into_iter().collect()
+    }
+
+    /// Find the sessions to deliver traffic from `origin` to
+    ///
+    /// Subscriptions filtering on other origins are skipped. A session gets
+    /// envelopes if any of its matching subscriptions asked for them.
+    pub fn find_recipients(
+        &self,
+        address: &str,
+        signal_type: Option<SignalType>,
+        origin: &Origin,
+    ) -> Vec<Recipient> {
+        let mut recipients: HashMap<SessionId, Recipient> = HashMap::new();
+        self.for_each_match(address, signal_type, |sub| {
+            if sub.accepts_origin(origin) {
+                sub.hits.fetch_add(1, Ordering::Relaxed);
+                recipients
+                    .entry(sub.session_id.clone())
+                    .and_modify(|r| r.merge(sub))
+                    .or_insert_with(|| Recipient::from_subscription(sub));
+            }
+        });
+        self.record_delivery(address, recipients.len());
+        recipients.into_values().collect()
+    }
+
+    /// Find the sessions subscribed to a param, split by delivery mode
+    ///
+    /// Returns sessions that get changes immediately, and sessions whose
+    /// matching subscriptions all batch, with the shortest of their windows.
+    /// Batched changes are delivered in SNAPSHOTs without envelopes.
+    pub fn find_param_subscribers(
+        &self,
+        address: &str,
+        origin: &Origin,
+    ) -> (Vec<Recipient>, Vec<(SessionId, Duration)>) {
+        let mut windows: HashMap<SessionId, (Option<Duration>, Recipient)> = HashMap::new();
+        self.for_each_match(address, Some(SignalType::Param), |sub| {
+            if !sub.accepts_origin(origin) {
+                return;
+            }
+            sub.hits.fetch_add(1, Ordering::Relaxed);
+            let window = batch_window(sub.options.batch);
+            windows
+                .entry(sub.session_id.clone())
+                .and_modify(|(current, recipient)| {
+                    *current = match (*current, window) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        _ => None,
+                    };
+                    recipient.merge(sub);
+                })
+                .or_insert_with(|| (window, Recipient::from_subscription(sub)));
+        });
+
+        self.record_delivery(address, windows.len());
+        let mut immediate = Vec::new();
+        let mut batched = Vec::new();
+        for (session_id, (window, recipient)) in windows {
+            match window {
+                Some(window) => batched.push((session_id, window)),
+                None => immediate.push(recipient),
+            }
+        }
+        (immediate, batched)
+    }
 
+    /// Call `f` for every subscription matching an address
+    fn for_each_match(
+        &self,
+        address: &str,
+        signal_type: Option<SignalType>,
+        mut f: impl FnMut(&Subscription),
+    ) {
         // Extract prefix from address (first segment)
         let address_prefix = address
             .split('/')
@@ -194,12 +444,95 @@ impl SubscriptionManager {
             if let Some(entry) = self.subscriptions.get(&key) {
                 let sub = entry.value();
                 if sub.matches(address, signal_type) {
-                    subscribers.insert(sub.session_id.clone());
+                    f(sub);
                 }
             }
         }
+    }
 
-        subscribers.into_iter().collect()
+    /// Count a message to `address` delivered to `sessions` sessions
+    fn record_delivery(&self, address: &str, sessions: usize) {
+        if sessions == 0 {
+            return;
+        }
+        if !self.hot.contains_key(address) && self.hot.len() >= HOT_ADDRESS_SLOTS {
+            // Space-saving: the coldest address makes room and its counts
+            // carry over to the new one
+            let coldest = self
+                .hot
+                .iter()
+                .min_by_key(|entry| entry.value().deliveries)
+                .map(|entry| entry.key().clone());
+            if let Some((_, stats)) = coldest.and_then(|address| self.hot.remove(&address)) {
+                self.hot.insert(
+                    address.to_string(),
+                    AddressStats {
+                        address: address.to_string(),
+                        ..stats
+                    },
+                );
+            }
+        }
+
+        let mut stats = self
+            .hot
+            .entry(address.to_string())
+            .or_insert_with(|| AddressStats {
+                address: address.to_string(),
+                messages: 0,
+                deliveries: 0,
+            });
+        stats.messages += 1;
+        stats.deliveries += sessions as u64;
+    }
+
+    /// Delivery statistics, with the `hot` busiest addresses
+    pub fn stats(&self, hot: usize) -> SubscriptionStats {
+        let mut patterns: BTreeMap<String, PatternStats> = BTreeMap::new();
+        let mut sessions = HashSet::new();
+        for entry in self.subscriptions.iter() {
+            let sub = entry.value();
+            sessions.insert(sub.session_id.clone());
+            let pattern = sub.pattern.address().as_str();
+            let stats = patterns
+                .entry(pattern.to_string())
+                .or_insert_with(|| PatternStats {
+                    pattern: pattern.to_string(),
+                    subscriptions: 0,
+                    hits: 0,
+                });
+            stats.subscriptions += 1;
+            stats.hits += sub.hits.load(Ordering::Relaxed);
+        }
+
+        let (orphans, mut patterns): (Vec<_>, Vec<_>) =
+            patterns.into_values().partition(|p| p.hits == 0);
+        patterns.sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.pattern.cmp(&b.pattern)));
+
+        let mut hot_addresses: Vec<AddressStats> =
+            self.hot.iter().map(|entry| entry.value().clone()).collect();
+        hot_addresses.sort_by(|a, b| {
+            b.deliveries
+                .cmp(&a.deliveries)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+        hot_addresses.truncate(hot);
+
+        SubscriptionStats {
+            subscriptions: self.subscriptions.len(),
+            sessions: sessions.len(),
+            patterns,
+            orphans: orphans.into_iter().map(|p| p.pattern).collect(),
+            hot_addresses,
+        }
+    }
+
+    /// Snapshot of all subscriptions (for diagnostics)
+    pub fn list(&self) -> Vec<Subscription> {
+        self.subscriptions
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
     }
 
     /// Get subscription count
@@ -213,6 +546,94 @@ impl SubscriptionManager {
     }
 }
 
+/// Subscriptions sharing a pattern and how often they matched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PatternStats {
+    pub pattern: String,
+    /// Subscriptions with this pattern, across sessions
+    pub subscriptions: usize,
+    /// Messages delivered through them
+    pub hits: u64,
+}
+
+/// Fan-out of one address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressStats {
+    pub address: String,
+    /// Messages to the address that had subscribers
+    pub messages: u64,
+    /// Sessions they were delivered to, summed
+    pub deliveries: u64,
+}
+
+/// Delivery statistics of a [`SubscriptionManager`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubscriptionStats {
+    pub subscriptions: usize,
+    /// Sessions with at least one subscription
+    pub sessions: usize,
+    /// Patterns that matched at least once, most hits first
+    pub patterns: Vec<PatternStats>,
+    /// Patterns that never matched anything
+    pub orphans: Vec<String>,
+    /// Addresses with the most deliveries, busiest first
+    pub hot_addresses: Vec<AddressStats>,
+}
+
+impl SubscriptionStats {
+    /// As a value for a SNAPSHOT on [`STATS_ADDRESS`]
+    pub fn to_value(&self) -> Value {
+        let count = |n: u64| Value::Int(n.min(i64::MAX as u64) as i64);
+        let map = |entries: Vec<(&str, Value)>| {
+            Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect(),
+            )
+        };
+
+        map(vec![
+            ("subscriptions", count(self.subscriptions as u64)),
+            ("sessions", count(self.sessions as u64)),
+            (
+                "patterns",
+                Value::Array(
+                    self.patterns
+                        .iter()
+                        .map(|p| {
+                            map(vec![
+                                ("pattern", Value::String(p.pattern.clone())),
+                                ("subscriptions", count(p.subscriptions as u64)),
+                                ("hits", count(p.hits)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "orphans",
+                Value::Array(self.orphans.iter().cloned().map(Value::String).collect()),
+            ),
+            (
+                "hot_addresses",
+                Value::Array(
+                    self.hot_addresses
+                        .iter()
+                        .map(|a| {
+                            map(vec![
+                                ("address", Value::String(a.address.clone())),
+                                ("messages", count(a.messages)),
+                                ("deliveries", count(a.deliveries)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
 impl Default for SubscriptionManager {
     fn default() -> Self {
         Self::new()
@@ -387,6 +808,124 @@ mod tests {
         assert!(subscribers.contains(&"session2".to_string()));
     }
 
+    #[test]
+    fn test_origin_filter() {
+        let manager = SubscriptionManager::new();
+        manager.add(
+            Subscription::new(
+                1,
+                "native-only".to_string(),
+                "/sensors/**",
+                vec![],
+                SubscribeOptions {
+                    origins: Some(vec!["clasp".to_string()]),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        );
+        manager.add(
+            Subscription::new(
+                1,
+                "audit".to_string(),
+                "/sensors/**",
+                vec![],
+                SubscribeOptions {
+                    envelope: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        );
+
+        let mqtt = Origin::new("mqtt").with_remote("sensor-7");
+        let recipients = manager.find_recipients("/sensors/temp", None, &mqtt);
+        assert_eq!(
+            recipients,
+            vec![Recipient {
+                session_id: "audit".to_string(),
+                envelope: true,
+                qos: None,
+                credits: None,
+            }]
+        );
+
+        let mut native = manager.find_recipients("/sensors/temp", None, &Origin::native());
+        native.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        assert_eq!(native.len(), 2);
+        assert_eq!(native[1].session_id, "native-only");
+        assert!(!native[1].envelope);
+
+        let (immediate, batched) = manager.find_param_subscribers("/sensors/temp", &mqtt);
+        assert_eq!(immediate.len(), 1);
+        assert!(batched.is_empty());
+
+        // System lookups ignore origin filters
+        assert_eq!(manager.find_subscribers("/sensors/temp", None).len(), 2);
+    }
+
+    #[test]
+    fn test_qos_override() {
+        let manager = SubscriptionManager::new();
+        let subscribe = |session: &str, id: u32, pattern: &str, qos: Option<QoS>| {
+            manager.add(
+                Subscription::new(
+                    id,
+                    session.to_string(),
+                    pattern,
+                    vec![],
+                    SubscribeOptions {
+                        qos,
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+            );
+        };
+        subscribe("monitor", 1, "/mixer/**", Some(QoS::Fire));
+        subscribe("monitor", 2, "/mixer/*/gain", Some(QoS::Confirm));
+        subscribe("console", 1, "/mixer/**", Some(QoS::Fire));
+        subscribe("console", 2, "/mixer/*/gain", None);
+
+        let mut recipients = manager.find_recipients("/mixer/1/gain", None, &Origin::native());
+        recipients.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        // A subscription without an override keeps the publisher's QoS
+        assert_eq!(recipients[0].qos, None);
+        assert_eq!(recipients[1].qos, Some(QoS::Confirm));
+
+        let (immediate, _) = manager.find_param_subscribers("/mixer/1/mute", &Origin::native());
+        assert!(immediate.iter().all(|r| r.qos == Some(QoS::Fire)));
+    }
+
+    #[test]
+    fn test_transfer_session() {
+        let manager = SubscriptionManager::new();
+        for (id, pattern) in [(1, "/kiosk/**"), (2, "/lights/*")] {
+            manager.add(
+                Subscription::new(
+                    id,
+                    "old".to_string(),
+                    pattern,
+                    vec![],
+                    SubscribeOptions::default(),
+                )
+                .unwrap(),
+            );
+        }
+
+        let mut moved = manager.transfer_session(&"old".to_string(), &"new".to_string());
+        moved.sort();
+        assert_eq!(moved, vec![1, 2]);
+        assert_eq!(manager.len(), 2);
+        assert_eq!(
+            manager.find_subscribers("/kiosk/screen", None),
+            vec!["new".to_string()]
+        );
+
+        manager.remove_session(&"old".to_string());
+        assert_eq!(manager.len(), 2);
+    }
+
     #[test]
     fn test_remove_session_cleans_up_by_prefix() {
         let manager = SubscriptionManager::new();
@@ -440,4 +979,140 @@ mod tests {
         let subscribers = manager.find_subscribers("/other/foo", None);
         assert_eq!(subscribers.len(), 0);
     }
+
+    #[test]
+    fn test_credit_window() {
+        let manager = SubscriptionManager::new();
+        let subscribe = |session: &str, id: u32, credits: Option<u32>| {
+            manager.add(
+                Subscription::new(
+                    id,
+                    session.to_string(),
+                    "/camera/**",
+                    vec![],
+                    SubscribeOptions {
+                        credits,
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+            );
+        };
+        subscribe("slow", 1, Some(2));
+        subscribe("fast", 1, None);
+
+        let recipient = |session: &str| {
+            manager
+                .find_recipients("/camera/meta", None, &Origin::native())
+                .into_iter()
+                .find(|r| r.session_id == session)
+                .unwrap()
+        };
+        let slow = recipient("slow");
+        assert!(slow.take_credit());
+        assert!(slow.take_credit());
+        assert!(!slow.take_credit());
+        assert!(recipient("fast").take_credit());
+
+        // Credits are shared with later lookups, and granted by id
+        assert!(!recipient("slow").take_credit());
+        assert!(manager.grant(&"slow".to_string(), 1, 1));
+        assert!(recipient("slow").take_credit());
+        assert!(!manager.grant(&"fast".to_string(), 1, 1));
+        assert!(!manager.grant(&"slow".to_string(), 9, 1));
+
+        // An unmetered subscription of the same session lifts flow control
+        subscribe("slow", 2, None);
+        assert!(recipient("slow").credits.is_none());
+    }
+
+    #[test]
+    fn test_stats() {
+        let manager = SubscriptionManager::new();
+        let subscribe = |session: &str, id: u32, pattern: &str| {
+            manager.add(
+                Subscription::new(
+                    id,
+                    session.to_string(),
+                    pattern,
+                    vec![],
+                    SubscribeOptions::default(),
+                )
+                .unwrap(),
+            );
+        };
+        subscribe("a", 1, "/mixer/**");
+        subscribe("b", 1, "/mixer/**");
+        subscribe("b", 2, "/mixer/1/gain");
+        subscribe("b", 3, "/unused/**");
+
+        let origin = Origin::native();
+        for _ in 0..3 {
+            manager.find_recipients("/mixer/1/gain", None, &origin);
+        }
+        manager.find_recipients("/mixer/2/gain", None, &origin);
+        manager.find_recipients("/nobody/listens", None, &origin);
+
+        let stats = manager.stats(STATS_HOT_ADDRESSES);
+        assert_eq!(stats.subscriptions, 4);
+        assert_eq!(stats.sessions, 2);
+        assert_eq!(
+            stats.patterns,
+            vec![
+                PatternStats {
+                    pattern: "/mixer/**".to_string(),
+                    subscriptions: 2,
+                    hits: 8,
+                },
+                PatternStats {
+                    pattern: "/mixer/1/gain".to_string(),
+                    subscriptions: 1,
+                    hits: 3,
+                },
+            ]
+        );
+        assert_eq!(stats.orphans, vec!["/unused/**".to_string()]);
+        assert_eq!(
+            stats.hot_addresses,
+            vec![
+                AddressStats {
+                    address: "/mixer/1/gain".to_string(),
+                    messages: 3,
+                    deliveries: 6,
+                },
+                AddressStats {
+                    address: "/mixer/2/gain".to_string(),
+                    messages: 1,
+                    deliveries: 2,
+                },
+            ]
+        );
+        assert_eq!(manager.stats(1).hot_addresses.len(), 1);
+    }
+
+    #[test]
+    fn test_hot_addresses_bounded() {
+        let manager = SubscriptionManager::new();
+        manager.add(
+            Subscription::new(
+                1,
+                "a".to_string(),
+                "/**",
+                vec![],
+                SubscribeOptions::default(),
+            )
+            .unwrap(),
+        );
+        let origin = Origin::native();
+        for _ in 0..5 {
+            manager.find_recipients("/busy", None, &origin);
+        }
+        for i in 0..HOT_ADDRESS_SLOTS * 2 {
+            manager.find_recipients(&format!("/once/{}", i), None, &origin);
+        }
+
+        let stats = manager.stats(usize::MAX);
+        assert_eq!(stats.hot_addresses.len(), HOT_ADDRESS_SLOTS);
+        assert_eq!(stats.hot_addresses[0].address, "/busy");
+    }
 }
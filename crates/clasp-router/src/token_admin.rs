@@ -0,0 +1,377 @@
+//! Runtime token management over the protocol
+//!
+//! Clients with `admin` scope on `/$sys/tokens/**` can manage the router's
+//! [`CpskValidator`] without a restart:
+//!
+//! | Message | Effect |
+//! |---------|--------|
+//! | `GET /$sys/tokens` | SNAPSHOT of all tokens |
+//! | `GET /$sys/tokens/<token>` | SNAPSHOT of one token |
+//! | `SET /$sys/tokens/<token> <scopes>` | Add or replace a token |
+//! | `SET /$sys/tokens/<token> null` | Revoke a token and disconnect its sessions |
+//!
+//! `<scopes>` is either a scope string (`"read:/**,write:/lights/**"`), an
+//! array of scope strings, or a map with `scopes`, and optional `subject` and
+//! `expires_in` (seconds, or a duration like `"4h"`).
+//!
+//! Device public keys ([`DeviceKeyValidator`]) are managed the same way under
+//! `/$sys/keys/<public-key-hex>`.
+//!
+//! When the router has a token file ([`Router::with_token_file`]), changes are
+//! written back to it. Tokens with an expiry are not persisted, so a guest
+//! token never outlives its expiry across a restart.
+//!
+//! [`Router::with_token_file`]: crate::Router::with_token_file
+
+use clasp_core::identity::proof_public_key;
+use clasp_core::security::{parse_duration, parse_scopes, to_unix_timestamp};
+use clasp_core::{
+    CpskValidator, DeviceKeyValidator, ParamValue, Scope, SnapshotMessage, TokenInfo, Value,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::error::{Result, RouterError};
+
+/// Address prefix for token management
+pub const TOKENS_PREFIX: &str = "/$sys/tokens";
+
+/// Address prefix for device key management
+pub const KEYS_PREFIX: &str = "/$sys/keys";
+
+/// A validator whose credentials can be managed at runtime and kept in a
+/// token file
+pub trait CredentialStore {
+    /// Address prefix the credentials are managed under
+    const PREFIX: &'static str;
+
+    /// Add or replace a credential
+    fn register_credential(&self, id: &str, info: TokenInfo) -> Result<()>;
+
+    /// Remove a credential; false if it wasn't registered
+    fn revoke_credential(&self, id: &str) -> bool;
+
+    /// The info registered for a credential
+    fn credential(&self, id: &str) -> Option<TokenInfo>;
+
+    /// All credentials with their info
+    fn credentials(&self) -> Vec<(String, TokenInfo)>;
+
+    /// Whether a session that presented `token` was authenticated by the
+    /// credential `id`
+    fn authenticated_by(token: &str, id: &str) -> bool;
+}
+
+impl CredentialStore for CpskValidator {
+    const PREFIX: &'static str = TOKENS_PREFIX;
+
+    fn register_credential(&self, id: &str, info: TokenInfo) -> Result<()> {
+        if !id.starts_with(CpskValidator::PREFIX) {
+            return Err(RouterError::InvalidMessage(format!(
+                "Token must start with '{}'",
+                CpskValidator::PREFIX
+            )));
+        }
+        self.register(id.to_string(), info);
+        Ok(())
+    }
+
+    fn revoke_credential(&self, id: &str) -> bool {
+        self.revoke(id)
+    }
+
+    fn credential(&self, id: &str) -> Option<TokenInfo> {
+        self.get(id)
+    }
+
+    fn credentials(&self) -> Vec<(String, TokenInfo)> {
+        self.entries()
+    }
+
+    fn authenticated_by(token: &str, id: &str) -> bool {
+        token == id
+    }
+}
+
+impl CredentialStore for DeviceKeyValidator {
+    const PREFIX: &'static str = KEYS_PREFIX;
+
+    fn register_credential(&self, id: &str, info: TokenInfo) -> Result<()> {
+        self.register(id, info)
+            .map_err(|e| RouterError::InvalidMessage(e.to_string()))
+    }
+
+    fn revoke_credential(&self, id: &str) -> bool {
+        self.revoke(id)
+    }
+
+    fn credential(&self, id: &str) -> Option<TokenInfo> {
+        self.get(id)
+    }
+
+    fn credentials(&self) -> Vec<(String, TokenInfo)> {
+        self.entries()
+    }
+
+    fn authenticated_by(token: &str, id: &str) -> bool {
+        proof_public_key(token).is_some_and(|key| key.eq_ignore_ascii_case(id))
+    }
+}
+
+/// Whether `address` is a token management address
+pub fn is_token_address(address: &str) -> bool {
+    is_admin_address(TOKENS_PREFIX, address)
+}
+
+/// Whether `address` is a device key management address
+pub fn is_key_address(address: &str) -> bool {
+    is_admin_address(KEYS_PREFIX, address)
+}
+
+fn is_admin_address(prefix: &str, address: &str) -> bool {
+    address == prefix
+        || address
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Extract the credential from `<prefix>/<credential>`
+pub(crate) fn credential_from_address<'a>(prefix: &str, address: &'a str) -> Option<&'a str> {
+    address
+        .strip_prefix(prefix)?
+        .strip_prefix('/')
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// Build the token info for a SET value
+pub(crate) fn token_info_from_value(token: &str, value: &Value) -> Result<TokenInfo> {
+    let invalid = |msg: String| RouterError::InvalidMessage(msg);
+    let scopes_from = |value: &Value| -> Result<Vec<Scope>> {
+        match value {
+            Value::String(s) => parse_scopes(s).map_err(|e| invalid(e.to_string())),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| {
+                    let s = item
+                        .as_str()
+                        .ok_or_else(|| invalid("scopes must be strings".to_string()))?;
+                    Scope::parse(s).map_err(|e| invalid(e.to_string()))
+                })
+                .collect(),
+            _ => Err(invalid(
+                "scopes must be a string or an array of strings".to_string(),
+            )),
+        }
+    };
+
+    match value {
+        Value::Map(map) => {
+            let scopes = scopes_from(
+                map.get("scopes")
+                    .ok_or_else(|| invalid("missing 'scopes'".to_string()))?,
+            )?;
+            let mut info = TokenInfo::new(token.to_string(), scopes);
+            if let Some(subject) = map.get("subject").and_then(Value::as_str) {
+                info = info.with_subject(subject);
+            }
+            match map.get("expires_in") {
+                None | Some(Value::Null) => {}
+                Some(Value::String(s)) => {
+                    let duration = parse_duration(s).map_err(|e| invalid(e.to_string()))?;
+                    info = info.with_expires_in(duration);
+                }
+                Some(other) => {
+                    let secs = other
+                        .as_i64()
+                        .filter(|secs| *secs > 0)
+                        .ok_or_else(|| invalid("invalid 'expires_in'".to_string()))?;
+                    info = info.with_expires_in(Duration::from_secs(secs as u64));
+                }
+            }
+            Ok(info)
+        }
+        other => Ok(TokenInfo::new(token.to_string(), scopes_from(other)?)),
+    }
+}
+
+/// Describe a token as a map value (scopes, subject, expires_at)
+fn token_value(info: &TokenInfo) -> Value {
+    let mut map = HashMap::new();
+    map.insert(
+        "scopes".to_string(),
+        Value::Array(
+            info.scopes
+                .iter()
+                .map(|scope| Value::String(scope.to_string()))
+                .collect(),
+        ),
+    );
+    if let Some(subject) = &info.subject {
+        map.insert("subject".to_string(), Value::String(subject.clone()));
+    }
+    if let Some(expires_at) = info.expires_at {
+        map.insert(
+            "expires_at".to_string(),
+            Value::Int(to_unix_timestamp(expires_at) as i64),
+        );
+    }
+    Value::Map(map)
+}
+
+/// Snapshot of the credentials selected by a GET address
+pub(crate) fn token_snapshot<S: CredentialStore>(store: &S, address: &str) -> SnapshotMessage {
+    let mut entries = match credential_from_address(S::PREFIX, address) {
+        Some(id) => store
+            .credential(id)
+            .map(|info| vec![(id.to_string(), info)])
+            .unwrap_or_default(),
+        None => store.credentials(),
+    };
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    SnapshotMessage {
+        params: entries
+            .into_iter()
+            .map(|(id, info)| ParamValue {
+                address: format!("{}/{}", S::PREFIX, id),
+                value: token_value(&info),
+                revision: 0,
+                writer: None,
+                timestamp: None,
+            })
+            .collect(),
+        correlation_id: None,
+        page: None,
+        sync: None,
+    }
+}
+
+/// Load tokens from a token file into `validator`
+///
+/// One token per line, optionally followed by comma-separated scopes
+/// (`cpsk_... read:/**,write:/lights/**`). A token without scopes gets
+/// `admin:/**`. Blank lines and `#` comments are ignored. Returns the number
+/// of tokens loaded.
+///
+/// Device key files use the same format with a public key in place of the
+/// token.
+pub fn load_token_file<S: CredentialStore>(path: &Path, validator: &S) -> Result<usize> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut count = 0;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (token, scopes) = match line.split_once(' ') {
+            Some((token, scopes)) => (token, scopes.trim()),
+            None => (line, "admin:/**"),
+        };
+        let scopes = parse_scopes(scopes)
+            .map_err(|e| RouterError::Config(format!("invalid scopes for token: {}", e)))?;
+        validator.register_credential(token, TokenInfo::new(token.to_string(), scopes))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Write the validator's tokens back to a token file
+///
+/// Tokens with an expiry are skipped. The file is replaced atomically.
+pub fn save_token_file<S: CredentialStore>(path: &Path, validator: &S) -> Result<()> {
+    let mut entries: Vec<(String, TokenInfo)> = validator
+        .credentials()
+        .into_iter()
+        .filter(|(_, info)| info.expires_at.is_none())
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut contents = format!(
+        "# CLASP credentials (managed by the router; edits at runtime via {})\n",
+        S::PREFIX
+    );
+    for (token, info) in entries {
+        let scopes: Vec<String> = info.scopes.iter().map(|s| s.to_string()).collect();
+        contents.push_str(&format!("{} {}\n", token, scopes.join(",")));
+    }
+
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_addresses() {
+        assert!(is_token_address("/$sys/tokens"));
+        assert!(is_token_address("/$sys/tokens/cpsk_abc"));
+        assert!(!is_token_address("/$sys/tokenset"));
+        assert_eq!(
+            credential_from_address(TOKENS_PREFIX, "/$sys/tokens/cpsk_abc"),
+            Some("cpsk_abc")
+        );
+        assert_eq!(credential_from_address(TOKENS_PREFIX, "/$sys/tokens"), None);
+        assert_eq!(
+            credential_from_address(TOKENS_PREFIX, "/$sys/tokens/a/b"),
+            None
+        );
+        assert!(is_key_address("/$sys/keys/abcd"));
+        assert!(!is_key_address("/$sys/tokens/abcd"));
+    }
+
+    #[test]
+    fn test_token_info_from_value() {
+        let info =
+            token_info_from_value("cpsk_a", &Value::String("read:/**,write:/lights/**".into()))
+                .unwrap();
+        assert_eq!(info.scopes.len(), 2);
+        assert!(info.expires_at.is_none());
+
+        let mut map = HashMap::new();
+        map.insert(
+            "scopes".to_string(),
+            Value::Array(vec![Value::String("write:/lights/**".into())]),
+        );
+        map.insert("subject".to_string(), Value::String("guest".into()));
+        map.insert("expires_in".to_string(), Value::String("2h".into()));
+        let info = token_info_from_value("cpsk_b", &Value::Map(map)).unwrap();
+        assert_eq!(info.subject.as_deref(), Some("guest"));
+        assert!(info.expires_at.is_some());
+
+        assert!(token_info_from_value("cpsk_c", &Value::Int(1)).is_err());
+        assert!(token_info_from_value("cpsk_c", &Value::String("bogus".into())).is_err());
+    }
+
+    #[test]
+    fn test_token_file_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("clasp-tokens-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tokens.txt");
+        std::fs::write(
+            &path,
+            "# comment\ncpsk_admin\ncpsk_lights write:/lights/**, read:/**\n",
+        )
+        .unwrap();
+
+        let validator = CpskValidator::new();
+        assert_eq!(load_token_file(&path, &validator).unwrap(), 2);
+        validator.register(
+            "cpsk_guest".into(),
+            TokenInfo::new("cpsk_guest".into(), vec![Scope::parse("read:/**").unwrap()])
+                .with_expires_in(Duration::from_secs(60)),
+        );
+        save_token_file(&path, &validator).unwrap();
+
+        let reloaded = CpskValidator::new();
+        assert_eq!(load_token_file(&path, &reloaded).unwrap(), 2);
+        assert!(!reloaded.exists("cpsk_guest"));
+        assert_eq!(reloaded.get("cpsk_lights").unwrap().scopes.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+//! Wildcard writes
+//!
+//! Blackout-style operations ("every layer's opacity to 0") otherwise need
+//! the client to know and enumerate every address. With [`WildcardWrites`]
+//! enabled, a SET or PUBLISH to a pattern such as
+//! `/lumen/scene/0/layer/*/opacity` is expanded against the retained
+//! addresses it matches, and each match is written individually, exactly as
+//! if the client had sent one message per address: subscribers see plain
+//! SETs/PUBLISHes on concrete addresses with their own revisions.
+//!
+//! Expansion only reaches addresses that already hold a value; nothing new
+//! is created. A pattern matching more than [`WildcardWrites::max_matches`]
+//! addresses is rejected as a whole with ERROR 504, and while wildcard
+//! writes are disabled (the default) writes to patterns are rejected with
+//! ERROR 202.
+
+use clasp_core::{ErrorCode, ErrorMessage};
+
+use crate::state::RouterState;
+
+/// Wildcard write configuration (off by default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WildcardWrites {
+    /// Most addresses one write may expand to (0 = disabled)
+    pub max_matches: usize,
+}
+
+impl WildcardWrites {
+    /// Allow wildcard writes expanding to at most `max_matches` addresses
+    pub fn new(max_matches: usize) -> Self {
+        Self { max_matches }
+    }
+
+    /// Reject writes to patterns
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_matches > 0
+    }
+
+    /// The retained addresses a write to `pattern` applies to, in order
+    // The error is sent as-is as the reply, so it isn't worth boxing
+    #[allow(clippy::result_large_err)]
+    pub fn expand(&self, pattern: &str, state: &RouterState) -> Result<Vec<String>, ErrorMessage> {
+        if !self.is_enabled() {
+            return Err(
+                ErrorMessage::new(ErrorCode::PatternError, "Wildcard writes are disabled")
+                    .with_address(pattern),
+            );
+        }
+
+        let mut addresses: Vec<String> = state
+            .get_matching(pattern)
+            .into_iter()
+            .map(|(address, _)| address)
+            .filter(|address| !address.starts_with("/$sys/"))
+            .collect();
+        if addresses.len() > self.max_matches {
+            return Err(ErrorMessage::new(
+                ErrorCode::LimitExceeded,
+                format!(
+                    "Pattern matches {} addresses (limit {})",
+                    addresses.len(),
+                    self.max_matches
+                ),
+            )
+            .with_address(pattern));
+        }
+        addresses.sort();
+        Ok(addresses)
+    }
+}
+
+/// Whether a write address is a pattern
+pub fn is_wildcard(address: &str) -> bool {
+    address.contains('*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clasp_core::Value;
+
+    fn state_with(addresses: &[&str]) -> RouterState {
+        let state = RouterState::new();
+        for address in addresses {
+            state
+                .set(
+                    address,
+                    Value::Float(1.0),
+                    &"writer".to_string(),
+                    None,
+                    false,
+                    false,
+                )
+                .unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_expand() {
+        let state = state_with(&[
+            "/lumen/scene/0/layer/2/opacity",
+            "/lumen/scene/0/layer/1/opacity",
+            "/lumen/scene/0/layer/1/blend",
+            "/lumen/scene/1/layer/1/opacity",
+        ]);
+
+        let writes = WildcardWrites::new(8);
+        assert_eq!(
+            writes
+                .expand("/lumen/scene/0/layer/*/opacity", &state)
+                .unwrap(),
+            vec![
+                "/lumen/scene/0/layer/1/opacity",
+                "/lumen/scene/0/layer/2/opacity"
+            ]
+        );
+        assert!(writes.expand("/other/*", &state).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_and_cap() {
+        let state = state_with(&["/a/1", "/a/2", "/a/3"]);
+
+        let error = WildcardWrites::disabled()
+            .expand("/a/*", &state)
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::PatternError as u16);
+
+        let error = WildcardWrites::new(2).expand("/a/*", &state).unwrap_err();
+        assert_eq!(error.code, ErrorCode::LimitExceeded as u16);
+        assert_eq!(error.address.as_deref(), Some("/a/*"));
+
+        assert_eq!(
+            WildcardWrites::new(3).expand("/a/*", &state).unwrap().len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_is_wildcard() {
+        assert!(is_wildcard("/a/*/b"));
+        assert!(is_wildcard("/a/**"));
+        assert!(is_wildcard("/zone5*"));
+        assert!(!is_wildcard("/a/b"));
+    }
+}
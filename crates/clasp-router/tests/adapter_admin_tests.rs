@@ -0,0 +1,278 @@
+//! Runtime adapter management tests (`/$sys/adapters`)
+
+#![cfg(all(feature = "osc-server", feature = "websocket"))]
+
+use clasp_client::Clasp;
+use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo, Value};
+use clasp_router::{
+    AdapterConfig, AdapterKind, AdapterStatus, OscServerConfig, Router, RouterConfig,
+};
+use clasp_test_utils::{find_available_port, find_available_udp_port, wait_for};
+use rosc::{OscMessage, OscPacket, OscType};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const ADMIN: &str = "cpsk_admin";
+const WRITER: &str = "cpsk_writer";
+
+/// Serve WebSocket on a free port; returns its URL
+async fn serve(router: &Arc<Router>) -> String {
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    format!("ws://127.0.0.1:{}", port)
+}
+
+fn authenticated_router() -> Arc<Router> {
+    let validator = CpskValidator::new();
+    validator.register(
+        ADMIN.to_string(),
+        TokenInfo::new(ADMIN.to_string(), vec![Scope::parse("admin:/**").unwrap()]),
+    );
+    validator.register(
+        WRITER.to_string(),
+        TokenInfo::new(WRITER.to_string(), vec![Scope::parse("write:/**").unwrap()]),
+    );
+    Arc::new(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            ..Default::default()
+        })
+        .with_validator(validator),
+    )
+}
+
+async fn connect(url: &str, token: &str) -> Clasp {
+    Clasp::builder(url)
+        .token(token)
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// Send `value` to `/synth/volume` until the router stores it under `/osc`
+async fn osc_reaches(router: &Router, port: u16, value: f32) -> bool {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let packet = rosc::encoder::encode(&OscPacket::Message(OscMessage {
+        addr: "/synth/volume".to_string(),
+        args: vec![OscType::Float(value)],
+    }))
+    .unwrap();
+    wait_for(
+        || {
+            let socket = &socket;
+            let packet = &packet;
+            async move {
+                let _ = socket.send_to(packet, ("127.0.0.1", port)).await;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                router
+                    .state()
+                    .get("/osc/synth/volume")
+                    .is_some_and(|v| v.as_f64() == Some(value as f64))
+            }
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(2),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_osc_adapter_started_and_stopped_at_runtime() {
+    let router = Arc::new(Router::default());
+    let url = serve(&router).await;
+    let client = Clasp::connect_to(&url).await.unwrap();
+
+    let port = find_available_udp_port();
+    router
+        .start_adapter(AdapterConfig::Osc(OscServerConfig {
+            bind_addr: format!("127.0.0.1:{}", port),
+            ..Default::default()
+        }))
+        .await
+        .unwrap();
+    assert!(osc_reaches(&router, port, 0.5).await);
+    assert_eq!(router.session_count(), 2);
+    assert_eq!(router.running_adapters().len(), 1);
+    assert_eq!(
+        router.health().adapters.get("OSC"),
+        Some(&AdapterStatus::Running)
+    );
+
+    // Stopping ends the OSC peer's session and frees the port
+    assert!(router.stop_adapter(AdapterKind::Osc).await);
+    assert!(!router.stop_adapter(AdapterKind::Osc).await);
+    assert_eq!(router.session_count(), 1);
+    assert!(router.running_adapters().is_empty());
+    assert_eq!(
+        router.health().adapters.get("OSC"),
+        Some(&AdapterStatus::Stopped)
+    );
+    drop(UdpSocket::bind(("127.0.0.1", port)).await.unwrap());
+
+    // The WebSocket side never stopped
+    assert!(client.is_connected());
+    client.set("/mixer/1/gain", 0.75).await.unwrap();
+    assert_eq!(
+        client.get("/mixer/1/gain").await.unwrap(),
+        Value::Float(0.75)
+    );
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_admin_manages_adapters_over_protocol() {
+    let router = authenticated_router();
+    let url = serve(&router).await;
+    let admin = connect(&url, ADMIN).await;
+
+    let port = find_available_udp_port();
+    admin
+        .set(
+            "/$sys/adapters/osc",
+            Value::String(format!("127.0.0.1:{}", port)),
+        )
+        .await
+        .unwrap();
+    assert!(osc_reaches(&router, port, 0.25).await);
+
+    let config = admin.get("/$sys/adapters/osc").await.unwrap();
+    let Value::Map(config) = config else {
+        panic!("expected adapter config map, got {:?}", config);
+    };
+    assert_eq!(
+        config.get("bind_addr"),
+        Some(&Value::String(format!("127.0.0.1:{}", port)))
+    );
+
+    // Setting it again moves the adapter, releasing the old port
+    let moved = find_available_udp_port();
+    let mut fields = std::collections::HashMap::new();
+    fields.insert(
+        "bind_addr".to_string(),
+        Value::String(format!("127.0.0.1:{}", moved)),
+    );
+    admin
+        .set("/$sys/adapters/osc", Value::Map(fields))
+        .await
+        .unwrap();
+    assert!(osc_reaches(&router, moved, 0.5).await);
+    drop(UdpSocket::bind(("127.0.0.1", port)).await.unwrap());
+
+    admin.set("/$sys/adapters/osc", Value::Null).await.unwrap();
+    assert!(
+        wait_for(
+            || async { router.running_adapters().is_empty() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert!(admin.is_connected());
+    admin.close().await;
+}
+
+#[tokio::test]
+async fn test_non_admin_cannot_manage_adapters() {
+    let router = authenticated_router();
+    let url = serve(&router).await;
+    let writer = connect(&url, WRITER).await;
+
+    let port = find_available_udp_port();
+    writer
+        .set(
+            "/$sys/adapters/osc",
+            Value::String(format!("127.0.0.1:{}", port)),
+        )
+        .await
+        .unwrap();
+    assert!(
+        wait_for(
+            || async { writer.last_error().is_some() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert_eq!(writer.last_error().unwrap().code, 301);
+    assert!(router.running_adapters().is_empty());
+    writer.close().await;
+}
+
+#[tokio::test]
+async fn test_bind_failure_reported() {
+    let router = Router::default();
+    let taken = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    let result = router
+        .start_adapter(AdapterConfig::Osc(OscServerConfig {
+            bind_addr: taken.local_addr().unwrap().to_string(),
+            ..Default::default()
+        }))
+        .await;
+    assert!(result.is_err());
+    assert!(router.running_adapters().is_empty());
+    assert!(matches!(
+        router.health().adapters.get("OSC"),
+        Some(AdapterStatus::Failed(_))
+    ));
+}
+
+#[cfg(feature = "mqtt-server")]
+#[tokio::test]
+async fn test_stopping_mqtt_disconnects_clients() {
+    use bytes::BytesMut;
+    use clasp_router::MqttServerConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let router = Router::default();
+    let port = find_available_port().await;
+    router
+        .start_adapter(AdapterConfig::Mqtt(MqttServerConfig {
+            bind_addr: format!("127.0.0.1:{}", port),
+            ..Default::default()
+        }))
+        .await
+        .unwrap();
+
+    let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut connect = BytesMut::new();
+    mqttbytes::v4::Connect::new("sensor-1")
+        .write(&mut connect)
+        .unwrap();
+    stream.write_all(&connect).await.unwrap();
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack).await.unwrap();
+    assert_eq!(router.session_count(), 1);
+
+    assert!(router.stop_adapter(AdapterKind::Mqtt).await);
+    let mut rest = Vec::new();
+    let closed = tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut rest)).await;
+    assert!(matches!(closed, Ok(Ok(0))));
+    assert!(
+        wait_for(
+            || async { router.session_count() == 0 },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert!(tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .is_ok());
+}
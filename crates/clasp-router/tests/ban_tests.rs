@@ -0,0 +1,174 @@
+//! Connection throttling and ban list tests (`/$sys/security`)
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo, Value};
+use clasp_router::{ConnectionGuard, Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+const ADMIN: &str = "cpsk_admin";
+const LOCALHOST: &str = "127.0.0.1";
+
+/// Start an authenticated router with an admin token and a read-only token;
+/// returns the router and its URL
+async fn start_router(guard: ConnectionGuard) -> (Arc<Router>, String) {
+    let validator = CpskValidator::new();
+    validator.register(
+        ADMIN.to_string(),
+        TokenInfo::new(ADMIN.to_string(), vec![Scope::parse("admin:/**").unwrap()]),
+    );
+    validator.register(
+        "cpsk_reader".to_string(),
+        TokenInfo::new(
+            "cpsk_reader".to_string(),
+            vec![Scope::parse("read:/**").unwrap()],
+        ),
+    );
+    let router = Arc::new(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            connection_guard: guard,
+            ..Default::default()
+        })
+        .with_validator(validator),
+    );
+
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    (router, format!("ws://127.0.0.1:{}", port))
+}
+
+async fn connect(url: &str, token: &str) -> clasp_client::Result<Clasp> {
+    Clasp::builder(url)
+        .token(token)
+        .reconnect(false)
+        .connect()
+        .await
+}
+
+#[tokio::test]
+async fn test_connection_attempts_throttled() {
+    let (router, url) =
+        start_router(ConnectionGuard::default().throttle(2, Duration::from_millis(500))).await;
+
+    let _first = connect(&url, ADMIN).await.unwrap();
+    let _second = connect(&url, ADMIN).await.unwrap();
+    assert!(connect(&url, ADMIN).await.is_err());
+    // Throttling is not a ban
+    assert!(!router.bans().is_banned(LOCALHOST.parse().unwrap()));
+
+    // The next window starts afresh
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert!(connect(&url, ADMIN).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_auth_failures_ban_ip() {
+    let (router, url) =
+        start_router(ConnectionGuard::default().ban_after(3, Duration::from_secs(60))).await;
+    let localhost: IpAddr = LOCALHOST.parse().unwrap();
+
+    for _ in 0..3 {
+        assert!(connect(&url, "cpsk_guess").await.is_err());
+    }
+    let ban = router.bans().get(localhost).expect("IP should be banned");
+    assert!(ban.expires.is_some());
+
+    // Valid credentials don't get a banned host in
+    assert!(connect(&url, ADMIN).await.is_err());
+
+    assert!(router.unban(localhost));
+    assert!(connect(&url, ADMIN).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_admin_manages_bans() {
+    let (_router, url) = start_router(ConnectionGuard::default()).await;
+    let admin = connect(&url, ADMIN).await.unwrap();
+    let events = ValueCollector::new();
+    admin
+        .subscribe("/$sys/security", events.callback_ref())
+        .await
+        .unwrap();
+
+    let address = "/$sys/security/bans/10.1.2.3";
+    let mut ban = std::collections::HashMap::new();
+    ban.insert("reason".to_string(), Value::String("scanner".into()));
+    ban.insert("duration".to_string(), Value::String("1h".into()));
+    admin.set_with_ack(address, Value::Map(ban)).await.unwrap();
+
+    let Value::Map(entry) = admin.get(address).await.unwrap() else {
+        panic!("expected a ban map");
+    };
+    assert_eq!(entry["reason"], Value::String("scanner".into()));
+    assert!(matches!(entry["expires_in"], Value::Int(secs) if secs > 3500));
+
+    admin.set_with_ack(address, Value::Null).await.unwrap();
+    assert!(matches!(
+        admin.set_with_ack(address, Value::Null).await,
+        Err(ClientError::InvalidAddress(_))
+    ));
+
+    assert!(
+        events.wait_for_count(2, Duration::from_secs(2)).await,
+        "ban events not published"
+    );
+    let reported: Vec<Value> = events
+        .values_for("/$sys/security")
+        .into_iter()
+        .map(|event| match event {
+            Value::Map(event) => event["event"].clone(),
+            other => panic!("expected an event map, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        reported,
+        vec![
+            Value::String("banned".into()),
+            Value::String("unbanned".into())
+        ]
+    );
+
+    // Malformed IPs and non-admins are rejected
+    assert!(matches!(
+        admin
+            .set_with_ack("/$sys/security/bans/not-an-ip", true)
+            .await,
+        Err(ClientError::InvalidAddress(_))
+    ));
+    let reader = connect(&url, "cpsk_reader").await.unwrap();
+    assert!(matches!(
+        reader.set_with_ack(address, true).await,
+        Err(ClientError::Forbidden(_))
+    ));
+
+    // Banning an IP closes its sessions
+    admin
+        .set_with_ack(&format!("/$sys/security/bans/{}", LOCALHOST), true)
+        .await
+        .unwrap();
+    assert!(
+        wait_for(
+            || async { !reader.is_connected() },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await,
+        "session from a banned IP was not closed"
+    );
+    assert!(connect(&url, ADMIN).await.is_err());
+}
@@ -0,0 +1,267 @@
+//! Batched delivery tests
+//!
+//! A raw WebSocket peer counts the frames the router actually sends, so the
+//! tests see SNAPSHOT batches rather than the client's per-address callbacks.
+
+use clasp_client::{Clasp, SubscribeOptions};
+use clasp_core::{
+    codec, HelloMessage, Message, PublishMessage, SignalType, SubscribeMessage, Value,
+};
+use clasp_router::{Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use clasp_transport::websocket::{WebSocketReceiver, WebSocketSender};
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+async fn start_router() -> String {
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let router = Router::new(RouterConfig::default());
+    tokio::spawn(async move { router.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    format!("ws://127.0.0.1:{}", port)
+}
+
+async fn connect(url: &str, name: &str) -> Clasp {
+    Clasp::builder(url)
+        .name(name)
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap()
+}
+
+fn batched(ms: u32) -> Option<SubscribeOptions> {
+    Some(SubscribeOptions {
+        batch: Some(ms),
+        ..Default::default()
+    })
+}
+
+/// A subscriber speaking raw frames
+struct Peer {
+    sender: WebSocketSender,
+    receiver: WebSocketReceiver,
+}
+
+impl Peer {
+    async fn connect(url: &str) -> Self {
+        let (sender, receiver) = WebSocketTransport::connect(url).await.unwrap();
+        let mut peer = Self { sender, receiver };
+        peer.send(Message::Hello(HelloMessage {
+            version: 3,
+            name: "dashboard".to_string(),
+            features: vec!["param".to_string(), "event".to_string()],
+            capabilities: None,
+            token: None,
+            since: None,
+        }))
+        .await;
+        peer.until(|msg| matches!(msg, Message::Welcome(_))).await;
+        peer
+    }
+
+    async fn send(&self, msg: Message) {
+        self.sender
+            .send(codec::encode(&msg).unwrap())
+            .await
+            .unwrap();
+    }
+
+    async fn recv(&mut self, within: Duration) -> Option<Message> {
+        loop {
+            match timeout(within, self.receiver.recv()).await {
+                Ok(Some(TransportEvent::Data(data))) => {
+                    return Some(codec::decode(&data).unwrap().0)
+                }
+                Ok(Some(TransportEvent::Connected)) => continue,
+                _ => return None,
+            }
+        }
+    }
+
+    async fn until(&mut self, done: impl Fn(&Message) -> bool) -> Message {
+        loop {
+            let msg = self
+                .recv(Duration::from_secs(2))
+                .await
+                .expect("timed out waiting for a frame");
+            if done(&msg) {
+                return msg;
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, id: u32, pattern: &str, options: Option<SubscribeOptions>) {
+        self.send(Message::Subscribe(SubscribeMessage {
+            id,
+            pattern: pattern.to_string(),
+            types: vec![],
+            options,
+            correlation_id: None,
+        }))
+        .await;
+        self.send(Message::Ping).await;
+        self.until(|msg| matches!(msg, Message::Pong)).await;
+    }
+
+    /// Everything received until the connection has been quiet for `idle`
+    async fn drain(&mut self, idle: Duration) -> Vec<Message> {
+        let mut frames = Vec::new();
+        while let Some(msg) = self.recv(idle).await {
+            frames.push(msg);
+        }
+        frames
+    }
+}
+
+#[tokio::test]
+async fn test_batched_subscription_coalesces() {
+    let url = start_router().await;
+    let mut peer = Peer::connect(&url).await;
+    peer.subscribe(1, "/**", batched(50)).await;
+
+    let writer = connect(&url, "fader-bank").await;
+    for i in 0..40 {
+        writer
+            .set(&format!("/mixer/{}/level", i % 4), i as f64 / 40.0)
+            .await
+            .unwrap();
+    }
+
+    let frames = peer.drain(Duration::from_millis(300)).await;
+    let params: Vec<_> = frames
+        .iter()
+        .flat_map(|msg| match msg {
+            Message::Snapshot(snapshot) => snapshot.params.clone(),
+            Message::Set(set) => panic!("unbatched SET for {}", set.address),
+            _ => vec![],
+        })
+        .collect();
+
+    // Latest value per address, no matter how the window split the writes
+    assert!(frames.len() < 40, "{} frames", frames.len());
+    for fader in 0..4 {
+        let address = format!("/mixer/{}/level", fader);
+        let last = params.iter().rev().find(|p| p.address == address).unwrap();
+        assert_eq!(last.value, Value::Float((36 + fader) as f64 / 40.0));
+    }
+    writer.close().await;
+}
+
+#[tokio::test]
+async fn test_unbatched_subscription_unchanged() {
+    let url = start_router().await;
+    let mut peer = Peer::connect(&url).await;
+    peer.subscribe(1, "/mixer/**", None).await;
+
+    let writer = connect(&url, "fader-bank").await;
+    for i in 0..10 {
+        writer.set("/mixer/0/level", i as f64).await.unwrap();
+    }
+
+    let frames = peer.drain(Duration::from_millis(200)).await;
+    let sets = frames
+        .iter()
+        .filter(|msg| matches!(msg, Message::Set(_)))
+        .count();
+    assert_eq!(sets, 10);
+    writer.close().await;
+}
+
+#[tokio::test]
+async fn test_immediate_subscription_wins() {
+    let url = start_router().await;
+    let mut peer = Peer::connect(&url).await;
+    // Same session: a batched catch-all and an immediate subscription
+    peer.subscribe(1, "/**", batched(50)).await;
+    peer.subscribe(2, "/cue/**", None).await;
+
+    let writer = connect(&url, "console").await;
+    writer.set("/cue/go", 1i64).await.unwrap();
+    writer.set("/mixer/0/level", 0.5).await.unwrap();
+
+    let frames = peer.drain(Duration::from_millis(300)).await;
+    assert!(frames
+        .iter()
+        .any(|msg| matches!(msg, Message::Set(set) if set.address == "/cue/go")));
+    assert!(frames.iter().any(|msg| matches!(
+        msg,
+        Message::Snapshot(s) if s.params.iter().any(|p| p.address == "/mixer/0/level")
+    )));
+    writer.close().await;
+}
+
+#[tokio::test]
+async fn test_events_are_not_batched() {
+    let url = start_router().await;
+    let mut peer = Peer::connect(&url).await;
+    peer.subscribe(1, "/**", batched(50)).await;
+
+    let writer = connect(&url, "console").await;
+    writer.emit("/cue/fire", Value::Int(3)).await.unwrap();
+
+    let msg = peer.until(|msg| matches!(msg, Message::Publish(_))).await;
+    assert!(matches!(
+        msg,
+        Message::Publish(PublishMessage {
+            signal: Some(SignalType::Event),
+            ..
+        })
+    ));
+    writer.close().await;
+}
+
+#[tokio::test]
+async fn test_client_batched_callbacks() {
+    let url = start_router().await;
+    let dashboard = connect(&url, "dashboard").await;
+    let collector = ValueCollector::new();
+    dashboard
+        .subscribe_with_options(
+            "/stage/**",
+            SubscribeOptions {
+                batch: Some(30),
+                ..Default::default()
+            },
+            collector.callback_ref(),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let writer = connect(&url, "console").await;
+    for i in 0..20 {
+        writer.set("/stage/dimmer", i as f64).await.unwrap();
+    }
+    writer.set("/stage/color", "amber").await.unwrap();
+
+    assert!(
+        wait_for(
+            || async {
+                collector.has_address("/stage/color")
+                    && collector.values_for("/stage/dimmer").last() == Some(&Value::Float(19.0))
+            },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    // Coalesced: far fewer callbacks than writes
+    assert!(collector.count() < 21, "{} callbacks", collector.count());
+
+    dashboard.close().await;
+    writer.close().await;
+}
@@ -0,0 +1,96 @@
+//! Memory budget tests
+//!
+//! Once retained state goes over the budget the router evicts params,
+//! lowest priority first, and reports each pass on `/$sys/evictions`.
+
+use clasp_core::Value;
+use clasp_router::{EvictionPriority, MemoryBudget, RouterConfig};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use std::time::Duration;
+
+const BUDGET: usize = 64 * 1024;
+
+#[tokio::test]
+async fn test_evictions_reported() {
+    let router = TestRouter::start_with_config(RouterConfig {
+        memory_budget: MemoryBudget::new(BUDGET)
+            .with_priority(EvictionPriority::new("/keep/**", 10)),
+        ..Default::default()
+    })
+    .await;
+
+    let operator = router.connect_client_named("Operator").await.unwrap();
+    let evictions = ValueCollector::new();
+    operator
+        .subscribe("/$sys/evictions", evictions.callback_ref())
+        .await
+        .unwrap();
+
+    let writer = router.connect_client_named("Writer").await.unwrap();
+    writer.set_confirmed("/keep/cue", 1).await.unwrap();
+
+    // A runaway publisher writing 1KB to ever-new addresses
+    for i in 0..200 {
+        writer
+            .set_confirmed(&format!("/runaway/{}", i), "x".repeat(1024))
+            .await
+            .unwrap();
+    }
+
+    assert!(
+        evictions.wait_for_count(1, Duration::from_secs(2)).await,
+        "no eviction reported"
+    );
+    let reports = evictions.values_for("/$sys/evictions");
+    let Value::Map(report) = &reports[0] else {
+        panic!("expected a map, got {:?}", reports[0]);
+    };
+    assert_eq!(report["budget"], Value::Int(BUDGET as i64));
+    assert!(matches!(report["usage"], Value::Int(usage) if usage > BUDGET as i64));
+    let Value::Array(addresses) = &report["addresses"] else {
+        panic!("expected evicted addresses");
+    };
+    assert!(!addresses.is_empty());
+    assert_eq!(addresses[0], Value::String("/runaway/0".to_string()));
+    assert!(!addresses.contains(&Value::String("/keep/cue".to_string())));
+
+    // The high-priority param and the most recent writes survive
+    let reader = router.connect_client_named("Reader").await.unwrap();
+    assert!(reader.get("/runaway/0").await.is_err());
+    assert_eq!(reader.get("/keep/cue").await.unwrap(), Value::Int(1));
+    assert_eq!(
+        reader.get("/runaway/199").await.unwrap(),
+        Value::String("x".repeat(1024))
+    );
+}
+
+#[tokio::test]
+async fn test_no_budget_no_evictions() {
+    let router = TestRouter::start().await;
+
+    let operator = router.connect_client_named("Operator").await.unwrap();
+    let evictions = ValueCollector::new();
+    operator
+        .subscribe("/$sys/evictions", evictions.callback_ref())
+        .await
+        .unwrap();
+
+    let writer = router.connect_client_named("Writer").await.unwrap();
+    for i in 0..100 {
+        writer
+            .set_confirmed(&format!("/data/{}", i), "x".repeat(1024))
+            .await
+            .unwrap();
+    }
+
+    assert!(
+        !evictions
+            .wait_for_count(1, Duration::from_millis(200))
+            .await
+    );
+    let reader = router.connect_client_named("Reader").await.unwrap();
+    assert_eq!(
+        reader.get("/data/0").await.unwrap(),
+        Value::String("x".repeat(1024))
+    );
+}
@@ -46,6 +46,8 @@ async fn test_bundle_atomic_execution() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             })
         })
         .collect();
@@ -108,6 +110,8 @@ async fn test_bundle_scheduled_execution() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     })];
 
     // Send scheduled bundle
@@ -171,6 +175,8 @@ async fn test_bundle_mixed_message_types() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
         Message::Publish(PublishMessage {
             address: "/mixed/event".to_string(),
@@ -263,6 +269,8 @@ async fn test_bundle_large_bundle() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             })
         })
         .collect();
@@ -334,6 +342,8 @@ async fn test_bundle_timestamp_precision() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     })];
 
     // Send scheduled bundle
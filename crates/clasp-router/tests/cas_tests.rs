@@ -0,0 +1,123 @@
+//! Compare-and-set tests
+//!
+//! A SET carrying a revision only applies if the param is still at it (0 =
+//! absent); otherwise the router answers with an ERROR holding the revision
+//! and value the param has now.
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{codec, ErrorCode, HelloMessage, Message, SetMessage, Value};
+use clasp_test_utils::TestRouter;
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_compare_and_set() {
+    let router = TestRouter::start().await;
+    let client = Clasp::connect_to(&router.url()).await.unwrap();
+
+    // 0 creates the param only if it doesn't exist yet
+    let rev = client
+        .compare_and_set("/doc/title", 0, "Draft")
+        .await
+        .unwrap();
+    let rev = client
+        .compare_and_set("/doc/title", rev, "Final")
+        .await
+        .unwrap();
+    assert_eq!(
+        client.get("/doc/title").await.unwrap(),
+        Value::from("Final")
+    );
+
+    let result = client.compare_and_set("/doc/title", 0, "Other").await;
+    match result {
+        Err(ClientError::RevisionConflict { revision, value }) => {
+            assert_eq!(revision, rev);
+            assert_eq!(value, Some(Value::from("Final")));
+        }
+        other => panic!("expected a revision conflict, got {:?}", other),
+    }
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_concurrent_editors_one_wins() {
+    let router = TestRouter::start().await;
+    let alice = Clasp::connect_to(&router.url()).await.unwrap();
+    let bob = Clasp::connect_to(&router.url()).await.unwrap();
+
+    let base = alice.set_confirmed("/doc/body", "hello").await.unwrap();
+
+    // Both edit from the same revision; only the first lands
+    alice
+        .compare_and_set("/doc/body", base, "hello alice")
+        .await
+        .unwrap();
+    let lost = bob.compare_and_set("/doc/body", base, "hello bob").await;
+    let Err(ClientError::RevisionConflict { revision, value }) = lost else {
+        panic!("expected a revision conflict, got {:?}", lost);
+    };
+    assert_eq!(value, Some(Value::from("hello alice")));
+
+    // Bob merges and retries against the revision he was told about
+    bob.compare_and_set("/doc/body", revision, "hello alice and bob")
+        .await
+        .unwrap();
+    assert_eq!(
+        alice.get("/doc/body").await.unwrap(),
+        Value::from("hello alice and bob")
+    );
+
+    alice.close().await;
+    bob.close().await;
+}
+
+#[tokio::test]
+async fn test_conflict_error_on_the_wire() {
+    let router = TestRouter::start().await;
+    let (sender, mut receiver) = WebSocketTransport::connect(&router.url()).await.unwrap();
+    let hello = Message::Hello(HelloMessage {
+        version: 2,
+        name: "Raw".to_string(),
+        features: vec!["param".to_string()],
+        capabilities: None,
+        token: None,
+        since: None,
+    });
+    sender.send(codec::encode(&hello).unwrap()).await.unwrap();
+
+    // Expecting revision 5 of a param that doesn't exist
+    let set = Message::Set(SetMessage {
+        address: "/doc/missing".to_string(),
+        value: Value::Int(1),
+        revision: Some(5),
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: Some(7),
+    });
+    sender.send(codec::encode(&set).unwrap()).await.unwrap();
+
+    let error = timeout(Duration::from_secs(5), async {
+        loop {
+            let Some(TransportEvent::Data(data)) = receiver.recv().await else {
+                continue;
+            };
+            if let (Message::Error(error), _) = codec::decode(&data).unwrap() {
+                return error;
+            }
+        }
+    })
+    .await
+    .expect("no ERROR arrived");
+
+    assert_eq!(error.error_code(), Some(ErrorCode::RevisionConflict));
+    assert_eq!(error.address.as_deref(), Some("/doc/missing"));
+    assert_eq!(error.correlation_id, Some(7));
+    assert_eq!(error.revision, Some(0));
+    assert_eq!(error.value, None);
+}
@@ -0,0 +1,239 @@
+//! Connection sharing tests
+//!
+//! Sub-clients multiplex their own sessions over the parent client's
+//! WebSocket.
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo, Value};
+use clasp_router::{Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_router(router: Router) -> (Arc<Router>, String) {
+    let router = Arc::new(router);
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    (router, format!("ws://127.0.0.1:{}", port))
+}
+
+async fn connect(url: &str, name: &str) -> Clasp {
+    Clasp::builder(url)
+        .name(name)
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn until(check: impl Fn() -> bool) -> bool {
+    wait_for(
+        || async { check() },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_sub_clients_get_own_sessions() {
+    let (router, url) = start_router(Router::new(RouterConfig::default())).await;
+
+    let app = connect(&url, "Show App").await;
+    let ui = app.sub_client("ui").await.unwrap();
+    let audio = app.sub_client("audio").await.unwrap();
+    assert!(ui.is_connected() && audio.is_connected());
+    assert_ne!(ui.channel(), audio.channel());
+
+    let mut names: Vec<String> = router.sessions().iter().map(|s| s.name.clone()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Show App", "audio", "ui"]);
+
+    let parent = app.session_id().unwrap();
+    for sub in [&ui, &audio] {
+        let session = router
+            .sessions()
+            .into_iter()
+            .find(|s| Some(&s.id) == sub.session_id().as_ref())
+            .unwrap();
+        assert_eq!(session.parent(), Some(&parent));
+    }
+
+    app.close().await;
+}
+
+#[tokio::test]
+async fn test_sub_clients_have_own_subscriptions() {
+    let (_router, url) = start_router(Router::new(RouterConfig::default())).await;
+
+    let app = connect(&url, "Show App").await;
+    let ui = app.sub_client("ui").await.unwrap();
+    let audio = app.sub_client("audio").await.unwrap();
+
+    let ui_values = ValueCollector::new();
+    ui.subscribe("/ui/**", ui_values.callback_ref())
+        .await
+        .unwrap();
+    let app_values = ValueCollector::new();
+    app.subscribe("/audio/**", app_values.callback_ref())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let console = connect(&url, "Console").await;
+    console.set("/ui/theme", "dark").await.unwrap();
+    audio.set("/audio/master", 0.8).await.unwrap();
+
+    assert!(until(|| ui_values.has_address("/ui/theme")).await);
+    assert!(until(|| app_values.has_address("/audio/master")).await);
+    assert!(!ui_values.has_address("/audio/master"));
+    assert!(!app_values.has_address("/ui/theme"));
+
+    // Each sub-client keeps its own param cache
+    assert_eq!(ui.cached("/ui/theme"), Some(Value::String("dark".into())));
+    assert_eq!(app.cached("/ui/theme"), None);
+
+    console.close().await;
+    app.close().await;
+}
+
+#[tokio::test]
+async fn test_sub_client_close() {
+    let (router, url) = start_router(Router::new(RouterConfig::default())).await;
+
+    let app = connect(&url, "Show App").await;
+    let ui = app.sub_client("ui").await.unwrap();
+    ui.subscribe("/ui/**", |_, _| {}).await.unwrap();
+    assert!(until(|| router.subscription_count() == 1).await);
+
+    ui.close().await;
+    assert!(until(|| router.session_count() == 1).await);
+    assert_eq!(router.subscription_count(), 0);
+    assert!(matches!(
+        ui.set("/ui/theme", "light").await,
+        Err(ClientError::NotConnected)
+    ));
+
+    // The shared connection is still usable
+    assert!(app.is_connected());
+    app.set("/app/ready", true).await.unwrap();
+    let again = app.sub_client("ui").await.unwrap();
+    assert!(again.is_connected());
+
+    app.close().await;
+}
+
+#[tokio::test]
+async fn test_closing_parent_closes_sub_clients() {
+    let (router, url) = start_router(Router::new(RouterConfig::default())).await;
+
+    let app = connect(&url, "Show App").await;
+    let ui = app.sub_client("ui").await.unwrap();
+    assert_eq!(router.session_count(), 2);
+
+    app.close().await;
+    assert!(!ui.is_connected());
+    assert!(until(|| router.sessions().iter().all(|s| s.parent().is_none())).await);
+}
+
+#[tokio::test]
+async fn test_sub_client_with_own_token() {
+    let validator = CpskValidator::new();
+    validator.register(
+        "cpsk_app".to_string(),
+        TokenInfo::new(
+            "cpsk_app".to_string(),
+            vec![Scope::parse("admin:/**").unwrap()],
+        ),
+    );
+    validator.register(
+        "cpsk_ui".to_string(),
+        TokenInfo::new(
+            "cpsk_ui".to_string(),
+            vec![Scope::parse("write:/ui/**").unwrap()],
+        ),
+    );
+    let (_router, url) = start_router(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            ..Default::default()
+        })
+        .with_validator(validator),
+    )
+    .await;
+
+    let app = Clasp::builder(&url)
+        .name("Show App")
+        .token("cpsk_app")
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap();
+
+    let ui = app.sub_client_with_token("ui", "cpsk_ui").await.unwrap();
+    ui.set_confirmed("/ui/theme", "dark").await.unwrap();
+    let denied = ui.set_confirmed("/audio/master", 0.5).await;
+    assert!(
+        matches!(denied, Err(ClientError::Forbidden(_))),
+        "{:?}",
+        denied
+    );
+
+    // A rejected sub-client doesn't take the connection down
+    let rejected = app.sub_client("anonymous").await;
+    assert!(
+        matches!(rejected, Err(ClientError::AuthFailed(_))),
+        "{:?}",
+        rejected.err()
+    );
+    assert!(app.is_connected());
+    assert!(ui.is_connected());
+
+    app.close().await;
+}
+
+#[tokio::test]
+async fn test_channel_limits() {
+    let (_router, url) = start_router(Router::new(RouterConfig {
+        max_channels: 1,
+        ..Default::default()
+    }))
+    .await;
+
+    let app = connect(&url, "Show App").await;
+    let _ui = app.sub_client("ui").await.unwrap();
+    let second = app.sub_client("audio").await;
+    assert!(
+        matches!(second, Err(ClientError::Server { code: 504, .. })),
+        "{:?}",
+        second.err()
+    );
+    app.close().await;
+
+    let (_router, url) = start_router(Router::new(RouterConfig {
+        max_channels: 0,
+        ..Default::default()
+    }))
+    .await;
+    let app = connect(&url, "Show App").await;
+    let refused = app.sub_client("ui").await;
+    assert!(
+        matches!(refused, Err(ClientError::Unsupported(_))),
+        "{:?}",
+        refused.err()
+    );
+    assert!(app.is_connected());
+    app.close().await;
+}
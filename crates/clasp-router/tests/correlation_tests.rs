@@ -0,0 +1,218 @@
+//! Correlation ID tests
+//!
+//! Replies to SET, GET and SUBSCRIBE echo the request's correlation id, so
+//! clients can match each ACK/ERROR/SNAPSHOT to the operation it answers.
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{codec, HelloMessage, Message, SetMessage, SubscribeMessage, Value};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use clasp_transport::{
+    websocket::{WebSocketReceiver, WebSocketSender},
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+async fn connect_raw(url: &str) -> (WebSocketSender, WebSocketReceiver) {
+    let (sender, mut receiver) = WebSocketTransport::connect(url).await.unwrap();
+
+    let hello = Message::Hello(HelloMessage {
+        version: 2,
+        name: "Raw".to_string(),
+        features: vec!["param".to_string()],
+        capabilities: None,
+        token: None,
+        since: None,
+    });
+    sender.send(codec::encode(&hello).unwrap()).await.unwrap();
+
+    let mut got_welcome = false;
+    let mut got_snapshot = false;
+    while !got_welcome || !got_snapshot {
+        match recv(&mut receiver).await {
+            Message::Welcome(_) => got_welcome = true,
+            Message::Snapshot(_) => got_snapshot = true,
+            _ => {}
+        }
+    }
+
+    (sender, receiver)
+}
+
+async fn recv(receiver: &mut WebSocketReceiver) -> Message {
+    loop {
+        match timeout(Duration::from_secs(2), receiver.recv()).await {
+            Ok(Some(TransportEvent::Data(data))) => return codec::decode(&data).unwrap().0,
+            Ok(Some(TransportEvent::Connected)) => continue,
+            other => panic!("expected a message, got {:?}", other.map(|_| ())),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_set_with_ack() {
+    let router = TestRouter::start().await;
+    let client = Clasp::connect_to(&router.url()).await.unwrap();
+
+    let (a, b, c) = tokio::join!(
+        client.set_with_ack("/corr/a", 1),
+        client.set_with_ack("/corr/b", 2),
+        client.set_with_ack("/corr/a", 3),
+    );
+    let (a, b, c) = (a.unwrap(), b.unwrap(), c.unwrap());
+
+    assert_eq!(a.address.as_deref(), Some("/corr/a"));
+    assert_eq!(b.address.as_deref(), Some("/corr/b"));
+    assert_eq!(c.address.as_deref(), Some("/corr/a"));
+    // Both writes to /corr/a were acknowledged with their own revision
+    assert_ne!(a.revision, c.revision);
+    assert_ne!(a.correlation_id, c.correlation_id);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_set_with_ack_typed_error() {
+    let router = TestRouter::start().await;
+    let owner = Clasp::connect_to(&router.url()).await.unwrap();
+    let other = Clasp::connect_to(&router.url()).await.unwrap();
+
+    owner.set_with_ack("/corr/locked", 1).await.unwrap();
+    owner.set_locked("/corr/locked", 1).await.unwrap();
+    owner.set_with_ack("/corr/sync", 0).await.unwrap();
+
+    let result = other.set_with_ack("/corr/locked", 2).await;
+    assert!(
+        matches!(result, Err(ClientError::Conflict(_))),
+        "{:?}",
+        result
+    );
+
+    owner.close().await;
+    other.close().await;
+}
+
+#[tokio::test]
+async fn test_get_unknown_address_fails_fast() {
+    let router = TestRouter::start().await;
+    let client = Clasp::connect_to(&router.url()).await.unwrap();
+
+    let result = timeout(Duration::from_secs(2), client.get("/corr/missing"))
+        .await
+        .expect("GET should be answered, not time out");
+    assert!(matches!(result, Err(ClientError::InvalidAddress(_))));
+
+    client.set_with_ack("/corr/present", 7).await.unwrap();
+    let other = Clasp::connect_to(&router.url()).await.unwrap();
+    assert_eq!(other.get("/corr/present").await.unwrap(), Value::Int(7));
+
+    client.close().await;
+    other.close().await;
+}
+
+#[tokio::test]
+async fn test_subscribe_ack_and_broadcast() {
+    let router = TestRouter::start().await;
+    let (sender, mut receiver) = connect_raw(&router.url()).await;
+
+    let subscribe = Message::Subscribe(SubscribeMessage {
+        id: 1,
+        pattern: "/corr/**".to_string(),
+        types: vec![],
+        options: None,
+        correlation_id: Some(41),
+    });
+    sender
+        .send(codec::encode(&subscribe).unwrap())
+        .await
+        .unwrap();
+
+    match recv(&mut receiver).await {
+        Message::Ack(ack) => {
+            assert_eq!(ack.correlation_id, Some(41));
+            assert_eq!(ack.address.as_deref(), Some("/corr/**"));
+        }
+        other => panic!("expected ACK, got {:?}", other),
+    }
+
+    let set = Message::Set(SetMessage {
+        address: "/corr/x".to_string(),
+        value: Value::Int(1),
+        revision: None,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: Some(42),
+    });
+    sender.send(codec::encode(&set).unwrap()).await.unwrap();
+
+    // The sender's own correlation id is not leaked to subscribers
+    let mut got_ack = false;
+    let mut got_broadcast = false;
+    while !got_ack || !got_broadcast {
+        match recv(&mut receiver).await {
+            Message::Ack(ack) => {
+                assert_eq!(ack.correlation_id, Some(42));
+                got_ack = true;
+            }
+            Message::Set(set) => {
+                assert_eq!(set.correlation_id, None);
+                assert!(set.revision.is_some());
+                got_broadcast = true;
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_confirmed_set_emit_and_bundle() {
+    let router = TestRouter::start().await;
+    let client = Clasp::connect_to(&router.url()).await.unwrap();
+    let observer = Clasp::connect_to(&router.url()).await.unwrap();
+
+    let events = ValueCollector::new();
+    observer
+        .subscribe("/cue/**", events.callback_ref())
+        .await
+        .unwrap();
+    // Round trip so the subscription is in place before publishing
+    observer.set_confirmed("/cue/ready", true).await.unwrap();
+
+    let first = client.set_confirmed("/cue/level", 0.5).await.unwrap();
+    let second = client.set_confirmed("/cue/level", 0.75).await.unwrap();
+    assert!(second > first);
+
+    client.emit_confirmed("/cue/go", 1).await.unwrap();
+    assert!(events.wait_for_count(4, Duration::from_secs(2)).await);
+    assert_eq!(events.values_for("/cue/go"), vec![Value::Int(1)]);
+
+    let ack = client
+        .bundle_confirmed(vec![
+            Message::Set(SetMessage {
+                address: "/cue/a".to_string(),
+                value: Value::Int(1),
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            }),
+            Message::Set(SetMessage {
+                address: "/cue/b".to_string(),
+                value: Value::Int(2),
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            }),
+        ])
+        .await
+        .unwrap();
+    assert!(ack.revision.is_some());
+    assert_eq!(observer.get("/cue/b").await.unwrap(), Value::Int(2));
+
+    client.close().await;
+    observer.close().await;
+}
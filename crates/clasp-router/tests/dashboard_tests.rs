@@ -0,0 +1,222 @@
+//! Integration tests for the web dashboard adapter
+
+#![cfg(feature = "dashboard")]
+
+use clasp_client::Clasp;
+use clasp_core::{CpskValidator, Scope, TokenInfo, Value};
+use clasp_router::{DashboardConfig, Router};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use serde_json::json;
+use std::time::Duration;
+
+/// Start a router (WebSocket) and its dashboard; returns (ws url, dashboard url)
+async fn start(router: Router) -> (String, String) {
+    let ws_port = find_available_port().await;
+    let http_port = find_available_port().await;
+
+    let dashboard = router.dashboard(DashboardConfig {
+        bind_addr: format!("127.0.0.1:{}", http_port),
+        ..Default::default()
+    });
+    tokio::spawn(async move { dashboard.serve().await });
+    let ws_addr = format!("127.0.0.1:{}", ws_port);
+    tokio::spawn(async move { router.serve_websocket(&ws_addr).await });
+
+    for port in [ws_port, http_port] {
+        wait_for(
+            || async move {
+                tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                    .await
+                    .is_ok()
+            },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await;
+    }
+
+    (
+        format!("ws://127.0.0.1:{}", ws_port),
+        format!("http://127.0.0.1:{}", http_port),
+    )
+}
+
+fn validator(tokens: &[(&str, &str)]) -> CpskValidator {
+    let validator = CpskValidator::new();
+    for (token, scope) in tokens {
+        validator.register(
+            token.to_string(),
+            TokenInfo::new(token.to_string(), vec![Scope::parse(scope).unwrap()]),
+        );
+    }
+    validator
+}
+
+#[tokio::test]
+async fn test_dashboard_serves_ui() {
+    let (_, base) = start(Router::default()).await;
+
+    let response = reqwest::get(format!("{}/", base)).await.unwrap();
+    assert!(response.status().is_success());
+    assert!(response.text().await.unwrap().contains("CLASP Router"));
+
+    let response = reqwest::get(format!("{}/app.js", base)).await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn test_dashboard_set_broadcasts_to_subscribers() {
+    let (ws_url, base) = start(Router::default()).await;
+
+    let client = Clasp::connect_to(&ws_url).await.unwrap();
+    let collector = ValueCollector::new();
+    client
+        .subscribe("/lights/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    let http = reqwest::Client::new();
+    let response = http
+        .post(format!("{}/api/params", base))
+        .json(&json!({ "address": "/lights/1", "value": 0.5 }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    assert!(collector.wait_for_count(1, Duration::from_secs(2)).await);
+    assert_eq!(collector.values_for("/lights/1"), vec![Value::Float(0.5)]);
+
+    let params: serde_json::Value = http
+        .get(format!("{}/api/params?pattern=/lights/**", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(params[0]["address"], "/lights/1");
+    assert_eq!(params[0]["value"], 0.5);
+
+    let sessions: serde_json::Value = http
+        .get(format!("{}/api/sessions", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+
+    let subscriptions: serde_json::Value = http
+        .get(format!("{}/api/subscriptions", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(subscriptions[0]["pattern"], "/lights/**");
+
+    let stats: serde_json::Value = http
+        .get(format!("{}/api/subscriptions/stats?hot=5", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stats["subscriptions"], 1);
+    assert_eq!(stats["patterns"][0]["pattern"], "/lights/**");
+    assert_eq!(stats["hot_addresses"][0]["address"], "/lights/1");
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_dashboard_token_scopes() {
+    let router = Router::default().with_validator(validator(&[
+        ("cpsk_admin", "admin:/**"),
+        ("cpsk_lights", "write:/lights/**"),
+    ]));
+    router
+        .state()
+        .set(
+            "/lights/1",
+            Value::Float(1.0),
+            &"test".to_string(),
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+    router
+        .state()
+        .set(
+            "/audio/gain",
+            Value::Float(0.3),
+            &"test".to_string(),
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+    let (_, base) = start(router).await;
+    let http = reqwest::Client::new();
+
+    // No token
+    let response = http
+        .get(format!("{}/api/metrics", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Session list needs admin
+    let response = http
+        .get(format!("{}/api/sessions", base))
+        .bearer_auth("cpsk_lights")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = http
+        .get(format!("{}/api/sessions", base))
+        .bearer_auth("cpsk_admin")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    // Namespace is filtered by read scope
+    let params: serde_json::Value = http
+        .get(format!("{}/api/params", base))
+        .bearer_auth("cpsk_lights")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let params = params.as_array().unwrap();
+    assert_eq!(params.len(), 1);
+    assert_eq!(params[0]["address"], "/lights/1");
+
+    // Writes need write scope on the address
+    let response = http
+        .post(format!("{}/api/params", base))
+        .bearer_auth("cpsk_lights")
+        .json(&json!({ "address": "/audio/gain", "value": 1.0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = http
+        .post(format!("{}/api/params", base))
+        .bearer_auth("cpsk_lights")
+        .json(&json!({ "address": "/lights/1", "value": 0.0 }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
@@ -0,0 +1,90 @@
+//! Duplicate SET suppression tests
+//!
+//! Unchanged SETs on dedup patterns are acknowledged with the current
+//! revision but never reach subscribers; other addresses are unaffected.
+
+use clasp_core::Value;
+use clasp_router::{DedupRule, RouterConfig, SetDedup};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use std::time::Duration;
+use tokio::time::sleep;
+
+async fn dedup_router() -> TestRouter {
+    TestRouter::start_with_config(RouterConfig {
+        dedup: SetDedup::new()
+            .with_rule(DedupRule::new("/sensors/**"))
+            .with_rule(DedupRule::new("/mixer/**").with_epsilon(0.01)),
+        ..Default::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_repeated_values_not_forwarded() {
+    let router = dedup_router().await;
+    let watcher = router.connect_client_named("Watcher").await.unwrap();
+    let collector = ValueCollector::new();
+    watcher
+        .subscribe("/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    let bridge = router.connect_client_named("Bridge").await.unwrap();
+    let first = bridge.set_confirmed("/sensors/temp", 21.5).await.unwrap();
+    for _ in 0..5 {
+        // Still acknowledged, with the revision that is already retained
+        let revision = bridge.set_confirmed("/sensors/temp", 21.5).await.unwrap();
+        assert_eq!(revision, first);
+    }
+    let changed = bridge.set_confirmed("/sensors/temp", 22.0).await.unwrap();
+    assert!(changed > first);
+
+    // Addresses without a rule are always forwarded
+    bridge.set_confirmed("/other", 1).await.unwrap();
+    bridge.set_confirmed("/other", 1).await.unwrap();
+
+    assert!(collector.wait_for_count(4, Duration::from_secs(2)).await);
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        collector.values_for("/sensors/temp"),
+        vec![Value::Float(21.5), Value::Float(22.0)]
+    );
+    assert_eq!(collector.values_for("/other").len(), 2);
+}
+
+#[tokio::test]
+async fn test_epsilon_suppresses_small_changes() {
+    let router = dedup_router().await;
+    let watcher = router.connect_client_named("Watcher").await.unwrap();
+    let collector = ValueCollector::new();
+    watcher
+        .subscribe("/mixer/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    let bridge = router.connect_client_named("Bridge").await.unwrap();
+    for value in [0.5, 0.505, 0.509, 0.52, 0.525] {
+        bridge.set_confirmed("/mixer/fader", value).await.unwrap();
+    }
+
+    assert!(collector.wait_for_count(2, Duration::from_secs(2)).await);
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        collector.values_for("/mixer/fader"),
+        vec![Value::Float(0.5), Value::Float(0.52)]
+    );
+}
+
+#[tokio::test]
+async fn test_locking_sets_not_suppressed() {
+    let router = dedup_router().await;
+    let owner = router.connect_client_named("Owner").await.unwrap();
+    let other = router.connect_client_named("Other").await.unwrap();
+
+    owner.set_confirmed("/sensors/door", true).await.unwrap();
+    owner.set_locked("/sensors/door", true).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    // A repeat from someone else still hits the lock
+    assert!(other.set_confirmed("/sensors/door", true).await.is_err());
+}
@@ -0,0 +1,183 @@
+//! Device key authentication tests (`DeviceIdentity`, `/$sys/keys`)
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{
+    CpskValidator, DeviceIdentity, DeviceKeyValidator, Scope, SecurityMode, TokenInfo, Value,
+};
+use clasp_router::{token_admin, Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const ADMIN: &str = "cpsk_admin";
+
+fn temp_key_file() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("clasp-device-keys-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("keys.txt")
+}
+
+/// Start an authenticated router with an admin token and `keys`; returns
+/// its URL
+async fn start_router(keys: DeviceKeyValidator, key_file: &Path) -> String {
+    let validator = CpskValidator::new();
+    validator.register(
+        ADMIN.to_string(),
+        TokenInfo::new(ADMIN.to_string(), vec![Scope::parse("admin:/**").unwrap()]),
+    );
+    let router = Arc::new(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            ..Default::default()
+        })
+        .with_validator(validator)
+        .with_device_keys(keys)
+        .with_device_key_file(key_file),
+    );
+
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    tokio::spawn(async move { router.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    format!("ws://127.0.0.1:{}", port)
+}
+
+async fn connect_device(url: &str, identity: &DeviceIdentity) -> clasp_client::Result<Clasp> {
+    Clasp::builder(url)
+        .name("sensor")
+        .identity(identity.clone())
+        .reconnect(false)
+        .connect()
+        .await
+}
+
+#[tokio::test]
+async fn test_registered_device_connects() {
+    let identity = DeviceIdentity::generate();
+    let keys = DeviceKeyValidator::new();
+    keys.register(
+        &identity.public_key(),
+        TokenInfo::new(
+            identity.public_key(),
+            vec![Scope::parse("write:/sensors/**").unwrap()],
+        ),
+    )
+    .unwrap();
+    let file = temp_key_file();
+    let url = start_router(keys, &file).await;
+
+    let device = connect_device(&url, &identity).await.unwrap();
+    assert!(device.set_with_ack("/sensors/temp", 21.5).await.is_ok());
+    // Scopes come from the registration
+    assert!(matches!(
+        device.set_with_ack("/lights/1", 1.0).await,
+        Err(ClientError::Forbidden(_))
+    ));
+
+    // A key the router doesn't know is turned away
+    assert!(connect_device(&url, &DeviceIdentity::generate())
+        .await
+        .is_err());
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_replayed_proof_rejected() {
+    let identity = DeviceIdentity::generate();
+    let keys = DeviceKeyValidator::new();
+    keys.register(
+        &identity.public_key(),
+        TokenInfo::new(
+            identity.public_key(),
+            vec![Scope::parse("read:/**").unwrap()],
+        ),
+    )
+    .unwrap();
+    let file = temp_key_file();
+    let url = start_router(keys, &file).await;
+
+    // A captured proof can't be used as a token
+    let proof = identity.proof();
+    let connect_with = |token: String| {
+        let url = url.clone();
+        async move {
+            Clasp::builder(&url)
+                .token(&token)
+                .reconnect(false)
+                .connect()
+                .await
+        }
+    };
+    assert!(connect_with(proof.clone()).await.is_ok());
+    assert!(connect_with(proof).await.is_err());
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_registers_and_revokes_key() {
+    let file = temp_key_file();
+    let url = start_router(DeviceKeyValidator::new(), &file).await;
+    let admin = Clasp::builder(&url)
+        .token(ADMIN)
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap();
+
+    let identity = DeviceIdentity::generate();
+    let address = format!("/$sys/keys/{}", identity.public_key());
+    assert!(connect_device(&url, &identity).await.is_err());
+
+    admin
+        .set_with_ack(&address, "write:/sensors/**")
+        .await
+        .unwrap();
+    let device = connect_device(&url, &identity).await.unwrap();
+
+    let Value::Map(info) = admin.get(&address).await.unwrap() else {
+        panic!("expected a key map");
+    };
+    assert_eq!(
+        info.get("scopes"),
+        Some(&Value::Array(vec![Value::String(
+            "write:/sensors/**".into()
+        )]))
+    );
+
+    // Persisted to the key file
+    let reloaded = DeviceKeyValidator::new();
+    assert_eq!(token_admin::load_token_file(&file, &reloaded).unwrap(), 1);
+    assert!(reloaded.get(&identity.public_key()).is_some());
+
+    // Malformed keys are rejected
+    assert!(matches!(
+        admin.set_with_ack("/$sys/keys/not-a-key", "read:/**").await,
+        Err(ClientError::InvalidAddress(_))
+    ));
+
+    // Revoking disconnects the device
+    admin.set_with_ack(&address, Value::Null).await.unwrap();
+    assert!(
+        wait_for(
+            || async { !device.is_connected() },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await,
+        "device session was not closed after revocation"
+    );
+    assert!(connect_device(&url, &identity).await.is_err());
+
+    admin.close().await;
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
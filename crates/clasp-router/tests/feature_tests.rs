@@ -0,0 +1,144 @@
+//! HELLO feature enforcement tests
+
+use clasp_client::Clasp;
+use clasp_core::{GesturePhase, Value};
+use clasp_router::{FeatureUsage, Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_router(config: RouterConfig) -> (Arc<Router>, String) {
+    let router = Arc::new(Router::new(config));
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    (router, format!("ws://127.0.0.1:{}", port))
+}
+
+async fn connect(url: &str, features: &[&str]) -> Clasp {
+    Clasp::builder(url)
+        .features(features.iter().map(|f| f.to_string()).collect())
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_undeclared_feature_rejected() {
+    let (router, url) = start_router(RouterConfig {
+        enforce_features: true,
+        ..Default::default()
+    })
+    .await;
+
+    let observer = connect(&url, &["param", "event", "gesture"]).await;
+    let collector = ValueCollector::new();
+    observer
+        .subscribe("/touch/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    let sender = connect(&url, &["param", "event"]).await;
+    sender
+        .gesture("/touch/1", 1, GesturePhase::Start, Value::Float(0.5))
+        .await
+        .unwrap();
+
+    assert!(
+        wait_for(
+            || async { sender.last_error().is_some() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    let error = sender.last_error().unwrap();
+    assert_eq!(error.code, 103);
+    assert!(error.message.contains("'gesture'"), "{}", error.message);
+    assert_eq!(error.address.as_deref(), Some("/touch/1"));
+
+    // Declared signal types still go through
+    sender.emit("/touch/tap", Value::Int(1)).await.unwrap();
+    assert!(collector.wait_for_count(1, Duration::from_secs(2)).await);
+    assert_eq!(collector.values_for("/touch/1"), Vec::<Value>::new());
+
+    let usage = router.feature_usage();
+    assert_eq!(
+        usage["gesture"],
+        FeatureUsage {
+            accepted: 0,
+            rejected: 1
+        }
+    );
+    assert_eq!(usage["event"].accepted, 1);
+
+    observer.close().await;
+    sender.close().await;
+}
+
+#[tokio::test]
+async fn test_router_without_feature_rejects() {
+    let (_router, url) = start_router(RouterConfig {
+        features: vec!["param".to_string(), "event".to_string()],
+        enforce_features: true,
+        ..Default::default()
+    })
+    .await;
+
+    let client = connect(&url, &["param", "event", "stream"]).await;
+    client.stream("/sensor/1", 0.5).await.unwrap();
+
+    assert!(
+        wait_for(
+            || async { client.last_error().is_some() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    let error = client.last_error().unwrap();
+    assert_eq!(error.code, 103);
+    assert!(error.message.contains("'stream'"), "{}", error.message);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_features_counted_without_enforcement() {
+    let (router, url) = start_router(RouterConfig::default()).await;
+
+    let client = connect(&url, &["param"]).await;
+    client
+        .gesture("/touch/1", 1, GesturePhase::Start, Value::Float(0.5))
+        .await
+        .unwrap();
+    client.set("/lights/1", 1.0).await.unwrap();
+
+    assert!(
+        wait_for(
+            || async {
+                let usage = router.feature_usage();
+                usage.get("gesture").map(|u| u.accepted) == Some(1)
+                    && usage.get("param").map(|u| u.accepted) == Some(1)
+            },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert!(client.last_error().is_none());
+
+    client.close().await;
+}
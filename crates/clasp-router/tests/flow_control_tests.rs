@@ -0,0 +1,167 @@
+//! Flow control tests
+//!
+//! A subscription made with a credit window gets at most that many
+//! deliveries ahead of the CREDIT grants its client sends back; the router
+//! drops the rest instead of queueing them.
+
+use clasp_core::{
+    codec, CreditMessage, HelloMessage, Message, SubscribeMessage, SubscribeOptions, Value,
+};
+use clasp_test_utils::TestRouter;
+use clasp_transport::websocket::{WebSocketReceiver, WebSocketSender};
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Connect raw and subscribe to `pattern` with a window of `credits`
+async fn subscribe_raw(
+    router: &TestRouter,
+    pattern: &str,
+    credits: u32,
+) -> (WebSocketSender, WebSocketReceiver) {
+    let (sender, receiver) = WebSocketTransport::connect(&router.url()).await.unwrap();
+    let hello = Message::Hello(HelloMessage {
+        version: 2,
+        name: "Raw".to_string(),
+        features: vec!["stream".to_string()],
+        capabilities: None,
+        token: None,
+        since: None,
+    });
+    sender.send(codec::encode(&hello).unwrap()).await.unwrap();
+
+    let subscribe = Message::Subscribe(SubscribeMessage {
+        id: 1,
+        pattern: pattern.to_string(),
+        types: vec![],
+        options: Some(SubscribeOptions {
+            credits: Some(credits),
+            ..Default::default()
+        }),
+        correlation_id: None,
+    });
+    sender
+        .send(codec::encode(&subscribe).unwrap())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    (sender, receiver)
+}
+
+/// Count the PUBLISHes arriving until the connection goes quiet
+async fn count_publishes(receiver: &mut WebSocketReceiver) -> usize {
+    let mut count = 0;
+    while let Ok(Some(event)) = timeout(Duration::from_millis(300), receiver.recv()).await {
+        if let TransportEvent::Data(data) = event {
+            if let (Message::Publish(_), _) = codec::decode(&data).unwrap() {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+#[tokio::test]
+async fn test_deliveries_stop_at_window() {
+    let router = TestRouter::start().await;
+    let (sender, mut receiver) = subscribe_raw(&router, "/cam/**", 3).await;
+    let producer = router.connect_client_named("Producer").await.unwrap();
+
+    for i in 0..10u8 {
+        producer
+            .stream("/cam/meta", Value::Bytes(vec![i]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(count_publishes(&mut receiver).await, 3);
+
+    let grant = Message::Credit(CreditMessage { id: 1, credits: 2 });
+    sender.send(codec::encode(&grant).unwrap()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    for i in 0..10u8 {
+        producer
+            .stream("/cam/meta", Value::Bytes(vec![i]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(count_publishes(&mut receiver).await, 2);
+}
+
+#[tokio::test]
+async fn test_byte_stream_keeps_flowing_past_window() {
+    let router = TestRouter::start().await;
+    let consumer = router.connect_client_named("Consumer").await.unwrap();
+    let producer = router.connect_client_named("Producer").await.unwrap();
+
+    let mut stream = consumer
+        .subscribe_bytes_with_window("/cam/**", 4)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // One at a time, so every grant lands before the window runs out
+    for i in 0..20u8 {
+        producer
+            .stream("/cam/meta", Value::Bytes(vec![i, i]))
+            .await
+            .unwrap();
+        let data = timeout(Duration::from_secs(2), stream.next())
+            .await
+            .expect("delivery stalled")
+            .unwrap();
+        assert_eq!(&data[..], &[i, i]);
+    }
+}
+
+#[tokio::test]
+async fn test_byte_stream_drops_burst_beyond_window() {
+    let router = TestRouter::start().await;
+    let consumer = router.connect_client_named("Consumer").await.unwrap();
+    let producer = router.connect_client_named("Producer").await.unwrap();
+
+    let mut stream = consumer
+        .subscribe_bytes_with_window("/cam/**", 4)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    for i in 0..10u8 {
+        producer
+            .stream("/cam/meta", Value::Bytes(vec![i]))
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let mut received = Vec::new();
+    while let Ok(Some(data)) = timeout(Duration::from_millis(300), stream.next()).await {
+        received.push(data[0]);
+    }
+    assert_eq!(received, vec![0, 1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_byte_stream_skips_other_values() {
+    let router = TestRouter::start().await;
+    let consumer = router.connect_client_named("Consumer").await.unwrap();
+    let producer = router.connect_client_named("Producer").await.unwrap();
+
+    let mut stream = consumer.subscribe_bytes("/cam/**").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    producer.stream("/cam/meta", 1.5).await.unwrap();
+    producer
+        .stream("/cam/meta", Value::Bytes(vec![7]))
+        .await
+        .unwrap();
+
+    let data = timeout(Duration::from_secs(2), stream.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&data[..], &[7]);
+}
@@ -0,0 +1,170 @@
+//! Integration tests for health reporting and graceful shutdown
+
+#![cfg(feature = "health")]
+
+use clasp_client::Clasp;
+use clasp_router::{AdapterStatus, HealthServerConfig, Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn wait_for_port(port: u16) {
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+}
+
+/// Start the health endpoints for `router`; returns the base URL
+async fn start_health(router: &Router) -> String {
+    let port = find_available_port().await;
+    let server = router.health_server(HealthServerConfig {
+        bind_addr: format!("127.0.0.1:{}", port),
+    });
+    tokio::spawn(async move { server.serve().await });
+    wait_for_port(port).await;
+    format!("http://127.0.0.1:{}", port)
+}
+
+#[tokio::test]
+async fn test_readyz_tracks_router_state() {
+    let router = Arc::new(Router::default());
+    let base = start_health(&router).await;
+
+    // Not listening yet
+    let response = reqwest::get(format!("{}/readyz", base)).await.unwrap();
+    assert_eq!(response.status(), 503);
+    let response = reqwest::get(format!("{}/healthz", base)).await.unwrap();
+    assert!(response.status().is_success());
+
+    let ws_port = find_available_port().await;
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move {
+        serving
+            .serve_websocket(&format!("127.0.0.1:{}", ws_port))
+            .await
+    });
+    wait_for_port(ws_port).await;
+
+    let client = Clasp::connect_to(&format!("ws://127.0.0.1:{}", ws_port))
+        .await
+        .unwrap();
+
+    let response = reqwest::get(format!("{}/readyz", base)).await.unwrap();
+    assert!(response.status().is_success());
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["ready"], true);
+    assert_eq!(report["sessions"], 1);
+    assert_eq!(report["adapters"]["WebSocket"]["state"], "running");
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_readyz_unavailable_at_session_limit() {
+    let router = Arc::new(Router::new(RouterConfig {
+        max_sessions: 1,
+        ..Default::default()
+    }));
+    let base = start_health(&router).await;
+
+    let ws_port = find_available_port().await;
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move {
+        serving
+            .serve_websocket(&format!("127.0.0.1:{}", ws_port))
+            .await
+    });
+    wait_for_port(ws_port).await;
+
+    let client = Clasp::connect_to(&format!("ws://127.0.0.1:{}", ws_port))
+        .await
+        .unwrap();
+
+    let response = reqwest::get(format!("{}/readyz", base)).await.unwrap();
+    assert_eq!(response.status(), 503);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_failed_adapter_reported() {
+    let router = Router::default();
+
+    // Port already taken
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    assert!(router.serve_websocket(&addr).await.is_err());
+
+    let report = router.health();
+    assert!(!report.ready);
+    assert!(matches!(
+        report.adapters.get("WebSocket"),
+        Some(AdapterStatus::Failed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_and_stops() {
+    let router = Arc::new(Router::default());
+    let base = start_health(&router).await;
+
+    let ws_port = find_available_port().await;
+    let serving = Arc::clone(&router);
+    let server = tokio::spawn(async move {
+        serving
+            .serve_websocket(&format!("127.0.0.1:{}", ws_port))
+            .await
+    });
+    wait_for_port(ws_port).await;
+
+    let client = Clasp::connect_to(&format!("ws://127.0.0.1:{}", ws_port))
+        .await
+        .unwrap();
+    assert!(client.is_connected());
+
+    let draining = Arc::clone(&router);
+    let shutdown = tokio::spawn(async move { draining.shutdown(Duration::from_millis(500)).await });
+
+    // Readiness drops as soon as draining starts
+    wait_for(
+        || async { router.is_draining() },
+        Duration::from_millis(10),
+        Duration::from_secs(1),
+    )
+    .await;
+    let response = reqwest::get(format!("{}/readyz", base)).await.unwrap();
+    assert_eq!(response.status(), 503);
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["draining"], true);
+
+    // The client is closed after the drain timeout and the accept loop exits
+    tokio::time::timeout(Duration::from_secs(5), shutdown)
+        .await
+        .unwrap()
+        .unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("accept loop did not exit")
+        .unwrap();
+    assert!(result.is_ok());
+
+    assert!(
+        wait_for(
+            || async { !client.is_connected() },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await
+    );
+    assert_eq!(
+        router.health().adapters.get("WebSocket"),
+        Some(&AdapterStatus::Stopped)
+    );
+}
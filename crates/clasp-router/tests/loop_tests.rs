@@ -0,0 +1,107 @@
+//! Feedback loop tests
+//!
+//! A value two sessions keep writing back to each other is dropped after
+//! `max_hops` hops, and the loop is reported on `/$sys/loops`.
+
+use clasp_client::Clasp;
+use clasp_core::{ErrorCode, Value};
+use clasp_router::{LoopDetection, RouterConfig};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Write every value received on `pattern` straight back, like a bridge
+/// whose far side echoes
+async fn echo(client: Clasp, pattern: &str) -> Arc<Clasp> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(String, Value)>();
+    client
+        .subscribe(pattern, move |value, address| {
+            let _ = tx.send((address.to_string(), value));
+        })
+        .await
+        .unwrap();
+
+    let client = Arc::new(client);
+    let echoing = Arc::clone(&client);
+    tokio::spawn(async move {
+        while let Some((address, value)) = rx.recv().await {
+            let _ = echoing.set(&address, value).await;
+        }
+    });
+    client
+}
+
+#[tokio::test]
+async fn test_bridge_loop_broken() {
+    let router = TestRouter::start_with_config(RouterConfig {
+        loop_detection: LoopDetection::new(8, Duration::from_millis(500)),
+        ..Default::default()
+    })
+    .await;
+
+    let operator = router.connect_client_named("Operator").await.unwrap();
+    let loops = ValueCollector::new();
+    operator
+        .subscribe("/$sys/loops", loops.callback_ref())
+        .await
+        .unwrap();
+
+    let mqtt = router.connect_client_named("MqttBridge").await.unwrap();
+    let osc = router.connect_client_named("OscBridge").await.unwrap();
+    let mqtt = echo(mqtt, "/mixer/**").await;
+    let _osc = echo(osc, "/mixer/**").await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let console = router.connect_client_named("Console").await.unwrap();
+    console.set("/mixer/fader/1", 0.75).await.unwrap();
+
+    assert!(
+        loops.wait_for_count(1, Duration::from_secs(2)).await,
+        "loop not reported"
+    );
+    let Value::Map(report) = &loops.values_for("/$sys/loops")[0] else {
+        panic!("expected a map");
+    };
+    assert_eq!(
+        report["address"],
+        Value::String("/mixer/fader/1".to_string())
+    );
+    assert!(matches!(report["hops"], Value::Int(hops) if hops > 8));
+
+    // The storm dies down: no more traffic once the loop is broken
+    let traffic = ValueCollector::new();
+    operator
+        .subscribe("/mixer/**", traffic.callback_ref())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let settled = traffic.count();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(traffic.count(), settled);
+    assert_eq!(loops.count(), 1);
+    assert_eq!(
+        mqtt.last_error().and_then(|e| ErrorCode::from_u16(e.code)),
+        Some(ErrorCode::FeedbackLoop)
+    );
+
+    // A new value goes through
+    console.set_confirmed("/mixer/fader/1", 0.5).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_alternating_writers_without_subscription() {
+    let router = TestRouter::start_with_config(RouterConfig {
+        loop_detection: LoopDetection::new(2, Duration::from_secs(1)),
+        ..Default::default()
+    })
+    .await;
+
+    // Two sensors reporting the same reading aren't a loop: neither
+    // receives what the other writes
+    let a = router.connect_client_named("SensorA").await.unwrap();
+    let b = router.connect_client_named("SensorB").await.unwrap();
+    for i in 0..10 {
+        let sensor = if i % 2 == 0 { &a } else { &b };
+        sensor.set_confirmed("/room/temp", 21).await.unwrap();
+    }
+}
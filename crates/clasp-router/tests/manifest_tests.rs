@@ -0,0 +1,166 @@
+//! Namespace manifest tests (`/$manifest/<app>`)
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{Manifest, SignalDefinition, SignalMeta, SignalType, Value};
+use clasp_router::{Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_router(router: Router) -> (Arc<Router>, String) {
+    let router = Arc::new(router);
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    (router, format!("ws://127.0.0.1:{}", port))
+}
+
+async fn connect(url: &str, name: &str) -> Clasp {
+    Clasp::builder(url)
+        .name(name)
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap()
+}
+
+fn signal(address: &str, datatype: &str, range: Option<(f64, f64)>) -> SignalDefinition {
+    SignalDefinition {
+        address: address.to_string(),
+        signal_type: SignalType::Param,
+        datatype: Some(datatype.to_string()),
+        access: None,
+        meta: Some(SignalMeta {
+            unit: None,
+            range,
+            default: None,
+            description: Some(format!("{} control", datatype)),
+        }),
+    }
+}
+
+fn mixer_manifest() -> Manifest {
+    Manifest::new("mixer", "/mixer")
+        .with_description("Main mixer")
+        .with_signal(signal("/mixer/*/gain", "float", Some((0.0, 1.0))))
+        .with_signal(signal("/mixer/*/mute", "bool", None))
+        .with_signal(SignalDefinition {
+            access: Some("r".to_string()),
+            ..signal("/mixer/*/meter", "float", None)
+        })
+}
+
+/// Whether a write was rejected with ERROR 402 (invalid value)
+fn invalid_value(result: Result<clasp_core::AckMessage, ClientError>) -> bool {
+    matches!(result, Err(ClientError::Server { code: 402, .. }))
+}
+
+#[tokio::test]
+async fn test_manifest_published_and_read_back() {
+    let (router, url) = start_router(Router::default()).await;
+    let app = connect(&url, "Mixer").await;
+    app.publish_manifest(&mixer_manifest()).await.unwrap();
+
+    let manifests = router.state().manifests();
+    assert_eq!(manifests.len(), 1);
+    assert_eq!(manifests[0].namespace, "/mixer");
+
+    // Tools read the manifest itself, and its signals through QUERY
+    let tool = connect(&url, "Tool").await;
+    let manifest = tool.manifest("mixer").await.unwrap();
+    assert_eq!(manifest.description.as_deref(), Some("Main mixer"));
+    assert_eq!(manifest.signals.len(), 3);
+    let signals = tool.query("/mixer/**").await.unwrap();
+    assert_eq!(signals.len(), 3);
+}
+
+#[tokio::test]
+async fn test_invalid_manifest_rejected() {
+    let (router, url) = start_router(Router::default()).await;
+    let app = connect(&url, "App").await;
+
+    assert!(invalid_value(app.set_with_ack("/$manifest/mixer", 1).await));
+
+    // The app named in the address and in the manifest must agree
+    assert!(invalid_value(
+        app.set_with_ack("/$manifest/lights", mixer_manifest().to_value())
+            .await,
+    ));
+    assert!(router.state().manifests().is_empty());
+}
+
+#[tokio::test]
+async fn test_manifest_owned_by_publisher() {
+    let (router, url) = start_router(Router::default()).await;
+    let owner = connect(&url, "Mixer").await;
+    owner.publish_manifest(&mixer_manifest()).await.unwrap();
+
+    let other = connect(&url, "Other").await;
+    let replacement = Manifest::new("mixer", "/mixer");
+    assert!(matches!(
+        other.publish_manifest(&replacement).await,
+        Err(ClientError::Forbidden(_))
+    ));
+
+    // The owner may replace its own manifest
+    owner.publish_manifest(&replacement).await.unwrap();
+    assert!(router.state().manifests()[0].signals.is_empty());
+}
+
+#[tokio::test]
+async fn test_writes_checked_against_manifest() {
+    let router = Router::new(RouterConfig {
+        validate_manifests: true,
+        ..Default::default()
+    });
+    let (_router, url) = start_router(router).await;
+    let owner = connect(&url, "Mixer").await;
+    owner.publish_manifest(&mixer_manifest()).await.unwrap();
+    let client = connect(&url, "Surface").await;
+
+    assert!(client.set_with_ack("/mixer/1/gain", 0.5).await.is_ok());
+    assert!(client.set_with_ack("/mixer/1/mute", true).await.is_ok());
+    // Outside the namespace nothing is checked
+    assert!(client.set_with_ack("/lights/1", "anything").await.is_ok());
+
+    assert!(invalid_value(
+        client.set_with_ack("/mixer/1/gain", 1.5).await
+    ));
+    assert!(invalid_value(client.set_with_ack("/mixer/1/mute", 1).await));
+    assert!(invalid_value(
+        client.set_with_ack("/mixer/1/solo", true).await
+    ));
+
+    // Read-only signals are written by the owner only
+    assert!(matches!(
+        client.set_with_ack("/mixer/1/meter", 0.2).await,
+        Err(ClientError::Forbidden(_))
+    ));
+    assert!(owner.set_with_ack("/mixer/1/meter", 0.2).await.is_ok());
+    assert_eq!(
+        client.get("/mixer/1/meter").await.unwrap(),
+        Value::Float(0.2)
+    );
+}
+
+#[tokio::test]
+async fn test_writes_unchecked_by_default() {
+    let (_router, url) = start_router(Router::default()).await;
+    let owner = connect(&url, "Mixer").await;
+    owner.publish_manifest(&mixer_manifest()).await.unwrap();
+
+    let client = connect(&url, "Surface").await;
+    assert!(client.set_with_ack("/mixer/1/gain", 1.5).await.is_ok());
+    assert!(client.set_with_ack("/mixer/1/solo", true).await.is_ok());
+}
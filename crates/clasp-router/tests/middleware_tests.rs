@@ -0,0 +1,211 @@
+//! Router middleware tests
+
+use async_trait::async_trait;
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{ErrorCode, Message, Value};
+use clasp_router::{Router, RouterConfig, RouterMiddleware, Session, Verdict};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_router(router: Router) -> String {
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    tokio::spawn(async move { router.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    format!("ws://127.0.0.1:{}", port)
+}
+
+async fn connect(url: &str, name: &str) -> Clasp {
+    Clasp::builder(url)
+        .name(name)
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap()
+}
+
+/// Refuses writes under /admin and clamps levels to 0..=1
+struct Policy;
+
+#[async_trait]
+impl RouterMiddleware for Policy {
+    async fn on_message(&self, _session: &Arc<Session>, msg: &mut Message) -> Verdict {
+        let Message::Set(set) = msg else {
+            return Verdict::Continue;
+        };
+        if set.address.starts_with("/admin/") {
+            return Verdict::reject(ErrorCode::Forbidden, "Admin params are read-only");
+        }
+        if let Value::Float(level) = set.value {
+            set.value = Value::Float(level.clamp(0.0, 1.0));
+        }
+        Verdict::Continue
+    }
+}
+
+/// Hides /private from guests and refuses sessions named "banned"
+struct Guests;
+
+#[async_trait]
+impl RouterMiddleware for Guests {
+    async fn on_connect(&self, session: &Arc<Session>) -> Verdict {
+        if session.name == "banned" {
+            Verdict::reject(ErrorCode::Forbidden, "Not welcome")
+        } else {
+            Verdict::Continue
+        }
+    }
+
+    async fn on_deliver(&self, session: &Arc<Session>, msg: &mut Message) -> Verdict {
+        match msg {
+            Message::Set(set)
+                if session.name == "guest" && set.address.starts_with("/private/") =>
+            {
+                Verdict::Drop
+            }
+            _ => Verdict::Continue,
+        }
+    }
+}
+
+/// Records which hooks ran, in order
+#[derive(Default)]
+struct Recorder {
+    log: Arc<Mutex<Vec<String>>>,
+    disconnects: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl RouterMiddleware for Recorder {
+    async fn on_connect(&self, session: &Arc<Session>) -> Verdict {
+        self.log.lock().push(format!("connect {}", session.name));
+        Verdict::Continue
+    }
+
+    async fn on_message(&self, _session: &Arc<Session>, msg: &mut Message) -> Verdict {
+        if let Message::Set(set) = msg {
+            self.log.lock().push(format!("set {}", set.address));
+        }
+        Verdict::Continue
+    }
+
+    async fn on_disconnect(&self, _session: &Arc<Session>) {
+        self.disconnects.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_on_message_rejects_and_rewrites() {
+    let recorder = Recorder::default();
+    let log = Arc::clone(&recorder.log);
+    let url = start_router(
+        Router::new(RouterConfig::default())
+            .with_middleware(Policy)
+            .with_middleware(recorder),
+    )
+    .await;
+    let client = connect(&url, "desk").await;
+
+    let result = client.set_with_ack("/admin/mode", 1).await;
+    assert!(matches!(result, Err(ClientError::Forbidden(_))));
+
+    client.set_confirmed("/lights/1", 2.5).await.unwrap();
+    let other = connect(&url, "viewer").await;
+    assert_eq!(other.get("/lights/1").await.unwrap(), Value::Float(1.0));
+
+    // The rejected SET stopped the chain before the recorder saw it
+    assert_eq!(
+        *log.lock(),
+        vec!["connect desk", "set /lights/1", "connect viewer"]
+    );
+
+    client.close().await;
+    other.close().await;
+}
+
+#[tokio::test]
+async fn test_on_deliver_filters_per_recipient() {
+    let url = start_router(Router::new(RouterConfig::default()).with_middleware(Guests)).await;
+
+    let staff = connect(&url, "staff").await;
+    let guest = connect(&url, "guest").await;
+    let staff_values = ValueCollector::new();
+    let guest_values = ValueCollector::new();
+    staff
+        .subscribe("/**", staff_values.callback_ref())
+        .await
+        .unwrap();
+    guest
+        .subscribe("/**", guest_values.callback_ref())
+        .await
+        .unwrap();
+
+    let writer = connect(&url, "writer").await;
+    writer.set_confirmed("/private/notes", 1).await.unwrap();
+    writer.set_confirmed("/public/notes", 2).await.unwrap();
+
+    assert!(staff_values.wait_for_count(2, Duration::from_secs(2)).await);
+    assert!(guest_values.wait_for_count(1, Duration::from_secs(2)).await);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(guest_values.count(), 1);
+    assert_eq!(
+        guest_values.values_for("/public/notes"),
+        vec![Value::Int(2)]
+    );
+
+    staff.close().await;
+    guest.close().await;
+    writer.close().await;
+}
+
+#[tokio::test]
+async fn test_on_connect_veto_and_disconnect() {
+    let recorder = Recorder::default();
+    let disconnects = Arc::clone(&recorder.disconnects);
+    let url = start_router(
+        Router::new(RouterConfig::default())
+            .with_middleware(Guests)
+            .with_middleware(recorder),
+    )
+    .await;
+
+    let banned = connect(&url, "banned").await;
+    assert!(
+        wait_for(
+            || async { !banned.is_connected() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert_eq!(
+        banned.last_error().map(|e| e.code),
+        Some(ErrorCode::Forbidden.as_u16())
+    );
+
+    // A vetoed session still gets its disconnect hooks
+    assert!(
+        wait_for(
+            || async { disconnects.load(Ordering::SeqCst) == 1 },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+
+    let allowed = connect(&url, "allowed").await;
+    assert!(allowed.set_confirmed("/lights/1", 0.5).await.is_ok());
+    allowed.close().await;
+}
@@ -0,0 +1,250 @@
+//! Session id strategy, client name collision and duplicate session tests
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo};
+use clasp_router::{
+    DuplicateSessions, NameCollision, Router, RouterConfig, SessionIdStrategy, SessionIdentity,
+};
+use clasp_test_utils::{find_available_port, wait_for};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_router(router: Router) -> (Arc<Router>, String) {
+    let router = Arc::new(router);
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    (router, format!("ws://127.0.0.1:{}", port))
+}
+
+async fn try_connect(url: &str, name: &str) -> clasp_client::Result<Clasp> {
+    Clasp::builder(url)
+        .name(name)
+        .reconnect(false)
+        .connect()
+        .await
+}
+
+#[tokio::test]
+async fn test_short_session_ids() {
+    let (_router, url) = start_router(Router::new(RouterConfig {
+        session_ids: SessionIdStrategy::Short,
+        ..Default::default()
+    }))
+    .await;
+
+    let client = try_connect(&url, "Lighting Desk").await.unwrap();
+    let id = client.session_id().unwrap();
+    assert!(id.starts_with("lighting-desk-"), "{}", id);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_subject_session_ids() {
+    let validator = CpskValidator::new();
+    validator.register(
+        "cpsk_alice".to_string(),
+        TokenInfo::new(
+            "cpsk_alice".to_string(),
+            vec![Scope::parse("write:/**").unwrap()],
+        )
+        .with_subject("alice"),
+    );
+    let (_router, url) = start_router(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            session_ids: SessionIdStrategy::Subject,
+            ..Default::default()
+        })
+        .with_validator(validator),
+    )
+    .await;
+
+    let client = Clasp::builder(&url)
+        .name("Desk")
+        .token("cpsk_alice")
+        .reconnect(false)
+        .connect()
+        .await
+        .unwrap();
+    let id = client.session_id().unwrap();
+    assert!(id.starts_with("alice-"), "{}", id);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_duplicate_name_rejected() {
+    let (_router, url) = start_router(Router::new(RouterConfig {
+        name_collision: NameCollision::Reject,
+        ..Default::default()
+    }))
+    .await;
+
+    let first = try_connect(&url, "Desk").await.unwrap();
+    let second = try_connect(&url, "Desk").await;
+    assert!(
+        matches!(second, Err(ClientError::Server { code: 101, .. })),
+        "{:?}",
+        second.err()
+    );
+
+    let other = try_connect(&url, "Stage").await.unwrap();
+    first.close().await;
+    other.close().await;
+}
+
+#[tokio::test]
+async fn test_duplicate_name_suffixed() {
+    let (router, url) = start_router(Router::new(RouterConfig {
+        name_collision: NameCollision::Suffix,
+        ..Default::default()
+    }))
+    .await;
+
+    let first = try_connect(&url, "Desk").await.unwrap();
+    let second = try_connect(&url, "Desk").await.unwrap();
+    let third = try_connect(&url, "Desk").await.unwrap();
+
+    let mut names: Vec<String> = router.sessions().iter().map(|s| s.name.clone()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Desk", "Desk (2)", "Desk (3)"]);
+
+    first.close().await;
+    second.close().await;
+    third.close().await;
+}
+
+async fn disconnects(client: &Clasp) -> bool {
+    wait_for(
+        || async { !client.is_connected() },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_duplicate_session_takeover() {
+    let (router, url) = start_router(Router::new(RouterConfig {
+        duplicate_sessions: DuplicateSessions::takeover(SessionIdentity::Name),
+        ..Default::default()
+    }))
+    .await;
+
+    let ghost = try_connect(&url, "Kiosk").await.unwrap();
+    ghost.subscribe("/kiosk/**", |_, _| {}).await.unwrap();
+    let other = try_connect(&url, "Stage").await.unwrap();
+
+    let kiosk = try_connect(&url, "Kiosk").await.unwrap();
+    assert!(disconnects(&ghost).await, "old session was not closed");
+    assert!(kiosk.is_connected());
+    assert!(other.is_connected());
+
+    let mut names: Vec<String> = router.sessions().iter().map(|s| s.name.clone()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Kiosk", "Stage"]);
+    assert_eq!(router.subscription_count(), 0);
+
+    kiosk.close().await;
+    other.close().await;
+}
+
+#[tokio::test]
+async fn test_takeover_transfers_subscriptions() {
+    let (router, url) = start_router(Router::new(RouterConfig {
+        duplicate_sessions: DuplicateSessions::takeover(SessionIdentity::Name).with_subscriptions(),
+        ..Default::default()
+    }))
+    .await;
+
+    let ghost = try_connect(&url, "Kiosk").await.unwrap();
+    ghost.subscribe("/kiosk/**", |_, _| {}).await.unwrap();
+    assert!(
+        wait_for(
+            || async { router.subscription_count() == 1 },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await
+    );
+
+    let kiosk = try_connect(&url, "Kiosk").await.unwrap();
+    assert!(disconnects(&ghost).await, "old session was not closed");
+
+    let sessions = router.sessions();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, kiosk.session_id().unwrap());
+    assert_eq!(sessions[0].subscriptions().len(), 1);
+    assert_eq!(router.subscription_count(), 1);
+
+    // The old connection going away doesn't take the moved subscription along
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(router.subscription_count(), 1);
+
+    kiosk.close().await;
+}
+
+#[tokio::test]
+async fn test_duplicate_subject_rejected() {
+    let validator = CpskValidator::new();
+    for token in ["cpsk_alice1", "cpsk_alice2"] {
+        validator.register(
+            token.to_string(),
+            TokenInfo::new(token.to_string(), vec![Scope::parse("write:/**").unwrap()])
+                .with_subject("alice"),
+        );
+    }
+    let (_router, url) = start_router(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            duplicate_sessions: DuplicateSessions::reject(SessionIdentity::Subject),
+            ..Default::default()
+        })
+        .with_validator(validator),
+    )
+    .await;
+
+    let connect = |name: &'static str, token: &'static str| {
+        Clasp::builder(&url)
+            .name(name)
+            .token(token)
+            .reconnect(false)
+            .connect()
+    };
+    let first = connect("Desk", "cpsk_alice1").await.unwrap();
+    // Different name, same subject
+    let second = connect("Laptop", "cpsk_alice2").await;
+    assert!(
+        matches!(second, Err(ClientError::Server { code: 101, .. })),
+        "{:?}",
+        second.err()
+    );
+
+    first.close().await;
+}
+
+#[tokio::test]
+async fn test_duplicate_sessions_allowed_by_default() {
+    let (router, url) = start_router(Router::new(RouterConfig::default())).await;
+
+    let first = try_connect(&url, "Kiosk").await.unwrap();
+    let second = try_connect(&url, "Kiosk").await.unwrap();
+    assert_eq!(router.session_count(), 2);
+    assert!(first.is_connected());
+
+    first.close().await;
+    second.close().await;
+}
@@ -0,0 +1,103 @@
+//! Origin tests
+//!
+//! Sessions carry where their traffic comes from. Subscribers can filter on
+//! it and ask for ENVELOPEs naming it; bridges declare theirs on
+//! `/$sys/session/origin`.
+
+use clasp_client::{Origin, SubscribeOptions};
+use clasp_core::Value;
+use clasp_test_utils::{TestRouter, ValueCollector};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_origin_filter_and_envelope() {
+    let router = TestRouter::start().await;
+
+    let native_only = router.connect_client_named("NativeOnly").await.unwrap();
+    let native = ValueCollector::new();
+    native_only
+        .subscribe_with_options(
+            "/sensors/**",
+            SubscribeOptions {
+                origins: Some(vec!["clasp".to_string()]),
+                ..Default::default()
+            },
+            native.callback_ref(),
+        )
+        .await
+        .unwrap();
+
+    let auditor = router.connect_client_named("Auditor").await.unwrap();
+    let all = ValueCollector::new();
+    auditor
+        .subscribe_with_options(
+            "/sensors/**",
+            SubscribeOptions {
+                envelope: true,
+                ..Default::default()
+            },
+            all.callback_ref(),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let bridge = router.connect_client_named("MqttBridge").await.unwrap();
+    bridge
+        .declare_origin(Origin::new("mqtt").with_remote("sensor-7"))
+        .await
+        .unwrap();
+    bridge.set_confirmed("/sensors/temp", 21.5).await.unwrap();
+
+    let console = router.connect_client_named("Console").await.unwrap();
+    console
+        .set_confirmed("/sensors/humidity", 40)
+        .await
+        .unwrap();
+
+    assert!(all.wait_for_count(2, Duration::from_secs(2)).await);
+    assert!(native.wait_for_count(1, Duration::from_secs(2)).await);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The filtered subscriber only saw the native write
+    assert!(native.values_for("/sensors/temp").is_empty());
+    assert_eq!(native.values_for("/sensors/humidity"), vec![Value::Int(40)]);
+
+    // Enveloped deliveries still reach callbacks, and name their origin
+    assert_eq!(all.values_for("/sensors/temp"), vec![Value::Float(21.5)]);
+    assert_eq!(
+        auditor.last_origin("/sensors/temp"),
+        Some(Origin::new("mqtt").with_remote("sensor-7"))
+    );
+    assert_eq!(
+        auditor.last_origin("/sensors/humidity"),
+        Some(Origin::native())
+    );
+    assert_eq!(native_only.last_origin("/sensors/humidity"), None);
+}
+
+#[tokio::test]
+async fn test_invalid_origin_rejected() {
+    let router = TestRouter::start().await;
+    let client = router.connect_client_named("Bridge").await.unwrap();
+
+    assert!(client.declare_origin(Origin::new("")).await.is_err());
+
+    // The session is still tagged as native
+    let watcher = router.connect_client_named("Watcher").await.unwrap();
+    let values = ValueCollector::new();
+    watcher
+        .subscribe_with_options(
+            "/x",
+            SubscribeOptions {
+                origins: Some(vec!["clasp".to_string()]),
+                ..Default::default()
+            },
+            values.callback_ref(),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    client.set_confirmed("/x", 1).await.unwrap();
+    assert!(values.wait_for_count(1, Duration::from_secs(2)).await);
+}
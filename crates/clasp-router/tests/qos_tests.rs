@@ -0,0 +1,136 @@
+//! Delivery QoS override tests
+//!
+//! Raw WebSocket peers read the QoS in each delivered frame's header, which
+//! follows the subscription's override rather than the publisher's default.
+
+use clasp_core::{
+    codec, HelloMessage, Message, QoS, SignalType, SubscribeMessage, SubscribeOptions,
+};
+use clasp_test_utils::TestRouter;
+use clasp_transport::websocket::{WebSocketReceiver, WebSocketSender};
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// A subscriber speaking raw frames
+struct Peer {
+    sender: WebSocketSender,
+    receiver: WebSocketReceiver,
+}
+
+impl Peer {
+    async fn connect(url: &str, name: &str) -> Self {
+        let (sender, receiver) = WebSocketTransport::connect(url).await.unwrap();
+        let mut peer = Self { sender, receiver };
+        peer.send(Message::Hello(HelloMessage {
+            version: 3,
+            name: name.to_string(),
+            features: vec!["param".to_string(), "stream".to_string()],
+            capabilities: None,
+            token: None,
+            since: None,
+        }))
+        .await;
+        peer.until(|msg| matches!(msg, Message::Welcome(_))).await;
+        peer
+    }
+
+    async fn send(&self, msg: Message) {
+        self.sender
+            .send(codec::encode(&msg).unwrap())
+            .await
+            .unwrap();
+    }
+
+    /// The next message and the QoS it was framed with
+    async fn recv(&mut self) -> (Message, QoS) {
+        loop {
+            match timeout(Duration::from_secs(2), self.receiver.recv()).await {
+                Ok(Some(TransportEvent::Data(data))) => {
+                    let (msg, frame) = codec::decode(&data).unwrap();
+                    return (msg, frame.flags.qos);
+                }
+                Ok(Some(TransportEvent::Connected)) => continue,
+                _ => panic!("timed out waiting for a frame"),
+            }
+        }
+    }
+
+    async fn until(&mut self, done: impl Fn(&Message) -> bool) -> (Message, QoS) {
+        loop {
+            let (msg, qos) = self.recv().await;
+            if done(&msg) {
+                return (msg, qos);
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, id: u32, pattern: &str, qos: Option<QoS>) {
+        self.send(Message::Subscribe(SubscribeMessage {
+            id,
+            pattern: pattern.to_string(),
+            types: vec![],
+            options: Some(SubscribeOptions {
+                qos,
+                ..Default::default()
+            }),
+            correlation_id: None,
+        }))
+        .await;
+        self.send(Message::Ping).await;
+        self.until(|msg| matches!(msg, Message::Pong)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_subscription_overrides_delivery_qos() {
+    let router = TestRouter::start().await;
+
+    let mut dashboard = Peer::connect(&router.url(), "dashboard").await;
+    dashboard.subscribe(1, "/mixer/**", Some(QoS::Fire)).await;
+    let mut recorder = Peer::connect(&router.url(), "recorder").await;
+    recorder.subscribe(1, "/mixer/**", Some(QoS::Commit)).await;
+    let mut console = Peer::connect(&router.url(), "console").await;
+    console.subscribe(1, "/mixer/**", None).await;
+
+    let writer = router.connect_client_named("fader-bank").await.unwrap();
+    writer.set("/mixer/1/gain", 0.5).await.unwrap();
+
+    // Params default to Confirm; each subscriber gets the QoS it asked for
+    let is_set = |msg: &Message| matches!(msg, Message::Set(_));
+    assert_eq!(dashboard.until(is_set).await.1, QoS::Fire);
+    assert_eq!(recorder.until(is_set).await.1, QoS::Commit);
+    assert_eq!(console.until(is_set).await.1, QoS::Confirm);
+
+    // Streams default to Fire and keep it without an override
+    writer.stream("/mixer/1/meter", 0.25).await.unwrap();
+    let is_stream =
+        |msg: &Message| matches!(msg, Message::Publish(p) if p.signal == Some(SignalType::Stream));
+    assert_eq!(recorder.until(is_stream).await.1, QoS::Commit);
+    assert_eq!(console.until(is_stream).await.1, QoS::Fire);
+}
+
+#[tokio::test]
+async fn test_overlapping_subscriptions_keep_publisher_qos() {
+    let router = TestRouter::start().await;
+
+    // One subscription without an override is enough to keep the default
+    let mut monitor = Peer::connect(&router.url(), "monitor").await;
+    monitor.subscribe(1, "/lights/**", Some(QoS::Fire)).await;
+    monitor.subscribe(2, "/lights/1/*", None).await;
+
+    let writer = router.connect_client_named("console").await.unwrap();
+    writer.set("/lights/1/level", 1.0).await.unwrap();
+    let (_, qos) = monitor.until(|msg| matches!(msg, Message::Set(_))).await;
+    assert_eq!(qos, QoS::Confirm);
+
+    writer.set("/lights/2/level", 1.0).await.unwrap();
+    let (msg, qos) = monitor.until(|msg| matches!(msg, Message::Set(_))).await;
+    let Message::Set(set) = msg else {
+        unreachable!()
+    };
+    assert_eq!(set.address, "/lights/2/level");
+    assert_eq!(qos, QoS::Fire);
+}
@@ -0,0 +1,151 @@
+//! Session re-authentication tests (`/$sys/session/token`)
+
+use clasp_client::{Clasp, ClientError};
+use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo, Value};
+use clasp_router::{Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for};
+use std::sync::Arc;
+use std::time::Duration;
+
+const SHORT: &str = "cpsk_short_lived";
+const LONG: &str = "cpsk_long_lived";
+const READ_ONLY: &str = "cpsk_read_only";
+
+/// Start an authenticated router where `SHORT` expires after one second
+async fn start_router() -> String {
+    let validator = CpskValidator::new();
+    let write = || vec![Scope::parse("write:/**").unwrap()];
+    validator.register(
+        SHORT.to_string(),
+        TokenInfo::new(SHORT.to_string(), write()).with_expires_in(Duration::from_secs(1)),
+    );
+    validator.register(LONG.to_string(), TokenInfo::new(LONG.to_string(), write()));
+    validator.register(
+        READ_ONLY.to_string(),
+        TokenInfo::new(
+            READ_ONLY.to_string(),
+            vec![Scope::parse("read:/**").unwrap()],
+        ),
+    );
+
+    let router = Arc::new(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            ..Default::default()
+        })
+        .with_validator(validator),
+    );
+
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    tokio::spawn(async move { router.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    format!("ws://127.0.0.1:{}", port)
+}
+
+async fn try_connect(url: &str, token: &str) -> clasp_client::Result<Clasp> {
+    Clasp::builder(url)
+        .token(token)
+        .reconnect(false)
+        .connect()
+        .await
+}
+
+async fn connect(url: &str, token: &str) -> Clasp {
+    try_connect(url, token).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_reauthenticate_before_expiry() {
+    let url = start_router().await;
+    let client = connect(&url, SHORT).await;
+
+    client.reauthenticate(LONG).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    // The original token has expired but the session carries the new one
+    client.set("/lights/1", 0.5).await.unwrap();
+    assert_eq!(client.get("/lights/1").await.unwrap(), Value::Float(0.5));
+    assert!(client.is_connected());
+    assert!(client.last_error().is_none());
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_reauthenticate_swaps_scopes() {
+    let url = start_router().await;
+    let client = connect(&url, LONG).await;
+
+    client.reauthenticate(READ_ONLY).await.unwrap();
+    client.set("/lights/1", 1.0).await.unwrap();
+
+    assert!(
+        wait_for(
+            || async { client.last_error().is_some() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert_eq!(client.last_error().unwrap().code, 301);
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_invalid_token_keeps_session() {
+    let url = start_router().await;
+    let client = connect(&url, LONG).await;
+
+    let result = client.reauthenticate("cpsk_unknown").await;
+    assert!(matches!(result, Err(ClientError::AuthFailed(_))));
+
+    // Still authorized with the original token
+    client.set("/lights/1", 0.25).await.unwrap();
+    assert_eq!(client.get("/lights/1").await.unwrap(), Value::Float(0.25));
+    assert!(client.is_connected());
+
+    client.close().await;
+}
+
+#[tokio::test]
+async fn test_expired_session_disconnected() {
+    let url = start_router().await;
+    let client = connect(&url, SHORT).await;
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    client.set("/lights/1", 0.5).await.unwrap();
+
+    assert!(
+        wait_for(
+            || async { !client.is_connected() },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await,
+        "session was not closed after its token expired"
+    );
+    assert_eq!(client.last_error().map(|e| e.code), Some(302));
+}
+
+#[tokio::test]
+async fn test_rejected_connect_is_typed() {
+    let url = start_router().await;
+
+    let result = try_connect(&url, "cpsk_unknown").await;
+    assert!(matches!(result, Err(ClientError::AuthFailed(_))));
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let result = try_connect(&url, SHORT).await;
+    assert!(matches!(result, Err(ClientError::TokenExpired)));
+}
@@ -6,7 +6,9 @@
 //! - Message routing
 //! - Subscription handling
 
-use clasp_core::{codec, HelloMessage, Message, SecurityMode, SetMessage, SubscribeMessage, Value};
+use clasp_core::{
+    codec, ErrorCode, HelloMessage, Message, SecurityMode, SetMessage, SubscribeMessage, Value,
+};
 use clasp_router::{Router, RouterConfig};
 use std::time::Duration;
 use tokio::time::timeout;
@@ -157,6 +159,7 @@ mod websocket_tests {
             features: vec!["param".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
         let hello_bytes = codec::encode(&hello).unwrap();
         sender.send(hello_bytes).await.unwrap();
@@ -217,6 +220,7 @@ mod websocket_tests {
             features: vec!["param".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
         sender.send(codec::encode(&hello).unwrap()).await.unwrap();
 
@@ -248,6 +252,8 @@ mod websocket_tests {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         sender.send(codec::encode(&set).unwrap()).await.unwrap();
 
@@ -311,6 +317,7 @@ mod websocket_tests {
                 features: vec!["param".to_string()],
                 capabilities: None,
                 token: None,
+                since: None,
             });
             sender.send(codec::encode(&hello).unwrap()).await.unwrap();
 
@@ -339,6 +346,7 @@ mod websocket_tests {
             pattern: "/test/**".to_string(),
             types: vec![],
             options: None,
+            correlation_id: None,
         });
         sender1
             .send(codec::encode(&subscribe).unwrap())
@@ -354,6 +362,8 @@ mod websocket_tests {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         sender2.send(codec::encode(&set).unwrap()).await.unwrap();
 
@@ -432,6 +442,7 @@ mod p2p_tests {
             features: vec!["param".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         });
         sender.send(codec::encode(&hello).unwrap()).await.unwrap();
 
@@ -489,6 +500,7 @@ mod p2p_tests {
             pattern: format!("{}{}", P2P_SIGNAL_PREFIX, session_b),
             types: vec![],
             options: None,
+            correlation_id: None,
         });
         sender_b
             .send(codec::encode(&subscribe).unwrap())
@@ -608,7 +620,7 @@ mod p2p_tests {
             "Should receive error for nonexistent session"
         );
         let err = error.unwrap().unwrap();
-        assert_eq!(err.code, 404);
+        assert_eq!(err.error_code(), Some(ErrorCode::AddressNotFound));
 
         router_handle.abort();
     }
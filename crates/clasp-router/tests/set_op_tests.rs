@@ -0,0 +1,92 @@
+//! Relative SET tests
+//!
+//! A SET with an `op` is applied by the router to the value it holds; the
+//! ACK carries the result and subscribers see it as a plain SET.
+
+use clasp_client::{ClientError, SetOp};
+use clasp_core::{ErrorCode, Value};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_concurrent_increments() {
+    let router = TestRouter::start().await;
+
+    let console = router.connect_client_named("Console").await.unwrap();
+    console.set_confirmed("/mixer/gain", 0).await.unwrap();
+
+    let watcher = router.connect_client_named("Watcher").await.unwrap();
+    let changes = ValueCollector::new();
+    watcher
+        .subscribe("/mixer/gain", changes.callback_ref())
+        .await
+        .unwrap();
+    assert!(changes.wait_for_count(1, Duration::from_secs(2)).await);
+
+    // Two encoder knobs ticking the same param at once lose no ticks
+    let mut knobs = Vec::new();
+    for name in ["KnobA", "KnobB"] {
+        let knob = Arc::new(router.connect_client_named(name).await.unwrap());
+        knobs.push(tokio::spawn(async move {
+            for _ in 0..50 {
+                knob.increment("/mixer/gain", 1).await.unwrap();
+            }
+        }));
+    }
+    for knob in knobs {
+        knob.await.unwrap();
+    }
+
+    assert_eq!(
+        console.increment("/mixer/gain", -10).await.unwrap(),
+        Value::Int(90)
+    );
+    assert_eq!(
+        console
+            .set_op("/mixer/gain", SetOp::Mul, 0.5)
+            .await
+            .unwrap(),
+        Value::Float(45.0)
+    );
+
+    // Subscribers only ever see absolute values
+    assert!(changes.wait_for_count(103, Duration::from_secs(2)).await);
+    assert_eq!(
+        changes.values_for("/mixer/gain").last(),
+        Some(&Value::Float(45.0))
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_and_errors() {
+    let router = TestRouter::start().await;
+    let client = router.connect_client_named("Console").await.unwrap();
+
+    client.set_confirmed("/fx/bypass", false).await.unwrap();
+    assert_eq!(
+        client.toggle("/fx/bypass").await.unwrap(),
+        Value::Bool(true)
+    );
+    assert_eq!(
+        client.toggle("/fx/bypass").await.unwrap(),
+        Value::Bool(false)
+    );
+
+    // Nothing to add to
+    assert!(matches!(
+        client.increment("/fx/missing", 1).await,
+        Err(ClientError::InvalidAddress(_))
+    ));
+
+    // Incompatible types leave the value alone
+    assert_eq!(
+        client
+            .increment("/fx/bypass", 1)
+            .await
+            .unwrap_err()
+            .error_code(),
+        Some(ErrorCode::InvalidValue)
+    );
+    assert_eq!(client.get("/fx/bypass").await.unwrap(), Value::Bool(false));
+}
@@ -0,0 +1,170 @@
+//! Snapshot diff tests
+//!
+//! A client that knows the state revision it last synced to can ask, in HELLO
+//! or SUBSCRIBE, for only what changed since; routers fall back to a full
+//! snapshot when they can't diff that far back.
+
+use clasp_client::{Clasp, ClaspBuilder};
+use clasp_core::{
+    codec, HelloMessage, Message, SnapshotMessage, SubscribeMessage, SubscribeOptions, Value,
+};
+use clasp_test_utils::TestRouter;
+use clasp_transport::websocket::{WebSocketReceiver, WebSocketSender};
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Connect raw, sending `since` in HELLO, and return the connect-time snapshot
+async fn hello(
+    router: &TestRouter,
+    since: Option<u64>,
+) -> (WebSocketSender, WebSocketReceiver, SnapshotMessage) {
+    let (sender, mut receiver) = WebSocketTransport::connect(&router.url()).await.unwrap();
+    let hello = Message::Hello(HelloMessage {
+        version: 2,
+        name: "Raw".to_string(),
+        features: vec!["param".to_string()],
+        capabilities: None,
+        token: None,
+        since,
+    });
+    sender.send(codec::encode(&hello).unwrap()).await.unwrap();
+    let snapshot = next_snapshot(&mut receiver).await;
+    (sender, receiver, snapshot)
+}
+
+async fn next_snapshot(receiver: &mut WebSocketReceiver) -> SnapshotMessage {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let Some(TransportEvent::Data(data)) = receiver.recv().await else {
+                continue;
+            };
+            if let (Message::Snapshot(snapshot), _) = codec::decode(&data).unwrap() {
+                return snapshot;
+            }
+        }
+    })
+    .await
+    .expect("no snapshot arrived")
+}
+
+fn addresses(snapshot: &SnapshotMessage) -> Vec<&str> {
+    let mut addresses: Vec<&str> = snapshot.params.iter().map(|p| p.address.as_str()).collect();
+    addresses.sort();
+    addresses
+}
+
+async fn populate(router: &TestRouter) -> Clasp {
+    let writer = router.connect_client_named("Writer").await.unwrap();
+    writer.set("/diff/a", 1).await.unwrap();
+    writer.set("/diff/b", 2).await.unwrap();
+    writer.set("/diff/c", 3).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    writer
+}
+
+#[tokio::test]
+async fn test_hello_since_gets_changes_only() {
+    let router = TestRouter::start().await;
+    let writer = populate(&router).await;
+
+    let (_sender, _receiver, full) = hello(&router, None).await;
+    let sync = full.sync.expect("snapshot should carry its revision");
+    assert_eq!(sync.since, None);
+    assert_eq!(addresses(&full), vec!["/diff/a", "/diff/b", "/diff/c"]);
+
+    writer.set("/diff/b", 20).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (_sender, _receiver, diff) = hello(&router, Some(sync.revision)).await;
+    let diff_sync = diff.sync.unwrap();
+    assert_eq!(diff_sync.since, Some(sync.revision));
+    assert!(diff_sync.revision > sync.revision);
+    assert_eq!(addresses(&diff), vec!["/diff/b"]);
+    assert_eq!(diff.params[0].value, Value::Int(20));
+}
+
+#[tokio::test]
+async fn test_hello_since_unchanged_is_empty() {
+    let router = TestRouter::start().await;
+    let _writer = populate(&router).await;
+
+    let (_sender, _receiver, full) = hello(&router, None).await;
+    let revision = full.sync.unwrap().revision;
+
+    let (_sender, _receiver, diff) = hello(&router, Some(revision)).await;
+    assert!(diff.params.is_empty());
+    assert_eq!(diff.sync.unwrap().since, Some(revision));
+}
+
+#[tokio::test]
+async fn test_subscribe_since_gets_changes_only() {
+    let router = TestRouter::start().await;
+    let writer = populate(&router).await;
+
+    let (sender, mut receiver, full) = hello(&router, None).await;
+    let revision = full.sync.unwrap().revision;
+
+    writer.set("/diff/a", 10).await.unwrap();
+    writer.set("/other/x", 1).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let subscribe = Message::Subscribe(SubscribeMessage {
+        id: 1,
+        pattern: "/diff/**".to_string(),
+        types: vec![],
+        options: Some(SubscribeOptions {
+            since: Some(revision),
+            ..Default::default()
+        }),
+        correlation_id: None,
+    });
+    sender
+        .send(codec::encode(&subscribe).unwrap())
+        .await
+        .unwrap();
+
+    let diff = next_snapshot(&mut receiver).await;
+    assert_eq!(addresses(&diff), vec!["/diff/a"]);
+    assert_eq!(diff.sync.unwrap().since, Some(revision));
+}
+
+#[tokio::test]
+async fn test_unknown_revision_falls_back_to_full() {
+    let router = TestRouter::start().await;
+    let _writer = populate(&router).await;
+
+    // Older than anything this router has kept, e.g. from before a restart
+    let (_sender, _receiver, stale) = hello(&router, Some(1)).await;
+    assert_eq!(stale.sync.unwrap().since, None);
+    assert_eq!(addresses(&stale), vec!["/diff/a", "/diff/b", "/diff/c"]);
+
+    // Newer than the router's own revision
+    let (_sender, _receiver, ahead) = hello(&router, Some(u64::MAX)).await;
+    assert_eq!(ahead.sync.unwrap().since, None);
+    assert_eq!(addresses(&ahead).len(), 3);
+}
+
+#[tokio::test]
+async fn test_client_tracks_synced_revision() {
+    let router = TestRouter::start().await;
+    let _writer = populate(&router).await;
+
+    let reader = ClaspBuilder::new(&router.url())
+        .name("Reader")
+        .diff_resync(true)
+        .connect()
+        .await
+        .unwrap();
+
+    let synced = clasp_test_utils::wait_for(
+        || async { reader.synced_revision().is_some() },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    assert!(synced, "client never recorded a revision");
+    assert_eq!(reader.cached("/diff/c"), Some(Value::Int(3)));
+}
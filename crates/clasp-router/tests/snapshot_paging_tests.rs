@@ -0,0 +1,157 @@
+//! Snapshot paging tests
+//!
+//! Snapshots too large for one frame are sent as numbered pages; the client
+//! reassembles them, or applies them page by page when asked to.
+
+use clasp_client::{Clasp, ClaspBuilder};
+use clasp_core::{codec, HelloMessage, Message, SetMessage, SnapshotPage, SubscribeMessage, Value};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use clasp_transport::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const PARAM_COUNT: usize = 3000;
+
+fn address(i: usize) -> String {
+    format!("/paged/namespace/with/a/fairly/long/prefix/param/{}", i)
+}
+
+/// Fill the router with enough state to need several pages
+async fn populate(router: &TestRouter) -> Clasp {
+    let writer = router.connect_client_named("Writer").await.unwrap();
+    let messages: Vec<Message> = (0..PARAM_COUNT)
+        .map(|i| {
+            Message::Set(SetMessage {
+                address: address(i),
+                value: Value::Int(i as i64),
+                revision: None,
+                lock: false,
+                unlock: false,
+                op: None,
+                correlation_id: None,
+            })
+        })
+        .collect();
+    for chunk in messages.chunks(500) {
+        writer.bundle_confirmed(chunk.to_vec()).await.unwrap();
+    }
+    writer
+}
+
+#[tokio::test]
+async fn test_large_snapshot_is_paged() {
+    let router = TestRouter::start().await;
+    let _writer = populate(&router).await;
+
+    let (sender, mut receiver) = WebSocketTransport::connect(&router.url()).await.unwrap();
+    let hello = Message::Hello(HelloMessage {
+        version: 2,
+        name: "Raw".to_string(),
+        features: vec!["param".to_string()],
+        capabilities: None,
+        token: None,
+        since: None,
+    });
+    sender.send(codec::encode(&hello).unwrap()).await.unwrap();
+    let subscribe = Message::Subscribe(SubscribeMessage {
+        id: 1,
+        pattern: "/paged/**".to_string(),
+        types: vec![],
+        options: None,
+        correlation_id: None,
+    });
+
+    // Skip WELCOME and the initial snapshot sent with it
+    let mut pages: Vec<SnapshotPage> = Vec::new();
+    let mut params = 0;
+    let mut subscribed = false;
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let Some(TransportEvent::Data(data)) = receiver.recv().await else {
+                continue;
+            };
+            let (Message::Snapshot(snapshot), _) = codec::decode(&data).unwrap() else {
+                continue;
+            };
+            let page = snapshot.page.expect("large snapshot should be paged");
+            if !subscribed {
+                if !page.more {
+                    sender
+                        .send(codec::encode(&subscribe).unwrap())
+                        .await
+                        .unwrap();
+                    subscribed = true;
+                }
+                continue;
+            }
+            pages.push(page);
+            params += snapshot.params.len();
+            if !page.more {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("snapshot pages did not arrive");
+
+    assert!(pages.len() > 1, "expected several pages");
+    for (i, page) in pages.iter().enumerate() {
+        assert_eq!(page.sequence, i as u32);
+    }
+    assert_eq!(params, PARAM_COUNT);
+}
+
+#[tokio::test]
+async fn test_client_reassembles_pages() {
+    let router = TestRouter::start().await;
+    let _writer = populate(&router).await;
+
+    let reader = router.connect_client_named("Reader").await.unwrap();
+    let collector = ValueCollector::new();
+    reader
+        .subscribe("/paged/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    assert!(
+        collector
+            .wait_for_count(PARAM_COUNT as u32, Duration::from_secs(5))
+            .await,
+        "received {}/{}",
+        collector.count(),
+        PARAM_COUNT
+    );
+    assert_eq!(
+        reader.cached(&address(PARAM_COUNT - 1)),
+        Some(Value::Int(2999))
+    );
+}
+
+#[tokio::test]
+async fn test_client_incremental_pages() {
+    let router = TestRouter::start().await;
+    let _writer = populate(&router).await;
+
+    let reader = ClaspBuilder::new(&router.url())
+        .name("Reader")
+        .incremental_snapshots(true)
+        .connect()
+        .await
+        .unwrap();
+    let collector = ValueCollector::new();
+    reader
+        .subscribe("/paged/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    assert!(
+        collector
+            .wait_for_count(PARAM_COUNT as u32, Duration::from_secs(5))
+            .await,
+        "received {}/{}",
+        collector.count(),
+        PARAM_COUNT
+    );
+}
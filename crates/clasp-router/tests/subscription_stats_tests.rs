@@ -0,0 +1,114 @@
+//! Subscription statistics tests (`/$sys/stats/subscriptions`)
+
+use clasp_client::Clasp;
+use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo, Value};
+use clasp_router::{Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for, ValueCollector};
+use std::sync::Arc;
+use std::time::Duration;
+
+const STATS: &str = "/$sys/stats/subscriptions";
+
+async fn start_router(router: Router) -> (Arc<Router>, String) {
+    let router = Arc::new(router);
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    let serving = Arc::clone(&router);
+    tokio::spawn(async move { serving.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    (router, format!("ws://127.0.0.1:{}", port))
+}
+
+async fn connect(url: &str, token: Option<&str>) -> Clasp {
+    let mut builder = Clasp::builder(url).reconnect(false);
+    if let Some(token) = token {
+        builder = builder.token(token);
+    }
+    builder.connect().await.unwrap()
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> &'a Value {
+    match value {
+        Value::Map(map) => map.get(key).unwrap(),
+        other => panic!("not a map: {:?}", other),
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    match field(value, key) {
+        Value::Array(items) => items,
+        other => panic!("not an array: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_stats_count_pattern_hits() {
+    let (router, url) = start_router(Router::default()).await;
+    let listener = connect(&url, None).await;
+    let collector = ValueCollector::new();
+    listener
+        .subscribe("/mixer/**", collector.callback_ref())
+        .await
+        .unwrap();
+    listener
+        .subscribe("/unused/**", collector.callback_ref())
+        .await
+        .unwrap();
+
+    let producer = connect(&url, None).await;
+    for i in 0..3 {
+        producer.set_with_ack("/mixer/1/gain", i).await.unwrap();
+    }
+    assert!(collector.wait_for_count(3, Duration::from_secs(2)).await);
+
+    let stats = router.subscription_stats(10);
+    assert_eq!(stats.subscriptions, 2);
+    assert_eq!(stats.patterns[0].pattern, "/mixer/**");
+    assert_eq!(stats.patterns[0].hits, 3);
+    assert_eq!(stats.orphans, vec!["/unused/**".to_string()]);
+    assert_eq!(stats.hot_addresses[0].address, "/mixer/1/gain");
+    assert_eq!(stats.hot_addresses[0].messages, 3);
+
+    // The same report over the protocol
+    let value = producer.get(STATS).await.unwrap();
+    assert_eq!(field(&value, "subscriptions"), &Value::Int(2));
+    let patterns = array(&value, "patterns");
+    assert_eq!(field(&patterns[0], "pattern"), &Value::from("/mixer/**"));
+    assert_eq!(field(&patterns[0], "hits"), &Value::Int(3));
+    assert_eq!(array(&value, "orphans"), &[Value::from("/unused/**")]);
+    let hot = array(&value, "hot_addresses");
+    assert_eq!(field(&hot[0], "address"), &Value::from("/mixer/1/gain"));
+}
+
+#[tokio::test]
+async fn test_stats_require_admin_scope() {
+    let validator = CpskValidator::new();
+    for (token, scope) in [("cpsk_admin", "admin:/**"), ("cpsk_reader", "read:/**")] {
+        validator.register(
+            token.to_string(),
+            TokenInfo::new(token.to_string(), vec![Scope::parse(scope).unwrap()]),
+        );
+    }
+    let router = Router::new(RouterConfig {
+        security_mode: SecurityMode::Authenticated,
+        ..Default::default()
+    })
+    .with_validator(validator);
+    let (_router, url) = start_router(router).await;
+
+    let reader = connect(&url, Some("cpsk_reader")).await;
+    assert!(reader.get(STATS).await.is_err());
+
+    let admin = connect(&url, Some("cpsk_admin")).await;
+    let value = admin.get(STATS).await.unwrap();
+    assert_eq!(field(&value, "sessions"), &Value::Int(0));
+}
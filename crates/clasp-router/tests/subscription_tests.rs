@@ -32,6 +32,7 @@ async fn connect_and_handshake(url: &str, name: &str) -> (WebSocketSender, WebSo
         features: vec!["param".to_string(), "event".to_string()],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender.send(codec::encode(&hello).unwrap()).await.unwrap();
 
@@ -73,6 +74,7 @@ async fn test_exact_match_subscription() {
         pattern: "/exact/path".to_string(),
         types: vec![],
         options: None,
+        correlation_id: None,
     });
     sub_sender
         .send(codec::encode(&subscribe).unwrap())
@@ -88,6 +90,8 @@ async fn test_exact_match_subscription() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     pub_sender
         .send(codec::encode(&set1).unwrap())
@@ -101,6 +105,8 @@ async fn test_exact_match_subscription() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     pub_sender
         .send(codec::encode(&set2).unwrap())
@@ -160,6 +166,7 @@ async fn test_single_wildcard_subscription() {
         pattern: "/sensors/*/temperature".to_string(),
         types: vec![],
         options: None,
+        correlation_id: None,
     });
     sub_sender
         .send(codec::encode(&subscribe).unwrap())
@@ -177,6 +184,8 @@ async fn test_single_wildcard_subscription() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
@@ -211,6 +220,7 @@ async fn test_multi_wildcard_subscription() {
         pattern: "/house/**".to_string(),
         types: vec![],
         options: None,
+        correlation_id: None,
     });
     sub_sender
         .send(codec::encode(&subscribe).unwrap())
@@ -236,6 +246,8 @@ async fn test_multi_wildcard_subscription() {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .unwrap(),
             )
@@ -286,6 +298,7 @@ async fn test_unsubscribe() {
                 pattern: "/test/**".to_string(),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
@@ -303,6 +316,8 @@ async fn test_unsubscribe() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
@@ -340,6 +355,8 @@ async fn test_unsubscribe() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
@@ -382,6 +399,7 @@ async fn test_multiple_subscriptions() {
                     pattern: pattern.to_string(),
                     types: vec![],
                     options: None,
+                    correlation_id: None,
                 }))
                 .unwrap(),
             )
@@ -401,6 +419,8 @@ async fn test_multiple_subscriptions() {
                     revision: None,
                     lock: false,
                     unlock: false,
+                    op: None,
+                    correlation_id: None,
                 }))
                 .unwrap(),
             )
@@ -445,6 +465,8 @@ async fn test_subscription_initial_snapshot() {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
@@ -470,6 +492,7 @@ async fn test_subscription_initial_snapshot() {
                 pattern: "/snapshot/**".to_string(),
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
@@ -513,6 +536,7 @@ async fn test_invalid_subscription_pattern() {
                 pattern: "".to_string(), // Invalid
                 types: vec![],
                 options: None,
+                correlation_id: None,
             }))
             .unwrap(),
         )
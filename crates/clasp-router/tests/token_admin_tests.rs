@@ -0,0 +1,186 @@
+//! Runtime token management tests (`/$sys/tokens`)
+
+use clasp_client::Clasp;
+use clasp_core::{CpskValidator, SecurityMode, Value};
+use clasp_router::{token_admin, Router, RouterConfig};
+use clasp_test_utils::{find_available_port, wait_for};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const ADMIN: &str = "cpsk_admin";
+const GUEST: &str = "cpsk_guest_controller";
+
+fn temp_token_file(contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("clasp-token-admin-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("tokens.txt");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Start an authenticated router loading `token_file`; returns its URL
+async fn start_router(token_file: &Path) -> String {
+    let validator = CpskValidator::new();
+    token_admin::load_token_file(token_file, &validator).unwrap();
+    let router = Arc::new(
+        Router::new(RouterConfig {
+            security_mode: SecurityMode::Authenticated,
+            ..Default::default()
+        })
+        .with_validator(validator)
+        .with_token_file(token_file),
+    );
+
+    let port = find_available_port().await;
+    let addr = format!("127.0.0.1:{}", port);
+    tokio::spawn(async move { router.serve_websocket(&addr).await });
+    wait_for(
+        || async move {
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .is_ok()
+        },
+        Duration::from_millis(10),
+        Duration::from_secs(5),
+    )
+    .await;
+    format!("ws://127.0.0.1:{}", port)
+}
+
+async fn connect(url: &str, token: &str) -> clasp_client::Result<Clasp> {
+    Clasp::builder(url)
+        .token(token)
+        .reconnect(false)
+        .connect()
+        .await
+}
+
+#[tokio::test]
+async fn test_admin_adds_and_revokes_token() {
+    let file = temp_token_file(&format!("{} admin:/**\n", ADMIN));
+    let url = start_router(&file).await;
+    let admin = connect(&url, ADMIN).await.unwrap();
+
+    // Unknown token is rejected
+    assert!(connect(&url, GUEST).await.is_err());
+
+    // Add it at runtime
+    admin
+        .set(
+            &format!("/$sys/tokens/{}", GUEST),
+            Value::String("write:/lights/**".into()),
+        )
+        .await
+        .unwrap();
+
+    let guest = {
+        let mut connected = None;
+        for _ in 0..50 {
+            if let Ok(client) = connect(&url, GUEST).await {
+                connected = Some(client);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        connected.expect("guest token was not registered")
+    };
+
+    let info = admin.get(&format!("/$sys/tokens/{}", GUEST)).await.unwrap();
+    let Value::Map(info) = info else {
+        panic!("expected token map, got {:?}", info);
+    };
+    assert_eq!(
+        info.get("scopes"),
+        Some(&Value::Array(vec![Value::String(
+            "write:/lights/**".into()
+        )]))
+    );
+
+    // Persisted to the token file
+    let reloaded = CpskValidator::new();
+    token_admin::load_token_file(&file, &reloaded).unwrap();
+    assert!(reloaded.exists(GUEST));
+    assert!(reloaded.exists(ADMIN));
+
+    // Revoking disconnects the guest and removes it from the file
+    admin
+        .set(&format!("/$sys/tokens/{}", GUEST), Value::Null)
+        .await
+        .unwrap();
+    assert!(
+        wait_for(
+            || async { !guest.is_connected() },
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await,
+        "guest session was not closed after revocation"
+    );
+    assert!(connect(&url, GUEST).await.is_err());
+
+    let reloaded = CpskValidator::new();
+    token_admin::load_token_file(&file, &reloaded).unwrap();
+    assert!(!reloaded.exists(GUEST));
+
+    admin.close().await;
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_non_admin_cannot_manage_tokens() {
+    let file = temp_token_file(&format!("{} admin:/**\ncpsk_writer write:/**\n", ADMIN));
+    let url = start_router(&file).await;
+    let writer = connect(&url, "cpsk_writer").await.unwrap();
+
+    writer
+        .set(
+            &format!("/$sys/tokens/{}", GUEST),
+            Value::String("admin:/**".into()),
+        )
+        .await
+        .unwrap();
+
+    assert!(
+        wait_for(
+            || async { writer.last_error().is_some() },
+            Duration::from_millis(10),
+            Duration::from_secs(2),
+        )
+        .await
+    );
+    assert_eq!(writer.last_error().unwrap().code, 301);
+    assert!(connect(&url, GUEST).await.is_err());
+
+    writer.close().await;
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_expiring_tokens_not_persisted() {
+    let file = temp_token_file(&format!("{}\n", ADMIN));
+    let url = start_router(&file).await;
+    let admin = connect(&url, ADMIN).await.unwrap();
+
+    let mut map = std::collections::HashMap::new();
+    map.insert("scopes".to_string(), Value::String("read:/**".into()));
+    map.insert("expires_in".to_string(), Value::Int(3600));
+    admin
+        .set(&format!("/$sys/tokens/{}", GUEST), Value::Map(map))
+        .await
+        .unwrap();
+
+    let info = admin.get(&format!("/$sys/tokens/{}", GUEST)).await.unwrap();
+    let Value::Map(info) = info else {
+        panic!("expected token map, got {:?}", info);
+    };
+    assert!(info.contains_key("expires_at"));
+
+    let reloaded = CpskValidator::new();
+    token_admin::load_token_file(&file, &reloaded).unwrap();
+    assert!(reloaded.exists(ADMIN));
+    assert!(!reloaded.exists(GUEST));
+
+    admin.close().await;
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,93 @@
+//! Wildcard write tests
+//!
+//! A SET to a pattern is applied to each retained address it matches, up to
+//! the configured cap; with wildcard writes disabled it is rejected.
+
+use clasp_client::ClientError;
+use clasp_core::{ErrorCode, Value};
+use clasp_router::{RouterConfig, WildcardWrites};
+use clasp_test_utils::{TestRouter, ValueCollector};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_set_matching_fans_out() {
+    let router = TestRouter::start_with_config(RouterConfig {
+        wildcard_writes: WildcardWrites::new(8),
+        ..Default::default()
+    })
+    .await;
+
+    let renderer = router.connect_client_named("Renderer").await.unwrap();
+    let changes = ValueCollector::new();
+    renderer
+        .subscribe("/lumen/**", changes.callback_ref())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let console = router.connect_client_named("Console").await.unwrap();
+    for layer in 0..3 {
+        console
+            .set_confirmed(&format!("/lumen/scene/0/layer/{}/opacity", layer), 1.0)
+            .await
+            .unwrap();
+    }
+    console
+        .set_confirmed("/lumen/scene/0/layer/0/blend", "add")
+        .await
+        .unwrap();
+
+    console
+        .set_matching("/lumen/scene/0/layer/*/opacity", 0.0)
+        .await
+        .unwrap();
+
+    // Subscribers see one SET per concrete address
+    assert!(changes.wait_for_count(7, Duration::from_secs(2)).await);
+    for layer in 0..3 {
+        let address = format!("/lumen/scene/0/layer/{}/opacity", layer);
+        assert_eq!(
+            changes.values_for(&address).last(),
+            Some(&Value::Float(0.0))
+        );
+        assert_eq!(renderer.get(&address).await.unwrap(), Value::Float(0.0));
+    }
+    assert_eq!(
+        changes.values_for("/lumen/scene/0/layer/0/blend"),
+        vec![Value::String("add".to_string())]
+    );
+
+    // Nothing is created for a pattern that matches nothing
+    console.set_matching("/lumen/scene/9/*", 1.0).await.unwrap();
+    assert!(renderer.get("/lumen/scene/9/*").await.is_err());
+
+    // Over the cap, nothing is applied
+    for i in 0..9 {
+        console
+            .set_confirmed(&format!("/cues/{}", i), 1)
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        console
+            .set_matching("/cues/*", 0)
+            .await
+            .unwrap_err()
+            .error_code(),
+        Some(ErrorCode::LimitExceeded)
+    );
+    assert_eq!(renderer.get("/cues/0").await.unwrap(), Value::Int(1));
+}
+
+#[tokio::test]
+async fn test_disabled_by_default() {
+    let router = TestRouter::start().await;
+    let client = router.connect_client_named("Console").await.unwrap();
+    client.set_confirmed("/lights/1", 1.0).await.unwrap();
+
+    assert!(matches!(
+        client.set_matching("/lights/*", 0.0).await,
+        Err(ClientError::InvalidAddress(_))
+    ));
+    assert_eq!(client.get("/lights/1").await.unwrap(), Value::Float(1.0));
+}
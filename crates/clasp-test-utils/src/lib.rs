@@ -6,6 +6,11 @@
 //! - Strong assertion helpers
 //! - Test router management
 //! - Value collectors for subscription testing
+//! - A scripted mock router for unit-testing client code
+
+mod mock_router;
+
+pub use mock_router::{Faults, MockRouter, MockRouterBuilder, Reply};
 
 use clasp_client::Clasp;
 use clasp_core::{SecurityMode, Value};
@@ -113,6 +118,18 @@ impl TestRouter {
             max_messages_per_second: 0, // Disable rate limiting for tests
             rate_limiting_enabled: false,
             state_config: clasp_router::RouterStateConfig::unlimited(), // No TTL in tests
+            enforce_features: false,
+            session_ids: clasp_router::SessionIdStrategy::default(),
+            name_collision: clasp_router::NameCollision::default(),
+            duplicate_sessions: clasp_router::DuplicateSessions::default(),
+            keepalive: None,
+            max_channels: 16,
+            dedup: clasp_router::SetDedup::default(),
+            memory_budget: clasp_router::MemoryBudget::default(),
+            loop_detection: clasp_router::LoopDetection::default(),
+            wildcard_writes: clasp_router::WildcardWrites::default(),
+            validate_manifests: false,
+            connection_guard: clasp_router::ConnectionGuard::default(),
         })
         .await
     }
@@ -0,0 +1,579 @@
+//! Programmable in-process mock router
+//!
+//! [`MockRouter`] speaks just enough of the protocol to unit-test client code
+//! without a real router: it welcomes every HELLO, ACKs SETs, answers GET and
+//! SUBSCRIBE from its own param table and PINGs with PONG. Scripted replies
+//! override any of that (e.g. reject a SET with an ERROR or never answer a
+//! GET), fault injection delays or drops what it sends, and every message a
+//! client sends is recorded for assertions.
+//!
+//! The mock doesn't route between clients; push messages to them with
+//! [`MockRouter::send`] or [`MockRouter::set`] instead.
+
+use bytes::Bytes;
+use clasp_core::{
+    address::glob_match, codec, AckMessage, ErrorCode, ErrorMessage, Message, ParamValue,
+    SetMessage, SnapshotMessage, SyncMessage, Value, WelcomeMessage, PROTOCOL_VERSION,
+};
+use clasp_transport::{
+    TransportEvent, TransportReceiver, TransportSender, TransportServer, WebSocketServer,
+};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
+
+use crate::find_available_port;
+
+/// What the mock answers a client message with
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// The mock's own answer (WELCOME, ACK, SNAPSHOT, PONG or nothing)
+    Default,
+    /// No answer at all, e.g. to make a request time out
+    Ignore,
+    /// An ERROR, carrying the request's address and correlation id
+    Error(ErrorCode, String),
+    /// These messages, sent as they are
+    Messages(Vec<Message>),
+}
+
+/// Delays and losses applied to everything the mock sends
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Faults {
+    /// Added to every message
+    pub latency: Duration,
+    /// Up to this much more, chosen per message; order is kept
+    pub jitter: Duration,
+    /// Fraction of messages silently dropped (0.0-1.0)
+    pub drop_rate: f64,
+}
+
+type Responder = Box<dyn Fn(&Message) -> Option<Reply> + Send + Sync>;
+
+/// Builder for a [`MockRouter`]
+pub struct MockRouterBuilder {
+    name: String,
+    params: Vec<(String, Value)>,
+    responders: Vec<Responder>,
+    faults: Faults,
+    seed: u64,
+}
+
+impl MockRouterBuilder {
+    /// Name sent in WELCOME
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Start with `address` holding `value`
+    pub fn param(mut self, address: &str, value: impl Into<Value>) -> Self {
+        self.params.push((address.to_string(), value.into()));
+        self
+    }
+
+    /// Answer messages `responder` returns a reply for
+    ///
+    /// Responders are asked in the order they were added; the first
+    /// `Some` wins, and messages none of them answer get the default reply.
+    pub fn respond<F>(mut self, responder: F) -> Self
+    where
+        F: Fn(&Message) -> Option<Reply> + Send + Sync + 'static,
+    {
+        self.responders.push(Box::new(responder));
+        self
+    }
+
+    /// Reject HELLOs with an ERROR
+    pub fn reject_hello(self, code: ErrorCode, message: &str) -> Self {
+        let reply = Reply::Error(code, message.to_string());
+        self.respond(move |msg| matches!(msg, Message::Hello(_)).then(|| reply.clone()))
+    }
+
+    /// Answer SETs to addresses matching `pattern` with `reply`
+    pub fn on_set(self, pattern: &str, reply: Reply) -> Self {
+        let pattern = pattern.to_string();
+        self.respond(move |msg| match msg {
+            Message::Set(set) if glob_match(&pattern, &set.address) => Some(reply.clone()),
+            _ => None,
+        })
+    }
+
+    /// Answer GETs of addresses matching `pattern` with `reply`
+    pub fn on_get(self, pattern: &str, reply: Reply) -> Self {
+        let pattern = pattern.to_string();
+        self.respond(move |msg| match msg {
+            Message::Get(get) if glob_match(&pattern, &get.address) => Some(reply.clone()),
+            _ => None,
+        })
+    }
+
+    /// Delay and drop what the mock sends
+    pub fn faults(mut self, faults: Faults) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// Seed for jitter and drops, so a run can be repeated exactly
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Start listening
+    pub async fn start(self) -> MockRouter {
+        let port = find_available_port().await;
+        let mut server = WebSocketServer::bind(&format!("127.0.0.1:{}", port))
+            .await
+            .expect("mock router failed to bind");
+
+        let state = Arc::new(MockState {
+            name: self.name,
+            params: Mutex::new(
+                self.params
+                    .into_iter()
+                    .map(|(address, value)| (address, (value, 1)))
+                    .collect(),
+            ),
+            responders: self.responders,
+            faults: Mutex::new(self.faults),
+            rng: Mutex::new(self.seed.max(1)),
+            received: Mutex::new(Vec::new()),
+            arrived: Notify::new(),
+            clients: Mutex::new(HashMap::new()),
+            next_client: Mutex::new(0),
+        });
+
+        let accept_state = Arc::clone(&state);
+        let handle = tokio::spawn(async move {
+            while let Ok((sender, receiver, _)) = server.accept().await {
+                accept_state.serve(sender, receiver);
+            }
+        });
+
+        MockRouter {
+            port,
+            handle: Some(handle),
+            state,
+        }
+    }
+}
+
+/// Frame queued for a client, or the order to hang up
+enum Outgoing {
+    Frame(Instant, Bytes),
+    Close,
+}
+
+struct MockState {
+    name: String,
+    /// Address -> (value, revision)
+    params: Mutex<HashMap<String, (Value, u64)>>,
+    responders: Vec<Responder>,
+    faults: Mutex<Faults>,
+    rng: Mutex<u64>,
+    received: Mutex<Vec<Message>>,
+    arrived: Notify,
+    clients: Mutex<HashMap<u32, mpsc::UnboundedSender<Outgoing>>>,
+    next_client: Mutex<u32>,
+}
+
+impl MockState {
+    fn serve<S, R>(self: &Arc<Self>, sender: S, mut receiver: R)
+    where
+        S: TransportSender + 'static,
+        R: TransportReceiver + 'static,
+    {
+        let id = {
+            let mut next = self.next_client.lock();
+            *next += 1;
+            *next
+        };
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        self.clients.lock().insert(id, tx);
+
+        // Frames go out one at a time, so jitter never reorders them
+        tokio::spawn(async move {
+            while let Some(outgoing) = rx.recv().await {
+                match outgoing {
+                    Outgoing::Frame(due, data) => {
+                        tokio::time::sleep_until(due.into()).await;
+                        if sender.send(data).await.is_err() {
+                            break;
+                        }
+                    }
+                    Outgoing::Close => {
+                        let _ = sender.close().await;
+                        break;
+                    }
+                }
+            }
+        });
+
+        let state = Arc::clone(self);
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    TransportEvent::Data(data) => {
+                        let Ok((message, _)) = codec::decode(&data) else {
+                            continue;
+                        };
+                        let replies = state.answer(id, &message);
+                        state.received.lock().push(message);
+                        state.arrived.notify_waiters();
+                        for reply in replies {
+                            state.send_to(id, &reply);
+                        }
+                    }
+                    TransportEvent::Disconnected { .. } => break,
+                    _ => {}
+                }
+            }
+            state.clients.lock().remove(&id);
+        });
+    }
+
+    fn answer(&self, client: u32, message: &Message) -> Vec<Message> {
+        let reply = self
+            .responders
+            .iter()
+            .find_map(|responder| responder(message))
+            .unwrap_or(Reply::Default);
+
+        match reply {
+            Reply::Default => self.default_reply(client, message),
+            Reply::Ignore => vec![],
+            Reply::Error(code, text) => {
+                let mut error = ErrorMessage::new(code, text);
+                error.correlation_id = correlation_id(message);
+                if let Some(address) = address(message) {
+                    error = error.with_address(address);
+                }
+                vec![Message::Error(error)]
+            }
+            Reply::Messages(messages) => messages,
+        }
+    }
+
+    fn default_reply(&self, client: u32, message: &Message) -> Vec<Message> {
+        match message {
+            Message::Hello(hello) => {
+                let welcome = Message::Welcome(WelcomeMessage {
+                    version: PROTOCOL_VERSION,
+                    session: format!("mock-{}", client),
+                    name: self.name.clone(),
+                    features: hello.features.clone(),
+                    time: clasp_core::time::now(),
+                    token: None,
+                });
+                vec![welcome, Message::Snapshot(self.snapshot("/**", None))]
+            }
+            Message::Set(set) => {
+                let revision = {
+                    let mut params = self.params.lock();
+                    let entry = params
+                        .entry(set.address.clone())
+                        .or_insert((Value::Null, 0));
+                    *entry = (set.value.clone(), entry.1 + 1);
+                    entry.1
+                };
+                vec![Message::Ack(AckMessage {
+                    address: Some(set.address.clone()),
+                    revision: Some(revision),
+                    locked: None,
+                    holder: None,
+                    value: None,
+                    correlation_id: set.correlation_id,
+                })]
+            }
+            Message::Get(get) => vec![Message::Snapshot(
+                self.snapshot(&get.address, get.correlation_id),
+            )],
+            Message::Subscribe(subscribe) => vec![Message::Snapshot(
+                self.snapshot(&subscribe.pattern, subscribe.correlation_id),
+            )],
+            Message::Ping => vec![Message::Pong],
+            Message::Sync(sync) => {
+                let now = clasp_core::time::now();
+                vec![Message::Sync(SyncMessage {
+                    t1: sync.t1,
+                    t2: Some(now),
+                    t3: Some(now),
+                })]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn snapshot(&self, pattern: &str, correlation_id: Option<u32>) -> SnapshotMessage {
+        let params = self
+            .params
+            .lock()
+            .iter()
+            .filter(|(address, _)| glob_match(pattern, address))
+            .map(|(address, (value, revision))| ParamValue {
+                address: address.clone(),
+                value: value.clone(),
+                revision: *revision,
+                writer: None,
+                timestamp: None,
+            })
+            .collect();
+        SnapshotMessage {
+            params,
+            correlation_id,
+            page: None,
+            sync: None,
+        }
+    }
+
+    /// Queue `message` for client `id`, subject to the configured faults
+    fn send_to(&self, id: u32, message: &Message) {
+        let Ok(data) = codec::encode(message) else {
+            return;
+        };
+        let faults = *self.faults.lock();
+        if faults.drop_rate > 0.0 && self.random() < faults.drop_rate {
+            return;
+        }
+        let jitter = faults.jitter.mul_f64(self.random());
+        let due = Instant::now() + faults.latency + jitter;
+        if let Some(client) = self.clients.lock().get(&id) {
+            let _ = client.send(Outgoing::Frame(due, data));
+        }
+    }
+
+    /// Next number in [0, 1) from a seeded xorshift
+    fn random(&self) -> f64 {
+        let mut state = self.rng.lock();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn correlation_id(message: &Message) -> Option<u32> {
+    match message {
+        Message::Set(set) => set.correlation_id,
+        Message::Get(get) => get.correlation_id,
+        Message::Subscribe(subscribe) => subscribe.correlation_id,
+        Message::Bundle(bundle) => bundle.correlation_id,
+        _ => None,
+    }
+}
+
+fn address(message: &Message) -> Option<&str> {
+    match message {
+        Message::Set(set) => Some(&set.address),
+        Message::Get(get) => Some(&get.address),
+        Message::Publish(publish) => Some(&publish.address),
+        Message::Subscribe(subscribe) => Some(&subscribe.pattern),
+        _ => None,
+    }
+}
+
+/// A scripted stand-in for a router, cleaned up on drop
+pub struct MockRouter {
+    port: u16,
+    handle: Option<tokio::task::JoinHandle<()>>,
+    state: Arc<MockState>,
+}
+
+impl MockRouter {
+    /// Start a mock with the default replies and no faults
+    pub async fn start() -> Self {
+        Self::builder().start().await
+    }
+
+    /// Configure a mock before starting it
+    pub fn builder() -> MockRouterBuilder {
+        MockRouterBuilder {
+            name: "Mock Router".to_string(),
+            params: Vec::new(),
+            responders: Vec::new(),
+            faults: Faults::default(),
+            seed: 0x5eed,
+        }
+    }
+
+    /// Get the WebSocket URL for this mock
+    pub fn url(&self) -> String {
+        format!("ws://127.0.0.1:{}", self.port)
+    }
+
+    /// Get the port number
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Number of connected clients
+    pub fn connections(&self) -> usize {
+        self.state.clients.lock().len()
+    }
+
+    /// Change the faults applied from now on
+    pub fn set_faults(&self, faults: Faults) {
+        *self.state.faults.lock() = faults;
+    }
+
+    /// Send `message` to every connected client
+    pub fn send(&self, message: &Message) {
+        let ids: Vec<u32> = self.state.clients.lock().keys().copied().collect();
+        for id in ids {
+            self.state.send_to(id, message);
+        }
+    }
+
+    /// Store `value` at `address` and send the SET to every connected client,
+    /// as if another client had written it
+    pub fn set(&self, address: &str, value: impl Into<Value>) {
+        let value = value.into();
+        let revision = {
+            let mut params = self.state.params.lock();
+            let entry = params
+                .entry(address.to_string())
+                .or_insert((Value::Null, 0));
+            *entry = (value.clone(), entry.1 + 1);
+            entry.1
+        };
+        self.send(&Message::Set(SetMessage {
+            address: address.to_string(),
+            value,
+            revision: Some(revision),
+            lock: false,
+            unlock: false,
+            op: None,
+            correlation_id: None,
+        }));
+    }
+
+    /// Current value at `address`
+    pub fn param(&self, address: &str) -> Option<Value> {
+        self.state
+            .params
+            .lock()
+            .get(address)
+            .map(|(value, _)| value.clone())
+    }
+
+    /// Hang up on every client, e.g. to exercise reconnects
+    pub fn disconnect_all(&self) {
+        for client in self.state.clients.lock().drain().map(|(_, tx)| tx) {
+            let _ = client.send(Outgoing::Close);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Received messages
+    // ------------------------------------------------------------------------
+
+    /// Every message received so far, oldest first
+    pub fn received(&self) -> Vec<Message> {
+        self.state.received.lock().clone()
+    }
+
+    /// Received messages `filter` accepts
+    pub fn received_matching<F>(&self, filter: F) -> Vec<Message>
+    where
+        F: Fn(&Message) -> bool,
+    {
+        self.state
+            .received
+            .lock()
+            .iter()
+            .filter(|message| filter(message))
+            .cloned()
+            .collect()
+    }
+
+    /// SETs received so far
+    pub fn sets(&self) -> Vec<SetMessage> {
+        self.state
+            .received
+            .lock()
+            .iter()
+            .filter_map(|message| match message {
+                Message::Set(set) => Some(set.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forget everything received so far
+    pub fn clear(&self) {
+        self.state.received.lock().clear();
+    }
+
+    /// Wait for a received message `filter` accepts, including ones that
+    /// arrived before the call
+    pub async fn wait_for<F>(&self, filter: F, max_wait: Duration) -> Option<Message>
+    where
+        F: Fn(&Message) -> bool,
+    {
+        let deadline = Instant::now() + max_wait;
+        loop {
+            let arrived = self.state.arrived.notified();
+            tokio::pin!(arrived);
+            arrived.as_mut().enable();
+
+            if let Some(message) = self
+                .state
+                .received
+                .lock()
+                .iter()
+                .find(|message| filter(message))
+            {
+                return Some(message.clone());
+            }
+            if tokio::time::timeout_at(deadline.into(), arrived)
+                .await
+                .is_err()
+            {
+                return None;
+            }
+        }
+    }
+
+    /// Assert a message `filter` accepts arrives within `max_wait`
+    pub async fn assert_received<F>(
+        &self,
+        filter: F,
+        max_wait: Duration,
+        msg: &str,
+    ) -> Result<Message, String>
+    where
+        F: Fn(&Message) -> bool,
+    {
+        self.wait_for(filter, max_wait)
+            .await
+            .ok_or_else(|| format!("{}: not received within {:?}", msg, max_wait))
+    }
+
+    /// Assert no received message so far is accepted by `filter`
+    pub fn assert_not_received<F>(&self, filter: F, msg: &str) -> Result<(), String>
+    where
+        F: Fn(&Message) -> bool,
+    {
+        match self.received_matching(filter).first() {
+            Some(message) => Err(format!("{}: received {:?}", msg, message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop the mock explicitly (also happens on drop)
+    pub fn stop(&mut self) {
+        self.disconnect_all();
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for MockRouter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
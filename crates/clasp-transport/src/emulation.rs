@@ -0,0 +1,527 @@
+//! Network emulation for testing on bad links
+//!
+//! [`EmulatedTransport::wrap`] puts a connection of any transport behind an
+//! emulated link, and [`EmulatedServer`] does the same for every connection
+//! a server accepts (e.g. `router.serve_on(EmulatedServer::new(server,
+//! profile))`). A [`NetworkProfile`] sets the link's behaviour in each
+//! direction:
+//!
+//! - `latency` is added to every frame, plus up to `jitter` more drawn from
+//!   the profile's [`Jitter`] distribution
+//! - `loss` is the fraction of frames silently dropped
+//! - `bandwidth` caps throughput; frames queue behind each other on the link
+//!
+//! Frames keep their order, as they would on a stream transport: a frame
+//! never overtakes one sent before it. Give the profile a seed to repeat a
+//! run's jitter and losses exactly.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::error::{Result, TransportError};
+use crate::traits::{
+    Transport, TransportEvent, TransportReceiver, TransportSender, TransportServer,
+};
+
+/// Shape of the extra delay added on top of a profile's latency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    /// Anywhere from nothing to the full jitter, evenly
+    #[default]
+    Uniform,
+    /// Mostly small, rarely the full jitter (half-normal, capped at 3 sigma)
+    Normal,
+    /// Mostly small with occasional spikes well past the jitter, like
+    /// Wi-Fi retransmissions (Pareto, capped at 10x)
+    Pareto,
+}
+
+/// Behaviour of an emulated link, applied to each direction separately
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkProfile {
+    /// Delay added to every frame
+    pub latency: Duration,
+    /// Scale of the extra, per-frame delay
+    pub jitter: Duration,
+    /// Distribution the extra delay is drawn from
+    pub distribution: Jitter,
+    /// Fraction of frames dropped (0.0-1.0)
+    pub loss: f64,
+    /// Bytes per second the link carries (`None` = unlimited)
+    pub bandwidth: Option<u64>,
+    /// Seed for jitter and losses (`None` = different every run)
+    pub seed: Option<u64>,
+}
+
+impl NetworkProfile {
+    /// A perfect link: no delay, loss or limit
+    pub const fn ideal() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            distribution: Jitter::Uniform,
+            loss: 0.0,
+            bandwidth: None,
+            seed: None,
+        }
+    }
+
+    /// Wired LAN
+    pub const fn lan() -> Self {
+        Self {
+            latency: Duration::from_micros(500),
+            jitter: Duration::from_micros(200),
+            ..Self::ideal()
+        }
+    }
+
+    /// Healthy Wi-Fi
+    pub const fn wifi() -> Self {
+        Self {
+            latency: Duration::from_millis(5),
+            jitter: Duration::from_millis(10),
+            distribution: Jitter::Pareto,
+            loss: 0.001,
+            ..Self::ideal()
+        }
+    }
+
+    /// Congested Wi-Fi at the back of a venue
+    pub const fn bad_wifi() -> Self {
+        Self {
+            latency: Duration::from_millis(30),
+            jitter: Duration::from_millis(50),
+            distribution: Jitter::Pareto,
+            loss: 0.02,
+            bandwidth: Some(250_000),
+            seed: None,
+        }
+    }
+
+    /// Mobile data
+    pub const fn cellular() -> Self {
+        Self {
+            latency: Duration::from_millis(50),
+            jitter: Duration::from_millis(30),
+            distribution: Jitter::Normal,
+            loss: 0.005,
+            bandwidth: Some(1_000_000),
+            seed: None,
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration, distribution: Jitter) -> Self {
+        self.jitter = jitter;
+        self.distribution = distribution;
+        self
+    }
+
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_bandwidth(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth = Some(bytes_per_second.max(1));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl Default for NetworkProfile {
+    fn default() -> Self {
+        Self::ideal()
+    }
+}
+
+/// One direction of an emulated link
+struct Link {
+    profile: NetworkProfile,
+    rng: u64,
+    /// When the link has finished transmitting what's queued
+    free_at: Instant,
+    /// When the last frame arrives, so later ones never overtake it
+    last_arrival: Instant,
+}
+
+impl Link {
+    fn new(profile: NetworkProfile, stream: u64) -> Self {
+        let seed = profile.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        let now = Instant::now();
+        Self {
+            profile,
+            // Distinct, non-zero state per direction
+            rng: (seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15)) | 1,
+            free_at: now,
+            last_arrival: now,
+        }
+    }
+
+    /// When a frame of `len` bytes sent now arrives, or `None` if it's lost
+    fn schedule(&mut self, len: usize) -> Option<Instant> {
+        if self.profile.loss > 0.0 && self.random() < self.profile.loss {
+            return None;
+        }
+
+        let now = Instant::now();
+        let sent = match self.profile.bandwidth {
+            Some(bandwidth) => {
+                let transmit = Duration::from_secs_f64(len as f64 / bandwidth as f64);
+                self.free_at = self.free_at.max(now) + transmit;
+                self.free_at
+            }
+            None => now,
+        };
+
+        let arrival = (sent + self.profile.latency + self.jitter()).max(self.last_arrival);
+        self.last_arrival = arrival;
+        Some(arrival)
+    }
+
+    fn jitter(&mut self) -> Duration {
+        if self.profile.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        let factor = match self.profile.distribution {
+            Jitter::Uniform => self.random(),
+            Jitter::Normal => {
+                // Box-Muller, folded to the positive half
+                let u1 = self.random().max(f64::MIN_POSITIVE);
+                let u2 = self.random();
+                let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                (z.abs() / 3.0).min(1.0)
+            }
+            Jitter::Pareto => {
+                // Shape 2, shifted to start at zero: median ~0.4
+                let u = self.random().max(f64::MIN_POSITIVE);
+                (1.0 / u.sqrt() - 1.0).min(10.0)
+            }
+        };
+        self.profile.jitter.mul_f64(factor)
+    }
+
+    /// Next number in [0, 1) from a xorshift
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Outbound frame on its way over the link
+enum Outbound {
+    Frame {
+        arrival: Instant,
+        data: Bytes,
+        expires_at: Option<Instant>,
+    },
+    Close,
+}
+
+/// Sender side of an emulated connection
+///
+/// Sends return as soon as the frame is on the link; it reaches the inner
+/// sender once its emulated delay has passed.
+pub struct EmulatedSender<S> {
+    inner: Arc<S>,
+    link: parking_lot::Mutex<Link>,
+    tx: mpsc::UnboundedSender<Outbound>,
+}
+
+impl<S: TransportSender + 'static> EmulatedSender<S> {
+    fn new(inner: S, profile: NetworkProfile) -> Self {
+        let inner = Arc::new(inner);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let writer = Arc::clone(&inner);
+        tokio::spawn(async move {
+            while let Some(outbound) = rx.recv().await {
+                match outbound {
+                    Outbound::Frame {
+                        arrival,
+                        data,
+                        expires_at,
+                    } => {
+                        tokio::time::sleep_until(arrival.into()).await;
+                        let sent = match expires_at {
+                            Some(expires_at) => writer.try_send_expiring(data, expires_at),
+                            None => writer.send(data).await,
+                        };
+                        if matches!(sent, Err(TransportError::ConnectionClosed)) {
+                            break;
+                        }
+                    }
+                    Outbound::Close => {
+                        let _ = writer.close().await;
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            inner,
+            link: parking_lot::Mutex::new(Link::new(profile, 0)),
+            tx,
+        }
+    }
+
+    fn enqueue(&self, data: Bytes, expires_at: Option<Instant>) -> Result<()> {
+        if !self.inner.is_connected() {
+            return Err(TransportError::ConnectionClosed);
+        }
+        let Some(arrival) = self.link.lock().schedule(data.len()) else {
+            return Ok(());
+        };
+        self.tx
+            .send(Outbound::Frame {
+                arrival,
+                data,
+                expires_at,
+            })
+            .map_err(|_| TransportError::ConnectionClosed)
+    }
+
+    /// The wrapped sender
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<S: TransportSender + 'static> TransportSender for EmulatedSender<S> {
+    async fn send(&self, data: Bytes) -> Result<()> {
+        self.enqueue(data, None)
+    }
+
+    fn try_send(&self, data: Bytes) -> Result<()> {
+        self.enqueue(data, None)
+    }
+
+    fn try_send_expiring(&self, data: Bytes, expires_at: Instant) -> Result<()> {
+        self.enqueue(data, Some(expires_at))
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    async fn close(&self) -> Result<()> {
+        // Goes over the link too, after whatever is still in flight
+        let _ = self.tx.send(Outbound::Close);
+        Ok(())
+    }
+}
+
+/// Receiver side of an emulated connection
+pub struct EmulatedReceiver {
+    rx: mpsc::UnboundedReceiver<TransportEvent>,
+}
+
+impl EmulatedReceiver {
+    fn new<R: TransportReceiver + 'static>(mut inner: R, profile: NetworkProfile) -> Self {
+        let (delayed_tx, mut delayed_rx) = mpsc::unbounded_channel::<(Instant, TransportEvent)>();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut link = Link::new(profile, 1);
+            while let Some(event) = inner.recv().await {
+                let arrival = match &event {
+                    TransportEvent::Data(data) => match link.schedule(data.len()) {
+                        Some(arrival) => arrival,
+                        None => continue,
+                    },
+                    // Connection events aren't lost, but don't overtake data
+                    _ => link.last_arrival.max(Instant::now()),
+                };
+                if delayed_tx.send((arrival, event)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            while let Some((arrival, event)) = delayed_rx.recv().await {
+                tokio::time::sleep_until(arrival.into()).await;
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { rx }
+    }
+}
+
+#[async_trait]
+impl TransportReceiver for EmulatedReceiver {
+    async fn recv(&mut self) -> Option<TransportEvent> {
+        self.rx.recv().await
+    }
+}
+
+/// Wraps connections of any transport in an emulated link
+///
+/// ```ignore
+/// let (sender, receiver) = EmulatedTransport::wrap(
+///     WebSocketTransport::connect("ws://localhost:7330").await?,
+///     NetworkProfile::bad_wifi().with_seed(7),
+/// );
+/// ```
+pub struct EmulatedTransport;
+
+impl EmulatedTransport {
+    /// Put an open connection behind a link with `profile`
+    pub fn wrap<S, R>(
+        (sender, receiver): (S, R),
+        profile: NetworkProfile,
+    ) -> (EmulatedSender<S>, EmulatedReceiver)
+    where
+        S: TransportSender + 'static,
+        R: TransportReceiver + 'static,
+    {
+        // The receive direction gets a seed of its own so both don't mirror
+        // each other's losses
+        let inbound = NetworkProfile {
+            seed: profile.seed.map(|seed| seed.wrapping_add(1)),
+            ..profile
+        };
+        (
+            EmulatedSender::new(sender, profile),
+            EmulatedReceiver::new(receiver, inbound),
+        )
+    }
+
+    /// Connect with `T` and wrap the connection
+    pub async fn connect<T: Transport>(
+        addr: &str,
+        profile: NetworkProfile,
+    ) -> Result<(EmulatedSender<T::Sender>, EmulatedReceiver)>
+    where
+        T::Sender: 'static,
+        T::Receiver: 'static,
+    {
+        Ok(Self::wrap(T::connect(addr).await?, profile))
+    }
+}
+
+/// Server whose accepted connections all sit behind an emulated link
+pub struct EmulatedServer<S> {
+    inner: S,
+    profile: NetworkProfile,
+    accepted: u64,
+}
+
+impl<S: TransportServer> EmulatedServer<S> {
+    pub fn new(inner: S, profile: NetworkProfile) -> Self {
+        Self {
+            inner,
+            profile,
+            accepted: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> TransportServer for EmulatedServer<S>
+where
+    S: TransportServer,
+    S::Sender: 'static,
+    S::Receiver: 'static,
+{
+    type Sender = EmulatedSender<S::Sender>;
+    type Receiver = EmulatedReceiver;
+
+    async fn accept(&mut self) -> Result<(Self::Sender, Self::Receiver, SocketAddr)> {
+        let (sender, receiver, addr) = self.inner.accept().await?;
+        // Each connection gets its own, still reproducible, randomness
+        self.accepted += 1;
+        let profile = NetworkProfile {
+            seed: self
+                .profile
+                .seed
+                .map(|seed| seed.wrapping_add(self.accepted << 1)),
+            ..self.profile
+        };
+        let (sender, receiver) = EmulatedTransport::wrap((sender, receiver), profile);
+        Ok((sender, receiver, addr))
+    }
+
+    fn local_addr(&self) -> Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_keeps_order_and_applies_latency() {
+        let profile = NetworkProfile::ideal()
+            .with_latency(Duration::from_millis(20))
+            .with_jitter(Duration::from_millis(50), Jitter::Pareto)
+            .with_seed(3);
+        let mut link = Link::new(profile, 0);
+        let start = Instant::now();
+
+        let mut last = start;
+        for _ in 0..200 {
+            let arrival = link.schedule(100).unwrap();
+            assert!(arrival >= last);
+            assert!(arrival >= start + Duration::from_millis(20));
+            last = arrival;
+        }
+    }
+
+    #[test]
+    fn test_link_loss_is_seeded() {
+        let profile = NetworkProfile::ideal().with_loss(0.3).with_seed(11);
+        let run = || {
+            let mut link = Link::new(profile, 0);
+            (0..1000)
+                .map(|_| link.schedule(10).is_some())
+                .collect::<Vec<_>>()
+        };
+        let first = run();
+        assert_eq!(first, run());
+
+        let lost = first.iter().filter(|delivered| !**delivered).count();
+        assert!((200..400).contains(&lost), "lost {}", lost);
+    }
+
+    #[test]
+    fn test_link_bandwidth_queues_frames() {
+        // 10 KB/s: each 1 KB frame takes 100ms on the link
+        let profile = NetworkProfile::ideal().with_bandwidth(10_000);
+        let mut link = Link::new(profile, 0);
+        let start = Instant::now();
+
+        link.schedule(1000).unwrap();
+        let fifth = (0..4).map(|_| link.schedule(1000).unwrap()).last().unwrap();
+        assert!(fifth >= start + Duration::from_millis(500));
+    }
+}
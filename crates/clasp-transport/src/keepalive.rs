@@ -0,0 +1,228 @@
+//! Keepalive and idle detection
+//!
+//! Connection-oriented transports (WebSocket, TCP, QUIC) share one
+//! [`KeepaliveConfig`]. When a connection has received nothing for
+//! `interval`, a ping goes out; if no traffic arrives within `timeout` the
+//! ping counts as missed, and after `max_missed` consecutive misses the
+//! connection is reported as `Disconnected` and closed.
+//!
+//! How the ping is sent depends on the transport:
+//! - WebSocket: protocol ping frames (answered automatically by the peer)
+//! - TCP: single-byte application ping/pong frames
+//! - QUIC: native keep-alive, with the idle timeout derived from this config
+
+use std::time::Duration;
+
+/// Keepalive settings for a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Idle time before a ping is sent (`None` disables keepalive)
+    pub interval: Option<Duration>,
+    /// How long to wait for traffic after a ping
+    pub timeout: Duration,
+    /// Consecutive missed pings before the connection is considered dead
+    pub max_missed: u32,
+}
+
+impl KeepaliveConfig {
+    /// Ping after `interval` of silence, with the default timeout and misses
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval: Some(interval),
+            timeout: Duration::from_secs(10),
+            max_missed: 2,
+        }
+    }
+
+    /// Never ping; dead connections are only noticed when a write fails
+    pub const fn disabled() -> Self {
+        Self {
+            interval: None,
+            timeout: Duration::from_secs(10),
+            max_missed: 2,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_missed(mut self, max_missed: u32) -> Self {
+        self.max_missed = max_missed.max(1);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    /// Longest a silent peer can go unnoticed
+    ///
+    /// Returns `None` when keepalive is disabled.
+    pub fn dead_after(&self) -> Option<Duration> {
+        self.interval
+            .map(|interval| interval + self.timeout * self.max_missed.max(1))
+    }
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+/// What a connection should do after a keepalive tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeepaliveTick {
+    /// Nothing to do
+    Idle,
+    /// Send a ping
+    Ping,
+    /// Too many pings went unanswered; close the connection
+    Dead,
+}
+
+/// Per-connection keepalive state, driven from a transport's IO loop
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct Keepalive {
+    config: KeepaliveConfig,
+    ticker: Option<tokio::time::Interval>,
+    last_rx: tokio::time::Instant,
+    ping_sent: Option<tokio::time::Instant>,
+    missed: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Keepalive {
+    pub(crate) fn new(config: KeepaliveConfig) -> Self {
+        // Tick often enough to notice both idleness and a late pong
+        let ticker = config.interval.map(|interval| {
+            let period = (interval.min(config.timeout) / 2).max(Duration::from_millis(10));
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker
+        });
+        Self {
+            config,
+            ticker,
+            last_rx: tokio::time::Instant::now(),
+            ping_sent: None,
+            missed: 0,
+        }
+    }
+
+    /// Record inbound traffic of any kind
+    pub(crate) fn received(&mut self) {
+        self.last_rx = tokio::time::Instant::now();
+        self.ping_sent = None;
+        self.missed = 0;
+    }
+
+    /// Wait for the next tick; never completes when keepalive is disabled
+    pub(crate) async fn tick(&mut self) -> KeepaliveTick {
+        match self.ticker.as_mut() {
+            Some(ticker) => {
+                ticker.tick().await;
+                self.poll(tokio::time::Instant::now())
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    fn poll(&mut self, now: tokio::time::Instant) -> KeepaliveTick {
+        let Some(interval) = self.config.interval else {
+            return KeepaliveTick::Idle;
+        };
+
+        if let Some(sent) = self.ping_sent {
+            if now.duration_since(sent) < self.config.timeout {
+                return KeepaliveTick::Idle;
+            }
+            self.missed += 1;
+            if self.missed >= self.config.max_missed.max(1) {
+                self.ping_sent = None;
+                return KeepaliveTick::Dead;
+            }
+            self.ping_sent = Some(now);
+            return KeepaliveTick::Ping;
+        }
+
+        if now.duration_since(self.last_rx) >= interval {
+            self.ping_sent = Some(now);
+            return KeepaliveTick::Ping;
+        }
+
+        KeepaliveTick::Idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    fn keepalive(interval_ms: u64, timeout_ms: u64, max_missed: u32) -> Keepalive {
+        Keepalive::new(
+            KeepaliveConfig::new(Duration::from_millis(interval_ms))
+                .with_timeout(Duration::from_millis(timeout_ms))
+                .with_max_missed(max_missed),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pings_only_when_idle() {
+        let mut ka = keepalive(100, 50, 2);
+        let start = Instant::now();
+        assert_eq!(
+            ka.poll(start + Duration::from_millis(50)),
+            KeepaliveTick::Idle
+        );
+        assert_eq!(
+            ka.poll(start + Duration::from_millis(100)),
+            KeepaliveTick::Ping
+        );
+        // Waiting for the pong
+        assert_eq!(
+            ka.poll(start + Duration::from_millis(120)),
+            KeepaliveTick::Idle
+        );
+    }
+
+    #[tokio::test]
+    async fn test_traffic_resets_missed_pings() {
+        let mut ka = keepalive(100, 50, 2);
+        let start = Instant::now();
+        assert_eq!(
+            ka.poll(start + Duration::from_millis(100)),
+            KeepaliveTick::Ping
+        );
+        assert_eq!(
+            ka.poll(start + Duration::from_millis(150)),
+            KeepaliveTick::Ping
+        );
+        ka.received();
+        assert_eq!(ka.missed, 0);
+        assert_eq!(ka.poll(Instant::now()), KeepaliveTick::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_dead_after_max_missed() {
+        let mut ka = keepalive(100, 50, 3);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(ka.poll(at(100)), KeepaliveTick::Ping);
+        assert_eq!(ka.poll(at(150)), KeepaliveTick::Ping);
+        assert_eq!(ka.poll(at(200)), KeepaliveTick::Ping);
+        assert_eq!(ka.poll(at(250)), KeepaliveTick::Dead);
+    }
+
+    #[test]
+    fn test_config() {
+        let config = KeepaliveConfig::default();
+        assert_eq!(config.interval, Some(Duration::from_secs(30)));
+        assert_eq!(config.dead_after(), Some(Duration::from_secs(50)));
+        assert_eq!(KeepaliveConfig::disabled().dead_after(), None);
+        assert_eq!(KeepaliveConfig::default().with_max_missed(0).max_missed, 1);
+    }
+}
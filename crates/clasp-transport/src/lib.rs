@@ -12,10 +12,25 @@
 //! - Serial (direct hardware, lowest latency) - native only
 //! - BLE (Bluetooth Low Energy, wireless controllers) - native only
 //! - WebRTC (P2P, NAT traversal, low-latency)
+//!
+//! [`EmulatedTransport`] wraps any of them in an emulated link with latency,
+//! jitter, loss and bandwidth limits, for testing on bad networks.
 
 pub mod error;
+pub mod keepalive;
 pub mod traits;
 
+// Latency/loss/bandwidth emulation around any transport
+#[cfg(not(target_arch = "wasm32"))]
+pub mod emulation;
+
+// Send queues of connection-oriented transports
+#[cfg(all(
+    any(feature = "websocket", feature = "tcp"),
+    not(target_arch = "wasm32")
+))]
+pub(crate) mod queue;
+
 // Native WebSocket (uses tokio-tungstenite)
 #[cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
 pub mod websocket;
@@ -43,7 +58,12 @@ pub mod ble;
 #[cfg(feature = "webrtc")]
 pub mod webrtc;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use emulation::{
+    EmulatedReceiver, EmulatedSender, EmulatedServer, EmulatedTransport, Jitter, NetworkProfile,
+};
 pub use error::{Result, TransportError};
+pub use keepalive::KeepaliveConfig;
 pub use traits::{Transport, TransportEvent, TransportReceiver, TransportSender, TransportServer};
 
 // Native WebSocket exports
@@ -0,0 +1,63 @@
+//! Send queue entries
+//!
+//! Connection-oriented transports hand data to a writer task through a
+//! bounded queue. When the peer reads slowly the queue fills, and data sent
+//! with [`TransportSender::try_send_expiring`] can sit there past the point
+//! it is useful. The writer drops such data instead of writing it.
+//!
+//! [`TransportSender::try_send_expiring`]: crate::TransportSender::try_send_expiring
+
+use std::time::Instant;
+use tracing::debug;
+
+/// Data waiting for a transport's writer
+#[derive(Debug)]
+pub(crate) struct Queued<T> {
+    data: T,
+    /// Dropped instead of written once this has passed
+    expires_at: Option<Instant>,
+}
+
+impl<T> Queued<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data,
+            expires_at: None,
+        }
+    }
+
+    pub fn expiring(data: T, expires_at: Instant) -> Self {
+        Self {
+            data,
+            expires_at: Some(expires_at),
+        }
+    }
+
+    /// The data, unless it expired while queued
+    pub fn take(self) -> Option<T> {
+        match self.expires_at {
+            Some(expires_at) if Instant::now() >= expires_at => {
+                debug!("Dropping expired frame from send queue");
+                None
+            }
+            _ => Some(self.data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_expired_data_is_dropped() {
+        assert_eq!(Queued::new(1).take(), Some(1));
+
+        let later = Instant::now() + Duration::from_secs(60);
+        assert_eq!(Queued::expiring(2, later).take(), Some(2));
+
+        let earlier = Instant::now() - Duration::from_millis(1);
+        assert_eq!(Queued::expiring(3, earlier).take(), None);
+    }
+}
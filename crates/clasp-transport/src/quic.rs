@@ -20,6 +20,7 @@ use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
 use crate::error::{Result, TransportError};
+use crate::keepalive::KeepaliveConfig;
 use crate::traits::{TransportEvent, TransportReceiver, TransportSender};
 
 #[cfg(feature = "quic")]
@@ -55,7 +56,7 @@ pub struct QuicConfig {
     pub enable_0rtt: bool,
     /// Keep-alive interval in milliseconds (0 to disable)
     pub keep_alive_ms: u64,
-    /// Maximum idle timeout in milliseconds
+    /// Maximum idle timeout in milliseconds (0 to disable)
     pub idle_timeout_ms: u64,
     /// Initial congestion window (packets)
     pub initial_window: u32,
@@ -99,6 +100,21 @@ impl QuicConfig {
             ..Default::default()
         }
     }
+
+    /// Derive keep-alive and idle timeout from a shared keepalive config
+    ///
+    /// QUIC pings natively every `interval`; the connection is closed after
+    /// [`KeepaliveConfig::dead_after`] without traffic. Disabling keepalive
+    /// also disables the idle timeout.
+    pub fn with_keepalive(mut self, keepalive: KeepaliveConfig) -> Self {
+        self.keep_alive_ms = keepalive
+            .interval
+            .map_or(0, |interval| interval.as_millis() as u64);
+        self.idle_timeout_ms = keepalive
+            .dead_after()
+            .map_or(0, |timeout| timeout.as_millis() as u64);
+        self
+    }
 }
 
 /// QUIC transport for CLASP
@@ -271,11 +287,15 @@ impl QuicTransport {
             transport
                 .keep_alive_interval(Some(std::time::Duration::from_millis(config.keep_alive_ms)));
         }
-        transport.max_idle_timeout(Some(
-            std::time::Duration::from_millis(config.idle_timeout_ms)
-                .try_into()
-                .unwrap(),
-        ));
+        if config.idle_timeout_ms > 0 {
+            transport.max_idle_timeout(Some(
+                std::time::Duration::from_millis(config.idle_timeout_ms)
+                    .try_into()
+                    .unwrap(),
+            ));
+        } else {
+            transport.max_idle_timeout(None);
+        }
         client_config.transport_config(Arc::new(transport));
 
         Ok(client_config)
@@ -308,11 +328,15 @@ impl QuicTransport {
             transport
                 .keep_alive_interval(Some(std::time::Duration::from_millis(config.keep_alive_ms)));
         }
-        transport.max_idle_timeout(Some(
-            std::time::Duration::from_millis(config.idle_timeout_ms)
-                .try_into()
-                .unwrap(),
-        ));
+        if config.idle_timeout_ms > 0 {
+            transport.max_idle_timeout(Some(
+                std::time::Duration::from_millis(config.idle_timeout_ms)
+                    .try_into()
+                    .unwrap(),
+            ));
+        } else {
+            transport.max_idle_timeout(None);
+        }
         server_config.transport_config(Arc::new(transport));
 
         Ok(server_config)
@@ -579,6 +603,34 @@ pub struct QuicSender {
     connected: Arc<Mutex<bool>>,
 }
 
+#[cfg(feature = "quic")]
+impl QuicSender {
+    fn spawn_send(&self, data: Bytes, expires_at: Option<std::time::Instant>) -> Result<()> {
+        if !self.is_connected() {
+            return Err(TransportError::NotConnected);
+        }
+
+        // QUIC doesn't have a channel buffer - spawn a task to send asynchronously
+        // This makes the call non-blocking from the caller's perspective
+        let send = Arc::clone(&self.send);
+        let connected = Arc::clone(&self.connected);
+        tokio::spawn(async move {
+            let mut stream = send.lock().await;
+            // Waiting for the stream is this transport's queue
+            if expires_at.is_some_and(|at| std::time::Instant::now() >= at) {
+                debug!("Dropping expired QUIC frame");
+                return;
+            }
+            if let Err(e) = stream.write_all(&data).await {
+                error!("QUIC async send failed: {}", e);
+                *connected.lock() = false;
+            }
+        });
+
+        Ok(())
+    }
+}
+
 #[cfg(feature = "quic")]
 #[async_trait]
 impl TransportSender for QuicSender {
@@ -597,23 +649,11 @@ impl TransportSender for QuicSender {
     }
 
     fn try_send(&self, data: Bytes) -> Result<()> {
-        if !self.is_connected() {
-            return Err(TransportError::NotConnected);
-        }
-
-        // QUIC doesn't have a channel buffer - spawn a task to send asynchronously
-        // This makes the call non-blocking from the caller's perspective
-        let send = Arc::clone(&self.send);
-        let connected = Arc::clone(&self.connected);
-        tokio::spawn(async move {
-            let mut stream = send.lock().await;
-            if let Err(e) = stream.write_all(&data).await {
-                error!("QUIC async send failed: {}", e);
-                *connected.lock() = false;
-            }
-        });
+        self.spawn_send(data, None)
+    }
 
-        Ok(())
+    fn try_send_expiring(&self, data: Bytes, expires_at: std::time::Instant) -> Result<()> {
+        self.spawn_send(data, Some(expires_at))
     }
 
     fn is_connected(&self) -> bool {
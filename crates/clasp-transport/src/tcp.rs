@@ -2,18 +2,25 @@
 //!
 //! Raw TCP transport for CLASP. Uses length-prefixed framing for message boundaries.
 //! Each message is preceded by a 4-byte big-endian length prefix.
+//!
+//! Single-byte frames are keepalive control frames (a CLASP frame is never
+//! shorter than its 4-byte header): [`PING_FRAME`] is answered with
+//! [`PONG_FRAME`], and neither is delivered to the receiver.
 
 use async_trait::async_trait;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use parking_lot::Mutex;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
 use crate::error::{Result, TransportError};
+use crate::keepalive::{Keepalive, KeepaliveConfig, KeepaliveTick};
+use crate::queue::Queued;
 use crate::traits::{TransportEvent, TransportReceiver, TransportSender, TransportServer};
 
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
@@ -24,6 +31,12 @@ const MAX_MESSAGE_SIZE: usize = 64 * 1024;
 /// Default channel buffer size for TCP connections
 const DEFAULT_CHANNEL_BUFFER_SIZE: usize = 1000;
 
+/// Keepalive ping control frame
+pub const PING_FRAME: &[u8] = &[0x00];
+
+/// Keepalive pong control frame
+pub const PONG_FRAME: &[u8] = &[0x01];
+
 /// TCP configuration
 #[derive(Debug, Clone)]
 pub struct TcpConfig {
@@ -31,8 +44,10 @@ pub struct TcpConfig {
     pub max_message_size: usize,
     /// Read buffer size
     pub read_buffer_size: usize,
-    /// Keep-alive interval in seconds (0 = disabled)
+    /// OS-level TCP keep-alive interval in seconds (0 = disabled)
     pub keepalive_secs: u64,
+    /// Application-level ping/pong and idle detection
+    pub keepalive: KeepaliveConfig,
 }
 
 impl Default for TcpConfig {
@@ -41,6 +56,7 @@ impl Default for TcpConfig {
             max_message_size: MAX_MESSAGE_SIZE,
             read_buffer_size: 8192,
             keepalive_secs: 30,
+            keepalive: KeepaliveConfig::default(),
         }
     }
 }
@@ -78,7 +94,8 @@ impl TcpTransport {
         }
 
         let connected = Arc::new(Mutex::new(true));
-        let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<Bytes>(DEFAULT_CHANNEL_BUFFER_SIZE);
+        let (outgoing_tx, mut outgoing_rx) =
+            mpsc::channel::<Queued<Bytes>>(DEFAULT_CHANNEL_BUFFER_SIZE);
         let (incoming_tx, incoming_rx) =
             mpsc::channel::<TransportEvent>(DEFAULT_CHANNEL_BUFFER_SIZE);
 
@@ -90,6 +107,7 @@ impl TcpTransport {
         let receiver = TcpReceiver { rx: incoming_rx };
 
         let max_size = self.config.max_message_size;
+        let keepalive = self.config.keepalive;
         let connected_clone = connected.clone();
 
         // Spawn reader/writer task
@@ -101,6 +119,7 @@ impl TcpTransport {
                 outgoing_rx,
                 incoming_tx,
                 max_size,
+                keepalive,
                 connected_clone,
             )
             .await;
@@ -121,27 +140,45 @@ impl Default for TcpTransport {
 async fn run_tcp_io_loop(
     mut reader: OwnedReadHalf,
     mut writer: OwnedWriteHalf,
-    mut outgoing_rx: mpsc::Receiver<Bytes>,
+    mut outgoing_rx: mpsc::Receiver<Queued<Bytes>>,
     incoming_tx: mpsc::Sender<TransportEvent>,
     max_size: usize,
+    keepalive: KeepaliveConfig,
     connected: Arc<Mutex<bool>>,
 ) {
     let mut read_buf = BytesMut::with_capacity(8192);
+    let mut keepalive = Keepalive::new(keepalive);
 
     loop {
         tokio::select! {
-            Some(data) = outgoing_rx.recv() => {
-                let len = data.len() as u32;
-                let mut frame = BytesMut::with_capacity(4 + data.len());
-                frame.put_u32(len);
-                frame.extend_from_slice(&data);
-
-                if let Err(e) = writer.write_all(&frame).await {
+            Some(queued) = outgoing_rx.recv() => {
+                let Some(data) = queued.take() else {
+                    continue;
+                };
+                if let Err(e) = write_frame(&mut writer, &data).await {
                     error!("TCP write error: {}", e);
                     break;
                 }
             }
 
+            tick = keepalive.tick() => match tick {
+                KeepaliveTick::Idle => {}
+                KeepaliveTick::Ping => {
+                    debug!("Sending keepalive ping");
+                    if let Err(e) = write_frame(&mut writer, PING_FRAME).await {
+                        error!("TCP write error: {}", e);
+                        break;
+                    }
+                }
+                KeepaliveTick::Dead => {
+                    warn!("TCP keepalive timed out");
+                    let _ = incoming_tx.send(TransportEvent::Disconnected {
+                        reason: Some("keepalive timeout".to_string()),
+                    }).await;
+                    break;
+                }
+            },
+
             result = reader.read_buf(&mut read_buf) => {
                 match result {
                     Ok(0) => {
@@ -150,6 +187,7 @@ async fn run_tcp_io_loop(
                         break;
                     }
                     Ok(_) => {
+                        keepalive.received();
                         while read_buf.len() >= 4 {
                             let len = (&read_buf[..4]).get_u32() as usize;
 
@@ -164,6 +202,16 @@ async fn run_tcp_io_loop(
                             if read_buf.len() >= 4 + len {
                                 read_buf.advance(4);
                                 let data = read_buf.split_to(len).freeze();
+                                if data == PING_FRAME {
+                                    if let Err(e) = write_frame(&mut writer, PONG_FRAME).await {
+                                        error!("TCP write error: {}", e);
+                                        break;
+                                    }
+                                    continue;
+                                }
+                                if data == PONG_FRAME {
+                                    continue;
+                                }
                                 if incoming_tx.send(TransportEvent::Data(data)).await.is_err() {
                                     break;
                                 }
@@ -185,9 +233,17 @@ async fn run_tcp_io_loop(
     *connected.lock() = false;
 }
 
+/// Write one length-prefixed frame
+async fn write_frame(writer: &mut OwnedWriteHalf, data: &[u8]) -> std::io::Result<()> {
+    let mut frame = BytesMut::with_capacity(4 + data.len());
+    frame.put_u32(data.len() as u32);
+    frame.extend_from_slice(data);
+    writer.write_all(&frame).await
+}
+
 /// TCP sender for writing messages
 pub struct TcpSender {
-    tx: mpsc::Sender<Bytes>,
+    tx: mpsc::Sender<Queued<Bytes>>,
     connected: Arc<Mutex<bool>>,
 }
 
@@ -199,20 +255,17 @@ impl TransportSender for TcpSender {
         }
 
         self.tx
-            .send(data)
+            .send(Queued::new(data))
             .await
             .map_err(|_| TransportError::SendFailed("Channel closed".into()))
     }
 
     fn try_send(&self, data: Bytes) -> Result<()> {
-        if !*self.connected.lock() {
-            return Err(TransportError::NotConnected);
-        }
+        self.enqueue(Queued::new(data))
+    }
 
-        self.tx.try_send(data).map_err(|e| match e {
-            mpsc::error::TrySendError::Full(_) => TransportError::BufferFull,
-            mpsc::error::TrySendError::Closed(_) => TransportError::ConnectionClosed,
-        })
+    fn try_send_expiring(&self, data: Bytes, expires_at: Instant) -> Result<()> {
+        self.enqueue(Queued::expiring(data, expires_at))
     }
 
     fn is_connected(&self) -> bool {
@@ -225,6 +278,19 @@ impl TransportSender for TcpSender {
     }
 }
 
+impl TcpSender {
+    fn enqueue(&self, data: Queued<Bytes>) -> Result<()> {
+        if !*self.connected.lock() {
+            return Err(TransportError::NotConnected);
+        }
+
+        self.tx.try_send(data).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => TransportError::BufferFull,
+            mpsc::error::TrySendError::Closed(_) => TransportError::ConnectionClosed,
+        })
+    }
+}
+
 /// TCP receiver for reading messages
 pub struct TcpReceiver {
     rx: mpsc::Receiver<TransportEvent>,
@@ -285,7 +351,8 @@ impl TransportServer for TcpServer {
         info!("TCP connection accepted from {}", peer_addr);
 
         let connected = Arc::new(Mutex::new(true));
-        let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<Bytes>(DEFAULT_CHANNEL_BUFFER_SIZE);
+        let (outgoing_tx, mut outgoing_rx) =
+            mpsc::channel::<Queued<Bytes>>(DEFAULT_CHANNEL_BUFFER_SIZE);
         let (incoming_tx, incoming_rx) =
             mpsc::channel::<TransportEvent>(DEFAULT_CHANNEL_BUFFER_SIZE);
 
@@ -297,6 +364,7 @@ impl TransportServer for TcpServer {
         let receiver = TcpReceiver { rx: incoming_rx };
 
         let max_size = self.config.max_message_size;
+        let keepalive = self.config.keepalive;
         let connected_clone = connected.clone();
 
         // Spawn reader/writer task
@@ -309,6 +377,7 @@ impl TransportServer for TcpServer {
                 outgoing_rx,
                 incoming_tx,
                 max_size,
+                keepalive,
                 connected_clone,
             )
             .await;
@@ -3,6 +3,7 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use std::net::SocketAddr;
+use std::time::Instant;
 
 use crate::error::Result;
 
@@ -29,6 +30,16 @@ pub trait TransportSender: Send + Sync {
     /// Returns Ok(()) if sent, Err with BufferFull if the channel is full
     fn try_send(&self, data: Bytes) -> Result<()>;
 
+    /// Try to send data that is stale after `expires_at`
+    ///
+    /// Transports with a send queue drop the data instead of writing it if it
+    /// is still queued at `expires_at`. By default it is sent like
+    /// [`try_send`](Self::try_send).
+    fn try_send_expiring(&self, data: Bytes, expires_at: Instant) -> Result<()> {
+        let _ = expires_at;
+        self.try_send(data)
+    }
+
     /// Check if connected
     fn is_connected(&self) -> bool;
 
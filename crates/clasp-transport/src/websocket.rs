@@ -6,6 +6,7 @@ use futures_util::{SinkExt, StreamExt};
 use parking_lot::Mutex;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tokio_tungstenite::{
     connect_async,
@@ -22,6 +23,8 @@ use tokio_tungstenite::{
 use tracing::{debug, error, info, warn};
 
 use crate::error::{Result, TransportError};
+use crate::keepalive::{Keepalive, KeepaliveConfig, KeepaliveTick};
+use crate::queue::Queued;
 use crate::traits::{
     Transport, TransportEvent, TransportReceiver, TransportSender, TransportServer,
 };
@@ -39,8 +42,8 @@ pub struct WebSocketConfig {
     pub subprotocol: String,
     /// Maximum message size
     pub max_message_size: usize,
-    /// Ping interval in seconds
-    pub ping_interval: u64,
+    /// Ping frames and idle detection
+    pub keepalive: KeepaliveConfig,
     /// Channel buffer size for send/receive queues
     pub channel_buffer_size: usize,
 }
@@ -50,7 +53,7 @@ impl Default for WebSocketConfig {
         Self {
             subprotocol: WS_SUBPROTOCOL.to_string(),
             max_message_size: 64 * 1024, // 64KB
-            ping_interval: 30,
+            keepalive: KeepaliveConfig::default(),
             channel_buffer_size: DEFAULT_CHANNEL_BUFFER_SIZE,
         }
     }
@@ -81,7 +84,7 @@ impl Default for WebSocketTransport {
 
 /// WebSocket sender
 pub struct WebSocketSender {
-    tx: mpsc::Sender<WsMessage>,
+    tx: mpsc::Sender<Queued<WsMessage>>,
     connected: Arc<Mutex<bool>>,
 }
 
@@ -93,22 +96,20 @@ impl TransportSender for WebSocketSender {
         }
 
         self.tx
-            .send(WsMessage::Binary(data.to_vec()))
+            .send(Queued::new(WsMessage::Binary(data.to_vec())))
             .await
             .map_err(|e| TransportError::SendFailed(e.to_string()))
     }
 
     fn try_send(&self, data: Bytes) -> Result<()> {
-        if !self.is_connected() {
-            return Err(TransportError::NotConnected);
-        }
+        self.enqueue(Queued::new(WsMessage::Binary(data.to_vec())))
+    }
 
-        self.tx
-            .try_send(WsMessage::Binary(data.to_vec()))
-            .map_err(|e| match e {
-                mpsc::error::TrySendError::Full(_) => TransportError::BufferFull,
-                mpsc::error::TrySendError::Closed(_) => TransportError::ConnectionClosed,
-            })
+    fn try_send_expiring(&self, data: Bytes, expires_at: Instant) -> Result<()> {
+        self.enqueue(Queued::expiring(
+            WsMessage::Binary(data.to_vec()),
+            expires_at,
+        ))
     }
 
     fn is_connected(&self) -> bool {
@@ -116,12 +117,25 @@ impl TransportSender for WebSocketSender {
     }
 
     async fn close(&self) -> Result<()> {
-        let _ = self.tx.send(WsMessage::Close(None)).await;
+        let _ = self.tx.send(Queued::new(WsMessage::Close(None))).await;
         *self.connected.lock() = false;
         Ok(())
     }
 }
 
+impl WebSocketSender {
+    fn enqueue(&self, message: Queued<WsMessage>) -> Result<()> {
+        if !self.is_connected() {
+            return Err(TransportError::NotConnected);
+        }
+
+        self.tx.try_send(message).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => TransportError::BufferFull,
+            mpsc::error::TrySendError::Closed(_) => TransportError::ConnectionClosed,
+        })
+    }
+}
+
 /// WebSocket receiver
 pub struct WebSocketReceiver {
     rx: mpsc::Receiver<TransportEvent>,
@@ -140,6 +154,24 @@ impl Transport for WebSocketTransport {
     type Receiver = WebSocketReceiver;
 
     async fn connect(url: &str) -> Result<(Self::Sender, Self::Receiver)> {
+        Self::connect_with_config(url, WebSocketConfig::default()).await
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+}
+
+impl WebSocketTransport {
+    /// Connect using the given configuration (keepalive, buffer sizes)
+    pub async fn connect_with_config(
+        url: &str,
+        config: WebSocketConfig,
+    ) -> Result<(WebSocketSender, WebSocketReceiver)> {
         info!("Connecting to WebSocket: {}", url);
 
         // Parse the URL to extract host for the Host header
@@ -186,8 +218,8 @@ impl Transport for WebSocketTransport {
         let (write, read) = ws_stream.split();
 
         // Create channels with larger buffers for better load handling
-        let (send_tx, mut send_rx) = mpsc::channel::<WsMessage>(DEFAULT_CHANNEL_BUFFER_SIZE);
-        let (event_tx, event_rx) = mpsc::channel::<TransportEvent>(DEFAULT_CHANNEL_BUFFER_SIZE);
+        let (send_tx, mut send_rx) = mpsc::channel::<Queued<WsMessage>>(config.channel_buffer_size);
+        let (event_tx, event_rx) = mpsc::channel::<TransportEvent>(config.channel_buffer_size);
 
         let connected = Arc::new(Mutex::new(true));
         let connected_write = connected.clone();
@@ -196,7 +228,10 @@ impl Transport for WebSocketTransport {
         // Spawn writer task
         tokio::spawn(async move {
             let mut write = write;
-            while let Some(msg) = send_rx.recv().await {
+            while let Some(queued) = send_rx.recv().await {
+                let Some(msg) = queued.take() else {
+                    continue;
+                };
                 if let Err(e) = write.send(msg).await {
                     error!("WebSocket write error: {}", e);
                     break;
@@ -207,13 +242,28 @@ impl Transport for WebSocketTransport {
 
         // Spawn reader task
         let event_tx_clone = event_tx.clone();
+        let ping_tx = send_tx.clone();
+        let mut keepalive = Keepalive::new(config.keepalive);
         tokio::spawn(async move {
             let mut read = read;
 
             // Send connected event
             let _ = event_tx_clone.send(TransportEvent::Connected).await;
 
-            while let Some(result) = read.next().await {
+            loop {
+                let result = tokio::select! {
+                    result = read.next() => match result {
+                        Some(result) => result,
+                        None => break,
+                    },
+                    tick = keepalive.tick() => {
+                        if !handle_keepalive(tick, &ping_tx, &event_tx_clone).await {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                keepalive.received();
                 match result {
                     Ok(msg) => {
                         match msg {
@@ -277,13 +327,31 @@ impl Transport for WebSocketTransport {
 
         Ok((sender, receiver))
     }
+}
 
-    fn local_addr(&self) -> Option<SocketAddr> {
-        None
-    }
-
-    fn remote_addr(&self) -> Option<SocketAddr> {
-        None
+/// Act on a keepalive tick; returns false once the connection is dead
+async fn handle_keepalive(
+    tick: KeepaliveTick,
+    ping_tx: &mpsc::Sender<Queued<WsMessage>>,
+    event_tx: &mpsc::Sender<TransportEvent>,
+) -> bool {
+    match tick {
+        KeepaliveTick::Idle => true,
+        KeepaliveTick::Ping => {
+            debug!("Sending keepalive ping");
+            let _ = ping_tx.try_send(Queued::new(WsMessage::Ping(Vec::new())));
+            true
+        }
+        KeepaliveTick::Dead => {
+            warn!("WebSocket keepalive timed out");
+            let _ = ping_tx.try_send(Queued::new(WsMessage::Close(None)));
+            let _ = event_tx
+                .send(TransportEvent::Disconnected {
+                    reason: Some("keepalive timeout".to_string()),
+                })
+                .await;
+            false
+        }
     }
 }
 
@@ -359,7 +427,7 @@ impl TransportServer for WebSocketServer {
 
         // Create channels with configurable buffer size for better load handling
         let buffer_size = self.config.channel_buffer_size;
-        let (send_tx, mut send_rx) = mpsc::channel::<WsMessage>(buffer_size);
+        let (send_tx, mut send_rx) = mpsc::channel::<Queued<WsMessage>>(buffer_size);
         let (event_tx, event_rx) = mpsc::channel::<TransportEvent>(buffer_size);
 
         let connected = Arc::new(Mutex::new(true));
@@ -369,7 +437,10 @@ impl TransportServer for WebSocketServer {
         // Spawn writer task
         tokio::spawn(async move {
             let mut write = write;
-            while let Some(msg) = send_rx.recv().await {
+            while let Some(queued) = send_rx.recv().await {
+                let Some(msg) = queued.take() else {
+                    continue;
+                };
                 if let Err(e) = write.send(msg).await {
                     error!("WebSocket write error: {}", e);
                     break;
@@ -380,12 +451,27 @@ impl TransportServer for WebSocketServer {
 
         // Spawn reader task
         let event_tx_clone = event_tx.clone();
+        let ping_tx = send_tx.clone();
+        let mut keepalive = Keepalive::new(self.config.keepalive);
         tokio::spawn(async move {
             let mut read = read;
 
             let _ = event_tx_clone.send(TransportEvent::Connected).await;
 
-            while let Some(result) = read.next().await {
+            loop {
+                let result = tokio::select! {
+                    result = read.next() => match result {
+                        Some(result) => result,
+                        None => break,
+                    },
+                    tick = keepalive.tick() => {
+                        if !handle_keepalive(tick, &ping_tx, &event_tx_clone).await {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                keepalive.received();
                 match result {
                     Ok(msg) => match msg {
                         WsMessage::Binary(data) => {
@@ -0,0 +1,117 @@
+//! Network Emulation Tests
+//!
+//! Real connections wrapped in an emulated link: frames arrive late, in
+//! order, or not at all, as the profile says.
+
+#![cfg(feature = "websocket")]
+
+use bytes::Bytes;
+use clasp_router::Router;
+use clasp_transport::{
+    EmulatedServer, EmulatedTransport, Jitter, NetworkProfile, TransportEvent, TransportReceiver,
+    TransportSender, TransportServer, WebSocketServer, WebSocketTransport,
+};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+/// Next data frame, or `None` if nothing arrives within `wait`
+async fn next_data(receiver: &mut impl TransportReceiver, wait: Duration) -> Option<Bytes> {
+    timeout(wait, async {
+        loop {
+            match receiver.recv().await {
+                Some(TransportEvent::Data(data)) => return data,
+                Some(TransportEvent::Disconnected { reason }) => {
+                    panic!("disconnected: {:?}", reason)
+                }
+                Some(_) => continue,
+                None => panic!("receiver closed"),
+            }
+        }
+    })
+    .await
+    .ok()
+}
+
+/// A WebSocket server and a client connection wrapped with `profile`
+async fn emulated_pair(
+    profile: NetworkProfile,
+) -> (
+    impl TransportSender,
+    impl TransportReceiver,
+    impl TransportSender,
+    impl TransportReceiver,
+) {
+    let mut server = WebSocketServer::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", server.local_addr().unwrap());
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let (client, client_rx) = EmulatedTransport::connect::<WebSocketTransport>(&url, profile)
+        .await
+        .unwrap();
+    let (server_tx, server_rx, _) = accept.await.unwrap();
+    (client, client_rx, server_tx, server_rx)
+}
+
+#[tokio::test]
+async fn test_latency_applies_both_ways() {
+    let profile = NetworkProfile::ideal().with_latency(Duration::from_millis(100));
+    let (client, mut client_rx, server_tx, mut server_rx) = emulated_pair(profile).await;
+
+    let start = Instant::now();
+    client.send(Bytes::from_static(b"ping")).await.unwrap();
+    let data = next_data(&mut server_rx, Duration::from_secs(2)).await;
+    assert_eq!(data, Some(Bytes::from_static(b"ping")));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let start = Instant::now();
+    server_tx.send(Bytes::from_static(b"pong")).await.unwrap();
+    let data = next_data(&mut client_rx, Duration::from_secs(2)).await;
+    assert_eq!(data, Some(Bytes::from_static(b"pong")));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn test_jitter_keeps_order() {
+    let profile = NetworkProfile::ideal()
+        .with_jitter(Duration::from_millis(20), Jitter::Pareto)
+        .with_seed(5);
+    let (client, _client_rx, _server_tx, mut server_rx) = emulated_pair(profile).await;
+
+    for i in 0..50u8 {
+        client.send(Bytes::from(vec![i])).await.unwrap();
+    }
+    for i in 0..50u8 {
+        let data = next_data(&mut server_rx, Duration::from_secs(2)).await;
+        assert_eq!(data, Some(Bytes::from(vec![i])));
+    }
+}
+
+#[tokio::test]
+async fn test_total_loss() {
+    let profile = NetworkProfile::ideal().with_loss(1.0);
+    let (client, _client_rx, _server_tx, mut server_rx) = emulated_pair(profile).await;
+
+    client.send(Bytes::from_static(b"gone")).await.unwrap();
+    assert_eq!(
+        next_data(&mut server_rx, Duration::from_millis(200)).await,
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_router_behind_emulated_server() {
+    let server = WebSocketServer::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", server.local_addr().unwrap());
+    let profile = NetworkProfile::ideal().with_latency(Duration::from_millis(50));
+
+    let router = Router::default();
+    tokio::spawn(async move {
+        let _ = router.serve_on(EmulatedServer::new(server, profile)).await;
+    });
+
+    let client = clasp_client::Clasp::connect_to(&url).await.unwrap();
+    let start = Instant::now();
+    client.set_with_ack("/emulated/value", 1.0).await.unwrap();
+    // Out and back over the router's side of the link
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
@@ -0,0 +1,85 @@
+//! Expiry Tests
+//!
+//! Frames queued with a deadline are dropped by the writer instead of being
+//! sent once the deadline has passed; frames without one always go out.
+
+#![cfg(all(feature = "websocket", feature = "tcp"))]
+
+use bytes::Bytes;
+use clasp_transport::{
+    TcpServer, TcpTransport, Transport, TransportEvent, TransportReceiver, TransportSender,
+    TransportServer, WebSocketServer, WebSocketTransport,
+};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+/// Wait for the next data frame
+async fn next_data(receiver: &mut impl TransportReceiver) -> Bytes {
+    timeout(Duration::from_secs(2), async {
+        loop {
+            match receiver.recv().await {
+                Some(TransportEvent::Data(data)) => return data,
+                Some(TransportEvent::Disconnected { reason }) => {
+                    panic!("disconnected: {:?}", reason)
+                }
+                Some(_) => continue,
+                None => panic!("receiver closed"),
+            }
+        }
+    })
+    .await
+    .expect("no data")
+}
+
+/// Send an already-expired frame, a live one, then a plain one
+fn send_mixed(sender: &impl TransportSender) {
+    let past = Instant::now() - Duration::from_millis(1);
+    let future = Instant::now() + Duration::from_secs(60);
+    sender
+        .try_send_expiring(Bytes::from_static(b"stale"), past)
+        .unwrap();
+    sender
+        .try_send_expiring(Bytes::from_static(b"fresh"), future)
+        .unwrap();
+    sender.try_send(Bytes::from_static(b"plain")).unwrap();
+}
+
+#[tokio::test]
+async fn test_websocket_drops_expired_frames() {
+    let mut server = WebSocketServer::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", server.local_addr().unwrap());
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let (_client, mut client_rx) = WebSocketTransport::connect(&url).await.unwrap();
+    let (sender, _receiver, _) = accept.await.unwrap();
+
+    send_mixed(&sender);
+    assert_eq!(
+        next_data(&mut client_rx).await,
+        Bytes::from_static(b"fresh")
+    );
+    assert_eq!(
+        next_data(&mut client_rx).await,
+        Bytes::from_static(b"plain")
+    );
+}
+
+#[tokio::test]
+async fn test_tcp_drops_expired_frames() {
+    let mut server = TcpServer::bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let (_client, mut client_rx) = TcpTransport::new().connect(&addr).await.unwrap();
+    let (sender, _receiver, _) = accept.await.unwrap();
+
+    send_mixed(&sender);
+    assert_eq!(
+        next_data(&mut client_rx).await,
+        Bytes::from_static(b"fresh")
+    );
+    assert_eq!(
+        next_data(&mut client_rx).await,
+        Bytes::from_static(b"plain")
+    );
+}
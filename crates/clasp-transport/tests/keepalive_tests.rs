@@ -0,0 +1,205 @@
+//! Keepalive Tests
+//!
+//! Peers that answer pings stay connected; a peer that goes silent is
+//! reported as disconnected once `max_missed` pings go unanswered.
+//! "Silent" peers are sockets that completed their handshake and are then
+//! never read, so they can't answer pings.
+
+#![cfg(all(feature = "websocket", feature = "tcp"))]
+
+use bytes::Bytes;
+use clasp_transport::tcp::TcpConfig;
+use clasp_transport::{
+    KeepaliveConfig, TcpServer, TcpTransport, Transport, TransportEvent, TransportReceiver,
+    TransportSender, TransportServer, WebSocketConfig, WebSocketServer, WebSocketTransport,
+};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+/// Ping after 50ms idle; dead after 150ms of silence
+fn fast() -> KeepaliveConfig {
+    KeepaliveConfig::new(Duration::from_millis(50))
+        .with_timeout(Duration::from_millis(50))
+        .with_max_missed(2)
+}
+
+fn ws_config(keepalive: KeepaliveConfig) -> WebSocketConfig {
+    WebSocketConfig {
+        keepalive,
+        ..Default::default()
+    }
+}
+
+/// Wait for a disconnect, skipping data; returns its reason
+async fn disconnected(receiver: &mut impl TransportReceiver, within: Duration) -> Option<String> {
+    timeout(within, async {
+        loop {
+            match receiver.recv().await {
+                Some(TransportEvent::Disconnected { reason }) => return reason,
+                Some(_) => continue,
+                None => return None,
+            }
+        }
+    })
+    .await
+    .expect("no disconnect")
+}
+
+/// Assert the connection stays up for a while
+async fn stays_connected(receiver: &mut impl TransportReceiver) {
+    let result = timeout(Duration::from_millis(500), async {
+        loop {
+            match receiver.recv().await {
+                Some(TransportEvent::Disconnected { reason }) => return reason,
+                Some(_) => continue,
+                None => return None,
+            }
+        }
+    })
+    .await;
+    assert!(result.is_err(), "disconnected: {:?}", result);
+}
+
+// ============================================================================
+// WebSocket
+// ============================================================================
+
+#[tokio::test]
+async fn test_websocket_keeps_responsive_client() {
+    let mut server = WebSocketServer::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .with_config(ws_config(fast()));
+    let url = format!("ws://{}", server.local_addr().unwrap());
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    // The client never pings, but answers the server's pings
+    let (client, _client_rx) =
+        WebSocketTransport::connect_with_config(&url, ws_config(KeepaliveConfig::disabled()))
+            .await
+            .unwrap();
+    let (_sender, mut receiver, _) = accept.await.unwrap();
+
+    stays_connected(&mut receiver).await;
+    assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn test_websocket_server_drops_silent_client() {
+    let mut server = WebSocketServer::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .with_config(ws_config(fast()));
+    let addr = server.local_addr().unwrap();
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (_silent, _) = tokio_tungstenite::client_async(format!("ws://{}", addr), stream)
+        .await
+        .unwrap();
+    let (sender, mut receiver, _) = accept.await.unwrap();
+
+    let reason = disconnected(&mut receiver, Duration::from_secs(2)).await;
+    assert_eq!(reason.as_deref(), Some("keepalive timeout"));
+    assert!(!sender.is_connected());
+}
+
+#[tokio::test]
+async fn test_websocket_client_detects_silent_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let silent = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        drop(ws);
+    });
+
+    let (sender, mut receiver) = WebSocketTransport::connect_with_config(&url, ws_config(fast()))
+        .await
+        .unwrap();
+
+    let reason = disconnected(&mut receiver, Duration::from_secs(2)).await;
+    assert_eq!(reason.as_deref(), Some("keepalive timeout"));
+    assert!(!sender.is_connected());
+    silent.abort();
+}
+
+#[tokio::test]
+async fn test_websocket_default_connect_keeps_working() {
+    let mut server = WebSocketServer::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", server.local_addr().unwrap());
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let (client, _rx) = WebSocketTransport::connect(&url).await.unwrap();
+    let (_sender, mut receiver, _) = accept.await.unwrap();
+
+    client.send(Bytes::from_static(b"hello")).await.unwrap();
+    loop {
+        match timeout(Duration::from_secs(2), receiver.recv())
+            .await
+            .unwrap()
+        {
+            Some(TransportEvent::Data(data)) => {
+                assert_eq!(&data[..], b"hello");
+                break;
+            }
+            Some(TransportEvent::Connected) => continue,
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
+
+// ============================================================================
+// TCP
+// ============================================================================
+
+fn tcp_config(keepalive: KeepaliveConfig) -> TcpConfig {
+    TcpConfig {
+        keepalive,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tcp_keeps_responsive_peer() {
+    let mut server = TcpServer::bind_with_config("127.0.0.1:0", tcp_config(fast()))
+        .await
+        .unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let (client, _client_rx) = TcpTransport::with_config(tcp_config(KeepaliveConfig::disabled()))
+        .connect(&addr)
+        .await
+        .unwrap();
+    let (_sender, mut receiver, _) = accept.await.unwrap();
+
+    stays_connected(&mut receiver).await;
+
+    // Control frames are never delivered as data
+    client.send(Bytes::from_static(b"frame")).await.unwrap();
+    match timeout(Duration::from_secs(2), receiver.recv())
+        .await
+        .unwrap()
+    {
+        Some(TransportEvent::Data(data)) => assert_eq!(&data[..], b"frame"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_tcp_drops_silent_peer() {
+    let mut server = TcpServer::bind_with_config("127.0.0.1:0", tcp_config(fast()))
+        .await
+        .unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let accept = tokio::spawn(async move { server.accept().await.unwrap() });
+    let _silent = TcpStream::connect(addr).await.unwrap();
+    let (sender, mut receiver, _) = accept.await.unwrap();
+
+    let reason = disconnected(&mut receiver, Duration::from_secs(2)).await;
+    assert_eq!(reason.as_deref(), Some("keepalive timeout"));
+    assert!(!sender.is_connected());
+}
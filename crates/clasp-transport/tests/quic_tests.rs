@@ -16,7 +16,7 @@ use std::time::Duration;
 
 use bytes::Bytes;
 use clasp_transport::quic::{CertVerification, QuicConfig, QuicTransport, CLASP_ALPN};
-use clasp_transport::{TransportReceiver, TransportSender};
+use clasp_transport::{KeepaliveConfig, TransportReceiver, TransportSender};
 use rcgen::{generate_simple_self_signed, CertifiedKey};
 
 // ============================================================================
@@ -72,6 +72,21 @@ async fn test_quic_config_custom() {
     assert_eq!(config.initial_window, 20, "initial_window should be 20");
 }
 
+#[tokio::test]
+async fn test_quic_config_with_keepalive() {
+    let keepalive = KeepaliveConfig::new(Duration::from_secs(2))
+        .with_timeout(Duration::from_secs(1))
+        .with_max_missed(3);
+    let config = QuicConfig::default().with_keepalive(keepalive);
+    assert_eq!(config.keep_alive_ms, 2000);
+    assert_eq!(config.idle_timeout_ms, 5000);
+
+    let config = QuicConfig::default().with_keepalive(KeepaliveConfig::disabled());
+    assert_eq!(config.keep_alive_ms, 0);
+    assert_eq!(config.idle_timeout_ms, 0);
+    assert!(QuicTransport::new_client_with_config(config).is_ok());
+}
+
 // ============================================================================
 // ALPN Test
 // ============================================================================
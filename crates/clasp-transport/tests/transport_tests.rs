@@ -41,6 +41,7 @@ async fn connect_and_handshake(
         features: vec!["param".to_string()],
         capabilities: None,
         token: None,
+        since: None,
     });
     sender.send(codec::encode(&hello)?).await?;
 
@@ -117,6 +118,7 @@ async fn test_websocket_binary_frames() {
         features: vec!["param".to_string()],
         capabilities: None,
         token: None,
+        since: None,
     });
     let bytes = codec::encode(&hello).expect("Encode failed");
     sender.send(bytes).await.expect("Send failed");
@@ -157,6 +159,7 @@ async fn test_roundtrip_encode_decode() {
             features: vec!["param".to_string(), "event".to_string()],
             capabilities: None,
             token: None,
+            since: None,
         }),
         Message::Set(SetMessage {
             address: "/test/value".to_string(),
@@ -164,6 +167,8 @@ async fn test_roundtrip_encode_decode() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
         Message::Set(SetMessage {
             address: "/test/string".to_string(),
@@ -171,6 +176,8 @@ async fn test_roundtrip_encode_decode() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
     ];
 
@@ -214,6 +221,7 @@ async fn test_roundtrip_via_server() {
         pattern: "/roundtrip/**".to_string(),
         types: vec![],
         options: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&sub_msg).unwrap())
@@ -229,6 +237,8 @@ async fn test_roundtrip_via_server() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&set_msg).unwrap())
@@ -351,6 +361,7 @@ async fn test_send_after_close() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
     let send_result = sender.send(codec::encode(&hello).unwrap()).await;
 
@@ -386,6 +397,8 @@ async fn test_large_message() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
     sender
         .send(codec::encode(&set).unwrap())
@@ -433,6 +446,8 @@ async fn test_message_size_boundaries() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         let encoded = codec::encode(&set).expect(&format!("Encode size {} failed", size));
         sender
@@ -553,6 +568,7 @@ async fn test_magic_byte_verification() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
 
     let encoded = codec::encode(&msg).expect("Encode failed");
@@ -569,6 +585,7 @@ async fn test_frame_header_format() {
         features: vec![],
         capabilities: None,
         token: None,
+        since: None,
     });
 
     let encoded = codec::encode(&msg).expect("Encode failed");
@@ -612,6 +629,8 @@ async fn test_value_roundtrip_all_types() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).expect(&format!("Encode {} failed", name));
@@ -2,7 +2,14 @@
 //!
 //! This crate provides WebAssembly bindings for Clasp,
 //! enabling browser-based clients.
+//!
+//! ## Crate Features
+//!
+//! - `p2p` - WebRTC peer-to-peer support
+//! - `offline` - IndexedDB-backed param cache and offline write queue
 
+#[cfg(feature = "offline")]
+pub mod offline;
 #[cfg(feature = "p2p")]
 pub mod p2p;
 
@@ -43,6 +50,8 @@ pub struct ClaspWasm {
     on_auth_error: Rc<RefCell<Option<js_sys::Function>>>,
     sub_id: Rc<RefCell<u32>>,
     token: Rc<RefCell<Option<String>>>,
+    #[cfg(feature = "offline")]
+    offline: Rc<RefCell<Option<Rc<offline::OfflineStore>>>>,
 }
 
 #[wasm_bindgen]
@@ -72,6 +81,8 @@ impl ClaspWasm {
             on_auth_error: Rc::new(RefCell::new(None)),
             sub_id: Rc::new(RefCell::new(1)),
             token: Rc::new(RefCell::new(token)),
+            #[cfg(feature = "offline")]
+            offline: Rc::new(RefCell::new(None)),
         };
 
         client.setup_handlers()?;
@@ -112,6 +123,7 @@ impl ClaspWasm {
                 ],
                 capabilities: None,
                 token: token_value,
+                since: None,
             });
 
             if let Ok(bytes) = codec::encode(&hello) {
@@ -130,6 +142,8 @@ impl ClaspWasm {
         let on_message_msg = on_message.clone();
         let on_auth_error_msg = on_auth_error.clone();
         let ws_msg = ws.clone();
+        #[cfg(feature = "offline")]
+        let offline_msg = self.offline.clone();
 
         let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
             if let Ok(abuf) = e.data().dyn_into::<js_sys::ArrayBuffer>() {
@@ -142,6 +156,18 @@ impl ClaspWasm {
                             *session_msg.borrow_mut() = Some(welcome.session.clone());
                             *connected_msg.borrow_mut() = true;
 
+                            // Flush writes queued while offline
+                            #[cfg(feature = "offline")]
+                            if let Some(store) = offline_msg.borrow().as_ref() {
+                                for write in store.take_pending() {
+                                    let msg = offline_set_message(&write, store.policy());
+                                    if let Ok(bytes) = codec::encode(&msg) {
+                                        let array = js_sys::Uint8Array::from(bytes.as_ref());
+                                        let _ = ws_msg.send_with_array_buffer(&array.buffer());
+                                    }
+                                }
+                            }
+
                             if let Some(callback) = on_connect_msg.borrow().as_ref() {
                                 let _ = callback.call0(&JsValue::NULL);
                             }
@@ -181,6 +207,11 @@ impl ClaspWasm {
                                 .borrow_mut()
                                 .insert(set.address.clone(), js_value.clone());
 
+                            #[cfg(feature = "offline")]
+                            if let Some(store) = offline_msg.borrow().as_ref() {
+                                store.cache_param(&set.address, &js_value, set.revision);
+                            }
+
                             if let Some(callback) = on_message_msg.borrow().as_ref() {
                                 let _ = callback.call2(
                                     &JsValue::NULL,
@@ -196,6 +227,15 @@ impl ClaspWasm {
                                     .borrow_mut()
                                     .insert(param.address.clone(), js_value.clone());
 
+                                #[cfg(feature = "offline")]
+                                if let Some(store) = offline_msg.borrow().as_ref() {
+                                    store.cache_param(
+                                        &param.address,
+                                        &js_value,
+                                        Some(param.revision),
+                                    );
+                                }
+
                                 if let Some(callback) = on_message_msg.borrow().as_ref() {
                                     let _ = callback.call2(
                                         &JsValue::NULL,
@@ -310,6 +350,7 @@ impl ClaspWasm {
             pattern: pattern.to_string(),
             types: vec![],
             options: Some(SubscribeOptions::default()),
+            correlation_id: None,
         });
 
         self.send_message(&msg);
@@ -323,7 +364,21 @@ impl ClaspWasm {
     }
 
     /// Set a value
+    ///
+    /// With offline support enabled, SETs made while disconnected are queued
+    /// and flushed on the next successful connection.
     pub fn set(&self, address: &str, value: JsValue) {
+        #[cfg(feature = "offline")]
+        if !*self.connected.borrow() {
+            if let Some(store) = self.offline.borrow().as_ref() {
+                self.params
+                    .borrow_mut()
+                    .insert(address.to_string(), value.clone());
+                store.enqueue(address, value);
+                return;
+            }
+        }
+
         let sf_value = js_to_value(&value);
         let msg = Message::Set(SetMessage {
             address: address.to_string(),
@@ -331,6 +386,8 @@ impl ClaspWasm {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
         self.send_message(&msg);
     }
@@ -362,6 +419,59 @@ impl ClaspWasm {
             .unwrap_or(JsValue::NULL)
     }
 
+    /// Enable the IndexedDB-backed offline cache and write queue
+    ///
+    /// Loads the last-known params into the local cache (so `get()` works
+    /// before the router answers) and restores any writes queued by a previous
+    /// page load. Queued writes are flushed on WELCOME using `policy`.
+    #[cfg(feature = "offline")]
+    #[wasm_bindgen(js_name = enableOffline)]
+    pub async fn enable_offline(
+        &self,
+        db_name: Option<String>,
+        policy: offline::ConflictPolicy,
+    ) -> Result<(), JsValue> {
+        let db_name = db_name.unwrap_or_else(|| offline::DEFAULT_DB_NAME.to_string());
+        let (store, cached) = offline::OfflineStore::open(&db_name, policy).await?;
+
+        {
+            let mut params = self.params.borrow_mut();
+            for (address, value) in cached {
+                params.entry(address).or_insert(value);
+            }
+        }
+
+        // Already connected: nothing will trigger a WELCOME flush, so do it now
+        if *self.connected.borrow() {
+            for write in store.take_pending() {
+                self.send_message(&offline_set_message(&write, store.policy()));
+            }
+        }
+
+        *self.offline.borrow_mut() = Some(store);
+        Ok(())
+    }
+
+    /// Number of SETs waiting to be flushed
+    #[cfg(feature = "offline")]
+    #[wasm_bindgen(getter, js_name = queuedWrites)]
+    pub fn queued_writes(&self) -> usize {
+        self.offline
+            .borrow()
+            .as_ref()
+            .map(|s| s.queued_count())
+            .unwrap_or(0)
+    }
+
+    /// Remove the persisted param cache and any queued writes
+    #[cfg(feature = "offline")]
+    #[wasm_bindgen(js_name = clearOffline)]
+    pub fn clear_offline(&self) {
+        if let Some(store) = self.offline.borrow().as_ref() {
+            store.clear();
+        }
+    }
+
     /// Close connection
     pub fn close(&self) {
         let _ = self.ws.close();
@@ -376,6 +486,24 @@ impl ClaspWasm {
     }
 }
 
+/// Build the SET for a queued offline write according to the conflict policy
+#[cfg(feature = "offline")]
+fn offline_set_message(write: &offline::QueuedWrite, policy: offline::ConflictPolicy) -> Message {
+    let revision = match policy {
+        offline::ConflictPolicy::ServerWins => write.base_revision,
+        _ => None,
+    };
+    Message::Set(SetMessage {
+        address: write.address.clone(),
+        value: js_to_value(&write.value),
+        revision,
+        lock: false,
+        unlock: false,
+        op: None,
+        correlation_id: None,
+    })
+}
+
 /// Convert Clasp Value to JsValue
 fn value_to_js(value: &Value) -> JsValue {
     match value {
@@ -0,0 +1,336 @@
+//! IndexedDB-backed offline support for browser clients
+//!
+//! Persists the last-known parameter cache and queues SETs made while the
+//! WebSocket is down. Queued writes are flushed on the next WELCOME according
+//! to a [`ConflictPolicy`], so touch panels running as PWAs survive network
+//! blips (and even page reloads) without losing operator input.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{IdbDatabase, IdbFactory, IdbObjectStore, IdbRequest};
+
+/// Default IndexedDB database name
+pub const DEFAULT_DB_NAME: &str = "clasp-offline";
+
+/// IndexedDB schema version
+const DB_VERSION: u32 = 1;
+
+/// Object store holding the last-known parameter values
+const PARAMS_STORE: &str = "params";
+
+/// Object store holding SETs queued while offline
+const QUEUE_STORE: &str = "queue";
+
+/// How queued writes are reconciled with the router on reconnect
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Replay queued writes unconditionally (last writer wins)
+    #[default]
+    ClientWins,
+    /// Replay queued writes guarded by the revision seen when they were queued,
+    /// so the router rejects any write whose param changed in the meantime
+    ServerWins,
+    /// Drop queued writes on reconnect
+    Discard,
+}
+
+/// A SET captured while the connection was down
+#[derive(Debug, Clone)]
+pub struct QueuedWrite {
+    /// Monotonic sequence number (IndexedDB key)
+    pub seq: u32,
+    /// Target address
+    pub address: String,
+    /// Value to write
+    pub value: JsValue,
+    /// Revision known when the first write to this address was queued
+    pub base_revision: Option<u64>,
+}
+
+impl QueuedWrite {
+    fn to_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&obj, &"seq".into(), &JsValue::from_f64(self.seq as f64));
+        let _ = js_sys::Reflect::set(&obj, &"address".into(), &JsValue::from_str(&self.address));
+        let _ = js_sys::Reflect::set(&obj, &"value".into(), &self.value);
+        if let Some(rev) = self.base_revision {
+            let _ = js_sys::Reflect::set(&obj, &"revision".into(), &JsValue::from_f64(rev as f64));
+        }
+        obj.into()
+    }
+
+    fn from_js(js: &JsValue) -> Option<Self> {
+        let seq = js_sys::Reflect::get(js, &"seq".into()).ok()?.as_f64()? as u32;
+        let address = js_sys::Reflect::get(js, &"address".into())
+            .ok()?
+            .as_string()?;
+        let value = js_sys::Reflect::get(js, &"value".into()).ok()?;
+        let base_revision = js_sys::Reflect::get(js, &"revision".into())
+            .ok()
+            .and_then(|r| r.as_f64())
+            .map(|r| r as u64);
+        Some(Self {
+            seq,
+            address,
+            value,
+            base_revision,
+        })
+    }
+}
+
+/// In-memory write queue with per-address coalescing
+///
+/// Only the latest value per address is kept, but the base revision of the
+/// first queued write is preserved so [`ConflictPolicy::ServerWins`] detects
+/// any change made by others since the client went offline.
+#[derive(Debug, Default)]
+pub struct WriteQueue {
+    entries: Vec<QueuedWrite>,
+    next_seq: u32,
+}
+
+impl WriteQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a write, coalescing with any pending write to the same address.
+    /// Returns the stored entry and the sequence number it replaced, if any.
+    pub fn push(
+        &mut self,
+        address: &str,
+        value: JsValue,
+        revision: Option<u64>,
+    ) -> (QueuedWrite, Option<u32>) {
+        let mut base_revision = revision;
+        let mut replaced = None;
+        if let Some(pos) = self.entries.iter().position(|e| e.address == address) {
+            let old = self.entries.remove(pos);
+            base_revision = old.base_revision;
+            replaced = Some(old.seq);
+        }
+
+        let entry = QueuedWrite {
+            seq: self.next_seq,
+            address: address.to_string(),
+            value,
+            base_revision,
+        };
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.entries.push(entry.clone());
+        (entry, replaced)
+    }
+
+    /// Restore entries loaded from storage (in sequence order)
+    pub fn restore(&mut self, mut entries: Vec<QueuedWrite>) {
+        entries.sort_by_key(|e| e.seq);
+        self.next_seq = entries.last().map(|e| e.seq.wrapping_add(1)).unwrap_or(0);
+        self.entries = entries;
+    }
+
+    /// Take all queued writes, leaving the queue empty
+    pub fn drain(&mut self) -> Vec<QueuedWrite> {
+        std::mem::take(&mut self.entries)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Persistent offline state for a [`crate::ClaspWasm`] client
+pub struct OfflineStore {
+    db: IdbDatabase,
+    policy: ConflictPolicy,
+    queue: RefCell<WriteQueue>,
+    revisions: RefCell<HashMap<String, u64>>,
+}
+
+impl OfflineStore {
+    /// Open (or create) the IndexedDB database and load its contents.
+    ///
+    /// Returns the store along with the cached params so the caller can seed
+    /// its in-memory cache before the first WELCOME arrives.
+    pub async fn open(
+        db_name: &str,
+        policy: ConflictPolicy,
+    ) -> Result<(Rc<Self>, Vec<(String, JsValue)>), JsValue> {
+        let factory = idb_factory()?;
+        let open_req = factory.open_with_u32(db_name, DB_VERSION)?;
+
+        let upgrade_req = open_req.clone();
+        let onupgrade = Closure::once(Box::new(move |_: JsValue| {
+            if let Ok(result) = upgrade_req.result() {
+                let db: IdbDatabase = result.unchecked_into();
+                let names = db.object_store_names();
+                if !names.contains(PARAMS_STORE) {
+                    let _ = db.create_object_store(PARAMS_STORE);
+                }
+                if !names.contains(QUEUE_STORE) {
+                    let _ = db.create_object_store(QUEUE_STORE);
+                }
+            }
+        }) as Box<dyn FnOnce(JsValue)>);
+        open_req.set_onupgradeneeded(Some(onupgrade.as_ref().unchecked_ref()));
+
+        let db: IdbDatabase = request_result(open_req.unchecked_ref::<IdbRequest>())
+            .await?
+            .unchecked_into();
+        drop(onupgrade);
+
+        let store = Self {
+            db,
+            policy,
+            queue: RefCell::new(WriteQueue::new()),
+            revisions: RefCell::new(HashMap::new()),
+        };
+
+        // Load cached params
+        let mut params = Vec::new();
+        let records: js_sys::Array =
+            request_result(&store.object_store(PARAMS_STORE, false)?.get_all()?)
+                .await?
+                .unchecked_into();
+        for record in records.iter() {
+            let address = js_sys::Reflect::get(&record, &"address".into())
+                .ok()
+                .and_then(|a| a.as_string());
+            let Some(address) = address else { continue };
+            let value = js_sys::Reflect::get(&record, &"value".into()).unwrap_or(JsValue::NULL);
+            if let Some(rev) = js_sys::Reflect::get(&record, &"revision".into())
+                .ok()
+                .and_then(|r| r.as_f64())
+            {
+                store
+                    .revisions
+                    .borrow_mut()
+                    .insert(address.clone(), rev as u64);
+            }
+            params.push((address, value));
+        }
+
+        // Load queued writes
+        let records: js_sys::Array =
+            request_result(&store.object_store(QUEUE_STORE, false)?.get_all()?)
+                .await?
+                .unchecked_into();
+        let entries = records
+            .iter()
+            .filter_map(|r| QueuedWrite::from_js(&r))
+            .collect();
+        store.queue.borrow_mut().restore(entries);
+
+        Ok((Rc::new(store), params))
+    }
+
+    /// Configured conflict policy
+    pub fn policy(&self) -> ConflictPolicy {
+        self.policy
+    }
+
+    /// Number of writes waiting for a connection
+    pub fn queued_count(&self) -> usize {
+        self.queue.borrow().len()
+    }
+
+    /// Record a param value received from the router
+    pub fn cache_param(&self, address: &str, value: &JsValue, revision: Option<u64>) {
+        if let Some(rev) = revision {
+            self.revisions.borrow_mut().insert(address.to_string(), rev);
+        }
+
+        let record = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&record, &"address".into(), &JsValue::from_str(address));
+        let _ = js_sys::Reflect::set(&record, &"value".into(), value);
+        if let Some(rev) = self.revisions.borrow().get(address) {
+            let _ =
+                js_sys::Reflect::set(&record, &"revision".into(), &JsValue::from_f64(*rev as f64));
+        }
+
+        if let Ok(store) = self.object_store(PARAMS_STORE, true) {
+            let _ = store.put_with_key(&record, &JsValue::from_str(address));
+        }
+    }
+
+    /// Queue a SET made while disconnected
+    pub fn enqueue(&self, address: &str, value: JsValue) {
+        let revision = self.revisions.borrow().get(address).copied();
+        let (entry, replaced) = self.queue.borrow_mut().push(address, value, revision);
+
+        if let Ok(store) = self.object_store(QUEUE_STORE, true) {
+            if let Some(seq) = replaced {
+                let _ = store.delete(&JsValue::from_f64(seq as f64));
+            }
+            let _ = store.put_with_key(&entry.to_js(), &JsValue::from_f64(entry.seq as f64));
+        }
+    }
+
+    /// Take the queued writes for flushing and clear them from storage.
+    ///
+    /// Returns an empty list under [`ConflictPolicy::Discard`].
+    pub fn take_pending(&self) -> Vec<QueuedWrite> {
+        let pending = self.queue.borrow_mut().drain();
+        if let Ok(store) = self.object_store(QUEUE_STORE, true) {
+            let _ = store.clear();
+        }
+
+        match self.policy {
+            ConflictPolicy::Discard => Vec::new(),
+            _ => pending,
+        }
+    }
+
+    /// Remove all cached params and queued writes
+    pub fn clear(&self) {
+        self.queue.borrow_mut().drain();
+        self.revisions.borrow_mut().clear();
+        for name in [PARAMS_STORE, QUEUE_STORE] {
+            if let Ok(store) = self.object_store(name, true) {
+                let _ = store.clear();
+            }
+        }
+    }
+
+    fn object_store(&self, name: &str, write: bool) -> Result<IdbObjectStore, JsValue> {
+        let mode = if write {
+            web_sys::IdbTransactionMode::Readwrite
+        } else {
+            web_sys::IdbTransactionMode::Readonly
+        };
+        self.db
+            .transaction_with_str_and_mode(name, mode)?
+            .object_store(name)
+    }
+}
+
+/// Get the IndexedDB factory from either a window or a worker global scope
+fn idb_factory() -> Result<IdbFactory, JsValue> {
+    js_sys::Reflect::get(&js_sys::global(), &"indexedDB".into())?
+        .dyn_into::<IdbFactory>()
+        .map_err(|_| JsValue::from_str("IndexedDB is not available"))
+}
+
+/// Await an IDB request and return its result
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let success_req = request.clone();
+        let onsuccess = Closure::once_into_js(move |_: JsValue| {
+            let result = success_req.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::NULL, &result);
+        });
+        let onerror = Closure::once_into_js(move |e: JsValue| {
+            let _ = reject.call1(&JsValue::NULL, &e);
+        });
+        request.set_onsuccess(Some(onsuccess.unchecked_ref()));
+        request.set_onerror(Some(onerror.unchecked_ref()));
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await
+}
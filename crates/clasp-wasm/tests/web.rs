@@ -32,6 +32,8 @@ fn test_value_conversion_null() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     }))
     .unwrap();
 
@@ -54,6 +56,8 @@ fn test_value_conversion_bool() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
         .unwrap();
 
@@ -79,6 +83,8 @@ fn test_value_conversion_int() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
         .unwrap();
 
@@ -104,6 +110,8 @@ fn test_value_conversion_float() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
         .unwrap();
 
@@ -140,6 +148,8 @@ fn test_value_conversion_string() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
         .unwrap();
 
@@ -171,6 +181,8 @@ fn test_value_conversion_bytes() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
         .unwrap();
 
@@ -226,6 +238,8 @@ fn test_value_conversion_array() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }))
         .unwrap();
 
@@ -253,6 +267,8 @@ fn test_value_conversion_map() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     }))
     .unwrap();
 
@@ -294,6 +310,8 @@ fn test_value_conversion_nested() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     }))
     .unwrap();
 
@@ -318,6 +336,7 @@ fn test_hello_message() {
         features: vec!["param".to_string(), "event".to_string()],
         capabilities: None,
         token: None,
+        since: None,
     });
 
     let encoded = codec::encode(&hello).unwrap();
@@ -342,6 +361,7 @@ fn test_hello_with_token() {
         features: vec!["param".to_string()],
         capabilities: None,
         token: Some(token.clone()),
+        since: None,
     });
 
     let encoded = codec::encode(&hello).unwrap();
@@ -387,6 +407,8 @@ fn test_set_message() {
         revision: Some(42),
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&set).unwrap();
@@ -409,6 +431,7 @@ fn test_subscribe_message() {
         pattern: "/lights/**".to_string(),
         types: vec![SignalType::Param],
         options: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&subscribe).unwrap();
@@ -449,6 +472,7 @@ fn test_address_patterns() {
             pattern: pattern.to_string(),
             types: vec![],
             options: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).unwrap();
@@ -478,6 +502,8 @@ fn test_binary_data_integrity() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).unwrap();
@@ -519,6 +545,8 @@ fn test_large_binary_payload() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).unwrap();
@@ -580,6 +608,8 @@ fn test_empty_containers() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).unwrap();
@@ -606,6 +636,8 @@ fn test_special_floats() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).unwrap();
@@ -650,6 +682,8 @@ fn test_unicode_strings() {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         });
 
         let encoded = codec::encode(&msg).unwrap();
@@ -676,6 +710,8 @@ fn benchmark_encoding() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let start = js_sys::Date::now();
@@ -702,6 +738,8 @@ fn benchmark_decoding() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     let encoded = codec::encode(&msg).unwrap();
@@ -765,3 +803,61 @@ fn test_uint8array() {
     assert_eq!(array.length(), 5);
     assert_eq!(array.to_vec(), data);
 }
+
+// =============================================================================
+// Offline Write Queue Tests
+// =============================================================================
+
+/// Test that repeated offline writes to one address coalesce
+#[cfg(feature = "offline")]
+#[wasm_bindgen_test]
+fn test_offline_queue_coalesces_by_address() {
+    use clasp_wasm::offline::WriteQueue;
+
+    let mut queue = WriteQueue::new();
+    let (first, replaced) = queue.push("/fader/1", JsValue::from_f64(0.1), Some(4));
+    assert_eq!(replaced, None);
+
+    queue.push("/fader/2", JsValue::from_f64(0.5), None);
+    let (latest, replaced) = queue.push("/fader/1", JsValue::from_f64(0.9), Some(7));
+    assert_eq!(replaced, Some(first.seq));
+
+    // Base revision is kept from the first queued write
+    assert_eq!(latest.base_revision, Some(4));
+
+    let drained = queue.drain();
+    assert_eq!(drained.len(), 2);
+    assert_eq!(drained[0].address, "/fader/2");
+    assert_eq!(drained[1].value.as_f64(), Some(0.9));
+    assert!(queue.is_empty());
+}
+
+/// Test that restored queues continue numbering after the last entry
+#[cfg(feature = "offline")]
+#[wasm_bindgen_test]
+fn test_offline_queue_restore_ordering() {
+    use clasp_wasm::offline::{QueuedWrite, WriteQueue};
+
+    let mut queue = WriteQueue::new();
+    queue.restore(vec![
+        QueuedWrite {
+            seq: 9,
+            address: "/b".to_string(),
+            value: JsValue::TRUE,
+            base_revision: None,
+        },
+        QueuedWrite {
+            seq: 3,
+            address: "/a".to_string(),
+            value: JsValue::FALSE,
+            base_revision: Some(1),
+        },
+    ]);
+
+    let (entry, _) = queue.push("/c", JsValue::NULL, None);
+    assert_eq!(entry.seq, 10);
+
+    let drained = queue.drain();
+    let order: Vec<&str> = drained.iter().map(|e| e.address.as_str()).collect();
+    assert_eq!(order, vec!["/a", "/b", "/c"]);
+}
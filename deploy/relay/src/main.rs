@@ -23,13 +23,22 @@
 //! # All protocols
 //! clasp-relay --mqtt-port 1883 --osc-port 8000 --quic-port 7331 --cert cert.pem --key key.pem
 //! ```
+//!
+//! `/healthz` and `/readyz` are served on `--health-port` (default 7390). On
+//! SIGTERM the relay stops reporting ready, drains clients for up to
+//! `--drain-timeout` seconds, then exits.
 
 use anyhow::Result;
 use clap::Parser;
 use clasp_core::SecurityMode;
-use clasp_router::{MultiProtocolConfig, Router, RouterConfig, RouterStateConfig};
+use clasp_router::{
+    ConnectionGuard, DuplicateSessions, LoopDetection, MemoryBudget, MultiProtocolConfig,
+    NameCollision, Router, RouterConfig, RouterStateConfig, SessionIdStrategy, SetDedup,
+    WildcardWrites,
+};
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing_subscriber::EnvFilter;
 
@@ -111,6 +120,16 @@ struct Cli {
     #[arg(long)]
     no_ttl: bool,
 
+    /// Memory budget for retained state in megabytes (0 = unlimited)
+    /// Least recently used parameters are evicted beyond it and reported on /$sys/evictions.
+    #[arg(long, default_value = "0")]
+    memory_budget_mb: usize,
+
+    /// Connection attempts allowed per IP per minute (0 = unlimited)
+    /// Throttled IPs are reported on /$sys/security.
+    #[arg(long, default_value = "60")]
+    max_connects_per_ip: u32,
+
     /// Rendezvous server port for WAN discovery (default: same as ws-port, serves /api/v1/*)
     /// Set to 0 to disable rendezvous server.
     #[arg(long, default_value = "7340")]
@@ -119,6 +138,15 @@ struct Cli {
     /// Rendezvous TTL in seconds (how long device registrations last)
     #[arg(long, default_value = "300")]
     rendezvous_ttl: u64,
+
+    /// Health check port serving /healthz and /readyz (0 = disabled)
+    #[cfg(feature = "health")]
+    #[arg(long, default_value = "7390")]
+    health_port: u16,
+
+    /// Seconds to wait for clients to disconnect on SIGTERM/Ctrl-C
+    #[arg(long, default_value = "10")]
+    drain_timeout: u64,
 }
 
 #[tokio::main]
@@ -187,9 +215,25 @@ async fn main() -> Result<()> {
         max_messages_per_second: 0, // No rate limiting for public relay
         rate_limiting_enabled: false,
         state_config,
+        enforce_features: false,
+        session_ids: SessionIdStrategy::default(),
+        name_collision: NameCollision::default(),
+        duplicate_sessions: DuplicateSessions::default(),
+        keepalive: None,
+        max_channels: 16,
+        dedup: SetDedup::default(),
+        memory_budget: match cli.memory_budget_mb {
+            0 => MemoryBudget::default(),
+            mb => MemoryBudget::new(mb * 1024 * 1024),
+        },
+        loop_detection: LoopDetection::default(),
+        wildcard_writes: WildcardWrites::default(),
+        validate_manifests: false,
+        connection_guard: ConnectionGuard::default()
+            .throttle(cli.max_connects_per_ip, Duration::from_secs(60)),
     };
 
-    let router = Router::new(config);
+    let router = Arc::new(Router::new(config));
 
     // Build multi-protocol configuration
     let mut protocols = Vec::new();
@@ -295,6 +339,16 @@ async fn main() -> Result<()> {
     #[cfg(not(feature = "osc-server"))]
     let osc_config: Option<()> = None;
 
+    // Health checks
+    #[cfg(feature = "health")]
+    let health_config = if cli.health_port > 0 {
+        let addr = format!("{}:{}", cli.host, cli.health_port);
+        tracing::info!("Health: http://{}/healthz, /readyz", addr);
+        Some(clasp_router::HealthServerConfig { bind_addr: addr })
+    } else {
+        None
+    };
+
     if protocols.is_empty() {
         anyhow::bail!("No protocols enabled. Enable at least one of: WebSocket, QUIC, MQTT, OSC");
     }
@@ -323,6 +377,8 @@ async fn main() -> Result<()> {
         mqtt: mqtt_config,
         #[cfg(feature = "osc-server")]
         osc: osc_config,
+        #[cfg(feature = "health")]
+        health: health_config,
     };
 
     tracing::info!("Router initialized, accepting connections...");
@@ -352,8 +408,38 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Drain and exit on SIGTERM (container stop, rolling deploys)
+    let drain = Duration::from_secs(cli.drain_timeout);
+    {
+        let router = Arc::clone(&router);
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            tracing::info!("Shutdown requested, draining connections...");
+            router.shutdown(drain).await;
+        });
+    }
+
     // Serve all protocols
     router.serve_all(multi_config).await?;
 
     Ok(())
 }
+
+/// Wait for SIGTERM or Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Cannot listen for SIGTERM: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,83 @@
+//! Browser dashboard on the `clasp-wasm` bindings
+//!
+//! ```bash
+//! wasm-pack build crates/clasp-wasm --target web
+//!
+//! # In-process router fed by a simulated sensor node; open http://127.0.0.1:8080
+//! cargo run -p clasp-examples --bin browser-dashboard
+//!
+//! # Against a running router
+//! cargo run -p clasp-examples --bin browser-dashboard -- --router ws://10.0.0.5:7330 --watch '/lights/**'
+//! ```
+
+use clap::Parser;
+use clasp_examples::dashboard::{self, DashboardConfig};
+use clasp_examples::sensor::SensorNode;
+use clasp_examples::LocalRouter;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Serve a browser dashboard that talks CLASP through clasp-wasm")]
+struct Args {
+    /// Router WebSocket URL (starts an in-process router with a sensor if omitted)
+    #[arg(long)]
+    router: Option<String>,
+
+    /// HTTP address for the page
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    http: String,
+
+    /// Patterns the page subscribes to (repeatable)
+    #[arg(long = "watch")]
+    patterns: Vec<String>,
+
+    /// wasm-pack output of crates/clasp-wasm
+    #[arg(long)]
+    pkg_dir: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    clasp_examples::init_logging();
+    let args = Args::parse();
+
+    let (clasp_url, _local) = match args.router {
+        Some(url) => (url, None),
+        None => {
+            let local = LocalRouter::start("browser-dashboard").await?;
+            let mut node = SensorNode::connect(&local.tcp_addr.to_string(), "greenhouse").await?;
+            tokio::spawn(async move { node.run(None).await });
+            (local.url.clone(), Some(local))
+        }
+    };
+
+    let patterns = if args.patterns.is_empty() {
+        dashboard::DEFAULT_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    } else {
+        args.patterns
+    };
+    let pkg_dir = args.pkg_dir.unwrap_or_else(dashboard::default_pkg_dir);
+    if !pkg_dir.join("clasp_wasm.js").exists() {
+        tracing::warn!(
+            "{} has no clasp_wasm.js; run `wasm-pack build crates/clasp-wasm --target web`",
+            pkg_dir.display()
+        );
+    }
+
+    let app = dashboard::app(DashboardConfig {
+        clasp_url: clasp_url.clone(),
+        patterns,
+        pkg_dir,
+    });
+    let listener = tokio::net::TcpListener::bind(&args.http).await?;
+    tracing::info!(
+        "Dashboard at http://{} (router {})",
+        listener.local_addr()?,
+        clasp_url
+    );
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Several routers ("rooms") sharing one namespace
+//!
+//! ```bash
+//! # Three in-process rooms, with a short demo of what crosses between them
+//! cargo run -p clasp-examples --bin federation
+//!
+//! # Join running routers until Ctrl-C
+//! cargo run -p clasp-examples --bin federation -- \
+//!     --room ws://stage:7330 --room ws://foh:7330 --share '/show/**'
+//! ```
+
+use clap::Parser;
+use clasp_client::Clasp;
+use clasp_examples::federation::{self, Federation};
+use clasp_examples::LocalRouter;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Relay a shared namespace between several CLASP routers")]
+struct Args {
+    /// Router WebSocket URL to join (repeatable; runs an in-process demo if omitted)
+    #[arg(long = "room")]
+    rooms: Vec<String>,
+
+    /// Pattern shared between rooms
+    #[arg(long, default_value = federation::DEFAULT_PATTERN)]
+    share: String,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    clasp_examples::init_logging();
+    let args = Args::parse();
+
+    if !args.rooms.is_empty() {
+        let federation = Federation::connect(&args.rooms, &args.share).await?;
+        tracing::info!(
+            "Sharing {} between {} rooms; Ctrl-C to stop",
+            args.share,
+            federation.room_count()
+        );
+        tokio::signal::ctrl_c().await?;
+        federation.close().await;
+        return Ok(());
+    }
+
+    let mut rooms = Vec::new();
+    for name in ["stage", "foh", "lobby"] {
+        rooms.push((name, LocalRouter::start(name).await?));
+    }
+    let urls: Vec<String> = rooms.iter().map(|(_, room)| room.url.clone()).collect();
+    let federation = Federation::connect(&urls, federation::DEFAULT_PATTERN).await?;
+
+    let stage = Clasp::connect_to(&rooms[0].1.url).await?;
+    stage.set("/shared/cue", "Act 1, scene 2").await?;
+    stage.set("/stage/house-lights", 0.2).await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    for (name, room) in &rooms {
+        let state = room.router.state();
+        tracing::info!(
+            "{:>5}: /shared/cue = {:?}, /stage/house-lights = {:?}",
+            name,
+            state.get("/shared/cue"),
+            state.get("/stage/house-lights")
+        );
+    }
+
+    stage.close().await;
+    federation.close().await;
+    Ok(())
+}
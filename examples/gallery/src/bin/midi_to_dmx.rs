@@ -0,0 +1,82 @@
+//! MIDI CC faders driving Art-Net DMX channels
+//!
+//! ```bash
+//! # Simulated faders through an in-process router, to a node on this host
+//! cargo run -p clasp-examples --bin midi-to-dmx -- --simulate
+//!
+//! # Real MIDI from a router running the MIDI bridge, to a node on the rig
+//! cargo run -p clasp-examples --bin midi-to-dmx -- \
+//!     --router ws://10.0.0.5:7330 --node 10.0.0.50:6454 --patch 1:7=0:1 --patch 1:10=0:2
+//! ```
+
+use clap::Parser;
+use clasp_bridge::artnet::ArtNetBridgeConfig;
+use clasp_client::Clasp;
+use clasp_examples::midi_dmx::{self, MidiToDmx, Patch};
+use clasp_examples::LocalRouter;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Map MIDI control changes to Art-Net DMX channels")]
+struct Args {
+    /// Router WebSocket URL (starts an in-process router if omitted)
+    #[arg(long)]
+    router: Option<String>,
+
+    /// Art-Net node to drive
+    #[arg(long, default_value = "127.0.0.1:6454")]
+    node: String,
+
+    /// `<midi ch>:<cc>=<universe>:<dmx ch>` (repeatable)
+    #[arg(long = "patch", default_values = ["1:7=0:1", "1:10=0:2"])]
+    patches: Vec<Patch>,
+
+    /// Sweep every patched fader once as simulated MIDI input
+    #[arg(long)]
+    simulate: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    clasp_examples::init_logging();
+    let args = Args::parse();
+
+    let (url, _local) = match args.router {
+        Some(url) => (url, None),
+        None => {
+            let local = LocalRouter::start("midi-to-dmx").await?;
+            (local.url.clone(), Some(local))
+        }
+    };
+
+    let mapper = MidiToDmx::start(
+        ArtNetBridgeConfig {
+            bind_addr: "0.0.0.0:0".to_string(),
+            remote_addr: Some(args.node.clone()),
+            ..Default::default()
+        },
+        args.patches.clone(),
+    )
+    .await?;
+    let client = Clasp::builder(&url).name("midi-to-dmx").connect().await?;
+    tracing::info!(
+        "Mapping {} MIDI controller(s) from {} to Art-Net node {}",
+        args.patches.len(),
+        url,
+        args.node
+    );
+
+    if args.simulate {
+        let faders = Clasp::connect_to(&url).await?;
+        let patches = args.patches.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                midi_dmx::simulate_faders(&faders, &patches, Duration::from_millis(50)).await
+            {
+                tracing::warn!("Fader simulation stopped: {}", e);
+            }
+        });
+    }
+
+    mapper.run(&client).await
+}
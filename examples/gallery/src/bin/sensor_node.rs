@@ -0,0 +1,63 @@
+//! ESP32-style sensor node on the `no_std` embedded client
+//!
+//! ```bash
+//! # With its own in-process router, logging what arrives there
+//! cargo run -p clasp-examples --bin sensor-node
+//!
+//! # Against a router's raw TCP listener (e.g. one served with TcpServer)
+//! cargo run -p clasp-examples --bin sensor-node -- --router 10.0.0.5:7331 --name greenhouse
+//! ```
+
+use clap::Parser;
+use clasp_examples::sensor::SensorNode;
+use clasp_examples::LocalRouter;
+
+#[derive(Parser)]
+#[command(about = "Simulated sensor publishing through the embedded CLASP client")]
+struct Args {
+    /// Router TCP address (starts an in-process router if omitted)
+    #[arg(long)]
+    router: Option<String>,
+
+    /// Node name, used in /sensors/<name>/...
+    #[arg(long, default_value = "greenhouse")]
+    name: String,
+
+    /// Stop after this many samples
+    #[arg(long)]
+    samples: Option<u64>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    clasp_examples::init_logging();
+    let args = Args::parse();
+
+    let (addr, _local) = match args.router {
+        Some(addr) => (addr, None),
+        None => {
+            let local = LocalRouter::start("sensor-node").await?;
+            tracing::info!(
+                "In-process router at {} (TCP {})",
+                local.url,
+                local.tcp_addr
+            );
+
+            let watcher = clasp_client::Clasp::connect_to(&local.url).await?;
+            watcher
+                .subscribe("/sensors/**", |value, address| {
+                    tracing::info!("router has {} = {:?}", address, value);
+                })
+                .await?;
+            (local.tcp_addr.to_string(), Some((local, watcher)))
+        }
+    };
+
+    let mut node = SensorNode::connect(&addr, &args.name).await?;
+    tracing::info!(
+        "Publishing /sensors/{}/* every {:?}",
+        args.name,
+        node.interval()
+    );
+    node.run(args.samples).await
+}
@@ -0,0 +1,93 @@
+//! Browser dashboard
+//!
+//! Serves one page that loads the `clasp-wasm` bindings and connects to the
+//! router straight from the browser; this server only hands out files. Build
+//! the bindings first:
+//!
+//! ```bash
+//! wasm-pack build crates/clasp-wasm --target web
+//! ```
+//!
+//! The page lists every value under the patterns it watches as they change
+//! and can SET any address, so it pairs with `sensor-node` (change
+//! `/sensors/<name>/interval`) or `midi-to-dmx` (watch `/dmx/**`).
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use std::path::{Component, PathBuf};
+use std::sync::Arc;
+
+/// The page, with `{{CLASP_URL}}` and `{{PATTERNS}}` left to fill in
+const INDEX_HTML: &str = include_str!("dashboard.html");
+
+/// Patterns the page subscribes to unless told otherwise
+pub const DEFAULT_PATTERNS: &[&str] = &["/sensors/**", "/dmx/**"];
+
+/// Where `wasm-pack build crates/clasp-wasm --target web` puts its output
+pub fn default_pkg_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../crates/clasp-wasm/pkg")
+}
+
+/// What the dashboard serves
+#[derive(Debug, Clone)]
+pub struct DashboardConfig {
+    /// WebSocket URL the browser connects to
+    pub clasp_url: String,
+    /// Patterns the page subscribes to
+    pub patterns: Vec<String>,
+    /// The `clasp-wasm` wasm-pack output, served under `/pkg/`
+    pub pkg_dir: PathBuf,
+}
+
+/// The page with its router URL and patterns filled in
+pub fn render_index(config: &DashboardConfig) -> String {
+    let patterns = config
+        .patterns
+        .iter()
+        .map(|p| format!("{:?}", p))
+        .collect::<Vec<_>>()
+        .join(", ");
+    INDEX_HTML
+        .replace("{{CLASP_URL}}", &format!("{:?}", config.clasp_url))
+        .replace("{{PATTERNS}}", &format!("[{}]", patterns))
+}
+
+/// HTTP routes: `/` for the page, `/pkg/*` for the bindings
+pub fn app(config: DashboardConfig) -> axum::Router {
+    let index = render_index(&config);
+    axum::Router::new()
+        .route("/", get(move || async move { Html(index) }))
+        .route("/pkg/*file", get(pkg_file))
+        .with_state(Arc::new(config.pkg_dir))
+}
+
+async fn pkg_file(State(dir): State<Arc<PathBuf>>, Path(file): Path<String>) -> Response {
+    let relative = std::path::Path::new(&file);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    match tokio::fs::read(dir.join(relative)).await {
+        Ok(body) => ([(header::CONTENT_TYPE, content_type(&file))], body).into_response(),
+        Err(_) => (
+            StatusCode::NOT_FOUND,
+            "clasp-wasm bindings not found; run `wasm-pack build crates/clasp-wasm --target web`",
+        )
+            .into_response(),
+    }
+}
+
+fn content_type(file: &str) -> &'static str {
+    match file.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("js") => "text/javascript",
+        Some("wasm") => "application/wasm",
+        Some("json") => "application/json",
+        Some("ts") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
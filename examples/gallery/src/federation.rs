@@ -0,0 +1,86 @@
+//! Multi-room federation
+//!
+//! Each room is its own router with its own sessions and state. A
+//! [`Federation`] joins them over ordinary client connections: it subscribes
+//! to a shared pattern (by default `/shared/**`) in every room and re-SETs
+//! each change in all the others. A value written anywhere under the pattern
+//! is seen everywhere; everything else stays in its room.
+//!
+//! The link remembers the last value it relayed for each address and drops
+//! changes equal to it, which is what stops a relayed SET from echoing back
+//! and forth between rooms. Concurrent writes of different values in two
+//! rooms settle on whichever is relayed last.
+
+use anyhow::Context;
+use clasp_client::Clasp;
+use clasp_core::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Pattern shared between rooms unless told otherwise
+pub const DEFAULT_PATTERN: &str = "/shared/**";
+
+/// Relays one pattern between several routers
+pub struct Federation {
+    rooms: Vec<Arc<Clasp>>,
+    relay: JoinHandle<()>,
+}
+
+impl Federation {
+    /// Connect to every room and start relaying `pattern` between them
+    pub async fn connect(urls: &[String], pattern: &str) -> anyhow::Result<Self> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut rooms = Vec::with_capacity(urls.len());
+
+        for (index, url) in urls.iter().enumerate() {
+            let room = Clasp::builder(url)
+                .name("federation")
+                .connect()
+                .await
+                .with_context(|| format!("connecting to room {}", url))?;
+            let tx = tx.clone();
+            room.subscribe(pattern, move |value, address| {
+                let _ = tx.send((index, address.to_string(), value));
+            })
+            .await?;
+            rooms.push(Arc::new(room));
+        }
+
+        let targets = rooms.clone();
+        let relay = tokio::spawn(async move {
+            let mut relayed: HashMap<String, Value> = HashMap::new();
+            while let Some((from, address, value)) = rx.recv().await {
+                if relayed.get(&address) == Some(&value) {
+                    continue;
+                }
+                relayed.insert(address.clone(), value.clone());
+
+                for (index, room) in targets.iter().enumerate() {
+                    if index == from {
+                        continue;
+                    }
+                    if let Err(e) = room.set(&address, value.clone()).await {
+                        tracing::warn!("Relaying {} to room {} failed: {}", address, index, e);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { rooms, relay })
+    }
+
+    /// Number of rooms joined
+    pub fn room_count(&self) -> usize {
+        self.rooms.len()
+    }
+
+    /// Stop relaying and disconnect from every room
+    pub async fn close(self) {
+        self.relay.abort();
+        for room in &self.rooms {
+            room.close().await;
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! CLASP examples gallery
+//!
+//! Runnable end-to-end examples. Each binary in `src/bin` is a thin CLI over
+//! one module here, and the smoke tests in `tests/` drive the same code:
+//!
+//! | Binary | Module | Shows |
+//! |--------|--------|-------|
+//! | `sensor-node` | [`sensor`] | An ESP32-style device on the no_std `clasp-embedded` client |
+//! | `browser-dashboard` | [`dashboard`] | A browser page on the `clasp-wasm` bindings |
+//! | `midi-to-dmx` | [`midi_dmx`] | MIDI CC faders patched to Art-Net DMX channels |
+//! | `federation` | [`federation`] | Several routers ("rooms") sharing one namespace |
+//!
+//! Every binary starts its own in-process router unless pointed at a running
+//! one, so `cargo run -p clasp-examples --bin <name>` works on its own.
+
+pub mod dashboard;
+pub mod federation;
+pub mod midi_dmx;
+pub mod sensor;
+
+use clasp_router::{Router, RouterConfig};
+use clasp_transport::{TcpServer, TransportServer, WebSocketServer};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// A router running in this process, for examples started without `--router`
+pub struct LocalRouter {
+    pub router: Arc<Router>,
+    /// WebSocket URL for clients and browsers
+    pub url: String,
+    /// Raw TCP address for embedded devices
+    pub tcp_addr: SocketAddr,
+}
+
+impl LocalRouter {
+    /// Start a router named `name` on ephemeral localhost ports
+    pub async fn start(name: &str) -> anyhow::Result<Self> {
+        let router = Arc::new(Router::new(RouterConfig {
+            name: name.to_string(),
+            ..Default::default()
+        }));
+
+        let ws = WebSocketServer::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", ws.local_addr()?);
+        let tcp = TcpServer::bind("127.0.0.1:0").await?;
+        let tcp_addr = tcp.local_addr()?;
+
+        let serving = Arc::clone(&router);
+        tokio::spawn(async move { serving.serve_on(ws).await });
+        let serving = Arc::clone(&router);
+        tokio::spawn(async move { serving.serve_on(tcp).await });
+
+        Ok(Self {
+            router,
+            url,
+            tcp_addr,
+        })
+    }
+}
+
+/// Log to stderr, honouring `RUST_LOG` (default `info`)
+pub fn init_logging() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+}
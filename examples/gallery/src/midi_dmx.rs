@@ -0,0 +1,169 @@
+//! MIDI-to-DMX mapping
+//!
+//! Follows MIDI control changes as clasp-bridge's MIDI bridge publishes them,
+//! `/midi/<device>/ch/<channel>/cc/<controller>` with 0-127 values, scales
+//! each patched controller to a 0-255 DMX level and sends it to an Art-Net
+//! node through clasp-bridge's [`ArtNetBridge`]. Every level is also SET at
+//! `/dmx/<universe>/<channel>` so dashboards can follow the rig.
+//!
+//! A patch is written `<midi channel>:<cc>=<universe>:<dmx channel>`, so
+//! `1:7=0:1` puts channel 1's volume fader on the first dimmer of universe 0.
+
+use anyhow::{anyhow, Context};
+use clasp_bridge::artnet::{ArtNetBridge, ArtNetBridgeConfig};
+use clasp_bridge::mapping::ValueTransform;
+use clasp_bridge::Bridge;
+use clasp_client::Clasp;
+use clasp_core::{Message, SetMessage, Value};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Pattern covering every MIDI bridge address
+pub const MIDI_PATTERN: &str = "/midi/**";
+
+/// One controller patched to one DMX channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Patch {
+    /// MIDI channel, 1-16
+    pub midi_channel: u8,
+    /// Controller number, 0-127
+    pub cc: u8,
+    /// Art-Net universe
+    pub universe: u16,
+    /// DMX channel, 1-512
+    pub dmx_channel: u16,
+}
+
+impl FromStr for Patch {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let pair = |part: &str| -> anyhow::Result<(u16, u16)> {
+            let (a, b) = part
+                .split_once(':')
+                .ok_or_else(|| anyhow!("expected `a:b`, got `{}`", part))?;
+            Ok((a.trim().parse()?, b.trim().parse()?))
+        };
+        let (midi, dmx) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected `<midi ch>:<cc>=<universe>:<dmx ch>`"))?;
+        let (midi_channel, cc) = pair(midi)?;
+        let (universe, dmx_channel) = pair(dmx)?;
+
+        if !(1..=16).contains(&midi_channel) || cc > 127 || !(1..=512).contains(&dmx_channel) {
+            return Err(anyhow!("patch `{}` out of range", s));
+        }
+        Ok(Self {
+            midi_channel: midi_channel as u8,
+            cc: cc as u8,
+            universe,
+            dmx_channel,
+        })
+    }
+}
+
+impl Patch {
+    /// Whether a MIDI bridge address is this patch's controller, on any device
+    pub fn matches(&self, address: &str) -> bool {
+        let parts: Vec<&str> = address.split('/').collect();
+        matches!(
+            parts.as_slice(),
+            ["", "midi", _, "ch", ch, "cc", cc]
+                if ch.parse() == Ok(self.midi_channel) && cc.parse() == Ok(self.cc)
+        )
+    }
+
+    /// Where the level is mirrored in CLASP
+    pub fn dmx_address(&self) -> String {
+        format!("/dmx/{}/{}", self.universe, self.dmx_channel)
+    }
+
+    /// The address ArtNetBridge sends as DMX
+    fn artnet_address(&self) -> String {
+        format!("/artnet/{}/{}", self.universe, self.dmx_channel)
+    }
+}
+
+/// Routes patched MIDI controllers to an Art-Net node
+pub struct MidiToDmx {
+    patches: Vec<Patch>,
+    scale: ValueTransform,
+    artnet: ArtNetBridge,
+}
+
+impl MidiToDmx {
+    /// Open the Art-Net socket; `config.remote_addr` is the node to drive
+    pub async fn start(config: ArtNetBridgeConfig, patches: Vec<Patch>) -> anyhow::Result<Self> {
+        if config.remote_addr.is_none() {
+            return Err(anyhow!("an Art-Net node address is required"));
+        }
+        let mut artnet = ArtNetBridge::new(config);
+        // Output only: incoming Art-Net is not part of this example
+        drop(artnet.start().await.context("starting Art-Net output")?);
+
+        Ok(Self {
+            patches,
+            scale: ValueTransform::scale(0.0, 127.0, 0.0, 255.0),
+            artnet,
+        })
+    }
+
+    /// The DMX level for a MIDI value, if `address` is patched
+    pub fn level(&self, address: &str, value: &Value) -> Option<(Patch, u8)> {
+        let patch = self.patches.iter().find(|p| p.matches(address))?;
+        let level = self.scale.apply(value).as_f64()?;
+        Some((*patch, level.round().clamp(0.0, 255.0) as u8))
+    }
+
+    /// Forward patched MIDI changes from the router until it disconnects
+    pub async fn run(&self, client: &Clasp) -> anyhow::Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        client
+            .subscribe(MIDI_PATTERN, move |value, address| {
+                let _ = tx.send((address.to_string(), value));
+            })
+            .await?;
+
+        while client.is_connected() {
+            let Ok(Some((address, value))) =
+                tokio::time::timeout(Duration::from_millis(500), rx.recv()).await
+            else {
+                continue;
+            };
+            let Some((patch, level)) = self.level(&address, &value) else {
+                continue;
+            };
+
+            self.artnet
+                .send(Message::Set(SetMessage {
+                    address: patch.artnet_address(),
+                    value: Value::Int(level as i64),
+                    revision: None,
+                    lock: false,
+                    unlock: false,
+                    op: None,
+                    correlation_id: None,
+                }))
+                .await?;
+            client.set(&patch.dmx_address(), level as i64).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Sweep every patched fader once, the way a MIDI bridge would publish it
+pub async fn simulate_faders(
+    client: &Clasp,
+    patches: &[Patch],
+    step: Duration,
+) -> anyhow::Result<()> {
+    for value in (0..=127).step_by(8).chain([127]) {
+        for patch in patches {
+            let address = format!("/midi/sim/ch/{}/cc/{}", patch.midi_channel, patch.cc);
+            client.set(&address, value as i64).await?;
+        }
+        tokio::time::sleep(step).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! ESP32-style sensor node
+//!
+//! The device side is the `no_std` [`clasp_embedded::Client`]: it only fills
+//! fixed buffers with frames and parses the frames it is handed, so on a
+//! microcontroller those bytes go straight to the network stack (lwIP,
+//! esp-idf sockets, embassy-net). Here they travel over a std TCP socket from
+//! `clasp-transport`, which carries exactly the same frames.
+//!
+//! The node publishes readings under `/sensors/<name>/` and follows
+//! `/sensors/<name>/interval` (milliseconds), which any client may set:
+//!
+//! ```text
+//! /sensors/greenhouse/temperature   Float  (°C)
+//! /sensors/greenhouse/humidity      Float  (%RH)
+//! /sensors/greenhouse/uptime        Int    (samples sent)
+//! /sensors/greenhouse/interval      Int    (ms, written by others)
+//! ```
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use clasp_embedded::{Client, Message, Value};
+use clasp_transport::tcp::{TcpReceiver, TcpSender};
+use clasp_transport::{TcpTransport, TransportEvent, TransportReceiver, TransportSender};
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+/// Sample interval until the router says otherwise
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Shortest interval the node accepts
+const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long to wait for the router's WELCOME
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One simulated sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub temperature: f64,
+    pub humidity: f64,
+}
+
+/// Deterministic readings: a slow day/night swing with humidity trailing it
+pub fn simulate(tick: u64) -> Reading {
+    let phase = tick as f64 / 40.0;
+    Reading {
+        temperature: 21.0 + 4.0 * phase.sin(),
+        humidity: 55.0 - 10.0 * (phase - 0.5).sin(),
+    }
+}
+
+/// A simulated device speaking the embedded client's frames over TCP
+pub struct SensorNode {
+    client: Client,
+    sender: TcpSender,
+    receiver: TcpReceiver,
+    prefix: String,
+    interval: Duration,
+    sent: u64,
+}
+
+impl SensorNode {
+    /// Connect to a router's TCP listener and complete the handshake
+    pub async fn connect(addr: &str, name: &str) -> anyhow::Result<Self> {
+        let (sender, receiver) = TcpTransport::new()
+            .connect(addr)
+            .await
+            .with_context(|| format!("connecting to {}", addr))?;
+        let mut node = Self {
+            client: Client::new(),
+            sender,
+            receiver,
+            prefix: format!("/sensors/{}", name),
+            interval: DEFAULT_INTERVAL,
+            sent: 0,
+        };
+
+        let hello = node.client.prepare_hello(name).to_vec();
+        node.send(hello).await?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while !node.client.is_connected() {
+            if !node.receive_until(deadline).await? {
+                bail!("router did not answer HELLO");
+            }
+        }
+
+        let interval = format!("{}/interval", node.prefix);
+        let subscribe = node.client.prepare_subscribe(&interval).to_vec();
+        node.send(subscribe).await?;
+        Ok(node)
+    }
+
+    /// Current sample interval
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Publish one reading
+    pub async fn publish(&mut self, reading: Reading) -> anyhow::Result<()> {
+        self.sent += 1;
+        let values = [
+            ("temperature", Value::Float(reading.temperature)),
+            ("humidity", Value::Float(reading.humidity)),
+            ("uptime", Value::Int(self.sent as i64)),
+        ];
+        for (name, value) in values {
+            let address = format!("{}/{}", self.prefix, name);
+            let frame = self.client.prepare_set(&address, value).to_vec();
+            self.send(frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Publish `samples` readings (forever if `None`), one per interval
+    pub async fn run(&mut self, samples: Option<u64>) -> anyhow::Result<()> {
+        let mut tick = 0;
+        while samples.is_none_or(|n| tick < n) {
+            self.publish(simulate(tick)).await?;
+            tick += 1;
+            let deadline = Instant::now() + self.interval;
+            while self.receive_until(deadline).await? {}
+        }
+        Ok(())
+    }
+
+    async fn send(&self, frame: Vec<u8>) -> anyhow::Result<()> {
+        self.sender.send(Bytes::from(frame)).await?;
+        Ok(())
+    }
+
+    /// Handle one frame; false once `deadline` passes
+    async fn receive_until(&mut self, deadline: Instant) -> anyhow::Result<bool> {
+        let frame = match timeout_at(deadline, self.receiver.recv()).await {
+            Err(_) => return Ok(false),
+            Ok(Some(TransportEvent::Data(frame))) => frame,
+            Ok(Some(TransportEvent::Disconnected { .. })) | Ok(None) => {
+                bail!("router closed the connection")
+            }
+            Ok(Some(_)) => return Ok(true),
+        };
+
+        match self.client.process(&frame) {
+            Some(Message::Set { address, value })
+                if address.strip_prefix(self.prefix.as_str()) == Some("/interval") =>
+            {
+                let ms = match value {
+                    Value::Int(ms) => ms.max(0) as u64,
+                    Value::Float(ms) => ms.max(0.0) as u64,
+                    _ => return Ok(true),
+                };
+                self.interval = Duration::from_millis(ms).max(MIN_INTERVAL);
+                tracing::info!("Sample interval now {:?}", self.interval);
+            }
+            Some(Message::Error { code, message }) => {
+                tracing::warn!("Router error {}: {}", code, message);
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+}
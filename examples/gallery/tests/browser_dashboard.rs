@@ -0,0 +1,37 @@
+//! Smoke test for `browser-dashboard`
+
+use clasp_examples::dashboard::{app, DashboardConfig};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn test_dashboard_serves_page_and_bindings() {
+    let pkg_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("dashboard-pkg");
+    std::fs::create_dir_all(&pkg_dir).unwrap();
+    std::fs::write(pkg_dir.join("clasp_wasm.js"), "export default 1;").unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let app = app(DashboardConfig {
+        clasp_url: "ws://127.0.0.1:7330".to_string(),
+        patterns: vec!["/lights/**".to_string()],
+        pkg_dir,
+    });
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let page = reqwest::get(&base).await.unwrap().text().await.unwrap();
+    assert!(page.contains(r#"const CLASP_URL = "ws://127.0.0.1:7330";"#));
+    assert!(page.contains(r#"const PATTERNS = ["/lights/**"];"#));
+    assert!(page.contains("ClaspWasm"));
+
+    let js = reqwest::get(format!("{}/pkg/clasp_wasm.js", base))
+        .await
+        .unwrap();
+    assert_eq!(js.status(), 200);
+    assert_eq!(js.headers()["content-type"], "text/javascript");
+
+    let missing = reqwest::get(format!("{}/pkg/clasp_wasm_bg.wasm", base))
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+    assert!(missing.text().await.unwrap().contains("wasm-pack build"));
+}
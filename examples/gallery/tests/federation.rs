@@ -0,0 +1,48 @@
+//! Smoke test for `federation`
+
+use clasp_client::Clasp;
+use clasp_core::Value;
+use clasp_examples::federation::{Federation, DEFAULT_PATTERN};
+use clasp_examples::LocalRouter;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_shared_namespace_crosses_rooms() {
+    let mut rooms = Vec::new();
+    for name in ["stage", "foh", "lobby"] {
+        rooms.push(LocalRouter::start(name).await.unwrap());
+    }
+    let urls: Vec<String> = rooms.iter().map(|room| room.url.clone()).collect();
+    let federation = Federation::connect(&urls, DEFAULT_PATTERN).await.unwrap();
+    assert_eq!(federation.room_count(), 3);
+
+    let stage = Clasp::connect_to(&rooms[0].url).await.unwrap();
+    stage.set("/shared/cue", "Act 1").await.unwrap();
+    stage.set("/stage/house-lights", 0.2).await.unwrap();
+
+    let lobby = Clasp::connect_to(&rooms[2].url).await.unwrap();
+    let mut cue = None;
+    for _ in 0..50 {
+        cue = rooms[2].router.state().get("/shared/cue");
+        if cue.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(cue, Some(Value::String("Act 1".to_string())));
+    assert_eq!(rooms[2].router.state().get("/stage/house-lights"), None);
+
+    // Changes flow back the other way, and the echo doesn't undo them
+    lobby.set("/shared/cue", "Intermission").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for room in &rooms {
+        assert_eq!(
+            room.router.state().get("/shared/cue"),
+            Some(Value::String("Intermission".to_string()))
+        );
+    }
+
+    stage.close().await;
+    lobby.close().await;
+    federation.close().await;
+}
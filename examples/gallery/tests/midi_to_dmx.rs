@@ -0,0 +1,78 @@
+//! Smoke test for `midi-to-dmx`
+
+use clasp_bridge::artnet::{ArtNetBridge, ArtNetBridgeConfig};
+use clasp_bridge::{Bridge, BridgeEvent};
+use clasp_client::Clasp;
+use clasp_core::{Message, Value};
+use clasp_examples::midi_dmx::{MidiToDmx, Patch};
+use clasp_examples::LocalRouter;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn test_patch_parsing() {
+    let patch: Patch = "1:7=0:12".parse().unwrap();
+    assert_eq!(
+        patch,
+        Patch {
+            midi_channel: 1,
+            cc: 7,
+            universe: 0,
+            dmx_channel: 12,
+        }
+    );
+    assert!(patch.matches("/midi/nanoKONTROL2/ch/1/cc/7"));
+    assert!(!patch.matches("/midi/nanoKONTROL2/ch/2/cc/7"));
+    assert!("1:7=0:0".parse::<Patch>().is_err());
+    assert!("17:7=0:1".parse::<Patch>().is_err());
+    assert!("1:7".parse::<Patch>().is_err());
+}
+
+#[tokio::test]
+async fn test_midi_fader_reaches_artnet_node() {
+    // The "node" is a second Art-Net bridge listening on a local port
+    let node_addr = std::net::UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let mut node = ArtNetBridge::new(ArtNetBridgeConfig {
+        bind_addr: node_addr.to_string(),
+        ..Default::default()
+    });
+    let mut dmx = node.start().await.unwrap();
+
+    let local = LocalRouter::start("test").await.unwrap();
+    let mapper = Arc::new(
+        MidiToDmx::start(
+            ArtNetBridgeConfig {
+                bind_addr: "127.0.0.1:0".to_string(),
+                remote_addr: Some(node_addr.to_string()),
+                ..Default::default()
+            },
+            vec!["1:7=0:3".parse().unwrap()],
+        )
+        .await
+        .unwrap(),
+    );
+    let client = Arc::new(Clasp::connect_to(&local.url).await.unwrap());
+    tokio::spawn({
+        let mapper = Arc::clone(&mapper);
+        let client = Arc::clone(&client);
+        async move { mapper.run(&client).await }
+    });
+
+    // What the MIDI bridge publishes when the fader is pushed all the way up
+    let midi = Clasp::connect_to(&local.url).await.unwrap();
+    let mut level = None;
+    for _ in 0..50 {
+        midi.set("/midi/nanoKONTROL2/ch/1/cc/7", 127).await.unwrap();
+        if let Ok(Some(BridgeEvent::ToClasp(Message::Set(set)))) =
+            tokio::time::timeout(Duration::from_millis(100), dmx.recv()).await
+        {
+            level = Some((set.address, set.value));
+            break;
+        }
+    }
+    assert_eq!(level, Some(("/artnet/0/3".to_string(), Value::Int(255))));
+    assert_eq!(local.router.state().get("/dmx/0/3"), Some(Value::Int(255)));
+}
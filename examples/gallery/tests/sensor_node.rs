@@ -0,0 +1,34 @@
+//! Smoke test for `sensor-node`
+
+use clasp_client::Clasp;
+use clasp_core::Value;
+use clasp_examples::sensor::{self, SensorNode, DEFAULT_INTERVAL};
+use clasp_examples::LocalRouter;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_sensor_node_publishes_and_follows_interval() {
+    let local = LocalRouter::start("test").await.unwrap();
+    let mut node = SensorNode::connect(&local.tcp_addr.to_string(), "greenhouse")
+        .await
+        .unwrap();
+    assert_eq!(node.interval(), DEFAULT_INTERVAL);
+
+    // A dashboard asks for faster samples while the node is running
+    let dashboard = Clasp::connect_to(&local.url).await.unwrap();
+    node.run(Some(1)).await.unwrap();
+    dashboard
+        .set("/sensors/greenhouse/interval", 20)
+        .await
+        .unwrap();
+    node.run(Some(3)).await.unwrap();
+    assert_eq!(node.interval(), Duration::from_millis(20));
+
+    let state = local.router.state();
+    assert_eq!(
+        state.get("/sensors/greenhouse/temperature"),
+        Some(Value::Float(sensor::simulate(2).temperature))
+    );
+    assert_eq!(state.get("/sensors/greenhouse/uptime"), Some(Value::Int(4)));
+    dashboard.close().await;
+}
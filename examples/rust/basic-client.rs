@@ -95,6 +95,8 @@ async fn main() -> Result<()> {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
         Message::Set(SetMessage {
             address: "/example/rust/bundle/b".to_string(),
@@ -102,6 +104,8 @@ async fn main() -> Result<()> {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
     ]).await?;
     println!("  Bundle sent");
@@ -116,6 +120,8 @@ async fn main() -> Result<()> {
             revision: None,
             lock: false,
             unlock: false,
+            op: None,
+            correlation_id: None,
         }),
     ], future_time).await?;
     println!("  Scheduled bundle queued");
@@ -38,16 +38,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             address: "/scene/active".to_string(),
             value: "sunset".into(),
             revision: None,
+            op: None,
+            correlation_id: None,
         }),
         Message::Set(SetMessage {
             address: "/lights/1/brightness".to_string(),
             value: 0.8.into(),
             revision: None,
+            op: None,
+            correlation_id: None,
         }),
         Message::Set(SetMessage {
             address: "/lights/2/brightness".to_string(),
             value: 0.6.into(),
             revision: None,
+            op: None,
+            correlation_id: None,
         }),
     ], None).await?;
 
@@ -68,6 +74,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             address: "/scheduled/counter".to_string(),
             value: 1.into(),
             revision: None,
+            op: None,
+            correlation_id: None,
         }),
     ], Some(execute_at)).await?;
 
@@ -92,11 +100,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 address: "/animation/brightness".to_string(),
                 value: brightness.into(),
                 revision: None,
+                op: None,
+                correlation_id: None,
             }),
             Message::Set(SetMessage {
                 address: "/animation/step".to_string(),
                 value: (i as i64).into(),
                 revision: None,
+                op: None,
+                correlation_id: None,
             }),
         ], Some(execute_time)).await?;
     }
@@ -114,6 +126,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             address: "/cue/current".to_string(),
             value: "intro".into(),
             revision: None,
+            op: None,
+            correlation_id: None,
         }),
         Message::Publish(PublishMessage {
             address: "/cue/started".to_string(),
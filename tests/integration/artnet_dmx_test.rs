@@ -73,6 +73,8 @@ async fn test_dmx_virtual_bridge() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     bridge.send(msg).await.expect("Failed to send DMX message");
@@ -140,6 +142,8 @@ async fn test_artnet_clasp_conversion() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     // This will fail because remote isn't listening, but tests the conversion
@@ -84,6 +84,8 @@ async fn test_clasp_to_osc() {
         revision: None,
         lock: false,
         unlock: false,
+        op: None,
+        correlation_id: None,
     });
 
     bridge.send(msg).await.expect("Failed to send message");
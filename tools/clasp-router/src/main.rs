@@ -14,18 +14,63 @@
 //! # WebSocket on default port (works on DO App Platform)
 //! clasp-router --listen 0.0.0.0:7330
 //!
+//! # Start from a preset: live (shows), iot (sensor networks) or dev
+//! clasp-router --profile live
+//!
 //! # QUIC with auto-generated self-signed cert (requires UDP, use on Droplet/VPS)
 //! clasp-router --listen 0.0.0.0:7331 --transport quic
 //!
 //! # QUIC with custom certificate
 //! clasp-router --transport quic --cert cert.der --key key.der
+//!
+//! # Web dashboard on port 7380, protected by a token
+//! clasp-router --dashboard 0.0.0.0:7380 --dashboard-token cpsk_...
+//!
+//! # Don't forward polled sensor values that didn't change (faders within 0.01)
+//! clasp-router --dedup '/sensors/**' --dedup '/mixer/**=0.01'
+//!
+//! # Cap retained state at 256 MB, evicting /scratch/** before anything else
+//! clasp-router --memory-budget-mb 256 --evict-priority '/scratch/**=-10'
+//!
+//! # Break bridge feedback loops sooner (after 4 bounces within 200ms)
+//! clasp-router --loop-max-hops 4 --loop-window-ms 200
+//!
+//! # Allow blackout-style writes such as SET /lumen/scene/0/layer/*/opacity 0
+//! clasp-router --wildcard-writes 256
+//!
+//! # Accept MQTT and OSC clients too; admins can add, move or stop these
+//! # later with SET /$sys/adapters/<mqtt|osc|dashboard>
+//! clasp-router --mqtt 0.0.0.0:1883 --osc 0.0.0.0:8000
+//!
+//! # Health checks for Kubernetes / DO App Platform
+//! clasp-router --health 0.0.0.0:7390 --drain-timeout 30
+//!
+//! # Keep params across restarts, and run at boot as a Windows service or
+//! # systemd unit with the same options
+//! clasp-router --state-file /var/lib/clasp/state.json --install-service
 //! ```
+//!
+//! On SIGTERM or Ctrl-C (or a service stop) the router stops reporting
+//! ready, waits up to `--drain-timeout` seconds for clients to disconnect,
+//! writes `--state-file`, then exits.
+
+mod service;
+mod state_file;
 
 use anyhow::Result;
 use clap::{Parser, ValueEnum};
-use clasp_core::{CpskValidator, Scope, SecurityMode, TokenInfo};
-use clasp_router::{Router, RouterConfig};
+use clasp_core::{CpskValidator, DeviceKeyValidator, Scope, SecurityMode, TokenInfo};
+use clasp_router::{
+    token_admin, ConnectionGuard, DedupRule, DuplicateAction, DuplicateSessions, EvictionPolicy,
+    EvictionPriority, LoopDetection, MemoryBudget, NameCollision, Router, RouterConfig,
+    RouterProfile, SessionIdStrategy, SessionIdentity, WildcardWrites,
+};
+use clasp_transport::KeepaliveConfig;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::EnvFilter;
 
 #[cfg(feature = "bridges")]
@@ -44,6 +89,21 @@ enum Transport {
     Quic,
 }
 
+/// Preset configuration; other options refine it
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Profile {
+    /// Show control: high rate limits, state never expires, warn-level logs
+    Live,
+
+    /// Sensor networks: low rate limits, unchanged readings not forwarded,
+    /// 24h state TTL, 30s keepalive
+    Iot,
+
+    /// Local development: no rate limits, 10 minute state TTL, strict
+    /// feature checks, debug logs
+    Dev,
+}
+
 /// Security/authentication mode
 #[derive(Debug, Clone, Copy, ValueEnum, Default)]
 enum AuthMode {
@@ -55,6 +115,70 @@ enum AuthMode {
     Authenticated,
 }
 
+/// Session id format
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum SessionIds {
+    /// Random UUID (default)
+    #[default]
+    Uuid,
+
+    /// Client name plus a short suffix, e.g. lighting-desk-3f2a
+    Short,
+
+    /// Token subject plus a short suffix, e.g. alice-3f2a
+    Subject,
+}
+
+/// Handling of duplicate client names
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum NamePolicy {
+    /// Allow duplicate names (default)
+    #[default]
+    Allow,
+
+    /// Refuse clients whose name is already in use
+    Reject,
+
+    /// Rename duplicates to "name (2)", "name (3)", ...
+    Suffix,
+}
+
+/// Handling of clients that reconnect while their old session is live
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum DuplicatePolicy {
+    /// Keep both sessions (default)
+    #[default]
+    Allow,
+
+    /// Refuse the new connection
+    Reject,
+
+    /// Close the old session and keep the new one
+    Takeover,
+}
+
+/// What identifies a client for --duplicate-sessions
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum Identity {
+    /// Client name from HELLO (default)
+    #[default]
+    Name,
+
+    /// Token subject (authenticated mode)
+    Subject,
+}
+
+/// Which params go first when the memory budget is exceeded
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum Eviction {
+    /// Least recently read or written (default)
+    #[default]
+    Lru,
+
+    /// Least recently written
+    Oldest,
+}
+
 #[derive(Parser)]
 #[command(name = "clasp-router")]
 #[command(about = "CLASP Router Server - routes messages between CLASP clients")]
@@ -67,11 +191,26 @@ EXAMPLES:
   # WebSocket server (default, works on DO App Platform)
   clasp-router --listen 0.0.0.0:7330
 
+  # Tuned for a live show (or --profile iot / --profile dev)
+  clasp-router --profile live
+
   # QUIC server with self-signed cert (requires UDP - Droplet/VPS only)
   clasp-router --listen 0.0.0.0:7331 --transport quic
 
   # With mDNS discovery announcement
   clasp-router --listen 0.0.0.0:7330 --announce --name "Studio Router"
+
+  # With the web dashboard (sessions, namespace editor, metrics)
+  clasp-router --dashboard 0.0.0.0:7380 --dashboard-token cpsk_...
+
+  # Accepting MQTT and OSC clients (changeable later via /$sys/adapters)
+  clasp-router --mqtt 0.0.0.0:1883 --osc 0.0.0.0:8000
+
+  # With /healthz and /readyz for orchestrator probes
+  clasp-router --health 0.0.0.0:7390
+
+  # Run at boot as a Windows service / systemd unit, keeping params
+  clasp-router --state-file state.json --install-service
 "#)]
 struct Cli {
     /// Listen address (host:port)
@@ -86,6 +225,10 @@ struct Cli {
     #[arg(short, long, default_value = "CLASP Router")]
     name: String,
 
+    /// Start from a preset tuned for a kind of deployment
+    #[arg(long, value_enum)]
+    profile: Option<Profile>,
+
     /// Enable mDNS discovery announcement
     #[arg(short, long)]
     announce: bool,
@@ -107,7 +250,8 @@ struct Cli {
     auth_mode: AuthMode,
 
     /// Token file for authenticated mode (one CPSK token per line)
-    /// Format: cpsk_<base62-random-32-chars>
+    /// Format: cpsk_<base62-random-32-chars>. Rewritten when admins
+    /// add or revoke tokens via /$sys/tokens.
     #[arg(long)]
     token_file: Option<String>,
 
@@ -115,24 +259,226 @@ struct Cli {
     #[arg(long)]
     token: Option<String>,
 
+    /// Device public key file for authenticated mode (one hex Ed25519 key
+    /// per line, optionally followed by scopes). Devices connect with a
+    /// signed proof instead of a token. Created if missing; rewritten when
+    /// admins add or revoke keys via /$sys/keys.
+    #[arg(long)]
+    device_keys: Option<String>,
+
+    /// Connection attempts allowed per IP per --guard-window (0 = unlimited);
+    /// throttled IPs are published on /$sys/security
+    #[arg(long, default_value = "0")]
+    max_connects_per_ip: u32,
+
+    /// Ban an IP for --ban-duration after this many failed authentications
+    /// per --guard-window (0 = never)
+    #[arg(long, default_value = "0")]
+    ban_after_failures: u32,
+
+    /// Window for --max-connects-per-ip and --ban-after-failures, in seconds
+    #[arg(long, default_value = "60")]
+    guard_window: u64,
+
+    /// How long automatic bans last, in seconds
+    #[arg(long, default_value = "900")]
+    ban_duration: u64,
+
+    /// Refuse connections from this IP until unbanned via
+    /// /$sys/security/bans (repeatable)
+    #[arg(long, value_name = "IP")]
+    ban: Vec<std::net::IpAddr>,
+
+    /// Serve the web dashboard on this address (e.g. 0.0.0.0:7380)
+    #[cfg(feature = "dashboard")]
+    #[arg(long)]
+    dashboard: Option<SocketAddr>,
+
+    /// Token for the dashboard (admin access). Defaults to the router's
+    /// tokens in authenticated mode; without either the dashboard is open.
+    #[cfg(feature = "dashboard")]
+    #[arg(long, requires = "dashboard")]
+    dashboard_token: Option<String>,
+
+    /// Accept MQTT clients on this address (e.g. 0.0.0.0:1883)
+    #[cfg(feature = "mqtt")]
+    #[arg(long)]
+    mqtt: Option<SocketAddr>,
+
+    /// Accept OSC over UDP on this address (e.g. 0.0.0.0:8000)
+    #[cfg(feature = "osc")]
+    #[arg(long)]
+    osc: Option<SocketAddr>,
+
+    /// Serve /healthz and /readyz on this address (e.g. 0.0.0.0:7390)
+    #[cfg(feature = "health")]
+    #[arg(long)]
+    health: Option<SocketAddr>,
+
+    /// Reject signal types (gesture, timeline, ...) that a client did not
+    /// declare in its HELLO features
+    #[arg(long)]
+    enforce_features: bool,
+
+    /// How session ids are generated
+    #[arg(long, default_value = "uuid")]
+    session_ids: SessionIds,
+
+    /// What to do when a client connects with a name already in use
+    #[arg(long, default_value = "allow")]
+    name_collision: NamePolicy,
+
+    /// What to do when a client connects while a session with the same
+    /// identity is still live
+    #[arg(long, default_value = "allow")]
+    duplicate_sessions: DuplicatePolicy,
+
+    /// What identifies a client for --duplicate-sessions
+    #[arg(long, default_value = "name")]
+    session_identity: Identity,
+
+    /// Move the old session's subscriptions to the new one on takeover
+    #[arg(long)]
+    transfer_subscriptions: bool,
+
+    /// Ping idle connections after this many seconds (0 = never; default:
+    /// the transport's own default)
+    #[arg(long)]
+    keepalive: Option<u64>,
+
+    /// Logical clients that may share one connection (0 = sharing disabled)
+    #[arg(long, default_value = "16")]
+    max_channels: usize,
+
+    /// Acknowledge but don't forward SETs that repeat the current value on
+    /// this pattern; `PATTERN=EPSILON` also ignores numeric changes up to
+    /// EPSILON (repeatable)
+    #[arg(long, value_name = "PATTERN[=EPSILON]", value_parser = parse_dedup)]
+    dedup: Vec<DedupRule>,
+
+    /// Memory budget for retained state and queued changes, in megabytes
+    /// (0 = unlimited); evictions are published on /$sys/evictions
+    #[arg(long, default_value = "0")]
+    memory_budget_mb: usize,
+
+    /// Order of eviction when over the memory budget
+    #[arg(long, value_enum, default_value = "lru")]
+    eviction_policy: Eviction,
+
+    /// Eviction priority of a pattern; lower priorities are evicted first,
+    /// unmatched addresses have 0 (repeatable)
+    #[arg(long, value_name = "PATTERN=PRIORITY", value_parser = parse_evict_priority)]
+    evict_priority: Vec<EvictionPriority>,
+
+    /// Drop a value after it has bounced between sessions this many times
+    /// (0 = no loop detection); loops are published on /$sys/loops
+    #[arg(long, default_value = "16")]
+    loop_max_hops: u32,
+
+    /// Longest gap between two bounces of a value, in milliseconds
+    #[arg(long, default_value = "500")]
+    loop_window_ms: u64,
+
+    /// Let SETs and PUBLISHes to patterns fan out to at most this many
+    /// retained addresses (0 = writes to patterns are rejected)
+    #[arg(long, default_value = "0")]
+    wildcard_writes: usize,
+
+    /// Reject writes that don't fit the manifest (/$manifest/<app>) owning
+    /// their namespace
+    #[arg(long)]
+    validate_manifests: bool,
+
+    /// Seconds to wait for clients to disconnect on SIGTERM/Ctrl-C
+    #[arg(long, default_value = "10")]
+    drain_timeout: u64,
+
+    /// Restore params from this file on start and write them back on stop
+    #[arg(long)]
+    state_file: Option<PathBuf>,
+
+    /// Also write --state-file every this many seconds (0 = only on stop)
+    #[arg(long, default_value = "30", requires = "state_file")]
+    state_flush_interval: u64,
+
+    /// Register as a service (Windows) or systemd unit (Linux) that starts at
+    /// boot with the other options given, then exit
+    #[arg(long, conflicts_with = "uninstall_service")]
+    install_service: bool,
+
+    /// Stop and remove the installed service, then exit
+    #[arg(long)]
+    uninstall_service: bool,
+
+    /// Run under the Windows service control manager (set by --install-service)
+    #[cfg(windows)]
+    #[arg(long, hide = true)]
+    service: bool,
+
     /// Enable verbose logging
     #[arg(short, long)]
     verbose: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn parse_dedup(arg: &str) -> std::result::Result<DedupRule, String> {
+    match arg.rsplit_once('=') {
+        Some((pattern, epsilon)) => {
+            let epsilon: f64 = epsilon
+                .parse()
+                .map_err(|_| format!("invalid epsilon '{}'", epsilon))?;
+            Ok(DedupRule::new(pattern).with_epsilon(epsilon))
+        }
+        None => Ok(DedupRule::new(arg)),
+    }
+}
+
+fn parse_evict_priority(arg: &str) -> std::result::Result<EvictionPriority, String> {
+    let (pattern, priority) = arg
+        .rsplit_once('=')
+        .ok_or_else(|| "expected PATTERN=PRIORITY".to_string())?;
+    let priority: i32 = priority
+        .parse()
+        .map_err(|_| format!("invalid priority '{}'", priority))?;
+    Ok(EvictionPriority::new(pattern, priority))
+}
+
+fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if cli.install_service {
+        return service::install(Duration::from_secs(cli.drain_timeout));
+    }
+    if cli.uninstall_service {
+        return service::uninstall();
+    }
+    #[cfg(windows)]
+    if cli.service {
+        return service::run_windows_service();
+    }
+
+    tokio::runtime::Runtime::new()?.block_on(run(cli, shutdown_signal()))
+}
+
+/// Run the router until `stop` resolves, then drain and exit
+async fn run(cli: Cli, stop: impl Future<Output = ()> + Send + 'static) -> Result<()> {
+    let profile = cli.profile.map(|profile| match profile {
+        Profile::Live => RouterProfile::Live,
+        Profile::Iot => RouterProfile::Iot,
+        Profile::Dev => RouterProfile::Dev,
+    });
+
     // Setup logging
     let filter = if cli.verbose {
         EnvFilter::new("debug")
     } else {
-        EnvFilter::new("info")
+        EnvFilter::new(profile.map_or("info", RouterProfile::log_level))
     };
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
     tracing::info!("Starting CLASP Router");
+    if let Some(profile) = profile {
+        tracing::info!("Profile: {:?}", profile);
+    }
     tracing::info!("Transport: {:?}", cli.transport);
     tracing::info!("Listening on: {}", cli.listen);
 
@@ -153,11 +499,79 @@ async fn main() -> Result<()> {
         AuthMode::Authenticated => SecurityMode::Authenticated,
     };
 
-    // Create router config
+    let session_ids = match cli.session_ids {
+        SessionIds::Uuid => SessionIdStrategy::Uuid,
+        SessionIds::Short => SessionIdStrategy::Short,
+        SessionIds::Subject => SessionIdStrategy::Subject,
+    };
+    let name_collision = match cli.name_collision {
+        NamePolicy::Allow => NameCollision::Allow,
+        NamePolicy::Reject => NameCollision::Reject,
+        NamePolicy::Suffix => NameCollision::Suffix,
+    };
+    let duplicate_sessions = DuplicateSessions {
+        identity: match cli.session_identity {
+            Identity::Name => SessionIdentity::Name,
+            Identity::Subject => SessionIdentity::Subject,
+        },
+        action: match cli.duplicate_sessions {
+            DuplicatePolicy::Allow => DuplicateAction::Allow,
+            DuplicatePolicy::Reject => DuplicateAction::Reject,
+            DuplicatePolicy::Takeover => DuplicateAction::Takeover,
+        },
+        transfer_subscriptions: cli.transfer_subscriptions,
+    };
+
+    let memory_budget = match cli.memory_budget_mb {
+        0 => MemoryBudget::default(),
+        mb => cli.evict_priority.iter().cloned().fold(
+            MemoryBudget::new(mb * 1024 * 1024).with_policy(match cli.eviction_policy {
+                Eviction::Lru => EvictionPolicy::Lru,
+                Eviction::Oldest => EvictionPolicy::OldestFirst,
+            }),
+            MemoryBudget::with_priority,
+        ),
+    };
+
+    // Create router config, on top of the profile's if one was given
+    let base = profile.map(RouterProfile::config).unwrap_or_default();
     let config = RouterConfig {
         name: cli.name.clone(),
         security_mode,
-        ..Default::default()
+        enforce_features: cli.enforce_features || base.enforce_features,
+        session_ids,
+        name_collision,
+        duplicate_sessions,
+        keepalive: cli
+            .keepalive
+            .map(|secs| match secs {
+                0 => KeepaliveConfig::disabled(),
+                secs => KeepaliveConfig::new(Duration::from_secs(secs)),
+            })
+            .or(base.keepalive),
+        max_channels: cli.max_channels,
+        dedup: cli
+            .dedup
+            .iter()
+            .cloned()
+            .fold(base.dedup.clone(), |dedup, rule| dedup.with_rule(rule)),
+        memory_budget,
+        loop_detection: LoopDetection::new(
+            cli.loop_max_hops,
+            Duration::from_millis(cli.loop_window_ms),
+        ),
+        wildcard_writes: WildcardWrites::new(cli.wildcard_writes),
+        validate_manifests: cli.validate_manifests,
+        connection_guard: ConnectionGuard::default()
+            .throttle(
+                cli.max_connects_per_ip,
+                Duration::from_secs(cli.guard_window),
+            )
+            .ban_after(
+                cli.ban_after_failures,
+                Duration::from_secs(cli.ban_duration),
+            ),
+        ..base
     };
 
     // Create router with optional token validator
@@ -192,43 +606,143 @@ async fn main() -> Result<()> {
             }
         }
 
-        // Load tokens from file
+        // Load tokens from file (format: token or token scope1,scope2)
         if let Some(token_file) = &cli.token_file {
             tracing::info!("Loading tokens from file: {}", token_file);
-            let contents = std::fs::read_to_string(token_file)?;
-            for line in contents.lines() {
-                let line = line.trim();
-                if line.is_empty() || line.starts_with('#') {
-                    continue;
-                }
-                // Format: token or token scope1,scope2 (space-separated)
-                if line.contains(' ') {
-                    let parts: Vec<&str> = line.splitn(2, ' ').collect();
-                    let scopes: Vec<&str> = parts[1].split(',').map(|s| s.trim()).collect();
-                    register_token(parts[0], scopes)?;
-                } else {
-                    // Token only - default to admin access
-                    register_token(line, vec!["admin:/**"])?;
-                }
+            token_admin::load_token_file(Path::new(token_file), &validator)?;
+        }
+
+        let device_keys = DeviceKeyValidator::new();
+        if let Some(key_file) = &cli.device_keys {
+            if Path::new(key_file).exists() {
+                tracing::info!("Loading device keys from file: {}", key_file);
+                token_admin::load_token_file(Path::new(key_file), &device_keys)?;
             }
         }
 
-        if validator.is_empty() {
+        if validator.is_empty() && device_keys.is_empty() {
             anyhow::bail!(
-                "Authenticated mode requires at least one token (use --token or --token-file)"
+                "Authenticated mode requires at least one token (use --token, --token-file or --device-keys)"
             );
         }
 
         tracing::info!(
-            "Security mode: Authenticated with {} token(s)",
-            validator.len()
+            "Security mode: Authenticated with {} token(s), {} device key(s)",
+            validator.len(),
+            device_keys.len()
         );
-        Router::new(config).with_validator(validator)
+        let mut router = Router::new(config).with_validator(validator);
+        // Runtime changes via /$sys/tokens and /$sys/keys are written back
+        if let Some(token_file) = &cli.token_file {
+            router = router.with_token_file(token_file);
+        }
+        if let Some(key_file) = &cli.device_keys {
+            router = router
+                .with_device_keys(device_keys)
+                .with_device_key_file(key_file);
+        }
+        router
     } else {
         tracing::info!("Security mode: Open (no authentication)");
         Router::new(config)
     };
 
+    for ip in &cli.ban {
+        router.ban(*ip, None, "banned on the command line");
+    }
+
+    #[cfg(feature = "dashboard")]
+    if let Some(addr) = cli.dashboard {
+        let mut dashboard = router.dashboard(clasp_router::DashboardConfig {
+            bind_addr: addr.to_string(),
+            ..Default::default()
+        });
+        if let Some(token) = &cli.dashboard_token {
+            let validator = CpskValidator::new();
+            validator.register(
+                token.clone(),
+                TokenInfo::new(token.clone(), vec![Scope::parse("admin:/**")?]),
+            );
+            dashboard = dashboard.with_validator(Arc::new(validator));
+        }
+        tokio::spawn(async move {
+            if let Err(e) = dashboard.serve().await {
+                tracing::error!("Dashboard error: {}", e);
+            }
+        });
+    }
+
+    // Started through the router so admins can stop or move them at runtime
+    #[cfg(feature = "mqtt")]
+    if let Some(addr) = cli.mqtt {
+        router
+            .start_adapter(clasp_router::AdapterConfig::Mqtt(
+                clasp_router::MqttServerConfig {
+                    bind_addr: addr.to_string(),
+                    ..Default::default()
+                },
+            ))
+            .await?;
+    }
+
+    #[cfg(feature = "osc")]
+    if let Some(addr) = cli.osc {
+        router
+            .start_adapter(clasp_router::AdapterConfig::Osc(
+                clasp_router::OscServerConfig {
+                    bind_addr: addr.to_string(),
+                    ..Default::default()
+                },
+            ))
+            .await?;
+    }
+
+    #[cfg(feature = "health")]
+    if let Some(addr) = cli.health {
+        let health = router.health_server(clasp_router::HealthServerConfig {
+            bind_addr: addr.to_string(),
+        });
+        tokio::spawn(async move {
+            if let Err(e) = health.serve().await {
+                tracing::error!("Health endpoint error: {}", e);
+            }
+        });
+    }
+
+    if let Some(path) = &cli.state_file {
+        let restored = state_file::load(&router, path)?;
+        tracing::info!("Restored {} params from {}", restored, path.display());
+    }
+
+    let router = Arc::new(router);
+    let drain = Duration::from_secs(cli.drain_timeout);
+    {
+        let router = Arc::clone(&router);
+        tokio::spawn(async move {
+            stop.await;
+            tracing::info!("Shutdown requested, draining connections...");
+            service::notify_stopping();
+            router.shutdown(drain).await;
+        });
+    }
+    service::spawn_notifier(&router);
+
+    // Flush periodically too, so a power cut loses at most one interval
+    let flush = Duration::from_secs(cli.state_flush_interval);
+    if let Some(path) = cli.state_file.clone().filter(|_| !flush.is_zero()) {
+        let router = Arc::clone(&router);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(flush);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                if let Err(e) = state_file::save(&router, &path) {
+                    tracing::warn!("Cannot write state file: {:#}", e);
+                }
+            }
+        });
+    }
+
     tracing::info!("Router ready, accepting connections...");
 
     // Run with appropriate transport
@@ -263,9 +777,33 @@ async fn main() -> Result<()> {
         }
     }
 
+    if let Some(path) = &cli.state_file {
+        let saved = state_file::save(&router, path)?;
+        tracing::info!("Wrote {} params to {}", saved, path.display());
+    }
+
     Ok(())
 }
 
+/// Wait for SIGTERM (sent by Kubernetes, Docker and App Platform) or Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Cannot listen for SIGTERM: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
 /// Generate a self-signed certificate for QUIC
 #[cfg(feature = "quic")]
 fn generate_self_signed_cert() -> Result<(Vec<u8>, Vec<u8>)> {
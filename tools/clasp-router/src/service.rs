@@ -0,0 +1,352 @@
+//! Running under a service manager
+//!
+//! `--install-service` registers the router with the platform's service
+//! manager, passing along the rest of the command line:
+//!
+//! - **Windows**: a service started at boot. The SCM restarts it 5s, 30s and
+//!   then 2 minutes after each failure, so a crash loop backs off.
+//! - **Linux**: a systemd unit with `Type=notify` and a watchdog. Restarts
+//!   start 5s apart and back off to 2 minutes.
+//!
+//! Under systemd the router reports READY once it accepts connections and
+//! STOPPING when it starts draining. It pings the watchdog while it is
+//! accepting or draining, so systemd restarts a wedged router.
+
+use anyhow::Result;
+use clasp_router::Router;
+use std::ffi::OsString;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Name the service is registered under
+pub const SERVICE_NAME: &str = "clasp-router";
+
+/// Delays before each restart after a failure, growing to the last one
+#[cfg_attr(not(any(windows, target_os = "linux")), allow(dead_code))]
+const RESTART_DELAYS: [Duration; 3] = [
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+    Duration::from_secs(120),
+];
+
+/// The command line the installed service runs with
+#[cfg_attr(not(any(windows, target_os = "linux")), allow(dead_code))]
+fn service_args() -> Vec<OsString> {
+    std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--install-service")
+        .collect()
+}
+
+/// Register the router as a Windows service that starts at boot
+#[cfg(windows)]
+pub fn install(_drain: Duration) -> Result<()> {
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceErrorControl,
+        ServiceFailureActions, ServiceFailureResetPeriod, ServiceInfo, ServiceStartType,
+        ServiceType,
+    };
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+    let mut launch_arguments = service_args();
+    launch_arguments.push("--service".into());
+    let info = ServiceInfo {
+        name: SERVICE_NAME.into(),
+        display_name: "CLASP Router".into(),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments,
+        dependencies: vec![],
+        account_name: None, // LocalSystem
+        account_password: None,
+    };
+    let service =
+        manager.create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)?;
+    service.set_description("Routes messages between CLASP clients")?;
+    service.update_failure_actions(ServiceFailureActions {
+        reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(24 * 3600)),
+        reboot_msg: None,
+        command: None,
+        actions: Some(
+            RESTART_DELAYS
+                .iter()
+                .map(|&delay| ServiceAction {
+                    action_type: ServiceActionType::Restart,
+                    delay,
+                })
+                .collect(),
+        ),
+    })?;
+    // Also restart after a clean exit with an error code, not just crashes
+    service.set_failure_actions_on_non_crash_failures(true)?;
+
+    println!("Installed the {} service", SERVICE_NAME);
+    println!("Start it with: sc start {}", SERVICE_NAME);
+    Ok(())
+}
+
+/// Stop and remove the Windows service
+#[cfg(windows)]
+pub fn uninstall() -> Result<()> {
+    use windows_service::service::{ServiceAccess, ServiceState};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(
+        SERVICE_NAME,
+        ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    )?;
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+    }
+    service.delete()?;
+    println!("Removed the {} service", SERVICE_NAME);
+    Ok(())
+}
+
+/// Hand the process over to the Windows service control manager
+#[cfg(windows)]
+pub fn run_windows_service() -> Result<()> {
+    windows_service::service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+windows_service::define_windows_service!(ffi_service_main, service_main);
+
+#[cfg(windows)]
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        tracing::error!("Service failed: {:#}", e);
+    }
+}
+
+#[cfg(windows)]
+fn run_service() -> Result<()> {
+    use clap::Parser;
+    use windows_service::service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
+        ServiceType,
+    };
+    use windows_service::service_control_handler::{
+        self, ServiceControlHandlerResult, ServiceStatusHandle,
+    };
+
+    fn report(
+        handle: ServiceStatusHandle,
+        state: ServiceState,
+        wait_hint: Duration,
+        exit_code: ServiceExitCode,
+    ) -> windows_service::Result<()> {
+        handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: match state {
+                ServiceState::Running => {
+                    ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+                }
+                _ => ServiceControlAccept::empty(),
+            },
+            exit_code,
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        })
+    }
+
+    // The SCM passes the installed launch arguments on the command line
+    let cli = crate::Cli::parse();
+    let drain = Duration::from_secs(cli.drain_timeout);
+
+    let stop = Arc::new(tokio::sync::Notify::new());
+    let handler = {
+        let stop = Arc::clone(&stop);
+        move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                stop.notify_one();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+    let handle = service_control_handler::register(SERVICE_NAME, handler)?;
+    report(
+        handle,
+        ServiceState::Running,
+        Duration::ZERO,
+        ServiceExitCode::NO_ERROR,
+    )?;
+
+    let stopped = async move {
+        stop.notified().await;
+        // Drain plus time to flush state
+        let _ = report(
+            handle,
+            ServiceState::StopPending,
+            drain + Duration::from_secs(10),
+            ServiceExitCode::NO_ERROR,
+        );
+    };
+    let result = tokio::runtime::Runtime::new()?.block_on(crate::run(cli, stopped));
+
+    // A non-zero exit code makes the SCM apply the failure actions
+    let exit_code = if result.is_ok() {
+        ServiceExitCode::NO_ERROR
+    } else {
+        ServiceExitCode::ServiceSpecific(1)
+    };
+    report(handle, ServiceState::Stopped, Duration::ZERO, exit_code)?;
+    result
+}
+
+/// Directory systemd units are installed to
+#[cfg(target_os = "linux")]
+const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Write a systemd unit for the router and enable it at boot
+#[cfg(target_os = "linux")]
+pub fn install(drain: Duration) -> Result<()> {
+    use anyhow::Context;
+
+    let exe = std::env::current_exe()?;
+    let exec_start: Vec<String> = std::iter::once(exe.into_os_string())
+        .chain(service_args())
+        .map(|arg| systemd_quote(&arg.to_string_lossy()))
+        .collect();
+    let unit = format!(
+        "[Unit]
+Description=CLASP Router
+Wants=network-online.target
+After=network-online.target
+# Keep restarting however often it fails
+StartLimitIntervalSec=0
+
+[Service]
+Type=notify
+ExecStart={exec_start}
+WatchdogSec=30
+Restart=always
+RestartSec={first}
+RestartSteps={steps}
+RestartMaxDelaySec={last}
+TimeoutStopSec={stop}
+
+[Install]
+WantedBy=multi-user.target
+",
+        exec_start = exec_start.join(" "),
+        first = RESTART_DELAYS[0].as_secs(),
+        steps = RESTART_DELAYS.len(),
+        last = RESTART_DELAYS[RESTART_DELAYS.len() - 1].as_secs(),
+        // Drain plus time to flush state
+        stop = drain.as_secs() + 10,
+    );
+
+    let path = std::path::Path::new(SYSTEMD_UNIT_DIR).join(format!("{}.service", SERVICE_NAME));
+    std::fs::write(&path, unit).with_context(|| format!("cannot write {}", path.display()))?;
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", SERVICE_NAME])?;
+
+    println!("Installed {}", path.display());
+    println!("Start it with: systemctl start {}", SERVICE_NAME);
+    Ok(())
+}
+
+/// Stop, disable and remove the systemd unit
+#[cfg(target_os = "linux")]
+pub fn uninstall() -> Result<()> {
+    let path = std::path::Path::new(SYSTEMD_UNIT_DIR).join(format!("{}.service", SERVICE_NAME));
+    systemctl(&["disable", "--now", SERVICE_NAME])?;
+    std::fs::remove_file(&path)?;
+    systemctl(&["daemon-reload"])?;
+    println!("Removed {}", path.display());
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn systemctl(args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new("systemctl")
+        .args(args)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("systemctl {} failed ({})", args.join(" "), status);
+    }
+    Ok(())
+}
+
+/// Quote an argument for a unit file's `ExecStart=`
+#[cfg(target_os = "linux")]
+fn systemd_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,@+".contains(c));
+    let quoted = if plain {
+        arg.to_string()
+    } else {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    };
+    // Specifiers and variables are expanded even inside quotes
+    quoted.replace('%', "%%").replace('$', "$$")
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn install(_drain: Duration) -> Result<()> {
+    anyhow::bail!("--install-service is supported on Windows and Linux (systemd)")
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn uninstall() -> Result<()> {
+    anyhow::bail!("--uninstall-service is supported on Windows and Linux (systemd)")
+}
+
+/// Report readiness to systemd and keep its watchdog fed
+///
+/// Does nothing unless the router was started by systemd with
+/// `Type=notify` (and `WatchdogSec=` for the watchdog).
+#[cfg(target_os = "linux")]
+pub fn spawn_notifier(router: &Arc<Router>) {
+    use sd_notify::NotifyState;
+
+    let router = Arc::clone(router);
+    tokio::spawn(async move {
+        while !router.health().accepting {
+            if router.is_draining() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready]) {
+            tracing::debug!("Cannot notify systemd: {}", e);
+        }
+
+        let mut usec = 0;
+        if !sd_notify::watchdog_enabled(false, &mut usec) {
+            return;
+        }
+        let interval = Duration::from_micros(usec) / 2;
+        tracing::info!("systemd watchdog enabled (ping every {:?})", interval);
+        loop {
+            tokio::time::sleep(interval).await;
+            if router.health().accepting || router.is_draining() {
+                let _ = sd_notify::notify(false, &[NotifyState::Watchdog]);
+            }
+        }
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn_notifier(_router: &Arc<Router>) {}
+
+/// Tell systemd the router is shutting down
+pub fn notify_stopping() {
+    #[cfg(target_os = "linux")]
+    let _ = sd_notify::notify(false, &[sd_notify::NotifyState::Stopping]);
+}
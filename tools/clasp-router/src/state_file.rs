@@ -0,0 +1,58 @@
+//! Retained params kept across restarts (`--state-file`)
+//!
+//! Params are written as JSON when the router stops and every
+//! `--state-flush-interval` seconds, so a power cut loses at most one
+//! interval of changes. The file is replaced atomically. Revisions and locks
+//! are not kept: restored params start over at revision 1.
+
+use anyhow::{Context, Result};
+use clasp_core::ParamValue;
+use clasp_router::Router;
+use std::path::Path;
+
+/// Session id restored params are attributed to
+const RESTORED_WRITER: &str = "state-file";
+
+/// Restore params saved by [`save`]; a missing file restores nothing
+pub fn load(router: &Router, path: &Path) -> Result<usize> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("cannot read {}", path.display())),
+    };
+    let params: Vec<ParamValue> = serde_json::from_slice(&contents)
+        .with_context(|| format!("invalid state file {}", path.display()))?;
+
+    let writer = RESTORED_WRITER.to_string();
+    let mut restored = 0;
+    for param in params {
+        if param.address.starts_with("/$sys/") {
+            continue;
+        }
+        match router
+            .state()
+            .set(&param.address, param.value, &writer, None, false, false)
+        {
+            Ok(_) => restored += 1,
+            Err(e) => tracing::warn!("Cannot restore {}: {}", param.address, e),
+        }
+    }
+    Ok(restored)
+}
+
+/// Write the router's params (except `/$sys/` ones) to `path`
+pub fn save(router: &Router, path: &Path) -> Result<usize> {
+    let params: Vec<ParamValue> = router
+        .state()
+        .full_snapshot()
+        .params
+        .into_iter()
+        .filter(|param| !param.address.starts_with("/$sys/"))
+        .collect();
+
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(&params)?)
+        .with_context(|| format!("cannot write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("cannot replace {}", path.display()))?;
+    Ok(params.len())
+}
@@ -17,7 +17,7 @@ use uuid::Uuid;
 
 // Import all bridge types
 #[cfg(feature = "osc")]
-use clasp_bridge::{OscBridge, OscBridgeConfig};
+use clasp_bridge::{OscBridge, OscBridgeConfig, OscFraming, OscTransport};
 
 #[cfg(feature = "midi")]
 use clasp_bridge::{MidiBridge, MidiBridgeConfig};
@@ -35,7 +35,7 @@ use clasp_bridge::{MqttBridge, MqttBridgeConfig};
 use clasp_bridge::{WebSocketBridge, WebSocketBridgeConfig, WsMode};
 
 #[cfg(feature = "http")]
-use clasp_bridge::{HttpBridge, HttpBridgeConfig, HttpMode};
+use clasp_bridge::{HttpBridge, HttpBridgeConfig, HttpMode, WebhookConfig};
 
 #[cfg(feature = "socketio")]
 use clasp_bridge::{SocketIOBridge, SocketIOBridgeConfig};
@@ -43,6 +43,21 @@ use clasp_bridge::{SocketIOBridge, SocketIOBridgeConfig};
 #[cfg(feature = "sacn")]
 use clasp_bridge::{SacnBridge, SacnBridgeConfig, SacnMode};
 
+#[cfg(feature = "hid")]
+use clasp_bridge::{CurveType, HidBridge, HidBridgeConfig};
+
+#[cfg(feature = "audio")]
+use clasp_bridge::{AudioBridge, AudioBridgeConfig};
+
+#[cfg(feature = "timecode")]
+use clasp_bridge::{TimecodeBridge, TimecodeBridgeConfig, TimecodeRate, TimecodeSource};
+
+#[cfg(feature = "plugin")]
+use clasp_bridge::PluginRegistry;
+
+#[cfg(feature = "hotkey")]
+use clasp_bridge::{HotkeyBinding, HotkeyBridge, HotkeyBridgeConfig};
+
 /// Request from Electron
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
@@ -167,6 +182,9 @@ struct ActiveBridge {
 struct BridgeService {
     bridges: RwLock<HashMap<String, ActiveBridge>>,
     signal_tx: mpsc::Sender<Response>,
+    /// Bridge plugins from `CLASP_PLUGIN_DIR`, by protocol
+    #[cfg(feature = "plugin")]
+    plugins: PluginRegistry,
 }
 
 impl BridgeService {
@@ -174,6 +192,8 @@ impl BridgeService {
         Self {
             bridges: RwLock::new(HashMap::new()),
             signal_tx,
+            #[cfg(feature = "plugin")]
+            plugins: load_plugins(),
         }
     }
 
@@ -192,6 +212,17 @@ impl BridgeService {
         let bridge: Box<dyn Bridge> = match source.as_str() {
             #[cfg(feature = "osc")]
             "osc" => {
+                // "udp" (default), "tcp" (packet-length framing) or "tcp-slip"
+                let transport = match extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("transport"))
+                    .and_then(|v| v.as_str())
+                {
+                    Some("tcp") => OscTransport::Tcp(OscFraming::PacketLength),
+                    Some("tcp-slip") => OscTransport::Tcp(OscFraming::Slip),
+                    _ => OscTransport::Udp,
+                };
+
                 let config = OscBridgeConfig {
                     bind_addr: source_addr.clone(),
                     remote_addr: if target == "osc" {
@@ -200,6 +231,7 @@ impl BridgeService {
                         None
                     },
                     namespace: "/osc".to_string(),
+                    transport,
                 };
                 Box::new(OscBridge::new(config))
             }
@@ -253,9 +285,23 @@ impl BridgeService {
                     .and_then(|v| v.as_u64())
                     .unwrap_or(0) as u16;
 
+                let interface_type = match extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("interface"))
+                    .and_then(|v| v.as_str())
+                {
+                    Some("enttec-pro") => DmxInterfaceType::EnttecPro,
+                    Some("enttec-open") => DmxInterfaceType::EnttecOpen,
+                    Some("ftdi") => DmxInterfaceType::Ftdi,
+                    Some("udmx") => DmxInterfaceType::Udmx,
+                    Some("artnet-loopback") => DmxInterfaceType::ArtNetLoopback,
+                    _ => DmxInterfaceType::Virtual,
+                };
+
                 let config = DmxBridgeConfig {
-                    port: Some(source_addr.clone()),
-                    interface_type: DmxInterfaceType::Virtual,
+                    // "auto" detects the interface
+                    port: Some(source_addr.clone()).filter(|p| p != "auto"),
+                    interface_type,
                     universe,
                     namespace: "/dmx".to_string(),
                     refresh_rate: 44.0,
@@ -285,6 +331,16 @@ impl BridgeService {
                     (source_addr.clone(), 1883)
                 };
 
+                // Optional "version", "tls", "birth" and "last_will" objects
+                // use the bridge config's own format
+                fn option<T: serde::de::DeserializeOwned>(
+                    extra_config: &Option<serde_json::Value>,
+                    key: &str,
+                ) -> Option<T> {
+                    let value = extra_config.as_ref()?.get(key)?.clone();
+                    serde_json::from_value(value).ok()
+                }
+
                 let config = MqttBridgeConfig {
                     broker_host,
                     broker_port,
@@ -295,6 +351,11 @@ impl BridgeService {
                     qos: 0,
                     keep_alive_secs: 60,
                     namespace: "/mqtt".to_string(),
+                    version: option(&extra_config, "version").unwrap_or_default(),
+                    tls: option(&extra_config, "tls"),
+                    birth: option(&extra_config, "birth"),
+                    last_will: option(&extra_config, "last_will"),
+                    mirror_retained: true,
                 };
                 Box::new(MqttBridge::new(config))
             }
@@ -321,16 +382,28 @@ impl BridgeService {
                     (WsMode::Server, source_addr.clone())
                 };
 
+                // "json" (default), "msgpack", "raw", "binary" or "mapped"
+                let format = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("format"))
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default();
+                let mapping = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("mapping"))
+                    .and_then(|v| serde_json::from_value(v.clone()).ok());
+
                 let config = WebSocketBridgeConfig {
                     mode,
                     url,
                     path: None,
-                    format: clasp_bridge::WsMessageFormat::Json,
+                    format,
                     ping_interval_secs: 30,
                     auto_reconnect: true,
                     reconnect_delay_secs: 5,
                     headers: std::collections::HashMap::new(),
                     namespace: "/ws".to_string(),
+                    mapping,
                 };
                 Box::new(WebSocketBridge::new(config))
             }
@@ -350,8 +423,20 @@ impl BridgeService {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(true);
 
+                // A "webhooks" list switches the bridge to outbound webhook mode
+                let webhooks: Vec<WebhookConfig> = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("webhooks"))
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default();
+                let mode = if webhooks.is_empty() {
+                    HttpMode::Server
+                } else {
+                    HttpMode::Webhook
+                };
+
                 let config = HttpBridgeConfig {
-                    mode: HttpMode::Server,
+                    mode,
                     url: source_addr.clone(),
                     endpoints: vec![],
                     cors_enabled: cors,
@@ -361,6 +446,7 @@ impl BridgeService {
                     namespace: "/http".to_string(),
                     poll_interval_ms: 0,
                     poll_endpoints: vec![],
+                    webhooks,
                 };
                 Box::new(HttpBridge::new(config))
             }
@@ -444,6 +530,12 @@ impl BridgeService {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(true);
 
+                let source_timeout_ms = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("source_timeout_ms"))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(2500);
+
                 let unicast_destinations: Vec<String> = extra_config
                     .as_ref()
                     .and_then(|c| c.get("unicast_destinations"))
@@ -470,10 +562,144 @@ impl BridgeService {
                     namespace: "/sacn".to_string(),
                     preview: false,
                     sync_address: 0,
+                    source_timeout_ms,
                 };
                 Box::new(SacnBridge::new(config))
             }
 
+            #[cfg(feature = "hid")]
+            "hid" => {
+                let dead_zone = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("dead_zone"))
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.1);
+
+                let curve = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("curve"))
+                    .and_then(|v| serde_json::from_value::<CurveType>(v.clone()).ok())
+                    .unwrap_or(CurveType::Linear);
+
+                let config = HidBridgeConfig {
+                    device: if source_addr.is_empty() || source_addr == "default" {
+                        None
+                    } else {
+                        Some(source_addr.clone())
+                    },
+                    dead_zone,
+                    curve,
+                    ..Default::default()
+                };
+                Box::new(HidBridge::new(config))
+            }
+
+            #[cfg(feature = "audio")]
+            "audio" => {
+                let defaults = AudioBridgeConfig::default();
+                let number = |key: &str| {
+                    extra_config
+                        .as_ref()
+                        .and_then(|c| c.get(key))
+                        .and_then(|v| v.as_f64())
+                };
+
+                let config = AudioBridgeConfig {
+                    device: if source_addr.is_empty() || source_addr == "default" {
+                        None
+                    } else {
+                        Some(source_addr.clone())
+                    },
+                    frame_rate: number("frame_rate").unwrap_or(defaults.frame_rate),
+                    bands: number("bands").map_or(defaults.bands, |n| n as usize),
+                    fft_size: number("fft_size").map_or(defaults.fft_size, |n| n as usize),
+                    smoothing: number("smoothing").unwrap_or(defaults.smoothing),
+                    ..defaults
+                };
+                Box::new(AudioBridge::new(config))
+            }
+
+            #[cfg(feature = "timecode")]
+            "timecode" => {
+                let defaults = TimecodeBridgeConfig::default();
+                let option = |key: &str| extra_config.as_ref().and_then(|c| c.get(key));
+
+                let source = match option("source").and_then(|v| v.as_str()) {
+                    Some("ltc") => TimecodeSource::Ltc,
+                    Some("none") => TimecodeSource::None,
+                    _ => TimecodeSource::ArtNet,
+                };
+                let rate = match option("rate").and_then(|v| v.as_f64()) {
+                    Some(r) if r < 24.5 => TimecodeRate::Film24,
+                    Some(r) if r < 27.5 => TimecodeRate::Ebu25,
+                    Some(r) if r < 29.99 => TimecodeRate::Df2997,
+                    Some(_) => TimecodeRate::Smpte30,
+                    None => defaults.rate,
+                };
+                let device = if source_addr.is_empty() || source_addr == "default" {
+                    None
+                } else {
+                    Some(source_addr.clone())
+                };
+
+                let config = TimecodeBridgeConfig {
+                    source,
+                    bind_addr: match (source, &device) {
+                        (TimecodeSource::ArtNet, Some(addr)) => addr.clone(),
+                        _ => defaults.bind_addr.clone(),
+                    },
+                    input_device: if source == TimecodeSource::Ltc {
+                        device
+                    } else {
+                        None
+                    },
+                    generate: option("generate")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    output_device: option("output_device")
+                        .and_then(|v| v.as_str())
+                        .map(String::from),
+                    rate,
+                    ..defaults
+                };
+                Box::new(TimecodeBridge::new(config))
+            }
+
+            #[cfg(feature = "hotkey")]
+            "hotkey" => {
+                let bindings: Vec<HotkeyBinding> = extra_config
+                    .as_ref()
+                    .and_then(|c| c.get("bindings"))
+                    .map(|v| serde_json::from_value(v.clone()))
+                    .transpose()?
+                    .unwrap_or_default();
+
+                let config = HotkeyBridgeConfig {
+                    bindings,
+                    mapping_file: if source_addr.is_empty() {
+                        None
+                    } else {
+                        Some(source_addr.clone().into())
+                    },
+                    ..Default::default()
+                };
+                Box::new(HotkeyBridge::new(config))
+            }
+
+            #[cfg(feature = "plugin")]
+            other if self.plugins.get(other).is_some() => {
+                // Plugins get the addresses alongside their own options
+                let mut config = match extra_config {
+                    Some(serde_json::Value::Object(map)) => map,
+                    _ => serde_json::Map::new(),
+                };
+                config.insert("source_addr".into(), source_addr.clone().into());
+                config.insert("target_addr".into(), target_addr.clone().into());
+
+                let plugin = self.plugins.get(other).expect("checked above");
+                Box::new(plugin.create_bridge(&serde_json::Value::Object(config))?)
+            }
+
             _ => {
                 return Err(anyhow!("Unsupported source protocol: {}", source));
             }
@@ -766,6 +992,8 @@ impl BridgeService {
                 revision: None,
                 lock: false,
                 unlock: false,
+                op: None,
+                correlation_id: None,
             });
             bridge.bridge.send(msg).await?;
             Ok(())
@@ -850,6 +1078,22 @@ fn base64_encode(data: &[u8]) -> String {
     result
 }
 
+/// Load bridge plugins from the directory in `CLASP_PLUGIN_DIR`, if set
+#[cfg(feature = "plugin")]
+fn load_plugins() -> PluginRegistry {
+    let Some(dir) = std::env::var_os("CLASP_PLUGIN_DIR") else {
+        return PluginRegistry::new();
+    };
+    // Plugins are native code; the directory is trusted like the binary itself
+    match unsafe { PluginRegistry::load_dir(&dir) } {
+        Ok(registry) => registry,
+        Err(e) => {
+            error!("Failed to load plugins from {:?}: {}", dir, e);
+            PluginRegistry::new()
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging to stderr